@@ -1,7 +1,13 @@
+use crate::options::{ConsumedCapacity, DeleteItemOptions, GetItemOptions};
 use crate::types::{Item, Key};
 use crate::wit::momento::aws_ddb::aws_ddb::{self as aws_ddb};
 use momento_functions_aws_auth::CredentialsProvider;
 use momento_functions_bytes::Data;
+use momento_functions_bytes::abi::{self, PackageFunction, UnsupportedByHost};
+
+/// `aws-ddb.client.delete-item`
+const DELETE_ITEM: PackageFunction =
+    PackageFunction::new("momento:aws-ddb", "aws-ddb.client.delete-item", 1, 1);
 
 /// DynamoDB client for host interfaces.
 ///
@@ -27,6 +33,9 @@ pub enum DynamoDBError {
     /// An error from the DynamoDB host interface.
     #[error(transparent)]
     Dynamo(#[from] aws_ddb::DdbError),
+    /// The host is too old for this DynamoDB call.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
 }
 
 /// An error occurred while using the extracting get_item wrapper.
@@ -45,6 +54,25 @@ pub enum GetItemError<E> {
     },
 }
 
+/// The result of [DynamoDBClient::get_item_raw_with].
+#[derive(Debug)]
+pub struct GetItemOutput {
+    /// The item, or `None` if there is no item with the key.
+    pub item: Option<Item>,
+    /// The capacity the read consumed, if it was requested.
+    pub consumed_capacity: Option<ConsumedCapacity>,
+}
+
+/// The result of [DynamoDBClient::delete_item_with].
+#[derive(Debug)]
+pub struct DeleteItemOutput {
+    /// The deleted item, with [ReturnValues::AllOld](crate::ReturnValues::AllOld) if there was
+    /// one.
+    pub attributes: Option<Item>,
+    /// The capacity the delete consumed, if it was requested.
+    pub consumed_capacity: Option<ConsumedCapacity>,
+}
+
 impl DynamoDBClient {
     /// Create a new DynamoDB client.
     ///
@@ -132,26 +160,85 @@ impl DynamoDBClient {
         table_name: impl Into<String>,
         key: impl Into<Key>,
     ) -> Result<Option<Item>, DynamoDBError> {
+        Ok(self
+            .get_item_raw_with(table_name, key, GetItemOptions::default())?
+            .item)
+    }
+
+    /// Get an item from a DynamoDB table with [GetItemOptions], like a consistent read or a
+    /// projection.
+    ///
+    /// Examples:
+    /// ________
+    /// ```rust,no_run
+    /// use momento_functions_aws_ddb::{DynamoDBClient, GetItemError, GetItemOptions, Item};
+    ///
+    /// /// Read an order back right after writing it.
+    /// fn get_order(client: &DynamoDBClient, order_id: &str) -> Result<Option<Item>, GetItemError<std::convert::Infallible>> {
+    ///     client.get_item_with("orders", ("order_id", order_id), GetItemOptions::new().consistent_read(true))
+    /// }
+    /// ```
+    pub fn get_item_with<V, E>(
+        &self,
+        table_name: impl Into<String>,
+        key: impl Into<Key>,
+        options: GetItemOptions,
+    ) -> Result<Option<V>, GetItemError<E>>
+    where
+        V: TryFrom<Item, Error = E>,
+    {
+        match self.get_item_raw_with(table_name, key, options)?.item {
+            Some(item) => Ok(Some(
+                V::try_from(item).map_err(|e| GetItemError::TryFrom { cause: e })?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Get an item from a DynamoDB table with [GetItemOptions], along with the capacity the
+    /// read consumed when [GetItemOptions::return_consumed_capacity] is set.
+    ///
+    /// Examples:
+    /// ________
+    /// ```rust,no_run
+    /// use momento_functions_aws_ddb::{DynamoDBClient, DynamoDBError, GetItemOptions, ReturnConsumedCapacity};
+    ///
+    /// fn read_units(client: &DynamoDBClient, id: &str) -> Result<Option<f64>, DynamoDBError> {
+    ///     let output = client.get_item_raw_with(
+    ///         "my_table",
+    ///         ("id", id),
+    ///         GetItemOptions::new()
+    ///             .projection("id")
+    ///             .return_consumed_capacity(ReturnConsumedCapacity::Total),
+    ///     )?;
+    ///     Ok(output.consumed_capacity.and_then(|capacity| capacity.capacity_units))
+    /// }
+    /// ```
+    pub fn get_item_raw_with(
+        &self,
+        table_name: impl Into<String>,
+        key: impl Into<Key>,
+        options: GetItemOptions,
+    ) -> Result<GetItemOutput, DynamoDBError> {
         let key: Key = key.into();
 
-        let output = self.client.get_item(&aws_ddb::GetItemRequest {
-            table_name: table_name.into(),
-            key: key.into(),
-            consistent_read: false,
-            return_consumed_capacity: aws_ddb::ReturnConsumedCapacity::None,
-            projection_expression: None,
-            expression_attribute_names: None,
-        })?;
+        let output = self
+            .client
+            .get_item(&options.into_request(table_name.into(), key.into()))?;
 
-        match output.item {
+        let item = match output.item {
             Some(item) => match item {
                 aws_ddb::Item::Json(data) => {
                     let bytes = Data::from(data).into_bytes();
-                    Ok(serde_json::from_slice(&bytes)?)
+                    serde_json::from_slice(&bytes)?
                 }
             },
-            None => Ok(None),
-        }
+            None => None,
+        };
+        Ok(GetItemOutput {
+            item,
+            consumed_capacity: output.consumed_capacity.map(Into::into),
+        })
     }
 
     /// Put an item into a DynamoDB table.
@@ -212,4 +299,73 @@ impl DynamoDBClient {
 
         Ok(())
     }
+
+    /// Delete an item from a DynamoDB table.
+    ///
+    /// Deleting an item that does not exist succeeds.
+    ///
+    /// Examples:
+    /// ________
+    /// ```rust,no_run
+    /// # use momento_functions_aws_ddb::{DynamoDBClient, DynamoDBError};
+    /// # fn delete_some_item(client: &DynamoDBClient) -> Result<(), DynamoDBError> {
+    /// client.delete_item("my_table", ("some_attribute", "some S value"))
+    /// # }
+    /// ```
+    pub fn delete_item(
+        &self,
+        table_name: impl Into<String>,
+        key: impl Into<Key>,
+    ) -> Result<(), DynamoDBError> {
+        self.delete_item_with(table_name, key, DeleteItemOptions::default())?;
+        Ok(())
+    }
+
+    /// Delete an item from a DynamoDB table with [DeleteItemOptions], like a condition or
+    /// returning the deleted item.
+    ///
+    /// Examples:
+    /// ________
+    /// Tombstone a record, keeping what was deleted:
+    /// ```rust,no_run
+    /// use momento_functions_aws_ddb::{DeleteItemOptions, DynamoDBClient, DynamoDBError, Item, ReturnValues};
+    ///
+    /// fn take_job(client: &DynamoDBClient, job_id: &str) -> Result<Option<Item>, DynamoDBError> {
+    ///     let output = client.delete_item_with(
+    ///         "jobs",
+    ///         ("job_id", job_id),
+    ///         DeleteItemOptions::new()
+    ///             .condition("attribute_exists(job_id) AND #s = :pending")
+    ///             .expression_attribute_name("#s", "status")
+    ///             .expression_attribute_value(":pending", "pending")
+    ///             .return_values(ReturnValues::AllOld),
+    ///     )?;
+    ///     Ok(output.attributes)
+    /// }
+    /// ```
+    pub fn delete_item_with(
+        &self,
+        table_name: impl Into<String>,
+        key: impl Into<Key>,
+        options: DeleteItemOptions,
+    ) -> Result<DeleteItemOutput, DynamoDBError> {
+        let key: Key = key.into();
+
+        abi::require(DELETE_ITEM)?;
+        let output = self
+            .client
+            .delete_item(options.into_request(table_name.into(), key.into())?)?;
+
+        let attributes = match output.attributes {
+            Some(aws_ddb::Item::Json(data)) => {
+                let bytes = Data::from(data).into_bytes();
+                Some(serde_json::from_slice(&bytes)?)
+            }
+            None => None,
+        };
+        Ok(DeleteItemOutput {
+            attributes,
+            consumed_capacity: output.consumed_capacity.map(Into::into),
+        })
+    }
 }
@@ -1,21 +1,28 @@
 //! Host interfaces for working with AWS DynamoDB.
 //!
-//! This crate provides a [`DynamoDBClient`] for putting and getting items in DynamoDB,
+//! This crate provides a [`DynamoDBClient`] for putting, getting, and deleting items in DynamoDB,
 //! using Momento's host-provided AWS communication channel.
 
 mod client;
+mod options;
 mod types;
 
 /// Internal module for WIT bindings.
 #[doc(hidden)]
 pub mod wit;
 
-pub use client::{DynamoDBClient, DynamoDBError, GetItemError};
+pub use client::{DeleteItemOutput, DynamoDBClient, DynamoDBError, GetItemError, GetItemOutput};
+pub use options::{
+    Capacity, ConsumedCapacity, DeleteItemOptions, GetItemOptions, ReturnConsumedCapacity,
+    ReturnValues,
+};
 pub use types::{
     AttributeValue, BinaryConversionError, ConversionError, Item, Key, KeyValue,
     NumericConversionError,
 };
 
+pub use momento_functions_bytes::abi::UnsupportedByHost;
+
 pub use momento_functions_aws_auth::{
     AuthError, Authorization, Credentials, CredentialsProvider, IamRole, provider,
 };
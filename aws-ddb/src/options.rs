@@ -0,0 +1,298 @@
+//! Options for DynamoDB requests, and the capacity they report.
+
+use momento_functions_bytes::Data;
+
+use crate::DynamoDBError;
+use crate::types::{AttributeValue, Item};
+use crate::wit::momento::aws_ddb::aws_ddb;
+
+/// Whether DynamoDB should report the capacity a request consumed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReturnConsumedCapacity {
+    /// Do not report consumed capacity.
+    #[default]
+    None,
+    /// Report the total capacity consumed.
+    Total,
+    /// Report the total capacity, broken down by table and index.
+    Indexes,
+}
+
+impl From<ReturnConsumedCapacity> for aws_ddb::ReturnConsumedCapacity {
+    fn from(value: ReturnConsumedCapacity) -> Self {
+        match value {
+            ReturnConsumedCapacity::None => aws_ddb::ReturnConsumedCapacity::None,
+            ReturnConsumedCapacity::Total => aws_ddb::ReturnConsumedCapacity::Total,
+            ReturnConsumedCapacity::Indexes => aws_ddb::ReturnConsumedCapacity::Indexes,
+        }
+    }
+}
+
+/// Which attributes DynamoDB should return from a write.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReturnValues {
+    /// Return nothing.
+    #[default]
+    None,
+    /// Return the item as it was before the write.
+    AllOld,
+}
+
+impl From<ReturnValues> for aws_ddb::ReturnValues {
+    fn from(value: ReturnValues) -> Self {
+        match value {
+            ReturnValues::None => aws_ddb::ReturnValues::None,
+            ReturnValues::AllOld => aws_ddb::ReturnValues::AllOld,
+        }
+    }
+}
+
+/// Options for [DynamoDBClient::get_item_with](crate::DynamoDBClient::get_item_with).
+///
+/// By default reads are eventually consistent, return every attribute, and do not report
+/// consumed capacity.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_aws_ddb::{GetItemOptions, ReturnConsumedCapacity};
+///
+/// // Read your own write, fetching only the attributes you need. `#s` stands in for
+/// // `status`, which is a reserved word.
+/// let options = GetItemOptions::new()
+///     .consistent_read(true)
+///     .projection("id, #s, updated_at")
+///     .expression_attribute_name("#s", "status")
+///     .return_consumed_capacity(ReturnConsumedCapacity::Total);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GetItemOptions {
+    consistent_read: bool,
+    projection_expression: Option<String>,
+    expression_attribute_names: Vec<(String, String)>,
+    return_consumed_capacity: ReturnConsumedCapacity,
+}
+
+impl GetItemOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a strongly consistent read, which reflects every write that succeeded before it.
+    /// Consistent reads consume twice the capacity of eventually consistent ones.
+    pub fn consistent_read(mut self, consistent_read: bool) -> Self {
+        self.consistent_read = consistent_read;
+        self
+    }
+
+    /// Return only the attributes in this projection expression, like `id, profile.name`.
+    pub fn projection(mut self, projection_expression: impl Into<String>) -> Self {
+        self.projection_expression = Some(projection_expression.into());
+        self
+    }
+
+    /// Substitute `name` for `placeholder`, like `#s`, in the projection expression. Use this
+    /// for attribute names that are reserved words or contain special characters.
+    pub fn expression_attribute_name(
+        mut self,
+        placeholder: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.expression_attribute_names
+            .push((placeholder.into(), name.into()));
+        self
+    }
+
+    /// Report the capacity the read consumed.
+    pub fn return_consumed_capacity(
+        mut self,
+        return_consumed_capacity: ReturnConsumedCapacity,
+    ) -> Self {
+        self.return_consumed_capacity = return_consumed_capacity;
+        self
+    }
+
+    pub(crate) fn into_request(
+        self,
+        table_name: String,
+        key: Vec<aws_ddb::KeyAttribute>,
+    ) -> aws_ddb::GetItemRequest {
+        aws_ddb::GetItemRequest {
+            table_name,
+            key,
+            consistent_read: self.consistent_read,
+            return_consumed_capacity: self.return_consumed_capacity.into(),
+            projection_expression: self.projection_expression,
+            expression_attribute_names: (!self.expression_attribute_names.is_empty())
+                .then_some(self.expression_attribute_names),
+        }
+    }
+}
+
+/// Options for [DynamoDBClient::delete_item_with](crate::DynamoDBClient::delete_item_with).
+///
+/// By default deletes are unconditional, return nothing, and do not report consumed capacity.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_aws_ddb::{DeleteItemOptions, ReturnValues};
+///
+/// // Only delete the session if it still belongs to this user, and get it back.
+/// let options = DeleteItemOptions::new()
+///     .condition("user_id = :user")
+///     .expression_attribute_value(":user", "user-42")
+///     .return_values(ReturnValues::AllOld);
+/// ```
+#[derive(Debug, Default)]
+pub struct DeleteItemOptions {
+    condition_expression: Option<String>,
+    expression_attribute_names: Vec<(String, String)>,
+    expression_attribute_values: Vec<(String, AttributeValue)>,
+    return_values: ReturnValues,
+    return_consumed_capacity: ReturnConsumedCapacity,
+}
+
+impl DeleteItemOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only delete the item if this condition expression holds, like
+    /// `attribute_exists(id)` or `version = :expected`. When it does not, the delete fails
+    /// with a [DynamoDBError].
+    pub fn condition(mut self, condition_expression: impl Into<String>) -> Self {
+        self.condition_expression = Some(condition_expression.into());
+        self
+    }
+
+    /// Substitute `name` for `placeholder`, like `#s`, in the condition expression.
+    pub fn expression_attribute_name(
+        mut self,
+        placeholder: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.expression_attribute_names
+            .push((placeholder.into(), name.into()));
+        self
+    }
+
+    /// Substitute `value` for `placeholder`, like `:expected`, in the condition expression.
+    pub fn expression_attribute_value(
+        mut self,
+        placeholder: impl Into<String>,
+        value: impl Into<AttributeValue>,
+    ) -> Self {
+        self.expression_attribute_values
+            .push((placeholder.into(), value.into()));
+        self
+    }
+
+    /// Return the deleted item.
+    pub fn return_values(mut self, return_values: ReturnValues) -> Self {
+        self.return_values = return_values;
+        self
+    }
+
+    /// Report the capacity the delete consumed.
+    pub fn return_consumed_capacity(
+        mut self,
+        return_consumed_capacity: ReturnConsumedCapacity,
+    ) -> Self {
+        self.return_consumed_capacity = return_consumed_capacity;
+        self
+    }
+
+    pub(crate) fn into_request(
+        self,
+        table_name: String,
+        key: Vec<aws_ddb::KeyAttribute>,
+    ) -> Result<aws_ddb::DeleteItemRequest, DynamoDBError> {
+        let condition = match self.condition_expression {
+            Some(expression) => Some(aws_ddb::Conditional {
+                expression,
+                expression_attribute_names: (!self.expression_attribute_names.is_empty())
+                    .then_some(self.expression_attribute_names),
+                expression_attribute_values: if self.expression_attribute_values.is_empty() {
+                    None
+                } else {
+                    let values = Item::from(self.expression_attribute_values);
+                    Some(aws_ddb::Item::Json(
+                        Data::from(serde_json::to_vec(&values)?).into(),
+                    ))
+                },
+            }),
+            None => None,
+        };
+        Ok(aws_ddb::DeleteItemRequest {
+            table_name,
+            key,
+            return_values: self.return_values.into(),
+            return_consumed_capacity: self.return_consumed_capacity.into(),
+            condition,
+        })
+    }
+}
+
+/// The capacity a request consumed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsumedCapacity {
+    /// The table the request was made against.
+    pub table_name: String,
+    /// The total capacity units consumed.
+    pub capacity_units: Option<f64>,
+    /// The total read capacity units consumed.
+    pub read_capacity_units: Option<f64>,
+    /// The total write capacity units consumed.
+    pub write_capacity_units: Option<f64>,
+    /// The capacity consumed from the table itself, with [ReturnConsumedCapacity::Indexes].
+    pub table: Option<Capacity>,
+    /// The capacity consumed from each local secondary index, with
+    /// [ReturnConsumedCapacity::Indexes].
+    pub local_secondary_indexes: Vec<(String, Capacity)>,
+    /// The capacity consumed from each global secondary index, with
+    /// [ReturnConsumedCapacity::Indexes].
+    pub global_secondary_indexes: Vec<(String, Capacity)>,
+}
+
+/// The capacity consumed from one table or index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capacity {
+    /// The capacity units consumed.
+    pub capacity_units: Option<f64>,
+    /// The read capacity units consumed.
+    pub read_capacity_units: Option<f64>,
+    /// The write capacity units consumed.
+    pub write_capacity_units: Option<f64>,
+}
+
+impl From<aws_ddb::Capacity> for Capacity {
+    fn from(value: aws_ddb::Capacity) -> Self {
+        Self {
+            capacity_units: value.capacity_units,
+            read_capacity_units: value.read_capacity_units,
+            write_capacity_units: value.write_capacity_units,
+        }
+    }
+}
+
+impl From<aws_ddb::ConsumedCapacity> for ConsumedCapacity {
+    fn from(value: aws_ddb::ConsumedCapacity) -> Self {
+        fn indexes(indexes: Option<Vec<(String, aws_ddb::Capacity)>>) -> Vec<(String, Capacity)> {
+            indexes
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, capacity)| (name, capacity.into()))
+                .collect()
+        }
+        Self {
+            table_name: value.table_name,
+            capacity_units: value.capacity_units,
+            read_capacity_units: value.read_capacity_units,
+            write_capacity_units: value.write_capacity_units,
+            table: value.table.map(Into::into),
+            local_secondary_indexes: indexes(value.local_secondary_indexes),
+            global_secondary_indexes: indexes(value.global_secondary_indexes),
+        }
+    }
+}
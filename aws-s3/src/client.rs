@@ -1,10 +1,23 @@
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use crate::wit::momento::aws_s3::aws_s3::{self as aws_s3};
 use momento_functions_aws_auth::CredentialsProvider;
 use momento_functions_bytes::{
     Data,
+    abi::{self, PackageFunction, UnsupportedByHost},
     encoding::{Encode, EncodeError, Extract, ExtractError},
 };
 
+/// `aws-s3.client.head`
+const HEAD: PackageFunction = PackageFunction::new("momento:aws-s3", "aws-s3.client.head", 1, 1);
+/// `aws-s3.client.get-with-options`
+const GET_WITH_OPTIONS: PackageFunction =
+    PackageFunction::new("momento:aws-s3", "aws-s3.client.get-with-options", 1, 1);
+/// `aws-s3.client.presign-post`
+const PRESIGN_POST: PackageFunction =
+    PackageFunction::new("momento:aws-s3", "aws-s3.client.presign-post", 1, 1);
+
 /// S3 client for host interfaces.
 ///
 /// This client uses Momento's host-provided AWS communication channel, which
@@ -44,9 +57,47 @@ where
         /// The underlying extract error.
         cause: E,
     },
+    /// The requested byte range selects no bytes, so there was nothing to get.
+    #[error(transparent)]
+    EmptyRange(#[from] EmptyRange),
     /// An error occurred when calling the host S3 interface.
     #[error(transparent)]
     S3Error(#[from] aws_s3::S3Error),
+    /// The host is too old for ranged or conditional gets.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+/// A byte range selected no bytes, like `5..5`, `5..3`, or `..0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("The byte range {start}..{end} is empty")]
+pub struct EmptyRange {
+    /// The first byte of the range.
+    pub start: u64,
+    /// The end of the range, exclusive.
+    pub end: u64,
+}
+
+/// An error occurred while getting an object's metadata from S3.
+#[derive(Debug, thiserror::Error)]
+pub enum S3HeadError {
+    /// An error occurred when calling the host S3 interface.
+    #[error(transparent)]
+    S3Error(#[from] aws_s3::S3Error),
+    /// The host is too old to get an object's metadata.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+/// An error occurred while presigning a POST policy.
+#[derive(Debug, thiserror::Error)]
+pub enum S3PresignError {
+    /// An error occurred when calling the host S3 interface.
+    #[error(transparent)]
+    S3Error(#[from] aws_s3::S3Error),
+    /// The host is too old to presign POST policies.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
 }
 
 /// A request to put an object into an S3 bucket.
@@ -111,6 +162,249 @@ pub struct PutObjectResponse {
     pub expiration: Option<String>,
 }
 
+/// An object's metadata, returned by [`S3Client::head_object`].
+pub struct ObjectMetadata {
+    /// The size of the object in bytes.
+    pub content_length: Option<u64>,
+    /// The MIME type of the object.
+    pub content_type: Option<String>,
+    /// Entity tag of the object.
+    pub etag: Option<String>,
+    /// Version identifier of the object when bucket versioning is enabled.
+    pub version_id: Option<String>,
+    /// When the object was last modified.
+    pub last_modified: Option<SystemTime>,
+    /// User-defined metadata stored alongside the object.
+    pub metadata: Vec<(String, String)>,
+}
+
+impl ObjectMetadata {
+    /// Look up a user-defined metadata value by name.
+    ///
+    /// Names are matched without regard to case, since S3 stores them as HTTP headers.
+    pub fn user_metadata(&self, name: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Options for a partial or conditional S3 get, used with [`S3Client::get_with_options`].
+///
+/// All options are unset by default, which makes the get behave like [`S3Client::get`].
+#[derive(Debug, Default, Clone)]
+pub struct GetObjectOptions {
+    range: Option<Result<String, EmptyRange>>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<SystemTime>,
+}
+
+impl GetObjectOptions {
+    /// Create a new set of options with nothing set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only get these bytes of the object, like `0..1024` or `1024..`.
+    ///
+    /// The end is exclusive, as with any Rust range. A range that selects no bytes, like
+    /// `5..5`, makes the get fail with [S3GetError::EmptyRange] without calling S3.
+    pub fn range(mut self, range: impl RangeBounds<u64>) -> Self {
+        self.range = Some(range_header(range));
+        self
+    }
+
+    /// Only get the last `length` bytes of the object, or all of it if it is shorter.
+    pub fn last_bytes(mut self, length: u64) -> Self {
+        self.range = Some(Ok(format!("bytes=-{length}")));
+        self
+    }
+
+    /// Only get the object if its ETag differs from this one.
+    ///
+    /// Pass the ETag from a previous get to avoid refetching an unchanged object.
+    pub fn if_none_match(mut self, etag: impl Into<String>) -> Self {
+        self.if_none_match = Some(etag.into());
+        self
+    }
+
+    /// Only get the object if it was modified after this time.
+    pub fn if_modified_since(mut self, time: SystemTime) -> Self {
+        self.if_modified_since = Some(time);
+        self
+    }
+}
+
+/// The outcome of a [`S3Client::get_with_options`] call.
+pub enum ConditionalGetResponse<T> {
+    /// The object, or the requested range of it.
+    Found(ObjectContent<T>),
+    /// The object has not changed since the ETag or time in the request's conditions.
+    NotModified {
+        /// The object's current ETag.
+        etag: Option<String>,
+    },
+    /// The requested range starts past the end of the object.
+    RangeNotSatisfiable {
+        /// The size of the object, like `bytes */4096`.
+        content_range: Option<String>,
+    },
+    /// No object exists with the given bucket and key.
+    NotFound,
+}
+
+/// An object, or part of one, returned by [`S3Client::get_with_options`].
+pub struct ObjectContent<T> {
+    /// The decoded body.
+    pub body: T,
+    /// The HTTP status S3 responded with: 200 for a whole object, or 206 for a range.
+    pub status: u16,
+    /// The length of the returned body.
+    pub content_length: Option<u64>,
+    /// Which bytes of the object were returned, like `bytes 0-1023/4096`, for a range.
+    pub content_range: Option<String>,
+    /// The MIME type of the object.
+    pub content_type: Option<String>,
+    /// Entity tag of the object.
+    pub etag: Option<String>,
+    /// Version identifier of the object when bucket versioning is enabled.
+    pub version_id: Option<String>,
+    /// Expiration metadata returned by S3, when a lifecycle rule applies.
+    pub expiration: Option<String>,
+    /// When the object was last modified.
+    pub last_modified: Option<SystemTime>,
+    /// User-defined metadata stored alongside the object.
+    pub metadata: Vec<(String, String)>,
+}
+
+impl<T> ObjectContent<T> {
+    /// True when only part of the object was returned.
+    pub fn is_partial(&self) -> bool {
+        self.status == 206
+    }
+}
+
+/// A condition S3 checks against a browser's upload form, for [`PresignedPostRequest::condition`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostCondition {
+    /// The form field must have exactly this value.
+    Equals {
+        /// The form field, like `Content-Type` or `x-amz-meta-owner`.
+        field: String,
+        /// The value it must have.
+        value: String,
+    },
+    /// The form field must start with this prefix. An empty prefix allows any value.
+    StartsWith {
+        /// The form field, like `key` or `Content-Type`.
+        field: String,
+        /// The prefix its value must start with.
+        prefix: String,
+    },
+    /// The uploaded file's size in bytes must be in this range.
+    ContentLengthRange(RangeInclusive<u64>),
+}
+
+impl From<PostCondition> for aws_s3::PostCondition {
+    fn from(condition: PostCondition) -> Self {
+        match condition {
+            PostCondition::Equals { field, value } => aws_s3::PostCondition::Equals((field, value)),
+            PostCondition::StartsWith { field, prefix } => {
+                aws_s3::PostCondition::StartsWith((field, prefix))
+            }
+            PostCondition::ContentLengthRange(range) => {
+                aws_s3::PostCondition::ContentLengthRange((*range.start(), *range.end()))
+            }
+        }
+    }
+}
+
+/// A POST policy to presign with [`S3Client::presigned_post`], letting a browser upload a file
+/// straight to S3 with a form.
+///
+/// Unlike a presigned PUT URL, a POST policy can limit what is uploaded, like its size and
+/// content type, and S3 rejects uploads that break those limits.
+///
+/// Construct with [`PresignedPostRequest::new`] and add limits with the builder methods.
+#[derive(Debug, Clone)]
+pub struct PresignedPostRequest {
+    bucket: String,
+    key: String,
+    expires_in: Duration,
+    fields: Vec<(String, String)>,
+    conditions: Vec<PostCondition>,
+}
+
+impl PresignedPostRequest {
+    /// Allow one upload to `key` in `bucket` until `expires_in` from now.
+    ///
+    /// `${filename}` in the key is replaced by the name of the uploaded file.
+    pub fn new(bucket: impl Into<String>, key: impl Into<String>, expires_in: Duration) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            expires_in,
+            fields: Vec::new(),
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Only accept files whose size in bytes is in `range`.
+    pub fn content_length_range(self, range: RangeInclusive<u64>) -> Self {
+        self.condition(PostCondition::ContentLengthRange(range))
+    }
+
+    /// Require this content type, which S3 stores with the object.
+    pub fn content_type(self, content_type: impl Into<String>) -> Self {
+        self.field("Content-Type", content_type)
+    }
+
+    /// Require a content type starting with `prefix`, like `image/`. The browser sets the
+    /// `Content-Type` form field itself.
+    pub fn content_type_starts_with(self, prefix: impl Into<String>) -> Self {
+        self.condition(PostCondition::StartsWith {
+            field: "Content-Type".to_string(),
+            prefix: prefix.into(),
+        })
+    }
+
+    /// Sign a form field, like `x-amz-meta-owner`, and return it for the form. S3 rejects the
+    /// upload if it is changed.
+    pub fn field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add a condition on the form.
+    pub fn condition(mut self, condition: PostCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    fn into_request(self) -> aws_s3::PresignPostRequest {
+        aws_s3::PresignPostRequest {
+            bucket: self.bucket,
+            key: self.key,
+            expires_in_seconds: self.expires_in.as_secs(),
+            fields: self.fields,
+            conditions: self.conditions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// A presigned POST policy, returned by [`S3Client::presigned_post`].
+///
+/// Send it to the browser, which submits a `multipart/form-data` POST to `url` with each of
+/// `fields`, in order, followed by the file in a field named `file`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedPost {
+    /// The URL to submit the form to.
+    pub url: String,
+    /// The form fields to submit before the file, including the policy and its signature.
+    pub fields: Vec<(String, String)>,
+}
+
 impl S3Client {
     /// Create a new S3 client.
     pub fn new(credentials: &CredentialsProvider) -> Self {
@@ -177,4 +471,264 @@ impl S3Client {
             Ok(None)
         }
     }
+
+    /// Get an object's size, ETag, modification time, and user-defined metadata without
+    /// getting its body.
+    ///
+    /// Returns `Ok(None)` if the object was not found.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use momento_functions_aws_s3::S3Client;
+    ///
+    /// # let client: S3Client = todo!();
+    /// match client.head_object("my-bucket", "my-key") {
+    ///     Ok(Some(metadata)) if metadata.content_length.unwrap_or(0) > 1024 * 1024 => {
+    ///         /* too big to cache; stream it in ranges instead */
+    ///     }
+    ///     Ok(Some(metadata)) => {
+    ///         println!("owner: {:?}", metadata.user_metadata("owner"));
+    ///     }
+    ///     Ok(None) => { /* key not found */ }
+    ///     Err(e) => eprintln!("head_object failed: {e}"),
+    /// }
+    /// ```
+    pub fn head_object(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+    ) -> Result<Option<ObjectMetadata>, S3HeadError> {
+        abi::require(HEAD)?;
+        let output = self.client.head(&aws_s3::GetObjectRequest {
+            bucket: bucket.into(),
+            key: key.into(),
+        })?;
+        Ok(output.map(|output| ObjectMetadata {
+            content_length: output.content_length,
+            content_type: output.content_type,
+            etag: output.etag,
+            version_id: output.version_id,
+            last_modified: output
+                .last_modified_epoch_seconds
+                .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds)),
+            metadata: output.metadata,
+        }))
+    }
+
+    /// Get part of an object from an S3 bucket.
+    ///
+    /// The end of the range is exclusive, so `0..1024` gets the first 1024 bytes.
+    /// Returns `Ok(None)` if the object was not found, and an error if the range starts past
+    /// the end of the object.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use momento_functions_aws_s3::S3Client;
+    ///
+    /// # let client: S3Client = todo!();
+    /// match client.get_range::<Vec<u8>>("my-bucket", "my-video", 0..1024 * 1024) {
+    ///     Ok(Some(content)) => {
+    ///         println!("got {:?} of the object", content.content_range);
+    ///     }
+    ///     Ok(None) => { /* key not found */ }
+    ///     Err(e) => eprintln!("get_range failed: {e}"),
+    /// }
+    /// ```
+    pub fn get_range<T: Extract>(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        range: impl RangeBounds<u64>,
+    ) -> Result<Option<ObjectContent<T>>, S3GetError<T::Error>> {
+        match self.get_with_options(bucket, key, GetObjectOptions::new().range(range))? {
+            ConditionalGetResponse::Found(content) => Ok(Some(content)),
+            ConditionalGetResponse::RangeNotSatisfiable { content_range } => {
+                Err(S3GetError::S3Error(aws_s3::S3Error::Malformed(format!(
+                    "range not satisfiable: object is {}",
+                    content_range.as_deref().unwrap_or("bytes */unknown")
+                ))))
+            }
+            // There are no conditions, so S3 does not respond with "not modified."
+            ConditionalGetResponse::NotModified { .. } | ConditionalGetResponse::NotFound => {
+                Ok(None)
+            }
+        }
+    }
+
+    /// Get an object, or part of one, from an S3 bucket, subject to conditions.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use momento_functions_aws_s3::{ConditionalGetResponse, GetObjectOptions, S3Client};
+    ///
+    /// # let client: S3Client = todo!();
+    /// # let cached_etag = "\"previous-etag\"";
+    /// let options = GetObjectOptions::new().if_none_match(cached_etag);
+    /// match client.get_with_options::<Vec<u8>>("my-bucket", "my-key", options) {
+    ///     Ok(ConditionalGetResponse::Found(content)) => {
+    ///         println!("object changed, new etag {:?}", content.etag);
+    ///     }
+    ///     Ok(ConditionalGetResponse::NotModified { .. }) => { /* serve the cached copy */ }
+    ///     Ok(ConditionalGetResponse::RangeNotSatisfiable { .. }) => { /* no range was asked for */ }
+    ///     Ok(ConditionalGetResponse::NotFound) => { /* key not found */ }
+    ///     Err(e) => eprintln!("get_with_options failed: {e}"),
+    /// }
+    /// ```
+    pub fn get_with_options<T: Extract>(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        options: GetObjectOptions,
+    ) -> Result<ConditionalGetResponse<T>, S3GetError<T::Error>> {
+        let range = options.range.transpose()?;
+        abi::require(GET_WITH_OPTIONS)?;
+        let output = self
+            .client
+            .get_with_options(
+                &aws_s3::GetObjectRequest {
+                    bucket: bucket.into(),
+                    key: key.into(),
+                },
+                &aws_s3::GetObjectOptions {
+                    range,
+                    if_none_match: options.if_none_match,
+                    if_modified_since_epoch_seconds: options
+                        .if_modified_since
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                        .map(|since_epoch| since_epoch.as_secs()),
+                },
+            )
+            .map_err(S3GetError::from)?;
+        match output.status {
+            304 => return Ok(ConditionalGetResponse::NotModified { etag: output.etag }),
+            416 => {
+                return Ok(ConditionalGetResponse::RangeNotSatisfiable {
+                    content_range: output.content_range,
+                });
+            }
+            _ => {}
+        }
+        let Some(wit_data) = output.body else {
+            return Ok(ConditionalGetResponse::NotFound);
+        };
+        let data: Data = wit_data.into();
+        let body = T::extract(data).map_err(|e| S3GetError::ExtractFailed { cause: e })?;
+        Ok(ConditionalGetResponse::Found(ObjectContent {
+            body,
+            status: output.status,
+            content_length: output.content_length,
+            content_range: output.content_range,
+            content_type: output.content_type,
+            etag: output.etag,
+            version_id: output.version_id,
+            expiration: output.expiration,
+            last_modified: output
+                .last_modified_epoch_seconds
+                .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds)),
+            metadata: output.metadata,
+        }))
+    }
+
+    /// Presign a POST policy with this client's credentials, so a browser can upload a file
+    /// straight to S3 without the file passing through your Function.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use momento_functions_aws_s3::{PresignedPostRequest, S3Client};
+    ///
+    /// # let client: S3Client = todo!();
+    /// let request = PresignedPostRequest::new(
+    ///     "user-uploads",
+    ///     "avatars/user-42/${filename}",
+    ///     Duration::from_secs(300),
+    /// )
+    /// .content_type_starts_with("image/")
+    /// .content_length_range(1..=5 * 1024 * 1024)
+    /// .field("x-amz-meta-owner", "user-42");
+    /// match client.presigned_post(request) {
+    ///     Ok(post) => println!("POST to {} with {} fields", post.url, post.fields.len()),
+    ///     Err(e) => eprintln!("presigned_post failed: {e}"),
+    /// }
+    /// ```
+    pub fn presigned_post(
+        &self,
+        request: PresignedPostRequest,
+    ) -> Result<PresignedPost, S3PresignError> {
+        abi::require(PRESIGN_POST)?;
+        let output = self.client.presign_post(&request.into_request())?;
+        Ok(PresignedPost {
+            url: output.url,
+            fields: output.fields,
+        })
+    }
+}
+
+/// Format a Rust range as an HTTP `Range` header value, whose end is inclusive. A range
+/// that selects no bytes has no header value.
+fn range_header(range: impl RangeBounds<u64>) -> Result<String, EmptyRange> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => end.checked_add(1),
+        Bound::Excluded(end) => Some(*end),
+        Bound::Unbounded => None,
+    };
+    match end {
+        Some(end) if end <= start => Err(EmptyRange { start, end }),
+        Some(end) => Ok(format!("bytes={start}-{}", end - 1)),
+        None => Ok(format!("bytes={start}-")),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_become_inclusive_headers() {
+        assert_eq!(Ok("bytes=0-1023".to_string()), range_header(0..1024));
+        assert_eq!(Ok("bytes=5-5".to_string()), range_header(5..=5));
+        assert_eq!(Ok("bytes=1024-".to_string()), range_header(1024..));
+        assert_eq!(Ok("bytes=7-".to_string()), range_header(7..=u64::MAX));
+        assert_eq!(Ok("bytes=0-".to_string()), range_header(..));
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn empty_ranges_are_rejected() {
+        assert_eq!(Err(EmptyRange { start: 5, end: 5 }), range_header(5..5));
+        assert_eq!(Err(EmptyRange { start: 5, end: 3 }), range_header(5..3));
+        assert_eq!(Err(EmptyRange { start: 0, end: 0 }), range_header(..0));
+        assert_eq!(Err(EmptyRange { start: 5, end: 4 }), range_header(5..=3));
+    }
+
+    #[test]
+    fn post_policies_sign_fields_and_conditions() {
+        let request = PresignedPostRequest::new(
+            "uploads",
+            "avatars/${filename}",
+            Duration::from_millis(300_500),
+        )
+        .content_type("image/png")
+        .content_type_starts_with("image/")
+        .content_length_range(1..=1024)
+        .into_request();
+        assert_eq!(300, request.expires_in_seconds);
+        assert_eq!(
+            vec![("Content-Type".to_string(), "image/png".to_string())],
+            request.fields
+        );
+        assert!(matches!(
+            request.conditions.as_slice(),
+            [
+                aws_s3::PostCondition::StartsWith((field, prefix)),
+                aws_s3::PostCondition::ContentLengthRange((1, 1024)),
+            ] if field == "Content-Type" && prefix == "image/"
+        ));
+    }
 }
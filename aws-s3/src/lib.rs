@@ -1,7 +1,7 @@
 //! Host interfaces for working with AWS S3.
 //!
-//! This crate provides an [`S3Client`] for putting and getting objects in S3,
-//! using Momento's host-provided AWS communication channel.
+//! This crate provides an [`S3Client`] for putting, getting, and inspecting objects in S3,
+//! and for presigning browser uploads, using Momento's host-provided AWS communication channel.
 //!
 //! Functions use `wasm32-wasip2` as the target architecture.
 //! They use the [WIT](https://component-model.bytecodealliance.org/design/wit.html) [Component Model](https://component-model.bytecodealliance.org/)
@@ -14,9 +14,13 @@ mod client;
 pub mod wit;
 
 pub use client::{
-    GetObjectResponse, PutObjectRequest, PutObjectResponse, S3Client, S3GetError, S3PutError,
+    ConditionalGetResponse, EmptyRange, GetObjectOptions, GetObjectResponse, ObjectContent,
+    ObjectMetadata, PostCondition, PresignedPost, PresignedPostRequest, PutObjectRequest,
+    PutObjectResponse, S3Client, S3GetError, S3HeadError, S3PresignError, S3PutError,
 };
 
+pub use momento_functions_bytes::abi::UnsupportedByHost;
+
 pub use momento_functions_aws_auth::{
     AuthError, Authorization, Credentials, CredentialsProvider, IamRole, provider,
 };
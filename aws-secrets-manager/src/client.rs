@@ -1,8 +1,13 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
 use std::time::Duration;
 
 use crate::wit::momento::aws_secrets::aws_secrets::{self as aws_secrets};
 use momento_functions_aws_auth::CredentialsProvider;
 use momento_functions_bytes::encoding::{Extract, ExtractError};
+use serde::de::DeserializeOwned;
+
+use crate::secret_string::zeroize;
 
 /// Secrets Manager client for host interfaces.
 ///
@@ -10,8 +15,69 @@ use momento_functions_bytes::encoding::{Extract, ExtractError};
 /// is kept hot at all times. When your Function has not run in several days or more,
 /// the channel is still hot and ready, keeping your Function invocations predictable
 /// even when your demand is unpredictable.
+///
+/// The client remembers which secret versions it has seen, so it can tell you when a secret
+/// has rotated. Keep one client for the life of your Function instance (for example in a
+/// `static`) to get the most out of this.
 pub struct SecretsManagerClient {
     client: aws_secrets::Client,
+    secrets: Mutex<HashMap<String, SecretState>>,
+}
+
+/// What the client has seen of a secret.
+#[derive(Default)]
+struct SecretState {
+    /// The next fetch must go to Secrets Manager, bypassing any cached value.
+    invalidated: bool,
+    /// The last version id returned for each version stage.
+    versions: HashMap<String, String>,
+}
+
+impl SecretState {
+    /// Record a version fetched from Secrets Manager, returning whether `stage` has rotated.
+    ///
+    /// A pinned version id (no `stage`) says nothing about what is current, so it leaves an
+    /// invalidation in place for the next stage fetch.
+    fn fetched(&mut self, stage: Option<String>, version_id: &str) -> bool {
+        let Some(stage) = stage else {
+            return false;
+        };
+        self.invalidated = false;
+        self.versions
+            .insert(stage, version_id.to_string())
+            .is_some_and(|previous| previous != version_id)
+    }
+}
+
+/// The AWS Secrets Manager version stage for the current version of a secret.
+pub const AWS_CURRENT: &str = "AWSCURRENT";
+/// The AWS Secrets Manager version stage for a version being rotated in.
+pub const AWS_PENDING: &str = "AWSPENDING";
+
+/// A secret value, along with which version of the secret it is.
+#[derive(Debug)]
+pub struct VersionedSecret<T> {
+    /// The secret value.
+    pub value: T,
+    /// The version id of the secret.
+    pub version_id: String,
+    /// The version stages attached to this version, like `AWSCURRENT` or `AWSPENDING`.
+    pub version_stages: Vec<String>,
+    /// When this version was created, in seconds since the unix epoch.
+    pub created_date_epoch_seconds: u64,
+    /// True when a different version was previously returned for this stage by this client.
+    ///
+    /// When this is set, credentials derived from the old version should be rebuilt.
+    pub rotated: bool,
+}
+
+/// The versions of a secret involved in a rotation.
+#[derive(Debug)]
+pub struct SecretVersions<T> {
+    /// The `AWSCURRENT` version of the secret.
+    pub current: VersionedSecret<T>,
+    /// The `AWSPENDING` version of the secret, when a rotation is in progress.
+    pub pending: Option<VersionedSecret<T>>,
 }
 
 /// Builder for a Secrets Manager `GetSecretValue` request.
@@ -71,6 +137,7 @@ impl SecretsManagerClient {
     pub fn new(credentials: &CredentialsProvider) -> Self {
         Self {
             client: aws_secrets::Client::new(credentials),
+            secrets: Default::default(),
         }
     }
 
@@ -92,6 +159,148 @@ impl SecretsManagerClient {
         allowed_staleness: Duration,
     ) -> Result<T, SecretsManagerGetSecretValueError<T::Error>> {
         self.do_get_secret_value(request, allowed_staleness)
+            .map(|secret| secret.value)
+    }
+
+    /// Get a secret stored as JSON, deserialized into your own type.
+    ///
+    /// The raw secret bytes are zeroed once they have been deserialized. Consider using
+    /// [SecretString](crate::SecretString) for the sensitive fields of your type, so they are
+    /// redacted if the value is ever logged.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use momento_functions_aws_secrets_manager::{GetSecretValueRequest, SecretString, SecretsManagerClient};
+    ///
+    /// #[derive(Debug, serde::Deserialize)]
+    /// struct DatabaseCredentials {
+    ///     username: String,
+    ///     password: SecretString,
+    /// }
+    ///
+    /// fn fetch(client: &SecretsManagerClient) {
+    ///     match client.get_secret_json::<DatabaseCredentials>(
+    ///         GetSecretValueRequest::new("my-database"),
+    ///         Duration::from_secs(300),
+    ///     ) {
+    ///         // The password is printed as [REDACTED]
+    ///         Ok(credentials) => println!("{credentials:?}"),
+    ///         Err(e) => eprintln!("get_secret_json failed: {e}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn get_secret_json<T: DeserializeOwned>(
+        &self,
+        request: GetSecretValueRequest,
+        allowed_staleness: Duration,
+    ) -> Result<T, SecretsManagerGetSecretValueError<serde_json::Error>> {
+        let secret = self
+            .do_get_secret_value::<Vec<u8>>(request, allowed_staleness)
+            .map_err(|e| match e {
+                SecretsManagerGetSecretValueError::ExtractFailed { cause } => match cause {},
+                SecretsManagerGetSecretValueError::SecretsManagerError(e) => {
+                    SecretsManagerGetSecretValueError::SecretsManagerError(e)
+                }
+            })?
+            .value;
+        let parsed = serde_json::from_slice(&secret)
+            .map_err(|cause| SecretsManagerGetSecretValueError::ExtractFailed { cause });
+        zeroize(secret);
+        parsed
+    }
+
+    /// Like [`get_secret_value`](Self::get_secret_value), but also returns the version of the
+    /// secret and whether it has rotated since this client last fetched it.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use momento_functions_aws_secrets_manager::{GetSecretValueRequest, SecretsManagerClient};
+    ///
+    /// fn fetch(client: &SecretsManagerClient) {
+    ///     match client.get_secret_versioned::<String>(
+    ///         GetSecretValueRequest::new("my-secret"),
+    ///         Duration::from_secs(300),
+    ///     ) {
+    ///         Ok(secret) if secret.rotated => println!("rotated to {}", secret.version_id),
+    ///         Ok(secret) => println!("still on {}", secret.version_id),
+    ///         Err(e) => eprintln!("get_secret_versioned failed: {e}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn get_secret_versioned<T: Extract>(
+        &self,
+        request: GetSecretValueRequest,
+        allowed_staleness: Duration,
+    ) -> Result<VersionedSecret<T>, SecretsManagerGetSecretValueError<T::Error>> {
+        self.do_get_secret_value(request, allowed_staleness)
+    }
+
+    /// Get both the `AWSCURRENT` and, if a rotation is in progress, the `AWSPENDING` versions
+    /// of a secret.
+    ///
+    /// During a rotation, callers may present either version. Accepting both avoids an
+    /// outage while the rotation settles.
+    pub fn get_secret_versions<T: Extract>(
+        &self,
+        secret_id: impl Into<String>,
+        allowed_staleness: Duration,
+    ) -> Result<SecretVersions<T>, SecretsManagerGetSecretValueError<T::Error>> {
+        let secret_id = secret_id.into();
+        let current = self.do_get_secret_value(
+            GetSecretValueRequest::new(&secret_id).version_stage(AWS_CURRENT),
+            allowed_staleness,
+        )?;
+        let pending = match self.do_get_secret_value(
+            GetSecretValueRequest::new(&secret_id).version_stage(AWS_PENDING),
+            allowed_staleness,
+        ) {
+            Ok(pending) => Some(pending),
+            Err(SecretsManagerGetSecretValueError::SecretsManagerError(
+                aws_secrets::SecretsError::NotFound,
+            )) => None,
+            Err(e) => return Err(e),
+        };
+        Ok(SecretVersions { current, pending })
+    }
+
+    /// Forget any cached value for a secret, so the next fetch goes to Secrets Manager
+    /// regardless of the allowed staleness.
+    ///
+    /// Call this when the credentials in a secret are rejected: the secret has likely been
+    /// rotated since it was cached.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use momento_functions_aws_secrets_manager::{GetSecretValueRequest, SecretsManagerClient};
+    ///
+    /// fn call_upstream(token: &str) -> Result<(), u16> {
+    ///     Err(401)
+    /// }
+    ///
+    /// fn fetch_and_call(client: &SecretsManagerClient) {
+    ///     let request = || GetSecretValueRequest::new("my-secret");
+    ///     let staleness = Duration::from_secs(300);
+    ///     let Ok(token) = client.get_secret_value::<String>(request(), staleness) else {
+    ///         return;
+    ///     };
+    ///     if let Err(401) = call_upstream(&token) {
+    ///         client.invalidate("my-secret");
+    ///         if let Ok(token) = client.get_secret_value::<String>(request(), staleness) {
+    ///             let _ = call_upstream(&token);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn invalidate(&self, secret_id: impl AsRef<str>) {
+        self.secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(secret_id.as_ref().to_string())
+            .or_default()
+            .invalidated = true;
     }
 
     /// Like [`get_secret_value`](Self::get_secret_value), but always makes a request to
@@ -101,13 +310,36 @@ impl SecretsManagerClient {
         request: GetSecretValueRequest,
     ) -> Result<T, SecretsManagerGetSecretValueError<T::Error>> {
         self.do_get_secret_value(request, Duration::from_secs(0))
+            .map(|secret| secret.value)
     }
 
     fn do_get_secret_value<T: Extract>(
         &self,
         request: GetSecretValueRequest,
         allowed_staleness: Duration,
-    ) -> Result<T, SecretsManagerGetSecretValueError<T::Error>> {
+    ) -> Result<VersionedSecret<T>, SecretsManagerGetSecretValueError<T::Error>> {
+        let invalidated = self
+            .secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&request.secret_id)
+            .is_some_and(|state| state.invalidated);
+        let allowed_staleness = if invalidated {
+            Duration::from_secs(0)
+        } else {
+            allowed_staleness
+        };
+        let secret_id = request.secret_id.clone();
+        // Fetching a pinned version id says nothing about rotation, so only track stages.
+        let stage = match &request.version_id {
+            Some(_) => None,
+            None => Some(
+                request
+                    .version_stage
+                    .clone()
+                    .unwrap_or_else(|| AWS_CURRENT.to_string()),
+            ),
+        };
         let response = self
             .client
             .get_secret_value(&aws_secrets::GetSecretValueRequest {
@@ -117,13 +349,48 @@ impl SecretsManagerClient {
                 allowed_staleness_seconds: allowed_staleness.as_secs(),
             })?;
 
+        let rotated = {
+            let mut secrets = self.secrets.lock().unwrap_or_else(PoisonError::into_inner);
+            secrets
+                .entry(secret_id)
+                .or_default()
+                .fetched(stage, &response.version_id)
+        };
+
         // Normalize the secret to bytes regardless of which variant was returned.
         let secret_bytes = match response.secret {
             aws_secrets::SecretValue::SecretBytes(bytes) => bytes,
             aws_secrets::SecretValue::SecretString(s) => s.into_bytes(),
         };
 
-        T::extract(secret_bytes.into())
-            .map_err(|cause| SecretsManagerGetSecretValueError::ExtractFailed { cause })
+        let value = T::extract(secret_bytes.into())
+            .map_err(|cause| SecretsManagerGetSecretValueError::ExtractFailed { cause })?;
+        Ok(VersionedSecret {
+            value,
+            version_id: response.version_id,
+            version_stages: response.version_stages,
+            created_date_epoch_seconds: response.created_date_epoch_seconds,
+            rotated,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_versions_leave_the_invalidation_for_the_next_stage_fetch() {
+        let mut state = SecretState::default();
+        assert!(!state.fetched(Some(AWS_CURRENT.to_string()), "v1"));
+
+        state.invalidated = true;
+        assert!(!state.fetched(None, "v0"));
+        assert!(state.invalidated);
+
+        assert!(state.fetched(Some(AWS_CURRENT.to_string()), "v2"));
+        assert!(!state.invalidated);
+        assert!(!state.fetched(Some(AWS_CURRENT.to_string()), "v2"));
     }
 }
@@ -8,12 +8,17 @@
 //! to describe the ABI.
 
 mod client;
+mod secret_string;
 
 /// Internal module for WIT bindings.
 #[doc(hidden)]
 pub mod wit;
 
-pub use client::{GetSecretValueRequest, SecretsManagerClient, SecretsManagerGetSecretValueError};
+pub use client::{
+    AWS_CURRENT, AWS_PENDING, GetSecretValueRequest, SecretVersions, SecretsManagerClient,
+    SecretsManagerGetSecretValueError, VersionedSecret,
+};
+pub use secret_string::SecretString;
 
 pub use momento_functions_aws_auth::{
     AuthError, Authorization, Credentials, CredentialsProvider, IamRole, provider,
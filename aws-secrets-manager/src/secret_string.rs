@@ -0,0 +1,120 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use momento_functions_bytes::Data;
+use momento_functions_bytes::encoding::Extract;
+
+/// A secret string that stays out of your logs.
+///
+/// `Debug` and `Display` print `[REDACTED]` instead of the value, so a stray `{:?}` in a log
+/// line does not leak credentials. The memory holding the secret is zeroed when it is dropped.
+///
+/// Use [SecretString::expose_secret] when you actually need the value.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use momento_functions_aws_secrets_manager::{GetSecretValueRequest, SecretString, SecretsManagerClient};
+///
+/// fn fetch(client: &SecretsManagerClient) {
+///     match client.get_secret_value::<SecretString>(
+///         GetSecretValueRequest::new("my-api-key"),
+///         Duration::from_secs(300),
+///     ) {
+///         Ok(api_key) => {
+///             // Prints "using [REDACTED]"
+///             println!("using {api_key}");
+///             let _header = format!("Bearer {}", api_key.expose_secret());
+///         }
+///         Err(e) => eprintln!("get_secret_value failed: {e}"),
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SecretString {
+    value: String,
+}
+
+impl SecretString {
+    /// Wrap a secret value.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+
+    /// Access the secret value. Take care not to log it!
+    pub fn expose_secret(&self) -> &str {
+        &self.value
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+impl Display for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        zeroize(std::mem::take(&mut self.value).into_bytes());
+    }
+}
+
+impl Extract for SecretString {
+    type Error = std::string::FromUtf8Error;
+
+    fn extract(payload: Data) -> Result<Self, Self::Error> {
+        String::from_utf8(payload.into_bytes()).map(Self::new)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SecretString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Overwrite the whole allocation of a buffer with zeros before freeing it.
+pub(crate) fn zeroize(mut buffer: Vec<u8>) {
+    let capacity = buffer.capacity();
+    let pointer = buffer.as_mut_ptr();
+    for offset in 0..capacity {
+        // SAFETY: `offset` is within the buffer's allocation, which we own. Volatile writes
+        // keep the compiler from eliding stores to memory that is about to be freed.
+        unsafe { std::ptr::write_volatile(pointer.add(offset), 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_never_shows_the_secret() {
+        let secret = SecretString::new("hunter2");
+        for formatted in [
+            format!("{secret:?}"),
+            format!("{secret:#?}"),
+            format!("{secret}"),
+            format!("{:?}", Some(secret.clone())),
+        ] {
+            assert!(!formatted.contains("hunter2"), "{formatted}");
+            assert!(formatted.contains("[REDACTED]"), "{formatted}");
+        }
+        assert_eq!("hunter2", secret.expose_secret());
+    }
+}
@@ -0,0 +1,133 @@
+//! Negotiating which functions of a host package are available.
+//!
+//! The host packages that build on `momento:bytes`, like `momento:aws-s3` and `momento:http`,
+//! stay at version 1.0.0 while functions are added to them, because a component only imports
+//! the functions it calls: a Function that calls nothing new instantiates on every 1.x host.
+//!
+//! Instead, the host advertises the level of each package it provides in the
+//! `__HOST_PACKAGE_VERSIONS` environment variable, like
+//! `momento:aws-s3@1.1.0,momento:http@1.1.0`, and each [PackageFunction] records the level
+//! that added it. The client crates check [require] before calling anything newer than 1.0.0,
+//! and return an error instead of trapping.
+
+use std::fmt::{self, Display};
+
+/// The environment variable the host uses to advertise the package levels it provides.
+pub const HOST_PACKAGE_VERSIONS_VARIABLE: &str = "__HOST_PACKAGE_VERSIONS";
+
+/// A function added to a host package after its 1.0.0 level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PackageFunction {
+    /// The package, like `momento:aws-s3`.
+    pub package: &'static str,
+    /// The function, like `aws-s3.client.head`.
+    pub function: &'static str,
+    /// The package's `major.minor` level that added the function.
+    pub since: (u32, u32),
+}
+
+impl PackageFunction {
+    /// A function of `package` added at the `major.minor` level.
+    pub const fn new(
+        package: &'static str,
+        function: &'static str,
+        major: u32,
+        minor: u32,
+    ) -> Self {
+        Self {
+            package,
+            function,
+            since: (major, minor),
+        }
+    }
+}
+
+/// The host does not provide a function this Function called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedByHost {
+    /// The function that was called.
+    pub function: PackageFunction,
+    /// The `major.minor` level of the package the host provides.
+    pub host: (u32, u32),
+}
+
+impl Display for UnsupportedByHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let PackageFunction {
+            package,
+            function,
+            since: (major, minor),
+        } = self.function;
+        write!(
+            f,
+            "{function} needs {package}@{major}.{minor}.0, but the host provides {package}@{}.{}.0",
+            self.host.0, self.host.1
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedByHost {}
+
+/// Check that the host provides `function` before calling it.
+///
+/// A host that does not advertise package levels is assumed to provide everything this
+/// Function was built against. A host that advertises them, but not for `function`'s package,
+/// provides that package at 1.0.0.
+pub fn require(function: PackageFunction) -> Result<(), UnsupportedByHost> {
+    check(
+        std::env::var(HOST_PACKAGE_VERSIONS_VARIABLE)
+            .ok()
+            .as_deref(),
+        function,
+    )
+}
+
+fn check(advertised: Option<&str>, function: PackageFunction) -> Result<(), UnsupportedByHost> {
+    let Some(advertised) = advertised else {
+        return Ok(());
+    };
+    let host = advertised
+        .split(',')
+        .filter_map(|entry| entry.trim().split_once('@'))
+        .find(|(package, _)| *package == function.package)
+        .and_then(|(_, version)| parse(version))
+        .unwrap_or((1, 0));
+    let (major, minor) = function.since;
+    if host.0 == major && minor <= host.1 {
+        Ok(())
+    } else {
+        Err(UnsupportedByHost { function, host })
+    }
+}
+
+/// The `major.minor` of a version like `1.2.0`. A missing minor number is read as 0.
+fn parse(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+    Some((major, minor))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const HEAD: PackageFunction =
+        PackageFunction::new("momento:aws-s3", "aws-s3.client.head", 1, 1);
+
+    #[test]
+    fn hosts_without_the_level_are_refused() {
+        assert!(check(None, HEAD).is_ok());
+        assert!(check(Some("momento:aws-s3@1.1.0"), HEAD).is_ok());
+        assert!(check(Some("momento:http@1.0.0, momento:aws-s3@1.3.2"), HEAD).is_ok());
+        assert!(check(Some("momento:aws-s3@2.1.0"), HEAD).is_err());
+
+        let error = check(Some("momento:http@1.1.0"), HEAD).expect_err("package not listed");
+        assert_eq!((1, 0), error.host);
+        assert_eq!(
+            "aws-s3.client.head needs momento:aws-s3@1.1.0, but the host provides momento:aws-s3@1.0.0",
+            error.to_string()
+        );
+    }
+}
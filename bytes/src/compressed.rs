@@ -0,0 +1,429 @@
+use crate::Data;
+#[cfg(feature = "zstd")]
+use crate::encoding::EncodeError;
+use crate::encoding::{Encode, Extract, ExtractError, Json};
+#[cfg(feature = "zstd")]
+use crate::zstd::{self, CompressionError, DEFAULT_ZSTD_LEVEL, ZstdDictionary};
+use miniz_oxide::inflate::TINFLStatus;
+
+/// Values smaller than this are stored uncompressed by default.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// DEFLATE values that inflate to more than this many bytes are refused by default.
+pub const DEFAULT_DECOMPRESSED_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The prefix byte recording how the rest of the value is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Codec {
+    /// The value is stored as-is.
+    Uncompressed = 0,
+    /// The value is compressed with raw DEFLATE.
+    Deflate = 1,
+    /// The value is a zstd frame, compressed on the host. Reading it needs the `zstd`
+    /// feature.
+    Zstd = 2,
+    /// The value is a zstd frame compressed with a named dictionary. The frame is preceded
+    /// by the dictionary name's length as one byte, then the name.
+    ZstdDictionary = 3,
+}
+
+impl TryFrom<u8> for Codec {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Codec::Uncompressed),
+            1 => Ok(Codec::Deflate),
+            2 => Ok(Codec::Zstd),
+            3 => Ok(Codec::ZstdDictionary),
+            unknown => Err(unknown),
+        }
+    }
+}
+
+/// Transparently compress a value when it is larger than `THRESHOLD` bytes.
+///
+/// The encoded value starts with a [Codec] byte, so values that were written
+/// uncompressed (because they were small) and values that were compressed can be
+/// read back the same way. Only values written with `Compressed` or
+/// `ZstdCompressed` can be read with `Compressed`.
+///
+/// DEFLATE runs in your Function, so `Compressed` works on every host. Values written with
+/// `ZstdCompressed` are only read back with the `zstd` feature, which decompresses them on
+/// the host.
+///
+/// This is useful for large JSON blobs and embeddings stored in the cache, which
+/// often compress well.
+///
+/// Examples:
+/// ________
+/// ```rust,no_run
+/// use momento_functions_bytes::encoding::{Compressed, Encode, Extract, Json};
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Document {
+///     text: String,
+/// }
+///
+/// let document = Document { text: "hello ".repeat(1000) };
+/// let data = match Compressed::new(Json(document)).try_serialize() {
+///     Ok(data) => data,
+///     Err(e) => {
+///         eprintln!("failed to encode: {e}");
+///         return;
+///     }
+/// };
+/// match Compressed::<Json<Document>>::extract(data) {
+///     Ok(Compressed(Json(document))) => println!("{} bytes of text", document.text.len()),
+///     Err(e) => eprintln!("failed to decode: {e}"),
+/// }
+/// ```
+/// ________
+/// Compress everything larger than 64 bytes:
+/// ```rust,no_run
+/// use momento_functions_bytes::encoding::Compressed;
+///
+/// let value: Compressed<Vec<u8>, 64> = Compressed(vec![0; 128]);
+/// ```
+/// ________
+/// DEFLATE values are inflated in your Function, so reading refuses any that would inflate
+/// to more than `LIMIT` bytes. Allow up to 1 MiB:
+/// ```rust,no_run
+/// use momento_functions_bytes::encoding::{Compressed, DEFAULT_COMPRESSION_THRESHOLD};
+///
+/// type Small = Compressed<Vec<u8>, DEFAULT_COMPRESSION_THRESHOLD, { 1024 * 1024 }>;
+/// ```
+#[derive(Debug)]
+pub struct Compressed<
+    T,
+    const THRESHOLD: usize = DEFAULT_COMPRESSION_THRESHOLD,
+    const LIMIT: usize = DEFAULT_DECOMPRESSED_LIMIT,
+>(pub T);
+
+impl<T> Compressed<T> {
+    /// Compress a value with the default threshold of [DEFAULT_COMPRESSION_THRESHOLD] bytes.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+/// Compressed JSON encoding and decoding. See [Compressed].
+pub type CompressedJson<T> = Compressed<Json<T>>;
+
+/// An error occurred while reading a [Compressed] value.
+#[derive(Debug, thiserror::Error)]
+pub enum CompressedExtractError<E: ExtractError> {
+    /// The value was empty, so it has no codec byte.
+    #[error("Compressed value is empty")]
+    Empty,
+    /// The codec byte is not one this version understands.
+    #[error("Unknown compression codec: {codec}")]
+    UnknownCodec {
+        /// The codec byte that was read.
+        codec: u8,
+    },
+    /// The value inflates to more than the limit.
+    #[error("Decompressed value exceeds the limit of {limit} bytes")]
+    TooLarge {
+        /// The limit that was exceeded.
+        limit: usize,
+    },
+    /// The value could not be decompressed.
+    #[error("Failed to decompress value: {message}")]
+    DecompressFailed {
+        /// A description of the decompression failure.
+        message: String,
+    },
+    /// The decompressed value could not be extracted.
+    #[error("Failed to extract decompressed value.")]
+    ExtractFailed {
+        /// The underlying extraction error.
+        cause: E,
+    },
+}
+
+impl<T: Encode, const THRESHOLD: usize, const LIMIT: usize> Encode
+    for Compressed<T, THRESHOLD, LIMIT>
+{
+    type Error = T::Error;
+
+    fn try_serialize(self) -> Result<Data, Self::Error> {
+        let bytes = self.0.try_serialize()?.into_bytes();
+        let mut encoded = Vec::with_capacity(bytes.len() + 1);
+        if bytes.len() < THRESHOLD {
+            encoded.push(Codec::Uncompressed as u8);
+            encoded.extend(bytes);
+        } else {
+            encoded.push(Codec::Deflate as u8);
+            encoded.extend(miniz_oxide::deflate::compress_to_vec(&bytes, 6));
+        }
+        Ok(encoded.into())
+    }
+}
+
+impl<T: Extract, const THRESHOLD: usize, const LIMIT: usize> Extract
+    for Compressed<T, THRESHOLD, LIMIT>
+{
+    type Error = CompressedExtractError<T::Error>;
+
+    fn extract(payload: Data) -> Result<Self, Self::Error> {
+        T::extract(decode(payload, LIMIT)?)
+            .map(Compressed)
+            .map_err(|cause| CompressedExtractError::ExtractFailed { cause })
+    }
+}
+
+/// Decode a value written by [Compressed] or `ZstdCompressed`, inflating DEFLATE values to at
+/// most `limit` bytes.
+fn decode<E: ExtractError>(payload: Data, limit: usize) -> Result<Data, CompressedExtractError<E>> {
+    let bytes = payload.into_bytes();
+    let Some((&codec, body)) = bytes.split_first() else {
+        return Err(CompressedExtractError::Empty);
+    };
+    let decompress_failed = |message: String| CompressedExtractError::DecompressFailed { message };
+    match Codec::try_from(codec).map_err(|codec| CompressedExtractError::UnknownCodec { codec })? {
+        Codec::Uncompressed => Ok(body.to_vec().into()),
+        Codec::Deflate => miniz_oxide::inflate::decompress_to_vec_with_limit(body, limit)
+            .map(Into::into)
+            .map_err(|e| match e.status {
+                TINFLStatus::HasMoreOutput => CompressedExtractError::TooLarge { limit },
+                _ => decompress_failed(e.to_string()),
+            }),
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => zstd::decompress(body, None).map_err(|e| decompress_failed(e.to_string())),
+        #[cfg(feature = "zstd")]
+        Codec::ZstdDictionary => {
+            let (dictionary, frame) = split_dictionary_name(body)
+                .ok_or_else(|| decompress_failed("missing zstd dictionary name".to_string()))?;
+            zstd::decompress(frame, Some(&ZstdDictionary::named(dictionary)))
+                .map_err(|e| decompress_failed(e.to_string()))
+        }
+        #[cfg(not(feature = "zstd"))]
+        Codec::Zstd | Codec::ZstdDictionary => Err(decompress_failed(
+            "the value is zstd-compressed; enable the zstd feature of momento-functions-bytes to read it"
+                .to_string(),
+        )),
+    }
+}
+
+#[cfg(feature = "zstd")]
+/// Split a [Codec::ZstdDictionary] body into the dictionary name and the zstd frame.
+fn split_dictionary_name(body: &[u8]) -> Option<(&str, &[u8])> {
+    let (&length, rest) = body.split_first()?;
+    let length = usize::from(length);
+    if rest.len() < length {
+        return None;
+    }
+    let (name, frame) = rest.split_at(length);
+    Some((std::str::from_utf8(name).ok()?, frame))
+}
+
+#[cfg(feature = "zstd")]
+/// Like [Compressed], but compressed with zstd on the host, which is faster and compresses
+/// better than DEFLATE without adding an encoder to your wasm. The host must provide
+/// `momento:bytes/compression`.
+///
+/// With a dictionary, the dictionary's name is stored with the value, so reading it back
+/// with [Compressed] or [ZstdCompressed] in any instance that registered the same dictionary
+/// works without naming it again. Dictionary names are limited to 255 bytes.
+///
+/// Examples:
+/// ________
+/// ```rust,no_run
+/// use momento_functions_bytes::encoding::{Compressed, Encode, Json, ZstdCompressed};
+/// use momento_functions_bytes::zstd::ZstdDictionary;
+///
+/// # fn example(trained: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+/// let dictionary = ZstdDictionary::register("orders-v1", trained)?;
+/// let data = ZstdCompressed::<_>::new(Json(serde_json::json!({"order": 42})))
+///     .with_dictionary(dictionary)
+///     .try_serialize()?;
+/// // Store `data` in the cache, then read it back later with `Compressed<Json<_>>`.
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ZstdCompressed<
+    T,
+    const THRESHOLD: usize = DEFAULT_COMPRESSION_THRESHOLD,
+    const LIMIT: usize = DEFAULT_DECOMPRESSED_LIMIT,
+> {
+    value: T,
+    level: i32,
+    dictionary: Option<ZstdDictionary>,
+}
+
+#[cfg(feature = "zstd")]
+impl<T, const THRESHOLD: usize, const LIMIT: usize> ZstdCompressed<T, THRESHOLD, LIMIT> {
+    /// Compress a value at [DEFAULT_ZSTD_LEVEL], without a dictionary.
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            level: DEFAULT_ZSTD_LEVEL,
+            dictionary: None,
+        }
+    }
+
+    /// Compress at this zstd level, from 1 to 22.
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Compress with this dictionary.
+    pub fn with_dictionary(mut self, dictionary: ZstdDictionary) -> Self {
+        self.dictionary = Some(dictionary);
+        self
+    }
+
+    /// The value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+#[cfg(feature = "zstd")]
+/// An error occurred while writing a [ZstdCompressed] value.
+#[derive(Debug, thiserror::Error)]
+pub enum ZstdEncodeError<E: EncodeError> {
+    /// The value could not be encoded.
+    #[error("Failed to encode value.")]
+    EncodeFailed {
+        /// The underlying encoding error.
+        cause: E,
+    },
+    /// The host could not compress the value.
+    #[error(transparent)]
+    Compression(#[from] CompressionError),
+    /// The dictionary name does not fit in the codec header.
+    #[error("zstd dictionary name is longer than 255 bytes: {name}")]
+    DictionaryNameTooLong {
+        /// The dictionary's name.
+        name: String,
+    },
+}
+
+#[cfg(feature = "zstd")]
+impl<E: EncodeError> EncodeError for ZstdEncodeError<E> {}
+
+#[cfg(feature = "zstd")]
+impl<T: Encode, const THRESHOLD: usize, const LIMIT: usize> Encode
+    for ZstdCompressed<T, THRESHOLD, LIMIT>
+{
+    type Error = ZstdEncodeError<T::Error>;
+
+    fn try_serialize(self) -> Result<Data, Self::Error> {
+        let data = self
+            .value
+            .try_serialize()
+            .map_err(|cause| ZstdEncodeError::EncodeFailed { cause })?;
+        let mut encoded = Vec::new();
+        if data.len() < THRESHOLD {
+            encoded.push(Codec::Uncompressed as u8);
+            encoded.extend(data.into_bytes());
+            return Ok(encoded.into());
+        }
+        match &self.dictionary {
+            None => encoded.push(Codec::Zstd as u8),
+            Some(dictionary) => {
+                let name = dictionary.name();
+                let length = u8::try_from(name.len()).map_err(|_| {
+                    ZstdEncodeError::DictionaryNameTooLong {
+                        name: name.to_string(),
+                    }
+                })?;
+                encoded.push(Codec::ZstdDictionary as u8);
+                encoded.push(length);
+                encoded.extend(name.as_bytes());
+            }
+        }
+        encoded.extend(zstd::compress(data, self.level, self.dictionary.as_ref())?.into_bytes());
+        Ok(encoded.into())
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<T: Extract, const THRESHOLD: usize, const LIMIT: usize> Extract
+    for ZstdCompressed<T, THRESHOLD, LIMIT>
+{
+    type Error = CompressedExtractError<T::Error>;
+
+    fn extract(payload: Data) -> Result<Self, Self::Error> {
+        T::extract(decode(payload, LIMIT)?)
+            .map(Self::new)
+            .map_err(|cause| CompressedExtractError::ExtractFailed { cause })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_are_not_compressed() {
+        let data = Compressed::<Vec<u8>>(b"tiny".to_vec())
+            .try_serialize()
+            .unwrap()
+            .into_bytes();
+        assert_eq!(b"\0tiny".to_vec(), data);
+    }
+
+    #[test]
+    fn large_values_round_trip() {
+        let value = "embedding ".repeat(500);
+        let data = Compressed::<String>(value.clone()).try_serialize().unwrap();
+        assert!(data.len() < value.len());
+        let Compressed(extracted) = Compressed::<String>::extract(data).unwrap();
+        assert_eq!(value, extracted);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn dictionary_names_precede_the_frame() {
+        assert_eq!(
+            Some(("orders-v1", b"frame".as_slice())),
+            split_dictionary_name(b"\x09orders-v1frame")
+        );
+        assert_eq!(Some(("", b"".as_slice())), split_dictionary_name(b"\0"));
+        assert_eq!(None, split_dictionary_name(b"\x09short"));
+        assert_eq!(None, split_dictionary_name(b""));
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn zstd_values_need_the_feature() {
+        let error =
+            Compressed::<Vec<u8>>::extract(vec![Codec::Zstd as u8, 1, 2].into()).unwrap_err();
+        assert!(matches!(
+            error,
+            CompressedExtractError::DecompressFailed { .. }
+        ));
+    }
+
+    #[test]
+    fn values_inflating_past_the_limit_are_refused() {
+        let data = Compressed::<Vec<u8>>(vec![0; 4096])
+            .try_serialize()
+            .unwrap()
+            .into_bytes();
+        let error = Compressed::<Vec<u8>, 1024, 4095>::extract(data.clone().into()).unwrap_err();
+        assert!(matches!(
+            error,
+            CompressedExtractError::TooLarge { limit: 4095 }
+        ));
+        let Compressed(extracted) =
+            Compressed::<Vec<u8>, 1024, 4096>::extract(data.into()).unwrap();
+        assert_eq!(4096, extracted.len());
+    }
+
+    #[test]
+    fn unknown_codecs_are_rejected() {
+        let error = Compressed::<Vec<u8>>::extract(vec![9, 1, 2].into()).unwrap_err();
+        assert!(matches!(
+            error,
+            CompressedExtractError::UnknownCodec { codec: 9 }
+        ));
+    }
+}
@@ -28,6 +28,71 @@ impl Data {
     pub fn into_bytes(self) -> Vec<u8> {
         self.data.into_bytes()
     }
+
+    /// The number of bytes ready to be read, without copying anything into your
+    /// function's memory.
+    ///
+    /// For inline data this is the exact length. For data buffered on the host, this is
+    /// how many bytes the host has ready to read right now, which can be less than the
+    /// full length while a body is still arriving. Treat it as a lower bound: a length
+    /// over your limit means the data is too large, but a smaller one does not mean it
+    /// fits. [Data::into_bytes_limited] checks both.
+    ///
+    /// Use this to reject oversized payloads before reading them:
+    /// ```rust,no_run
+    /// # use momento_functions_bytes::Data;
+    /// fn handle(payload: Data) -> Result<Vec<u8>, String> {
+    ///     if 1024 * 1024 < payload.len() {
+    ///         return Err(format!("body of {} bytes is too large", payload.len()));
+    ///     }
+    ///     Ok(payload.into_bytes())
+    /// }
+    /// ```
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// True when there are no bytes ready to be read.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Turn the Data into a plain `Vec<u8>`, refusing to read more than `limit` bytes
+    /// into your function's memory.
+    ///
+    /// The length that is ready is checked before anything is copied, and reading stops as
+    /// soon as the limit is exceeded, so more bytes arriving later are caught too. To process
+    /// large bodies without holding them in memory, read from `Data` in chunks via
+    /// `std::io::Read` instead.
+    pub fn into_bytes_limited(self, limit: usize) -> Result<Vec<u8>, DataTooLarge> {
+        if limit < self.len() {
+            return Err(DataTooLarge {
+                limit,
+                length: self.len(),
+            });
+        }
+        let mut buffer = Vec::with_capacity(self.len());
+        let mut reader = std::io::Read::take(self, (limit as u64).saturating_add(1));
+        // Reads from Data do not fail today; treat a failure like the end of the stream.
+        let _ = std::io::Read::read_to_end(&mut reader, &mut buffer);
+        if limit < buffer.len() {
+            return Err(DataTooLarge {
+                limit,
+                length: buffer.len(),
+            });
+        }
+        Ok(buffer)
+    }
+}
+
+/// The Data was larger than the limit you asked for.
+#[derive(Debug, thiserror::Error)]
+#[error("Data of at least {length} bytes exceeds the limit of {limit} bytes")]
+pub struct DataTooLarge {
+    /// The limit that was exceeded.
+    pub limit: usize,
+    /// How many bytes were seen before giving up. The full length may be larger.
+    pub length: usize,
 }
 
 impl std::io::Read for Data {
@@ -117,6 +182,70 @@ impl From<&str> for Data {
     }
 }
 
+/// Builds an inline [Data] by writing into it.
+///
+/// Serializers that implement `std::io::Write` can write straight into the buffer that
+/// becomes the body, instead of producing their own buffer that is then handed over.
+/// Reserve the expected size up front to avoid regrowing the buffer for large bodies.
+///
+/// ```rust,no_run
+/// # use momento_functions_bytes::{Data, DataWriter};
+/// let mut writer = DataWriter::with_capacity(64 * 1024);
+/// if let Err(e) = serde_json::to_writer(&mut writer, &vec!["a", "b"]) {
+///     eprintln!("failed to serialize: {e}");
+/// }
+/// let body: Data = writer.finish();
+/// ```
+#[derive(Debug, Default)]
+pub struct DataWriter {
+    buffer: Vec<u8>,
+}
+
+impl DataWriter {
+    /// Create an empty writer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty writer with room for `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// True when nothing has been written yet.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Finish writing, turning the written bytes into [Data] without copying them.
+    pub fn finish(self) -> Data {
+        self.buffer.into()
+    }
+}
+
+impl std::io::Write for DataWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.buffer.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 enum Location {
     Inline {
         buffer: VecDeque<u8>,
@@ -126,6 +255,13 @@ enum Location {
     },
 }
 impl Location {
+    fn len(&self) -> usize {
+        match self {
+            Location::Inline { buffer } => buffer.len(),
+            Location::OnHost { resource } => resource.remaining() as usize,
+        }
+    }
+
     fn into_bytes(self) -> Vec<u8> {
         match self {
             Location::Inline { buffer } => buffer.into(),
@@ -163,3 +299,32 @@ impl From<Data> for crate::wit::momento::bytes::bytes::Data {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limited_read_within_limit() {
+        let data = Data::from("hello");
+        assert_eq!(5, data.len());
+        assert_eq!(b"hello".to_vec(), data.into_bytes_limited(5).unwrap());
+    }
+
+    #[test]
+    fn limited_read_over_limit() {
+        let error = Data::from("hello").into_bytes_limited(4).unwrap_err();
+        assert_eq!(4, error.limit);
+        assert_eq!(5, error.length);
+    }
+
+    #[test]
+    fn limited_read_without_a_limit() {
+        let data = Data::from("hello");
+        assert_eq!(
+            b"hello".to_vec(),
+            data.into_bytes_limited(usize::MAX).unwrap()
+        );
+    }
+}
@@ -2,7 +2,14 @@
 
 use std::convert::Infallible;
 
-use crate::Data;
+use crate::{Data, DataWriter};
+
+pub use crate::compressed::{
+    Codec, Compressed, CompressedExtractError, CompressedJson, DEFAULT_COMPRESSION_THRESHOLD,
+    DEFAULT_DECOMPRESSED_LIMIT,
+};
+#[cfg(feature = "zstd")]
+pub use crate::compressed::{ZstdCompressed, ZstdEncodeError};
 
 /// Required to be implemented by encode error types.
 pub trait EncodeError: std::error::Error + 'static {}
@@ -108,6 +115,73 @@ impl Extract for Data {
     }
 }
 
+/// The exact bytes of a payload, in your function's memory.
+///
+/// Pair it with another extractor as `(Body, T)` to receive both the raw bytes and the parsed
+/// value, for payloads like webhooks where a signature covers the bytes on the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Body(pub Vec<u8>);
+
+impl Body {
+    /// The raw bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Parse the bytes with another extractor.
+    pub fn extract<T: Extract>(self) -> Result<T, T::Error> {
+        T::extract(self.0.into())
+    }
+}
+
+impl Extract for Body {
+    type Error = Infallible;
+    fn extract(payload: Data) -> Result<Self, Self::Error> {
+        Ok(Body(payload.into_bytes()))
+    }
+}
+
+impl<T: Extract> Extract for (Body, T) {
+    type Error = T::Error;
+    fn extract(payload: Data) -> Result<Self, Self::Error> {
+        let bytes = payload.into_bytes();
+        let value = T::extract(bytes.clone().into())?;
+        Ok((Body(bytes), value))
+    }
+}
+
+/// The payload as it was received, without copying it into your function's memory.
+///
+/// Unlike [Body], this cannot be paired with another extractor, because reading the payload
+/// for one would consume it for the other. Check what you need from [BodyData::len] or by
+/// reading it, and call [BodyData::extract] to parse it once you are satisfied.
+#[derive(Debug)]
+pub struct BodyData(pub Data);
+
+impl BodyData {
+    /// The number of bytes ready to be read. See [Data::len].
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// True when there are no bytes ready to be read.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Parse the payload with another extractor.
+    pub fn extract<T: Extract>(self) -> Result<T, T::Error> {
+        T::extract(self.0)
+    }
+}
+
+impl Extract for BodyData {
+    type Error = Infallible;
+    fn extract(payload: Data) -> Result<Self, Self::Error> {
+        Ok(BodyData(payload))
+    }
+}
+
 /// JSON encoding and decoding
 pub struct Json<T>(pub T);
 impl<T: serde::de::DeserializeOwned> Extract for Json<T> {
@@ -120,6 +194,123 @@ impl<T: serde::de::DeserializeOwned> Extract for Json<T> {
 impl<T: serde::Serialize> Encode for Json<T> {
     type Error = serde_json::Error;
     fn try_serialize(self) -> Result<Data, Self::Error> {
-        serde_json::to_vec(&self.0).map(Into::into)
+        let mut writer = DataWriter::new();
+        serde_json::to_writer(&mut writer, &self.0)?;
+        Ok(writer.finish())
+    }
+}
+
+impl<T> Json<T> {
+    /// Encode the value as indented, human-readable JSON.
+    pub fn pretty(value: T) -> PrettyJson<T> {
+        PrettyJson(value)
+    }
+}
+
+/// JSON encoding with indentation, from [Json::pretty].
+pub struct PrettyJson<T>(pub T);
+
+impl<T: serde::Serialize> Encode for PrettyJson<T> {
+    type Error = serde_json::Error;
+    fn try_serialize(self) -> Result<Data, Self::Error> {
+        let mut writer = DataWriter::new();
+        serde_json::to_writer_pretty(&mut writer, &self.0)?;
+        Ok(writer.finish())
+    }
+}
+
+/// Newline-delimited JSON: one compact JSON value per line.
+///
+/// Encoding writes every value into a single buffer, so large outputs are not built up
+/// from a separate buffer per value. Extracting skips blank lines.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_bytes::encoding::{Encode, JsonLines};
+///
+/// #[derive(serde::Serialize)]
+/// struct Hit {
+///     id: u32,
+///     score: f32,
+/// }
+///
+/// let hits = (0..3).map(|id| Hit { id, score: 1.0 / (id + 1) as f32 });
+/// match JsonLines(hits).try_serialize() {
+///     Ok(body) => println!("encoded {} bytes", body.len()),
+///     Err(e) => eprintln!("failed to encode: {e}"),
+/// }
+/// ```
+pub struct JsonLines<I>(pub I);
+
+impl<I> Encode for JsonLines<I>
+where
+    I: IntoIterator,
+    I::Item: serde::Serialize,
+{
+    type Error = serde_json::Error;
+    fn try_serialize(self) -> Result<Data, Self::Error> {
+        let mut writer = DataWriter::new();
+        for value in self.0 {
+            serde_json::to_writer(&mut writer, &value)?;
+            std::io::Write::write_all(&mut writer, b"\n").map_err(serde_json::Error::io)?;
+        }
+        Ok(writer.finish())
+    }
+}
+
+impl<T: serde::de::DeserializeOwned> Extract for JsonLines<Vec<T>> {
+    type Error = serde_json::Error;
+    fn extract(payload: Data) -> Result<Self, Self::Error> {
+        let bytes = payload.into_bytes();
+        bytes
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.trim_ascii().is_empty())
+            .map(serde_json::from_slice)
+            .collect::<Result<_, _>>()
+            .map(JsonLines)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines_round_trip() {
+        let encoded = JsonLines(vec![1, 2, 3]).try_serialize().unwrap();
+        let bytes = encoded.into_bytes();
+        assert_eq!(b"1\n2\n3\n".to_vec(), bytes);
+
+        let JsonLines(values) = JsonLines::<Vec<u32>>::extract(Data::from(bytes)).unwrap();
+        assert_eq!(vec![1, 2, 3], values);
+    }
+
+    #[test]
+    fn json_lines_skips_blank_lines() {
+        let JsonLines(values) =
+            JsonLines::<Vec<String>>::extract(Data::from("\"a\"\r\n\n  \n\"b\"")).unwrap();
+        assert_eq!(vec!["a".to_string(), "b".to_string()], values);
+    }
+
+    #[test]
+    fn raw_bodies_are_kept_alongside_extracted_values() {
+        let (Body(bytes), Json(value)) =
+            <(Body, Json<serde_json::Value>)>::extract(Data::from(r#"{ "a": 1 }"#)).unwrap();
+        assert_eq!(br#"{ "a": 1 }"#.to_vec(), bytes);
+        assert_eq!(serde_json::json!({ "a": 1 }), value);
+
+        let body = <BodyData as Extract>::extract(Data::from("[1, 2]")).unwrap();
+        assert_eq!(6, body.len());
+        let Json(values) = body.extract::<Json<Vec<u32>>>().unwrap();
+        assert_eq!(vec![1, 2], values);
+    }
+
+    #[test]
+    fn pretty_json_is_indented() {
+        let encoded = Json::pretty(serde_json::json!({ "a": 1 }))
+            .try_serialize()
+            .unwrap();
+        assert_eq!(b"{\n  \"a\": 1\n}".to_vec(), encoded.into_bytes());
     }
 }
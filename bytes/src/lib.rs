@@ -5,10 +5,16 @@
 //! to another request or response without copying it into your function's memory.
 //! This can improve performance for large buffers, when you're passing data through.
 
+pub mod abi;
+mod compressed;
 mod data;
 /// Internal module for WIT bindings.
 #[doc(hidden)]
 pub mod wit;
 
-pub use data::Data;
+pub use data::{Data, DataTooLarge, DataWriter};
 pub mod encoding;
+#[cfg(feature = "scratch")]
+pub mod scratch;
+#[cfg(feature = "zstd")]
+pub mod zstd;
@@ -0,0 +1,141 @@
+//! Scratch memory on the host, for the rest of one invocation.
+//!
+//! Some Functions assemble more data than fits in their own memory before sending it on,
+//! like the parts of a multipart upload that arrive in separate chunks. A [Scratch] buffer
+//! keeps those bytes on the host instead. Appending [Data] that is already on the host, like a
+//! request body, moves it without copying it through your Function.
+//!
+//! Scratch memory belongs to the invocation that created it. It is freed when the [Scratch] is
+//! dropped, and at the end of the invocation at the latest, so a buffer that is forgotten does
+//! not leak into later requests. Using a buffer after its invocation ended returns
+//! [ScratchError::Expired]; don't keep one in a static.
+//!
+//! Scratch buffers need the `scratch` feature and a host that provides `momento:bytes/scratch`.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_bytes::Data;
+//! use momento_functions_bytes::scratch::{Scratch, ScratchError};
+//!
+//! /// Join the parts of an upload, in order, into one body to send on.
+//! fn assemble(parts: Vec<Data>) -> Result<Data, ScratchError> {
+//!     let scratch = Scratch::new();
+//!     for part in parts {
+//!         scratch.append(part)?;
+//!     }
+//!     scratch.to_data()
+//! }
+//! ```
+
+use std::ops::Range;
+
+use crate::Data;
+use crate::wit::momento::bytes::scratch;
+
+/// An error returned by scratch memory.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ScratchError {
+    /// The invocation's scratch memory is used up.
+    #[error("scratch memory limit of {limit_bytes} bytes reached")]
+    OutOfSpace {
+        /// How many bytes of scratch memory the invocation may use.
+        limit_bytes: u64,
+    },
+    /// The requested range is past the end of the buffer.
+    #[error("out of range: {0}")]
+    OutOfRange(String),
+    /// The buffer was created by an invocation that has ended.
+    #[error("scratch buffer was used after its invocation ended")]
+    Expired,
+    /// The request failed for some other reason.
+    #[error("scratch memory failed: {0}")]
+    Other(String),
+}
+
+impl From<scratch::ScratchError> for ScratchError {
+    fn from(e: scratch::ScratchError) -> Self {
+        match e {
+            scratch::ScratchError::OutOfSpace(limit_bytes) => Self::OutOfSpace { limit_bytes },
+            scratch::ScratchError::OutOfRange(s) => Self::OutOfRange(s),
+            scratch::ScratchError::Expired => Self::Expired,
+            scratch::ScratchError::Other(s) => Self::Other(s),
+        }
+    }
+}
+
+/// A growable buffer in host memory, freed when it is dropped or the invocation ends.
+///
+/// `Scratch` also implements `std::io::Write`, so libraries that write to a stream can write
+/// into it.
+#[derive(Debug)]
+pub struct Scratch {
+    buffer: scratch::ScratchBuffer,
+}
+
+impl Default for Scratch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scratch {
+    /// An empty buffer.
+    pub fn new() -> Self {
+        Self {
+            buffer: scratch::ScratchBuffer::new(),
+        }
+    }
+
+    /// The number of bytes in the buffer.
+    pub fn len(&self) -> Result<u64, ScratchError> {
+        Ok(self.buffer.len()?)
+    }
+
+    /// True when the buffer holds no bytes.
+    pub fn is_empty(&self) -> Result<bool, ScratchError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Add `value` to the end of the buffer.
+    pub fn append(&self, value: impl Into<Data>) -> Result<(), ScratchError> {
+        Ok(self.buffer.append(value.into().into())?)
+    }
+
+    /// The bytes in `range`, as host data. The buffer is unchanged.
+    pub fn slice(&self, range: Range<u64>) -> Result<Data, ScratchError> {
+        let length = range.end.saturating_sub(range.start);
+        Ok(self.buffer.slice(range.start, length)?.into())
+    }
+
+    /// The whole buffer, as host data to pass on as a body or read back. The buffer is
+    /// unchanged.
+    pub fn to_data(&self) -> Result<Data, ScratchError> {
+        self.slice(0..self.len()?)
+    }
+
+    /// Shorten the buffer to `length` bytes. Longer lengths leave it unchanged.
+    pub fn truncate(&self, length: u64) -> Result<(), ScratchError> {
+        Ok(self.buffer.truncate(length)?)
+    }
+}
+
+impl std::io::Write for Scratch {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.append(buf).map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How many bytes of scratch memory this invocation may use, across all of its buffers.
+pub fn limit_bytes() -> u64 {
+    scratch::limit_bytes()
+}
+
+/// How many bytes of scratch memory this invocation is using.
+pub fn used_bytes() -> u64 {
+    scratch::used_bytes()
+}
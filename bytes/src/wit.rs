@@ -1,5 +1,23 @@
-wit_bindgen::generate!({
-    world: "momento:bytes/imports",
-    path: [ "wit" ],
-    generate_unused_types: true,
-});
+// Each feature that needs more from the host generates from the world that describes it, so
+// the bindings only ever name what the enabled features call.
+macro_rules! generate {
+    ($world:literal) => {
+        wit_bindgen::generate!({
+            world: $world,
+            path: [ "wit" ],
+            generate_unused_types: true,
+        });
+    };
+}
+
+#[cfg(not(any(feature = "zstd", feature = "scratch")))]
+generate!("momento:bytes/imports");
+
+#[cfg(all(feature = "zstd", not(feature = "scratch")))]
+generate!("momento:bytes/with-zstd");
+
+#[cfg(all(feature = "scratch", not(feature = "zstd")))]
+generate!("momento:bytes/with-scratch");
+
+#[cfg(all(feature = "zstd", feature = "scratch"))]
+generate!("momento:bytes/with-zstd-scratch");
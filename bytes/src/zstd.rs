@@ -0,0 +1,124 @@
+//! zstd compression on the host.
+//!
+//! A zstd encoder compiled into a Function adds hundreds of kilobytes to its wasm. These
+//! functions hand the work to the host instead, and take and return [Data], so a body that is
+//! buffered on the host is compressed without being copied into your Function's memory.
+//!
+//! Small values that share structure, like JSON documents of the same shape, compress much
+//! better with a shared dictionary. Register one with [ZstdDictionary::register] when your
+//! Function starts, and pass it to every call that compresses or decompresses those values.
+//!
+//! **Examples:**
+//! Compress a response body for a caller that accepts zstd:
+//! ```rust,no_run
+//! use momento_functions_bytes::Data;
+//! use momento_functions_bytes::zstd::{self, DEFAULT_ZSTD_LEVEL};
+//!
+//! fn encode_body(body: Data) -> Result<(Data, &'static str), zstd::CompressionError> {
+//!     Ok((zstd::compress(body, DEFAULT_ZSTD_LEVEL, None)?, "zstd"))
+//! }
+//! ```
+//! ________
+//! With a dictionary trained on your documents:
+//! ```rust,no_run
+//! use momento_functions_bytes::zstd::{self, ZstdDictionary};
+//!
+//! # fn example() -> Result<(), zstd::CompressionError> {
+//! # let trained: Vec<u8> = Vec::new();
+//! let dictionary = ZstdDictionary::register("products-v1", trained)?;
+//! let compressed = zstd::compress(r#"{"id":42,"name":"widget"}"#, 3, Some(&dictionary))?;
+//! let original = zstd::decompress(compressed, Some(&dictionary))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Data;
+use crate::wit::momento::bytes::compression;
+
+/// The zstd level used when you don't choose one. It is zstd's own default: fast, with a
+/// good ratio.
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// An error returned by host compression.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum CompressionError {
+    /// No dictionary with this name has been registered in this instance.
+    #[error("unknown zstd dictionary: {0}")]
+    UnknownDictionary(String),
+    /// The compression level is outside of what zstd supports.
+    #[error("invalid zstd level: {0}")]
+    InvalidLevel(i32),
+    /// The input is not a valid zstd frame, or needs a different dictionary.
+    #[error("corrupt zstd data: {0}")]
+    Corrupt(String),
+    /// The decompressed value would be larger than the host allows.
+    #[error("decompressed value too large: {0}")]
+    TooLarge(String),
+    /// The request failed for some other reason.
+    #[error("compression failed: {0}")]
+    Other(String),
+}
+
+impl From<compression::CompressionError> for CompressionError {
+    fn from(e: compression::CompressionError) -> Self {
+        match e {
+            compression::CompressionError::UnknownDictionary(s) => Self::UnknownDictionary(s),
+            compression::CompressionError::InvalidLevel(level) => Self::InvalidLevel(level),
+            compression::CompressionError::Corrupt(s) => Self::Corrupt(s),
+            compression::CompressionError::TooLarge(s) => Self::TooLarge(s),
+            compression::CompressionError::Other(s) => Self::Other(s),
+        }
+    }
+}
+
+/// A zstd dictionary registered with the host for this Function instance.
+///
+/// The dictionary is referred to by name, so values compressed with it can only be
+/// decompressed by an instance that registered the same dictionary under the same name. Put
+/// a version in the name, and register a new name rather than changing a dictionary that
+/// stored values were compressed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZstdDictionary {
+    name: String,
+}
+
+impl ZstdDictionary {
+    /// Register `dictionary` under `name`. Registering a name again replaces its dictionary.
+    pub fn register(
+        name: impl Into<String>,
+        dictionary: impl Into<Data>,
+    ) -> Result<Self, CompressionError> {
+        let name = name.into();
+        compression::register_zstd_dictionary(&name, dictionary.into().into())?;
+        Ok(Self { name })
+    }
+
+    /// A dictionary that was already registered under `name` in this instance.
+    pub fn named(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// The name the dictionary is registered under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Compress `value` at `level`, which zstd accepts from 1 to 22, with `dictionary` if given.
+pub fn compress(
+    value: impl Into<Data>,
+    level: i32,
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<Data, CompressionError> {
+    let dictionary = dictionary.map(ZstdDictionary::name);
+    Ok(compression::zstd_compress(value.into().into(), level, dictionary)?.into())
+}
+
+/// Decompress a zstd frame, with the `dictionary` it was compressed with if any.
+pub fn decompress(
+    value: impl Into<Data>,
+    dictionary: Option<&ZstdDictionary>,
+) -> Result<Data, CompressionError> {
+    let dictionary = dictionary.map(ZstdDictionary::name);
+    Ok(compression::zstd_decompress(value.into().into(), dictionary)?.into())
+}
@@ -46,6 +46,23 @@ use crate::{
 ///     Err(e) => eprintln!("cache get failed: {e}"),
 /// }
 /// ```
+/// ________
+/// Compressed Json, written with `Compressed::new(Json(..))`:
+/// ```rust,no_run
+/// use momento_functions_cache::get;
+/// use momento_functions_bytes::encoding::{Compressed, CompressedJson, Json};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Embedding {
+///   vector: Vec<f32>
+/// }
+///
+/// match get::<CompressedJson<Embedding>>("my_key") {
+///     Ok(Some(Compressed(Json(value)))) => { /* use value */ }
+///     Ok(None) => { /* key not found */ }
+///     Err(e) => eprintln!("cache get failed: {e}"),
+/// }
+/// ```
 pub fn get<T: Extract>(key: impl Into<Data>) -> Result<Option<T>, CacheGetError<T::Error>> {
     match cache_scalar::get(key.into().into())? {
         Some(v) => T::extract(v.into())
@@ -93,6 +110,27 @@ pub fn get<T: Extract>(key: impl Into<Data>) -> Result<Option<T>, CacheGetError<
 ///     Err(e) => eprintln!("cache set failed: {e}"),
 /// }
 /// ```
+/// ________
+/// Compressed Json (read it back with `get::<CompressedJson<MyStruct>>`):
+/// ```rust,no_run
+/// use momento_functions_cache::set;
+/// # use std::time::Duration;
+/// use momento_functions_bytes::encoding::{Compressed, Json};
+///
+/// #[derive(serde::Serialize)]
+/// struct Embedding {
+///    vector: Vec<f32>
+/// }
+///
+/// match set(
+///     "my_key",
+///     Compressed::new(Json(Embedding { vector: vec![0.0; 1536] })),
+///     Duration::from_secs(60),
+/// ) {
+///     Ok(()) => {}
+///     Err(e) => eprintln!("cache set failed: {e}"),
+/// }
+/// ```
 pub fn set<E: Encode>(
     key: impl Into<Data>,
     value: E,
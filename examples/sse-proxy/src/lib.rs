@@ -13,6 +13,7 @@
 //! ```
 
 use momento_functions_bytes::Data;
+use momento_functions_guest_web::proxy::error_for_status;
 use momento_functions_guest_web::{
     SseEvent, WebEnvironment, WebError, WebResult, invoke, sse_streaming_response_with_headers,
 };
@@ -45,12 +46,7 @@ fn proxy(request_body: Data) -> WebResult<()> {
 
     // Surface upstream failures before we commit to an SSE response, so the caller
     // gets a normal HTTP error rather than a half-open event stream.
-    if !(200..300).contains(&upstream.status) {
-        return Err(WebError::message(format!(
-            "upstream returned {} before streaming could begin",
-            upstream.status
-        )));
-    }
+    let upstream = error_for_status(upstream)?;
 
     let mut response = sse_streaming_response_with_headers(upstream.status, upstream.headers)
         .map_err(WebError::message)?;
@@ -7,9 +7,11 @@
 
 use itertools::Itertools;
 use momento_functions_bytes::encoding::{Extract, Json};
-use momento_functions_guest_web::{WebEnvironment, WebError, WebResponse, WebResult, invoke};
+use momento_functions_guest_web::proxy::error_for_status;
+use momento_functions_guest_web::{WebEnvironment, WebResponse, WebResult, invoke};
 use momento_functions_host_log::{LogDestination, configure_logs};
 use momento_functions_http::{Request as HttpRequest, invoke as http_invoke};
+use momento_functions_text::truncate_tokens;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tiktoken_rs::{CoreBPE, cl100k_base_singleton};
@@ -146,14 +148,7 @@ fn index_in_turbopuffer(
             ),
     )?;
     log::debug!("turbopuffer response status: {}", response.status);
-    if response.status != 200 {
-        let body = response.body.into_bytes();
-        let message = format!(
-            "Failed to index documents: {}",
-            String::from_utf8(body).unwrap_or_default()
-        );
-        return Err(WebError::message(message));
-    }
+    error_for_status(response)?;
     Ok(())
 }
 
@@ -171,21 +166,15 @@ fn get_embeddings(
             prepared.push("no_content".to_string());
             continue;
         }
-        let mut tokens = tokenizer.encode_with_special_tokens(document);
-        if tokens.len() > MAX_TOKENS {
+        let truncated = truncate_tokens(tokenizer, document, MAX_TOKENS);
+        if truncated.len() < document.len() {
             log::debug!(
-                "token length was {}, truncating to {MAX_TOKENS}",
-                tokens.len(),
+                "truncated document from {} to {} bytes to fit {MAX_TOKENS} tokens",
+                document.len(),
+                truncated.len()
             );
-            tokens.truncate(MAX_TOKENS);
-            prepared.push(tokenizer.decode(tokens).map_err(|e| {
-                WebError::message(format!(
-                    "Failed to convert truncated tokens back to string for OpenAI input: {e:?}"
-                ))
-            })?);
-        } else {
-            prepared.push(document.clone());
         }
+        prepared.push(truncated.to_string());
     }
 
     let response = http_invoke(
@@ -201,14 +190,7 @@ fn get_embeddings(
                 .to_string(),
             ),
     )?;
-    if response.status != 200 {
-        let bytes = response.body.into_bytes();
-        log::error!(
-            "OpenAI returned non-200: {}",
-            String::from_utf8_lossy(&bytes)
-        );
-        return Err(WebError::message("OpenAI failed to return embeddings"));
-    }
+    let response = error_for_status(response)?;
     let Json(EmbeddingResponse { mut data }) = Json::<EmbeddingResponse>::extract(response.body)?;
     data.sort_by_key(|d| d.index);
     Ok(data)
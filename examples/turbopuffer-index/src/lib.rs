@@ -8,6 +8,7 @@
 
 use itertools::Itertools;
 use momento_functions_bytes::encoding::Json;
+use momento_functions_guest_web::proxy::error_for_status;
 use momento_functions_guest_web::{WebEnvironment, WebResponse, WebResult, invoke};
 use momento_functions_host_log::{LogDestination, configure_logs};
 use momento_functions_http::{Request as HttpRequest, invoke as http_invoke};
@@ -77,16 +78,7 @@ fn index_document(Json(documents): Json<Vec<Document>>) -> WebResult<WebResponse
                     .to_string(),
                 ),
         )?;
-        if response.status != 200 {
-            let body = response.body.into_bytes();
-            let message = format!(
-                "Failed to index documents: {}",
-                String::from_utf8(body).unwrap_or_default()
-            );
-            return Ok(WebResponse::new()
-                .with_status(response.status)
-                .with_body(json!({ "message": message }).to_string())?);
-        }
+        error_for_status(response)?;
     }
 
     Ok(WebResponse::new()
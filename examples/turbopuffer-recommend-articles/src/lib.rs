@@ -11,11 +11,13 @@ use std::{collections::HashMap, time::Duration};
 
 use momento_functions_bytes::encoding::{Extract, Json};
 use momento_functions_cache as cache;
+use momento_functions_guest_web::proxy::error_for_status;
 use momento_functions_guest_web::{WebEnvironment, WebError, WebResponse, WebResult, invoke};
 use momento_functions_host_log::{LogDestination, configure_logs};
 use momento_functions_http::{Request as HttpRequest, invoke as http_invoke};
+use momento_functions_turbopuffer::Filter;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
+use serde_json::json;
 
 #[derive(Deserialize, Debug)]
 struct Request {
@@ -180,27 +182,12 @@ fn get_article_embeddings_from_turbopuffer(
                 json!({
                     "top_k": article_ids.len(),
                     "include_attributes": vec!["id", "vector"],
-                    "filters": Filter::Comparison(ComparisonFilter(
-                        "id".to_string(),
-                        ComparisonOp::In,
-                        Value::Array(
-                            article_ids
-                                .iter()
-                                .map(|id| Value::String(id.clone()))
-                                .collect(),
-                        ),
-                    )),
+                    "filters": Filter::field("id").is_in(article_ids),
                 })
                 .to_string(),
             ),
     )?;
-    if response.status != 200 {
-        let bytes = response.body.into_bytes();
-        return Err(WebError::message(format!(
-            "Failed to get indexed embeddings: {}",
-            String::from_utf8(bytes).unwrap_or_default()
-        )));
-    }
+    let response = error_for_status(response)?;
     let Json(QueryResponse { rows }) = Json::<QueryResponse>::extract(response.body)?;
 
     let mut embeddings = Vec::with_capacity(rows.len());
@@ -235,22 +222,12 @@ fn get_similar_articles_from_turbopuffer(
                     "rank_by": ["vector", "ANN", mean_vector],
                     "top_k": topk,
                     "include_attributes": vec!["metadata$title", "metadata$link"],
-                    "filters": Filter::Comparison(ComparisonFilter(
-                        "id".to_string(),
-                        ComparisonOp::NotIn,
-                        Value::Array(seen.iter().map(|id| Value::String(id.clone())).collect()),
-                    )),
+                    "filters": Filter::field("id").not_in(seen),
                 })
                 .to_string(),
             ),
     )?;
-    if response.status != 200 {
-        let bytes = response.body.into_bytes();
-        return Err(WebError::message(format!(
-            "Failed to search documents: {}",
-            String::from_utf8(bytes).unwrap_or_default()
-        )));
-    }
+    let response = error_for_status(response)?;
     let Json(QueryResponse { rows }) = Json::<QueryResponse>::extract(response.body)?;
     Ok(rows
         .into_iter()
@@ -291,39 +268,3 @@ fn setup_logging() -> WebResult<()> {
     configure_logs([LogDestination::topic(env.function_name()).into()])?;
     Ok(())
 }
-
-// ---------------------------------------------------------------------------
-// Recursive filter representation, see turbopuffer-search-articles.
-// ---------------------------------------------------------------------------
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum Filter {
-    Logical(LogicalFilter),
-    Not(NotFilter),
-    Comparison(ComparisonFilter),
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LogicalFilter(pub String, pub Vec<Filter>);
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NotFilter(pub String, pub Box<Filter>);
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ComparisonFilter(pub String, pub ComparisonOp, pub Value);
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-pub enum ComparisonOp {
-    Eq,
-    Neq,
-    In,
-    NotIn,
-    Lt,
-    Lte,
-    Gt,
-    Gte,
-    Glob,
-    NotGlob,
-}
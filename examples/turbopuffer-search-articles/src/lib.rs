@@ -9,11 +9,13 @@ use std::time::Duration;
 
 use momento_functions_bytes::encoding::{Extract, Json};
 use momento_functions_cache as cache;
+use momento_functions_guest_web::proxy::error_for_status;
 use momento_functions_guest_web::{WebEnvironment, WebError, WebResponse, WebResult, invoke};
 use momento_functions_host_log::{LogDestination, configure_logs};
 use momento_functions_http::{Request as HttpRequest, invoke as http_invoke};
+use momento_functions_turbopuffer::Filter;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
+use serde_json::json;
 
 #[derive(Deserialize, Debug)]
 struct Request {
@@ -110,16 +112,7 @@ fn search(Json(request): Json<Request>) -> WebResult<WebResponse> {
             ),
     )?;
 
-    if response.status != 200 {
-        let body = response.body.into_bytes();
-        let message = format!(
-            "Failed to search documents: {}",
-            String::from_utf8(body).unwrap_or_default()
-        );
-        return Ok(WebResponse::new()
-            .with_status(response.status)
-            .with_body(json!({ "message": message }).to_string())?);
-    }
+    let response = error_for_status(response)?;
 
     let Json(QueryResponse { rows }) = Json::<QueryResponse>::extract(response.body)?;
     let response_body = serde_json::to_vec(&rows)?;
@@ -193,40 +186,3 @@ fn setup_logging() -> WebResult<()> {
     configure_logs([LogDestination::topic(env.function_name()).into()])?;
     Ok(())
 }
-
-// ---------------------------------------------------------------------------
-// Recursive filter representation. Turbopuffer validates the structure;
-// these types just carry it across the wire.
-// ---------------------------------------------------------------------------
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum Filter {
-    Logical(LogicalFilter),
-    Not(NotFilter),
-    Comparison(ComparisonFilter),
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LogicalFilter(pub String, pub Vec<Filter>);
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NotFilter(pub String, pub Box<Filter>);
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ComparisonFilter(pub String, pub ComparisonOp, pub Value);
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-pub enum ComparisonOp {
-    Eq,
-    Neq,
-    In,
-    NotIn,
-    Lt,
-    Lte,
-    Gt,
-    Gte,
-    Glob,
-    NotGlob,
-}
@@ -17,6 +17,7 @@ use std::time::Duration;
 
 use momento_functions_bytes::encoding::{Extract, Json};
 use momento_functions_cache as cache;
+use momento_functions_guest_web::proxy::error_for_status;
 use momento_functions_guest_web::{WebEnvironment, WebError, WebResponse, WebResult, invoke};
 use momento_functions_host_log::{LogDestination, configure_logs};
 use momento_functions_http::{Request as HttpRequest, invoke as http_invoke};
@@ -95,16 +96,7 @@ fn search(Json(request): Json<Request>) -> WebResult<WebResponse> {
             ),
     )?;
 
-    if response.status != 200 {
-        let body = response.body.into_bytes();
-        let message = format!(
-            "Failed to search documents: {}",
-            String::from_utf8(body).unwrap_or_default()
-        );
-        return Ok(WebResponse::new()
-            .with_status(response.status)
-            .with_body(json!({ "message": message }).to_string())?);
-    }
+    let response = error_for_status(response)?;
     let Json(QueryResponse { rows }) = Json::<QueryResponse>::extract(response.body)?;
     let response_body = serde_json::to_vec(&rows)?;
     Ok(WebResponse::new()
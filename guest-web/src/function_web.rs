@@ -56,6 +56,33 @@ use crate::IntoWebResponse;
 ///     Json(Response { message: format!("Hello, {}!", request.name) })
 /// }
 /// ```
+///
+/// **Raw Body Alongside Typed Input:**
+///
+/// Accept `(Body, T)` to receive the exact request bytes together with the extracted value, for
+/// webhooks whose signature covers the bytes on the wire. Accept [BodyData] instead to check the
+/// payload before it is copied into your function's memory, and call [BodyData::extract] once
+/// you are satisfied.
+///
+/// [BodyData]: momento_functions_bytes::encoding::BodyData
+/// [BodyData::extract]: momento_functions_bytes::encoding::BodyData::extract
+/// ```rust
+/// use momento_functions_bytes::encoding::{Body, Json};
+/// use momento_functions_guest_web::{invoke, WebResponse};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Event {
+///     kind: String,
+/// }
+///
+/// invoke!(webhook);
+/// fn webhook((body, Json(event)): (Body, Json<Event>)) -> WebResponse {
+///     if body.as_bytes().is_empty() {
+///         return WebResponse::new().with_status(401);
+///     }
+///     WebResponse::new().with_status(202).header("x-event-kind", event.kind)
+/// }
+/// ```
 #[macro_export]
 macro_rules! invoke {
     ($post_handler: ident) => {
@@ -82,22 +109,22 @@ where
     TExtract: Extract,
     TResponse: IntoWebResponse,
 {
+    crate::memory::invocation_started();
     let payload: momento_functions_bytes::Data = payload.into();
-    let request = match TExtract::extract(payload) {
-        Ok(request) => request,
-        Err(error) => {
-            return guest_function_web::Response {
-                status: 400,
-                headers: vec![],
-                body: momento_functions_bytes::Data::from(
-                    format!("Failed to parse request body: {error}")
-                        .to_string()
-                        .as_bytes()
-                        .to_vec(),
-                )
-                .into(),
-            };
-        }
+    let response = match TExtract::extract(payload) {
+        Ok(request) => handler(request).response(),
+        Err(error) => guest_function_web::Response {
+            status: 400,
+            headers: vec![],
+            body: momento_functions_bytes::Data::from(
+                format!("Failed to parse request body: {error}")
+                    .to_string()
+                    .as_bytes()
+                    .to_vec(),
+            )
+            .into(),
+        },
     };
-    handler(request).response()
+    crate::memory::invocation_finished();
+    response
 }
@@ -0,0 +1,141 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// A `Cache-Control` directive, for [WebResponse::cache_control](crate::WebResponse::cache_control).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheDirective {
+    /// `public`: any cache may store the response.
+    Public,
+    /// `private`: only the client's own cache may store the response.
+    Private,
+    /// `no-cache`: caches must revalidate before reusing the response.
+    NoCache,
+    /// `no-store`: the response must not be stored at all.
+    NoStore,
+    /// `max-age`: how long the response stays fresh.
+    MaxAge(Duration),
+    /// `s-maxage`: how long the response stays fresh in shared caches.
+    SharedMaxAge(Duration),
+    /// `must-revalidate`: stale responses must be revalidated before reuse.
+    MustRevalidate,
+    /// `immutable`: the response will not change while it is fresh.
+    Immutable,
+}
+
+impl Display for CacheDirective {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheDirective::Public => write!(f, "public"),
+            CacheDirective::Private => write!(f, "private"),
+            CacheDirective::NoCache => write!(f, "no-cache"),
+            CacheDirective::NoStore => write!(f, "no-store"),
+            CacheDirective::MaxAge(age) => write!(f, "max-age={}", age.as_secs()),
+            CacheDirective::SharedMaxAge(age) => write!(f, "s-maxage={}", age.as_secs()),
+            CacheDirective::MustRevalidate => write!(f, "must-revalidate"),
+            CacheDirective::Immutable => write!(f, "immutable"),
+        }
+    }
+}
+
+/// Headers that may only appear once in a response. Setting one of these again replaces it.
+const SINGLE_VALUED: &[&str] = &[
+    "age",
+    "cache-control",
+    "content-disposition",
+    "content-encoding",
+    "content-language",
+    "content-length",
+    "content-location",
+    "content-range",
+    "content-type",
+    "date",
+    "etag",
+    "expires",
+    "last-modified",
+    "location",
+    "retry-after",
+    "server",
+];
+
+/// Add a header, lowercasing its name.
+///
+/// Single-valued headers like `content-type` replace any earlier value. Other headers are
+/// appended, unless the exact same name and value are already present.
+pub(crate) fn insert_header(headers: &mut Vec<(String, String)>, name: String, value: String) {
+    let name = name.to_ascii_lowercase();
+    if SINGLE_VALUED.contains(&name.as_str()) {
+        headers.retain(|(existing, _)| *existing != name);
+    } else if headers
+        .iter()
+        .any(|(existing, existing_value)| *existing == name && *existing_value == value)
+    {
+        return;
+    }
+    headers.push((name, value));
+}
+
+pub(crate) fn cache_control_value(directives: impl IntoIterator<Item = CacheDirective>) -> String {
+    directives
+        .into_iter()
+        .map(|directive| directive.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn insert_all(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        for (name, value) in pairs {
+            insert_header(&mut headers, name.to_string(), value.to_string());
+        }
+        headers
+    }
+
+    #[test]
+    fn single_valued_headers_are_replaced_regardless_of_case() {
+        let headers = insert_all(&[
+            ("Content-Type", "text/plain"),
+            ("x-trace", "1"),
+            ("content-type", "application/json"),
+        ]);
+        assert_eq!(
+            headers,
+            vec![
+                ("x-trace".to_string(), "1".to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeatable_headers_drop_only_exact_duplicates() {
+        let headers = insert_all(&[
+            ("Set-Cookie", "a=1"),
+            ("set-cookie", "b=2"),
+            ("SET-COOKIE", "a=1"),
+        ]);
+        assert_eq!(
+            headers,
+            vec![
+                ("set-cookie".to_string(), "a=1".to_string()),
+                ("set-cookie".to_string(), "b=2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn cache_control_joins_directives() {
+        assert_eq!(
+            cache_control_value([
+                CacheDirective::Public,
+                CacheDirective::MaxAge(Duration::from_secs(60)),
+                CacheDirective::Immutable,
+            ]),
+            "public, max-age=60, immutable"
+        );
+    }
+}
@@ -1,13 +1,22 @@
+mod encoding;
 mod function_web;
+mod headers;
 mod into_web_response;
+pub mod memory;
+#[cfg(feature = "proxy")]
+pub mod proxy;
 mod response;
 mod response_stream;
+#[cfg(feature = "s3-proxy")]
+pub mod s3_proxy;
+pub mod signed_url;
 mod web_environment;
 /// Internal module for WIT bindings.
 #[doc(hidden)]
 pub mod wit;
 
 pub use function_web::invoke_template;
+pub use headers::CacheDirective;
 pub use into_web_response::IntoWebResponse;
 pub use response::WebError;
 pub use response::WebResponse;
@@ -0,0 +1,214 @@
+//! Guest memory statistics
+//!
+//! Heap usage is only known when the Function counts its allocations. Install
+//! [TrackingAllocator] as the global allocator to enable it:
+//! ```rust,no_run
+//! use momento_functions_guest_web::memory::TrackingAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: TrackingAllocator = TrackingAllocator::system();
+//! ```
+//!
+//! Linear memory size is always available. It only grows, so it shows the most memory the
+//! instance has ever needed rather than what it is using now.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+#[cfg(target_arch = "wasm32")]
+const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+static TRACKING: AtomicBool = AtomicBool::new(false);
+static HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static REPORT_AFTER_INVOCATION: AtomicBool = AtomicBool::new(false);
+
+/// A global allocator that counts the bytes allocated through it.
+pub struct TrackingAllocator<A = System> {
+    inner: A,
+}
+
+impl TrackingAllocator<System> {
+    /// Count allocations made with the system allocator.
+    pub const fn system() -> Self {
+        Self { inner: System }
+    }
+}
+
+impl<A> TrackingAllocator<A> {
+    /// Count allocations made with another allocator.
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+fn allocated(size: usize) {
+    TRACKING.store(true, Ordering::Relaxed);
+    let current = HEAP_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_HEAP_BYTES.fetch_max(current, Ordering::Relaxed);
+}
+
+fn freed(size: usize) {
+    HEAP_BYTES.fetch_sub(size, Ordering::Relaxed);
+}
+
+// SAFETY: every call is forwarded to the inner allocator unchanged; only the counters are added.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: the caller upholds `GlobalAlloc::alloc`'s contract, which we pass through.
+        let pointer = unsafe { self.inner.alloc(layout) };
+        if !pointer.is_null() {
+            allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: the caller upholds `GlobalAlloc::alloc_zeroed`'s contract, which we pass through.
+        let pointer = unsafe { self.inner.alloc_zeroed(layout) };
+        if !pointer.is_null() {
+            allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds `GlobalAlloc::dealloc`'s contract, which we pass through.
+        unsafe { self.inner.dealloc(pointer, layout) };
+        freed(layout.size());
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: the caller upholds `GlobalAlloc::realloc`'s contract, which we pass through.
+        let new_pointer = unsafe { self.inner.realloc(pointer, layout, new_size) };
+        if !new_pointer.is_null() {
+            freed(layout.size());
+            allocated(new_size);
+        }
+        new_pointer
+    }
+}
+
+/// A snapshot of the Function's memory use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Bytes currently allocated on the heap. `None` without a [TrackingAllocator].
+    pub heap_bytes: Option<usize>,
+    /// The most bytes allocated on the heap at once since the last [reset_peak]. `None`
+    /// without a [TrackingAllocator].
+    pub peak_heap_bytes: Option<usize>,
+    /// The size of the instance's linear memory.
+    pub linear_memory_bytes: usize,
+}
+
+/// Take a snapshot of the Function's memory use.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_guest_web::memory;
+///
+/// let stats = memory::memory_stats();
+/// if let Some(peak) = stats.peak_heap_bytes {
+///     println!("peak heap: {peak} bytes of {} linear", stats.linear_memory_bytes);
+/// }
+/// ```
+pub fn memory_stats() -> MemoryStats {
+    let tracking = TRACKING.load(Ordering::Relaxed);
+    MemoryStats {
+        heap_bytes: tracking.then(|| HEAP_BYTES.load(Ordering::Relaxed)),
+        peak_heap_bytes: tracking.then(|| PEAK_HEAP_BYTES.load(Ordering::Relaxed)),
+        linear_memory_bytes: linear_memory_bytes(),
+    }
+}
+
+/// Start measuring the peak heap again from the current heap size.
+pub fn reset_peak() {
+    PEAK_HEAP_BYTES.store(HEAP_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Log the memory statistics at the end of each invocation.
+///
+/// When enabled, the peak is [reset](reset_peak) as each invocation starts, so each log shows
+/// the peak heap of that invocation alone. The statistics are logged at `info` level with the
+/// `log` crate, so they go wherever your logs are configured to go.
+pub fn report_after_invocation(enabled: bool) {
+    REPORT_AFTER_INVOCATION.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn invocation_started() {
+    if REPORT_AFTER_INVOCATION.load(Ordering::Relaxed) {
+        reset_peak();
+    }
+}
+
+pub(crate) fn invocation_finished() {
+    if !REPORT_AFTER_INVOCATION.load(Ordering::Relaxed) {
+        return;
+    }
+    let stats = memory_stats();
+    match (stats.heap_bytes, stats.peak_heap_bytes) {
+        (Some(heap), Some(peak)) => log::info!(
+            "invocation memory: heap_bytes={heap} peak_heap_bytes={peak} linear_memory_bytes={}",
+            stats.linear_memory_bytes
+        ),
+        _ => log::info!(
+            "invocation memory: linear_memory_bytes={}",
+            stats.linear_memory_bytes
+        ),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn linear_memory_bytes() -> usize {
+    core::arch::wasm32::memory_size(0) * WASM_PAGE_SIZE
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn linear_memory_bytes() -> usize {
+    0
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // The counters are process-wide, so the tests take turns with them.
+    static COUNTERS: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn peak_keeps_the_most_allocated_at_once() {
+        let _counters = COUNTERS.lock().unwrap();
+        let start = HEAP_BYTES.load(Ordering::Relaxed);
+        reset_peak();
+
+        allocated(100);
+        allocated(50);
+        freed(100);
+        allocated(20);
+
+        let stats = memory_stats();
+        assert_eq!(Some(start + 70), stats.heap_bytes);
+        assert_eq!(Some(start + 150), stats.peak_heap_bytes);
+
+        freed(70);
+    }
+
+    #[test]
+    fn reset_peak_starts_from_the_current_heap() {
+        let _counters = COUNTERS.lock().unwrap();
+        let start = HEAP_BYTES.load(Ordering::Relaxed);
+
+        allocated(200);
+        freed(150);
+        reset_peak();
+        assert_eq!(Some(start + 50), memory_stats().peak_heap_bytes);
+
+        allocated(10);
+        assert_eq!(Some(start + 60), memory_stats().peak_heap_bytes);
+
+        freed(60);
+    }
+}
@@ -0,0 +1,410 @@
+//! Forward the incoming request to an upstream and relay its response
+//!
+//! The request and response bodies are passed along as [Data] without being read, so
+//! they stay on the host rather than being copied into your Function's memory.
+//!
+//! When a Function calls an upstream API itself, [error_for_status] turns the upstream's
+//! failures into consistent error responses for the caller.
+
+use momento_functions_bytes::Data;
+use momento_functions_http::{HttpError, Request, Response, invoke};
+use serde_json::{Value, json};
+
+use crate::encoding::percent_encode;
+use crate::web_environment::{header_list, query_parameter_list};
+use crate::{WebEnvironment, WebError, WebResponse};
+
+/// Headers that describe a single connection rather than the message, so they are not
+/// forwarded in either direction.
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "content-length",
+    "host",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// The caller's credentials, which are not forwarded upstream unless
+/// [Proxy::forward_credentials] is set.
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie"];
+
+/// Headers from a failed upstream response that are passed on to the caller by
+/// [proxy_error], because they tell the caller when to retry or identify the failed request.
+const ERROR_HEADERS: &[&str] = &[
+    "retry-after",
+    "x-request-id",
+    "x-amzn-requestid",
+    "x-amz-request-id",
+    "request-id",
+];
+
+/// Header prefixes for rate limit information, like `x-ratelimit-remaining`, which
+/// [proxy_error] also passes on.
+const ERROR_HEADER_PREFIXES: &[&str] = &["ratelimit-", "x-ratelimit-"];
+
+/// The most of a failed upstream's body [proxy_error] reads.
+const ERROR_BODY_LIMIT: usize = 64 * 1024;
+
+/// The most characters of a failed upstream's plain text body [proxy_error] uses as the
+/// message.
+const ERROR_MESSAGE_LIMIT: usize = 1024;
+
+enum Rewrite {
+    Set(String, String),
+    Remove(String),
+}
+
+/// Forwards the current request to an upstream with the same method, path, and query.
+///
+/// Repeated headers and query parameters are forwarded as they came. The caller's
+/// `authorization` and `cookie` headers are not, since they are the caller's credentials for
+/// your Function rather than for the upstream.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_bytes::Data;
+/// use momento_functions_guest_web::proxy::Proxy;
+/// use momento_functions_guest_web::{WebResponse, WebResult};
+///
+/// momento_functions_guest_web::invoke!(gateway);
+/// fn gateway(body: Data) -> WebResult<WebResponse> {
+///     Ok(Proxy::new("https://api.example.com/v1")
+///         .set_request_header("x-api-key", "upstream-key")
+///         .set_response_header("cache-control", "no-store")
+///         .forward(body)?)
+/// }
+/// ```
+pub struct Proxy {
+    upstream: String,
+    request_rewrites: Vec<Rewrite>,
+    response_rewrites: Vec<Rewrite>,
+    forward_credentials: bool,
+}
+
+impl Proxy {
+    /// Proxy to `upstream`, a base URL such as `https://api.example.com/v1`. The invocation
+    /// path is appended to it.
+    pub fn new(upstream: impl Into<String>) -> Self {
+        Self {
+            upstream: upstream.into(),
+            request_rewrites: Vec::new(),
+            response_rewrites: Vec::new(),
+            forward_credentials: false,
+        }
+    }
+
+    /// Forward the caller's `authorization` and `cookie` headers upstream too, for an
+    /// upstream that authenticates the same callers as your Function.
+    pub fn forward_credentials(mut self) -> Self {
+        self.forward_credentials = true;
+        self
+    }
+
+    /// Send this header upstream, replacing the caller's value if there was one.
+    pub fn set_request_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.request_rewrites
+            .push(Rewrite::Set(name.into(), value.into()));
+        self
+    }
+
+    /// Do not send the caller's value of this header upstream.
+    pub fn remove_request_header(mut self, name: impl Into<String>) -> Self {
+        self.request_rewrites.push(Rewrite::Remove(name.into()));
+        self
+    }
+
+    /// Return this header to the caller, replacing the upstream's value if there was one.
+    pub fn set_response_header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.response_rewrites
+            .push(Rewrite::Set(name.into(), value.into()));
+        self
+    }
+
+    /// Do not return the upstream's value of this header to the caller.
+    pub fn remove_response_header(mut self, name: impl Into<String>) -> Self {
+        self.response_rewrites.push(Rewrite::Remove(name.into()));
+        self
+    }
+
+    /// Forward the request with `body`, usually the Function's own payload, and relay the
+    /// upstream's status, headers, and body.
+    pub fn forward(self, body: Data) -> Result<WebResponse, HttpError> {
+        let environment = WebEnvironment::load();
+        let url = upstream_url(
+            &self.upstream,
+            environment.http_path(),
+            query_parameter_list()
+                .iter()
+                .map(|(name, value)| (name, value)),
+        );
+        let request_headers = rewrite(
+            caller_headers(header_list(), self.forward_credentials),
+            &self.request_rewrites,
+        );
+        let response = invoke(
+            Request::new(url, environment.http_method())
+                .with_headers(request_headers)
+                .with_body(body),
+        )?;
+        let response_headers = rewrite(response.headers, &self.response_rewrites);
+        let relayed = WebResponse::new()
+            .with_status(response.status)
+            .with_headers(response_headers);
+        match relayed.with_body(response.body) {
+            Ok(relayed) => Ok(relayed),
+            Err(never) => match never {},
+        }
+    }
+}
+
+/// Pass a successful upstream response through, or turn a failed one into an error with
+/// [proxy_error].
+///
+/// Responses with a 2xx status are successful.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_bytes::Data;
+/// use momento_functions_bytes::encoding::{Extract, Json};
+/// use momento_functions_guest_web::proxy::error_for_status;
+/// use momento_functions_guest_web::WebResult;
+/// use momento_functions_http::{Request, invoke};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Forecast {
+///     summary: String,
+/// }
+///
+/// momento_functions_guest_web::invoke!(forecast);
+/// fn forecast(_body: Data) -> WebResult<String> {
+///     let response = error_for_status(invoke(Request::new(
+///         "https://weather.example.com/forecast/seattle",
+///         "GET",
+///     ))?)?;
+///     let Json(forecast) = Json::<Forecast>::extract(response.body)?;
+///     Ok(forecast.summary)
+/// }
+/// ```
+pub fn error_for_status(upstream: Response) -> Result<Response, WebError> {
+    if (200..300).contains(&upstream.status) {
+        Ok(upstream)
+    } else {
+        Err(WebError::from_response(proxy_error(upstream)))
+    }
+}
+
+/// Turn a failed upstream response into a response for the caller.
+///
+/// Keeps the upstream's status, and only the headers that tell the caller when to retry or
+/// identify the failed request, like `retry-after` and `x-request-id`. The body is JSON:
+/// ```json
+/// { "upstream_status": 429, "message": "Rate limit exceeded" }
+/// ```
+/// The message is the `message` or `error` of a JSON error body, or the start of a plain
+/// text one.
+pub fn proxy_error(upstream: Response) -> WebResponse {
+    let headers = upstream
+        .headers
+        .into_iter()
+        .filter(|(name, _)| {
+            let name = name.to_ascii_lowercase();
+            ERROR_HEADERS.contains(&name.as_str())
+                || ERROR_HEADER_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+    let message = match upstream.body.into_bytes_limited(ERROR_BODY_LIMIT) {
+        Ok(body) => error_message(&body),
+        Err(_) => format!("the upstream's error body is over {ERROR_BODY_LIMIT} bytes"),
+    };
+    let body = json!({ "upstream_status": upstream.status, "message": message });
+    WebResponse::new()
+        .with_status(upstream.status)
+        .with_headers(headers)
+        .content_type("application/json")
+        .with_body(body)
+        // Serializing a status and a string cannot fail.
+        .unwrap_or_else(|_| WebResponse::new().with_status(upstream.status))
+}
+
+fn error_message(body: &[u8]) -> String {
+    if let Ok(body) = serde_json::from_slice::<Value>(body) {
+        let message = body
+            .get("message")
+            .or_else(|| body.pointer("/error/message"))
+            .or_else(|| body.get("error"))
+            .and_then(Value::as_str);
+        if let Some(message) = message {
+            return message.to_string();
+        }
+    }
+    String::from_utf8_lossy(body)
+        .trim()
+        .chars()
+        .take(ERROR_MESSAGE_LIMIT)
+        .collect()
+}
+
+/// The caller's headers to send upstream, without its credentials unless they are forwarded.
+fn caller_headers(
+    headers: &[(String, String)],
+    forward_credentials: bool,
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            forward_credentials || !CREDENTIAL_HEADERS.contains(&name.to_ascii_lowercase().as_str())
+        })
+        .cloned()
+        .collect()
+}
+
+fn rewrite(
+    headers: impl IntoIterator<Item = (String, String)>,
+    rewrites: &[Rewrite],
+) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = headers
+        .into_iter()
+        .filter(|(name, _)| !HOP_BY_HOP.contains(&name.to_ascii_lowercase().as_str()))
+        .collect();
+    for rewrite in rewrites {
+        match rewrite {
+            Rewrite::Set(name, value) => {
+                headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+                headers.push((name.clone(), value.clone()));
+            }
+            Rewrite::Remove(name) => {
+                headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+            }
+        }
+    }
+    headers
+}
+
+fn upstream_url<'a>(
+    upstream: &str,
+    path: &str,
+    query: impl Iterator<Item = (&'a String, &'a String)>,
+) -> String {
+    let mut url = upstream.trim_end_matches('/').to_string();
+    let path = path.trim_start_matches('/');
+    if !path.is_empty() {
+        url.push('/');
+        url.push_str(path);
+    }
+    let mut separator = if url.contains('?') { '&' } else { '?' };
+    for (name, value) in query {
+        url.push(separator);
+        url.push_str(&percent_encode(name));
+        url.push('=');
+        url.push_str(&percent_encode(value));
+        separator = '&';
+    }
+    url
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::IntoWebResponse;
+
+    #[test]
+    fn upstream_url_joins_path_and_encodes_query() {
+        let query = [
+            ("q".to_string(), "a b&c".to_string()),
+            ("tag".to_string(), "x".to_string()),
+            ("tag".to_string(), "y".to_string()),
+        ];
+        assert_eq!(
+            "https://api.example.com/v1/items/42?q=a%20b%26c&tag=x&tag=y",
+            upstream_url(
+                "https://api.example.com/v1/",
+                "/items/42",
+                query.iter().map(|(name, value)| (name, value))
+            )
+        );
+        assert_eq!(
+            "https://api.example.com",
+            upstream_url("https://api.example.com", "", std::iter::empty())
+        );
+    }
+
+    #[test]
+    fn error_messages_come_from_json_or_text_bodies() {
+        assert_eq!(
+            "Rate limit exceeded",
+            error_message(br#"{"error": {"message": "Rate limit exceeded", "type": "rate"}}"#)
+        );
+        assert_eq!(
+            "namespace not found",
+            error_message(br#"{"error": "namespace not found"}"#)
+        );
+        assert_eq!("bad gateway", error_message(b"  bad gateway\n"));
+        assert_eq!(ERROR_MESSAGE_LIMIT, error_message(&[b'x'; 4096]).len());
+
+        let response = proxy_error(Response {
+            status: 429,
+            headers: vec![
+                ("Retry-After".to_string(), "30".to_string()),
+                ("X-RateLimit-Remaining".to_string(), "0".to_string()),
+                ("Set-Cookie".to_string(), "session=abc".to_string()),
+            ],
+            body: Data::from(br#"{"message": "slow down"}"#.to_vec()),
+        })
+        .response();
+        assert_eq!(429, response.status);
+        let headers: Vec<_> = response
+            .headers
+            .iter()
+            .map(|header| header.name.as_str())
+            .collect();
+        assert_eq!(
+            vec!["retry-after", "x-ratelimit-remaining", "content-type"],
+            headers
+        );
+        let body: Value = serde_json::from_slice(&Data::from(response.body).into_bytes()).unwrap();
+        assert_eq!(
+            json!({ "upstream_status": 429, "message": "slow down" }),
+            body
+        );
+    }
+
+    #[test]
+    fn rewrite_drops_hop_by_hop_and_applies_rewrites() {
+        let headers = rewrite(
+            [
+                ("Host".to_string(), "function.example.com".to_string()),
+                ("Authorization".to_string(), "Bearer caller".to_string()),
+                ("Accept".to_string(), "application/json".to_string()),
+            ],
+            &[
+                Rewrite::Remove("authorization".to_string()),
+                Rewrite::Set("accept".to_string(), "text/plain".to_string()),
+            ],
+        );
+        assert_eq!(
+            vec![("accept".to_string(), "text/plain".to_string())],
+            headers
+        );
+
+        let caller = [
+            ("Authorization".to_string(), "Bearer caller".to_string()),
+            ("Cookie".to_string(), "session=1".to_string()),
+            ("Accept".to_string(), "text/html".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ];
+        assert_eq!(&caller[2..], caller_headers(&caller, false));
+        assert_eq!(&caller[..], caller_headers(&caller, true));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::IntoWebResponse;
+use crate::headers::{CacheDirective, cache_control_value, insert_header};
 use crate::wit::exports::momento::web_function::guest_function_web::Response;
 use momento_functions_bytes::Data;
 use momento_functions_bytes::encoding::Encode;
@@ -28,6 +29,34 @@ impl WebError {
             response,
         }
     }
+
+    /// Sets the HTTP status of the error response. Errors are 500s unless you change them.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use momento_functions_bytes::Data;
+    /// use momento_functions_guest_web::{WebError, WebResult};
+    ///
+    /// fn handle(payload: Data) -> WebResult<Vec<u8>> {
+    ///     let body = payload
+    ///         .into_bytes_limited(1024 * 1024)
+    ///         .map_err(|e| WebError::from(e).with_status(413))?;
+    ///     Ok(body)
+    /// }
+    /// ```
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.response.status = status;
+        self
+    }
+
+    /// An error that responds with `response` as it is.
+    #[cfg(feature = "proxy")]
+    pub(crate) fn from_response(response: WebResponse) -> Self {
+        Self {
+            source: None,
+            response,
+        }
+    }
 }
 
 impl<E: Error + 'static> From<E> for WebError {
@@ -100,17 +129,54 @@ impl WebResponse {
     }
 
     /// Adds a header to the response.
+    ///
+    /// Header names are lowercased. Headers that may only appear once, like `content-type`,
+    /// replace any earlier value; others are appended unless the same value is already set.
     pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.push((key.into(), value.into()));
+        insert_header(&mut self.headers, key.into(), value.into());
         self
     }
 
     /// Overrides the collection of headers for the response.
+    ///
+    /// The headers are normalized the same way as [WebResponse::header].
     pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
-        self.headers = headers;
+        self.headers = Vec::with_capacity(headers.len());
+        for (name, value) in headers {
+            insert_header(&mut self.headers, name, value);
+        }
         self
     }
 
+    /// Sets the `content-type` header, like `application/json` or `text/html; charset=utf-8`.
+    pub fn content_type(self, mime: impl Into<String>) -> Self {
+        self.header("content-type", mime)
+    }
+
+    /// Sets the `cache-control` header from a list of directives.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use momento_functions_guest_web::{CacheDirective, WebResponse};
+    ///
+    /// let response = WebResponse::new()
+    ///     .content_type("text/css")
+    ///     .cache_control([
+    ///         CacheDirective::Public,
+    ///         CacheDirective::MaxAge(Duration::from_secs(86400)),
+    ///         CacheDirective::Immutable,
+    ///     ]);
+    /// ```
+    pub fn cache_control(self, directives: impl IntoIterator<Item = CacheDirective>) -> Self {
+        self.header("cache-control", cache_control_value(directives))
+    }
+
+    /// Sets the `location` header, for redirects and newly created resources.
+    pub fn location(self, url: impl Into<String>) -> Self {
+        self.header("location", url)
+    }
+
     /// Sets the response body. If encoding the body fails, returns an error.
     pub fn with_body<E: Encode>(mut self, body: E) -> Result<Self, E::Error> {
         let body = body.try_serialize()?;
@@ -0,0 +1,326 @@
+//! Serve S3 objects to browsers, with support for `Range` requests
+//!
+//! Browsers seek in videos and resume large downloads with `Range` requests, and revalidate
+//! their cached copies with `If-None-Match`. [S3Proxy] answers both against an S3 object the
+//! way a static file server would: `206 Partial Content` with `Content-Range` for a range,
+//! `304 Not Modified` for an unchanged object, and `416 Range Not Satisfiable` for a range
+//! past the end.
+//!
+//! The object is passed along as [Data] without being read, so it stays on the host rather
+//! than being copied into your Function's memory.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use momento_functions_aws_s3::{ConditionalGetResponse, GetObjectOptions, S3Client, S3GetError};
+use momento_functions_bytes::Data;
+
+use crate::headers::cache_control_value;
+use crate::web_environment::headers;
+use crate::{CacheDirective, WebResponse};
+
+/// The bytes of an object a `Range` header asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteRange {
+    /// From `start` through `end`, inclusive, or through the end of the object.
+    From { start: u64, end: Option<u64> },
+    /// The last this many bytes.
+    Last(u64),
+}
+
+/// Serves objects from one S3 bucket, honoring the caller's `Range`, `If-Range`, and
+/// `If-None-Match` headers.
+///
+/// Requests with several ranges, like `bytes=0-99,200-299`, get the whole object, which
+/// HTTP allows in place of a multipart response.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_aws_s3::S3Client;
+/// use momento_functions_bytes::Data;
+/// use std::time::Duration;
+/// use momento_functions_guest_web::s3_proxy::S3Proxy;
+/// use momento_functions_guest_web::{CacheDirective, WebEnvironment, WebResponse, WebResult};
+///
+/// # fn client() -> S3Client { todo!() }
+/// momento_functions_guest_web::invoke!(videos);
+/// fn videos(_body: Data) -> WebResult<WebResponse> {
+///     let client = client();
+///     let key = WebEnvironment::load().http_path().trim_start_matches('/');
+///     Ok(S3Proxy::new(&client, "my-videos")
+///         .cache_control([CacheDirective::Public, CacheDirective::MaxAge(Duration::from_secs(3600))])
+///         .serve(key)?)
+/// }
+/// ```
+pub struct S3Proxy<'a> {
+    client: &'a S3Client,
+    bucket: String,
+    cache_control: Option<String>,
+}
+
+impl<'a> S3Proxy<'a> {
+    /// Serve objects from `bucket` with `client`.
+    pub fn new(client: &'a S3Client, bucket: impl Into<String>) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+            cache_control: None,
+        }
+    }
+
+    /// Send this `cache-control` with found and unchanged objects.
+    pub fn cache_control(mut self, directives: impl IntoIterator<Item = CacheDirective>) -> Self {
+        self.cache_control = Some(cache_control_value(directives));
+        self
+    }
+
+    /// Respond to the current request with the object at `key`, or the part of it the caller
+    /// asked for.
+    ///
+    /// Missing objects are `404 Not Found`. Errors from S3 are returned for the caller to
+    /// map to a response.
+    pub fn serve(&self, key: impl Into<String>) -> Result<WebResponse, S3GetError<Infallible>> {
+        self.serve_with(key.into(), headers())
+    }
+
+    fn serve_with(
+        &self,
+        key: String,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<WebResponse, S3GetError<Infallible>> {
+        let header = |name: &str| {
+            request_headers
+                .iter()
+                .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim())
+        };
+        let range = header("range").and_then(parse_range);
+        let if_none_match = header("if-none-match");
+
+        let mut response = self.get(&key, range, if_none_match)?;
+        // A range applies only while the object still has the ETag the caller's partial copy
+        // came from. Otherwise the caller needs the whole object again.
+        if let (Some(_), Some(if_range)) = (range, header("if-range")) {
+            let validated = match &response {
+                ConditionalGetResponse::Found(content) => {
+                    !content.is_partial() || strong_match(if_range, content.etag.as_deref())
+                }
+                ConditionalGetResponse::RangeNotSatisfiable { .. } => false,
+                ConditionalGetResponse::NotModified { .. } | ConditionalGetResponse::NotFound => {
+                    true
+                }
+            };
+            if !validated {
+                response = self.get(&key, None, if_none_match)?;
+            }
+        }
+        Ok(respond(response, self.cache_control.as_deref()))
+    }
+
+    fn get(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+        if_none_match: Option<&str>,
+    ) -> Result<ConditionalGetResponse<Data>, S3GetError<Infallible>> {
+        let mut options = GetObjectOptions::new();
+        options = match range {
+            Some(ByteRange::From {
+                start,
+                end: Some(end),
+            }) => options.range(start..=end),
+            Some(ByteRange::From { start, end: None }) => options.range(start..),
+            Some(ByteRange::Last(length)) => options.last_bytes(length),
+            None => options,
+        };
+        if let Some(etag) = if_none_match {
+            options = options.if_none_match(etag);
+        }
+        self.client
+            .get_with_options(self.bucket.clone(), key, options)
+    }
+}
+
+/// Parse a `Range` header with a single byte range. Anything else is ignored, so the whole
+/// object is served.
+fn parse_range(header: &str) -> Option<ByteRange> {
+    let (unit, spec) = header.split_once('=')?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") || spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.trim().split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    if start.is_empty() {
+        return match end.parse() {
+            Ok(0) | Err(_) => None,
+            Ok(length) => Some(ByteRange::Last(length)),
+        };
+    }
+    let start = start.parse().ok()?;
+    if end.is_empty() {
+        return Some(ByteRange::From { start, end: None });
+    }
+    let end = end.parse().ok()?;
+    (start <= end).then_some(ByteRange::From {
+        start,
+        end: Some(end),
+    })
+}
+
+/// Whether an `If-Range` value names this ETag. Weak ETags never match.
+fn strong_match(if_range: &str, etag: Option<&str>) -> bool {
+    !if_range.starts_with("W/") && etag.is_some_and(|etag| etag == if_range)
+}
+
+fn respond(response: ConditionalGetResponse<Data>, cache_control: Option<&str>) -> WebResponse {
+    let response = match response {
+        ConditionalGetResponse::Found(content) => {
+            let partial = content.is_partial();
+            let mut found = WebResponse::new()
+                .with_status(if partial { 206 } else { 200 })
+                .header("accept-ranges", "bytes");
+            if let Some(content_range) = content.content_range.filter(|_| partial) {
+                found = found.header("content-range", content_range);
+            }
+            if let Some(content_type) = content.content_type {
+                found = found.content_type(content_type);
+            }
+            if let Some(etag) = content.etag {
+                found = found.header("etag", etag);
+            }
+            match found.with_body(content.body) {
+                Ok(found) => found,
+                Err(never) => match never {},
+            }
+        }
+        ConditionalGetResponse::NotModified { etag } => {
+            let unchanged = WebResponse::new().with_status(304);
+            match etag {
+                Some(etag) => unchanged.header("etag", etag),
+                None => unchanged,
+            }
+        }
+        ConditionalGetResponse::RangeNotSatisfiable { content_range } => {
+            let unsatisfiable = WebResponse::new()
+                .with_status(416)
+                .header("accept-ranges", "bytes");
+            return match content_range {
+                Some(content_range) => unsatisfiable.header("content-range", content_range),
+                None => unsatisfiable,
+            };
+        }
+        ConditionalGetResponse::NotFound => return WebResponse::new().with_status(404),
+    };
+    match cache_control {
+        Some(cache_control) => response.header("cache-control", cache_control),
+        None => response,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use momento_functions_aws_s3::ObjectContent;
+
+    use super::*;
+    use crate::IntoWebResponse;
+
+    fn status_and_headers(response: WebResponse) -> (u16, Vec<(String, String)>) {
+        let response = response.response();
+        let headers = response
+            .headers
+            .into_iter()
+            .map(|header| (header.name, header.value))
+            .collect();
+        (response.status, headers)
+    }
+
+    fn pairs(headers: &[(&str, &str)]) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        assert_eq!(
+            Some(ByteRange::From {
+                start: 0,
+                end: Some(1023)
+            }),
+            parse_range("bytes=0-1023")
+        );
+        assert_eq!(
+            Some(ByteRange::From {
+                start: 4096,
+                end: None
+            }),
+            parse_range("bytes=4096-")
+        );
+        assert_eq!(Some(ByteRange::Last(500)), parse_range("Bytes=-500"));
+        assert_eq!(None, parse_range("bytes=0-99,200-299"));
+        assert_eq!(None, parse_range("bytes=100-99"));
+        assert_eq!(None, parse_range("bytes=-0"));
+        assert_eq!(None, parse_range("items=0-9"));
+
+        assert!(strong_match("\"abc\"", Some("\"abc\"")));
+        assert!(!strong_match("W/\"abc\"", Some("W/\"abc\"")));
+        assert!(!strong_match("\"abc\"", None));
+    }
+
+    #[test]
+    fn ranges_are_206_with_content_range() {
+        let partial = ConditionalGetResponse::Found(ObjectContent {
+            body: Data::from(vec![0; 1024]),
+            status: 206,
+            content_length: Some(1024),
+            content_range: Some("bytes 0-1023/4096".to_string()),
+            content_type: Some("video/mp4".to_string()),
+            etag: Some("\"abc\"".to_string()),
+            version_id: None,
+            expiration: None,
+            last_modified: None,
+            metadata: Vec::new(),
+        });
+        assert_eq!(
+            (
+                206,
+                pairs(&[
+                    ("accept-ranges", "bytes"),
+                    ("content-range", "bytes 0-1023/4096"),
+                    ("content-type", "video/mp4"),
+                    ("etag", "\"abc\""),
+                    ("cache-control", "public"),
+                ])
+            ),
+            status_and_headers(respond(partial, Some("public")))
+        );
+
+        let unsatisfiable = ConditionalGetResponse::RangeNotSatisfiable {
+            content_range: Some("bytes */4096".to_string()),
+        };
+        assert_eq!(
+            (
+                416,
+                pairs(&[
+                    ("accept-ranges", "bytes"),
+                    ("content-range", "bytes */4096")
+                ])
+            ),
+            status_and_headers(respond(unsatisfiable, Some("public")))
+        );
+
+        let unchanged = ConditionalGetResponse::NotModified {
+            etag: Some("\"abc\"".to_string()),
+        };
+        assert_eq!(
+            (304, pairs(&[("etag", "\"abc\"")])),
+            status_and_headers(respond(unchanged, None))
+        );
+        assert_eq!(
+            (404, Vec::new()),
+            status_and_headers(respond(ConditionalGetResponse::NotFound, None))
+        );
+    }
+}
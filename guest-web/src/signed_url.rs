@@ -0,0 +1,280 @@
+//! Pre-signed invocation URLs
+//!
+//! A backend that holds a shared secret can hand a browser a URL that invokes your Function
+//! until it expires, without giving the browser a Momento API key. The URL carries an
+//! `expires` query parameter, an optional `caller`, and a `signature`: an HMAC-SHA256 over
+//! the invocation path, the expiry, and the caller.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_guest_web::signed_url::UrlSigner;
+//! use momento_functions_guest_web::{WebError, WebResult};
+//!
+//! momento_functions_guest_web::invoke!(download);
+//! fn download(_payload: Vec<u8>) -> WebResult<String> {
+//!     let signer = UrlSigner::new(std::env::var("URL_SIGNING_SECRET").unwrap_or_default());
+//!     let signed = signer
+//!         .verify_current_request()
+//!         .map_err(|e| WebError::from(e).with_status(403))?;
+//!     Ok(format!("hello, {}", signed.caller.as_deref().unwrap_or("anonymous")))
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use momento_functions_hmac::{HmacKey, constant_time_eq, hex};
+
+use crate::WebEnvironment;
+use crate::encoding::percent_encode;
+use crate::web_environment::query_parameters;
+
+const EXPIRES_PARAMETER: &str = "expires";
+const CALLER_PARAMETER: &str = "caller";
+const SIGNATURE_PARAMETER: &str = "signature";
+
+/// The request's signature could not be verified.
+#[derive(Debug, thiserror::Error)]
+pub enum SignedUrlError {
+    /// The URL has no `signature` parameter.
+    #[error("the url is not signed")]
+    MissingSignature,
+    /// The URL has no `expires` parameter, or it is not a number of seconds.
+    #[error("the url has no valid expiry")]
+    InvalidExpiry,
+    /// The URL's expiry has passed.
+    #[error("the url expired at {expires_at_epoch_seconds}")]
+    Expired {
+        /// When the URL expired, in seconds since the Unix epoch.
+        expires_at_epoch_seconds: u64,
+    },
+    /// The signature does not match the path, expiry, and caller.
+    #[error("the url signature is not valid")]
+    InvalidSignature,
+    /// The URL would expire too far in the future to represent.
+    #[error("the url would expire {valid_for:?} from now, which is too far in the future")]
+    ExpiryOutOfRange {
+        /// How long the URL was asked to be valid for.
+        valid_for: Duration,
+    },
+}
+
+/// A request whose signed URL was verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedRequest {
+    /// The caller the URL was issued to, if it was issued to one.
+    pub caller: Option<String>,
+    /// When the URL stops working.
+    pub expires: SystemTime,
+}
+
+/// Signs and verifies invocation URLs with a shared secret.
+pub struct UrlSigner {
+    key: HmacKey,
+}
+
+impl UrlSigner {
+    /// Create a signer with the secret shared with whatever issues the URLs.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: HmacKey::new(secret),
+        }
+    }
+
+    /// Make the query string that signs `path` until `valid_for` from now.
+    ///
+    /// `path` is relative to the Function's base URL, the same as
+    /// [WebEnvironment::http_path]. Append the result to the invocation URL after a `?`.
+    /// Fails if the expiry is too far in the future to represent.
+    pub fn sign(
+        &self,
+        path: &str,
+        valid_for: Duration,
+        caller: Option<&str>,
+    ) -> Result<String, SignedUrlError> {
+        let expires = epoch_seconds(SystemTime::now())
+            .checked_add(valid_for.as_secs())
+            .filter(|expires| {
+                SystemTime::UNIX_EPOCH
+                    .checked_add(Duration::from_secs(*expires))
+                    .is_some()
+            })
+            .ok_or(SignedUrlError::ExpiryOutOfRange { valid_for })?;
+        let signature = hex(&self.signature(path, expires, caller));
+        let mut query = format!("{EXPIRES_PARAMETER}={expires}");
+        if let Some(caller) = caller {
+            query.push_str(&format!("&{CALLER_PARAMETER}={}", percent_encode(caller)));
+        }
+        query.push_str(&format!("&{SIGNATURE_PARAMETER}={signature}"));
+        Ok(query)
+    }
+
+    /// Verify the signed URL of the request this Function is handling.
+    pub fn verify_current_request(&self) -> Result<SignedRequest, SignedUrlError> {
+        self.verify(
+            WebEnvironment::load().http_path(),
+            query_parameters(),
+            SystemTime::now(),
+        )
+    }
+
+    /// Verify a signed URL's path and query parameters as of `now`.
+    pub fn verify(
+        &self,
+        path: &str,
+        query: &HashMap<String, String>,
+        now: SystemTime,
+    ) -> Result<SignedRequest, SignedUrlError> {
+        let signature = query
+            .get(SIGNATURE_PARAMETER)
+            .ok_or(SignedUrlError::MissingSignature)?;
+        let expires: u64 = query
+            .get(EXPIRES_PARAMETER)
+            .and_then(|expires| expires.parse().ok())
+            .ok_or(SignedUrlError::InvalidExpiry)?;
+        let caller = query.get(CALLER_PARAMETER).cloned();
+
+        let expected = hex(&self.signature(path, expires, caller.as_deref()));
+        if !constant_time_eq(
+            expected.as_bytes(),
+            signature.to_ascii_lowercase().as_bytes(),
+        ) {
+            return Err(SignedUrlError::InvalidSignature);
+        }
+        if expires <= epoch_seconds(now) {
+            return Err(SignedUrlError::Expired {
+                expires_at_epoch_seconds: expires,
+            });
+        }
+        Ok(SignedRequest {
+            caller,
+            expires: SystemTime::UNIX_EPOCH
+                .checked_add(Duration::from_secs(expires))
+                .ok_or(SignedUrlError::InvalidExpiry)?,
+        })
+    }
+
+    fn signature(&self, path: &str, expires: u64, caller: Option<&str>) -> [u8; 32] {
+        // Mark whether there is a caller, so no caller and an empty caller sign differently.
+        let caller = match caller {
+            Some(caller) => format!("+{caller}"),
+            None => "-".to_string(),
+        };
+        let message = format!("{}\n{expires}\n{caller}", path.trim_matches('/'));
+        self.key.sign(message.as_bytes())
+    }
+}
+
+fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> HashMap<String, String> {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.replace("%40", "@")))
+            .collect()
+    }
+
+    #[test]
+    fn signed_urls_verify_until_they_expire() {
+        let signer = UrlSigner::new("secret");
+        let query = parse(
+            &signer
+                .sign(
+                    "/reports/42",
+                    Duration::from_secs(60),
+                    Some("user@example.com"),
+                )
+                .unwrap(),
+        );
+
+        let signed = signer
+            .verify("reports/42", &query, SystemTime::now())
+            .unwrap();
+        assert_eq!(Some("user@example.com".to_string()), signed.caller);
+
+        let later = SystemTime::now() + Duration::from_secs(120);
+        assert!(matches!(
+            signer.verify("reports/42", &query, later),
+            Err(SignedUrlError::Expired { .. })
+        ));
+    }
+
+    #[test]
+    fn tampered_urls_are_rejected() {
+        let signer = UrlSigner::new("secret");
+        let mut query = parse(
+            &signer
+                .sign("reports/42", Duration::from_secs(60), None)
+                .unwrap(),
+        );
+
+        assert!(matches!(
+            signer.verify("reports/43", &query, SystemTime::now()),
+            Err(SignedUrlError::InvalidSignature)
+        ));
+        assert!(matches!(
+            UrlSigner::new("other").verify("reports/42", &query, SystemTime::now()),
+            Err(SignedUrlError::InvalidSignature)
+        ));
+
+        query.insert(CALLER_PARAMETER.to_string(), "someone".to_string());
+        assert!(matches!(
+            signer.verify("reports/42", &query, SystemTime::now()),
+            Err(SignedUrlError::InvalidSignature)
+        ));
+
+        query.remove(SIGNATURE_PARAMETER);
+        assert!(matches!(
+            signer.verify("reports/42", &query, SystemTime::now()),
+            Err(SignedUrlError::MissingSignature)
+        ));
+    }
+
+    #[test]
+    fn an_empty_caller_is_not_the_same_as_no_caller() {
+        let signer = UrlSigner::new("secret");
+        let mut query = parse(
+            &signer
+                .sign("reports/42", Duration::from_secs(60), None)
+                .unwrap(),
+        );
+
+        query.insert(CALLER_PARAMETER.to_string(), String::new());
+        assert!(matches!(
+            signer.verify("reports/42", &query, SystemTime::now()),
+            Err(SignedUrlError::InvalidSignature)
+        ));
+
+        let signed = signer
+            .verify(
+                "reports/42",
+                &parse(
+                    &signer
+                        .sign("reports/42", Duration::from_secs(60), Some(""))
+                        .unwrap(),
+                ),
+                SystemTime::now(),
+            )
+            .unwrap();
+        assert_eq!(Some(String::new()), signed.caller);
+    }
+
+    #[test]
+    fn expiries_that_overflow_are_rejected() {
+        let signer = UrlSigner::new("secret");
+        assert!(matches!(
+            signer.sign("reports/42", Duration::MAX, None),
+            Err(SignedUrlError::ExpiryOutOfRange { .. })
+        ));
+    }
+}
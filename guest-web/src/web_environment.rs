@@ -20,19 +20,23 @@ static GET_ENVIRONMENT_ONCE: LazyLock<WebEnvironment> = LazyLock::new(|| {
         invocation_id,
     }
 });
-static GET_HEADERS_ONCE: LazyLock<HashMap<String, String>> = LazyLock::new(|| {
+static GET_HEADER_LIST_ONCE: LazyLock<Vec<(String, String)>> = LazyLock::new(|| {
     web_function_support::headers()
         .into_iter()
         .map(|web_function_support::Header { name, value }| (name, value))
         .collect()
 });
-// Yes, this is a hashmap, but query parameters can be repeated. Usually people don't do that though.
-static GET_QUERY_PARAMETERS_ONCE: LazyLock<HashMap<String, String>> = LazyLock::new(|| {
+static GET_HEADERS_ONCE: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| GET_HEADER_LIST_ONCE.iter().cloned().collect());
+static GET_QUERY_PARAMETER_LIST_ONCE: LazyLock<Vec<(String, String)>> = LazyLock::new(|| {
     web_function_support::query_parameters()
         .into_iter()
         .map(|web_function_support::QueryParameter { name, value }| (name, value))
         .collect()
 });
+// Yes, this is a hashmap, but query parameters can be repeated. Usually people don't do that though.
+static GET_QUERY_PARAMETERS_ONCE: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| GET_QUERY_PARAMETER_LIST_ONCE.iter().cloned().collect());
 static GET_TOKEN_METADATA_ONCE: LazyLock<Option<String>> =
     LazyLock::new(web_function_support::token_metadata);
 static GET_HTTP_METHOD_ONCE: LazyLock<String> = LazyLock::new(web_function_support::http_method);
@@ -129,6 +133,18 @@ pub fn query_parameters() -> &'static HashMap<String, String> {
     &GET_QUERY_PARAMETERS_ONCE
 }
 
+/// Every header of the request in order, including repeated ones.
+#[cfg_attr(not(feature = "proxy"), allow(dead_code))]
+pub(crate) fn header_list() -> &'static [(String, String)] {
+    &GET_HEADER_LIST_ONCE
+}
+
+/// Every query parameter of the request in order, including repeated ones.
+#[cfg_attr(not(feature = "proxy"), allow(dead_code))]
+pub(crate) fn query_parameter_list() -> &'static [(String, String)] {
+    &GET_QUERY_PARAMETER_LIST_ONCE
+}
+
 /// Returns the metadata within the caller's token, if present.
 pub fn token_metadata() -> &'static Option<String> {
     &GET_TOKEN_METADATA_ONCE
@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::HarnessError;
+
+pub(crate) fn build_example(
+    workspace: impl AsRef<Path>,
+    package: &str,
+    example: &str,
+) -> Result<PathBuf, HarnessError> {
+    cargo_build(
+        workspace.as_ref(),
+        &["-p", package, "--example", example],
+        example,
+    )
+}
+
+pub(crate) fn build_package(
+    workspace: impl AsRef<Path>,
+    package: &str,
+) -> Result<PathBuf, HarnessError> {
+    cargo_build(workspace.as_ref(), &["-p", package], package)
+}
+
+fn cargo_build(workspace: &Path, args: &[&str], target: &str) -> Result<PathBuf, HarnessError> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = Command::new(cargo)
+        .current_dir(workspace)
+        .args([
+            "build",
+            "--release",
+            "--target",
+            "wasm32-wasip2",
+            "--message-format",
+            "json",
+        ])
+        .args(args)
+        .output()
+        .map_err(|cause| HarnessError::Spawn {
+            command: "cargo",
+            cause,
+        })?;
+    if !output.status.success() {
+        return Err(HarnessError::Build {
+            output: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    wasm_artifact(&String::from_utf8_lossy(&output.stdout), target).ok_or_else(|| {
+        HarnessError::NoArtifact {
+            target: target.to_string(),
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct Message {
+    reason: String,
+    #[serde(default)]
+    target: Option<Target>,
+    #[serde(default)]
+    filenames: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    name: String,
+}
+
+/// Find the `.wasm` file built for `target` in cargo's JSON messages.
+fn wasm_artifact(messages: &str, target: &str) -> Option<PathBuf> {
+    // Cargo names library targets with underscores.
+    let target = target.replace('-', "_");
+    messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Message>(line).ok())
+        .filter(|message| message.reason == "compiler-artifact")
+        .filter(|message| {
+            message
+                .target
+                .as_ref()
+                .is_some_and(|t| t.name.replace('-', "_") == target)
+        })
+        .flat_map(|message| message.filenames)
+        .find(|file| {
+            file.extension()
+                .is_some_and(|extension| extension == "wasm")
+        })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_wasm_for_the_target() {
+        let messages = [
+            r#"{"reason":"compiler-artifact","target":{"name":"serde"},"filenames":["/t/libserde.rlib"]}"#,
+            r#"{"reason":"build-script-executed","package_id":"x"}"#,
+            r#"{"reason":"compiler-artifact","target":{"name":"web-function"},"filenames":["/t/examples/web_function.d","/t/examples/web_function.wasm"]}"#,
+            "not json",
+        ]
+        .join("\n");
+        assert_eq!(
+            Some(PathBuf::from("/t/examples/web_function.wasm")),
+            wasm_artifact(&messages, "web-function")
+        );
+        assert_eq!(None, wasm_artifact(&messages, "serde"));
+    }
+}
@@ -0,0 +1,187 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::HarnessError;
+
+/// An HTTP response.
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Make a request with the `curl` command, sending `body` on stdin so large uploads are not
+/// limited by the length of a command line.
+///
+/// Headers carry the API key, so they are passed in a file only this user can read rather
+/// than on the command line, where any process on the machine can see them.
+pub(crate) fn request(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<Response, HarnessError> {
+    let header_file = (!headers.is_empty())
+        .then(|| HeaderFile::write(headers))
+        .transpose()
+        .map_err(|cause| HarnessError::Spawn {
+            command: "curl",
+            cause,
+        })?;
+    let mut command = Command::new("curl");
+    command.args(["--silent", "--show-error", "--include", "--request", method]);
+    if let Some(header_file) = &header_file {
+        command
+            .arg("--header")
+            .arg(format!("@{}", header_file.path.display()));
+    }
+    if !body.is_empty() {
+        command.args(["--data-binary", "@-"]);
+    }
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|cause| HarnessError::Spawn {
+            command: "curl",
+            cause,
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body).map_err(|cause| HarnessError::Spawn {
+            command: "curl",
+            cause,
+        })?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|cause| HarnessError::Spawn {
+            command: "curl",
+            cause,
+        })?;
+    if !output.status.success() {
+        return Err(HarnessError::Request {
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    parse_response(&output.stdout)
+}
+
+/// Request headers written to a file for `--header @file`, removed when dropped.
+struct HeaderFile {
+    path: PathBuf,
+}
+
+impl HeaderFile {
+    fn write(headers: &[(&str, &str)]) -> std::io::Result<Self> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "momento-harness-headers-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut file = create_private(&path)?;
+        let header_file = Self { path };
+        for (name, value) in headers {
+            writeln!(file, "{name}: {value}")?;
+        }
+        Ok(header_file)
+    }
+}
+
+impl Drop for HeaderFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Create a new file that only the current user can read.
+fn create_private(path: &std::path::Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+/// Parse curl's `--include` output: a status line and headers, a blank line, then the body.
+fn parse_response(output: &[u8]) -> Result<Response, HarnessError> {
+    let mut rest = output;
+    loop {
+        let Some(split) = rest.windows(4).position(|window| window == b"\r\n\r\n") else {
+            return Err(HarnessError::Request {
+                message: "response had no headers".to_string(),
+            });
+        };
+        let head = String::from_utf8_lossy(&rest[..split]);
+        let body = &rest[split + 4..];
+        let mut lines = head.split("\r\n");
+        let status = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| HarnessError::Request {
+                message: format!("response had no status line: {head}"),
+            })?;
+        // Interim responses like `100 Continue` come before the real one.
+        if (100..200).contains(&status) {
+            rest = body;
+            continue;
+        }
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+        return Ok(Response {
+            status,
+            headers,
+            body: body.to_vec(),
+        });
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_included_headers() {
+        let output = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/2 200 \r\nContent-Type: text/plain\r\nx-a: b: c\r\n\r\nhello\r\n\r\nworld";
+        let response = parse_response(output).unwrap();
+        assert_eq!(200, response.status);
+        assert_eq!(
+            vec![
+                ("content-type".to_string(), "text/plain".to_string()),
+                ("x-a".to_string(), "b: c".to_string()),
+            ],
+            response.headers
+        );
+        assert_eq!(b"hello\r\n\r\nworld".as_slice(), response.body);
+
+        assert!(parse_response(b"garbage").is_err());
+    }
+
+    #[test]
+    fn headers_are_written_to_a_private_file() {
+        let file = HeaderFile::write(&[("authorization", "secret"), ("x-a", "b")]).unwrap();
+        assert_eq!(
+            "authorization: secret\nx-a: b\n",
+            fs::read_to_string(&file.path).unwrap()
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file.path).unwrap().permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+        let path = file.path.clone();
+        drop(file);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use serde::de::DeserializeOwned;
+
+use crate::{HarnessError, LogCollector, build, curl};
+
+/// A cache in a Momento cell to deploy Functions to.
+#[derive(Debug, Clone)]
+pub struct Harness {
+    pub(crate) cell_hostname: String,
+    pub(crate) api_key: String,
+    pub(crate) cache_name: String,
+    workspace: PathBuf,
+}
+
+impl Harness {
+    /// Deploy to `cache_name` in the cell at `cell_hostname`, like `cell-4-us-west-2-1.prod.a.momentohq.com`.
+    pub fn new(
+        cell_hostname: impl Into<String>,
+        api_key: impl Into<String>,
+        cache_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            cell_hostname: cell_hostname.into(),
+            api_key: api_key.into(),
+            cache_name: cache_name.into(),
+            workspace: PathBuf::from("."),
+        }
+    }
+
+    /// Deploy to the cache named by the `MOMENTO_CELL_HOSTNAME`, `MOMENTO_API_KEY`, and
+    /// `MOMENTO_CACHE_NAME` environment variables.
+    pub fn from_env() -> Result<Self, HarnessError> {
+        let names = [
+            "MOMENTO_CELL_HOSTNAME",
+            "MOMENTO_API_KEY",
+            "MOMENTO_CACHE_NAME",
+        ];
+        let values: Vec<Option<String>> = names
+            .iter()
+            .map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+            .collect();
+        match values.as_slice() {
+            [Some(cell_hostname), Some(api_key), Some(cache_name)] => {
+                Ok(Self::new(cell_hostname, api_key, cache_name))
+            }
+            _ => Err(HarnessError::Missing {
+                variables: names
+                    .iter()
+                    .zip(&values)
+                    .filter(|(_, value)| value.is_none())
+                    .map(|(name, _)| name.to_string())
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Run `cargo` in this directory instead of the current one.
+    pub fn workspace(mut self, workspace: impl Into<PathBuf>) -> Self {
+        self.workspace = workspace.into();
+        self
+    }
+
+    /// Build an example of `package` to wasm in release mode, returning the `.wasm` file.
+    ///
+    /// The example must have `crate-type = ["cdylib"]`, like the examples in
+    /// `momento-functions`.
+    pub fn build_example(&self, package: &str, example: &str) -> Result<PathBuf, HarnessError> {
+        build::build_example(&self.workspace, package, example)
+    }
+
+    /// Build `package` to wasm in release mode, returning the `.wasm` file. Use this for
+    /// Functions that are their own crate, like the v2 examples.
+    pub fn build_package(&self, package: &str) -> Result<PathBuf, HarnessError> {
+        build::build_package(&self.workspace, package)
+    }
+
+    /// Upload a built Function as `name`, replacing any Function with that name in the cache.
+    pub fn deploy(
+        &self,
+        name: impl Into<String>,
+        wasm: impl AsRef<Path>,
+    ) -> Result<DeployedFunction, HarnessError> {
+        let name = name.into();
+        let wasm = std::fs::read(wasm.as_ref()).map_err(|cause| HarnessError::ReadWasm {
+            path: wasm.as_ref().to_path_buf(),
+            cause,
+        })?;
+        let body = serde_json::json!({
+            "inline_wasm": base64::engine::general_purpose::STANDARD.encode(wasm),
+        });
+        let response = curl::request(
+            "PUT",
+            &format!(
+                "https://api.cache.{}/functions/manage/{}/{name}",
+                self.cell_hostname, self.cache_name
+            ),
+            &[
+                ("authorization", &self.api_key),
+                ("content-type", "application/json"),
+            ],
+            body.to_string().as_bytes(),
+        )?;
+        if !(200..300).contains(&response.status) {
+            return Err(HarnessError::Rejected {
+                status: response.status,
+                body: String::from_utf8_lossy(&response.body).into_owned(),
+            });
+        }
+        Ok(DeployedFunction {
+            harness: self.clone(),
+            name,
+        })
+    }
+
+    /// Start collecting messages published to `topic` in the cache, like the logs of a
+    /// Function that logs to `LogDestination::topic`. Create the collector before invoking,
+    /// so it sees everything the invocation logs.
+    pub fn logs(&self, topic: impl Into<String>) -> LogCollector {
+        LogCollector::new(self.clone(), topic.into())
+    }
+}
+
+/// A Function that has been deployed to a cache.
+#[derive(Debug, Clone)]
+pub struct DeployedFunction {
+    harness: Harness,
+    name: String,
+}
+
+impl DeployedFunction {
+    /// The Function's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Invoke the Function with `body`.
+    pub fn invoke(&self, body: impl AsRef<[u8]>) -> Result<Invocation, HarnessError> {
+        self.invoke_with_headers(body, &[])
+    }
+
+    /// Invoke the Function with `body` and extra request headers.
+    pub fn invoke_with_headers(
+        &self,
+        body: impl AsRef<[u8]>,
+        headers: &[(&str, &str)],
+    ) -> Result<Invocation, HarnessError> {
+        let mut all_headers = vec![("authorization", self.harness.api_key.as_str())];
+        all_headers.extend_from_slice(headers);
+        let response = curl::request(
+            "POST",
+            &format!(
+                "https://api.cache.{}/functions/{}/{}",
+                self.harness.cell_hostname, self.harness.cache_name, self.name
+            ),
+            &all_headers,
+            body.as_ref(),
+        )?;
+        Ok(Invocation {
+            status: response.status,
+            headers: response.headers,
+            body: response.body,
+        })
+    }
+}
+
+/// A Function's response to an invocation.
+///
+/// The `assert_` methods panic with the response body when they fail, so a failing test
+/// shows what the Function said.
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    /// The HTTP status.
+    pub status: u16,
+    /// The response headers, with lower-cased names.
+    pub headers: Vec<(String, String)>,
+    /// The response body.
+    pub body: Vec<u8>,
+}
+
+impl Invocation {
+    /// The body as text, replacing invalid UTF-8.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// The body decoded as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, HarnessError> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// The first value of a header, by case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Panic unless the status is `status`.
+    #[track_caller]
+    pub fn assert_status(&self, status: u16) -> &Self {
+        assert_eq!(
+            status,
+            self.status,
+            "unexpected status; body: {}",
+            self.text()
+        );
+        self
+    }
+
+    /// Panic unless the body contains `text`.
+    #[track_caller]
+    pub fn assert_body_contains(&self, text: &str) -> &Self {
+        assert!(
+            self.text().contains(text),
+            "body does not contain {text:?}: {}",
+            self.text()
+        );
+        self
+    }
+}
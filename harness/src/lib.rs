@@ -0,0 +1,117 @@
+//! End-to-end tests for Momento Functions against a deployed cell.
+//!
+//! This crate runs on your machine or in CI, not inside a Function. It builds a Function to
+//! `wasm32-wasip2` with `cargo`, uploads it to a cache with the Momento API, invokes it, and
+//! collects what it logs to a topic, so a test can assert on real responses instead of
+//! following the `curl` instructions in an example's docs. HTTP calls are made with the
+//! `curl` command, so both `cargo` and `curl` must be on the `PATH`.
+//!
+//! [Harness::from_env] reads `MOMENTO_API_KEY`, `MOMENTO_CELL_HOSTNAME`, and
+//! `MOMENTO_CACHE_NAME`. When they are not set it returns [HarnessError::Missing], so tests
+//! can skip themselves on machines without credentials.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_harness::{Harness, HarnessError};
+//!
+//! #[test]
+//! fn greeter_says_hello() {
+//!     let harness = match Harness::from_env() {
+//!         Ok(harness) => harness,
+//!         Err(HarnessError::Missing { variables }) => {
+//!             eprintln!("skipping: {variables:?} not set");
+//!             return;
+//!         }
+//!         Err(e) => panic!("{e}"),
+//!     };
+//!     let wasm = harness
+//!         .build_example("momento-functions", "web-function-json-greeter")
+//!         .expect("build");
+//!     let function = harness.deploy("greeter-ci", &wasm).expect("deploy");
+//!     let logs = harness.logs("greeter-ci");
+//!
+//!     function
+//!         .invoke(r#"{"name": "CI"}"#)
+//!         .expect("invoke")
+//!         .assert_status(200)
+//!         .assert_body_contains("Hello, CI");
+//!     let lines = logs
+//!         .wait_for(|line| line.contains("greet"), std::time::Duration::from_secs(10))
+//!         .expect("logs");
+//!     assert!(!lines.is_empty());
+//! }
+//! ```
+
+mod build;
+mod curl;
+mod deploy;
+mod logs;
+
+pub use deploy::{DeployedFunction, Harness, Invocation};
+pub use logs::LogCollector;
+
+/// An error occurred while building, deploying, or invoking a Function.
+#[derive(Debug, thiserror::Error)]
+pub enum HarnessError {
+    /// Required environment variables were not set.
+    #[error("Missing environment variables: {}", variables.join(", "))]
+    Missing {
+        /// The names of every missing variable.
+        variables: Vec<String>,
+    },
+    /// A command could not be run.
+    #[error("Failed to run {command}: {cause}")]
+    Spawn {
+        /// The command that could not be run, like `cargo`.
+        command: &'static str,
+        /// The underlying error.
+        cause: std::io::Error,
+    },
+    /// `cargo build` failed.
+    #[error("Build failed:\n{output}")]
+    Build {
+        /// What cargo printed.
+        output: String,
+    },
+    /// `cargo build` succeeded but produced no `.wasm` file for the target.
+    #[error("No wasm artifact was built for {target}")]
+    NoArtifact {
+        /// The package or example that was built.
+        target: String,
+    },
+    /// A built Function could not be read.
+    #[error("Failed to read {}: {cause}", path.display())]
+    ReadWasm {
+        /// The path of the `.wasm` file.
+        path: std::path::PathBuf,
+        /// The underlying error.
+        cause: std::io::Error,
+    },
+    /// `curl` could not complete the request.
+    #[error("Request failed: {message}")]
+    Request {
+        /// What curl printed.
+        message: String,
+    },
+    /// The Momento API rejected a request.
+    #[error("Momento returned status {status}: {body}")]
+    Rejected {
+        /// The HTTP status of the response.
+        status: u16,
+        /// The body of the response.
+        body: String,
+    },
+    /// A response could not be decoded.
+    #[error("Failed to decode response: {cause}")]
+    Decode {
+        /// The underlying decoding error.
+        #[from]
+        cause: serde_json::Error,
+    },
+    /// Nothing matching was logged in time.
+    #[error("Timed out waiting for logs; saw {} lines", lines.len())]
+    Timeout {
+        /// Every line received before the timeout.
+        lines: Vec<String>,
+    },
+}
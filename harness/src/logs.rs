@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::{Harness, HarnessError, curl};
+
+/// Collects the messages published to a topic.
+///
+/// Each poll long-polls the topic's HTTP subscribe endpoint and picks up where the last
+/// one left off.
+#[derive(Debug)]
+pub struct LogCollector {
+    harness: Harness,
+    topic: String,
+    next_sequence_number: Option<u64>,
+    lines: Vec<String>,
+}
+
+impl LogCollector {
+    pub(crate) fn new(harness: Harness, topic: String) -> Self {
+        Self {
+            harness,
+            topic,
+            next_sequence_number: None,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Every message received so far.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Wait once for new messages, returning them. Returns no messages if none arrived
+    /// before the server ended the poll.
+    pub fn poll(&mut self) -> Result<Vec<String>, HarnessError> {
+        let mut url = format!(
+            "https://api.cache.{}/topics/{}/{}",
+            self.harness.cell_hostname, self.harness.cache_name, self.topic
+        );
+        if let Some(sequence_number) = self.next_sequence_number {
+            url.push_str(&format!("?sequence_number={sequence_number}"));
+        }
+        let response = curl::request(
+            "GET",
+            &url,
+            &[("authorization", &self.harness.api_key)],
+            &[],
+        )?;
+        if response.status != 200 {
+            return Err(HarnessError::Rejected {
+                status: response.status,
+                body: String::from_utf8_lossy(&response.body).into_owned(),
+            });
+        }
+        let (lines, next) = parse_items(&response.body)?;
+        if next.is_some() {
+            self.next_sequence_number = next;
+        }
+        self.lines.extend(lines.iter().cloned());
+        Ok(lines)
+    }
+
+    /// Poll until a message matches `predicate` or `timeout` passes, returning every
+    /// message received so far.
+    pub fn wait_for(
+        mut self,
+        predicate: impl Fn(&str) -> bool,
+        timeout: Duration,
+    ) -> Result<Vec<String>, HarnessError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.lines.iter().any(|line| predicate(line)) {
+                return Ok(self.lines);
+            }
+            if deadline <= Instant::now() {
+                return Err(HarnessError::Timeout { lines: self.lines });
+            }
+            self.poll()?;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Items {
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    item: Option<TopicItem>,
+}
+
+#[derive(Deserialize)]
+struct TopicItem {
+    topic_sequence_number: u64,
+    value: TopicValue,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TopicValue {
+    Text(String),
+    Binary(String),
+}
+
+/// The messages in a subscribe response, and the sequence number to poll from next.
+fn parse_items(body: &[u8]) -> Result<(Vec<String>, Option<u64>), HarnessError> {
+    let items: Items = serde_json::from_slice(body)?;
+    let mut next = None;
+    let lines = items
+        .items
+        .into_iter()
+        // Discontinuities carry no message; they only mean some were missed.
+        .filter_map(|item| item.item)
+        .map(|item| {
+            next = Some(item.topic_sequence_number + 1);
+            match item.value {
+                TopicValue::Text(text) | TopicValue::Binary(text) => text,
+            }
+        })
+        .collect();
+    Ok((lines, next))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_messages_and_the_next_sequence_number() {
+        let body = br#"{"items": [
+            {"item": {"topic_sequence_number": 4, "value": {"text": "first"}}},
+            {"discontinuity": {"last_topic_sequence": 4, "new_topic_sequence": 6}},
+            {"item": {"topic_sequence_number": 6, "value": {"text": "second"}}}
+        ]}"#;
+        let (lines, next) = parse_items(body).unwrap();
+        assert_eq!(vec!["first", "second"], lines);
+        assert_eq!(Some(7), next);
+
+        assert_eq!((vec![], None), parse_items(b"{}").unwrap());
+    }
+}
@@ -0,0 +1,116 @@
+//! HMAC-SHA256 for signed URLs, session tokens, and webhook signatures.
+//!
+//! Signing helpers in the guest and host crates all sign with a shared secret, print the
+//! signature as hex, and compare what they are given against what they expect. This crate
+//! does those three things once, so every helper checks signatures the same way.
+
+use std::fmt::Write;
+
+use sha2::{Digest, Sha256};
+
+/// The block size of SHA-256, which HMAC pads keys to.
+const BLOCK_SIZE: usize = 64;
+
+/// A shared secret, prepared for signing with HMAC-SHA256. Its bytes are not printed by
+/// [Debug].
+#[derive(Clone)]
+pub struct HmacKey([u8; BLOCK_SIZE]);
+
+impl HmacKey {
+    /// Prepare `secret` for signing. Secrets longer than a block are hashed first, as HMAC
+    /// requires.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        let secret = secret.as_ref();
+        let mut key = [0; BLOCK_SIZE];
+        if BLOCK_SIZE < secret.len() {
+            key[..32].copy_from_slice(&Sha256::digest(secret));
+        } else {
+            key[..secret.len()].copy_from_slice(secret);
+        }
+        Self(key)
+    }
+
+    /// The HMAC-SHA256 of `message`.
+    pub fn sign(&self, message: &[u8]) -> [u8; 32] {
+        let inner = Sha256::new()
+            .chain_update(self.0.map(|byte| byte ^ 0x36))
+            .chain_update(message)
+            .finalize();
+        Sha256::new()
+            .chain_update(self.0.map(|byte| byte ^ 0x5c))
+            .chain_update(inner)
+            .finalize()
+            .into()
+    }
+}
+
+impl std::fmt::Debug for HmacKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HmacKey([REDACTED])")
+    }
+}
+
+/// `bytes` as lowercase hex.
+pub fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
+
+/// The bytes of a hex string, in either case. `None` if it is not hex.
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Whether `a` and `b` are equal, taking the same time wherever they first differ, for
+/// comparing secrets and signatures without leaking how much of a guess was right.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_rfc_4231() {
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            hex(&HmacKey::new("Jefe").sign(b"what do ya want for nothing?"))
+        );
+        // Test case 6, with a key longer than a block.
+        assert_eq!(
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            hex(&HmacKey::new([0xaa; 131])
+                .sign(b"Test Using Larger Than Block-Size Key - Hash Key First"))
+        );
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(Some(vec![0, 159, 255]), from_hex(&hex(&[0, 159, 255])));
+        assert_eq!(Some(vec![171]), from_hex("AB"));
+        assert_eq!(None, from_hex("abc"));
+        assert_eq!(None, from_hex("zz"));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert_eq!(
+            "HmacKey([REDACTED])",
+            format!("{:?}", HmacKey::new("secret"))
+        );
+    }
+}
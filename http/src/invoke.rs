@@ -1,7 +1,21 @@
 use momento_functions_bytes::Data;
+use momento_functions_bytes::abi::{self, PackageFunction, UnsupportedByHost};
 use thiserror::Error;
 
-use crate::{request::Request, wit::momento::http::http};
+use crate::{
+    request::{HttpVersion, Request, RequestOptions, SigV4aAuthorization},
+    wit::momento::http::http,
+};
+
+/// `http.invoke-sigv4a`
+const INVOKE_SIGV4A: PackageFunction =
+    PackageFunction::new("momento:http", "http.invoke-sigv4a", 1, 1);
+/// `http.invoke-with-options`
+const INVOKE_WITH_OPTIONS: PackageFunction =
+    PackageFunction::new("momento:http", "http.invoke-with-options", 1, 1);
+/// `http.invoke-with-connection-info`
+const INVOKE_WITH_CONNECTION_INFO: PackageFunction =
+    PackageFunction::new("momento:http", "http.invoke-with-connection-info", 1, 1);
 
 /// An error returned by an HTTP request.
 #[derive(Debug, Error)]
@@ -21,6 +35,9 @@ pub enum HttpError {
     /// A provided header value was not valid.
     #[error("invalid header value '{value}': {error}")]
     InvalidHeaderValue { value: String, error: String },
+    /// The host is too old for this kind of request.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
 }
 
 impl From<http::Error> for HttpError {
@@ -58,6 +75,16 @@ pub struct Response {
     pub body: Data,
 }
 
+/// How a [`Response`] was received, from [`invoke_with_connection_info`], for diagnosing connection reuse and protocol
+/// negotiation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// The HTTP version the response was received over.
+    pub version: HttpVersion,
+    /// True when the request was sent on a pooled connection opened by an earlier request.
+    pub reused: bool,
+}
+
 impl From<http::Response> for Response {
     fn from(r: http::Response) -> Self {
         Response {
@@ -68,6 +95,15 @@ impl From<http::Response> for Response {
     }
 }
 
+impl From<http::ConnectionInfo> for ConnectionInfo {
+    fn from(connection: http::ConnectionInfo) -> Self {
+        ConnectionInfo {
+            version: connection.version.into(),
+            reused: connection.reused,
+        }
+    }
+}
+
 /// Send an HTTP request.
 ///
 /// # Arguments
@@ -103,3 +139,99 @@ pub fn invoke(request: Request) -> Result<Response, HttpError> {
         .map(Into::into)
         .map_err(Into::into)
 }
+
+/// Send an HTTP request signed with AWS SigV4A, which is valid in several regions.
+///
+/// S3 multi-region access points only accept SigV4A signatures. The request's own
+/// [`Authorization`](crate::Authorization) is ignored.
+///
+/// # Examples
+/// ________
+/// Get an object through an S3 multi-region access point:
+/// ```rust,no_run
+/// use momento_functions_http::{IamRoleSigV4a, Request, SigV4aAuthorization, invoke_sigv4a};
+///
+/// let request = Request::new(
+///     "https://mfzwi23gnjvgw.mrap.accesspoint.s3-global.amazonaws.com/reports/latest.json",
+///     "GET",
+/// );
+/// let authorization = SigV4aAuthorization::Federated(IamRoleSigV4a {
+///     role_arn: "arn:aws:iam::123456789012:role/reports-reader".to_string(),
+///     region_set: vec!["*".to_string()],
+///     service: "s3".to_string(),
+/// });
+/// match invoke_sigv4a(request, authorization) {
+///     Ok(response) => println!("status: {}", response.status),
+///     Err(e) => eprintln!("request failed: {e}"),
+/// }
+/// ```
+pub fn invoke_sigv4a(
+    request: Request,
+    authorization: SigV4aAuthorization,
+) -> Result<Response, HttpError> {
+    abi::require(INVOKE_SIGV4A)?;
+    http::invoke_sigv4a(request.into(), &authorization.into())
+        .map(Into::into)
+        .map_err(Into::into)
+}
+
+/// Send an HTTP request with options, like hedging.
+///
+/// # Arguments
+/// * `request` - The request to send.
+/// * `options` - How to send it.
+///
+/// # Examples
+/// ________
+/// Hedge a GET to a third-party API with unpredictable tail latency:
+/// ```rust,no_run
+/// use momento_functions_http::{invoke_with_options, Request, RequestOptions};
+/// use std::time::Duration;
+///
+/// match invoke_with_options(
+///     Request::new("https://example.com/api", "GET"),
+///     RequestOptions::new().hedge(Duration::from_millis(150)),
+/// ) {
+///     Ok(response) => println!("status: {}", response.status),
+///     Err(e) => eprintln!("request failed: {e}"),
+/// }
+/// ```
+pub fn invoke_with_options(
+    request: Request,
+    options: RequestOptions,
+) -> Result<Response, HttpError> {
+    abi::require(INVOKE_WITH_OPTIONS)?;
+    let options = options.for_request(&request);
+    http::invoke_with_options(request.into(), options)
+        .map(Into::into)
+        .map_err(Into::into)
+}
+
+/// Send an HTTP request with options, also returning how its response was received.
+///
+/// # Examples
+/// ________
+/// Check that requests to an upstream reuse pooled HTTP/2 connections:
+/// ```rust,no_run
+/// use momento_functions_http::{invoke_with_connection_info, HttpVersion, Request, RequestOptions};
+///
+/// match invoke_with_connection_info(
+///     Request::new("https://example.com/api", "GET"),
+///     RequestOptions::new().http_version(HttpVersion::Http2),
+/// ) {
+///     Ok((response, connection)) => {
+///         println!("status {} over {:?}, reused: {}", response.status, connection.version, connection.reused)
+///     }
+///     Err(e) => eprintln!("request failed: {e}"),
+/// }
+/// ```
+pub fn invoke_with_connection_info(
+    request: Request,
+    options: RequestOptions,
+) -> Result<(Response, ConnectionInfo), HttpError> {
+    abi::require(INVOKE_WITH_CONNECTION_INFO)?;
+    let options = options.for_request(&request);
+    http::invoke_with_connection_info(request.into(), options)
+        .map(|response| (response.response.into(), response.connection.into()))
+        .map_err(Into::into)
+}
@@ -2,8 +2,10 @@
 //!
 //! This crate provides a typed API for sending HTTP requests, with support
 //! for custom headers, request bodies, and AWS authorization strategies.
+//! Use [`start`] or [`join_all`] to send independent requests concurrently.
 
 mod invoke;
+mod pending;
 mod request;
 pub mod sse;
 
@@ -11,6 +13,14 @@ pub mod sse;
 #[doc(hidden)]
 pub mod wit;
 
-pub use invoke::{HttpError, Response, invoke};
+pub use invoke::{
+    ConnectionInfo, HttpError, Response, invoke, invoke_sigv4a, invoke_with_connection_info,
+    invoke_with_options,
+};
 pub use momento_functions_bytes::Data;
-pub use request::{Authorization, AwsSigV4Secret, IamRole, Request};
+pub use momento_functions_bytes::abi::UnsupportedByHost;
+pub use pending::{PendingResponse, join_all, start, wait_any};
+pub use request::{
+    Authorization, AwsSigV4Secret, AwsSigV4aSecret, HttpVersion, IamRole, IamRoleSigV4a, Request,
+    RequestOptions, SigV4aAuthorization,
+};
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use momento_functions_bytes::abi::{self, PackageFunction};
+
+use crate::{
+    invoke::{HttpError, Response, invoke},
+    request::Request,
+    wit::momento::http::http,
+};
+
+/// `http.start` and `http.wait-any`
+const START: PackageFunction = PackageFunction::new("momento:http", "http.start", 1, 1);
+
+/// A request that has been sent, whose response may not have arrived yet.
+///
+/// Create one with [`start`]. Requests you start are in flight at the same time, so
+/// independent calls cost as much time as the slowest one rather than all of them added up.
+pub struct PendingResponse {
+    inner: http::PendingResponse,
+}
+
+impl PendingResponse {
+    /// True when the response has arrived, so [`PendingResponse::wait`] will not block.
+    pub fn is_ready(&self) -> bool {
+        self.inner.ready()
+    }
+
+    /// Wait for the response.
+    pub fn wait(self) -> Result<Response, HttpError> {
+        self.inner.wait().map(Into::into).map_err(Into::into)
+    }
+}
+
+/// Send an HTTP request without waiting for its response.
+///
+/// Fails with [`HttpError::Unsupported`] on hosts that are too old to send requests
+/// concurrently.
+///
+/// # Examples
+/// ________
+/// Look up two independent things at once:
+/// ```rust,no_run
+/// use momento_functions_http::{start, Request};
+///
+/// # fn f() -> Result<(), momento_functions_http::HttpError> {
+/// let profile = start(Request::new("https://example.com/profile/42", "GET"))?;
+/// let orders = start(Request::new("https://example.com/orders?user=42", "GET"))?;
+///
+/// match (profile.wait(), orders.wait()) {
+///     (Ok(profile), Ok(orders)) => {
+///         println!("statuses: {} {}", profile.status, orders.status);
+///     }
+///     (Err(e), _) | (_, Err(e)) => eprintln!("request failed: {e}"),
+/// }
+/// # Ok(()) }
+/// ```
+pub fn start(request: Request) -> Result<PendingResponse, HttpError> {
+    abi::require(START)?;
+    Ok(PendingResponse {
+        inner: http::start(request.into()),
+    })
+}
+
+/// Send several HTTP requests concurrently and wait for all of their responses.
+///
+/// The results are in the same order as the requests. On hosts that are too old to send
+/// requests concurrently, they are sent one after another instead.
+///
+/// # Examples
+/// ________
+/// ```rust,no_run
+/// use momento_functions_http::{join_all, Request};
+///
+/// let responses = join_all(
+///     ["a", "b", "c"].map(|id| Request::new(format!("https://example.com/items/{id}"), "GET")),
+/// );
+/// for response in responses {
+///     match response {
+///         Ok(response) => println!("status: {}", response.status),
+///         Err(e) => eprintln!("request failed: {e}"),
+///     }
+/// }
+/// ```
+pub fn join_all(requests: impl IntoIterator<Item = Request>) -> Vec<Result<Response, HttpError>> {
+    let requests = requests.into_iter();
+    if abi::require(START).is_err() {
+        return requests.map(invoke).collect();
+    }
+    let pending: Vec<PendingResponse> = requests
+        .map(|request| PendingResponse {
+            inner: http::start(request.into()),
+        })
+        .collect();
+    pending.into_iter().map(PendingResponse::wait).collect()
+}
+
+/// Wait until any of the pending responses has arrived, returning its index.
+///
+/// Returns `None` if `timeout` elapses first, or if `pending` is empty. Without a
+/// timeout, this waits as long as it takes.
+pub fn wait_any(pending: &[PendingResponse], timeout: Option<Duration>) -> Option<usize> {
+    // A pending response only exists if the host provides `start`, and `wait-any` with it, so
+    // only an empty list needs to be kept from a host that may lack them.
+    if pending.is_empty() {
+        return None;
+    }
+    let pending: Vec<&http::PendingResponse> = pending.iter().map(|p| &p.inner).collect();
+    let timeout_milliseconds =
+        timeout.map(|timeout| u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+    http::wait_any(&pending, timeout_milliseconds).map(|index| index as usize)
+}
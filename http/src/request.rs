@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use momento_functions_bytes::{
     Data,
     encoding::{Encode, Json},
@@ -45,6 +47,72 @@ impl From<IamRole> for http::IamRole {
     }
 }
 
+/// SigV4A credentials for signing an AWS request that is valid in several regions.
+///
+/// S3 multi-region access points only accept SigV4A signatures.
+pub struct AwsSigV4aSecret {
+    /// AWS access key ID.
+    pub access_key_id: String,
+    /// AWS secret access key.
+    pub secret_access_key: String,
+    /// The regions the signature is valid in, like `us-east-1` and `us-west-2`, or `*` for
+    /// all regions.
+    pub region_set: Vec<String>,
+    /// AWS service name.
+    pub service: String,
+}
+
+impl From<AwsSigV4aSecret> for http::AwsSigv4aSecret {
+    fn from(s: AwsSigV4aSecret) -> Self {
+        http::AwsSigv4aSecret {
+            access_key_id: s.access_key_id,
+            secret_access_key: s.secret_access_key,
+            region_set: s.region_set,
+            service: s.service,
+        }
+    }
+}
+
+/// An IAM role for Momento to federate into, signing the request with SigV4A.
+pub struct IamRoleSigV4a {
+    /// The ARN of the IAM role.
+    pub role_arn: String,
+    /// The regions the signature is valid in, like `us-east-1` and `us-west-2`, or `*` for
+    /// all regions.
+    pub region_set: Vec<String>,
+    /// The AWS service name.
+    pub service: String,
+}
+
+impl From<IamRoleSigV4a> for http::IamRoleSigv4a {
+    fn from(r: IamRoleSigV4a) -> Self {
+        http::IamRoleSigv4a {
+            role_arn: r.role_arn,
+            region_set: r.region_set,
+            service: r.service,
+        }
+    }
+}
+
+/// How to sign a request with SigV4A, for [`invoke_sigv4a`](crate::invoke_sigv4a).
+pub enum SigV4aAuthorization {
+    /// Sign the request using explicit credentials.
+    AwsSigV4aSecret(AwsSigV4aSecret),
+    /// Federate into an IAM role for the request.
+    Federated(IamRoleSigV4a),
+}
+
+impl From<SigV4aAuthorization> for http::Sigv4aAuthorization {
+    fn from(authorization: SigV4aAuthorization) -> Self {
+        match authorization {
+            SigV4aAuthorization::AwsSigV4aSecret(s) => {
+                http::Sigv4aAuthorization::AwsSigv4aSecret(s.into())
+            }
+            SigV4aAuthorization::Federated(r) => http::Sigv4aAuthorization::Federated(r.into()),
+        }
+    }
+}
+
 /// Authorization strategy for an HTTP request.
 pub enum Authorization {
     /// No special authorization. You can still include an `Authorization` header manually.
@@ -187,3 +255,132 @@ impl From<Request> for http::Request {
         }
     }
 }
+
+/// A version of HTTP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpVersion {
+    /// HTTP/1.1.
+    Http1,
+    /// HTTP/2.
+    Http2,
+}
+
+impl From<HttpVersion> for http::HttpVersion {
+    fn from(version: HttpVersion) -> Self {
+        match version {
+            HttpVersion::Http1 => http::HttpVersion::Http1,
+            HttpVersion::Http2 => http::HttpVersion::Http2,
+        }
+    }
+}
+
+impl From<http::HttpVersion> for HttpVersion {
+    fn from(version: http::HttpVersion) -> Self {
+        match version {
+            http::HttpVersion::Http1 => HttpVersion::Http1,
+            http::HttpVersion::Http2 => HttpVersion::Http2,
+        }
+    }
+}
+
+/// Options for how an HTTP request is sent, used with [`invoke_with_options`](crate::invoke_with_options).
+///
+/// The connection options are hints for the pool of connections the host keeps to the
+/// request's host, which every request to that host shares. The host may clamp them.
+///
+/// # Examples
+/// ________
+/// ```rust,no_run
+/// use momento_functions_http::RequestOptions;
+/// use std::time::Duration;
+///
+/// let options = RequestOptions::new().hedge(Duration::from_millis(200));
+/// ```
+/// ________
+/// Multiplex many requests to one upstream over a few HTTP/2 connections:
+/// ```rust,no_run
+/// use momento_functions_http::{HttpVersion, RequestOptions};
+/// use std::time::Duration;
+///
+/// let options = RequestOptions::new()
+///     .http_version(HttpVersion::Http2)
+///     .max_connections_per_host(4)
+///     .pool_idle_timeout(Duration::from_secs(90));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct RequestOptions {
+    hedge_after: Option<Duration>,
+    version: Option<HttpVersion>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<u32>,
+    max_connections_per_host: Option<u32>,
+}
+
+impl RequestOptions {
+    /// Create options that send the request normally.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hedge the request: if there is no response after `delay`, send a second, identical
+    /// request, and use whichever response arrives first.
+    ///
+    /// This trims tail latency from slow upstreams at the cost of some extra requests.
+    /// A delay around the upstream's 95th percentile latency is a good place to start.
+    ///
+    /// Hedging is only applied to `GET` and `HEAD` requests, since sending a request
+    /// twice is only safe when it is idempotent. Other requests are sent once.
+    pub fn hedge(mut self, delay: Duration) -> Self {
+        self.hedge_after = Some(delay);
+        self
+    }
+
+    /// Only use this HTTP version.
+    ///
+    /// By default, HTTP/2 is used when the server offers it over TLS, and HTTP/1.1
+    /// otherwise. HTTP/2 to an `http://` URL is sent with prior knowledge, so the server
+    /// must support it.
+    pub fn http_version(mut self, version: HttpVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Keep idle connections to the request's host open for `timeout`, so later requests
+    /// can reuse them instead of opening new ones.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep at most `max` idle connections to the request's host open.
+    pub fn pool_max_idle_per_host(mut self, max: u32) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Open at most `max` connections to the request's host at once. Further requests wait
+    /// for a free connection.
+    ///
+    /// Over HTTP/2, each connection carries many requests at once, so a few are usually
+    /// enough.
+    pub fn max_connections_per_host(mut self, max: u32) -> Self {
+        self.max_connections_per_host = Some(max);
+        self
+    }
+
+    pub(crate) fn for_request(&self, request: &Request) -> http::RequestOptions {
+        let idempotent =
+            request.verb.eq_ignore_ascii_case("GET") || request.verb.eq_ignore_ascii_case("HEAD");
+        http::RequestOptions {
+            hedge_after_milliseconds: self.hedge_after.filter(|_| idempotent).map(milliseconds),
+            version: self.version.map(Into::into),
+            pool_idle_timeout_milliseconds: self.pool_idle_timeout.map(milliseconds),
+            pool_max_idle_per_host: self.pool_max_idle_per_host,
+            max_connections_per_host: self.max_connections_per_host,
+        }
+    }
+}
+
+fn milliseconds(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
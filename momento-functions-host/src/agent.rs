@@ -0,0 +1,316 @@
+//! Running a model's tool calls until it answers
+//!
+//! [Agent] sends a [ChatRequest] to a [Chat] model, calls the Rust functions registered for
+//! the tools it asks for, sends their results back, and repeats until the model replies
+//! without calling a tool. Tools are plain closures, so they can read the cache, query
+//! DynamoDB, or call out over HTTP.
+//!
+//! A tool that fails, or that the model calls with bad arguments, does not end the run: the
+//! error goes back to the model as the tool's result, so it can try again or answer without
+//! it. [Agent::max_iterations] bounds how many times the model is asked.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::agent::{Agent, AgentError};
+//! use momento_functions_host::cache;
+//! use momento_functions_host::llm::{AnthropicChat, ChatRequest, Tool};
+//! use serde_json::json;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Lookup {
+//!     sku: String,
+//! }
+//!
+//! fn answer(question: &str) -> Result<String, AgentError> {
+//!     let agent = Agent::new(AnthropicChat::from_env())
+//!         .tool(
+//!             Tool::new(
+//!                 "stock_level",
+//!                 "How many units of a product are in stock",
+//!                 json!({
+//!                     "type": "object",
+//!                     "properties": { "sku": { "type": "string" } },
+//!                     "required": ["sku"],
+//!                 }),
+//!             ),
+//!             |Lookup { sku }| {
+//!                 let stock: Option<Vec<u8>> = cache::get(format!("stock:{sku}"))?;
+//!                 Ok::<_, cache::CacheGetError<_>>(
+//!                     stock.map(|units| String::from_utf8_lossy(&units).into_owned()),
+//!                 )
+//!             },
+//!         )
+//!         .max_iterations(4);
+//!     let run = agent.run(ChatRequest::new().user(question))?;
+//!     Ok(run.answer)
+//! }
+//! ```
+
+use std::fmt::Display;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::llm::{Chat, ChatError, ChatRequest, Message, Tool, ToolCall, ToolResult, Usage};
+
+/// An error occurred while running an [Agent].
+#[derive(Debug, thiserror::Error)]
+pub enum AgentError {
+    /// The model could not be asked.
+    #[error(transparent)]
+    Chat(#[from] ChatError),
+    /// The model was still calling tools after the most iterations allowed.
+    #[error("Agent was still calling tools after {iterations} iterations")]
+    MaxIterations {
+        /// How many times the model was asked.
+        iterations: usize,
+        /// The conversation so far, including the last tool results.
+        request: Box<ChatRequest>,
+    },
+}
+
+type Handler<'a> = Box<dyn Fn(&Value) -> Result<String, String> + 'a>;
+
+/// Runs a model's tool calls with registered Rust functions until it answers.
+pub struct Agent<'a, C> {
+    model: C,
+    tools: Vec<(Tool, Handler<'a>)>,
+    max_iterations: usize,
+}
+
+/// The outcome of [Agent::run].
+#[derive(Debug, Clone)]
+pub struct AgentRun {
+    /// The model's final reply.
+    pub answer: String,
+    /// The whole conversation, ending with the final reply. Send it with a new question to
+    /// continue.
+    pub request: ChatRequest,
+    /// Every tool call the model made, in order.
+    pub tool_calls: Vec<ToolCall>,
+    /// How many times the model was asked.
+    pub iterations: usize,
+    /// The tokens used across every iteration.
+    pub usage: Usage,
+}
+
+impl<'a, C: Chat> Agent<'a, C> {
+    /// An agent with no tools, that asks `model` up to 8 times.
+    pub fn new(model: C) -> Self {
+        Self {
+            model,
+            tools: Vec::new(),
+            max_iterations: 8,
+        }
+    }
+
+    /// Offer the model `tool`, and answer its calls with `handler`.
+    ///
+    /// The model's arguments are deserialized into `A`. The result is sent back as JSON,
+    /// except for strings, which are sent as they are. An argument that cannot be
+    /// deserialized, or an `Err` from `handler`, is sent back to the model as a failed
+    /// result.
+    pub fn tool<A, R, E>(mut self, tool: Tool, handler: impl Fn(A) -> Result<R, E> + 'a) -> Self
+    where
+        A: DeserializeOwned,
+        R: Serialize,
+        E: Display,
+    {
+        let handler = move |arguments: &Value| {
+            let arguments =
+                A::deserialize(arguments).map_err(|e| format!("invalid arguments: {e}"))?;
+            let result = handler(arguments).map_err(|e| e.to_string())?;
+            match serde_json::to_value(result) {
+                Ok(Value::String(text)) => Ok(text),
+                Ok(value) => Ok(value.to_string()),
+                Err(e) => Err(format!("could not encode the result: {e}")),
+            }
+        };
+        self.tools.push((tool, Box::new(handler)));
+        self
+    }
+
+    /// The most times to ask the model. Each reply with tool calls uses one, and so does
+    /// the final answer.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Send `request` with this agent's tools, and answer tool calls until the model replies
+    /// without one.
+    pub fn run(&self, request: ChatRequest) -> Result<AgentRun, AgentError> {
+        let mut request = request;
+        for (tool, _) in &self.tools {
+            if !request
+                .tools
+                .iter()
+                .any(|offered| offered.name == tool.name)
+            {
+                request.tools.push(tool.clone());
+            }
+        }
+        let mut tool_calls = Vec::new();
+        let mut usage = Usage::default();
+        for iteration in 1..=self.max_iterations {
+            let response = self.model.send(&request)?;
+            usage.input_tokens += response.usage.input_tokens;
+            usage.output_tokens += response.usage.output_tokens;
+            request.messages.push(response.message());
+
+            let calls: Vec<ToolCall> = response.tool_calls().cloned().collect();
+            if calls.is_empty() {
+                return Ok(AgentRun {
+                    answer: response.text(),
+                    request,
+                    tool_calls,
+                    iterations: iteration,
+                    usage,
+                });
+            }
+            let results: Vec<ToolResult> = calls.iter().map(|call| self.call(call)).collect();
+            request.messages.push(Message::tool_results(results));
+            tool_calls.extend(calls);
+        }
+        Err(AgentError::MaxIterations {
+            iterations: self.max_iterations,
+            request: Box::new(request),
+        })
+    }
+
+    fn call(&self, call: &ToolCall) -> ToolResult {
+        let Some((_, handler)) = self.tools.iter().find(|(tool, _)| tool.name == call.name) else {
+            log::warn!("model called unknown tool {}", call.name);
+            return ToolResult::error(call, format!("there is no tool named {}", call.name));
+        };
+        match handler(&call.arguments) {
+            Ok(content) => ToolResult::new(call, content),
+            Err(message) => {
+                log::debug!("tool {} failed: {message}", call.name);
+                ToolResult::error(call, message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use std::cell::RefCell;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::llm::{ChatResponse, Content, StopReason, StreamEvent};
+
+    /// Replies with each scripted response in turn, recording the requests it was sent.
+    struct Scripted {
+        replies: RefCell<Vec<ChatResponse>>,
+        requests: RefCell<Vec<ChatRequest>>,
+    }
+
+    impl Scripted {
+        fn new(replies: Vec<Vec<Content>>) -> Self {
+            Self {
+                replies: RefCell::new(
+                    replies
+                        .into_iter()
+                        .rev()
+                        .map(|content| ChatResponse {
+                            content,
+                            stop_reason: StopReason::EndTurn,
+                            usage: Usage {
+                                input_tokens: 10,
+                                output_tokens: 1,
+                            },
+                        })
+                        .collect(),
+                ),
+                requests: RefCell::default(),
+            }
+        }
+    }
+
+    impl Chat for &Scripted {
+        fn send(&self, request: &ChatRequest) -> Result<ChatResponse, ChatError> {
+            self.requests.borrow_mut().push(request.clone());
+            self.replies.borrow_mut().pop().ok_or(ChatError::Empty)
+        }
+
+        fn collect_stream(&self, _: &ChatRequest) -> Result<Vec<StreamEvent>, ChatError> {
+            Err(ChatError::Stream {
+                message: "scripted replies are not streamed".to_string(),
+            })
+        }
+    }
+
+    fn call(id: &str, name: &str, arguments: Value) -> Content {
+        Content::ToolCall(ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments,
+        })
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Add {
+        a: i64,
+        b: i64,
+    }
+
+    fn calculator<'a>(model: &'a Scripted) -> Agent<'a, &'a Scripted> {
+        Agent::new(model).tool(
+            Tool::new("add", "add two numbers", json!({"type": "object"})),
+            |Add { a, b }| a.checked_add(b).ok_or("overflow"),
+        )
+    }
+
+    #[test]
+    fn tool_results_are_fed_back_until_an_answer() {
+        let model = Scripted::new(vec![
+            vec![
+                call("1", "add", json!({"a": 2, "b": 3})),
+                call("2", "add", json!({"a": "two"})),
+                call("3", "divide", json!({})),
+            ],
+            vec![Content::Text("5".to_string())],
+        ]);
+        let run = calculator(&model)
+            .run(ChatRequest::new().user("2 + 3?"))
+            .unwrap();
+        assert_eq!("5", run.answer);
+        assert_eq!(2, run.iterations);
+        assert_eq!(3, run.tool_calls.len());
+        assert_eq!(20, run.usage.input_tokens);
+
+        let requests = model.requests.borrow();
+        assert_eq!("add", requests[0].tools[0].name);
+        let Content::ToolResult(added) = &requests[1].messages[2].content[0] else {
+            panic!("expected tool results");
+        };
+        assert_eq!(("5", false), (added.content.as_str(), added.is_error));
+        let failed: Vec<bool> = requests[1].messages[2].content[1..]
+            .iter()
+            .map(|content| matches!(content, Content::ToolResult(result) if result.is_error))
+            .collect();
+        assert_eq!(vec![true, true], failed);
+        assert_eq!(4, run.request.messages.len());
+    }
+
+    #[test]
+    fn iterations_are_bounded() {
+        let model = Scripted::new(vec![
+            vec![call("1", "add", json!({"a": 1, "b": 1}))],
+            vec![call("2", "add", json!({"a": 2, "b": 2}))],
+        ]);
+        let error = calculator(&model)
+            .max_iterations(2)
+            .run(ChatRequest::new().user("count up"))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AgentError::MaxIterations { iterations: 2, ref request } if request.messages.len() == 5
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+//! Typed clients for third-party REST APIs
+//!
+//! Calling a REST API by hand means building the URL and headers, encoding the body,
+//! checking the status, and decoding the reply for every endpoint. [declare_api!] writes
+//! that once: list each endpoint's method, path template, and request and response types,
+//! and it generates a client struct with one method per endpoint on top of [crate::http].
+//!
+//! Path templates name the method's parameters in braces, like
+//! `/v2/namespaces/{namespace}/query`. Parameters can be any [Display] type, and are
+//! percent-encoded into the path. Request bodies are sent as JSON, and the response is
+//! decoded from JSON into the declared type; use [serde_json::Value] for endpoints you don't
+//! want to model, or `()` for endpoints with nothing useful to return.
+//!
+//! How the client signs in is an [ApiAuth], given when the client is created.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::api::{ApiAuth, ApiError};
+//! use momento_functions_host::declare_api;
+//!
+//! #[derive(serde::Serialize)]
+//! struct Query {
+//!     rank_by: serde_json::Value,
+//!     top_k: usize,
+//! }
+//!
+//! #[derive(serde::Deserialize)]
+//! struct QueryResponse {
+//!     rows: Vec<serde_json::Value>,
+//! }
+//!
+//! declare_api! {
+//!     /// The Turbopuffer vector database.
+//!     pub struct Turbopuffer;
+//!
+//!     /// Query a namespace.
+//!     pub fn query(namespace: &str) -> QueryResponse = POST "/v2/namespaces/{namespace}/query" with Query;
+//!     /// Delete a namespace and everything in it.
+//!     pub fn delete_namespace(namespace: &str) -> serde_json::Value = DELETE "/v2/namespaces/{namespace}";
+//! }
+//!
+//! fn search(vector: Vec<f32>) -> Result<QueryResponse, ApiError> {
+//!     let turbopuffer = Turbopuffer::new(
+//!         "https://gcp-us-central1.turbopuffer.com",
+//!         ApiAuth::Bearer(std::env::var("TURBOPUFFER_API_KEY").unwrap_or_default()),
+//!     );
+//!     turbopuffer.query(
+//!         "products",
+//!         &Query {
+//!             rank_by: serde_json::json!(["vector", "ANN", vector]),
+//!             top_k: 10,
+//!         },
+//!     )
+//! }
+//! ```
+//!
+//! [Display]: std::fmt::Display
+
+use std::convert::Infallible;
+use std::fmt::Display;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "aws")]
+use crate::aws;
+use crate::http::{
+    self, HttpDeleteError, HttpGetError, HttpPostError, HttpPutError, Response, encode_component,
+};
+
+/// An error occurred while calling an API declared with [declare_api!](crate::declare_api).
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    /// A GET request failed.
+    #[error(transparent)]
+    Get(#[from] HttpGetError),
+    /// A PUT request failed.
+    #[error(transparent)]
+    Put(#[from] HttpPutError<Infallible>),
+    /// A POST request failed.
+    #[error(transparent)]
+    Post(#[from] HttpPostError<Infallible>),
+    /// A DELETE request failed.
+    #[error(transparent)]
+    Delete(#[from] HttpDeleteError),
+    /// The API responded with a status other than 2xx.
+    #[error("API returned status {status}: {message}")]
+    Status {
+        /// The HTTP status the API responded with.
+        status: u16,
+        /// The body of the API's response.
+        message: String,
+    },
+    /// The request body could not be encoded.
+    #[error("Failed to encode request.")]
+    Encode {
+        /// The underlying encoding error.
+        cause: serde_json::Error,
+    },
+    /// The API's response could not be decoded into the declared type.
+    #[error("Failed to decode response.")]
+    Decode {
+        /// The underlying decoding error.
+        cause: serde_json::Error,
+    },
+}
+
+/// How an API client signs in.
+#[derive(Clone)]
+pub enum ApiAuth {
+    /// No authentication, or authentication headers added with the client's `header` method.
+    None,
+    /// An `authorization: Bearer` token.
+    Bearer(String),
+    /// An API key in a header, like `x-api-key`.
+    Header {
+        /// The header's name.
+        name: String,
+        /// The API key.
+        value: String,
+    },
+    /// HTTP basic authentication.
+    Basic {
+        /// The user to sign in as.
+        username: String,
+        /// The user's password.
+        password: String,
+    },
+    /// AWS SigV4, for AWS service APIs.
+    #[cfg(feature = "aws")]
+    AwsSigV4 {
+        /// The credentials to sign with.
+        credentials: aws::auth::Credentials,
+        /// The service's region.
+        region: String,
+        /// The service's signing name, like `bedrock` or `execute-api`.
+        service: String,
+    },
+}
+
+/// An HTTP method an endpoint can be declared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// `GET`
+    Get,
+    /// `PUT`
+    Put,
+    /// `POST`
+    Post,
+    /// `DELETE`
+    Delete,
+}
+
+/// The client behind the structs [declare_api!](crate::declare_api) generates.
+///
+/// It can also be used directly, for endpoints that are not declared.
+#[derive(Clone)]
+pub struct ApiClient {
+    base_url: String,
+    auth: ApiAuth,
+    headers: Vec<(String, String)>,
+}
+
+impl ApiClient {
+    /// A client for the API at `base_url`, like `https://api.example.com`, signing in with
+    /// `auth`.
+    pub fn new(base_url: impl Into<String>, auth: ApiAuth) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            auth,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Send this header with every request, like a `user-agent` or an API version.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send a request to `path` with an optional JSON `body`, and decode the JSON response.
+    ///
+    /// An empty response body decodes as JSON `null`, so `()` and `Option` work for endpoints
+    /// that return nothing.
+    pub fn call<B, R>(&self, method: Method, path: &str, body: Option<&B>) -> Result<R, ApiError>
+    where
+        B: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let body = body
+            .map(serde_json::to_vec)
+            .transpose()
+            .map_err(|cause| ApiError::Encode { cause })?;
+        let response = self.send(method, format!("{}{path}", self.base_url), body)?;
+        if !(200..300).contains(&response.status) {
+            return Err(ApiError::Status {
+                status: response.status,
+                message: String::from_utf8_lossy(&response.body).into_owned(),
+            });
+        }
+        let body: &[u8] = if response.body.is_empty() {
+            b"null"
+        } else {
+            &response.body
+        };
+        serde_json::from_slice(body).map_err(|cause| ApiError::Decode { cause })
+    }
+
+    fn send(
+        &self,
+        method: Method,
+        url: String,
+        body: Option<Vec<u8>>,
+    ) -> Result<Response, ApiError> {
+        let mut headers = vec![("accept".to_string(), "application/json".to_string())];
+        if body.is_some() {
+            headers.push(("content-type".to_string(), "application/json".to_string()));
+        }
+        match &self.auth {
+            ApiAuth::Bearer(token) => {
+                headers.push(("authorization".to_string(), format!("Bearer {token}")));
+            }
+            ApiAuth::Header { name, value } => headers.push((name.clone(), value.clone())),
+            ApiAuth::Basic { username, password } => {
+                use base64::Engine;
+                let encoded = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{username}:{password}"));
+                headers.push(("authorization".to_string(), format!("Basic {encoded}")));
+            }
+            _ => {}
+        }
+        headers.extend(self.headers.iter().cloned());
+        let body = body.unwrap_or_default();
+
+        #[cfg(feature = "aws")]
+        if let ApiAuth::AwsSigV4 {
+            credentials,
+            region,
+            service,
+        } = &self.auth
+        {
+            let credentials = credentials.clone();
+            return Ok(match method {
+                Method::Get => http::get_aws_sigv4(url, headers, credentials, region, service)?,
+                Method::Put => {
+                    http::put_aws_sigv4(url, headers, credentials, region, service, body)?
+                }
+                Method::Post => {
+                    http::post_aws_sigv4(url, headers, credentials, region, service, body)?
+                }
+                Method::Delete => {
+                    http::delete_aws_sigv4(url, headers, credentials, region, service)?
+                }
+            });
+        }
+        Ok(match method {
+            Method::Get => http::get(url, headers)?,
+            Method::Put => http::put(url, headers, body)?,
+            Method::Post => http::post(url, headers, body)?,
+            Method::Delete => http::delete(url, headers)?,
+        })
+    }
+}
+
+/// Fill in a path template, like `/users/{id}/orders`, percent-encoding each parameter.
+///
+/// Placeholders without a parameter are left as they are.
+pub fn render_path(template: &str, parameters: &[(&str, &dyn Display)]) -> String {
+    let mut path = template.to_string();
+    for (name, value) in parameters {
+        path = path.replace(
+            &format!("{{{name}}}"),
+            &encode_component(&value.to_string()),
+        );
+    }
+    path
+}
+
+/// Declare a typed client for a REST API.
+///
+/// Each endpoint is declared as a method with its path parameters, its response type, its
+/// HTTP method (`GET`, `PUT`, `POST`, or `DELETE`), its path template, and, after `with`,
+/// its JSON request body's type. Endpoints with a body take it as a last parameter named
+/// `body`. The generated struct has `new(base_url, auth)` and `header(name, value)`
+/// constructors, like [ApiClient].
+///
+/// See [crate::api] for an example.
+#[macro_export]
+macro_rules! declare_api {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident;
+        $(
+            $(#[$endpoint_meta:meta])*
+            $endpoint_vis:vis fn $endpoint:ident($($parameter:ident: $parameter_type:ty),* $(,)?)
+                -> $response:ty = $method:ident $path:literal $(with $request:ty)?;
+        )*
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        $vis struct $name {
+            client: $crate::api::ApiClient,
+        }
+
+        impl $name {
+            /// A client for the API at `base_url`, signing in with `auth`.
+            $vis fn new(base_url: impl Into<String>, auth: $crate::api::ApiAuth) -> Self {
+                Self {
+                    client: $crate::api::ApiClient::new(base_url, auth),
+                }
+            }
+
+            /// Send this header with every request.
+            $vis fn header(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+                Self {
+                    client: self.client.header(name, value),
+                }
+            }
+
+            $(
+                $(#[$endpoint_meta])*
+                $endpoint_vis fn $endpoint(
+                    &self,
+                    $($parameter: $parameter_type,)*
+                    $(body: &$request)?
+                ) -> Result<$response, $crate::api::ApiError> {
+                    let path = $crate::api::render_path(
+                        $path,
+                        &[$((stringify!($parameter), &$parameter as &dyn ::std::fmt::Display)),*],
+                    );
+                    $crate::declare_api!(@call self.client, $method, path $(, body: $request)?)
+                }
+            )*
+        }
+    };
+    (@call $client:expr, $method:ident, $path:ident) => {
+        $client.call::<(), _>($crate::declare_api!(@method $method), &$path, None)
+    };
+    (@call $client:expr, $method:ident, $path:ident, $body:ident: $request:ty) => {
+        $client.call::<$request, _>($crate::declare_api!(@method $method), &$path, Some($body))
+    };
+    (@method GET) => { $crate::api::Method::Get };
+    (@method PUT) => { $crate::api::Method::Put };
+    (@method POST) => { $crate::api::Method::Post };
+    (@method DELETE) => { $crate::api::Method::Delete };
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize)]
+    struct NewOrder {
+        sku: String,
+    }
+
+    crate::declare_api! {
+        /// Orders.
+        struct Orders;
+
+        fn order(id: u64) -> serde_json::Value = GET "/orders/{id}";
+        fn create(customer: &str) -> serde_json::Value = POST "/customers/{customer}/orders" with NewOrder;
+        fn cancel(customer: &str, id: u64,) -> () = DELETE "/customers/{customer}/orders/{id}";
+    }
+
+    #[test]
+    fn declares_a_method_per_endpoint() {
+        let orders =
+            Orders::new("https://api.example.com/", ApiAuth::None).header("x-version", "2");
+        assert_eq!("https://api.example.com", orders.client.base_url);
+        assert_eq!(
+            vec![("x-version".to_string(), "2".to_string())],
+            orders.client.headers
+        );
+        let _: fn(&Orders, u64) -> Result<serde_json::Value, ApiError> = Orders::order;
+        let _: fn(&Orders, &str, &NewOrder) -> Result<serde_json::Value, ApiError> = Orders::create;
+        let _: fn(&Orders, &str, u64) -> Result<(), ApiError> = Orders::cancel;
+    }
+
+    #[test]
+    fn path_parameters_are_encoded() {
+        let namespace = "docs/2024";
+        let limit = 10;
+        assert_eq!(
+            "/v2/namespaces/docs%2F2024/query?limit=10&cursor={cursor}",
+            render_path(
+                "/v2/namespaces/{namespace}/query?limit={limit}&cursor={cursor}",
+                &[("namespace", &namespace), ("limit", &limit)],
+            )
+        );
+    }
+}
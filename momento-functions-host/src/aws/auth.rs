@@ -50,6 +50,7 @@ macro_rules! build_environment_aws_credentials {
 }
 
 /// The authorization strategy to use when connecting to AWS services.
+#[derive(Clone)]
 pub enum Credentials {
     /// Credentials that are hardcoded in the application.
     /// You should use a different strategy if you can.
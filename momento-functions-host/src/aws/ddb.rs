@@ -5,6 +5,7 @@
 
 use super::auth;
 use base64::Engine;
+use momento_functions_wit::abi::{self, HostCapability};
 use momento_functions_wit::host::momento::host;
 use momento_functions_wit::host::momento::host::aws_ddb::DdbError;
 use serde::{Deserialize, Serialize};
@@ -230,6 +231,202 @@ impl DynamoDBClient {
 
         Ok(())
     }
+
+    /// Run a PartiQL statement, reading every page of its results.
+    ///
+    /// Examples:
+    /// ________
+    /// ```rust,no_run
+    /// use momento_functions_host::aws::ddb::{DynamoDBClient, DynamoDBError, Item, Statement};
+    ///
+    /// fn open_orders(client: &DynamoDBClient, customer: &str) -> Result<Vec<Item>, DynamoDBError> {
+    ///     client.execute_statement(
+    ///         Statement::new(r#"SELECT * FROM "orders" WHERE customer = ? AND status = ?"#)
+    ///             .parameter(customer)
+    ///             .parameter("open"),
+    ///     )
+    /// }
+    /// ```
+    pub fn execute_statement(
+        &self,
+        statement: impl Into<Statement>,
+    ) -> Result<Vec<Item>, DynamoDBError> {
+        let statement = statement.into();
+        let mut items = Vec::new();
+        let mut next_token = None;
+        loop {
+            let page = self.execute_statement_page(statement.clone(), next_token, None)?;
+            items.extend(page.items);
+            match page.next_token {
+                Some(token) => next_token = Some(token),
+                None => return Ok(items),
+            }
+        }
+    }
+
+    /// Run a PartiQL statement, reading one page of its results.
+    ///
+    /// Pass the [StatementPage::next_token] of the previous page to read the next one.
+    /// `limit` caps how many items are evaluated for this page, so a page may hold fewer
+    /// items than the limit and still have a next page.
+    pub fn execute_statement_page(
+        &self,
+        statement: impl Into<Statement>,
+        next_token: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<StatementPage, DynamoDBError> {
+        require(HostCapability::DynamoDbPartiQl)?;
+        let statement = statement.into();
+        let output = self
+            .client
+            .execute_statement(&host::aws_ddb::ExecuteStatementRequest {
+                parameters: statement.parameters_json()?,
+                statement: statement.statement,
+                consistent_read: statement.consistent_read,
+                next_token,
+                limit,
+            })?;
+        Ok(StatementPage {
+            items: output
+                .items
+                .into_iter()
+                .map(item_from_host)
+                .collect::<Result<_, _>>()?,
+            next_token: output.next_token,
+        })
+    }
+
+    /// Run up to 25 PartiQL statements in one request.
+    ///
+    /// Each statement succeeds or fails on its own, so the result has one entry per
+    /// statement, in order: the item a read returned, `None` for writes and reads that found
+    /// nothing, or the statement's error.
+    ///
+    /// Examples:
+    /// ________
+    /// ```rust,no_run
+    /// use momento_functions_host::aws::ddb::{DynamoDBClient, DynamoDBError, Statement};
+    ///
+    /// fn close_orders(client: &DynamoDBClient, order_ids: &[&str]) -> Result<(), DynamoDBError> {
+    ///     let statements = order_ids.iter().map(|id| {
+    ///         Statement::new(r#"UPDATE "orders" SET status = 'closed' WHERE id = ?"#).parameter(*id)
+    ///     });
+    ///     for (id, result) in order_ids.iter().zip(client.batch_execute_statement(statements)?) {
+    ///         if let Err(e) = result {
+    ///             eprintln!("failed to close {id}: {e}");
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn batch_execute_statement(
+        &self,
+        statements: impl IntoIterator<Item = impl Into<Statement>>,
+    ) -> Result<Vec<Result<Option<Item>, BatchStatementError>>, DynamoDBError> {
+        require(HostCapability::DynamoDbPartiQl)?;
+        let statements = statements
+            .into_iter()
+            .map(|statement| {
+                let statement = statement.into();
+                Ok(host::aws_ddb::BatchStatementRequest {
+                    parameters: statement.parameters_json()?,
+                    statement: statement.statement,
+                    consistent_read: statement.consistent_read,
+                })
+            })
+            .collect::<Result<Vec<_>, DynamoDBError>>()?;
+        self.client
+            .batch_execute_statement(&statements)?
+            .into_iter()
+            .map(|response| match response.error {
+                Some(error) => Ok(Err(BatchStatementError {
+                    code: error.code,
+                    message: error.message,
+                })),
+                None => Ok(Ok(response.item.map(item_from_host).transpose()?)),
+            })
+            .collect()
+    }
+}
+
+fn item_from_host(item: host::aws_ddb::Item) -> Result<Item, DynamoDBError> {
+    match item {
+        host::aws_ddb::Item::Json(j) => Ok(serde_json::from_str(&j)?),
+    }
+}
+
+fn require(capability: HostCapability) -> Result<(), DynamoDBError> {
+    abi::require(capability).map_err(|e| DdbError::Other(e.to_string()).into())
+}
+
+/// A PartiQL statement and the values for its `?` placeholders.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    statement: String,
+    parameters: Vec<AttributeValue>,
+    consistent_read: bool,
+}
+
+impl Statement {
+    /// A statement, like `SELECT * FROM "orders" WHERE id = ?`.
+    pub fn new(statement: impl Into<String>) -> Self {
+        Self {
+            statement: statement.into(),
+            parameters: Vec::new(),
+            consistent_read: false,
+        }
+    }
+
+    /// Add the value for the next `?` placeholder.
+    pub fn parameter(mut self, value: impl Into<AttributeValue>) -> Self {
+        self.parameters.push(value.into());
+        self
+    }
+
+    /// Read with strong consistency. Statements are eventually consistent by default.
+    pub fn consistent_read(mut self, consistent_read: bool) -> Self {
+        self.consistent_read = consistent_read;
+        self
+    }
+
+    fn parameters_json(&self) -> Result<Option<String>, serde_json::Error> {
+        if self.parameters.is_empty() {
+            return Ok(None);
+        }
+        serde_json::to_string(&self.parameters).map(Some)
+    }
+}
+
+impl From<&str> for Statement {
+    fn from(statement: &str) -> Self {
+        Statement::new(statement)
+    }
+}
+
+impl From<String> for Statement {
+    fn from(statement: String) -> Self {
+        Statement::new(statement)
+    }
+}
+
+/// One page of a PartiQL statement's results.
+#[derive(Debug, Clone)]
+pub struct StatementPage {
+    /// The items on this page.
+    pub items: Vec<Item>,
+    /// Pass this to [DynamoDBClient::execute_statement_page] to read the next page. `None`
+    /// on the last page.
+    pub next_token: Option<String>,
+}
+
+/// One statement of a [DynamoDBClient::batch_execute_statement] failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct BatchStatementError {
+    /// The DynamoDB error code, like `ConditionalCheckFailed`.
+    pub code: String,
+    /// The error message.
+    pub message: String,
 }
 
 /// DynamoDB key type
@@ -401,7 +598,7 @@ impl From<KeyValue> for host::aws_ddb::KeyValue {
 ///
 /// let item: Item = MyStruct { some_attribute: "some value".to_string() }.into();
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item {
     /// The item object
     #[serde(flatten)]
@@ -409,7 +606,7 @@ pub struct Item {
 }
 
 /// A value within the item object
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AttributeValue {
     /// A B value
     #[serde(rename = "B")]
@@ -618,3 +815,27 @@ where
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statement_parameters_are_dynamodb_json() {
+        let statement =
+            Statement::new(r#"SELECT * FROM "orders" WHERE customer = ? AND total > ?"#)
+                .parameter("bob")
+                .parameter(100);
+        assert_eq!(
+            Some(r#"[{"S":"bob"},{"N":"100"}]"#.to_string()),
+            statement.parameters_json().unwrap()
+        );
+        assert_eq!(
+            None,
+            Statement::from("SELECT * FROM t")
+                .parameters_json()
+                .unwrap()
+        );
+    }
+}
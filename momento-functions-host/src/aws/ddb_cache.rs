@@ -0,0 +1,380 @@
+//! DynamoDB items cached in Momento, written through or behind
+//!
+//! [CachedTable] reads items from the cache, falling back to DynamoDB and caching what it
+//! finds. Writes follow the table's [WriteStrategy]:
+//! * [WriteStrategy::WriteThrough] puts the item in DynamoDB, then in the cache. The write
+//!   is durable when [CachedTable::put] returns.
+//! * [WriteStrategy::WriteBehind] puts the item in the cache, then spawns a writer Function
+//!   to put it in DynamoDB. [CachedTable::put] returns as soon as the cache has it, and
+//!   readers see it right away, but DynamoDB catches up later.
+//!
+//! The writer Function deserializes its payload as a [WriteBehind] and passes it to
+//! [write_behind], which retries the put and, if it still fails, records a [DeadLetter] in
+//! a cache list so the write can be inspected and replayed. Spawned writers may finish in
+//! any order, so when one key is written many times in quick succession, the last write to
+//! reach DynamoDB is the one that stays.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::aws::auth::AwsCredentialsProvider;
+//! use momento_functions_host::aws::ddb::{DynamoDBClient, Item};
+//! use momento_functions_host::aws::ddb_cache::{self, CachedTable, DdbCacheError, WriteBehind};
+//! use momento_functions_host::build_environment_aws_credentials;
+//! use momento_functions_host::retry::RetryPolicy;
+//! use std::time::Duration;
+//!
+//! # fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let credentials =
+//!     AwsCredentialsProvider::new("us-east-1", build_environment_aws_credentials!())?;
+//! let client = DynamoDBClient::new(&credentials);
+//!
+//! // Counters change often and can lose the last few updates, so write them behind.
+//! let counters = CachedTable::new(&client, "page_views")
+//!     .ttl(Duration::from_secs(600))
+//!     .write_behind("page-views-writer");
+//! counters.put(("page", "/home"), [("page", "/home"), ("views", "1024")])?;
+//! let item: Option<Item> = counters.get(("page", "/home"))?;
+//! # Ok(()) }
+//!
+//! // The `page-views-writer` Function:
+//! fn writer(write: WriteBehind) -> Result<(), DdbCacheError> {
+//!     # let credentials = AwsCredentialsProvider::new("us-east-1", build_environment_aws_credentials!()).unwrap();
+//!     let client = DynamoDBClient::new(&credentials);
+//!     ddb_cache::write_behind(&client, write, &RetryPolicy::new())?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::ddb::{DynamoDBClient, DynamoDBError, Item, Key};
+use crate::cache::{
+    self, CacheDeleteError, CacheGetError, CacheListPushBackError, CacheSetError, CollectionTtl,
+};
+use crate::encoding::Json;
+use crate::retry::{self, RetryPolicy};
+use crate::{FunctionSpawnError, spawn};
+
+/// How long dead letters are kept after the last one was recorded.
+const DEAD_LETTER_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// The most dead letters kept per table. Older ones are dropped first.
+const MAX_DEAD_LETTERS: u32 = 1000;
+
+/// An error occurred while reading or writing a [CachedTable].
+#[derive(Debug, thiserror::Error)]
+pub enum DdbCacheError {
+    /// An error occurred when calling DynamoDB.
+    #[error(transparent)]
+    Dynamo(#[from] DynamoDBError),
+    /// The item could not be read from the cache.
+    #[error(transparent)]
+    CacheRead(#[from] CacheGetError<Infallible>),
+    /// The item could not be written to the cache.
+    #[error(transparent)]
+    CacheWrite(#[from] CacheSetError<Infallible>),
+    /// The item could not be removed from the cache.
+    #[error(transparent)]
+    Invalidate(#[from] CacheDeleteError),
+    /// The writer Function could not be spawned. The item was removed from the cache again.
+    #[error(transparent)]
+    Spawn(#[from] FunctionSpawnError<serde_json::Error>),
+    /// A failed write could not be recorded as a dead letter.
+    #[error(transparent)]
+    DeadLetter(#[from] CacheListPushBackError<serde_json::Error>),
+    /// A cached item could not be encoded or decoded.
+    #[error("Failed to encode or decode a cached item: {0}")]
+    Encoding(#[from] serde_json::Error),
+    /// The item could not be converted to the requested type.
+    #[error("Failed to convert item: {message}")]
+    Convert {
+        /// What the conversion reported.
+        message: String,
+    },
+}
+
+/// When a [CachedTable] writes to DynamoDB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteStrategy {
+    /// Put items in DynamoDB, then in the cache.
+    WriteThrough,
+    /// Put items in the cache, then spawn a Function to put them in DynamoDB.
+    WriteBehind {
+        /// The Function that calls [write_behind] with its payload.
+        writer_function: String,
+    },
+}
+
+/// The payload a [WriteStrategy::WriteBehind] writer Function receives.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteBehind {
+    /// The table to put the item in.
+    pub table_name: String,
+    /// The item.
+    pub item: Item,
+    /// The cache list to record the write in if it fails.
+    pub dead_letter_key: String,
+    /// When the item was written to the cache, in milliseconds since the Unix epoch.
+    pub queued_at_millis: u64,
+}
+
+/// A write-behind that failed, as recorded in the dead letter list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// The write that failed. Pass it to [write_behind] again to replay it.
+    pub write: WriteBehind,
+    /// The last error DynamoDB returned.
+    pub error: String,
+    /// When the write was given up on, in milliseconds since the Unix epoch.
+    pub failed_at_millis: u64,
+}
+
+/// What [write_behind] did with a write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteBehindOutcome {
+    /// The item was put in DynamoDB.
+    Written,
+    /// Every attempt failed, and the write was recorded as a [DeadLetter].
+    DeadLettered,
+}
+
+/// A DynamoDB table read through the cache.
+pub struct CachedTable<'a> {
+    client: &'a DynamoDBClient,
+    table_name: String,
+    key_prefix: String,
+    ttl: Duration,
+    strategy: WriteStrategy,
+    dead_letter_key: Option<String>,
+}
+
+impl<'a> CachedTable<'a> {
+    /// Cache items from `table_name` under `ddb:{table_name}:` keys for 5 minutes, writing
+    /// through.
+    pub fn new(client: &'a DynamoDBClient, table_name: impl Into<String>) -> Self {
+        let table_name = table_name.into();
+        Self {
+            client,
+            key_prefix: format!("ddb:{table_name}:"),
+            table_name,
+            ttl: Duration::from_secs(5 * 60),
+            strategy: WriteStrategy::WriteThrough,
+            dead_letter_key: None,
+        }
+    }
+
+    /// How long items stay in the cache after they are read or written.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The prefix of the cache keys items are stored under.
+    pub fn key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// When to write to DynamoDB.
+    pub fn strategy(mut self, strategy: WriteStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Write behind, with `writer_function` putting items in DynamoDB.
+    pub fn write_behind(self, writer_function: impl Into<String>) -> Self {
+        self.strategy(WriteStrategy::WriteBehind {
+            writer_function: writer_function.into(),
+        })
+    }
+
+    /// The cache list failed write-behinds are recorded in. Defaults to
+    /// `{key_prefix}dead-letters`.
+    pub fn dead_letter_key(mut self, dead_letter_key: impl Into<String>) -> Self {
+        self.dead_letter_key = Some(dead_letter_key.into());
+        self
+    }
+
+    /// Get an item from the cache, or from DynamoDB when it is not cached.
+    pub fn get<V>(&self, key: impl Into<Key>) -> Result<Option<V>, DdbCacheError>
+    where
+        V: TryFrom<Item>,
+        V::Error: Display,
+    {
+        let key = key.into();
+        let cache_key = self.cache_key(&key)?;
+        let item = match cache::get::<Vec<u8>>(&cache_key)? {
+            Some(cached) => serde_json::from_slice(&cached)?,
+            None => match self.client.get_item_raw(&self.table_name, key)? {
+                Some(item) => {
+                    cache::set(&cache_key, serde_json::to_vec(&item)?, self.ttl)?;
+                    item
+                }
+                None => return Ok(None),
+            },
+        };
+        V::try_from(item)
+            .map(Some)
+            .map_err(|e| DdbCacheError::Convert {
+                message: e.to_string(),
+            })
+    }
+
+    /// Put an item at `key`, following the table's [WriteStrategy].
+    ///
+    /// `key` must be the item's own key attributes.
+    pub fn put(&self, key: impl Into<Key>, item: impl Into<Item>) -> Result<(), DdbCacheError> {
+        let cache_key = self.cache_key(&key.into())?;
+        let item: Item = item.into();
+        let cached = serde_json::to_vec(&item)?;
+        match &self.strategy {
+            WriteStrategy::WriteThrough => {
+                self.client.put_item(&self.table_name, item)?;
+                if let Err(e) = cache::set(&cache_key, cached, self.ttl) {
+                    // Don't leave the previous value to be read until it expires.
+                    self.invalidate_after_failure(&cache_key);
+                    return Err(e.into());
+                }
+            }
+            WriteStrategy::WriteBehind { writer_function } => {
+                cache::set(&cache_key, cached, self.ttl)?;
+                let write = WriteBehind {
+                    table_name: self.table_name.clone(),
+                    item,
+                    dead_letter_key: self.resolved_dead_letter_key(),
+                    queued_at_millis: now_millis(),
+                };
+                if let Err(e) = spawn(writer_function, Json(&write)) {
+                    // Nothing will write it to DynamoDB, so don't let readers see it.
+                    self.invalidate_after_failure(&cache_key);
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the item at `key` from the cache, so the next read goes to DynamoDB.
+    pub fn invalidate(&self, key: impl Into<Key>) -> Result<(), DdbCacheError> {
+        cache::delete(self.cache_key(&key.into())?)?;
+        Ok(())
+    }
+
+    fn cache_key(&self, key: &Key) -> Result<String, DdbCacheError> {
+        cache_key(&self.key_prefix, key)
+    }
+
+    fn resolved_dead_letter_key(&self) -> String {
+        self.dead_letter_key
+            .clone()
+            .unwrap_or_else(|| format!("{}dead-letters", self.key_prefix))
+    }
+
+    fn invalidate_after_failure(&self, cache_key: &str) {
+        if let Err(e) = cache::delete(cache_key) {
+            log::warn!("failed to invalidate {cache_key} after a failed write: {e}");
+        }
+    }
+}
+
+/// Put a write-behind's item in DynamoDB, retrying with `policy`. If every attempt fails,
+/// the write is recorded as a [DeadLetter] at the end of its dead letter list.
+///
+/// Call this from the writer Function with its payload.
+pub fn write_behind(
+    client: &DynamoDBClient,
+    write: WriteBehind,
+    policy: &RetryPolicy,
+) -> Result<WriteBehindOutcome, DdbCacheError> {
+    let result = retry::with_backoff(policy, || {
+        client.put_item(&write.table_name, write.item.clone())
+    });
+    let Err(e) = result else {
+        return Ok(WriteBehindOutcome::Written);
+    };
+    log::error!(
+        "write-behind to {} failed, recording it in {}: {e}",
+        write.table_name,
+        write.dead_letter_key
+    );
+    let dead_letter_key = write.dead_letter_key.clone();
+    cache::list_push_back(
+        dead_letter_key,
+        Json(DeadLetter {
+            write,
+            error: e.to_string(),
+            failed_at_millis: now_millis(),
+        }),
+        CollectionTtl::refresh_on_update(DEAD_LETTER_TTL),
+        Some(MAX_DEAD_LETTERS),
+    )?;
+    Ok(WriteBehindOutcome::DeadLettered)
+}
+
+/// The cache key for `key`: its values as JSON, so keys of different types or shapes never
+/// collide.
+fn cache_key(key_prefix: &str, key: &Key) -> Result<String, DdbCacheError> {
+    let values = match key {
+        Key::Hash { value, .. } => serde_json::to_string(value)?,
+        Key::HashRange {
+            hash_value,
+            range_value,
+            ..
+        } => serde_json::to_string(&(hash_value, range_value))?,
+    };
+    Ok(format!("{key_prefix}{values}"))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| u64::try_from(now.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::aws::ddb::KeyValue;
+
+    fn hash_range(hash: impl Into<KeyValue>, range: impl Into<KeyValue>) -> Key {
+        Key::HashRange {
+            hash_key: "customer".to_string(),
+            hash_value: hash.into(),
+            range_key: "order".to_string(),
+            range_value: range.into(),
+        }
+    }
+
+    #[test]
+    fn cache_keys_do_not_collide_across_key_shapes() {
+        let key = |key: Key| cache_key("ddb:orders:", &key).unwrap();
+        assert_eq!(r#"ddb:orders:{"S":"7"}"#, key(("id", "7").into()));
+        assert_eq!(r#"ddb:orders:{"N":7}"#, key(("id", 7).into()));
+        assert_eq!(r#"ddb:orders:[{"S":"a"},{"N":7}]"#, key(hash_range("a", 7)));
+        assert_ne!(key(hash_range("a#b", "c")), key(hash_range("a", "b#c")));
+    }
+
+    #[test]
+    fn write_behinds_round_trip_through_a_dead_letter() {
+        let letter = DeadLetter {
+            write: WriteBehind {
+                table_name: "orders".to_string(),
+                item: [("id", "7")].into(),
+                dead_letter_key: "ddb:orders:dead-letters".to_string(),
+                queued_at_millis: 1,
+            },
+            error: "throttled".to_string(),
+            failed_at_millis: 2,
+        };
+        let json = serde_json::to_string(&letter).unwrap();
+        assert!(json.contains(r#""item":{"id":{"S":"7"}}"#), "{json}");
+        let letter: DeadLetter = serde_json::from_str(&json).unwrap();
+        assert_eq!("orders", letter.write.table_name);
+        assert!(letter.write.item.attributes.contains_key("id"));
+    }
+}
@@ -0,0 +1,380 @@
+//! Host interfaces for reading AWS DynamoDB Streams
+//!
+//! A scheduled Function can use this to process a table's change events and update
+//! caches or topics, without deploying a separate stream consumer.
+//!
+//! Shard iterators expire after a few minutes, so rather than holding on to them between
+//! invocations, keep a checkpoint (the last processed sequence number) somewhere durable,
+//! like the cache, and resume from it with [DynamoDBStreamsClient::poll_shard].
+
+use super::auth;
+use super::ddb::{DynamoDBError, Item};
+use momento_functions_wit::abi::{self, HostCapability};
+use momento_functions_wit::host::momento::host;
+use momento_functions_wit::host::momento::host::aws_ddb::DdbError;
+
+/// DynamoDB Streams client for host interfaces.
+///
+/// This client uses Momento's host-provided AWS communication channel, which
+/// is kept hot at all times. When your Function has not run in several days or more,
+/// the channel is still hot and ready, keeping your Function invocations predictable
+/// even when your demand is unpredictable.
+pub struct DynamoDBStreamsClient {
+    client: host::aws_ddb_streams::Client,
+}
+
+/// Where in a shard to start reading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardIteratorType {
+    /// The oldest record still retained in the shard.
+    TrimHorizon,
+    /// Just after the most recent record in the shard.
+    Latest,
+    /// The record with this sequence number.
+    AtSequenceNumber(String),
+    /// The record just after this sequence number.
+    AfterSequenceNumber(String),
+}
+
+/// A shard of a DynamoDB stream.
+#[derive(Debug, Clone)]
+pub struct Shard {
+    /// The shard's id.
+    pub shard_id: String,
+    /// The shard this one was split from, if any. Read the parent to its end first to
+    /// process records in order.
+    pub parent_shard_id: Option<String>,
+    /// The first sequence number in the shard.
+    pub starting_sequence_number: Option<String>,
+    /// The last sequence number in the shard. Present once the shard is closed.
+    pub ending_sequence_number: Option<String>,
+}
+
+impl Shard {
+    /// True when the shard will receive no more records.
+    pub fn is_closed(&self) -> bool {
+        self.ending_sequence_number.is_some()
+    }
+}
+
+/// An opaque position within a shard, from [DynamoDBStreamsClient::shard_iterator].
+#[derive(Debug, Clone)]
+pub struct ShardIterator(String);
+
+/// The kind of change a [StreamRecord] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEventName {
+    /// An item was added.
+    Insert,
+    /// An item was updated.
+    Modify,
+    /// An item was deleted.
+    Remove,
+}
+
+/// A single change to a table.
+#[derive(Debug)]
+pub struct StreamRecord {
+    /// A unique id for this event.
+    pub event_id: String,
+    /// The kind of change.
+    pub event_name: StreamEventName,
+    /// The record's position in its shard.
+    pub sequence_number: String,
+    /// Approximately when the change was made.
+    pub approximate_creation_epoch_seconds: Option<u64>,
+    /// The key attributes of the changed item.
+    pub keys: Item,
+    /// The item after the change, if the stream view type includes new images.
+    pub new_image: Option<Item>,
+    /// The item before the change, if the stream view type includes old images.
+    pub old_image: Option<Item>,
+}
+
+/// Records read from a shard with [DynamoDBStreamsClient::get_records].
+#[derive(Debug)]
+pub struct RecordBatch {
+    /// The records, in order. This can be empty even when more records follow.
+    pub records: Vec<StreamRecord>,
+    /// Where to read next. `None` once a closed shard has been read to its end.
+    pub next_iterator: Option<ShardIterator>,
+}
+
+/// Records read from a shard with [DynamoDBStreamsClient::poll_shard].
+#[derive(Debug)]
+pub struct ShardPoll {
+    /// The records, in order.
+    pub records: Vec<StreamRecord>,
+    /// The checkpoint to pass to the next poll of this shard. This is the last record's
+    /// sequence number, or the checkpoint you passed in when no records were read.
+    pub checkpoint: Option<String>,
+    /// True when the shard is closed and has been read to its end. You can stop polling
+    /// it and move on to its children.
+    pub finished: bool,
+}
+
+impl DynamoDBStreamsClient {
+    /// Create a new DynamoDB Streams client.
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::aws::auth::AwsCredentialsProvider;
+    /// # use momento_functions_host::aws::ddb_streams::DynamoDBStreamsClient;
+    /// # use momento_functions_host::build_environment_aws_credentials;
+    /// let credentials = match AwsCredentialsProvider::new(
+    ///     "us-east-1",
+    ///     build_environment_aws_credentials!(),
+    /// ) {
+    ///     Ok(credentials) => credentials,
+    ///     Err(e) => {
+    ///         eprintln!("failed to build credentials: {e}");
+    ///         return;
+    ///     }
+    /// };
+    /// let client = DynamoDBStreamsClient::new(&credentials);
+    /// ```
+    pub fn new(credentials: &auth::AwsCredentialsProvider) -> Self {
+        Self {
+            client: host::aws_ddb_streams::Client::new(credentials.resource()),
+        }
+    }
+
+    /// List every shard of a stream, following pagination.
+    pub fn list_shards(&self, stream_arn: impl Into<String>) -> Result<Vec<Shard>, DynamoDBError> {
+        require_streams()?;
+        let stream_arn = stream_arn.into();
+        let mut shards = Vec::new();
+        let mut exclusive_start_shard_id = None;
+        loop {
+            let output =
+                self.client
+                    .describe_stream(&host::aws_ddb_streams::DescribeStreamRequest {
+                        stream_arn: stream_arn.clone(),
+                        exclusive_start_shard_id,
+                        limit: None,
+                    })?;
+            shards.extend(output.shards.into_iter().map(|shard| Shard {
+                shard_id: shard.shard_id,
+                parent_shard_id: shard.parent_shard_id,
+                starting_sequence_number: shard.starting_sequence_number,
+                ending_sequence_number: shard.ending_sequence_number,
+            }));
+            match output.last_evaluated_shard_id {
+                Some(shard_id) => exclusive_start_shard_id = Some(shard_id),
+                None => return Ok(shards),
+            }
+        }
+    }
+
+    /// Get an iterator for reading a shard from the given position.
+    pub fn shard_iterator(
+        &self,
+        stream_arn: impl Into<String>,
+        shard_id: impl Into<String>,
+        iterator_type: ShardIteratorType,
+    ) -> Result<ShardIterator, DynamoDBError> {
+        require_streams()?;
+        let shard_iterator =
+            self.client
+                .get_shard_iterator(&host::aws_ddb_streams::GetShardIteratorRequest {
+                    stream_arn: stream_arn.into(),
+                    shard_id: shard_id.into(),
+                    shard_iterator_type: iterator_type.into(),
+                })?;
+        Ok(ShardIterator(shard_iterator))
+    }
+
+    /// Read up to `limit` records from a shard iterator.
+    pub fn get_records(
+        &self,
+        iterator: &ShardIterator,
+        limit: Option<u32>,
+    ) -> Result<RecordBatch, DynamoDBError> {
+        require_streams()?;
+        let output = self
+            .client
+            .get_records(&host::aws_ddb_streams::GetRecordsRequest {
+                shard_iterator: iterator.0.clone(),
+                limit,
+            })?;
+        Ok(RecordBatch {
+            records: output
+                .records
+                .into_iter()
+                .map(StreamRecord::try_from)
+                .collect::<Result<_, _>>()?,
+            next_iterator: output.next_shard_iterator.map(ShardIterator),
+        })
+    }
+
+    /// Read the records after `checkpoint` from a shard, or from the oldest retained
+    /// record when there is no checkpoint yet.
+    ///
+    /// Examples:
+    /// ________
+    /// ```rust,no_run
+    /// use momento_functions_host::aws::ddb_streams::DynamoDBStreamsClient;
+    /// use momento_functions_host::cache;
+    /// use std::time::Duration;
+    ///
+    /// # let client: DynamoDBStreamsClient = todo!();
+    /// let stream_arn = "arn:aws:dynamodb:us-east-1:123456789012:table/my_table/stream/label";
+    /// let shards = match client.list_shards(stream_arn) {
+    ///     Ok(shards) => shards,
+    ///     Err(e) => {
+    ///         eprintln!("failed to list shards: {e}");
+    ///         return;
+    ///     }
+    /// };
+    /// for shard in shards {
+    ///     let checkpoint_key = format!("checkpoint:{}", shard.shard_id);
+    ///     let checkpoint = cache::get::<Vec<u8>>(checkpoint_key.as_str())
+    ///         .ok()
+    ///         .flatten()
+    ///         .and_then(|checkpoint| String::from_utf8(checkpoint).ok());
+    ///     match client.poll_shard(stream_arn, &shard.shard_id, checkpoint.as_deref(), Some(100)) {
+    ///         Ok(poll) => {
+    ///             for record in &poll.records {
+    ///                 println!("{:?} {:?}", record.event_name, record.keys);
+    ///             }
+    ///             if let Some(checkpoint) = poll.checkpoint {
+    ///                 let _ = cache::set(checkpoint_key.as_str(), checkpoint, Duration::from_secs(86400));
+    ///             }
+    ///         }
+    ///         Err(e) => eprintln!("failed to poll shard {}: {e}", shard.shard_id),
+    ///     }
+    /// }
+    /// ```
+    pub fn poll_shard(
+        &self,
+        stream_arn: impl Into<String>,
+        shard_id: impl Into<String>,
+        checkpoint: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<ShardPoll, DynamoDBError> {
+        let iterator_type = match checkpoint {
+            Some(sequence_number) => {
+                ShardIteratorType::AfterSequenceNumber(sequence_number.to_string())
+            }
+            None => ShardIteratorType::TrimHorizon,
+        };
+        let iterator = self.shard_iterator(stream_arn, shard_id, iterator_type)?;
+        let batch = self.get_records(&iterator, limit)?;
+        Ok(ShardPoll::resumed(checkpoint, batch))
+    }
+}
+
+impl ShardPoll {
+    /// What a poll resumed from `checkpoint` read. An empty batch keeps the checkpoint.
+    fn resumed(checkpoint: Option<&str>, batch: RecordBatch) -> Self {
+        let checkpoint = batch
+            .records
+            .last()
+            .map(|record| record.sequence_number.clone())
+            .or_else(|| checkpoint.map(str::to_string));
+        Self {
+            finished: batch.next_iterator.is_none(),
+            records: batch.records,
+            checkpoint,
+        }
+    }
+}
+
+impl From<ShardIteratorType> for host::aws_ddb_streams::ShardIteratorType {
+    fn from(value: ShardIteratorType) -> Self {
+        match value {
+            ShardIteratorType::TrimHorizon => Self::TrimHorizon,
+            ShardIteratorType::Latest => Self::Latest,
+            ShardIteratorType::AtSequenceNumber(s) => Self::AtSequenceNumber(s),
+            ShardIteratorType::AfterSequenceNumber(s) => Self::AfterSequenceNumber(s),
+        }
+    }
+}
+
+impl TryFrom<host::aws_ddb_streams::StreamRecord> for StreamRecord {
+    type Error = DynamoDBError;
+
+    fn try_from(value: host::aws_ddb_streams::StreamRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            event_id: value.event_id,
+            event_name: match value.event_name {
+                host::aws_ddb_streams::EventName::Insert => StreamEventName::Insert,
+                host::aws_ddb_streams::EventName::Modify => StreamEventName::Modify,
+                host::aws_ddb_streams::EventName::Remove => StreamEventName::Remove,
+            },
+            sequence_number: value.sequence_number,
+            approximate_creation_epoch_seconds: value.approximate_creation_epoch_seconds,
+            keys: parse_item(value.keys)?,
+            new_image: value.new_image.map(parse_item).transpose()?,
+            old_image: value.old_image.map(parse_item).transpose()?,
+        })
+    }
+}
+
+fn parse_item(item: host::aws_ddb::Item) -> Result<Item, DynamoDBError> {
+    match item {
+        host::aws_ddb::Item::Json(j) => Ok(serde_json::from_str(&j)?),
+    }
+}
+
+/// Check that the host provides DynamoDB Streams, reporting it as a Dynamo error if not.
+fn require_streams() -> Result<(), DynamoDBError> {
+    abi::require(HostCapability::DynamoDbStreams)
+        .map_err(|e| DynamoDBError::Dynamo(DdbError::Other(e.to_string())))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn record(sequence_number: &str) -> StreamRecord {
+        StreamRecord {
+            event_id: format!("event-{sequence_number}"),
+            event_name: StreamEventName::Insert,
+            sequence_number: sequence_number.to_string(),
+            approximate_creation_epoch_seconds: None,
+            keys: Item {
+                attributes: Default::default(),
+            },
+            new_image: None,
+            old_image: None,
+        }
+    }
+
+    fn batch(records: Vec<StreamRecord>, open: bool) -> RecordBatch {
+        RecordBatch {
+            records,
+            next_iterator: open.then(|| ShardIterator("next".to_string())),
+        }
+    }
+
+    #[test]
+    fn empty_batches_keep_the_checkpoint() {
+        let poll = ShardPoll::resumed(Some("100"), batch(vec![], true));
+        assert!(poll.records.is_empty());
+        assert_eq!(Some("100".to_string()), poll.checkpoint);
+        assert!(!poll.finished);
+
+        let poll = ShardPoll::resumed(None, batch(vec![], true));
+        assert_eq!(None, poll.checkpoint);
+    }
+
+    #[test]
+    fn records_move_the_checkpoint_to_the_last_one() {
+        let poll = ShardPoll::resumed(Some("100"), batch(vec![record("101"), record("102")], true));
+        assert_eq!(2, poll.records.len());
+        assert_eq!(Some("102".to_string()), poll.checkpoint);
+        assert!(!poll.finished);
+    }
+
+    #[test]
+    fn closed_shards_finish_when_read_to_their_end() {
+        let poll = ShardPoll::resumed(Some("100"), batch(vec![record("101")], false));
+        assert_eq!(Some("101".to_string()), poll.checkpoint);
+        assert!(poll.finished);
+
+        let poll = ShardPoll::resumed(Some("101"), batch(vec![], false));
+        assert_eq!(Some("101".to_string()), poll.checkpoint);
+        assert!(poll.finished);
+    }
+}
@@ -1,5 +1,6 @@
 //! Host interfaces for working with AWS Lambda
 use crate::encoding::{Encode, EncodeError, Extract, ExtractError};
+use momento_functions_wit::abi::{self, HostCapability};
 use momento_functions_wit::host::momento::host;
 use momento_functions_wit::host::momento::host::aws_lambda::LambdaError;
 
@@ -32,6 +33,22 @@ where
     LambdaError(#[from] LambdaError),
 }
 
+/// An error occurred while reading a streamed Lambda response.
+#[derive(Debug, thiserror::Error)]
+pub enum InvokeStreamError {
+    /// The function failed partway through streaming its response.
+    #[error("Function failed while streaming its response: {code}: {details:?}")]
+    FunctionError {
+        /// The error code reported by Lambda.
+        code: String,
+        /// Details about the error, if Lambda provided any.
+        details: Option<String>,
+    },
+    /// An error occurred when reading from the host stream.
+    #[error(transparent)]
+    LambdaError(#[from] LambdaError),
+}
+
 impl LambdaClient {
     /// Create a new Lambda client.
     ///
@@ -170,6 +187,129 @@ impl LambdaClient {
             payload: output.payload,
         })
     }
+
+    /// Invoke a lambda function that streams its response.
+    ///
+    /// The returned stream yields the response payload in chunks as the function produces
+    /// them, so you can relay a long response (like one from an LLM) without waiting for
+    /// all of it. The function must be configured for response streaming.
+    ///
+    /// Examples:
+    /// ________
+    /// ```rust,no_run
+    /// # use momento_functions_host::aws::lambda::LambdaClient;
+    /// # let client: LambdaClient = todo!();
+    /// match client.invoke_with_response_stream("my_streaming_function", "tell me a story") {
+    ///     Ok(stream) => {
+    ///         for chunk in stream {
+    ///             match chunk {
+    ///                 Ok(chunk) => print!("{}", String::from_utf8_lossy(&chunk)),
+    ///                 Err(e) => {
+    ///                     eprintln!("stream failed: {e}");
+    ///                     break;
+    ///                 }
+    ///             }
+    ///         }
+    ///     }
+    ///     Err(e) => eprintln!("invoke failed: {e}"),
+    /// }
+    /// ```
+    pub fn invoke_with_response_stream<E: Encode>(
+        &self,
+        name: impl Into<LambdaName>,
+        payload: E,
+    ) -> Result<InvokeResponseStream, InvokeError<E::Error>> {
+        abi::require(HostCapability::LambdaResponseStreaming)
+            .map_err(|e| LambdaError::Other(e.to_string()))?;
+        let (function_name, qualifier) = name.into().into_inner();
+        let request = host::aws_lambda::InvokeWithResponseStreamRequest {
+            function_name,
+            qualifier,
+            payload: Some(
+                payload
+                    .try_serialize()
+                    .map_err(|e| InvokeError::EncodeFailed { cause: e })?
+                    .into(),
+            ),
+            log_type: None,
+            client_context: None,
+        };
+        let stream = self.client.invoke_with_response_stream(&request)?;
+
+        Ok(InvokeResponseStream {
+            stream,
+            log_result: None,
+            complete: false,
+        })
+    }
+}
+
+/// A streamed response from Lambda, from [LambdaClient::invoke_with_response_stream].
+///
+/// Iterate it to read the payload chunks in order. If the function fails partway
+/// through, the last item is an [InvokeStreamError::FunctionError].
+pub struct InvokeResponseStream {
+    stream: host::aws_lambda::InvokeResponseStream,
+    log_result: Option<String>,
+    complete: bool,
+}
+
+impl InvokeResponseStream {
+    /// Get the status code of the response
+    pub fn status_code(&self) -> i32 {
+        self.stream.status_code()
+    }
+
+    /// The version of the function that handled the invocation.
+    pub fn executed_version(&self) -> Option<String> {
+        self.stream.executed_version()
+    }
+
+    /// The content type of the streamed payload, as set by the function.
+    pub fn content_type(&self) -> Option<String> {
+        self.stream.content_type()
+    }
+
+    /// The tail of the function's log, once the stream is complete.
+    pub fn log_result(&self) -> Option<&str> {
+        self.log_result.as_deref()
+    }
+
+    /// True once the function has finished its response.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+impl Iterator for InvokeResponseStream {
+    type Item = Result<Vec<u8>, InvokeStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.complete {
+            return None;
+        }
+        match self.stream.next() {
+            Some(Ok(host::aws_lambda::InvokeStreamEvent::PayloadChunk(chunk))) => Some(Ok(chunk)),
+            Some(Ok(host::aws_lambda::InvokeStreamEvent::InvokeComplete(complete))) => {
+                self.complete = true;
+                self.log_result = complete.log_result;
+                complete.error_code.map(|code| {
+                    Err(InvokeStreamError::FunctionError {
+                        code,
+                        details: complete.error_details,
+                    })
+                })
+            }
+            Some(Err(e)) => {
+                self.complete = true;
+                Some(Err(e.into()))
+            }
+            None => {
+                self.complete = true;
+                None
+            }
+        }
+    }
 }
 
 /// Result from Lambda
@@ -2,6 +2,12 @@
 
 pub mod auth;
 pub mod ddb;
+pub mod ddb_cache;
+pub mod ddb_streams;
 pub mod lambda;
+#[cfg(feature = "http")]
+pub mod opensearch;
+pub mod rds_data;
 pub mod s3;
 pub mod secrets_manager;
+pub mod tenant;
@@ -0,0 +1,63 @@
+//! Host interfaces for searching Amazon OpenSearch Service
+//!
+//! Amazon OpenSearch Service domains and OpenSearch Serverless collections take the same REST
+//! calls as any other cluster, signed with SigV4. These constructors build a
+//! [SearchClient] that signs its requests; everything else is in [crate::elasticsearch].
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::aws::opensearch;
+//! use momento_functions_host::build_environment_aws_credentials;
+//! use serde_json::json;
+//!
+//! # fn f() -> Result<(), momento_functions_host::elasticsearch::SearchError> {
+//! let client = opensearch::serverless(
+//!     "https://abc123.us-east-1.aoss.amazonaws.com",
+//!     build_environment_aws_credentials!(),
+//!     "us-east-1",
+//! );
+//! let results = client.search::<serde_json::Value>(
+//!     "articles",
+//!     &json!({ "query": { "match": { "title": "latency" } } }),
+//! )?;
+//! # Ok(()) }
+//! ```
+
+use crate::aws::auth::Credentials;
+use crate::elasticsearch::{SearchAuth, SearchClient};
+
+/// A client for the Amazon OpenSearch Service domain at `endpoint`, like
+/// `https://search-products-abc123.us-east-1.es.amazonaws.com`.
+pub fn domain(
+    endpoint: impl Into<String>,
+    credentials: Credentials,
+    region: impl Into<String>,
+) -> SearchClient {
+    signed(endpoint, credentials, region, "es")
+}
+
+/// A client for the OpenSearch Serverless collection at `endpoint`, like
+/// `https://abc123.us-east-1.aoss.amazonaws.com`.
+pub fn serverless(
+    endpoint: impl Into<String>,
+    credentials: Credentials,
+    region: impl Into<String>,
+) -> SearchClient {
+    signed(endpoint, credentials, region, "aoss")
+}
+
+fn signed(
+    endpoint: impl Into<String>,
+    credentials: Credentials,
+    region: impl Into<String>,
+    service: &str,
+) -> SearchClient {
+    SearchClient::new(
+        endpoint,
+        SearchAuth::AwsSigV4 {
+            credentials,
+            region: region.into(),
+            service: service.to_string(),
+        },
+    )
+}
@@ -0,0 +1,529 @@
+//! Host interfaces for running SQL with the Amazon RDS Data API
+//!
+//! The Data API runs SQL against Aurora over HTTPS, so a Function can look up relational
+//! data without opening a database connection from wasm. Statements take named parameters,
+//! written `:name` in the SQL, and rows come back as [Row]s or deserialized into your own
+//! types with [RdsDataClient::query].
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::aws::auth::AwsCredentialsProvider;
+//! use momento_functions_host::aws::rds_data::{RdsDataClient, Statement};
+//! use momento_functions_host::build_environment_aws_credentials;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Order {
+//!     id: i64,
+//!     status: String,
+//!     total_cents: i64,
+//! }
+//!
+//! fn open_orders(customer: &str) -> Result<Vec<Order>, Box<dyn std::error::Error>> {
+//!     let credentials =
+//!         AwsCredentialsProvider::new("us-east-1", build_environment_aws_credentials!())?;
+//!     let client = RdsDataClient::new(
+//!         &credentials,
+//!         "arn:aws:rds:us-east-1:123456789012:cluster:shop",
+//!         "arn:aws:secretsmanager:us-east-1:123456789012:secret:shop-reader",
+//!     )
+//!     .database("shop");
+//!     Ok(client.query(
+//!         Statement::new(
+//!             "select id, status, total_cents from orders where customer = :customer and status <> 'closed'",
+//!         )
+//!         .bind("customer", customer),
+//!     )?)
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
+use momento_functions_wit::host::momento::host::aws_rds_data;
+use serde::de::DeserializeOwned;
+
+use super::auth;
+
+pub use crate::sql_types::{Column, Row, SqlValue};
+
+/// RDS Data API client for host interfaces.
+///
+/// This client uses Momento's host-provided AWS communication channel, which
+/// is kept hot at all times. When your Function has not run in several days or more,
+/// the channel is still hot and ready, keeping your Function invocations predictable
+/// even when your demand is unpredictable.
+pub struct RdsDataClient {
+    client: aws_rds_data::Client,
+    resource_arn: String,
+    secret_arn: String,
+    database: Option<String>,
+    schema: Option<String>,
+}
+
+/// An error returned from an RDS Data API call.
+#[derive(Debug, thiserror::Error)]
+pub enum RdsDataError {
+    /// The request was not authorized.
+    #[error("Unauthorized: {message}")]
+    Unauthorized {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The request was malformed, or the SQL is not valid.
+    #[error("Malformed request: {message}")]
+    Malformed {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The database was not found, or it is paused and resuming.
+    #[error("Database unavailable: {message}")]
+    Unavailable {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The statement ran past its timeout.
+    #[error("Statement timed out: {message}")]
+    StatementTimeout {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The request failed for some other reason.
+    #[error("RDS Data API request failed: {message}")]
+    Other {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The host is too old to run SQL.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+impl From<aws_rds_data::RdsDataError> for RdsDataError {
+    fn from(e: aws_rds_data::RdsDataError) -> Self {
+        match e {
+            aws_rds_data::RdsDataError::Unauthorized(message) => Self::Unauthorized { message },
+            aws_rds_data::RdsDataError::Malformed(message) => Self::Malformed { message },
+            aws_rds_data::RdsDataError::Unavailable(message) => Self::Unavailable { message },
+            aws_rds_data::RdsDataError::StatementTimeout(message) => {
+                Self::StatementTimeout { message }
+            }
+            aws_rds_data::RdsDataError::Other(message) => Self::Other { message },
+        }
+    }
+}
+
+/// An error occurred while running a query and deserializing its rows.
+#[derive(Debug, thiserror::Error)]
+pub enum QueryError {
+    /// The statement failed.
+    #[error(transparent)]
+    RdsData(#[from] RdsDataError),
+    /// A row could not be deserialized into the requested type.
+    #[error("Failed to deserialize row {index}.")]
+    Row {
+        /// The row's position in the result.
+        index: usize,
+        /// The underlying deserialization error.
+        cause: serde_json::Error,
+    },
+}
+
+impl From<aws_rds_data::Field> for SqlValue {
+    fn from(field: aws_rds_data::Field) -> Self {
+        match field {
+            aws_rds_data::Field::Null => SqlValue::Null,
+            aws_rds_data::Field::Boolean(b) => SqlValue::Boolean(b),
+            aws_rds_data::Field::Long(l) => SqlValue::Long(l),
+            aws_rds_data::Field::Double(d) => SqlValue::Double(d),
+            aws_rds_data::Field::Text(s) => SqlValue::Text(s),
+            aws_rds_data::Field::Blob(b) => SqlValue::Blob(b),
+        }
+    }
+}
+
+impl From<SqlValue> for aws_rds_data::Field {
+    fn from(value: SqlValue) -> Self {
+        match value {
+            SqlValue::Null => aws_rds_data::Field::Null,
+            SqlValue::Boolean(b) => aws_rds_data::Field::Boolean(b),
+            SqlValue::Long(l) => aws_rds_data::Field::Long(l),
+            SqlValue::Double(d) => aws_rds_data::Field::Double(d),
+            SqlValue::Text(s) => aws_rds_data::Field::Text(s),
+            SqlValue::Blob(b) => aws_rds_data::Field::Blob(b),
+        }
+    }
+}
+
+/// How the database should read a text parameter, for types the Data API has no value for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeHint {
+    /// `YYYY-MM-DD`
+    Date,
+    /// `HH:MM:SS[.FFF]`
+    Time,
+    /// `YYYY-MM-DD HH:MM:SS[.FFF]`
+    Timestamp,
+    /// A decimal number, kept exact.
+    Decimal,
+    /// A JSON document.
+    Json,
+    /// A UUID.
+    Uuid,
+}
+
+impl From<TypeHint> for aws_rds_data::TypeHint {
+    fn from(hint: TypeHint) -> Self {
+        match hint {
+            TypeHint::Date => aws_rds_data::TypeHint::Date,
+            TypeHint::Time => aws_rds_data::TypeHint::Time,
+            TypeHint::Timestamp => aws_rds_data::TypeHint::Timestamp,
+            TypeHint::Decimal => aws_rds_data::TypeHint::Decimal,
+            TypeHint::Json => aws_rds_data::TypeHint::Json,
+            TypeHint::Uuid => aws_rds_data::TypeHint::Uuid,
+        }
+    }
+}
+
+/// A SQL statement and its parameters.
+///
+/// Bind parameters instead of formatting values into the SQL, so they can't change what the
+/// statement does.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    sql: String,
+    parameters: Vec<aws_rds_data::SqlParameter>,
+}
+
+impl Statement {
+    /// A statement with no parameters bound yet.
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Bind `:name` to `value`.
+    pub fn bind(mut self, name: impl Into<String>, value: impl Into<SqlValue>) -> Self {
+        self.parameters.push(aws_rds_data::SqlParameter {
+            name: name.into(),
+            value: value.into().into(),
+            type_hint: None,
+        });
+        self
+    }
+
+    /// Bind `:name` to text the database should read as `hint`, like a timestamp or UUID.
+    pub fn bind_as(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+        hint: TypeHint,
+    ) -> Self {
+        self.parameters.push(aws_rds_data::SqlParameter {
+            name: name.into(),
+            value: aws_rds_data::Field::Text(value.into()),
+            type_hint: Some(hint.into()),
+        });
+        self
+    }
+}
+
+/// The result of running a statement.
+#[derive(Debug, Clone)]
+pub struct StatementOutput {
+    /// The result's columns.
+    pub columns: Vec<Column>,
+    /// The rows a query returned.
+    pub rows: Vec<Row>,
+    /// How many rows an insert, update, or delete changed.
+    pub records_updated: i64,
+    /// Values the database generated for an insert, like an auto-incremented key.
+    pub generated_fields: Vec<SqlValue>,
+}
+
+impl From<aws_rds_data::ExecuteStatementOutput> for StatementOutput {
+    fn from(output: aws_rds_data::ExecuteStatementOutput) -> Self {
+        let columns: Vec<Column> = output
+            .columns
+            .into_iter()
+            .map(|column| Column {
+                name: column.name,
+                type_name: column.type_name,
+                nullable: Some(column.nullable),
+            })
+            .collect();
+        let shared: Arc<[Column]> = columns.clone().into();
+        Self {
+            rows: output
+                .records
+                .into_iter()
+                .map(|record| {
+                    Row::new(
+                        shared.clone(),
+                        record.into_iter().map(SqlValue::from).collect(),
+                    )
+                })
+                .collect(),
+            columns,
+            records_updated: output.number_of_records_updated,
+            generated_fields: output
+                .generated_fields
+                .into_iter()
+                .map(SqlValue::from)
+                .collect(),
+        }
+    }
+}
+
+impl RdsDataClient {
+    /// Create a new RDS Data API client for the Aurora cluster `resource_arn`, signing in with
+    /// the database credentials in the Secrets Manager secret `secret_arn`.
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::aws::auth::AwsCredentialsProvider;
+    /// # use momento_functions_host::aws::rds_data::RdsDataClient;
+    /// # use momento_functions_host::build_environment_aws_credentials;
+    /// let credentials = match AwsCredentialsProvider::new(
+    ///     "us-east-1",
+    ///     build_environment_aws_credentials!(),
+    /// ) {
+    ///     Ok(credentials) => credentials,
+    ///     Err(e) => {
+    ///         eprintln!("failed to build credentials: {e}");
+    ///         return;
+    ///     }
+    /// };
+    /// let client = RdsDataClient::new(
+    ///     &credentials,
+    ///     "arn:aws:rds:us-east-1:123456789012:cluster:shop",
+    ///     "arn:aws:secretsmanager:us-east-1:123456789012:secret:shop-reader",
+    /// );
+    /// ```
+    pub fn new(
+        credentials: &auth::AwsCredentialsProvider,
+        resource_arn: impl Into<String>,
+        secret_arn: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: aws_rds_data::Client::new(credentials.resource()),
+            resource_arn: resource_arn.into(),
+            secret_arn: secret_arn.into(),
+            database: None,
+            schema: None,
+        }
+    }
+
+    /// Run statements in this database, instead of the cluster's default.
+    pub fn database(mut self, database: impl Into<String>) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    /// Run statements in this schema, instead of the database's default.
+    pub fn schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    /// Run a statement.
+    pub fn execute(&self, statement: Statement) -> Result<StatementOutput, RdsDataError> {
+        self.execute_in(statement, None)
+    }
+
+    /// Run a query, deserializing each row into `T` by column name.
+    pub fn query<T: DeserializeOwned>(&self, statement: Statement) -> Result<Vec<T>, QueryError> {
+        deserialize_rows(self.execute(statement)?)
+    }
+
+    /// Run a query that returns at most one row, deserializing it into `T`.
+    pub fn query_one<T: DeserializeOwned>(
+        &self,
+        statement: Statement,
+    ) -> Result<Option<T>, QueryError> {
+        Ok(self.query(statement)?.into_iter().next())
+    }
+
+    /// Start a transaction. Statements run through it are committed together by
+    /// [Transaction::commit], and rolled back if it is dropped without committing.
+    pub fn begin(&self) -> Result<Transaction<'_>, RdsDataError> {
+        abi::require(HostCapability::RdsData)?;
+        let id = self
+            .client
+            .begin_transaction(&aws_rds_data::TransactionRequest {
+                resource_arn: self.resource_arn.clone(),
+                secret_arn: self.secret_arn.clone(),
+                database: self.database.clone(),
+                schema: self.schema.clone(),
+            })?;
+        Ok(Transaction {
+            client: self,
+            id: Some(id),
+        })
+    }
+
+    fn execute_in(
+        &self,
+        statement: Statement,
+        transaction_id: Option<&str>,
+    ) -> Result<StatementOutput, RdsDataError> {
+        abi::require(HostCapability::RdsData)?;
+        Ok(self
+            .client
+            .execute_statement(&aws_rds_data::ExecuteStatementRequest {
+                resource_arn: self.resource_arn.clone(),
+                secret_arn: self.secret_arn.clone(),
+                sql: statement.sql,
+                database: self.database.clone(),
+                schema: self.schema.clone(),
+                parameters: statement.parameters,
+                transaction_id: transaction_id.map(str::to_string),
+            })?
+            .into())
+    }
+}
+
+/// A transaction from [RdsDataClient::begin].
+///
+/// Dropping a transaction without committing it rolls it back.
+pub struct Transaction<'a> {
+    client: &'a RdsDataClient,
+    id: Option<String>,
+}
+
+impl Transaction<'_> {
+    /// Run a statement in this transaction.
+    pub fn execute(&self, statement: Statement) -> Result<StatementOutput, RdsDataError> {
+        self.client.execute_in(statement, self.id.as_deref())
+    }
+
+    /// Run a query in this transaction, deserializing each row into `T` by column name.
+    pub fn query<T: DeserializeOwned>(&self, statement: Statement) -> Result<Vec<T>, QueryError> {
+        deserialize_rows(self.execute(statement)?)
+    }
+
+    /// Commit the transaction's statements.
+    pub fn commit(mut self) -> Result<(), RdsDataError> {
+        match self.id.take() {
+            Some(id) => Ok(self.client.client.commit_transaction(
+                &self.client.resource_arn,
+                &self.client.secret_arn,
+                &id,
+            )?),
+            None => Ok(()),
+        }
+    }
+
+    /// Undo the transaction's statements.
+    pub fn rollback(mut self) -> Result<(), RdsDataError> {
+        self.rollback_in_place()
+    }
+
+    fn rollback_in_place(&mut self) -> Result<(), RdsDataError> {
+        match self.id.take() {
+            Some(id) => Ok(self.client.client.rollback_transaction(
+                &self.client.resource_arn,
+                &self.client.secret_arn,
+                &id,
+            )?),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.rollback_in_place() {
+            log::warn!("failed to roll back abandoned transaction: {e}");
+        }
+    }
+}
+
+fn deserialize_rows<T: DeserializeOwned>(output: StatementOutput) -> Result<Vec<T>, QueryError> {
+    crate::sql_types::deserialize_rows(&output.rows, |index, cause| QueryError::Row {
+        index,
+        cause,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn column(name: &str, type_name: &str) -> aws_rds_data::ColumnMetadata {
+        aws_rds_data::ColumnMetadata {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            nullable: true,
+        }
+    }
+
+    #[test]
+    fn rows_deserialize_by_column_name() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Order {
+            id: i64,
+            note: Option<String>,
+            paid: bool,
+            attributes: Value,
+            receipt: String,
+        }
+
+        let output = StatementOutput::from(aws_rds_data::ExecuteStatementOutput {
+            columns: vec![
+                column("id", "int8"),
+                column("note", "text"),
+                column("paid", "bool"),
+                column("attributes", "jsonb"),
+                column("receipt", "bytea"),
+            ],
+            records: vec![vec![
+                aws_rds_data::Field::Long(7),
+                aws_rds_data::Field::Null,
+                aws_rds_data::Field::Boolean(true),
+                aws_rds_data::Field::Text(r#"{"gift":true}"#.to_string()),
+                aws_rds_data::Field::Blob(b"hi".to_vec()),
+            ]],
+            number_of_records_updated: 0,
+            generated_fields: vec![],
+        });
+
+        assert_eq!(Some(&SqlValue::Long(7)), output.rows[0].get("id"));
+        assert_eq!(None, output.rows[0].get("missing"));
+        let orders: Vec<Order> = deserialize_rows(output).unwrap();
+        assert_eq!(
+            vec![Order {
+                id: 7,
+                note: None,
+                paid: true,
+                attributes: serde_json::json!({"gift": true}),
+                receipt: "aGk=".to_string(),
+            }],
+            orders
+        );
+    }
+
+    #[test]
+    fn parameters_bind_in_order() {
+        let statement = Statement::new("select :a, :b, :c")
+            .bind("a", 1)
+            .bind("b", None::<&str>)
+            .bind_as("c", "2024-01-02", TypeHint::Date);
+        let names: Vec<&str> = statement
+            .parameters
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(vec!["a", "b", "c"], names);
+        assert!(matches!(
+            statement.parameters[1].value,
+            aws_rds_data::Field::Null
+        ));
+        assert!(matches!(
+            statement.parameters[2].type_hint,
+            Some(aws_rds_data::TypeHint::Date)
+        ));
+    }
+}
@@ -1,7 +1,12 @@
 //! Host interfaces for working with AWS S3
+use std::ops::Range;
+
+use momento_functions_wit::abi::{self, HostCapability};
 use momento_functions_wit::host::momento::host;
 use momento_functions_wit::host::momento::host::aws_s3::S3Error;
 
+use serde::de::DeserializeOwned;
+
 use crate::encoding::{Encode, EncodeError, Extract, ExtractError};
 
 use super::auth;
@@ -26,7 +31,7 @@ pub struct S3Client {
 /// # Examples
 ///
 /// ```rust,no_run
-/// use momento_functions_host::aws::s3::ObjectOptions;
+/// use momento_functions_host::aws::s3::{ObjectOptions, ServerSideEncryption};
 ///
 /// // Set only content-type
 /// let options = ObjectOptions {
@@ -40,6 +45,14 @@ pub struct S3Client {
 ///     content_encoding: Some("gzip".to_string()),
 ///     ..Default::default()
 /// };
+///
+/// // Encrypt with a customer managed KMS key
+/// let options = ObjectOptions {
+///     server_side_encryption: Some(ServerSideEncryption::Kms {
+///         key_id: Some("arn:aws:kms:us-east-1:123456789012:key/1234abcd-12ab-34cd-56ef-1234567890ab".to_string()),
+///     }),
+///     ..Default::default()
+/// };
 /// ```
 #[derive(Debug, Default, Clone)]
 pub struct ObjectOptions {
@@ -47,6 +60,57 @@ pub struct ObjectOptions {
     pub content_type: Option<String>,
     /// The encoding of the object (e.g. `"gzip"`, `"br"`).
     pub content_encoding: Option<String>,
+    /// How S3 should encrypt the object at rest. Only used when putting an object; S3
+    /// decrypts objects transparently on get.
+    pub server_side_encryption: Option<ServerSideEncryption>,
+}
+
+impl ObjectOptions {
+    /// Set the content type guessed from the object's key and its bytes with
+    /// [mime::guess](crate::mime::guess), for uploads whose type the caller did not say.
+    ///
+    /// ```rust,no_run
+    /// use momento_functions_host::aws::s3::ObjectOptions;
+    ///
+    /// let body = b"<!doctype html><h1>hi</h1>";
+    /// let options = ObjectOptions::default().with_guessed_content_type("pages/index", body);
+    /// assert_eq!(Some("text/html"), options.content_type.as_deref());
+    /// ```
+    pub fn with_guessed_content_type(mut self, key: &str, body: &[u8]) -> Self {
+        self.content_type = Some(crate::mime::guess(key, body).to_string());
+        self
+    }
+}
+
+/// Server-side encryption for S3 objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerSideEncryption {
+    /// SSE-S3: AES-256 with keys managed by S3.
+    S3Managed,
+    /// SSE-KMS: encryption with an AWS KMS key.
+    Kms {
+        /// The KMS key id or ARN. `None` uses the account's AWS managed `aws/s3` key.
+        key_id: Option<String>,
+    },
+}
+
+impl From<ServerSideEncryption> for host::aws_s3::ServerSideEncryption {
+    fn from(value: ServerSideEncryption) -> Self {
+        match value {
+            ServerSideEncryption::S3Managed => host::aws_s3::ServerSideEncryption::Aes256,
+            ServerSideEncryption::Kms { key_id } => host::aws_s3::ServerSideEncryption::Kms(key_id),
+        }
+    }
+}
+
+/// Options for [`S3Client::copy_object_with_options`].
+///
+/// All fields default to `None`, so you only need to set the ones you care about.
+#[derive(Debug, Default, Clone)]
+pub struct CopyOptions {
+    /// How S3 should encrypt the copy at rest. When `None`, the destination bucket's default
+    /// encryption applies.
+    pub server_side_encryption: Option<ServerSideEncryption>,
 }
 
 /// The result of a [`S3Client::get_with_options`] call.
@@ -94,6 +158,245 @@ where
     S3Error(#[from] S3Error),
 }
 
+/// An error occurred while selecting typed records from an S3 object.
+#[derive(Debug, thiserror::Error)]
+pub enum S3SelectError {
+    /// A selected record could not be deserialized.
+    #[error("Failed to deserialize selected record {index}: {cause}")]
+    Decode {
+        /// The record's position in the results, starting at 0.
+        index: usize,
+        /// The underlying deserialization error.
+        cause: serde_json::Error,
+    },
+    /// An error occurred when calling the host s3 interface.
+    #[error(transparent)]
+    S3Error(#[from] S3Error),
+}
+
+/// An S3 Select query: a SQL expression, and how the object and the results are formatted.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use momento_functions_host::aws::s3::{Compression, CsvHeader, CsvInput, SelectInput, SelectQuery};
+///
+/// let query = SelectQuery::new(
+///     "SELECT s.id, s.total FROM S3Object s WHERE CAST(s.total AS INT) > 100",
+///     SelectInput::Csv(CsvInput {
+///         header: CsvHeader::Use,
+///         ..Default::default()
+///     }),
+/// )
+/// .compression(Compression::Gzip);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SelectQuery {
+    expression: String,
+    input: SelectInput,
+    compression: Compression,
+    output: SelectOutput,
+    scan_range: Option<Range<u64>>,
+}
+
+impl SelectQuery {
+    /// Run `expression`, like `SELECT s.id FROM S3Object s WHERE s.status = 'open'`, against
+    /// an object in the `input` format. Results are JSON, one record per line, unless you
+    /// change it with [SelectQuery::output].
+    pub fn new(expression: impl Into<String>, input: SelectInput) -> Self {
+        Self {
+            expression: expression.into(),
+            input,
+            compression: Compression::None,
+            output: SelectOutput::Json {
+                record_delimiter: None,
+            },
+            scan_range: None,
+        }
+    }
+
+    /// How the object is compressed.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// How the results are formatted.
+    pub fn output(mut self, output: SelectOutput) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Only scan this byte range of the object. Records that start in the range are
+    /// returned whole, so an object can be split into ranges and queried in parallel.
+    pub fn scan_range(mut self, scan_range: Range<u64>) -> Self {
+        self.scan_range = Some(scan_range);
+        self
+    }
+}
+
+/// The format of an object queried with S3 Select.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectInput {
+    /// A CSV object.
+    Csv(CsvInput),
+    /// A JSON object.
+    Json(JsonInputType),
+    /// A Parquet object.
+    Parquet,
+}
+
+/// How a CSV object is read.
+///
+/// All fields default to S3's defaults, so you only need to set the ones you care about.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CsvInput {
+    /// How the first line is treated.
+    pub header: CsvHeader,
+    /// The character between fields. Defaults to `,`.
+    pub field_delimiter: Option<String>,
+    /// The character between records. Defaults to a newline.
+    pub record_delimiter: Option<String>,
+    /// The character fields are quoted with. Defaults to `"`.
+    pub quote_character: Option<String>,
+    /// Lines starting with this character are skipped.
+    pub comments: Option<String>,
+    /// Whether quoted fields may contain the record delimiter.
+    pub allow_quoted_record_delimiter: bool,
+}
+
+/// How the first line of a CSV object is treated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CsvHeader {
+    /// The first line is a record. Columns are referred to by position, like `s._1`.
+    #[default]
+    None,
+    /// The first line holds column names, but columns are referred to by position.
+    Ignore,
+    /// The first line holds column names, and columns may be referred to by name.
+    Use,
+}
+
+/// How a JSON object is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonInputType {
+    /// The object is one JSON document.
+    Document,
+    /// The object holds one JSON value per line.
+    Lines,
+}
+
+/// How an object queried with S3 Select is compressed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Not compressed.
+    #[default]
+    None,
+    /// Compressed with gzip.
+    Gzip,
+    /// Compressed with bzip2.
+    Bzip2,
+}
+
+/// How the results of an S3 Select query are formatted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectOutput {
+    /// CSV records.
+    Csv {
+        /// The character between fields. Defaults to `,`.
+        field_delimiter: Option<String>,
+        /// The character between records. Defaults to a newline.
+        record_delimiter: Option<String>,
+    },
+    /// JSON objects.
+    Json {
+        /// The character between records. Defaults to a newline.
+        record_delimiter: Option<String>,
+    },
+}
+
+/// The results of an S3 Select query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectResults {
+    /// The selected records, in the query's output format.
+    pub records: Vec<u8>,
+    /// How much of the object was scanned, if S3 reported it.
+    pub stats: Option<SelectStats>,
+}
+
+/// How much of an object an S3 Select query read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectStats {
+    /// The bytes of the object that were scanned.
+    pub bytes_scanned: u64,
+    /// The bytes that were processed, after decompression.
+    pub bytes_processed: u64,
+    /// The bytes of records that were returned.
+    pub bytes_returned: u64,
+}
+
+impl SelectQuery {
+    fn into_request(self, bucket: String, key: String) -> host::aws_s3::SelectObjectContentRequest {
+        host::aws_s3::SelectObjectContentRequest {
+            bucket,
+            key,
+            expression: self.expression,
+            input: match self.input {
+                SelectInput::Csv(csv) => host::aws_s3::SelectInput::Csv(host::aws_s3::CsvInput {
+                    header: match csv.header {
+                        CsvHeader::None => host::aws_s3::CsvHeader::None,
+                        CsvHeader::Ignore => host::aws_s3::CsvHeader::Ignore,
+                        CsvHeader::Use => host::aws_s3::CsvHeader::UseNames,
+                    },
+                    field_delimiter: csv.field_delimiter,
+                    record_delimiter: csv.record_delimiter,
+                    quote_character: csv.quote_character,
+                    comments: csv.comments,
+                    allow_quoted_record_delimiter: csv.allow_quoted_record_delimiter,
+                }),
+                SelectInput::Json(json) => host::aws_s3::SelectInput::Json(match json {
+                    JsonInputType::Document => host::aws_s3::JsonInputType::Document,
+                    JsonInputType::Lines => host::aws_s3::JsonInputType::Lines,
+                }),
+                SelectInput::Parquet => host::aws_s3::SelectInput::Parquet,
+            },
+            compression: match self.compression {
+                Compression::None => host::aws_s3::SelectCompression::None,
+                Compression::Gzip => host::aws_s3::SelectCompression::Gzip,
+                Compression::Bzip2 => host::aws_s3::SelectCompression::Bzip2,
+            },
+            output: match self.output {
+                SelectOutput::Csv {
+                    field_delimiter,
+                    record_delimiter,
+                } => host::aws_s3::SelectOutput::Csv(host::aws_s3::CsvOutput {
+                    field_delimiter,
+                    record_delimiter,
+                }),
+                SelectOutput::Json { record_delimiter } => {
+                    host::aws_s3::SelectOutput::Json(record_delimiter)
+                }
+            },
+            scan_range: self.scan_range.map(|range| (range.start, range.end)),
+        }
+    }
+}
+
+/// Deserialize JSON records separated by `delimiter`.
+fn json_records<T: DeserializeOwned>(
+    records: &[u8],
+    delimiter: &str,
+) -> Result<Vec<T>, S3SelectError> {
+    String::from_utf8_lossy(records)
+        .split(delimiter)
+        .filter(|record| !record.trim().is_empty())
+        .enumerate()
+        .map(|(index, record)| {
+            serde_json::from_str(record).map_err(|cause| S3SelectError::Decode { index, cause })
+        })
+        .collect()
+}
+
 impl S3Client {
     /// Create a new S3 client.
     ///
@@ -185,7 +488,7 @@ impl S3Client {
     /// Put an object into an S3 bucket with additional options.
     ///
     /// This is the same as [`put`](S3Client::put), but allows you to specify
-    /// content-type and content-encoding for the object.
+    /// content-type, content-encoding, and server-side encryption for the object.
     ///
     /// # Examples
     ///
@@ -213,26 +516,178 @@ impl S3Client {
         body: E,
         options: ObjectOptions,
     ) -> Result<(), S3PutError<E::Error>> {
-        let _output = self
-            .client
-            .put_extended(
-                &host::aws_s3::PutObjectRequest {
-                    bucket: bucket.into(),
-                    key: key.into(),
-                    body: body
-                        .try_serialize()
-                        .map_err(|e| S3PutError::EncodeFailed { cause: e })?
-                        .into(),
-                },
-                &host::aws_s3::ObjectOptions {
-                    content_type: options.content_type,
-                    content_encoding: options.content_encoding,
-                },
-            )
-            .map_err(S3PutError::from)?;
+        let request = host::aws_s3::PutObjectRequest {
+            bucket: bucket.into(),
+            key: key.into(),
+            body: body
+                .try_serialize()
+                .map_err(|e| S3PutError::EncodeFailed { cause: e })?
+                .into(),
+        };
+        let object_options = host::aws_s3::ObjectOptions {
+            content_type: options.content_type,
+            content_encoding: options.content_encoding,
+        };
+        let _output = match options.server_side_encryption {
+            Some(encryption) => {
+                require(HostCapability::S3ServerSideEncryption)?;
+                self.client
+                    .put_encrypted(&request, &object_options, &encryption.into())
+            }
+            None => self.client.put_extended(&request, &object_options),
+        }
+        .map_err(S3PutError::from)?;
         Ok(())
     }
 
+    /// Copy an object within S3, without reading it into your Function.
+    ///
+    /// The copy keeps the source object's metadata, like content-type. To move an object,
+    /// copy it and then delete the source.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::aws::s3::S3Client;
+    /// # let client: S3Client = todo!();
+    /// match client.copy_object("uploads", "incoming/report.pdf", "archive", "2024/report.pdf") {
+    ///     Ok(()) => {}
+    ///     Err(e) => eprintln!("copy_object failed: {e}"),
+    /// }
+    /// ```
+    pub fn copy_object(
+        &self,
+        source_bucket: impl Into<String>,
+        source_key: impl Into<String>,
+        destination_bucket: impl Into<String>,
+        destination_key: impl Into<String>,
+    ) -> Result<(), S3Error> {
+        self.copy_object_with_options(
+            source_bucket,
+            source_key,
+            destination_bucket,
+            destination_key,
+            CopyOptions::default(),
+        )
+    }
+
+    /// Copy an object within S3 with additional options, like encrypting the copy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::aws::s3::{CopyOptions, S3Client, ServerSideEncryption};
+    /// # let client: S3Client = todo!();
+    /// match client.copy_object_with_options(
+    ///     "uploads",
+    ///     "incoming/report.pdf",
+    ///     "archive",
+    ///     "2024/report.pdf",
+    ///     CopyOptions {
+    ///         server_side_encryption: Some(ServerSideEncryption::S3Managed),
+    ///     },
+    /// ) {
+    ///     Ok(()) => {}
+    ///     Err(e) => eprintln!("copy_object_with_options failed: {e}"),
+    /// }
+    /// ```
+    pub fn copy_object_with_options(
+        &self,
+        source_bucket: impl Into<String>,
+        source_key: impl Into<String>,
+        destination_bucket: impl Into<String>,
+        destination_key: impl Into<String>,
+        options: CopyOptions,
+    ) -> Result<(), S3Error> {
+        require(HostCapability::S3Copy)?;
+        let _output = self.client.copy(&host::aws_s3::CopyObjectRequest {
+            source_bucket: source_bucket.into(),
+            source_key: source_key.into(),
+            destination_bucket: destination_bucket.into(),
+            destination_key: destination_key.into(),
+            server_side_encryption: options.server_side_encryption.map(Into::into),
+        })?;
+        Ok(())
+    }
+
+    /// Run an S3 Select query against an object, returning just the records it selects.
+    ///
+    /// S3 filters the object, so only the selected records are transferred to your
+    /// Function. Use [`select`](S3Client::select) to deserialize JSON records into a type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::aws::s3::{JsonInputType, S3Client, SelectInput, SelectQuery};
+    /// # let client: S3Client = todo!();
+    /// let query = SelectQuery::new(
+    ///     "SELECT * FROM S3Object s WHERE s.level = 'error' LIMIT 100",
+    ///     SelectInput::Json(JsonInputType::Lines),
+    /// );
+    /// match client.select_object_content("logs", "2024/06/01.jsonl", query) {
+    ///     Ok(results) => println!("{} bytes of errors", results.records.len()),
+    ///     Err(e) => eprintln!("select_object_content failed: {e}"),
+    /// }
+    /// ```
+    pub fn select_object_content(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        query: SelectQuery,
+    ) -> Result<SelectResults, S3Error> {
+        require(HostCapability::S3Select)?;
+        let request = query.into_request(bucket.into(), key.into());
+        let output = self.client.select_object_content(&request)?;
+        Ok(SelectResults {
+            records: output.records,
+            stats: output.stats.map(|stats| SelectStats {
+                bytes_scanned: stats.bytes_scanned,
+                bytes_processed: stats.bytes_processed,
+                bytes_returned: stats.bytes_returned,
+            }),
+        })
+    }
+
+    /// Run an S3 Select query against an object, deserializing each selected record.
+    ///
+    /// The query's output is always JSON, whatever [SelectQuery::output] says.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::aws::s3::{CsvHeader, CsvInput, S3Client, SelectInput, SelectQuery};
+    /// # let client: S3Client = todo!();
+    /// #[derive(serde::Deserialize)]
+    /// struct Order {
+    ///     id: String,
+    ///     total: String,
+    /// }
+    ///
+    /// let query = SelectQuery::new(
+    ///     "SELECT s.id, s.total FROM S3Object s WHERE s.customer = 'bob'",
+    ///     SelectInput::Csv(CsvInput {
+    ///         header: CsvHeader::Use,
+    ///         ..Default::default()
+    ///     }),
+    /// );
+    /// match client.select::<Order>("exports", "orders.csv", query) {
+    ///     Ok(orders) => println!("bob has {} orders", orders.len()),
+    ///     Err(e) => eprintln!("select failed: {e}"),
+    /// }
+    /// ```
+    pub fn select<T: DeserializeOwned>(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        query: SelectQuery,
+    ) -> Result<Vec<T>, S3SelectError> {
+        let query = query.output(SelectOutput::Json {
+            record_delimiter: None,
+        });
+        let results = self.select_object_content(bucket, key, query)?;
+        json_records(&results.records, "\n")
+    }
+
     /// Get an object from an S3 bucket.
     ///
     /// The output's body is wrapped in an `Option`, with `None` indicating the object
@@ -350,3 +805,48 @@ impl S3Client {
         }
     }
 }
+
+/// Check that the host provides `capability`, reporting it as an S3 error if not.
+fn require(capability: HostCapability) -> Result<(), S3Error> {
+    abi::require(capability).map_err(|e| S3Error::Other(e.to_string()))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selected_json_records_are_deserialized_in_order() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Order {
+            id: String,
+        }
+
+        let records = b"{\"id\":\"a\"}\n{\"id\":\"b\"}\n";
+        let orders: Vec<Order> = json_records(records, "\n").unwrap();
+        assert_eq!(
+            vec![Order { id: "a".into() }, Order { id: "b".into() }],
+            orders
+        );
+
+        let error = json_records::<Order>(b"{\"id\":\"a\"}\n{\"id\":7}", "\n").unwrap_err();
+        assert!(matches!(error, S3SelectError::Decode { index: 1, .. }));
+    }
+
+    #[test]
+    fn queries_default_to_json_lines_output() {
+        let query =
+            SelectQuery::new("SELECT * FROM S3Object", SelectInput::Parquet).scan_range(0..1024);
+        let request = query.into_request("exports".to_string(), "orders.parquet".to_string());
+        assert!(matches!(
+            request.output,
+            host::aws_s3::SelectOutput::Json(None)
+        ));
+        assert!(matches!(
+            request.compression,
+            host::aws_s3::SelectCompression::None
+        ));
+        assert_eq!(Some((0, 1024)), request.scan_range);
+    }
+}
@@ -0,0 +1,251 @@
+//! AWS credentials chosen per request, for Functions that serve many tenants
+//!
+//! A multi-tenant Function often needs each caller's requests to run as that tenant's own AWS
+//! role, so one tenant can never reach another's data. The credentials are only known once a
+//! request arrives, for example from the caller's token metadata or a secret fetched for them.
+//! [TenantCredentialsCache] builds an [AwsCredentialsProvider] from those values the first time
+//! a tenant is seen, and hands back the same provider for that tenant's later requests instead
+//! of authenticating again.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use std::sync::LazyLock;
+//! use std::time::Duration;
+//!
+//! use momento_functions_host::aws::auth::Credentials;
+//! use momento_functions_host::aws::s3::S3Client;
+//! use momento_functions_host::aws::tenant::{TenantCredentialsCache, TenantCredentialsError};
+//! use momento_functions_host::web_extensions::headers;
+//!
+//! static CREDENTIALS: LazyLock<TenantCredentialsCache> =
+//!     LazyLock::new(|| TenantCredentialsCache::new().with_ttl(Duration::from_secs(15 * 60)));
+//!
+//! # #[derive(Debug, thiserror::Error)]
+//! # #[error("unknown tenant")]
+//! # struct UnknownTenant;
+//! fn s3_for_caller() -> Result<S3Client, TenantCredentialsError<UnknownTenant>> {
+//!     let tenant = headers().get("x-tenant-id").ok_or(TenantCredentialsError::Resolve {
+//!         cause: UnknownTenant,
+//!     })?;
+//!     let provider = CREDENTIALS.get_or_create(tenant, "us-east-1", || {
+//!         // Look the tenant up however you like; this runs only on a cache miss.
+//!         Ok(Credentials::Federated {
+//!             role_arn: format!("arn:aws:iam::123456789012:role/tenant-{tenant}"),
+//!         })
+//!     })?;
+//!     Ok(S3Client::new(&provider))
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use momento_functions_wit::host::momento::host::aws_auth::AuthError;
+
+use super::auth::{AwsCredentialsProvider, Credentials};
+
+/// An error occurred while getting a tenant's credentials provider.
+#[derive(Debug, thiserror::Error)]
+pub enum TenantCredentialsError<E>
+where
+    E: std::error::Error,
+{
+    /// The tenant's credentials could not be resolved.
+    #[error("Failed to resolve tenant credentials: {cause}")]
+    Resolve {
+        /// The error returned while resolving the credentials.
+        cause: E,
+    },
+    /// The resolved credentials were rejected by the host.
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+}
+
+/// Credentials providers for each tenant, created from credentials resolved at request time.
+///
+/// Providers are keyed by tenant and region. Keep one cache for the Function instance, in a
+/// static or in the Function's extensions, so that it outlives each request.
+pub struct TenantCredentialsCache {
+    providers: Mutex<TenantMap<Arc<AwsCredentialsProvider>>>,
+}
+
+impl Default for TenantCredentialsCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TenantCredentialsCache {
+    /// The number of tenants kept by default.
+    pub const DEFAULT_MAX_TENANTS: usize = 1024;
+
+    /// A cache for up to [DEFAULT_MAX_TENANTS](Self::DEFAULT_MAX_TENANTS) tenants, whose
+    /// providers do not expire.
+    pub fn new() -> Self {
+        Self {
+            providers: Mutex::new(TenantMap::new(Self::DEFAULT_MAX_TENANTS, None)),
+        }
+    }
+
+    /// Resolve each tenant's credentials again once its provider is `ttl` old.
+    ///
+    /// Use this when tenants' credentials rotate, so a rotated secret is picked up.
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        self.configure(|providers| providers.ttl = Some(ttl))
+    }
+
+    /// Keep providers for at most `max_tenants` tenants. When a new tenant arrives at a full
+    /// cache, the provider that was created longest ago is dropped.
+    pub fn with_max_tenants(self, max_tenants: usize) -> Self {
+        self.configure(|providers| providers.max_entries = max_tenants.max(1))
+    }
+
+    /// The provider for `tenant` in `region`, creating it from the credentials returned by
+    /// `resolve` if there is none yet.
+    ///
+    /// `resolve` runs only when the cache has no current provider for the tenant, so this is
+    /// the place to fetch the tenant's secret or look up their role.
+    pub fn get_or_create<E: std::error::Error>(
+        &self,
+        tenant: impl AsRef<str>,
+        region: impl AsRef<str>,
+        resolve: impl FnOnce() -> Result<Credentials, E>,
+    ) -> Result<Arc<AwsCredentialsProvider>, TenantCredentialsError<E>> {
+        let key = (tenant.as_ref().to_string(), region.as_ref().to_string());
+        if let Some(provider) = self.lock().get(&key, Instant::now()) {
+            return Ok(provider);
+        }
+        // Resolving can call other host interfaces, so don't hold the lock while it runs.
+        let credentials = resolve().map_err(|cause| TenantCredentialsError::Resolve { cause })?;
+        let provider = Arc::new(AwsCredentialsProvider::new(&key.1, credentials)?);
+        self.lock()
+            .insert(key, Arc::clone(&provider), Instant::now());
+        Ok(provider)
+    }
+
+    /// Drop the providers for `tenant` in every region, so their credentials are resolved
+    /// again on the next request. Call this when a tenant's credentials are revoked.
+    pub fn invalidate(&self, tenant: impl AsRef<str>) {
+        let tenant = tenant.as_ref();
+        self.lock().retain(|(key_tenant, _)| key_tenant != tenant);
+    }
+
+    /// Drop every provider.
+    pub fn clear(&self) {
+        self.lock().retain(|_| false);
+    }
+
+    fn configure(
+        self,
+        configure: impl FnOnce(&mut TenantMap<Arc<AwsCredentialsProvider>>),
+    ) -> Self {
+        configure(&mut self.lock());
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TenantMap<Arc<AwsCredentialsProvider>>> {
+        self.providers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A (tenant, region) key.
+type TenantKey = (String, String);
+
+/// Values keyed by tenant, each expiring `ttl` after it was inserted, and at most
+/// `max_entries` of them.
+struct TenantMap<T> {
+    entries: HashMap<TenantKey, (T, Instant)>,
+    max_entries: usize,
+    ttl: Option<Duration>,
+}
+
+impl<T: Clone> TenantMap<T> {
+    fn new(max_entries: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries,
+            ttl,
+        }
+    }
+
+    fn get(&mut self, key: &TenantKey, now: Instant) -> Option<T> {
+        let (value, created_at) = self.entries.get(key)?;
+        if self
+            .ttl
+            .is_some_and(|ttl| ttl <= now.saturating_duration_since(*created_at))
+        {
+            self.entries.remove(key);
+            return None;
+        }
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: TenantKey, value: T, now: Instant) {
+        while !self.entries.contains_key(&key) && self.max_entries <= self.entries.len() {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, created_at))| *created_at)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+        self.entries.insert(key, (value, now));
+    }
+
+    fn retain(&mut self, keep: impl Fn(&TenantKey) -> bool) {
+        self.entries.retain(|key, _| keep(key));
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn key(tenant: &str) -> TenantKey {
+        (tenant.to_string(), "us-east-1".to_string())
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let start = Instant::now();
+        let mut map = TenantMap::new(10, Some(Duration::from_secs(60)));
+        map.insert(key("a"), 1, start);
+        assert_eq!(Some(1), map.get(&key("a"), start + Duration::from_secs(59)));
+        assert_eq!(None, map.get(&key("a"), start + Duration::from_secs(60)));
+        assert_eq!(None, map.get(&key("b"), start));
+
+        let mut forever = TenantMap::new(10, None);
+        forever.insert(key("a"), 1, start);
+        assert_eq!(
+            Some(1),
+            forever.get(&key("a"), start + Duration::from_secs(1 << 20))
+        );
+    }
+
+    #[test]
+    fn full_maps_drop_the_oldest_entry() {
+        let start = Instant::now();
+        let mut map = TenantMap::new(2, None);
+        map.insert(key("a"), 1, start);
+        map.insert(key("b"), 2, start + Duration::from_secs(1));
+        // Replacing an entry does not evict another.
+        map.insert(key("b"), 3, start + Duration::from_secs(2));
+        assert_eq!(Some(1), map.get(&key("a"), start));
+
+        map.insert(key("c"), 4, start + Duration::from_secs(3));
+        assert_eq!(None, map.get(&key("a"), start));
+        assert_eq!(Some(3), map.get(&key("b"), start));
+        assert_eq!(Some(4), map.get(&key("c"), start));
+
+        map.retain(|(tenant, _)| tenant != "b");
+        assert_eq!(None, map.get(&key("b"), start));
+        assert_eq!(Some(4), map.get(&key("c"), start));
+    }
+}
@@ -1,8 +1,11 @@
 //! Host interfaces for working with Momento Cache apis
 
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::encoding::{Encode, EncodeError, Extract, ExtractError};
+use crate::host_calls::timed;
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
 use momento_functions_wit::host::momento::functions::cache_list;
 use momento_functions_wit::host::momento::functions::cache_scalar;
 
@@ -72,7 +75,9 @@ pub enum CacheGetError<E: ExtractError> {
 /// }
 /// ```
 pub fn get<T: Extract>(key: impl AsRef<[u8]>) -> Result<Option<T>, CacheGetError<T::Error>> {
-    match cache_scalar::get(key.as_ref())? {
+    let value = timed("cache.get", || cache_scalar::get(key.as_ref()))?;
+    record_lookup(value.is_some());
+    match value {
         Some(v) => T::extract(v)
             .map(Some)
             .map_err(|e| CacheGetError::ExtractFailed { cause: e }),
@@ -123,14 +128,13 @@ pub fn set<E: Encode>(
     value: E,
     ttl: Duration,
 ) -> Result<(), CacheSetError<E::Error>> {
-    cache_scalar::set(
-        key.as_ref(),
-        &value
-            .try_serialize()
-            .map_err(|e| CacheSetError::EncodeFailed { cause: e })?
-            .into(),
-        saturate_ttl(ttl),
-    )
+    let value = value
+        .try_serialize()
+        .map_err(|e| CacheSetError::EncodeFailed { cause: e })?
+        .into();
+    timed("cache.set", || {
+        cache_scalar::set(key.as_ref(), &value, saturate_ttl(ttl))
+    })
     .map_err(Into::into)
 }
 
@@ -222,15 +226,13 @@ pub fn set_if<E: Encode>(
     ttl: Duration,
     condition: SetIfCondition,
 ) -> Result<SetIfResult, CacheSetIfError<E::Error>> {
-    cache_scalar::set_if(
-        key.as_ref(),
-        &value
-            .try_serialize()
-            .map_err(|e| CacheSetIfError::EncodeFailed { cause: e })?
-            .into(),
-        saturate_ttl(ttl),
-        &condition,
-    )
+    let value = value
+        .try_serialize()
+        .map_err(|e| CacheSetIfError::EncodeFailed { cause: e })?
+        .into();
+    timed("cache.set_if", || {
+        cache_scalar::set_if(key.as_ref(), &value, saturate_ttl(ttl), &condition)
+    })
     .map_err(Into::into)
 }
 
@@ -249,7 +251,119 @@ pub fn set_if<E: Encode>(
 /// }
 /// ```
 pub fn delete(key: impl AsRef<[u8]>) -> Result<(), CacheDeleteError> {
-    cache_scalar::delete(key.as_ref()).map_err(Into::into)
+    timed("cache.delete", || cache_scalar::delete(key.as_ref())).map_err(Into::into)
+}
+
+/// An error occurred when reading an item's metadata.
+#[derive(thiserror::Error, Debug)]
+pub enum CacheItemMetadataError {
+    /// The host does not provide item metadata.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+    /// An error occurred when calling the host cache function.
+    #[error(transparent)]
+    CacheError(#[from] cache_scalar::Error),
+}
+
+/// Details about a stored item, read without transferring its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemMetadata {
+    /// The size of the stored value, in bytes.
+    pub size_bytes: u64,
+    /// How long until the item expires, or `None` if it does not expire.
+    pub remaining_ttl: Option<Duration>,
+    /// When the item was last read, if the cache tracks it.
+    pub last_accessed: Option<SystemTime>,
+}
+
+/// Read the size and remaining time-to-live of an item, without reading its value.
+///
+/// Reading metadata does not count as a hit or a miss in [stats]. A last access time too far in
+/// the future to represent is reported as unknown.
+///
+/// Examples:
+/// ________
+/// ```rust,no_run
+/// # use momento_functions_host::cache;
+/// match cache::item_metadata("my_key") {
+///     Ok(Some(metadata)) if metadata.size_bytes < 1024 => { /* not worth compressing */ }
+///     Ok(Some(metadata)) => { /* compress */ }
+///     Ok(None) => { /* key not found */ }
+///     Err(e) => eprintln!("cache item_metadata failed: {e}"),
+/// }
+/// ```
+pub fn item_metadata(
+    key: impl AsRef<[u8]>,
+) -> Result<Option<ItemMetadata>, CacheItemMetadataError> {
+    abi::require(HostCapability::CacheItemMetadata)?;
+    Ok(timed("cache.item_metadata", || {
+        cache_scalar::get_item_metadata(key.as_ref())
+    })?
+    .map(|metadata| ItemMetadata {
+        size_bytes: metadata.size_bytes,
+        remaining_ttl: metadata
+            .remaining_ttl_milliseconds
+            .map(Duration::from_millis),
+        last_accessed: metadata
+            .last_accessed_epoch_millis
+            .and_then(|millis| UNIX_EPOCH.checked_add(Duration::from_millis(millis))),
+    }))
+}
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+fn record_lookup(hit: bool) {
+    if hit { &HITS } else { &MISSES }.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Hits and misses of [get] and [get_with_hash] in this Function instance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups that found a value.
+    pub hits: u64,
+    /// Lookups that found nothing.
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// The fraction of lookups that were hits, or `None` before the first lookup.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (0 < lookups).then(|| self.hits as f64 / lookups as f64)
+    }
+}
+
+/// The cache hits and misses counted since this Function instance started, or since the last
+/// [reset_stats].
+///
+/// Lookups that fail with an error are not counted. The counters belong to this instance
+/// only, so they are meant to be logged or published as metrics, not compared between
+/// instances.
+///
+/// Examples:
+/// ________
+/// ```rust,no_run
+/// # use momento_functions_host::cache;
+/// let stats = cache::stats();
+/// if let Some(ratio) = stats.hit_ratio() {
+///     log::info!("cache hit ratio {ratio:.2} over {} lookups", stats.hits + stats.misses);
+/// }
+/// ```
+pub fn stats() -> CacheStats {
+    CacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
+
+/// Reset the counters of [stats] to zero, returning what they were. Use this to report
+/// counts per interval rather than since the instance started.
+pub fn reset_stats() -> CacheStats {
+    CacheStats {
+        hits: HITS.swap(0, Ordering::Relaxed),
+        misses: MISSES.swap(0, Ordering::Relaxed),
+    }
 }
 
 /// An error occurred when getting a value with its hash from the cache.
@@ -308,7 +422,11 @@ pub struct GetWithHashValue<T> {
 pub fn get_with_hash<T: Extract>(
     key: impl AsRef<[u8]>,
 ) -> Result<Option<GetWithHashValue<T>>, CacheGetWithHashError<T::Error>> {
-    match cache_scalar::get_with_hash(key.as_ref())? {
+    let result = timed("cache.get_with_hash", || {
+        cache_scalar::get_with_hash(key.as_ref())
+    })?;
+    record_lookup(matches!(result, GetWithHashResult::Found(_)));
+    match result {
         GetWithHashResult::Found(found) => {
             let value = T::extract(found.value)
                 .map_err(|e| CacheGetWithHashError::ExtractFailed { cause: e })?;
@@ -359,15 +477,13 @@ pub fn set_if_hash<E: Encode>(
     ttl: Duration,
     condition: SetIfHashCondition,
 ) -> Result<SetIfHashResult, CacheSetIfHashError<E::Error>> {
-    cache_scalar::set_if_hash(
-        key.as_ref(),
-        &value
-            .try_serialize()
-            .map_err(|e| CacheSetIfHashError::EncodeFailed { cause: e })?
-            .into(),
-        saturate_ttl(ttl),
-        &condition,
-    )
+    let value = value
+        .try_serialize()
+        .map_err(|e| CacheSetIfHashError::EncodeFailed { cause: e })?
+        .into();
+    timed("cache.set_if_hash", || {
+        cache_scalar::set_if_hash(key.as_ref(), &value, saturate_ttl(ttl), &condition)
+    })
     .map_err(Into::into)
 }
 
@@ -0,0 +1,381 @@
+//! Batching analytics events to ClickHouse over its HTTP interface
+//!
+//! [ClickHouseClient] inserts rows as `JSONEachRow` and runs simple `SELECT`s, deserializing
+//! each row by column name. For a steady flow of events, an [Inserter] buffers rows and
+//! sends them in chunks bounded by row count and size, so one large batch doesn't become one
+//! oversized insert.
+//!
+//! ClickHouse pushes back when it is merging too many parts or running too many queries.
+//! Chunks that are pushed back are retried with the client's [RetryPolicy], and if they
+//! still fail, [Inserter] keeps their rows so the Function can try again later instead of
+//! losing them.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::clickhouse::{ClickHouseClient, ClickHouseError, Query};
+//!
+//! #[derive(serde::Serialize)]
+//! struct PageView<'a> {
+//!     path: &'a str,
+//!     user_id: &'a str,
+//!     at_millis: u64,
+//! }
+//!
+//! #[derive(serde::Deserialize)]
+//! struct TopPage {
+//!     path: String,
+//!     views: u64,
+//! }
+//!
+//! fn record(views: &[PageView]) -> Result<Vec<TopPage>, ClickHouseError> {
+//!     let client = ClickHouseClient::new("https://abc123.us-east-1.aws.clickhouse.cloud:8443")
+//!         .credentials("default", std::env::var("CLICKHOUSE_KEY").unwrap_or_default())
+//!         .database("analytics");
+//!     client.insert("page_views", views)?;
+//!     client.select(
+//!         Query::new(
+//!             "SELECT path, count() AS views FROM page_views WHERE user_id = {user:String} \
+//!              GROUP BY path ORDER BY views DESC LIMIT 10",
+//!         )
+//!         .param("user", "user-7"),
+//!     )
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::fmt::Write;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::http::{self, HttpPostError, encode_component};
+use crate::retry::{self, RetryPolicy};
+
+/// The ClickHouse error codes that mean the server is pushing back, and the insert should be
+/// retried later: `TOO_MANY_SIMULTANEOUS_QUERIES`, `MEMORY_LIMIT_EXCEEDED`, and
+/// `TOO_MANY_PARTS`.
+const BACKPRESSURE_CODES: [u32; 3] = [202, 241, 252];
+
+/// An error occurred while talking to ClickHouse.
+#[derive(Debug, thiserror::Error)]
+pub enum ClickHouseError {
+    /// The request to ClickHouse failed.
+    #[error(transparent)]
+    Http(#[from] HttpPostError<Infallible>),
+    /// ClickHouse rejected the query.
+    #[error("ClickHouse returned status {status}: {message}")]
+    Server {
+        /// The HTTP status ClickHouse responded with.
+        status: u16,
+        /// ClickHouse's error code, from the `X-ClickHouse-Exception-Code` header.
+        code: Option<u32>,
+        /// The body of ClickHouse's response.
+        message: String,
+    },
+    /// A row could not be encoded as JSON.
+    #[error("Failed to encode row.")]
+    Encode {
+        /// The underlying encoding error.
+        cause: serde_json::Error,
+    },
+    /// A row of a result could not be decoded.
+    #[error("Failed to decode row {index}.")]
+    Decode {
+        /// The row's position in the result.
+        index: usize,
+        /// The underlying decoding error.
+        cause: serde_json::Error,
+    },
+}
+
+impl ClickHouseError {
+    /// Whether ClickHouse is pushing back on load, so the request should be retried later.
+    pub fn is_backpressure(&self) -> bool {
+        match self {
+            ClickHouseError::Server { status, code, .. } => {
+                *status == 429
+                    || *status == 503
+                    || code.is_some_and(|code| BACKPRESSURE_CODES.contains(&code))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A `SELECT` and the values of its `{name:Type}` parameters.
+#[derive(Debug, Clone)]
+pub struct Query {
+    sql: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl Query {
+    /// A query with no parameters bound yet. Leave out the `FORMAT` clause; results are
+    /// always requested as `JSONEachRow`.
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Bind the parameter written `{name:Type}` in the SQL to `value`, in ClickHouse's text
+    /// form for its type.
+    pub fn param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.parameters.push((name.into(), value.to_string()));
+        self
+    }
+}
+
+/// A client for ClickHouse's HTTP interface.
+#[derive(Debug, Clone)]
+pub struct ClickHouseClient {
+    url: String,
+    user: Option<String>,
+    key: Option<String>,
+    database: Option<String>,
+    async_insert: bool,
+    retry: RetryPolicy,
+}
+
+impl ClickHouseClient {
+    /// A client for the ClickHouse server at `url`, like `https://host:8443`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            user: None,
+            key: None,
+            database: None,
+            async_insert: false,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Sign in as `user` with `key`, its password.
+    pub fn credentials(mut self, user: impl Into<String>, key: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Use this database instead of the user's default.
+    pub fn database(mut self, database: impl Into<String>) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    /// Let ClickHouse buffer inserts server-side and write them together, which suits many
+    /// small inserts. Inserts still wait until their rows are written.
+    pub fn async_insert(mut self, async_insert: bool) -> Self {
+        self.async_insert = async_insert;
+        self
+    }
+
+    /// Retry requests that ClickHouse pushes back on with this policy.
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Insert `rows` into `table`, in chunks of the [Inserter]'s default size.
+    ///
+    /// `table` is written into the SQL as is, so don't take it from a request.
+    pub fn insert<T: Serialize>(&self, table: &str, rows: &[T]) -> Result<(), ClickHouseError> {
+        let mut inserter = self.inserter(table);
+        for row in rows {
+            inserter.write(row)?;
+        }
+        inserter.flush()
+    }
+
+    /// An [Inserter] that buffers rows for `table`.
+    pub fn inserter(&self, table: impl Into<String>) -> Inserter<'_> {
+        Inserter {
+            client: self,
+            table: table.into(),
+            max_rows: Inserter::DEFAULT_MAX_ROWS,
+            max_bytes: Inserter::DEFAULT_MAX_BYTES,
+            lines: Vec::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Run a query, deserializing each row into `T` by column name.
+    pub fn select<T: DeserializeOwned>(&self, query: Query) -> Result<Vec<T>, ClickHouseError> {
+        let parameters: Vec<(&str, String)> = query
+            .parameters
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        let body = format!(
+            "{} FORMAT JSONEachRow",
+            query.sql.trim_end().trim_end_matches(';')
+        );
+        let response = retry::with_backoff(&self.retry, || {
+            self.post(&parameters, body.clone().into_bytes())
+        })?;
+        response
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                serde_json::from_slice(line)
+                    .map_err(|cause| ClickHouseError::Decode { index, cause })
+            })
+            .collect()
+    }
+
+    fn post(
+        &self,
+        parameters: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<Vec<u8>, ClickHouseError> {
+        let mut url = format!("{}/?", self.url);
+        if self.async_insert {
+            url.push_str("async_insert=1&wait_for_async_insert=1&");
+        }
+        for (name, value) in parameters {
+            let _ = write!(
+                url,
+                "param_{}={}&",
+                encode_component(name),
+                encode_component(value)
+            );
+        }
+        url.pop();
+        let mut headers = vec![("content-type".to_string(), "text/plain".to_string())];
+        if let Some(user) = &self.user {
+            headers.push(("x-clickhouse-user".to_string(), user.clone()));
+        }
+        if let Some(key) = &self.key {
+            headers.push(("x-clickhouse-key".to_string(), key.clone()));
+        }
+        if let Some(database) = &self.database {
+            headers.push(("x-clickhouse-database".to_string(), database.clone()));
+        }
+        let response = http::post(url, headers, body)?;
+        if !(200..300).contains(&response.status) {
+            let code = response
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("x-clickhouse-exception-code"))
+                .and_then(|(_, value)| value.trim().parse().ok());
+            return Err(ClickHouseError::Server {
+                status: response.status,
+                code,
+                message: String::from_utf8_lossy(&response.body).into_owned(),
+            });
+        }
+        Ok(response.body)
+    }
+}
+
+/// Buffers rows for a table and inserts them in bounded chunks.
+///
+/// A chunk is sent when it reaches [max_rows](Self::max_rows) or
+/// [max_bytes](Self::max_bytes). Call [flush](Self::flush) at the end to send the rest. If
+/// a chunk fails, its rows stay buffered, and the next write or flush sends them again.
+pub struct Inserter<'a> {
+    client: &'a ClickHouseClient,
+    table: String,
+    max_rows: usize,
+    max_bytes: usize,
+    lines: Vec<Vec<u8>>,
+    bytes: usize,
+}
+
+impl Inserter<'_> {
+    /// How many rows a chunk holds by default.
+    pub const DEFAULT_MAX_ROWS: usize = 10_000;
+
+    /// How large a chunk grows by default: 1 MiB.
+    pub const DEFAULT_MAX_BYTES: usize = 1024 * 1024;
+
+    /// Send a chunk once it holds this many rows.
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+
+    /// Send a chunk once its rows add up to this many bytes of JSON.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes.max(1);
+        self
+    }
+
+    /// Buffer `row`, sending the chunk if it is full.
+    pub fn write<T: Serialize>(&mut self, row: &T) -> Result<(), ClickHouseError> {
+        let line = serde_json::to_vec(row).map_err(|cause| ClickHouseError::Encode { cause })?;
+        self.bytes += line.len() + 1;
+        self.lines.push(line);
+        if self.max_rows <= self.lines.len() || self.max_bytes <= self.bytes {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Send every buffered row.
+    pub fn flush(&mut self) -> Result<(), ClickHouseError> {
+        while !self.lines.is_empty() {
+            let take = chunk_len(&self.lines, self.max_rows, self.max_bytes);
+            let mut body = format!("INSERT INTO {} FORMAT JSONEachRow\n", self.table).into_bytes();
+            for line in &self.lines[..take] {
+                body.extend_from_slice(line);
+                body.push(b'\n');
+            }
+            retry::with_backoff(&self.client.retry, || self.client.post(&[], body.clone()))?;
+            let sent: usize = self.lines.drain(..take).map(|line| line.len() + 1).sum();
+            self.bytes -= sent;
+        }
+        Ok(())
+    }
+
+    /// How many rows are buffered and not yet inserted.
+    pub fn pending_rows(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+/// How many of `lines` fit in one chunk. A chunk always holds at least one line, so a row
+/// larger than `max_bytes` is still sent on its own.
+fn chunk_len(lines: &[Vec<u8>], max_rows: usize, max_bytes: usize) -> usize {
+    let mut bytes = 0;
+    let mut take = 0;
+    for line in lines.iter().take(max_rows) {
+        bytes += line.len() + 1;
+        if 0 < take && max_bytes < bytes {
+            break;
+        }
+        take += 1;
+    }
+    take
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{ErrorKind, HostError};
+
+    #[test]
+    fn chunks_are_bounded_by_rows_and_bytes() {
+        let lines: Vec<Vec<u8>> = (0..5).map(|_| vec![b'x'; 9]).collect();
+        assert_eq!(5, chunk_len(&lines, 10, 1_000));
+        assert_eq!(2, chunk_len(&lines, 2, 1_000));
+        assert_eq!(3, chunk_len(&lines, 10, 30));
+        assert_eq!(1, chunk_len(&lines, 10, 1));
+    }
+
+    #[test]
+    fn pushback_is_throttling() {
+        let server = |status, code| ClickHouseError::Server {
+            status,
+            code,
+            message: String::new(),
+        };
+        assert!(server(500, Some(252)).is_backpressure());
+        assert_eq!(ErrorKind::Throttled, server(500, Some(202)).kind());
+        assert_eq!(ErrorKind::Throttled, server(503, None).kind());
+        assert_eq!(ErrorKind::InvalidRequest, server(400, Some(62)).kind());
+    }
+}
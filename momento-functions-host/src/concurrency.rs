@@ -0,0 +1,233 @@
+//! Limits on how many calls to a resource are in flight at once
+//!
+//! An upstream like an LLM provider allows only so many concurrent requests per key. [limit]
+//! returns a [Limiter] with that many permits for a named resource. Each call takes a
+//! [Permit] and gives it back when the permit is dropped. When every permit is taken, the
+//! call either waits for one with [Limiter::acquire], or is shed right away with
+//! [Limiter::try_acquire], returning an error your Function can turn into a `429` or `503`
+//! instead of piling onto the upstream's rate limit.
+//!
+//! Limiters are shared by name across the instance, so the limit holds wherever in the
+//! Function the resource is called from.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::concurrency;
+//! use std::time::Duration;
+//!
+//! let openai = concurrency::limit("openai", 4);
+//! match openai.run(Duration::from_secs(2), || {
+//!     momento_functions_host::http::get("https://api.openai.com/v1/models", [])
+//! }) {
+//!     Ok(response) => { /* use response */ }
+//!     Err(e) => eprintln!("too many calls to openai: {e}"),
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::retry::invocation_deadline;
+
+/// How often [Limiter::acquire] checks for a free permit.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+static LIMITERS: Mutex<BTreeMap<String, Arc<Mutex<Slots>>>> = Mutex::new(BTreeMap::new());
+
+/// An error occurred while taking a permit.
+#[derive(Debug, thiserror::Error)]
+pub enum ConcurrencyError {
+    /// Every permit was taken, so the call was shed without waiting.
+    #[error("{resource} is at its limit of {limit} concurrent calls")]
+    Saturated {
+        /// The limited resource.
+        resource: String,
+        /// How many calls may be in flight.
+        limit: usize,
+    },
+    /// No permit was given back before the wait ran out, or before the invocation's deadline.
+    #[error("Timed out after {waited:?} waiting for one of {limit} permits for {resource}")]
+    TimedOut {
+        /// The limited resource.
+        resource: String,
+        /// How many calls may be in flight.
+        limit: usize,
+        /// How long the call waited.
+        waited: Duration,
+    },
+}
+
+struct Slots {
+    limit: usize,
+    in_flight: usize,
+}
+
+/// The permits for one named resource, from [limit].
+#[derive(Clone)]
+pub struct Limiter {
+    name: String,
+    slots: Arc<Mutex<Slots>>,
+}
+
+/// One call's share of a [Limiter]. The permit is given back when this is dropped.
+pub struct Permit {
+    slots: Arc<Mutex<Slots>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut slots = lock(&self.slots);
+        slots.in_flight = slots.in_flight.saturating_sub(1);
+    }
+}
+
+/// The limiter for `resource`, allowing `max_in_flight` calls at once.
+///
+/// Every limiter named `resource` shares the same permits. Calling this again with a
+/// different `max_in_flight` changes the limit for all of them; lowering it below the number
+/// of calls in flight only holds back new calls until enough permits are given back.
+pub fn limit(resource: impl Into<String>, max_in_flight: usize) -> Limiter {
+    let name = resource.into();
+    let mut limiters = LIMITERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let slots = limiters
+        .entry(name.clone())
+        .or_insert_with(|| {
+            Arc::new(Mutex::new(Slots {
+                limit: max_in_flight,
+                in_flight: 0,
+            }))
+        })
+        .clone();
+    lock(&slots).limit = max_in_flight;
+    Limiter { name, slots }
+}
+
+impl Limiter {
+    /// The limited resource.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How many calls may be in flight.
+    pub fn limit(&self) -> usize {
+        lock(&self.slots).limit
+    }
+
+    /// How many calls are in flight.
+    pub fn in_flight(&self) -> usize {
+        lock(&self.slots).in_flight
+    }
+
+    /// Take a permit if one is free, or shed the call.
+    pub fn try_acquire(&self) -> Result<Permit, ConcurrencyError> {
+        let mut slots = lock(&self.slots);
+        if slots.in_flight < slots.limit {
+            slots.in_flight += 1;
+            Ok(Permit {
+                slots: self.slots.clone(),
+            })
+        } else {
+            Err(ConcurrencyError::Saturated {
+                resource: self.name.clone(),
+                limit: slots.limit,
+            })
+        }
+    }
+
+    /// Take a permit, waiting up to `wait` for one to be given back.
+    ///
+    /// The wait never runs past the invocation's deadline when the host provides one.
+    /// Permits are only given back by calls running at the same time as this one, so a
+    /// Function that already holds a permit for this resource waits out the whole `wait`.
+    pub fn acquire(&self, wait: Duration) -> Result<Permit, ConcurrencyError> {
+        self.acquire_before(wait, invocation_deadline())
+    }
+
+    /// Run `call` under a permit, waiting up to `wait` for one as [Limiter::acquire] does.
+    pub fn run<T>(&self, wait: Duration, call: impl FnOnce() -> T) -> Result<T, ConcurrencyError> {
+        let _permit = self.acquire(wait)?;
+        Ok(call())
+    }
+
+    fn acquire_before(
+        &self,
+        wait: Duration,
+        deadline: Option<SystemTime>,
+    ) -> Result<Permit, ConcurrencyError> {
+        let start = Instant::now();
+        let wait = match deadline {
+            Some(deadline) => wait.min(
+                deadline
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default(),
+            ),
+            None => wait,
+        };
+        loop {
+            match self.try_acquire() {
+                Ok(permit) => return Ok(permit),
+                Err(ConcurrencyError::Saturated { resource, limit }) => {
+                    let waited = start.elapsed();
+                    if wait <= waited {
+                        return Err(ConcurrencyError::TimedOut {
+                            resource,
+                            limit,
+                            waited,
+                        });
+                    }
+                    std::thread::sleep(POLL_INTERVAL.min(wait - waited));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn lock(slots: &Mutex<Slots>) -> MutexGuard<'_, Slots> {
+    slots
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sheds_calls_past_the_limit() {
+        let openai = limit("test-openai", 2);
+        let first = openai.try_acquire().unwrap();
+        let _second = limit("test-openai", 2).try_acquire().unwrap();
+        assert_eq!(2, openai.in_flight());
+        assert!(matches!(
+            openai.try_acquire(),
+            Err(ConcurrencyError::Saturated { limit: 2, .. })
+        ));
+
+        drop(first);
+        assert_eq!(1, openai.in_flight());
+        let _third = openai.try_acquire().unwrap();
+
+        limit("test-openai", 3);
+        assert_eq!(3, openai.limit());
+        assert!(openai.run(Duration::ZERO, || "called").is_ok());
+        assert_eq!(2, openai.in_flight());
+    }
+
+    #[test]
+    fn waits_until_the_deadline() {
+        let search = limit("test-search", 1);
+        let _held = search.try_acquire().unwrap();
+        let deadline = SystemTime::now() + Duration::from_millis(20);
+        let start = Instant::now();
+        assert!(matches!(
+            search.acquire_before(Duration::from_secs(10), Some(deadline)),
+            Err(ConcurrencyError::TimedOut { limit: 1, .. })
+        ));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,802 @@
+//! Typed configuration from environment variables
+//!
+//! Functions are configured with environment variables. Rather than reading each one with
+//! `std::env::var()` and handling the errors by hand, describe your configuration as a struct
+//! and load it in one go. Each field is read from the upper-cased environment variable of the
+//! same name, with an optional prefix.
+//!
+//! - Use `#[serde(default)]` or `Option` for variables with defaults.
+//! - Numbers and booleans are parsed from the variable's text.
+//! - `Vec` fields are read from comma-separated values.
+//! - Unit-only enums are read from the variant name.
+//!
+//! When variables are missing, the error lists every missing variable, not just the first.
+//!
+//! Wrap secrets like API keys in [Secret], so they are redacted when the configuration is
+//! printed with `Debug`.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::config::Config;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Settings {
+//!     // Read from OPENAI_API_KEY
+//!     openai_api_key: String,
+//!     // Read from TTL_SECONDS, defaulting to 0
+//!     #[serde(default)]
+//!     ttl_seconds: u64,
+//!     // Read from REGION, if present
+//!     region: Option<String>,
+//! }
+//!
+//! match Config::from_env::<Settings>() {
+//!     Ok(settings) => println!("ttl: {}", settings.ttl_seconds),
+//!     Err(e) => eprintln!("bad configuration: {e}"),
+//! }
+//! ```
+//!
+//! ## Profiles and configuration documents
+//!
+//! A [Config] can select a profile, like `dev`, `staging`, or `prod`, from an environment
+//! variable, and read the fields the environment does not set from a configuration document,
+//! like the one attached to the Function (see [crate::config_store]). With prefix `APP_` and
+//! profile `prod`, each field is taken from the first of these that sets it:
+//!
+//! 1. the profile's environment variable, like `APP_PROD_API_KEY`;
+//! 2. the environment variable, like `APP_API_KEY`;
+//! 3. the field in the document's `profiles.prod` object;
+//! 4. the field at the top level of the document;
+//! 5. the field's serde default.
+//!
+//! For example, this document sets a default region and raises the ttl in `prod`:
+//! ```json
+//! {
+//!     "region": "us-west-2",
+//!     "ttl_seconds": 60,
+//!     "profiles": { "prod": { "ttl_seconds": 3600 } }
+//! }
+//! ```
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::config::{Config, Secret};
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Settings {
+//!     api_key: Secret<String>,
+//!     region: String,
+//!     #[serde(default)]
+//!     ttl_seconds: u64,
+//! }
+//!
+//! # fn f() -> Result<(), momento_functions_host::config::ConfigError> {
+//! let settings: Settings = Config::with_prefix("APP_")
+//!     .profile_from("APP_ENV")
+//!     .attached_document()?
+//!     .load()?;
+//! // Prints `api_key: [REDACTED]`.
+//! println!("{settings:?}");
+//! # Ok(()) }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Debug, Display};
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, IntoDeserializer, Visitor};
+use serde::{Deserialize, Deserializer, forward_to_deserialize_any};
+use serde_json::Value;
+
+use crate::config_store::ConfigStoreError;
+
+/// What a [Secret] is printed as.
+const REDACTED: &str = "[REDACTED]";
+
+/// An error occurred while loading configuration from the environment.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// Required environment variables were not set.
+    #[error("Missing environment variables: {}", variables.join(", "))]
+    Missing {
+        /// The names of every missing variable.
+        variables: Vec<String>,
+    },
+    /// An environment variable was set, but could not be parsed into its field's type.
+    #[error("Invalid value for environment variable {variable}: {message}")]
+    Invalid {
+        /// The name of the invalid variable.
+        variable: String,
+        /// Why the value could not be parsed.
+        message: String,
+    },
+    /// A field of the configuration document could not be parsed into its type.
+    #[error("Invalid value for {field} in the configuration document: {message}")]
+    InvalidDocumentField {
+        /// The path of the invalid field, like `profiles.prod.ttl_seconds`.
+        field: String,
+        /// Why the value could not be parsed.
+        message: String,
+    },
+    /// The attached configuration document could not be read.
+    #[error(transparent)]
+    Document(#[from] ConfigStoreError),
+    /// The configuration was loaded, but failed validation.
+    #[error("Invalid configuration: {message}")]
+    Validation {
+        /// The message returned by the validation.
+        message: String,
+    },
+}
+
+/// A configuration value that is redacted from `Debug` output, like an API key.
+///
+/// It deserializes as the value it wraps. Read the value with [Secret::expose].
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wrap `value`.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The secret value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwrap the secret value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+/// Validation for a configuration type, run by [Config::from_env_validated] after loading.
+pub trait ValidateConfig {
+    /// Check the loaded configuration, returning a message describing what is wrong.
+    fn validate(&self) -> Result<(), String>;
+}
+
+/// Loads typed configuration from environment variables.
+///
+/// Use [Config::from_env] for unprefixed variables, or [Config::with_prefix] to read
+/// a namespaced set of variables.
+#[derive(Clone, Default)]
+pub struct Config {
+    prefix: String,
+    profile: Option<String>,
+    profile_variable: Option<String>,
+    document: Option<Value>,
+}
+
+impl Debug for Config {
+    // The document may hold secrets, so it is left out.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Config")
+            .field("prefix", &self.prefix)
+            .field("profile", &self.profile)
+            .field("profile_variable", &self.profile_variable)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A configuration value, and where it came from.
+struct Setting {
+    origin: Origin,
+    value: String,
+}
+
+/// Where a configuration value came from, for error messages.
+#[derive(Debug, Clone)]
+enum Origin {
+    Variable(String),
+    Document(String),
+}
+
+impl Config {
+    /// Load configuration from unprefixed environment variables.
+    pub fn from_env<T: DeserializeOwned>() -> Result<T, ConfigError> {
+        Config::default().load()
+    }
+
+    /// Load configuration from unprefixed environment variables, then validate it.
+    pub fn from_env_validated<T: DeserializeOwned + ValidateConfig>() -> Result<T, ConfigError> {
+        Config::default().load_validated()
+    }
+
+    /// Read variables starting with `prefix`. A field `api_key` with prefix `MY_APP_`
+    /// is read from `MY_APP_API_KEY`.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use momento_functions_host::config::Config;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Turbopuffer {
+    ///     api_key: String,
+    ///     region: String,
+    ///     namespace: String,
+    /// }
+    ///
+    /// let turbopuffer: Turbopuffer = match Config::with_prefix("TURBOPUFFER_").load() {
+    ///     Ok(config) => config,
+    ///     Err(e) => {
+    ///         eprintln!("bad configuration: {e}");
+    ///         return;
+    ///     }
+    /// };
+    /// ```
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Use the `profile` profile, like `prod`, whatever the environment says.
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Select the profile from the environment variable `variable`, like `APP_ENV=prod`. When
+    /// the variable is not set, no profile is used.
+    pub fn profile_from(mut self, variable: impl Into<String>) -> Self {
+        self.profile_variable = Some(variable.into());
+        self
+    }
+
+    /// Read the fields the environment does not set from `document`, a JSON object.
+    ///
+    /// Each top-level field, except `profiles`, is read like the environment variable of the
+    /// same name: strings as they are, numbers and booleans as their text, and arrays as
+    /// comma-separated values. `profiles` holds an object of overrides for each profile.
+    pub fn document(mut self, document: Value) -> Self {
+        self.document = Some(document);
+        self
+    }
+
+    /// Read the fields the environment does not set from the document attached to this
+    /// Function, if one is attached. See [Config::document] for how it is read.
+    pub fn attached_document(self) -> Result<Self, ConfigError> {
+        match crate::config_store::document()? {
+            Some(document) => Ok(self.document(document.parse()?)),
+            None => Ok(self),
+        }
+    }
+
+    /// Load configuration from the environment.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        self.load_from(std::env::vars())
+    }
+
+    /// Load configuration from the environment, then validate it.
+    pub fn load_validated<T: DeserializeOwned + ValidateConfig>(&self) -> Result<T, ConfigError> {
+        let config: T = self.load()?;
+        config
+            .validate()
+            .map_err(|message| ConfigError::Validation { message })?;
+        Ok(config)
+    }
+
+    fn load_from<T: DeserializeOwned>(
+        &self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<T, ConfigError> {
+        let vars = self.settings(vars);
+        // serde reports one missing field at a time. Keep going with placeholders so the
+        // error can list every missing variable at once.
+        let mut missing = BTreeSet::new();
+        loop {
+            let deserializer = EnvDeserializer {
+                prefix: &self.prefix,
+                vars: &vars,
+                missing: &missing,
+            };
+            match T::deserialize(deserializer) {
+                Ok(config) if missing.is_empty() => return Ok(config),
+                Ok(_) => break,
+                Err(DeError::Missing(field)) if !missing.contains(&field) => {
+                    missing.insert(field);
+                }
+                Err(DeError::Invalid { origin, message }) if missing.is_empty() => {
+                    return Err(match origin {
+                        Origin::Variable(variable) => ConfigError::Invalid { variable, message },
+                        Origin::Document(field) => {
+                            ConfigError::InvalidDocumentField { field, message }
+                        }
+                    });
+                }
+                Err(DeError::Custom(message)) if missing.is_empty() => {
+                    return Err(ConfigError::Validation { message });
+                }
+                Err(_) => break,
+            }
+        }
+        Err(ConfigError::Missing {
+            variables: missing
+                .into_iter()
+                .map(|field| self.variable_name(&field))
+                .collect(),
+        })
+    }
+
+    fn variable_name(&self, field: &str) -> String {
+        format!("{}{}", self.prefix, field.to_uppercase())
+    }
+
+    /// Layer the document, its profile overrides, the environment, and the profile's
+    /// environment, each replacing the values before it. Settings are keyed by the variable
+    /// name their field is read from.
+    fn settings(
+        &self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> BTreeMap<String, Setting> {
+        let vars: BTreeMap<String, String> = vars.into_iter().collect();
+        let profile = self
+            .profile
+            .clone()
+            .or_else(|| {
+                let variable = self.profile_variable.as_ref()?;
+                vars.get(variable).map(|profile| profile.trim().to_string())
+            })
+            .filter(|profile| !profile.is_empty());
+
+        let mut settings = BTreeMap::new();
+        if let Some(Value::Object(document)) = &self.document {
+            let base = document.iter().filter(|(field, _)| *field != "profiles");
+            for (field, value) in base {
+                self.insert_document_field(&mut settings, field.clone(), field, value);
+            }
+            let overrides = profile
+                .as_ref()
+                .and_then(|profile| document.get("profiles")?.get(profile));
+            if let (Some(profile), Some(Value::Object(overrides))) = (&profile, overrides) {
+                for (field, value) in overrides {
+                    let path = format!("profiles.{profile}.{field}");
+                    self.insert_document_field(&mut settings, path, field, value);
+                }
+            }
+        }
+
+        let profile_prefix = profile.map(|profile| {
+            let profile: String = profile
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!("{}{profile}_", self.prefix)
+        });
+        let in_profile = |name: &str| {
+            profile_prefix
+                .as_ref()
+                .is_some_and(|profile_prefix| name.starts_with(profile_prefix))
+        };
+        for (name, value) in &vars {
+            if name.starts_with(&self.prefix) && !in_profile(name) {
+                let origin = Origin::Variable(name.clone());
+                settings.insert(
+                    name.clone(),
+                    Setting {
+                        origin,
+                        value: value.clone(),
+                    },
+                );
+            }
+        }
+        if let Some(profile_prefix) = &profile_prefix {
+            for (name, value) in &vars {
+                if let Some(field) = name.strip_prefix(profile_prefix.as_str()) {
+                    let origin = Origin::Variable(name.clone());
+                    settings.insert(
+                        format!("{}{field}", self.prefix),
+                        Setting {
+                            origin,
+                            value: value.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        settings
+    }
+
+    fn insert_document_field(
+        &self,
+        settings: &mut BTreeMap<String, Setting>,
+        path: String,
+        field: &str,
+        value: &Value,
+    ) {
+        let value = match value {
+            Value::Null => return,
+            Value::String(value) => value.clone(),
+            Value::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Value::String(item) => item.clone(),
+                    item => item.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+            value => value.to_string(),
+        };
+        settings.insert(
+            self.variable_name(field),
+            Setting {
+                origin: Origin::Document(path),
+                value,
+            },
+        );
+    }
+}
+
+#[derive(Debug)]
+enum DeError {
+    Missing(String),
+    Invalid { origin: Origin, message: String },
+    Custom(String),
+}
+
+impl Display for DeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeError::Missing(field) => write!(f, "missing field {field}"),
+            DeError::Invalid { origin, message } => write!(f, "{origin:?}: {message}"),
+            DeError::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl serde::de::Error for DeError {
+    fn custom<T: Display>(message: T) -> Self {
+        DeError::Custom(message.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        DeError::Missing(field.to_string())
+    }
+}
+
+struct EnvDeserializer<'a> {
+    prefix: &'a str,
+    vars: &'a BTreeMap<String, Setting>,
+    missing: &'a BTreeSet<String>,
+}
+
+impl<'de> serde::Deserializer<'de> for EnvDeserializer<'_> {
+    type Error = DeError;
+
+    /// Without a list of fields, every prefixed variable is offered, keyed by its lower-cased name.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let entries = self.vars.iter().map(|(name, setting)| {
+            let field = name[self.prefix.len()..].to_lowercase();
+            (
+                field,
+                ValueDeserializer::Present {
+                    origin: &setting.origin,
+                    value: &setting.value,
+                },
+            )
+        });
+        visitor.visit_map(MapDeserializer::new(entries))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let entries = fields.iter().filter_map(|field| {
+            let variable = format!("{}{}", self.prefix, field.to_uppercase());
+            match self.vars.get(&variable) {
+                Some(Setting { origin, value }) => {
+                    Some((*field, ValueDeserializer::Present { origin, value }))
+                }
+                None if self.missing.contains(*field) => {
+                    Some((*field, ValueDeserializer::Placeholder))
+                }
+                None => None,
+            }
+        });
+        visitor.visit_map(MapDeserializer::new(entries))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Deserializes one environment variable's text into whatever type its field wants.
+enum ValueDeserializer<'a> {
+    Present {
+        origin: &'a Origin,
+        value: &'a str,
+    },
+    /// Stands in for a missing variable while collecting the full list of missing variables.
+    Placeholder,
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for ValueDeserializer<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! parse_value {
+    ($method:ident, $visit:ident) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self {
+                ValueDeserializer::Present { origin, value } => match value.trim().parse() {
+                    Ok(parsed) => visitor.$visit(parsed),
+                    Err(e) => Err(DeError::Invalid {
+                        origin: origin.clone(),
+                        message: format!("{e}: {value:?}"),
+                    }),
+                },
+                ValueDeserializer::Placeholder => visitor.$visit(Default::default()),
+            }
+        }
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            ValueDeserializer::Present { value, .. } => visitor.visit_str(value),
+            ValueDeserializer::Placeholder => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            ValueDeserializer::Present { origin, value } => {
+                match value.trim().to_lowercase().as_str() {
+                    "true" | "1" | "yes" => visitor.visit_bool(true),
+                    "false" | "0" | "no" => visitor.visit_bool(false),
+                    _ => Err(DeError::Invalid {
+                        origin: origin.clone(),
+                        message: format!("expected a boolean: {value:?}"),
+                    }),
+                }
+            }
+            ValueDeserializer::Placeholder => visitor.visit_bool(false),
+        }
+    }
+
+    parse_value!(deserialize_i8, visit_i8);
+    parse_value!(deserialize_i16, visit_i16);
+    parse_value!(deserialize_i32, visit_i32);
+    parse_value!(deserialize_i64, visit_i64);
+    parse_value!(deserialize_i128, visit_i128);
+    parse_value!(deserialize_u8, visit_u8);
+    parse_value!(deserialize_u16, visit_u16);
+    parse_value!(deserialize_u32, visit_u32);
+    parse_value!(deserialize_u64, visit_u64);
+    parse_value!(deserialize_u128, visit_u128);
+    parse_value!(deserialize_f32, visit_f32);
+    parse_value!(deserialize_f64, visit_f64);
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            ValueDeserializer::Present { value, .. } => visitor.visit_str(value),
+            ValueDeserializer::Placeholder => visitor.visit_str(""),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            ValueDeserializer::Present { .. } => visitor.visit_some(self),
+            ValueDeserializer::Placeholder => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            ValueDeserializer::Present { origin, value } => {
+                let items = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|value| ValueDeserializer::Present { origin, value });
+                visitor.visit_seq(SeqDeserializer::new(items))
+            }
+            ValueDeserializer::Placeholder => {
+                visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<Self>()))
+            }
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            ValueDeserializer::Present { value, .. } => {
+                visitor.visit_enum(IntoDeserializer::<DeError>::into_deserializer(value))
+            }
+            ValueDeserializer::Placeholder => Err(DeError::Custom("placeholder enum".to_string())),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char bytes byte_buf unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Settings {
+        api_key: String,
+        #[serde(default)]
+        ttl_seconds: u64,
+        region: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn loads_typed_fields() {
+        let settings: Settings = Config::with_prefix("APP_")
+            .load_from(vars(&[
+                ("APP_API_KEY", "secret"),
+                ("APP_TTL_SECONDS", "60"),
+                ("APP_TAGS", "a, b,c"),
+                ("OTHER", "ignored"),
+            ]))
+            .unwrap();
+        assert_eq!("secret", settings.api_key);
+        assert_eq!(60, settings.ttl_seconds);
+        assert_eq!(None, settings.region);
+        assert_eq!(vec!["a", "b", "c"], settings.tags);
+    }
+
+    #[test]
+    fn reports_invalid_values() {
+        let error = Config::default()
+            .load_from::<Settings>(vars(&[("API_KEY", "secret"), ("TTL_SECONDS", "soon")]))
+            .unwrap_err();
+        match error {
+            ConfigError::Invalid { variable, .. } => assert_eq!("TTL_SECONDS", variable),
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_every_missing_variable() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Required {
+            api_key: String,
+            namespace: String,
+            port: u16,
+        }
+
+        let error = Config::with_prefix("TP_")
+            .load_from::<Required>(vars(&[("TP_PORT", "80")]))
+            .unwrap_err();
+        match error {
+            ConfigError::Missing { variables } => {
+                assert_eq!(vec!["TP_API_KEY", "TP_NAMESPACE"], variables)
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn layers_profile_environment_and_document() {
+        let document = serde_json::json!({
+            "api_key": "from-document",
+            "ttl_seconds": 60,
+            "region": "us-west-2",
+            "tags": ["a", "b"],
+            "profiles": { "prod": { "ttl_seconds": 3600, "region": "us-east-1" } },
+        });
+        let config = Config::with_prefix("APP_")
+            .profile_from("APP_ENV")
+            .document(document);
+        let settings: Settings = config
+            .load_from(vars(&[
+                ("APP_ENV", "prod"),
+                ("APP_REGION", "eu-west-1"),
+                ("APP_PROD_REGION", "eu-central-1"),
+            ]))
+            .unwrap();
+        assert_eq!("from-document", settings.api_key);
+        assert_eq!(3600, settings.ttl_seconds);
+        assert_eq!(Some("eu-central-1"), settings.region.as_deref());
+        assert_eq!(vec!["a", "b"], settings.tags);
+
+        let staging: Settings = config.load_from(vars(&[("APP_ENV", "staging")])).unwrap();
+        assert_eq!(60, staging.ttl_seconds);
+        assert_eq!(Some("us-west-2"), staging.region.as_deref());
+    }
+
+    #[test]
+    fn redacts_secrets_and_names_invalid_document_fields() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Credentials {
+            api_key: Secret<String>,
+            #[serde(default)]
+            port: u16,
+        }
+
+        let credentials: Credentials = Config::default()
+            .load_from(vars(&[("API_KEY", "hunter2")]))
+            .unwrap();
+        assert_eq!("hunter2", credentials.api_key.expose());
+        let debug = format!("{credentials:?}");
+        assert!(debug.contains("api_key: [REDACTED]"), "{debug}");
+        assert!(!debug.contains("hunter2"), "{debug}");
+
+        let error = Config::default()
+            .profile("prod")
+            .document(serde_json::json!({ "profiles": { "prod": { "port": "high" } } }))
+            .load_from::<Credentials>(vars(&[("API_KEY", "hunter2")]))
+            .unwrap_err();
+        match error {
+            ConfigError::InvalidDocumentField { field, .. } => {
+                assert_eq!("profiles.prod.port", field)
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+}
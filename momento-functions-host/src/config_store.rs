@@ -0,0 +1,291 @@
+//! Configuration documents attached to a Function
+//!
+//! Operators can attach a JSON or YAML document to a Function when they put it, separately
+//! from its environment variables. Read it with [load], or keep it current in a long-lived
+//! instance with [Reloading], which picks up a replaced document without a rebuild or a
+//! redeploy.
+//!
+//! YAML documents are converted to JSON by the host, so either format deserializes with
+//! serde the same way.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::config_store::Reloading;
+//! use std::time::Duration;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Routing {
+//!     upstream: String,
+//!     #[serde(default)]
+//!     canary_percent: u8,
+//! }
+//!
+//! static ROUTING: Reloading<Routing> = Reloading::new(Duration::from_secs(30));
+//!
+//! match ROUTING.get() {
+//!     Ok(routing) => println!("routing to {}", routing.upstream),
+//!     Err(e) => eprintln!("bad configuration: {e}"),
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
+use momento_functions_wit::host::momento::host::config_store;
+use serde::de::DeserializeOwned;
+
+use crate::refresh::RefreshTimer;
+
+/// An error occurred while reading the configuration document.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigStoreError {
+    /// The host could not read the document.
+    #[error("Failed to read the configuration document: {message}")]
+    Unavailable {
+        /// Why the document could not be read.
+        message: String,
+    },
+    /// No document is attached to this Function.
+    #[error("No configuration document is attached to this Function")]
+    NotAttached,
+    /// The document could not be deserialized into the requested type.
+    #[error("Invalid configuration document (version {version}): {cause}")]
+    Invalid {
+        /// The version of the invalid document.
+        version: String,
+        /// Why the document could not be deserialized.
+        #[source]
+        cause: serde_json::Error,
+    },
+    /// The host is too old to attach configuration documents.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+impl From<config_store::ConfigStoreError> for ConfigStoreError {
+    fn from(e: config_store::ConfigStoreError) -> Self {
+        match e {
+            config_store::ConfigStoreError::Unavailable(message) => Self::Unavailable { message },
+        }
+    }
+}
+
+/// The format an operator attached a document in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// A JSON document.
+    Json,
+    /// A YAML document, converted to JSON by the host.
+    Yaml,
+}
+
+/// The configuration document attached to this Function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDocument {
+    /// Identifies this revision of the document. It changes whenever the document is replaced.
+    pub version: String,
+    /// The format the document was attached in.
+    pub format: DocumentFormat,
+    /// The document as JSON.
+    pub json: String,
+}
+
+impl ConfigDocument {
+    /// Deserialize the document.
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, ConfigStoreError> {
+        serde_json::from_str(&self.json).map_err(|cause| ConfigStoreError::Invalid {
+            version: self.version.clone(),
+            cause,
+        })
+    }
+}
+
+impl From<config_store::ConfigDocument> for ConfigDocument {
+    fn from(document: config_store::ConfigDocument) -> Self {
+        Self {
+            version: document.version,
+            format: match document.format {
+                config_store::DocumentFormat::Json => DocumentFormat::Json,
+                config_store::DocumentFormat::Yaml => DocumentFormat::Yaml,
+            },
+            json: document.json,
+        }
+    }
+}
+
+/// The document attached to this Function, or `None` if there is none.
+pub fn document() -> Result<Option<ConfigDocument>, ConfigStoreError> {
+    abi::require(HostCapability::ConfigStore)?;
+    Ok(config_store::get_document()?.map(Into::into))
+}
+
+/// The version of the attached document, without reading the document.
+pub fn version() -> Result<Option<String>, ConfigStoreError> {
+    abi::require(HostCapability::ConfigStore)?;
+    Ok(config_store::document_version()?)
+}
+
+/// Deserialize the attached document.
+pub fn load<T: DeserializeOwned>() -> Result<T, ConfigStoreError> {
+    document()?.ok_or(ConfigStoreError::NotAttached)?.parse()
+}
+
+struct Loaded<T> {
+    version: String,
+    value: Arc<T>,
+    refresh: RefreshTimer,
+}
+
+/// The attached document, deserialized and reloaded when it is replaced.
+///
+/// The document is loaded on first use. After that, its version is checked at most once per
+/// interval, and the document is read again only when the version changed. When a new
+/// version cannot be read or deserialized, the last good value keeps being used and the
+/// failure is logged.
+pub struct Reloading<T> {
+    check_every: Duration,
+    loaded: Mutex<Option<Loaded<T>>>,
+}
+
+impl<T: DeserializeOwned> Reloading<T> {
+    /// Check for a new version of the document at most once per `check_every`.
+    pub const fn new(check_every: Duration) -> Self {
+        Self {
+            check_every,
+            loaded: Mutex::new(None),
+        }
+    }
+
+    /// The current value of the document.
+    ///
+    /// Fails only when no version has ever been loaded.
+    pub fn get(&self) -> Result<Arc<T>, ConfigStoreError> {
+        self.get_with(version, document)
+    }
+
+    /// Drop the loaded value, so the next [Reloading::get] reads the document again.
+    pub fn reset(&self) {
+        *self
+            .loaded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    fn get_with(
+        &self,
+        version: impl FnOnce() -> Result<Option<String>, ConfigStoreError>,
+        document: impl FnOnce() -> Result<Option<ConfigDocument>, ConfigStoreError>,
+    ) -> Result<Arc<T>, ConfigStoreError> {
+        let mut loaded = self
+            .loaded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(current) = loaded.as_mut() else {
+            let document = document()?.ok_or(ConfigStoreError::NotAttached)?;
+            let value: Arc<T> = Arc::new(document.parse()?);
+            let mut refresh = RefreshTimer::new();
+            refresh.restart();
+            *loaded = Some(Loaded {
+                version: document.version,
+                value: value.clone(),
+                refresh,
+            });
+            return Ok(value);
+        };
+        if !current.refresh.due(self.check_every) {
+            return Ok(current.value.clone());
+        }
+        match version() {
+            Ok(Some(version)) if version == current.version => {}
+            Ok(_) => match reload(document) {
+                Ok((version, value)) => {
+                    current.version = version;
+                    current.value = Arc::new(value);
+                }
+                Err(e) => log::warn!("keeping configuration version {}: {e}", current.version),
+            },
+            Err(e) => log::warn!("failed to check the configuration version: {e}"),
+        }
+        Ok(current.value.clone())
+    }
+}
+
+fn reload<T: DeserializeOwned>(
+    document: impl FnOnce() -> Result<Option<ConfigDocument>, ConfigStoreError>,
+) -> Result<(String, T), ConfigStoreError> {
+    let document = document()?.ok_or(ConfigStoreError::NotAttached)?;
+    let value = document.parse()?;
+    Ok((document.version, value))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Routing {
+        upstream: String,
+    }
+
+    fn attached(version: &str, json: &str) -> Result<Option<ConfigDocument>, ConfigStoreError> {
+        Ok(Some(ConfigDocument {
+            version: version.to_string(),
+            format: DocumentFormat::Yaml,
+            json: json.to_string(),
+        }))
+    }
+
+    fn version(version: &str) -> impl FnOnce() -> Result<Option<String>, ConfigStoreError> {
+        move || Ok(Some(version.to_string()))
+    }
+
+    #[test]
+    fn invalid_documents_name_their_version() {
+        let document = attached("v3", r#"{"upstream": 7}"#).unwrap().unwrap();
+        let error = document.parse::<Routing>().expect_err("wrong type");
+        assert!(matches!(&error, ConfigStoreError::Invalid { version, .. } if version == "v3"));
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid configuration document (version v3)")
+        );
+
+        let unloaded = Reloading::<Routing>::new(Duration::ZERO);
+        let error = unloaded
+            .get_with(version("v1"), || Ok(None))
+            .expect_err("none");
+        assert!(matches!(error, ConfigStoreError::NotAttached));
+    }
+
+    #[test]
+    fn reloads_keep_the_last_good_version() {
+        let routing = Reloading::<Routing>::new(Duration::ZERO);
+        let a = r#"{"upstream": "a"}"#;
+        let first = routing
+            .get_with(version("v1"), || attached("v1", a))
+            .unwrap();
+        assert_eq!("a", first.upstream);
+
+        let unchanged = routing
+            .get_with(version("v1"), || panic!("the version did not change"))
+            .unwrap();
+        assert_eq!("a", unchanged.upstream);
+
+        let broken = routing
+            .get_with(version("v2"), || attached("v2", "{"))
+            .unwrap();
+        assert_eq!("a", broken.upstream);
+        assert_eq!(
+            "v1",
+            routing.loaded.lock().unwrap().as_ref().unwrap().version
+        );
+
+        let b = r#"{"upstream": "b"}"#;
+        let replaced = routing
+            .get_with(version("v3"), || attached("v3", b))
+            .unwrap();
+        assert_eq!("b", replaced.upstream);
+    }
+}
@@ -0,0 +1,120 @@
+//! Creating and listing caches and topics, for Functions that provision what they use
+//!
+//! A bootstrap Function in a dev environment can create the caches and topics it depends on
+//! instead of failing because nobody made them yet. Creating is idempotent: a cache or topic
+//! that already exists is left alone and reported as [Provisioned::AlreadyExists].
+//!
+//! These calls manage your account, so the host only allows them for Functions deployed with
+//! the control-plane permission. Other Functions get [ControlError::PermissionDenied]. This
+//! module is behind the `control` feature, which is off by default.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::control::{self, ControlError, Provisioned};
+//!
+//! fn bootstrap() -> Result<(), ControlError> {
+//!     if control::create_cache("orders")? == Provisioned::Created {
+//!         log::info!("created cache orders");
+//!     }
+//!     control::create_topic("orders", "order-events")?;
+//!     for cache in control::list_caches()? {
+//!         log::debug!("cache {}", cache.name);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
+use momento_functions_wit::host::momento::host::control;
+
+/// An error occurred while managing caches or topics.
+#[derive(Debug, thiserror::Error)]
+pub enum ControlError {
+    /// The Function was not deployed with the control-plane permission.
+    #[error("Permission denied: {message}")]
+    PermissionDenied {
+        /// The host's description of what was denied.
+        message: String,
+    },
+    /// A cache or topic name is not valid.
+    #[error("Invalid argument: {message}")]
+    InvalidArgument {
+        /// Why the name is invalid.
+        message: String,
+    },
+    /// The account's limit for caches or topics has been reached.
+    #[error("Limit exceeded: {message}")]
+    LimitExceeded {
+        /// Which limit was reached.
+        message: String,
+    },
+    /// The request failed for some other reason.
+    #[error("Control-plane request failed: {message}")]
+    Other {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The host is too old to manage caches or topics.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+impl From<control::ControlError> for ControlError {
+    fn from(e: control::ControlError) -> Self {
+        match e {
+            control::ControlError::PermissionDenied(message) => Self::PermissionDenied { message },
+            control::ControlError::InvalidArgument(message) => Self::InvalidArgument { message },
+            control::ControlError::LimitExceeded(message) => Self::LimitExceeded { message },
+            control::ControlError::InternalError(message) => Self::Other { message },
+        }
+    }
+}
+
+/// Whether a cache or topic was made by the call or was already there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provisioned {
+    /// The call created it.
+    Created,
+    /// It already existed and was left unchanged.
+    AlreadyExists,
+}
+
+impl From<control::Provisioned> for Provisioned {
+    fn from(provisioned: control::Provisioned) -> Self {
+        match provisioned {
+            control::Provisioned::Created => Self::Created,
+            control::Provisioned::AlreadyExists => Self::AlreadyExists,
+        }
+    }
+}
+
+/// A cache in this Function's account and region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheInfo {
+    /// The cache's name.
+    pub name: String,
+}
+
+/// Create the cache `name` if it does not exist.
+pub fn create_cache(name: impl AsRef<str>) -> Result<Provisioned, ControlError> {
+    abi::require(HostCapability::ControlPlane)?;
+    Ok(control::create_cache(name.as_ref())?.into())
+}
+
+/// The caches in this Function's account and region.
+pub fn list_caches() -> Result<Vec<CacheInfo>, ControlError> {
+    abi::require(HostCapability::ControlPlane)?;
+    Ok(control::list_caches()?
+        .into_iter()
+        .map(|cache| CacheInfo { name: cache.name })
+        .collect())
+}
+
+/// Create `topic` in the cache `cache_name` if it does not exist.
+pub fn create_topic(
+    cache_name: impl AsRef<str>,
+    topic: impl AsRef<str>,
+) -> Result<Provisioned, ControlError> {
+    abi::require(HostCapability::ControlPlane)?;
+    Ok(control::create_topic(cache_name.as_ref(), topic.as_ref())?.into())
+}
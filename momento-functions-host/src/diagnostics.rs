@@ -0,0 +1,257 @@
+//! Connectivity checks for the endpoints a Function depends on
+//!
+//! When a private Valkey cluster or an internal API is unreachable, the call that fails
+//! usually reports only a timeout. [check_endpoint] walks the steps of reaching an endpoint —
+//! resolving its hostname, connecting, and the TLS handshake — and reports which one failed,
+//! so a Function can return an error that says what to fix.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::diagnostics::check_endpoint;
+//!
+//! match check_endpoint("rediss://my.valkey.instance:6379") {
+//!     Ok(report) => match report.diagnosis() {
+//!         Some(diagnosis) => eprintln!("cannot reach valkey: {diagnosis}"),
+//!         None => println!("reachable at {:?}", report.connection),
+//!     },
+//!     Err(e) => eprintln!("bad url: {e}"),
+//! }
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
+use momento_functions_wit::host::momento::host::diagnostics;
+
+/// An endpoint could not be checked.
+#[derive(Debug, thiserror::Error)]
+pub enum DiagnosticsError {
+    /// The url could not be parsed.
+    #[error("Invalid url: {message}")]
+    InvalidUrl {
+        /// Why the url is invalid.
+        message: String,
+    },
+    /// The url has no port, and its scheme has no default port.
+    #[error("Unknown port: {message}")]
+    UnknownPort {
+        /// Which scheme has no default port.
+        message: String,
+    },
+    /// The host is too old to check endpoints.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+impl From<diagnostics::DiagnosticsError> for DiagnosticsError {
+    fn from(e: diagnostics::DiagnosticsError) -> Self {
+        match e {
+            diagnostics::DiagnosticsError::InvalidUrl(message) => Self::InvalidUrl { message },
+            diagnostics::DiagnosticsError::UnknownPort(message) => Self::UnknownPort { message },
+        }
+    }
+}
+
+/// The addresses a hostname resolved to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    /// The resolved IP addresses.
+    pub addresses: Vec<String>,
+    /// How long resolution took.
+    pub duration: Duration,
+}
+
+/// A TCP connection that was opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    /// The address that accepted the connection.
+    pub address: String,
+    /// How long connecting took.
+    pub duration: Duration,
+}
+
+/// A TLS handshake that completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsHandshake {
+    /// The negotiated protocol version, like `TLSv1.3`.
+    pub protocol_version: String,
+    /// The subject of the server's leaf certificate.
+    pub certificate_subject: Option<String>,
+    /// When the server's leaf certificate expires.
+    pub certificate_expires_at: Option<SystemTime>,
+    /// How long the handshake took.
+    pub duration: Duration,
+}
+
+/// The step of reaching an endpoint that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStage {
+    /// Resolving the hostname.
+    Resolve,
+    /// Opening a TCP connection.
+    Connect,
+    /// The TLS handshake.
+    Tls,
+}
+
+/// Why an endpoint could not be reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckFailure {
+    /// The step that failed.
+    pub stage: CheckStage,
+    /// The host's description of the failure.
+    pub message: String,
+}
+
+/// What happened while trying to reach an endpoint.
+///
+/// Each step is present only if it succeeded. The first step that failed is described by
+/// [failure](EndpointReport::failure).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointReport {
+    /// The hostname from the url.
+    pub host: String,
+    /// The port from the url, or the scheme's default port.
+    pub port: u16,
+    /// Whether the scheme uses TLS, like `https` or `rediss`.
+    pub tls_expected: bool,
+    /// The addresses the hostname resolved to.
+    pub resolution: Option<Resolution>,
+    /// The connection that was opened.
+    pub connection: Option<Connection>,
+    /// The TLS handshake, when the scheme uses TLS.
+    pub tls: Option<TlsHandshake>,
+    /// The step that failed, if any.
+    pub failure: Option<CheckFailure>,
+}
+
+impl EndpointReport {
+    /// Whether every step succeeded.
+    pub fn is_reachable(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// A description of what failed and what to check, or `None` if the endpoint is
+    /// reachable. This is meant to be returned to the person configuring the Function.
+    pub fn diagnosis(&self) -> Option<String> {
+        let failure = self.failure.as_ref()?;
+        let Self { host, port, .. } = self;
+        let message = &failure.message;
+        Some(match failure.stage {
+            CheckStage::Resolve => format!(
+                "{host} could not be resolved ({message}). Check the hostname. If it is a \
+                 private hostname, check that it resolves from the VPC your Functions are \
+                 peered with."
+            ),
+            CheckStage::Connect => {
+                let addresses = self
+                    .resolution
+                    .as_ref()
+                    .map(|resolution| resolution.addresses.join(", "))
+                    .unwrap_or_default();
+                format!(
+                    "{host} resolved to {addresses}, but port {port} did not accept a \
+                     connection ({message}). If it is a private endpoint, check that VPC \
+                     peering is configured and that security groups allow traffic from \
+                     Functions on port {port}."
+                )
+            }
+            CheckStage::Tls => format!(
+                "{host}:{port} accepted a connection, but the TLS handshake failed \
+                 ({message}). Check that the endpoint serves TLS on port {port} and that its \
+                 certificate is valid for {host}."
+            ),
+        })
+    }
+}
+
+/// Resolve, connect to, and for TLS schemes handshake with the endpoint in `url`.
+///
+/// The url's scheme picks the default port and whether TLS is expected: `https`, `rediss`,
+/// and `valkeys` use TLS, while `http`, `redis`, and `valkey` do not. Nothing is sent after
+/// the handshake, so this is safe to call against any endpoint.
+///
+/// An unreachable endpoint is not an error: it is an [EndpointReport] with a
+/// [failure](EndpointReport::failure).
+pub fn check_endpoint(url: impl AsRef<str>) -> Result<EndpointReport, DiagnosticsError> {
+    abi::require(HostCapability::EndpointDiagnostics)?;
+    Ok(diagnostics::check_endpoint(url.as_ref())?.into())
+}
+
+fn millis(millis: u32) -> Duration {
+    Duration::from_millis(millis.into())
+}
+
+impl From<diagnostics::EndpointReport> for EndpointReport {
+    fn from(report: diagnostics::EndpointReport) -> Self {
+        Self {
+            host: report.host,
+            port: report.port,
+            tls_expected: report.tls_expected,
+            resolution: report.resolution.map(|resolution| Resolution {
+                addresses: resolution.addresses,
+                duration: millis(resolution.duration_millis),
+            }),
+            connection: report.connection.map(|connection| Connection {
+                address: connection.address,
+                duration: millis(connection.duration_millis),
+            }),
+            tls: report.tls.map(|tls| TlsHandshake {
+                protocol_version: tls.protocol_version,
+                certificate_subject: tls.certificate_subject,
+                certificate_expires_at: tls
+                    .certificate_expires_at
+                    .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds)),
+                duration: millis(tls.duration_millis),
+            }),
+            failure: report.failure.map(|failure| CheckFailure {
+                stage: match failure.stage {
+                    diagnostics::CheckStage::Resolve => CheckStage::Resolve,
+                    diagnostics::CheckStage::Connect => CheckStage::Connect,
+                    diagnostics::CheckStage::Tls => CheckStage::Tls,
+                },
+                message: failure.message,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn report(failure: Option<CheckStage>) -> EndpointReport {
+        EndpointReport {
+            host: "my.valkey.instance".to_string(),
+            port: 6379,
+            tls_expected: true,
+            resolution: Some(Resolution {
+                addresses: vec!["10.0.1.5".to_string(), "10.0.2.5".to_string()],
+                duration: Duration::from_millis(3),
+            }),
+            connection: None,
+            tls: None,
+            failure: failure.map(|stage| CheckFailure {
+                stage,
+                message: "timed out".to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn diagnosis_names_the_failed_step() {
+        assert!(report(None).is_reachable());
+        assert_eq!(None, report(None).diagnosis());
+
+        let connect = report(Some(CheckStage::Connect)).diagnosis().unwrap();
+        assert!(connect.starts_with("my.valkey.instance resolved to 10.0.1.5, 10.0.2.5"));
+        assert!(connect.contains("VPC peering"));
+        assert!(connect.contains("(timed out)"));
+
+        let resolve = report(Some(CheckStage::Resolve)).diagnosis().unwrap();
+        assert!(resolve.starts_with("my.valkey.instance could not be resolved"));
+        let tls = report(Some(CheckStage::Tls)).diagnosis().unwrap();
+        assert!(tls.starts_with("my.valkey.instance:6379 accepted a connection"));
+    }
+}
@@ -0,0 +1,564 @@
+//! Indexing and searching documents in Elasticsearch or OpenSearch
+//!
+//! [SearchClient] covers the calls most Functions make against a search cluster: indexing
+//! and fetching single documents, [bulk](SearchClient::bulk) writes, and
+//! [search](SearchClient::search) with the cluster's JSON query DSL. It speaks the REST API
+//! Elasticsearch and OpenSearch share, and signs in with a password, an API key, or, for
+//! Amazon OpenSearch Service, SigV4 through [crate::aws::opensearch].
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::elasticsearch::{BulkOperation, SearchAuth, SearchClient, SearchError};
+//! use serde_json::json;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Product {
+//!     name: String,
+//!     price_cents: u64,
+//! }
+//!
+//! fn reindex_and_search(products: Vec<(String, Product)>) -> Result<Vec<Product>, SearchError> {
+//!     let client = SearchClient::new(
+//!         "https://search.example.com:9200",
+//!         SearchAuth::ApiKey(std::env::var("ELASTIC_API_KEY").unwrap_or_default()),
+//!     );
+//!     let operations = products
+//!         .iter()
+//!         .map(|(id, product)| BulkOperation::index("products", id, product))
+//!         .collect::<Result<Vec<_>, _>>()?;
+//!     let report = client.bulk(operations)?;
+//!     for failure in report.failures() {
+//!         log::warn!("failed to index {}: {:?}", failure.id, failure.error);
+//!     }
+//!
+//!     let results = client.search::<Product>(
+//!         "products",
+//!         &json!({ "query": { "match": { "name": "espresso" } }, "size": 10 }),
+//!     )?;
+//!     Ok(results.hits.into_iter().map(|hit| hit.source).collect())
+//! }
+//! ```
+
+use std::convert::Infallible;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(feature = "aws")]
+use crate::aws;
+use crate::http::{
+    self, HttpDeleteError, HttpGetError, HttpPostError, HttpPutError, Response, encode_component,
+};
+
+/// An error occurred while calling a search cluster.
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    /// A GET request failed.
+    #[error(transparent)]
+    Get(#[from] HttpGetError),
+    /// A PUT request failed.
+    #[error(transparent)]
+    Put(#[from] HttpPutError<Infallible>),
+    /// A POST request failed.
+    #[error(transparent)]
+    Post(#[from] HttpPostError<Infallible>),
+    /// A DELETE request failed.
+    #[error(transparent)]
+    Delete(#[from] HttpDeleteError),
+    /// The cluster rejected the request.
+    #[error("Search cluster returned status {status}: {message}")]
+    Cluster {
+        /// The HTTP status the cluster responded with.
+        status: u16,
+        /// The error's type, like `index_not_found_exception` or `version_conflict_engine_exception`.
+        error_type: Option<String>,
+        /// The error's reason, or the body of the response when it has none.
+        message: String,
+    },
+    /// A document or query could not be encoded.
+    #[error("Failed to encode request.")]
+    Encode {
+        /// The underlying encoding error.
+        cause: serde_json::Error,
+    },
+    /// The cluster's response could not be decoded.
+    #[error("Failed to decode response.")]
+    Decode {
+        /// The underlying decoding error.
+        cause: serde_json::Error,
+    },
+}
+
+/// How a [SearchClient] signs in.
+#[derive(Clone)]
+pub enum SearchAuth {
+    /// No authentication, for clusters on a private network.
+    None,
+    /// HTTP basic authentication.
+    Basic {
+        /// The user to sign in as.
+        username: String,
+        /// The user's password.
+        password: String,
+    },
+    /// An Elasticsearch API key, in its encoded form.
+    ApiKey(String),
+    /// AWS SigV4, for Amazon OpenSearch Service. See [crate::aws::opensearch].
+    #[cfg(feature = "aws")]
+    AwsSigV4 {
+        /// The credentials to sign with.
+        credentials: aws::auth::Credentials,
+        /// The domain's region.
+        region: String,
+        /// `es` for managed domains, or `aoss` for OpenSearch Serverless.
+        service: String,
+    },
+}
+
+/// A client for the Elasticsearch and OpenSearch REST API.
+#[derive(Clone)]
+pub struct SearchClient {
+    url: String,
+    auth: SearchAuth,
+}
+
+/// What happened to an indexed or deleted document.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WriteResult {
+    /// The document's id.
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// `created`, `updated`, `deleted`, `not_found`, or `noop`.
+    pub result: String,
+    /// The document's version after the write.
+    #[serde(rename = "_version", default)]
+    pub version: Option<u64>,
+}
+
+/// A search's matching documents.
+#[derive(Debug, Clone)]
+pub struct SearchResults<T> {
+    /// How many documents matched. Clusters may stop counting at 10,000 unless the query sets
+    /// `track_total_hits`.
+    pub total: u64,
+    /// The matching documents on this page, best first.
+    pub hits: Vec<Hit<T>>,
+    /// The query's `aggs` results, if it asked for any.
+    pub aggregations: Option<Value>,
+}
+
+/// A matching document.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Hit<T> {
+    /// The document's index.
+    #[serde(rename = "_index")]
+    pub index: String,
+    /// The document's id.
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// How well the document matched, unless the query sorts by something else.
+    #[serde(rename = "_score")]
+    pub score: Option<f64>,
+    /// The document.
+    #[serde(rename = "_source")]
+    pub source: T,
+}
+
+/// One write in a [SearchClient::bulk] request.
+#[derive(Debug, Clone)]
+pub struct BulkOperation {
+    action: Value,
+    document: Option<Value>,
+}
+
+impl BulkOperation {
+    /// Index `document` as `id`, replacing any document with that id.
+    pub fn index(index: &str, id: &str, document: &impl Serialize) -> Result<Self, SearchError> {
+        Ok(Self {
+            action: serde_json::json!({ "index": { "_index": index, "_id": id } }),
+            document: Some(encode(document)?),
+        })
+    }
+
+    /// Index `document` as `id`, failing if a document with that id exists.
+    pub fn create(index: &str, id: &str, document: &impl Serialize) -> Result<Self, SearchError> {
+        Ok(Self {
+            action: serde_json::json!({ "create": { "_index": index, "_id": id } }),
+            document: Some(encode(document)?),
+        })
+    }
+
+    /// Merge `fields` into the document `id`.
+    pub fn update(index: &str, id: &str, fields: &impl Serialize) -> Result<Self, SearchError> {
+        Ok(Self {
+            action: serde_json::json!({ "update": { "_index": index, "_id": id } }),
+            document: Some(serde_json::json!({ "doc": encode(fields)? })),
+        })
+    }
+
+    /// Delete the document `id`.
+    pub fn delete(index: &str, id: &str) -> Self {
+        Self {
+            action: serde_json::json!({ "delete": { "_index": index, "_id": id } }),
+            document: None,
+        }
+    }
+}
+
+/// The outcome of a [SearchClient::bulk] request, one item per operation in order.
+#[derive(Debug, Clone)]
+pub struct BulkReport {
+    /// Each operation's outcome.
+    pub items: Vec<BulkItem>,
+}
+
+impl BulkReport {
+    /// The operations that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &BulkItem> {
+        self.items.iter().filter(|item| item.error.is_some())
+    }
+}
+
+/// The outcome of one bulk operation.
+#[derive(Debug, Clone)]
+pub struct BulkItem {
+    /// The document's id.
+    pub id: String,
+    /// The operation's HTTP status, like 201 for a created document or 409 for a conflict.
+    pub status: u16,
+    /// Why the operation failed, if it did.
+    pub error: Option<Value>,
+}
+
+impl SearchClient {
+    /// A client for the cluster at `url`, like `https://search.example.com:9200`.
+    pub fn new(url: impl Into<String>, auth: SearchAuth) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            auth,
+        }
+    }
+
+    /// Index `document` as `id`, replacing any document with that id.
+    pub fn index(
+        &self,
+        index: &str,
+        id: &str,
+        document: &impl Serialize,
+    ) -> Result<WriteResult, SearchError> {
+        let body = serde_json::to_vec(document).map_err(|cause| SearchError::Encode { cause })?;
+        let response = self.put(&document_path(index, id), body)?;
+        decode(response)
+    }
+
+    /// The document `id`, if it exists.
+    pub fn get<T: DeserializeOwned>(
+        &self,
+        index: &str,
+        id: &str,
+    ) -> Result<Option<T>, SearchError> {
+        #[derive(Deserialize)]
+        struct Found<T> {
+            #[serde(rename = "_source")]
+            source: T,
+        }
+
+        let response = self.get_path(&document_path(index, id))?;
+        if response.status == 404 {
+            return Ok(None);
+        }
+        let Found { source } = decode(response)?;
+        Ok(Some(source))
+    }
+
+    /// Delete the document `id`. Deleting a document that does not exist is not an error;
+    /// its result is `not_found`.
+    pub fn delete(&self, index: &str, id: &str) -> Result<WriteResult, SearchError> {
+        let response = self.delete_path(&document_path(index, id))?;
+        if response.status == 404 {
+            return Ok(WriteResult {
+                id: id.to_string(),
+                result: "not_found".to_string(),
+                version: None,
+            });
+        }
+        decode(response)
+    }
+
+    /// Send several writes in one request.
+    ///
+    /// The request succeeds as long as the cluster accepted it, even if some operations
+    /// failed; check [BulkReport::failures].
+    pub fn bulk(
+        &self,
+        operations: impl IntoIterator<Item = BulkOperation>,
+    ) -> Result<BulkReport, SearchError> {
+        let mut body = Vec::new();
+        for operation in operations {
+            body.extend(encode_line(&operation.action)?);
+            if let Some(document) = &operation.document {
+                body.extend(encode_line(document)?);
+            }
+        }
+        if body.is_empty() {
+            return Ok(BulkReport { items: Vec::new() });
+        }
+        let response = self.post("/_bulk", "application/x-ndjson", body)?;
+        let BulkResponse { items } = decode(response)?;
+        Ok(BulkReport {
+            items: items
+                .into_iter()
+                .filter_map(|item| item.into_values().next())
+                .map(|item| BulkItem {
+                    id: item.id,
+                    status: item.status,
+                    error: item.error,
+                })
+                .collect(),
+        })
+    }
+
+    /// Search `index` with `query`, a request body in the cluster's query DSL.
+    ///
+    /// `index` can name several indices, like `logs-a,logs-b`, or a pattern, like `logs-*`.
+    pub fn search<T: DeserializeOwned>(
+        &self,
+        index: &str,
+        query: &impl Serialize,
+    ) -> Result<SearchResults<T>, SearchError> {
+        let body = serde_json::to_vec(query).map_err(|cause| SearchError::Encode { cause })?;
+        let path = format!("/{}/_search", encode_component(index).replace("%2C", ","));
+        let response = self.post(&path, "application/json", body)?;
+        let SearchResponse { hits, aggregations } = decode(response)?;
+        Ok(SearchResults {
+            total: hits.total.map(|total| total.value()).unwrap_or_default(),
+            hits: hits.hits,
+            aggregations,
+        })
+    }
+
+    fn headers(&self, content_type: &str) -> Vec<(String, String)> {
+        let mut headers = vec![
+            ("content-type".to_string(), content_type.to_string()),
+            ("accept".to_string(), "application/json".to_string()),
+        ];
+        match &self.auth {
+            SearchAuth::Basic { username, password } => {
+                use base64::Engine;
+                let encoded = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{username}:{password}"));
+                headers.push(("authorization".to_string(), format!("Basic {encoded}")));
+            }
+            SearchAuth::ApiKey(key) => {
+                headers.push(("authorization".to_string(), format!("ApiKey {key}")));
+            }
+            _ => {}
+        }
+        headers
+    }
+
+    fn get_path(&self, path: &str) -> Result<Response, SearchError> {
+        let url = format!("{}{path}", self.url);
+        let headers = self.headers("application/json");
+        Ok(match &self.auth {
+            #[cfg(feature = "aws")]
+            SearchAuth::AwsSigV4 {
+                credentials,
+                region,
+                service,
+            } => http::get_aws_sigv4(url, headers, credentials.clone(), region, service)?,
+            _ => http::get(url, headers)?,
+        })
+    }
+
+    fn put(&self, path: &str, body: Vec<u8>) -> Result<Response, SearchError> {
+        let url = format!("{}{path}", self.url);
+        let headers = self.headers("application/json");
+        Ok(match &self.auth {
+            #[cfg(feature = "aws")]
+            SearchAuth::AwsSigV4 {
+                credentials,
+                region,
+                service,
+            } => http::put_aws_sigv4(url, headers, credentials.clone(), region, service, body)?,
+            _ => http::put(url, headers, body)?,
+        })
+    }
+
+    fn post(&self, path: &str, content_type: &str, body: Vec<u8>) -> Result<Response, SearchError> {
+        let url = format!("{}{path}", self.url);
+        let headers = self.headers(content_type);
+        Ok(match &self.auth {
+            #[cfg(feature = "aws")]
+            SearchAuth::AwsSigV4 {
+                credentials,
+                region,
+                service,
+            } => http::post_aws_sigv4(url, headers, credentials.clone(), region, service, body)?,
+            _ => http::post(url, headers, body)?,
+        })
+    }
+
+    fn delete_path(&self, path: &str) -> Result<Response, SearchError> {
+        let url = format!("{}{path}", self.url);
+        let headers = self.headers("application/json");
+        Ok(match &self.auth {
+            #[cfg(feature = "aws")]
+            SearchAuth::AwsSigV4 {
+                credentials,
+                region,
+                service,
+            } => http::delete_aws_sigv4(url, headers, credentials.clone(), region, service)?,
+            _ => http::delete(url, headers)?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct BulkResponse {
+    items: Vec<std::collections::HashMap<String, BulkResponseItem>>,
+}
+
+#[derive(Deserialize)]
+struct BulkResponseItem {
+    #[serde(rename = "_id", default)]
+    id: String,
+    status: u16,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse<T> {
+    hits: SearchHits<T>,
+    #[serde(default)]
+    aggregations: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct SearchHits<T> {
+    #[serde(default)]
+    total: Option<Total>,
+    hits: Vec<Hit<T>>,
+}
+
+/// Elasticsearch 7 and later and OpenSearch report `{"value": n}`; older clusters a number.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Total {
+    Object { value: u64 },
+    Count(u64),
+}
+
+impl Total {
+    fn value(self) -> u64 {
+        match self {
+            Total::Object { value } | Total::Count(value) => value,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ClusterError {
+    error: ClusterErrorBody,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ClusterErrorBody {
+    Detailed {
+        #[serde(rename = "type")]
+        error_type: String,
+        reason: Option<String>,
+    },
+    Message(String),
+}
+
+fn document_path(index: &str, id: &str) -> String {
+    format!("/{}/_doc/{}", encode_component(index), encode_component(id))
+}
+
+fn encode(value: &impl Serialize) -> Result<Value, SearchError> {
+    serde_json::to_value(value).map_err(|cause| SearchError::Encode { cause })
+}
+
+fn encode_line(value: &Value) -> Result<Vec<u8>, SearchError> {
+    let mut line = serde_json::to_vec(value).map_err(|cause| SearchError::Encode { cause })?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn decode<T: DeserializeOwned>(response: Response) -> Result<T, SearchError> {
+    if !(200..300).contains(&response.status) {
+        let (error_type, message) = match serde_json::from_slice::<ClusterError>(&response.body) {
+            Ok(ClusterError {
+                error: ClusterErrorBody::Detailed { error_type, reason },
+            }) => (
+                Some(error_type),
+                reason.unwrap_or_else(|| String::from_utf8_lossy(&response.body).into_owned()),
+            ),
+            Ok(ClusterError {
+                error: ClusterErrorBody::Message(message),
+            }) => (None, message),
+            Err(_) => (None, String::from_utf8_lossy(&response.body).into_owned()),
+        };
+        return Err(SearchError::Cluster {
+            status: response.status,
+            error_type,
+            message,
+        });
+    }
+    serde_json::from_slice(&response.body).map_err(|cause| SearchError::Decode { cause })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> Response {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn search_responses_decode_either_total() {
+        let SearchResponse::<Value> { hits, .. } = decode(response(
+            200,
+            r#"{"hits":{"total":{"value":2,"relation":"eq"},"hits":[{"_index":"p","_id":"a b","_score":1.5,"_source":{"n":1}}]}}"#,
+        ))
+        .unwrap();
+        assert_eq!(Some(2), hits.total.map(Total::value));
+        assert_eq!("a b", hits.hits[0].id);
+
+        let SearchResponse::<Value> { hits, .. } =
+            decode(response(200, r#"{"hits":{"total":7,"hits":[]}}"#)).unwrap();
+        assert_eq!(Some(7), hits.total.map(Total::value));
+        assert_eq!("/p/_doc/a%20b%2F1", document_path("p", "a b/1"));
+    }
+
+    #[test]
+    fn cluster_errors_keep_their_type() {
+        let error = decode::<Value>(response(
+            404,
+            r#"{"error":{"type":"index_not_found_exception","reason":"no such index [p]"},"status":404}"#,
+        ))
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::Cluster { status: 404, error_type: Some(ref t), ref message }
+                if t == "index_not_found_exception" && message == "no such index [p]"
+        ));
+        assert!(matches!(
+            decode::<Value>(response(502, "bad gateway")).unwrap_err(),
+            SearchError::Cluster {
+                error_type: None,
+                ..
+            }
+        ));
+    }
+}
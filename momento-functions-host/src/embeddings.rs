@@ -0,0 +1,1058 @@
+//! Text embeddings from pluggable providers
+//!
+//! [Embedder] turns text into vectors. Write your indexing and search code against it, and
+//! you can swap [OpenAiEmbedder] for [BedrockTitanEmbedder], or for [StubEmbedder] while
+//! developing, without touching any HTTP plumbing. Wrap any of them in a [CachedEmbedder]
+//! to keep embeddings in your Momento cache, so repeated queries skip the provider entirely.
+//! To index many documents, [BatchEmbedder] chunks them to the provider's limits and paces
+//! itself when throttled, and [embed_in_workers] spreads them over spawned Functions. When a
+//! chunk fails, [BatchEmbedder::embed_each] returns a [ResumeToken] so the retry skips the
+//! chunks that are already indexed.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::embeddings::{CachedEmbedder, Embedder, OpenAiEmbedder};
+//! use std::time::Duration;
+//!
+//! let embedder = CachedEmbedder::new(OpenAiEmbedder::from_env(), Duration::from_secs(300));
+//! match embedder.embed(&["Portland Trail Blazers".to_string()]) {
+//!     Ok(vectors) => { /* query your vector index with vectors[0] */ }
+//!     Err(e) => eprintln!("embed failed: {e}"),
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::ops::Range;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::HostError;
+#[cfg(feature = "aws")]
+use crate::aws;
+use crate::cache::{self, CacheGetError, CacheSetError};
+use crate::encoding::Json;
+use crate::http::{self, HttpPostError};
+use crate::parallel::{self, ParallelError};
+use crate::retry::{self, RetryPolicy};
+
+/// An error occurred while embedding text.
+#[derive(Debug, thiserror::Error)]
+pub enum EmbedError {
+    /// The request to the embedding provider failed.
+    #[error(transparent)]
+    Http(#[from] HttpPostError<serde_json::Error>),
+    /// The embedding provider rejected the request.
+    #[error("Embedding provider returned status {status}: {message}")]
+    Provider {
+        /// The HTTP status the provider responded with.
+        status: u16,
+        /// The body of the provider's response.
+        message: String,
+    },
+    /// The embedding provider's response could not be decoded.
+    #[error("Failed to decode embedding response.")]
+    Decode {
+        /// The underlying decoding error.
+        #[from]
+        cause: serde_json::Error,
+    },
+    /// The embedding provider did not return one embedding for each text.
+    #[error("Embedding provider returned {actual} embeddings for {expected} texts")]
+    Count {
+        /// How many texts were embedded.
+        expected: usize,
+        /// How many embeddings came back.
+        actual: usize,
+    },
+    /// A cached embedding could not be read.
+    #[error(transparent)]
+    CacheRead(#[from] CacheGetError<Infallible>),
+    /// An embedding could not be cached.
+    #[error(transparent)]
+    CacheWrite(#[from] CacheSetError<Infallible>),
+}
+
+/// Turns text into embedding vectors.
+pub trait Embedder {
+    /// A name for the model and its settings, like `openai/text-embedding-3-small`.
+    ///
+    /// Embeddings from different models are not comparable, so [CachedEmbedder] keys its
+    /// entries by this id.
+    fn model_id(&self) -> String;
+
+    /// Embed each of `texts`, returning one vector per text in the same order.
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError>;
+}
+
+/// Embeddings from the OpenAI embeddings API.
+///
+/// Defaults to `text-embedding-3-small`, which returns 1536 dimensions.
+#[derive(Debug, Clone)]
+pub struct OpenAiEmbedder {
+    api_key: String,
+    model: String,
+    dimensions: Option<u32>,
+}
+
+impl OpenAiEmbedder {
+    /// Create an embedder that authenticates with `api_key`.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "text-embedding-3-small".to_string(),
+            dimensions: None,
+        }
+    }
+
+    /// Create an embedder with the API key from the `OPENAI_API_KEY` environment variable.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("OPENAI_API_KEY").unwrap_or_default())
+    }
+
+    /// Use a different embedding model, like `text-embedding-3-large`.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Ask the model for shorter vectors. Only supported by `text-embedding-3` models.
+    pub fn dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct OpenAiRequest<'a> {
+    model: &'a str,
+    encoding_format: &'static str,
+    input: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+impl Embedder for OpenAiEmbedder {
+    fn model_id(&self) -> String {
+        match self.dimensions {
+            Some(dimensions) => format!("openai/{}/{dimensions}", self.model),
+            None => format!("openai/{}", self.model),
+        }
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let response = http::post(
+            "https://api.openai.com/v1/embeddings",
+            [
+                (
+                    "authorization".to_string(),
+                    format!("Bearer {}", self.api_key),
+                ),
+                ("content-type".to_string(), "application/json".to_string()),
+            ],
+            Json(OpenAiRequest {
+                model: &self.model,
+                encoding_format: "float",
+                // OpenAI recommends replacing newlines, which can degrade embeddings.
+                input: texts.iter().map(|text| text.replace('\n', " ")).collect(),
+                dimensions: self.dimensions,
+            }),
+        )?;
+        let OpenAiResponse { mut data } = decode(response)?;
+        data.sort_by_key(|embedding| embedding.index);
+        expect_count(
+            texts.len(),
+            data.into_iter()
+                .map(|embedding| embedding.embedding)
+                .collect(),
+        )
+    }
+}
+
+/// Embeddings from Amazon Titan text embedding models on Bedrock.
+///
+/// Defaults to `amazon.titan-embed-text-v2:0`, which returns 1024 normalized dimensions.
+/// Titan embeds one text per request, so this makes one request for each text.
+#[cfg(feature = "aws")]
+#[derive(Clone)]
+pub struct BedrockTitanEmbedder {
+    credentials: aws::auth::Credentials,
+    region: String,
+    model: String,
+    dimensions: Option<u32>,
+    normalize: Option<bool>,
+}
+
+#[cfg(feature = "aws")]
+impl BedrockTitanEmbedder {
+    /// Create an embedder that calls Bedrock in `region` with `credentials`.
+    ///
+    /// ```rust,no_run
+    /// use momento_functions_host::build_environment_aws_credentials;
+    /// use momento_functions_host::embeddings::BedrockTitanEmbedder;
+    ///
+    /// let embedder = BedrockTitanEmbedder::new(build_environment_aws_credentials!(), "us-west-2")
+    ///     .dimensions(512);
+    /// ```
+    pub fn new(credentials: aws::auth::Credentials, region: impl Into<String>) -> Self {
+        Self {
+            credentials,
+            region: region.into(),
+            model: "amazon.titan-embed-text-v2:0".to_string(),
+            dimensions: None,
+            normalize: None,
+        }
+    }
+
+    /// Use a different Titan model, like `amazon.titan-embed-text-v1`.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Ask the model for 256 or 512 dimensions instead of 1024. Only supported by v2.
+    pub fn dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Whether the model should return unit length vectors. Only supported by v2.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = Some(normalize);
+        self
+    }
+}
+
+#[cfg(feature = "aws")]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TitanRequest<'a> {
+    input_text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    normalize: Option<bool>,
+}
+
+#[cfg(feature = "aws")]
+#[derive(Deserialize)]
+struct TitanResponse {
+    embedding: Vec<f32>,
+}
+
+#[cfg(feature = "aws")]
+impl Embedder for BedrockTitanEmbedder {
+    fn model_id(&self) -> String {
+        format!(
+            "bedrock/{}/{}/{}",
+            self.model,
+            self.dimensions.map(|d| d.to_string()).unwrap_or_default(),
+            self.normalize.map(|n| n.to_string()).unwrap_or_default(),
+        )
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        let url = format!(
+            "https://bedrock-runtime.{}.amazonaws.com/model/{}/invoke",
+            self.region, self.model
+        );
+        texts
+            .iter()
+            .map(|text| {
+                let response = http::post_aws_sigv4(
+                    url.as_str(),
+                    [
+                        ("content-type".to_string(), "application/json".to_string()),
+                        ("accept".to_string(), "application/json".to_string()),
+                    ],
+                    self.credentials.clone(),
+                    self.region.as_str(),
+                    "bedrock",
+                    Json(TitanRequest {
+                        input_text: text,
+                        dimensions: self.dimensions,
+                        normalize: self.normalize,
+                    }),
+                )?;
+                let TitanResponse { embedding } = decode(response)?;
+                Ok(embedding)
+            })
+            .collect()
+    }
+}
+
+/// Deterministic embeddings computed locally, for tests and development.
+///
+/// The same text always gets the same unit length vector, and different texts get unrelated
+/// ones. They carry no meaning, so similarity search over them returns arbitrary neighbors.
+#[derive(Debug, Clone, Copy)]
+pub struct StubEmbedder {
+    dimensions: usize,
+}
+
+impl StubEmbedder {
+    /// Create an embedder that returns vectors with `dimensions` entries.
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions }
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = Vec::with_capacity(self.dimensions);
+        let mut block = 0_u64;
+        while vector.len() < self.dimensions {
+            let digest = Sha256::new()
+                .chain_update(block.to_le_bytes())
+                .chain_update(text)
+                .finalize();
+            vector.extend(
+                digest
+                    .chunks_exact(2)
+                    .map(|pair| f32::from(i16::from_le_bytes([pair[0], pair[1]])) / 32768.0)
+                    .take(self.dimensions - vector.len()),
+            );
+            block += 1;
+        }
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if 0.0 < norm {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+impl Embedder for StubEmbedder {
+    fn model_id(&self) -> String {
+        format!("stub/{}", self.dimensions)
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+/// Keeps another [Embedder]'s embeddings in your Momento cache.
+///
+/// Each text is looked up by a hash of the model id and the text, and only the misses are
+/// sent to the wrapped embedder, in one call.
+#[derive(Debug, Clone)]
+pub struct CachedEmbedder<E> {
+    inner: E,
+    ttl: Duration,
+    key_prefix: String,
+}
+
+impl<E: Embedder> CachedEmbedder<E> {
+    /// Cache `inner`'s embeddings for `ttl`.
+    pub fn new(inner: E, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            key_prefix: "embedding:".to_string(),
+        }
+    }
+
+    /// Prefix cache keys with `key_prefix` instead of `embedding:`.
+    pub fn key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// The wrapped embedder.
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    fn cache_key(&self, model_id: &str, text: &str) -> String {
+        let digest = Sha256::new()
+            .chain_update(model_id)
+            .chain_update([0])
+            .chain_update(text)
+            .finalize();
+        let mut key = self.key_prefix.clone();
+        for byte in digest {
+            let _ = write!(key, "{byte:02x}");
+        }
+        key
+    }
+}
+
+impl<E: Embedder> Embedder for CachedEmbedder<E> {
+    fn model_id(&self) -> String {
+        self.inner.model_id()
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        let model_id = self.inner.model_id();
+        let keys: Vec<String> = texts
+            .iter()
+            .map(|text| self.cache_key(&model_id, text))
+            .collect();
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let mut misses = Vec::new();
+        for (index, key) in keys.iter().enumerate() {
+            let cached = cache::get::<Vec<u8>>(key.as_str())?.and_then(|bytes| from_bytes(&bytes));
+            if cached.is_none() {
+                misses.push(index);
+            }
+            embeddings.push(cached);
+        }
+        if !misses.is_empty() {
+            let missed_texts: Vec<String> = misses.iter().map(|&i| texts[i].clone()).collect();
+            let fetched = expect_count(misses.len(), self.inner.embed(&missed_texts)?)?;
+            for (index, embedding) in misses.into_iter().zip(fetched) {
+                cache::set(keys[index].as_str(), to_bytes(&embedding), self.ttl)?;
+                embeddings[index] = Some(embedding);
+            }
+        }
+        Ok(embeddings.into_iter().flatten().collect())
+    }
+}
+
+/// Embeds many texts in chunks a provider accepts, slowing down when it is throttled.
+///
+/// Texts are split into chunks of at most [chunk_size](Self::chunk_size) texts and
+/// [max_chunk_chars](Self::max_chunk_chars) characters, and each chunk is embedded with
+/// retries. When the provider throttles a chunk, the pause before the next one doubles;
+/// when a chunk goes through cleanly, it halves. A chunk that still fails is reported in
+/// [BatchEmbeddings::failures] and the rest carry on.
+///
+/// ```rust,no_run
+/// use momento_functions_host::embeddings::{BatchEmbedder, OpenAiEmbedder};
+///
+/// let texts: Vec<String> = (0..1000).map(|i| format!("article {i}")).collect();
+/// let batch = BatchEmbedder::new(OpenAiEmbedder::from_env()).chunk_size(100);
+/// let embedded = batch.embed_all(&texts);
+/// for failure in &embedded.failures {
+///     log::warn!("texts {}..{} failed: {}", failure.offset, failure.offset + failure.count, failure.message);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchEmbedder<E> {
+    inner: E,
+    chunk_size: usize,
+    max_chunk_chars: usize,
+    retry: RetryPolicy,
+    max_pause: Duration,
+}
+
+/// The outcome of embedding a batch of texts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchEmbeddings {
+    /// One entry per text, in order: its vector, or `None` if its chunk failed.
+    pub vectors: Vec<Option<Vec<f32>>>,
+    /// The chunks that failed.
+    pub failures: Vec<ChunkFailure>,
+}
+
+impl BatchEmbeddings {
+    /// Whether every text was embedded.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A chunk of texts that could not be embedded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkFailure {
+    /// The position of the chunk's first text.
+    pub offset: usize,
+    /// How many texts are in the chunk.
+    pub count: usize,
+    /// Why the chunk failed.
+    pub message: String,
+    /// Whether embedding the chunk again later may succeed.
+    pub retryable: bool,
+}
+
+/// What [BatchEmbedder::embed_each] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchProgress {
+    /// How many texts were embedded and handled by this call.
+    pub completed: usize,
+    /// How many texts were skipped because the resume token said they were done.
+    pub skipped: usize,
+    /// The chunks that failed, either embedding or in the handler.
+    pub failures: Vec<ChunkFailure>,
+    /// Pass this to the next call to skip the chunks that are done. `None` when nothing
+    /// failed.
+    pub resume_token: Option<ResumeToken>,
+}
+
+/// Which chunks of a [BatchEmbedder::embed_each] call are done, so a retry can skip them.
+///
+/// It is opaque text: print it with `to_string` to hand it to a caller, and `parse` it when
+/// they send it back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeToken {
+    #[serde(rename = "f")]
+    fingerprint: String,
+    /// Ranges of texts that are done, merged and in order.
+    #[serde(rename = "c")]
+    completed: Vec<(usize, usize)>,
+}
+
+/// A resume token could not be read, or was made for different texts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Resume token is invalid or was made for a different batch")]
+pub struct InvalidResumeToken;
+
+impl std::fmt::Display for ResumeToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_vec(self).map_err(|_| std::fmt::Error)?;
+        f.write_str(&URL_SAFE_NO_PAD.encode(json))
+    }
+}
+
+impl std::str::FromStr for ResumeToken {
+    type Err = InvalidResumeToken;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let json = URL_SAFE_NO_PAD
+            .decode(token.trim())
+            .map_err(|_| InvalidResumeToken)?;
+        serde_json::from_slice(&json).map_err(|_| InvalidResumeToken)
+    }
+}
+
+/// The [Partition](crate::parallel::Partition) an [embed_in_workers] worker receives.
+pub type EmbedPartition = crate::parallel::Partition<Vec<String>>;
+
+impl<E: Embedder> BatchEmbedder<E> {
+    /// Embed with `inner` in chunks of 100 texts and up to 100,000 characters, retrying each
+    /// chunk with the default [RetryPolicy].
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            chunk_size: 100,
+            max_chunk_chars: 100_000,
+            retry: RetryPolicy::default(),
+            max_pause: Duration::from_secs(5),
+        }
+    }
+
+    /// The most texts to send in one request. OpenAI accepts up to 2048, and Bedrock Titan
+    /// embeds one text per request regardless.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// The most characters to send in one request, to stay under a provider's token limit.
+    /// A single text longer than this is sent in a chunk of its own.
+    pub fn max_chunk_chars(mut self, max_chunk_chars: usize) -> Self {
+        self.max_chunk_chars = max_chunk_chars;
+        self
+    }
+
+    /// How to retry each chunk.
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The longest pause between chunks while the provider is throttling. Defaults to 5
+    /// seconds.
+    pub fn max_pause(mut self, max_pause: Duration) -> Self {
+        self.max_pause = max_pause;
+        self
+    }
+
+    /// Embed every one of `texts`.
+    pub fn embed_all(&self, texts: &[String]) -> BatchEmbeddings {
+        let mut embedded = BatchEmbeddings {
+            vectors: vec![None; texts.len()],
+            failures: Vec::new(),
+        };
+        let mut pause = Duration::ZERO;
+        for chunk in chunk_ranges(texts, self.chunk_size, self.max_chunk_chars) {
+            match self.embed_chunk(texts, chunk.clone(), &mut pause) {
+                Ok(vectors) => {
+                    for (slot, vector) in embedded.vectors[chunk].iter_mut().zip(vectors) {
+                        *slot = Some(vector);
+                    }
+                }
+                Err(e) => embedded.failures.push(failure(chunk, &e)),
+            }
+        }
+        embedded
+    }
+
+    /// Embed `texts` chunk by chunk, handing each chunk's vectors to `handle`, and return a
+    /// [ResumeToken] for the chunks that are done when any chunk fails.
+    ///
+    /// `handle` gets the chunk's range in `texts` and its vectors, and typically upserts
+    /// them into a vector index. A chunk is done when it was embedded and `handle` returned
+    /// `Ok`. Pass the token from a previous call with the same `texts` to skip the chunks it
+    /// ran, so a retry only redoes what failed. Tokens only apply to the texts and settings
+    /// they were made for; anything else is rejected.
+    ///
+    /// ```rust,no_run
+    /// use momento_functions_host::embeddings::{BatchEmbedder, OpenAiEmbedder, ResumeToken};
+    /// use momento_functions_host::vector_store::{TurbopufferStore, VectorDocument, VectorStore};
+    ///
+    /// # fn f(texts: Vec<String>, token: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let store = TurbopufferStore::from_env();
+    /// let resume = token.map(|token| token.parse::<ResumeToken>()).transpose()?;
+    /// let progress = BatchEmbedder::new(OpenAiEmbedder::from_env()).embed_each(
+    ///     &texts,
+    ///     resume.as_ref(),
+    ///     |range, vectors| {
+    ///         let documents: Vec<VectorDocument> = range
+    ///             .zip(vectors)
+    ///             .map(|(i, vector)| VectorDocument::new(i.to_string(), vector))
+    ///             .collect();
+    ///         store.upsert(&documents)
+    ///     },
+    /// )?;
+    /// if let Some(token) = progress.resume_token {
+    ///     log::warn!("{} chunks failed, resume with {token}", progress.failures.len());
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn embed_each<H: HostError>(
+        &self,
+        texts: &[String],
+        resume: Option<&ResumeToken>,
+        mut handle: impl FnMut(Range<usize>, Vec<Vec<f32>>) -> Result<(), H>,
+    ) -> Result<BatchProgress, InvalidResumeToken> {
+        let fingerprint = self.fingerprint(texts);
+        let mut done = match resume {
+            Some(token) if token.fingerprint != fingerprint => return Err(InvalidResumeToken),
+            Some(token) => token.completed.clone(),
+            None => Vec::new(),
+        };
+        let mut progress = BatchProgress::default();
+        let mut pause = Duration::ZERO;
+        for chunk in chunk_ranges(texts, self.chunk_size, self.max_chunk_chars) {
+            if done
+                .iter()
+                .any(|&(start, end)| start <= chunk.start && chunk.end <= end)
+            {
+                progress.skipped += chunk.len();
+                continue;
+            }
+            let result = self
+                .embed_chunk(texts, chunk.clone(), &mut pause)
+                .map_err(|e| failure(chunk.clone(), &e))
+                .and_then(|vectors| {
+                    handle(chunk.clone(), vectors).map_err(|e| {
+                        log::warn!("failed to handle embeddings for texts {chunk:?}: {e}");
+                        failure(chunk.clone(), &e)
+                    })
+                });
+            match result {
+                Ok(()) => {
+                    progress.completed += chunk.len();
+                    done.push((chunk.start, chunk.end));
+                }
+                Err(failure) => progress.failures.push(failure),
+            }
+        }
+        if !progress.failures.is_empty() {
+            progress.resume_token = Some(ResumeToken {
+                fingerprint,
+                completed: merge_ranges(done),
+            });
+        }
+        Ok(progress)
+    }
+
+    /// Embed one chunk with retries, pausing first if the provider has been throttling.
+    fn embed_chunk(
+        &self,
+        texts: &[String],
+        chunk: Range<usize>,
+        pause: &mut Duration,
+    ) -> Result<Vec<Vec<f32>>, EmbedError> {
+        if !pause.is_zero() {
+            std::thread::sleep(*pause);
+        }
+        let mut throttled = false;
+        let result = retry::with_backoff(&self.retry, || {
+            let result = self
+                .inner
+                .embed(&texts[chunk.clone()])
+                .and_then(|vectors| expect_count(chunk.len(), vectors));
+            if let Err(e) = &result {
+                throttled |= e.is_throttled();
+            }
+            result
+        });
+        *pause = match throttled {
+            true => (*pause * 2)
+                .max(Duration::from_millis(250))
+                .min(self.max_pause),
+            false if *pause < Duration::from_millis(20) => Duration::ZERO,
+            false => *pause / 2,
+        };
+        if let Err(e) = &result {
+            log::warn!("failed to embed texts {chunk:?}: {e}");
+        }
+        result
+    }
+
+    /// A hash of the texts and everything that decides how they are chunked.
+    fn fingerprint(&self, texts: &[String]) -> String {
+        let mut hasher = Sha256::new()
+            .chain_update(self.inner.model_id())
+            .chain_update([0])
+            .chain_update((self.chunk_size as u64).to_le_bytes())
+            .chain_update((self.max_chunk_chars as u64).to_le_bytes());
+        for text in texts {
+            hasher.update((text.len() as u64).to_le_bytes());
+            hasher.update(text);
+        }
+        let mut fingerprint = String::with_capacity(32);
+        for byte in &hasher.finalize()[..16] {
+            let _ = write!(fingerprint, "{byte:02x}");
+        }
+        fingerprint
+    }
+
+    /// Embed a worker's partition of an [embed_in_workers] job and report the result.
+    ///
+    /// ```rust,no_run
+    /// use momento_functions_host::embeddings::{BatchEmbedder, EmbedPartition, OpenAiEmbedder};
+    ///
+    /// // Registered with `momento_functions::spawn!(embed_worker, EmbedPartition);`
+    /// fn embed_worker(work: EmbedPartition) {
+    ///     let batch = BatchEmbedder::new(OpenAiEmbedder::from_env());
+    ///     if let Err(e) = batch.complete_partition(&work) {
+    ///         log::error!("failed to report partition {}: {e}", work.index);
+    ///     }
+    /// }
+    /// ```
+    pub fn complete_partition(&self, work: &EmbedPartition) -> Result<(), ParallelError> {
+        work.complete(self.embed_all(&work.payload))
+    }
+}
+
+/// Embed `texts` in parallel, splitting them among spawned `function_name` workers of
+/// `texts_per_worker` texts each.
+///
+/// Each worker should call [BatchEmbedder::complete_partition]. Workers that have not
+/// reported by `timeout` are reported as failed chunks rather than failing the whole batch.
+/// Every worker paces itself, so the provider sees the sum of their request rates.
+pub fn embed_in_workers(
+    function_name: impl AsRef<str>,
+    job_id: impl Into<String>,
+    texts: &[String],
+    texts_per_worker: usize,
+    timeout: Duration,
+) -> Result<BatchEmbeddings, ParallelError> {
+    let texts_per_worker = texts_per_worker.max(1);
+    let fan_out = parallel::fan_out(
+        function_name,
+        job_id,
+        texts.chunks(texts_per_worker).map(<[String]>::to_vec),
+    )?;
+    let results = match fan_out.wait::<BatchEmbeddings>(timeout) {
+        Ok(results) => results.into_iter().map(Some).collect(),
+        Err(ParallelError::TimedOut { .. }) => fan_out.poll::<BatchEmbeddings>()?,
+        Err(e) => return Err(e),
+    };
+    if let Err(e) = fan_out.clean_up() {
+        log::warn!(
+            "failed to clean up results of job {}: {e}",
+            fan_out.job_id()
+        );
+    }
+    Ok(merge_partitions(texts.len(), texts_per_worker, results))
+}
+
+/// Put each worker's embeddings back at its partition's offset.
+fn merge_partitions(
+    total: usize,
+    texts_per_worker: usize,
+    results: Vec<Option<BatchEmbeddings>>,
+) -> BatchEmbeddings {
+    let mut merged = BatchEmbeddings::default();
+    for (index, result) in results.into_iter().enumerate() {
+        let offset = index * texts_per_worker;
+        let count = texts_per_worker.min(total - offset);
+        match result {
+            Some(result) => {
+                merged.vectors.extend(result.vectors);
+                merged
+                    .failures
+                    .extend(result.failures.into_iter().map(|failure| ChunkFailure {
+                        offset: offset + failure.offset,
+                        ..failure
+                    }));
+            }
+            None => {
+                merged.vectors.extend(std::iter::repeat_n(None, count));
+                merged.failures.push(ChunkFailure {
+                    offset,
+                    count,
+                    message: "the worker did not report before the timeout".to_string(),
+                    retryable: true,
+                });
+            }
+        }
+    }
+    merged
+}
+
+fn failure(chunk: Range<usize>, error: &impl HostError) -> ChunkFailure {
+    ChunkFailure {
+        offset: chunk.start,
+        count: chunk.len(),
+        message: error.to_string(),
+        retryable: error.is_retryable(),
+    }
+}
+
+/// Sort `ranges` and join the ones that touch or overlap.
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Split `texts` into ranges of at most `chunk_size` texts and `max_chars` characters.
+fn chunk_ranges(texts: &[String], chunk_size: usize, max_chars: usize) -> Vec<Range<usize>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut chars = 0;
+    for (index, text) in texts.iter().enumerate() {
+        let len = text.chars().count();
+        if start < index && (chunk_size <= index - start || max_chars < chars + len) {
+            chunks.push(start..index);
+            start = index;
+            chars = 0;
+        }
+        chars += len;
+    }
+    if start < texts.len() {
+        chunks.push(start..texts.len());
+    }
+    chunks
+}
+
+fn decode<T: serde::de::DeserializeOwned>(mut response: http::Response) -> Result<T, EmbedError> {
+    if response.status != 200 {
+        return Err(EmbedError::Provider {
+            status: response.status,
+            message: String::from_utf8_lossy(&response.body).into_owned(),
+        });
+    }
+    let Json(body) = response.extract::<Json<T>>()?;
+    Ok(body)
+}
+
+fn expect_count(expected: usize, embeddings: Vec<Vec<f32>>) -> Result<Vec<Vec<f32>>, EmbedError> {
+    if embeddings.len() == expected {
+        Ok(embeddings)
+    } else {
+        Err(EmbedError::Count {
+            expected,
+            actual: embeddings.len(),
+        })
+    }
+}
+
+/// Little-endian `f32`s, the layout the examples have always cached embeddings in.
+fn to_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn from_bytes(bytes: &[u8]) -> Option<Vec<f32>> {
+    if !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+    Some(
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stub_embeddings_are_deterministic_unit_vectors() {
+        let stub = StubEmbedder::new(40);
+        let texts = [
+            "hello".to_string(),
+            "world".to_string(),
+            "hello".to_string(),
+        ];
+        let vectors = stub.embed(&texts).unwrap();
+        assert_eq!(3, vectors.len());
+        assert_eq!(vectors[0], vectors[2]);
+        assert_ne!(vectors[0], vectors[1]);
+        for vector in &vectors {
+            assert_eq!(40, vector.len());
+            let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-5, "{norm}");
+        }
+        assert!(StubEmbedder::new(0).embed(&texts).unwrap()[0].is_empty());
+    }
+
+    #[test]
+    fn cache_keys_depend_on_model_and_text() {
+        let embedder = CachedEmbedder::new(StubEmbedder::new(8), Duration::from_secs(1));
+        let key = embedder.cache_key("a", "text");
+        assert!(key.starts_with("embedding:"));
+        assert_eq!("embedding:".len() + 64, key.len());
+        assert_eq!(key, embedder.cache_key("a", "text"));
+        assert_ne!(key, embedder.cache_key("b", "text"));
+        assert_ne!(key, embedder.cache_key("a", "other"));
+        assert_ne!(embedder.cache_key("ab", "c"), embedder.cache_key("a", "bc"));
+    }
+
+    #[test]
+    fn chunks_respect_count_and_size() {
+        let texts: Vec<String> = ["aaaa", "bb", "cccccccc", "d", "e", "f"]
+            .iter()
+            .map(|text| text.to_string())
+            .collect();
+        assert_eq!(vec![0..3, 3..6], chunk_ranges(&texts, 3, 100));
+        assert_eq!(vec![0..2, 2..3, 3..6], chunk_ranges(&texts, 10, 7));
+        assert!(chunk_ranges(&[], 3, 100).is_empty());
+    }
+
+    #[test]
+    fn batches_report_failed_chunks() {
+        struct FailsOn(&'static str);
+        impl Embedder for FailsOn {
+            fn model_id(&self) -> String {
+                "fails".to_string()
+            }
+            fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError> {
+                match texts.iter().any(|text| text == self.0) {
+                    true => Err(EmbedError::Provider {
+                        status: 400,
+                        message: "bad input".to_string(),
+                    }),
+                    false => Ok(texts.iter().map(|_| vec![1.0]).collect()),
+                }
+            }
+        }
+
+        let texts: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let embedded = BatchEmbedder::new(FailsOn("2"))
+            .chunk_size(2)
+            .embed_all(&texts);
+        assert_eq!(
+            vec![true, true, false, false, true],
+            embedded
+                .vectors
+                .iter()
+                .map(Option::is_some)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, embedded.failures.len());
+        assert_eq!((2, 2, false), {
+            let failure = &embedded.failures[0];
+            (failure.offset, failure.count, failure.retryable)
+        });
+
+        let first_worker = BatchEmbedder::new(FailsOn("2"))
+            .chunk_size(2)
+            .embed_all(&texts[..3]);
+        let merged = merge_partitions(5, 3, vec![Some(first_worker), None]);
+        assert_eq!(5, merged.vectors.len());
+        assert_eq!(
+            vec![(2, 1), (3, 2)],
+            merged
+                .failures
+                .iter()
+                .map(|failure| (failure.offset, failure.count))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn resume_tokens_skip_completed_chunks() {
+        let texts: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let batch = BatchEmbedder::new(StubEmbedder::new(2)).chunk_size(2);
+        let mut handled = Vec::new();
+        let run = |resume: Option<&ResumeToken>, fail: bool, handled: &mut Vec<Range<usize>>| {
+            batch
+                .embed_each(&texts, resume, |range, vectors| {
+                    assert_eq!(range.len(), vectors.len());
+                    handled.push(range.clone());
+                    match fail && range.start == 2 {
+                        true => Err(EmbedError::Provider {
+                            status: 503,
+                            message: "index unavailable".to_string(),
+                        }),
+                        false => Ok(()),
+                    }
+                })
+                .unwrap()
+        };
+
+        let first = run(None, true, &mut handled);
+        assert_eq!((5, 0), (first.completed, first.skipped));
+        assert_eq!((2, 2, true), {
+            let failure = &first.failures[0];
+            (failure.offset, failure.count, failure.retryable)
+        });
+        let token = first.resume_token.unwrap();
+        assert_eq!(vec![(0, 2), (4, 7)], token.completed);
+
+        let token: ResumeToken = token.to_string().parse().unwrap();
+        handled.clear();
+        let second = run(Some(&token), false, &mut handled);
+        assert_eq!(vec![2..4], handled);
+        assert_eq!(
+            (2, 5, None),
+            (second.completed, second.skipped, second.resume_token)
+        );
+
+        let other = BatchEmbedder::new(StubEmbedder::new(2)).chunk_size(3);
+        let rejected = other.embed_each(&texts, Some(&token), |_, _| Ok::<_, EmbedError>(()));
+        assert_eq!(Err(InvalidResumeToken), rejected);
+        assert_eq!(
+            Err(InvalidResumeToken),
+            "not a token".parse::<ResumeToken>()
+        );
+    }
+
+    #[test]
+    fn ranges_merge_when_they_touch() {
+        assert_eq!(
+            vec![(0, 4), (6, 9)],
+            merge_ranges(vec![(6, 8), (2, 4), (0, 2), (7, 9)])
+        );
+        assert!(merge_ranges(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn cached_bytes_round_trip() {
+        let embedding = vec![0.5, -1.25, 3.0];
+        assert_eq!(Some(embedding.clone()), from_bytes(&to_bytes(&embedding)));
+        assert_eq!(None, from_bytes(&[0, 1, 2]));
+    }
+}
@@ -85,6 +85,44 @@ impl Extract for Vec<u8> {
     }
 }
 
+/// The exact bytes of a payload, as they were received.
+///
+/// On its own, this is the same as extracting `Vec<u8>`. Pair it with another
+/// extractor as `(Body, T)` to receive both the raw bytes and the parsed value, for
+/// payloads like webhooks where a signature covers the bytes on the wire.
+///
+/// To verify the bytes before parsing them at all, take just the [Body] and call
+/// [Body::extract] once you are satisfied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Body(pub Vec<u8>);
+
+impl Body {
+    /// The raw bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Parse the bytes with another extractor.
+    pub fn extract<T: Extract>(self) -> Result<T, T::Error> {
+        T::extract(self.0)
+    }
+}
+
+impl Extract for Body {
+    type Error = Infallible;
+    fn extract(payload: Vec<u8>) -> Result<Self, Self::Error> {
+        Ok(Body(payload))
+    }
+}
+
+impl<T: Extract> Extract for (Body, T) {
+    type Error = T::Error;
+    fn extract(payload: Vec<u8>) -> Result<Self, Self::Error> {
+        let value = T::extract(payload.clone())?;
+        Ok((Body(payload), value))
+    }
+}
+
 /// JSON encoding and decoding
 pub struct Json<T>(pub T);
 impl<T: serde::de::DeserializeOwned> Extract for Json<T> {
@@ -0,0 +1,156 @@
+//! Gather a page's data from several sources at once
+//!
+//! Hydrating a page often means a handful of unrelated lookups: a profile from the cache, an
+//! order from DynamoDB, a recommendation from an HTTP service. [fetch_all] takes them as a
+//! tuple of closures and returns a tuple of their results, each with its own type and its
+//! own error, so one failed source doesn't throw away the others. The page can render what
+//! it has and fall back for the rest.
+//!
+//! The host runs one call at a time today, so the lookups run in order. When the host
+//! can run calls concurrently, `fetch_all` will issue them together, and code written
+//! against it gets that without changes. Don't make one lookup depend on another's side
+//! effects.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::aws::ddb::{DynamoDBClient, Item};
+//! use momento_functions_host::encoding::Json;
+//! use momento_functions_host::fetch::fetch_all;
+//! use momento_functions_host::{cache, http};
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Profile {
+//!     name: String,
+//! }
+//!
+//! fn page(ddb: &DynamoDBClient, user: &str) -> String {
+//!     let (profile, order, recommendations) = fetch_all((
+//!         || cache::get::<Json<Profile>>(format!("profile:{user}")),
+//!         || ddb.get_item_raw("orders", ("user", user)),
+//!         || http::get(format!("https://recs.example.com/{user}"), []),
+//!     ));
+//!     let name = match profile {
+//!         Ok(Some(Json(profile))) => profile.name,
+//!         Ok(None) => "stranger".to_string(),
+//!         Err(e) => {
+//!             log::warn!("profile unavailable: {e}");
+//!             "stranger".to_string()
+//!         }
+//!     };
+//!     let order: Option<Item> = order.ok().flatten();
+//!     let recommendations = recommendations.map(|r| r.body).unwrap_or_default();
+//!     format!(
+//!         "hello {name}: {} order(s), {} bytes of recommendations",
+//!         order.iter().count(),
+//!         recommendations.len()
+//!     )
+//! }
+//! ```
+
+/// A set of lookups [fetch_all] can run: a tuple of up to 8 closures that each return a
+/// `Result`.
+pub trait Lookups {
+    /// The lookups' results, a tuple in the same order.
+    type Output;
+
+    /// Run every lookup, returning each one's result.
+    fn fetch_all(self) -> Self::Output;
+}
+
+/// Run every lookup, returning each one's result in a tuple in the same order.
+///
+/// Every lookup runs, even when an earlier one fails.
+pub fn fetch_all<L: Lookups>(lookups: L) -> L::Output {
+    lookups.fetch_all()
+}
+
+macro_rules! impl_lookups {
+    ($(($lookup:ident, $value:ident, $error:ident, $index:tt)),+) => {
+        impl<$($lookup, $value, $error),+> Lookups for ($($lookup,)+)
+        where
+            $($lookup: FnOnce() -> Result<$value, $error>),+
+        {
+            type Output = ($(Result<$value, $error>,)+);
+
+            fn fetch_all(self) -> Self::Output {
+                // Tuple fields are evaluated in order, so lookups run first to last.
+                ($((self.$index)(),)+)
+            }
+        }
+    };
+}
+
+impl_lookups!((A, AV, AE, 0));
+impl_lookups!((A, AV, AE, 0), (B, BV, BE, 1));
+impl_lookups!((A, AV, AE, 0), (B, BV, BE, 1), (C, CV, CE, 2));
+impl_lookups!(
+    (A, AV, AE, 0),
+    (B, BV, BE, 1),
+    (C, CV, CE, 2),
+    (D, DV, DE, 3)
+);
+impl_lookups!(
+    (A, AV, AE, 0),
+    (B, BV, BE, 1),
+    (C, CV, CE, 2),
+    (D, DV, DE, 3),
+    (E, EV, EE, 4)
+);
+impl_lookups!(
+    (A, AV, AE, 0),
+    (B, BV, BE, 1),
+    (C, CV, CE, 2),
+    (D, DV, DE, 3),
+    (E, EV, EE, 4),
+    (F, FV, FE, 5)
+);
+impl_lookups!(
+    (A, AV, AE, 0),
+    (B, BV, BE, 1),
+    (C, CV, CE, 2),
+    (D, DV, DE, 3),
+    (E, EV, EE, 4),
+    (F, FV, FE, 5),
+    (G, GV, GE, 6)
+);
+impl_lookups!(
+    (A, AV, AE, 0),
+    (B, BV, BE, 1),
+    (C, CV, CE, 2),
+    (D, DV, DE, 3),
+    (E, EV, EE, 4),
+    (F, FV, FE, 5),
+    (G, GV, GE, 6),
+    (H, HV, HE, 7)
+);
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn every_lookup_runs_and_keeps_its_own_error() {
+        let ran = Cell::new(0);
+        let (count, missing, name) = fetch_all((
+            || {
+                ran.set(ran.get() + 1);
+                Ok::<_, String>(3_u32)
+            },
+            || {
+                ran.set(ran.get() + 1);
+                Err::<u32, _>(std::fmt::Error)
+            },
+            || {
+                ran.set(ran.get() + 1);
+                Ok::<_, std::io::Error>("ada")
+            },
+        ));
+        assert_eq!(3, ran.get());
+        assert_eq!(Ok(3), count);
+        assert!(missing.is_err());
+        assert_eq!("ada", name.unwrap());
+    }
+}
@@ -0,0 +1,358 @@
+//! Feature flags and experiments from a shared JSON document
+//!
+//! Configure a [FlagSource] once per instance, then ask whether a flag is enabled for a unit,
+//! like a user id or session id. The document is fetched on first use and refreshed after
+//! the refresh interval. When a refresh fails, the last document keeps being used.
+//!
+//! Bucketing is consistent: a unit always lands in the same bucket for a given flag or
+//! experiment, on every instance, so raising a rollout percentage only ever adds units.
+//!
+//! The document looks like this:
+//! ```json
+//! {
+//!     "flags": {
+//!         "new-checkout": { "enabled": true, "rollout_percent": 25, "allow": ["user-7"] },
+//!         "dark-mode": { "enabled": false }
+//!     },
+//!     "experiments": {
+//!         "button-color": {
+//!             "variants": [{ "name": "blue", "weight": 1 }, { "name": "green", "weight": 1 }]
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::flags::{self, FlagSource};
+//! use std::time::Duration;
+//!
+//! flags::configure(
+//!     FlagSource::Http {
+//!         url: "https://config.example.com/flags.json".to_string(),
+//!         headers: vec![],
+//!     },
+//!     Duration::from_secs(60),
+//! );
+//!
+//! let user_id = "user-42";
+//! if flags::enabled("new-checkout", user_id) {
+//!     println!("new checkout");
+//! }
+//! match flags::variant("button-color", user_id).as_deref() {
+//!     Some("green") => println!("green button"),
+//!     _ => println!("default button"),
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::refresh::RefreshTimer;
+
+/// The number of buckets units are hashed into. Rollout percentages have a resolution of
+/// 1/100th of a percent.
+pub(crate) const BUCKETS: u64 = 10_000;
+
+/// An error occurred while loading the flag document.
+#[derive(Debug, thiserror::Error)]
+pub enum FlagsError {
+    /// No source has been set with [configure].
+    #[error("Feature flags have not been configured")]
+    NotConfigured,
+    /// The document could not be fetched.
+    #[error("Failed to fetch the flag document: {message}")]
+    Fetch {
+        /// Why the fetch failed.
+        message: String,
+    },
+    /// The document was fetched, but is not a valid flag document.
+    #[error("Invalid flag document: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+/// Where to load the flag document from.
+pub enum FlagSource {
+    /// A fixed document, useful for tests and defaults.
+    Static(FlagDocument),
+    /// Fetch the document over HTTP with a GET.
+    #[cfg(feature = "http")]
+    Http {
+        /// The document's URL.
+        url: String,
+        /// Headers to send, like an authorization header.
+        headers: Vec<(String, String)>,
+    },
+    /// Fetch the document from an S3 object.
+    #[cfg(feature = "aws")]
+    S3 {
+        /// The client to read the object with.
+        client: crate::aws::s3::S3Client,
+        /// The bucket holding the document.
+        bucket: String,
+        /// The document's key.
+        key: String,
+    },
+}
+
+/// A set of feature flags and experiments.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FlagDocument {
+    /// Flags by name.
+    #[serde(default)]
+    pub flags: HashMap<String, Flag>,
+    /// Experiments by name.
+    #[serde(default)]
+    pub experiments: HashMap<String, Experiment>,
+}
+
+/// A feature flag.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Flag {
+    /// When false, the flag is off for everyone except the `allow` list.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The percentage of units the flag is on for, when enabled. Defaults to 100.
+    #[serde(default = "default_rollout_percent")]
+    pub rollout_percent: f64,
+    /// Units the flag is always on for.
+    #[serde(default)]
+    pub allow: HashSet<String>,
+    /// Units the flag is always off for. This wins over `allow`.
+    #[serde(default)]
+    pub deny: HashSet<String>,
+}
+
+fn default_rollout_percent() -> f64 {
+    100.0
+}
+
+/// An experiment, splitting units between weighted variants.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Experiment {
+    /// The variants. A unit's share of each is its weight over the total weight.
+    pub variants: Vec<Variant>,
+}
+
+/// One arm of an [Experiment].
+#[derive(Debug, Clone, Deserialize)]
+pub struct Variant {
+    /// The variant's name.
+    pub name: String,
+    /// The variant's relative weight.
+    pub weight: u32,
+}
+
+impl FlagDocument {
+    /// Parse a flag document from JSON.
+    pub fn from_json(json: &[u8]) -> Result<Self, FlagsError> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    /// Whether the flag is on for `unit_id`. Unknown flags are off.
+    pub fn enabled(&self, name: &str, unit_id: &str) -> bool {
+        let Some(flag) = self.flags.get(name) else {
+            return false;
+        };
+        if flag.deny.contains(unit_id) {
+            return false;
+        }
+        if flag.allow.contains(unit_id) {
+            return true;
+        }
+        flag.enabled && (bucket(name, unit_id) as f64) < flag.rollout_percent * 100.0
+    }
+
+    /// The experiment variant `unit_id` is assigned to. `None` for unknown experiments
+    /// and experiments without weighted variants.
+    pub fn variant(&self, name: &str, unit_id: &str) -> Option<&str> {
+        let experiment = self.experiments.get(name)?;
+        let total: u64 = experiment
+            .variants
+            .iter()
+            .map(|variant| u64::from(variant.weight))
+            .sum();
+        if total == 0 {
+            return None;
+        }
+        let mut point = bucket(name, unit_id) * total / BUCKETS;
+        for variant in &experiment.variants {
+            let weight = u64::from(variant.weight);
+            if point < weight {
+                return Some(&variant.name);
+            }
+            point -= weight;
+        }
+        None
+    }
+}
+
+/// Hash a unit into one of [BUCKETS] buckets for a flag or experiment.
+///
+/// This is FNV-1a, which is stable across instances and releases, unlike the standard
+/// library's hashers.
+pub(crate) fn bucket(name: &str, unit_id: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.bytes().chain([b':']).chain(unit_id.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash % BUCKETS
+}
+
+struct State {
+    source: FlagSource,
+    refresh_interval: Duration,
+    document: Option<FlagDocument>,
+    refresh: RefreshTimer,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+/// Set where flags are loaded from, and how often to refresh them.
+///
+/// The document is fetched lazily, on the next lookup. Configuring again replaces the source
+/// and discards the current document.
+pub fn configure(source: FlagSource, refresh_interval: Duration) {
+    let mut state = STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *state = Some(State {
+        source,
+        refresh_interval,
+        document: None,
+        refresh: RefreshTimer::new(),
+    });
+}
+
+/// Fetch the flag document now, rather than waiting for the refresh interval.
+pub fn refresh() -> Result<(), FlagsError> {
+    let mut state = STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let state = state.as_mut().ok_or(FlagsError::NotConfigured)?;
+    let document = fetch(&state.source)?;
+    state.document = Some(document);
+    state.refresh.restart();
+    Ok(())
+}
+
+/// Whether the flag is on for `unit_id`.
+///
+/// Flags are off when they are unknown, when flags have not been configured, or when the
+/// document has never been fetched successfully.
+pub fn enabled(name: &str, unit_id: &str) -> bool {
+    with_document(|document| document.enabled(name, unit_id)).unwrap_or(false)
+}
+
+/// The experiment variant `unit_id` is assigned to.
+pub fn variant(name: &str, unit_id: &str) -> Option<String> {
+    with_document(|document| document.variant(name, unit_id).map(str::to_string)).flatten()
+}
+
+fn with_document<T>(f: impl FnOnce(&FlagDocument) -> T) -> Option<T> {
+    let mut state = STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let state = state.as_mut()?;
+    if state.refresh.due(state.refresh_interval) {
+        match fetch(&state.source) {
+            Ok(document) => state.document = Some(document),
+            Err(e) => log::warn!("failed to refresh feature flags: {e}"),
+        }
+    }
+    state.document.as_ref().map(f)
+}
+
+fn fetch(source: &FlagSource) -> Result<FlagDocument, FlagsError> {
+    match source {
+        FlagSource::Static(document) => Ok(document.clone()),
+        #[cfg(feature = "http")]
+        FlagSource::Http { url, headers } => {
+            let response =
+                crate::http::get(url.clone(), headers.clone()).map_err(|e| FlagsError::Fetch {
+                    message: e.to_string(),
+                })?;
+            if !(200..300).contains(&response.status) {
+                return Err(FlagsError::Fetch {
+                    message: format!("{url} returned status {}", response.status),
+                });
+            }
+            FlagDocument::from_json(&response.body)
+        }
+        #[cfg(feature = "aws")]
+        FlagSource::S3 {
+            client,
+            bucket,
+            key,
+        } => match client.get::<Vec<u8>>(bucket.clone(), key.clone()) {
+            Ok(Some(json)) => FlagDocument::from_json(&json),
+            Ok(None) => Err(FlagsError::Fetch {
+                message: format!("s3://{bucket}/{key} does not exist"),
+            }),
+            Err(e) => Err(FlagsError::Fetch {
+                message: e.to_string(),
+            }),
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn document() -> FlagDocument {
+        FlagDocument::from_json(
+            br#"{
+                "flags": {
+                    "half": { "enabled": true, "rollout_percent": 50, "allow": ["vip"], "deny": ["banned"] },
+                    "off": { "enabled": false, "allow": ["vip"] }
+                },
+                "experiments": {
+                    "color": { "variants": [{ "name": "blue", "weight": 1 }, { "name": "green", "weight": 3 }] }
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rollout_is_consistent_and_proportional() {
+        let document = document();
+        let units: Vec<String> = (0..2000).map(|i| format!("user-{i}")).collect();
+        let on = units
+            .iter()
+            .filter(|unit| document.enabled("half", unit))
+            .count();
+        assert!((800..1200).contains(&on), "{on} of 2000 enabled");
+        for unit in &units {
+            assert_eq!(
+                document.enabled("half", unit),
+                document.enabled("half", unit)
+            );
+        }
+    }
+
+    #[test]
+    fn allow_and_deny_lists_override_rollout() {
+        let document = document();
+        assert!(document.enabled("off", "vip"));
+        assert!(!document.enabled("off", "user-1"));
+        assert!(!document.enabled("half", "banned"));
+        assert!(!document.enabled("missing", "vip"));
+    }
+
+    #[test]
+    fn variants_follow_weights() {
+        let document = document();
+        let green = (0..2000)
+            .filter(|i| document.variant("color", &format!("user-{i}")) == Some("green"))
+            .count();
+        assert!((1300..1700).contains(&green), "{green} of 2000 green");
+        assert_eq!(None, document.variant("missing", "user-1"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Timings of recent host calls
+//!
+//! Cache and HTTP calls made through this crate record how long they took. [recent] returns
+//! the most recent ones, oldest first, so a slow request can be traced to the call that made
+//! it slow. Only this instance's calls are recorded, and only the last [CAPACITY] are kept.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::host_calls;
+//!
+//! for timing in host_calls::recent() {
+//!     log::debug!("{} took {:?} (ok: {})", timing.call, timing.duration, timing.ok);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many timings are kept.
+pub const CAPACITY: usize = 64;
+
+/// How long one host call took.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostCallTiming {
+    /// The call, like `cache.get` or `http.post`.
+    pub call: &'static str,
+    /// How long it took.
+    pub duration: Duration,
+    /// Whether it succeeded.
+    pub ok: bool,
+}
+
+static RECENT: Mutex<VecDeque<HostCallTiming>> = Mutex::new(VecDeque::new());
+
+/// The most recent host calls, oldest first.
+pub fn recent() -> Vec<HostCallTiming> {
+    lock().iter().copied().collect()
+}
+
+/// Forget the recorded timings.
+pub fn clear() {
+    lock().clear();
+}
+
+/// Run `f`, a host call, and record how long it took.
+pub(crate) fn timed<T, E>(call: &'static str, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let start = Instant::now();
+    let result = f();
+    record(HostCallTiming {
+        call,
+        duration: start.elapsed(),
+        ok: result.is_ok(),
+    });
+    result
+}
+
+fn record(timing: HostCallTiming) {
+    let mut recent = lock();
+    if recent.len() == CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(timing);
+}
+
+fn lock() -> std::sync::MutexGuard<'static, VecDeque<HostCallTiming>> {
+    RECENT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_most_recent_calls() {
+        clear();
+        assert_eq!(Ok(1), timed("cache.get", || Ok::<_, ()>(1)));
+        assert_eq!(Err(()), timed("http.get", || Err::<(), _>(())));
+        let recorded: Vec<_> = recent().iter().map(|t| (t.call, t.ok)).collect();
+        assert_eq!(vec![("cache.get", true), ("http.get", false)], recorded);
+
+        for _ in 0..CAPACITY {
+            let _ = timed("cache.set", || Ok::<_, ()>(()));
+        }
+        let recent = recent();
+        assert_eq!(CAPACITY, recent.len());
+        assert!(recent.iter().all(|timing| timing.call == "cache.set"));
+    }
+}
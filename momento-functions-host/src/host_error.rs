@@ -0,0 +1,938 @@
+//! A common classification for errors from host interfaces
+//!
+//! Every module has its own error enum, which is the right shape for handling a specific
+//! failure. [HostError] sorts all of them into the same few [ErrorKind]s, so code that only
+//! needs to know whether to retry, back off, or give up, like retry loops and circuit
+//! breakers, can be written once for every host interface.
+
+/// What went wrong, in terms that are the same for every host interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The credentials or permissions were rejected.
+    Unauthorized,
+    /// The request was rate limited or hit a limit. Back off before retrying.
+    Throttled,
+    /// The service timed out, was unreachable, or failed internally. Retrying may succeed.
+    Unavailable,
+    /// The request was malformed or had invalid arguments.
+    InvalidRequest,
+    /// The thing the request refers to does not exist.
+    NotFound,
+    /// A precondition or condition on the request did not hold.
+    Conflict,
+    /// A value could not be encoded or extracted on this side of the call.
+    Encoding,
+    /// Any other failure.
+    Other,
+}
+
+/// Whose side of a call an error is on, in the terms of HTTP status classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusClass {
+    /// Like a 4xx: the request was refused, and sending it again unchanged will be refused
+    /// again, unless it was [throttled](ErrorKind::Throttled).
+    ClientError,
+    /// Like a 5xx: the call failed for reasons outside the request.
+    ServerError,
+}
+
+impl ErrorKind {
+    /// Whether the same request may succeed if it is sent again.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ErrorKind::Throttled | ErrorKind::Unavailable)
+    }
+
+    /// The HTTP status class this kind of error corresponds to.
+    pub fn status_class(self) -> StatusClass {
+        match self {
+            ErrorKind::Unauthorized
+            | ErrorKind::Throttled
+            | ErrorKind::InvalidRequest
+            | ErrorKind::NotFound
+            | ErrorKind::Conflict => StatusClass::ClientError,
+            ErrorKind::Unavailable | ErrorKind::Encoding | ErrorKind::Other => {
+                StatusClass::ServerError
+            }
+        }
+    }
+
+    /// Classify an HTTP response status. `None` for statuses that are not errors.
+    ///
+    /// Host HTTP calls return error statuses as responses rather than errors, so use this
+    /// to treat them like any other host error.
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            0..400 => None,
+            401 | 403 | 407 => Some(ErrorKind::Unauthorized),
+            404 | 410 => Some(ErrorKind::NotFound),
+            409 | 412 => Some(ErrorKind::Conflict),
+            429 => Some(ErrorKind::Throttled),
+            408 | 500..600 => Some(ErrorKind::Unavailable),
+            400..500 => Some(ErrorKind::InvalidRequest),
+            _ => Some(ErrorKind::Other),
+        }
+    }
+}
+
+/// An error from a host interface.
+///
+/// To retry calls that fail with a retryable error, use [crate::retry::with_backoff].
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_host::{HostError, StatusClass};
+///
+/// /// Respond the same way to any host failure.
+/// fn status_for(error: &impl HostError) -> u16 {
+///     if error.is_throttled() {
+///         return 503;
+///     }
+///     match error.status_class() {
+///         StatusClass::ClientError => 400,
+///         StatusClass::ServerError => 502,
+///     }
+/// }
+///
+/// match momento_functions_host::cache::get::<Vec<u8>>("my_key") {
+///     Ok(value) => { /* use value */ }
+///     Err(e) => eprintln!("cache get failed ({:?}), responding {}: {e}", e.kind(), status_for(&e)),
+/// }
+/// ```
+pub trait HostError: std::error::Error {
+    /// What went wrong.
+    fn kind(&self) -> ErrorKind;
+
+    /// Whether the same request may succeed if it is sent again.
+    fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// Whether the request was rate limited.
+    fn is_throttled(&self) -> bool {
+        self.kind() == ErrorKind::Throttled
+    }
+
+    /// Whether the credentials or permissions were rejected.
+    fn is_auth(&self) -> bool {
+        self.kind() == ErrorKind::Unauthorized
+    }
+
+    /// The HTTP status class this error corresponds to.
+    fn status_class(&self) -> StatusClass {
+        self.kind().status_class()
+    }
+}
+
+/// Implement [HostError] for a module's error enum. Variants listed as `encoding` hold a
+/// local encode or extract failure; variants listed as `source` wrap an error that is
+/// already classified.
+macro_rules! host_error {
+    (
+        $error:ident $(<$generic:ident: $bound:path>)? {
+            $(encoding: [$($encoding:ident),* $(,)?],)?
+            $(source: [$($source:ident),* $(,)?],)?
+            $(other: { $($pattern:pat => $kind:expr),* $(,)? },)?
+        }
+    ) => {
+        impl $(<$generic: $bound>)? HostError for $error $(<$generic>)? {
+            fn kind(&self) -> ErrorKind {
+                match self {
+                    $($(Self::$encoding { .. } => ErrorKind::Encoding,)*)?
+                    $($(Self::$source(e) => e.kind(),)*)?
+                    $($($pattern => $kind,)*)?
+                }
+            }
+        }
+    };
+}
+
+mod cache {
+    use super::{ErrorKind, HostError};
+    use crate::cache::*;
+    use crate::encoding::{EncodeError, ExtractError};
+    use momento_functions_wit::host::momento::functions::{cache_list, cache_scalar};
+
+    impl HostError for cache_scalar::Error {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                cache_scalar::Error::InternalError
+                | cache_scalar::Error::RequestCancelled
+                | cache_scalar::Error::Timeout => ErrorKind::Unavailable,
+                cache_scalar::Error::InvalidArgument(_) => ErrorKind::InvalidRequest,
+                cache_scalar::Error::PermissionDenied(_) => ErrorKind::Unauthorized,
+                cache_scalar::Error::LimitExceeded(_) => ErrorKind::Throttled,
+                cache_scalar::Error::FailedPrecondition(_) => ErrorKind::Conflict,
+                cache_scalar::Error::NotFound(_) => ErrorKind::NotFound,
+            }
+        }
+    }
+
+    impl HostError for cache_list::Error {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                cache_list::Error::InternalError
+                | cache_list::Error::RequestCancelled
+                | cache_list::Error::Timeout => ErrorKind::Unavailable,
+                cache_list::Error::InvalidArgument(_) => ErrorKind::InvalidRequest,
+                cache_list::Error::PermissionDenied(_) => ErrorKind::Unauthorized,
+                cache_list::Error::LimitExceeded(_) => ErrorKind::Throttled,
+                cache_list::Error::FailedPrecondition(_) => ErrorKind::Conflict,
+                cache_list::Error::NotFound(_) => ErrorKind::NotFound,
+            }
+        }
+    }
+
+    host_error!(CacheSetError<E: EncodeError> { encoding: [EncodeFailed], source: [CacheError], });
+    host_error!(CacheGetError<E: ExtractError> { encoding: [ExtractFailed], source: [CacheError], });
+    host_error!(CacheSetIfError<E: EncodeError> { encoding: [EncodeFailed], source: [CacheError], });
+    host_error!(CacheDeleteError {
+        source: [CacheError],
+    });
+    host_error!(CacheItemMetadataError {
+        source: [CacheError],
+        other: { CacheItemMetadataError::Unsupported(_) => ErrorKind::Other },
+    });
+    host_error!(CacheGetWithHashError<E: ExtractError> { encoding: [ExtractFailed], source: [CacheError], });
+    host_error!(CacheSetIfHashError<E: EncodeError> { encoding: [EncodeFailed], source: [CacheError], });
+    host_error!(CacheListPushBackError<E: EncodeError> { encoding: [EncodeFailed], source: [CacheError], });
+    host_error!(CacheListPushFrontError<E: EncodeError> { encoding: [EncodeFailed], source: [CacheError], });
+    host_error!(CacheListFetchError<E: ExtractError> { encoding: [ExtractFailed], source: [CacheError], });
+}
+
+mod concurrency {
+    use super::{ErrorKind, HostError};
+    use crate::concurrency::ConcurrencyError;
+
+    host_error!(ConcurrencyError {
+        other: {
+            ConcurrencyError::Saturated { .. } => ErrorKind::Throttled,
+            ConcurrencyError::TimedOut { .. } => ErrorKind::Throttled,
+        },
+    });
+}
+
+mod config_store {
+    use super::{ErrorKind, HostError};
+    use crate::config_store::ConfigStoreError;
+
+    host_error!(ConfigStoreError {
+        other: {
+            ConfigStoreError::Unavailable { .. } => ErrorKind::Unavailable,
+            ConfigStoreError::NotAttached => ErrorKind::NotFound,
+            ConfigStoreError::Invalid { .. } => ErrorKind::Encoding,
+            ConfigStoreError::Unsupported(_) => ErrorKind::Other,
+        },
+    });
+}
+
+#[cfg(feature = "control")]
+mod control {
+    use super::{ErrorKind, HostError};
+    use crate::control::ControlError;
+
+    host_error!(ControlError {
+        other: {
+            ControlError::PermissionDenied { .. } => ErrorKind::Unauthorized,
+            ControlError::InvalidArgument { .. } => ErrorKind::InvalidRequest,
+            // An account limit on caches or topics does not lift by waiting.
+            ControlError::LimitExceeded { .. } => ErrorKind::Conflict,
+            ControlError::Other { .. } => ErrorKind::Other,
+            ControlError::Unsupported(_) => ErrorKind::Other,
+        },
+    });
+}
+
+mod diagnostics {
+    use super::{ErrorKind, HostError};
+    use crate::diagnostics::DiagnosticsError;
+
+    host_error!(DiagnosticsError {
+        other: {
+            DiagnosticsError::InvalidUrl { .. } => ErrorKind::InvalidRequest,
+            DiagnosticsError::UnknownPort { .. } => ErrorKind::InvalidRequest,
+            DiagnosticsError::Unsupported(_) => ErrorKind::Other,
+        },
+    });
+}
+
+mod keyed_set {
+    use super::{ErrorKind, HostError};
+    use crate::keyed_set::KeyedSetError;
+
+    host_error!(KeyedSetError {
+        encoding: [InvalidManifest, Encode],
+        source: [ManifestRead, ManifestCreate, ManifestUpdate, Write, Delete, Metadata],
+        other: {
+            KeyedSetError::Contended { .. } => ErrorKind::Conflict,
+        },
+    });
+}
+
+mod quota {
+    use super::{ErrorKind, HostError};
+    use crate::quota::QuotaError;
+
+    host_error!(QuotaError {
+        encoding: [InvalidCounter],
+        source: [Read, Create, Update],
+        other: {
+            QuotaError::Exceeded { .. } => ErrorKind::Throttled,
+            QuotaError::Contended { .. } => ErrorKind::Conflict,
+        },
+    });
+}
+
+mod sessions {
+    use super::{ErrorKind, HostError};
+    use crate::sessions::SessionError;
+
+    host_error!(SessionError {
+        source: [Read, Write, Destroy],
+    });
+}
+
+mod signing {
+    use super::{ErrorKind, HostError};
+    use crate::signing::{SignatureError, SigningError};
+
+    host_error!(SigningError {
+        other: {
+            SigningError::InvalidKey { .. } => ErrorKind::InvalidRequest,
+            SigningError::UnsupportedAlgorithm { .. } => ErrorKind::InvalidRequest,
+            SigningError::Other { .. } => ErrorKind::Other,
+            SigningError::Unsupported(_) => ErrorKind::Other,
+        },
+    });
+
+    host_error!(SignatureError {
+        source: [Signing],
+        other: {
+            SignatureError::MissingSignature => ErrorKind::Unauthorized,
+            SignatureError::InvalidTimestamp => ErrorKind::Unauthorized,
+            SignatureError::Expired { .. } => ErrorKind::Unauthorized,
+            SignatureError::InvalidSignature => ErrorKind::Unauthorized,
+        },
+    });
+}
+
+mod spawn {
+    use super::{ErrorKind, HostError};
+    use crate::FunctionSpawnError;
+    use crate::encoding::EncodeError;
+    use momento_functions_wit::host::momento::host::spawn::SpawnError;
+
+    impl HostError for SpawnError {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                SpawnError::FunctionNotFound => ErrorKind::NotFound,
+                SpawnError::InternalError => ErrorKind::Unavailable,
+                SpawnError::Limit(_) => ErrorKind::Throttled,
+            }
+        }
+    }
+
+    host_error!(FunctionSpawnError<E: EncodeError> {
+        encoding: [EncodeFailed],
+        source: [FunctionSpawnError],
+    });
+}
+
+#[cfg(feature = "http")]
+mod http {
+    use super::{ErrorKind, HostError};
+    use crate::encoding::EncodeError;
+    use crate::http::*;
+    use momento_functions_wit::host::momento::host::http;
+
+    impl HostError for http::Error {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                http::Error::InternalError | http::Error::RequestError(_) => ErrorKind::Unavailable,
+                http::Error::InvalidUrl(_)
+                | http::Error::InvalidHeaderName(_)
+                | http::Error::InvalidHeaderValue(_) => ErrorKind::InvalidRequest,
+            }
+        }
+    }
+
+    host_error!(HttpGetError {
+        source: [HttpError],
+    });
+    host_error!(HttpPutError<E: EncodeError> { encoding: [EncodeFailed], source: [HttpError], });
+    host_error!(HttpPostError<E: EncodeError> { encoding: [EncodeFailed], source: [HttpError], });
+    host_error!(HttpDeleteError {
+        source: [HttpError],
+    });
+    host_error!(HttpRequestError {
+        source: [HttpError],
+        other: { HttpRequestError::Unsupported(_) => ErrorKind::Other },
+    });
+
+    use crate::embeddings::{EmbedError, InvalidResumeToken};
+
+    host_error!(EmbedError {
+        encoding: [Decode],
+        source: [Http, CacheRead, CacheWrite],
+        other: {
+            EmbedError::Provider { status, .. } => {
+                ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+            },
+            EmbedError::Count { .. } => ErrorKind::Other,
+        },
+    });
+    impl HostError for InvalidResumeToken {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::InvalidRequest
+        }
+    }
+
+    use crate::api::ApiError;
+
+    host_error!(ApiError {
+        encoding: [Encode, Decode],
+        source: [Get, Put, Post, Delete],
+        other: {
+            ApiError::Status { status, .. } => {
+                ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+            },
+        },
+    });
+
+    use crate::clickhouse::ClickHouseError;
+
+    host_error!(ClickHouseError {
+        source: [Http],
+        other: {
+            error @ ClickHouseError::Server { status, .. } => {
+                if error.is_backpressure() {
+                    ErrorKind::Throttled
+                } else {
+                    ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+                }
+            },
+            ClickHouseError::Encode { .. } | ClickHouseError::Decode { .. } => ErrorKind::Encoding,
+        },
+    });
+
+    use crate::elasticsearch::SearchError;
+
+    host_error!(SearchError {
+        encoding: [Encode, Decode],
+        source: [Get, Put, Post, Delete],
+        other: {
+            SearchError::Cluster { status, .. } => {
+                ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+            },
+        },
+    });
+
+    use crate::notify::NotifyError;
+
+    host_error!(NotifyError {
+        encoding: [Decode],
+        source: [Http],
+        other: {
+            NotifyError::Rejected { status, .. } => {
+                ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+            },
+            NotifyError::Slack { error } => match error.as_str() {
+                "ratelimited" | "rate_limited" => ErrorKind::Throttled,
+                "not_authed" | "invalid_auth" | "account_inactive" | "token_revoked"
+                | "token_expired" | "missing_scope" | "not_in_channel" => ErrorKind::Unauthorized,
+                "channel_not_found" | "thread_not_found" => ErrorKind::NotFound,
+                "internal_error" | "fatal_error" | "service_unavailable" => ErrorKind::Unavailable,
+                _ => ErrorKind::InvalidRequest,
+            },
+        },
+    });
+
+    use crate::oidc::{JwtError, OidcError};
+
+    host_error!(OidcError {
+        encoding: [Decode],
+        source: [Http, CacheRead, CacheWrite],
+        other: {
+            OidcError::Provider { status, .. } => {
+                ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+            },
+            OidcError::IssuerMismatch { .. } => ErrorKind::InvalidRequest,
+        },
+    });
+    host_error!(JwtError {
+        source: [Provider, Signing],
+        other: {
+            JwtError::MissingToken
+            | JwtError::Malformed { .. }
+            | JwtError::UnsupportedAlgorithm { .. }
+            | JwtError::UnknownKey { .. }
+            | JwtError::InvalidSignature
+            | JwtError::Expired { .. }
+            | JwtError::NotYetValid { .. }
+            | JwtError::WrongIssuer { .. }
+            | JwtError::WrongAudience
+            | JwtError::MissingClaim { .. }
+            | JwtError::Claims { .. } => ErrorKind::Unauthorized,
+        },
+    });
+
+    use crate::agent::AgentError;
+
+    host_error!(AgentError {
+        source: [Chat],
+        other: {
+            AgentError::MaxIterations { .. } => ErrorKind::Other,
+        },
+    });
+
+    use crate::rag::{ChatError, RagError};
+
+    host_error!(ChatError {
+        encoding: [Decode],
+        source: [Http],
+        other: {
+            ChatError::Rejected { status, .. } => {
+                ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+            },
+            ChatError::Empty => ErrorKind::Other,
+            // Mid-stream errors are overloads and internal errors, not bad requests.
+            ChatError::Stream { .. } => ErrorKind::Unavailable,
+        },
+    });
+    host_error!(RagError {
+        source: [Embed, Retrieve, Generate],
+        other: {
+            RagError::NoEmbedding => ErrorKind::Other,
+        },
+    });
+}
+
+mod vector_store {
+    use super::{ErrorKind, HostError};
+    use crate::vector_store::VectorStoreError;
+
+    impl HostError for VectorStoreError {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                #[cfg(feature = "http")]
+                VectorStoreError::Http(e) => e.kind(),
+                VectorStoreError::Rejected { status, .. } => {
+                    ErrorKind::from_status(*status).unwrap_or(ErrorKind::Other)
+                }
+                VectorStoreError::Decode { .. } => ErrorKind::Encoding,
+                #[cfg(feature = "redis")]
+                VectorStoreError::Search(e) => e.kind(),
+                VectorStoreError::UnsupportedFilter { .. } => ErrorKind::InvalidRequest,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "aws")]
+mod aws {
+    use super::{ErrorKind, HostError};
+    use crate::aws::ddb::{DynamoDBError, GetItemError};
+    use crate::aws::ddb_cache::DdbCacheError;
+    use crate::aws::lambda::{InvokeError, InvokeStreamError};
+    use crate::aws::rds_data::{QueryError, RdsDataError};
+    use crate::aws::s3::{S3GetError, S3PutError, S3SelectError};
+    use crate::aws::secrets_manager::SecretsManagerGetSecretValueError;
+    use crate::aws::tenant::TenantCredentialsError;
+    use crate::encoding::{EncodeError, ExtractError};
+    use crate::snapshot::SnapshotError;
+    use momento_functions_wit::host::momento::host::aws_auth::AuthError;
+    use momento_functions_wit::host::momento::host::aws_ddb::DdbError;
+    use momento_functions_wit::host::momento::host::aws_lambda::LambdaError;
+    use momento_functions_wit::host::momento::host::aws_s3::S3Error;
+    use momento_functions_wit::host::momento::host::aws_secrets::SecretsError;
+
+    /// AWS error codes that mean the request was rate limited or the service is shedding load.
+    ///
+    /// `LimitExceededException` is left out: several services use it for quotas that do not
+    /// lift by waiting, so it must not look retryable.
+    const THROTTLING_CODES: &[&str] = &[
+        "Throttling",
+        "ThrottlingException",
+        "ThrottledException",
+        "RequestThrottled",
+        "RequestThrottledException",
+        "TooManyRequestsException",
+        "ProvisionedThroughputExceededException",
+        "RequestLimitExceeded",
+        "TransactionInProgressException",
+        "SlowDown",
+        "EC2ThrottledException",
+        "BandwidthLimitExceeded",
+    ];
+
+    /// Classify an AWS failure the host could not classify, by the error code in its message,
+    /// like `ThrottlingException: Rate exceeded`.
+    pub(super) fn aws_other_kind(message: &str) -> ErrorKind {
+        let throttled = message
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| THROTTLING_CODES.contains(&word));
+        if throttled {
+            ErrorKind::Throttled
+        } else {
+            ErrorKind::Other
+        }
+    }
+
+    impl HostError for AuthError {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                AuthError::Unauthorized(_) => ErrorKind::Unauthorized,
+            }
+        }
+    }
+
+    host_error!(DdbError {
+        other: {
+            DdbError::Unauthorized(_) => ErrorKind::Unauthorized,
+            DdbError::Malformed(_) => ErrorKind::InvalidRequest,
+            DdbError::Other(message) => aws_other_kind(message),
+        },
+    });
+    host_error!(S3Error {
+        other: {
+            S3Error::Unauthorized(_) => ErrorKind::Unauthorized,
+            S3Error::Malformed(_) => ErrorKind::InvalidRequest,
+            S3Error::Other(message) => aws_other_kind(message),
+        },
+    });
+    host_error!(LambdaError {
+        other: {
+            LambdaError::Unauthorized(_) => ErrorKind::Unauthorized,
+            LambdaError::Malformed(_) => ErrorKind::InvalidRequest,
+            LambdaError::Other(message) => aws_other_kind(message),
+        },
+    });
+    host_error!(SecretsError {
+        other: {
+            SecretsError::NotFound => ErrorKind::NotFound,
+            SecretsError::Unauthorized(_) => ErrorKind::Unauthorized,
+            SecretsError::Malformed(_) => ErrorKind::InvalidRequest,
+            SecretsError::Other(message) => aws_other_kind(message),
+        },
+    });
+
+    host_error!(DynamoDBError {
+        encoding: [SerDeJson],
+        source: [Dynamo],
+    });
+    host_error!(DdbCacheError {
+        encoding: [Encoding, Convert],
+        source: [Dynamo, CacheRead, CacheWrite, Invalidate, Spawn, DeadLetter],
+    });
+    impl<E> HostError for GetItemError<E>
+    where
+        Self: std::error::Error,
+    {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                GetItemError::TryFrom { .. } => ErrorKind::Encoding,
+                GetItemError::Dynamo { cause } => cause.kind(),
+            }
+        }
+    }
+    host_error!(S3PutError<E: EncodeError> { encoding: [EncodeFailed], source: [S3Error], });
+    host_error!(S3GetError<E: ExtractError> { encoding: [ExtractFailed], source: [S3Error], });
+    host_error!(S3SelectError {
+        encoding: [Decode],
+        source: [S3Error],
+    });
+    host_error!(InvokeError<E: EncodeError> { encoding: [EncodeFailed], source: [LambdaError], });
+    host_error!(InvokeStreamError {
+        source: [LambdaError],
+        other: { InvokeStreamError::FunctionError { .. } => ErrorKind::Other },
+    });
+    host_error!(SecretsManagerGetSecretValueError<E: ExtractError> {
+        encoding: [ExtractFailed],
+        source: [SecretsManagerError],
+    });
+    host_error!(RdsDataError {
+        other: {
+            RdsDataError::Unauthorized { .. } => ErrorKind::Unauthorized,
+            RdsDataError::Malformed { .. } => ErrorKind::InvalidRequest,
+            RdsDataError::Unavailable { .. } => ErrorKind::Unavailable,
+            RdsDataError::StatementTimeout { .. } => ErrorKind::Unavailable,
+            RdsDataError::Other { message } => aws_other_kind(message),
+            RdsDataError::Unsupported(_) => ErrorKind::Other,
+        },
+    });
+    host_error!(QueryError {
+        encoding: [Row],
+        source: [RdsData],
+    });
+    host_error!(SnapshotError {
+        encoding: [Invalid],
+        source: [CacheRead, Metadata, CacheWrite, Upload, Download],
+        other: {
+            SnapshotError::NotFound { .. } => ErrorKind::NotFound,
+        },
+    });
+    host_error!(TenantCredentialsError<E: std::error::Error> {
+        source: [Auth],
+        other: { TenantCredentialsError::Resolve { .. } => ErrorKind::Other },
+    });
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use super::{ErrorKind, HostError};
+    use crate::kafka::KafkaError;
+
+    host_error!(KafkaError {
+        other: {
+            KafkaError::Connection { .. } => ErrorKind::Unavailable,
+            KafkaError::Unauthorized { .. } => ErrorKind::Unauthorized,
+            KafkaError::UnknownTopic { .. } => ErrorKind::NotFound,
+            KafkaError::RecordTooLarge { .. } => ErrorKind::InvalidRequest,
+            KafkaError::Timeout { .. } => ErrorKind::Unavailable,
+            KafkaError::Other { .. } => ErrorKind::Other,
+            KafkaError::Unsupported(_) => ErrorKind::Other,
+        },
+    });
+}
+
+#[cfg(feature = "redis")]
+mod redis {
+    use super::{ErrorKind, HostError};
+    use crate::encoding::{EncodeError, ExtractError};
+    use crate::redis::script::ScriptError;
+    use crate::redis::search::SearchError;
+    use crate::redis::*;
+    use momento_functions_wit::host::momento::host::redis::RedisError;
+
+    impl HostError for RedisError {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                RedisError::Other(_) => ErrorKind::Other,
+            }
+        }
+    }
+
+    /// Classify a Redis error reply by its prefix, like `WRONGTYPE` or `LOADING`.
+    fn simple_error_kind(message: &str) -> ErrorKind {
+        let prefix = message.split_whitespace().next().unwrap_or_default();
+        match prefix {
+            "LOADING" | "BUSY" | "TRYAGAIN" | "CLUSTERDOWN" | "MASTERDOWN" | "READONLY" => {
+                ErrorKind::Unavailable
+            }
+            "NOAUTH" | "NOPERM" | "WRONGPASS" => ErrorKind::Unauthorized,
+            "OOM" => ErrorKind::Throttled,
+            _ => ErrorKind::InvalidRequest,
+        }
+    }
+
+    host_error!(RedisGetError<E: ExtractError> {
+        encoding: [ExtractFailed],
+        source: [RedisError],
+        other: {
+            RedisGetError::SimpleError { message } => simple_error_kind(message),
+            _ => ErrorKind::Other,
+        },
+    });
+    host_error!(RedisSetError<E: EncodeError> {
+        encoding: [EncodeError],
+        source: [RedisError],
+        other: {
+            RedisSetError::SimpleError { message } => simple_error_kind(message),
+            RedisSetError::UnexpectedValueResponse { .. } => ErrorKind::Other,
+        },
+    });
+    host_error!(RedisDeleteError {
+        source: [RedisError],
+        other: {
+            RedisDeleteError::SimpleError { message } => simple_error_kind(message),
+            RedisDeleteError::UnexpectedValueResponse { .. } => ErrorKind::Other,
+        },
+    });
+    host_error!(RedisPipeError {
+        source: [RedisError],
+        other: {
+            RedisPipeError::CrossSlot { .. } => ErrorKind::InvalidRequest,
+            RedisPipeError::MissingResponses { .. } => ErrorKind::Other,
+        },
+    });
+    host_error!(ScriptError {
+        source: [RedisError],
+        other: {
+            ScriptError::SimpleError { message } => simple_error_kind(message),
+            ScriptError::UnexpectedResponse { .. } => ErrorKind::Other,
+        },
+    });
+    host_error!(SearchError {
+        source: [RedisError],
+        other: {
+            SearchError::SimpleError { message } => simple_error_kind(message),
+            SearchError::UnexpectedResponse { .. } => ErrorKind::Other,
+        },
+    });
+}
+
+#[cfg(feature = "sql")]
+mod sql {
+    use super::{ErrorKind, HostError};
+    use crate::sql::{SqlError, SqlQueryError};
+
+    /// Classify a database error by its SQLSTATE class, the code's first two characters.
+    fn sqlstate_kind(code: &str) -> ErrorKind {
+        match code.get(..2).unwrap_or_default() {
+            "08" | "57" | "58" | "HY" => ErrorKind::Unavailable,
+            "28" => ErrorKind::Unauthorized,
+            "23" | "40" => ErrorKind::Conflict,
+            "53" => ErrorKind::Throttled,
+            "02" => ErrorKind::NotFound,
+            "22" | "42" | "0A" => ErrorKind::InvalidRequest,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    host_error!(SqlError {
+        other: {
+            SqlError::Connection { .. } => ErrorKind::Unavailable,
+            SqlError::Unauthorized { .. } => ErrorKind::Unauthorized,
+            SqlError::Database { code, .. } => sqlstate_kind(code),
+            SqlError::Other { .. } => ErrorKind::Other,
+            SqlError::Unsupported(_) => ErrorKind::Other,
+        },
+    });
+    host_error!(SqlQueryError {
+        encoding: [Row],
+        source: [Sql],
+    });
+}
+
+#[cfg(feature = "topics")]
+mod topics {
+    use super::{ErrorKind, HostError};
+    use crate::encoding::EncodeError;
+    use crate::topics::{PublishError, PublishSequencedError, SubscribeError};
+    use momento_functions_wit::host::momento::functions::topic;
+
+    impl HostError for topic::Error {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                topic::Error::InternalError
+                | topic::Error::RequestCancelled
+                | topic::Error::Timeout => ErrorKind::Unavailable,
+                topic::Error::InvalidArgument(_) => ErrorKind::InvalidRequest,
+                topic::Error::PermissionDenied(_) => ErrorKind::Unauthorized,
+                topic::Error::LimitExceeded(_) => ErrorKind::Throttled,
+                topic::Error::FailedPrecondition(_) => ErrorKind::Conflict,
+                topic::Error::NotFound(_) => ErrorKind::NotFound,
+            }
+        }
+    }
+
+    host_error!(PublishError<E: EncodeError> { encoding: [EncodeFailed], source: [PublishError], });
+    host_error!(PublishSequencedError<E: EncodeError> {
+        encoding: [EncodeFailed],
+        source: [PublishError],
+        other: { PublishSequencedError::Unsupported(_) => ErrorKind::Other },
+    });
+    host_error!(SubscribeError {
+        source: [SubscribeError],
+        other: { SubscribeError::Unsupported(_) => ErrorKind::Other },
+    });
+}
+
+#[cfg(feature = "token")]
+mod token {
+    use super::{ErrorKind, HostError};
+    use crate::token::GenerateDisposableTokenError;
+    use momento_functions_wit::host::momento::functions::token::TokenError;
+
+    impl HostError for TokenError {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                TokenError::InternalError => ErrorKind::Unavailable,
+                TokenError::InvalidArgument(_) => ErrorKind::InvalidRequest,
+                TokenError::PermissionDenied(_) => ErrorKind::Unauthorized,
+                TokenError::LimitExceeded(_) => ErrorKind::Throttled,
+            }
+        }
+    }
+
+    host_error!(GenerateDisposableTokenError {
+        source: [TokenError],
+    });
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use momento_functions_wit::host::momento::functions::cache_scalar;
+
+    #[test]
+    fn statuses_are_classified() {
+        assert_eq!(None, ErrorKind::from_status(204));
+        assert_eq!(None, ErrorKind::from_status(304));
+        assert_eq!(Some(ErrorKind::Throttled), ErrorKind::from_status(429));
+        assert_eq!(Some(ErrorKind::Unavailable), ErrorKind::from_status(503));
+        assert_eq!(Some(ErrorKind::Unauthorized), ErrorKind::from_status(403));
+        assert_eq!(Some(ErrorKind::InvalidRequest), ErrorKind::from_status(422));
+        assert!(ErrorKind::from_status(504).unwrap().is_retryable());
+        assert!(!ErrorKind::from_status(400).unwrap().is_retryable());
+    }
+
+    #[test]
+    fn wrapped_errors_take_their_source_kind() {
+        let throttled: crate::cache::CacheDeleteError =
+            cache_scalar::Error::LimitExceeded("too many requests".to_string()).into();
+        assert!(throttled.is_throttled());
+        assert!(throttled.is_retryable());
+        assert_eq!(StatusClass::ClientError, throttled.status_class());
+
+        let encoding = crate::cache::CacheSetError::EncodeFailed {
+            cause: serde_json::from_str::<u8>("not json").unwrap_err(),
+        };
+        assert_eq!(ErrorKind::Encoding, encoding.kind());
+        assert!(!encoding.is_retryable());
+        assert_eq!(StatusClass::ServerError, encoding.status_class());
+    }
+
+    #[cfg(feature = "aws")]
+    #[test]
+    fn aws_throttling_codes_are_throttled() {
+        use momento_functions_wit::host::momento::host::aws_s3::S3Error;
+
+        let slow_down = S3Error::Other("SlowDown: Please reduce your request rate.".to_string());
+        assert!(slow_down.is_throttled());
+        assert!(slow_down.is_retryable());
+        assert_eq!(
+            ErrorKind::Throttled,
+            aws::aws_other_kind("ProvisionedThroughputExceededException: throughput exceeded")
+        );
+        assert_eq!(
+            ErrorKind::Other,
+            aws::aws_other_kind("InternalFailure: NoThrottlingHere")
+        );
+    }
+
+    #[cfg(feature = "aws")]
+    #[test]
+    fn aws_quota_limits_are_not_retried() {
+        use momento_functions_wit::host::momento::host::aws_s3::S3Error;
+
+        let quota = S3Error::Other("LimitExceededException: too many buckets".to_string());
+        assert_eq!(ErrorKind::Other, quota.kind());
+        assert!(!quota.is_throttled());
+        assert!(!quota.is_retryable());
+        assert_eq!(
+            ErrorKind::Throttled,
+            aws::aws_other_kind("RequestLimitExceeded: request rate exceeded")
+        );
+    }
+
+    #[cfg(feature = "control")]
+    #[test]
+    fn control_limits_are_not_retried() {
+        let limit = crate::control::ControlError::LimitExceeded {
+            message: "too many caches".to_string(),
+        };
+        assert_eq!(ErrorKind::Conflict, limit.kind());
+        assert!(!limit.is_retryable());
+    }
+}
@@ -0,0 +1,830 @@
+//! Markdown rendering and HTML sanitization
+//!
+//! Small implementations for content Functions like comment renderers and docs previews,
+//! written to stay light in a wasm build rather than to cover every corner of CommonMark.
+//!
+//! - [render_markdown] handles headings, paragraphs, emphasis, code, links, images, lists,
+//!   block quotes, and rules. Raw HTML in the Markdown is escaped, not passed through, so
+//!   the output is safe to serve even when the Markdown is untrusted.
+//! - [sanitize] keeps an allowlist of formatting tags and attributes from untrusted HTML.
+//!   Scripts, styles, embedded content, event handlers, and `javascript:` links are removed.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::html;
+//!
+//! let comment = "**Nice** post! See [the docs](https://docs.momentohq.com).";
+//! let rendered = html::render_markdown(comment);
+//!
+//! let pasted = r#"<p onclick="steal()">hi<script>steal()</script></p>"#;
+//! assert_eq!("<p>hi</p>", html::sanitize(pasted));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Tags kept by [sanitize].
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "del",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Tags removed by [sanitize] along with everything inside them.
+const DROPPED_WITH_CONTENT: &[&str] = &[
+    "embed", "frame", "iframe", "math", "noscript", "object", "script", "style", "svg", "template",
+    "textarea", "title",
+];
+
+/// Tags that have no closing tag.
+const VOID_TAGS: &[&str] = &["br", "hr", "img"];
+
+/// How deeply block quotes, links, and emphasis nest before the rest is rendered as text, so
+/// deeply nested input cannot exhaust the stack.
+const MAX_NESTING: usize = 32;
+
+/// Escape text for use in HTML content or a quoted attribute value.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    push_escaped(&mut escaped, text);
+    escaped
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// True for URLs that are safe to link to: relative URLs and `http`, `https`, and `mailto`.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    let scheme_end = url.find([':', '/', '?', '#']);
+    match scheme_end {
+        Some(index) if url[index..].starts_with(':') => {
+            let scheme = url[..index].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+/// Keep only safe formatting from untrusted HTML.
+///
+/// Allowed tags are re-written with only their allowed attributes: `href` and `title` on
+/// links, and `src`, `alt`, and `title` on images, with unsafe URLs removed. Other tags are
+/// removed but their text is kept, except for tags like `script` and `style`, which are
+/// removed with their content. Comments are removed, all text is re-escaped, and unclosed
+/// tags are closed.
+pub fn sanitize(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut open: Vec<String> = Vec::new();
+    let mut dropping: Option<String> = None;
+    for token in Tokenizer::new(html) {
+        if let Some(dropped) = &dropping {
+            if let Token::EndTag(name) = &token
+                && name == dropped
+            {
+                dropping = None;
+            }
+            continue;
+        }
+        match token {
+            Token::Text(text) => push_escaped(&mut out, &decode_entities(text)),
+            Token::StartTag {
+                name,
+                attributes,
+                self_closing,
+            } => {
+                if DROPPED_WITH_CONTENT.contains(&name.as_str()) {
+                    if !self_closing {
+                        dropping = Some(name);
+                    }
+                    continue;
+                }
+                if !ALLOWED_TAGS.contains(&name.as_str()) {
+                    continue;
+                }
+                out.push('<');
+                out.push_str(&name);
+                for (attribute, value) in attributes {
+                    if !allowed_attribute(&name, &attribute, &value) {
+                        continue;
+                    }
+                    let _ = write!(out, " {attribute}=\"");
+                    push_escaped(&mut out, &value);
+                    out.push('"');
+                }
+                if name == "a" {
+                    out.push_str(" rel=\"nofollow noopener noreferrer\"");
+                }
+                out.push('>');
+                if !VOID_TAGS.contains(&name.as_str()) && !self_closing {
+                    open.push(name);
+                }
+            }
+            Token::EndTag(name) => {
+                if let Some(position) = open.iter().rposition(|open| *open == name) {
+                    for unclosed in open.drain(position..).rev() {
+                        let _ = write!(out, "</{unclosed}>");
+                    }
+                }
+            }
+        }
+    }
+    for unclosed in open.into_iter().rev() {
+        let _ = write!(out, "</{unclosed}>");
+    }
+    out
+}
+
+fn allowed_attribute(tag: &str, attribute: &str, value: &str) -> bool {
+    match (tag, attribute) {
+        ("a", "href") | ("img", "src") => is_safe_url(value),
+        ("a", "title") | ("img", "alt") | ("img", "title") => true,
+        _ => false,
+    }
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    StartTag {
+        name: String,
+        attributes: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    EndTag(String),
+}
+
+struct Tokenizer<'a> {
+    html: &'a str,
+    position: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(html: &'a str) -> Self {
+        Self { html, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.html[self.position..]
+    }
+
+    /// Parse a tag starting at `<`, returning it and its length, or `None` when the `<`
+    /// does not start a tag and should be treated as text.
+    ///
+    /// A tag that is never closed takes up the rest of the input, as it does in browsers.
+    /// Treating it as text instead would rescan the rest of the input for every `<` in it.
+    fn tag(&self) -> Option<(Option<Token<'a>>, usize)> {
+        let rest = self.rest();
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let length = comment.find("-->").map_or(rest.len(), |end| end + 7);
+            return Some((None, length));
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            let length = rest.find('>').map_or(rest.len(), |end| end + 1);
+            return Some((None, length));
+        }
+        let (closing, body) = match rest[1..].strip_prefix('/') {
+            Some(body) => (true, body),
+            None => (false, &rest[1..]),
+        };
+        let name_length = body
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(body.len());
+        if name_length == 0 || !body.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let name = body[..name_length].to_ascii_lowercase();
+        let Some(end) = find_tag_end(&body[name_length..]) else {
+            return Some((None, rest.len()));
+        };
+        let length = rest.len() - body.len() + name_length + end + 1;
+        if closing {
+            return Some((Some(Token::EndTag(name)), length));
+        }
+        let inside = &body[name_length..name_length + end];
+        let self_closing = inside.trim_end().ends_with('/');
+        Some((
+            Some(Token::StartTag {
+                name,
+                attributes: parse_attributes(inside.trim_end().trim_end_matches('/')),
+                self_closing,
+            }),
+            length,
+        ))
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return None;
+            }
+            if rest.starts_with('<') {
+                match self.tag() {
+                    Some((token, length)) => {
+                        self.position += length;
+                        match token {
+                            Some(token) => return Some(token),
+                            None => continue,
+                        }
+                    }
+                    None => {
+                        self.position += 1;
+                        return Some(Token::Text("<"));
+                    }
+                }
+            }
+            let length = rest.find('<').unwrap_or(rest.len());
+            self.position += length;
+            return Some(Token::Text(&rest[..length]));
+        }
+    }
+}
+
+/// Find the `>` that ends a tag, skipping over quoted attribute values.
+fn find_tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(mut text: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    loop {
+        text = text.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if text.is_empty() {
+            return attributes;
+        }
+        let name_length = text
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(text.len());
+        let name = text[..name_length].to_ascii_lowercase();
+        text = text[name_length..].trim_start();
+        let mut value = String::new();
+        if let Some(after_equals) = text.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (raw, rest) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after_equals[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+            value = decode_entities(raw);
+            text = rest;
+        }
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+    }
+}
+
+/// Decode character references, leaving unknown ones as they are.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest[1..]
+            .find(';')
+            .map(|end| end + 1)
+            .filter(|end| *end <= 10)
+        else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let reference = &rest[1..end];
+        let character = match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => reference
+                .strip_prefix("#x")
+                .or_else(|| reference.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| reference.strip_prefix('#').and_then(|n| n.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(character) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Render Markdown to HTML.
+pub fn render_markdown(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut out = String::with_capacity(markdown.len() * 2);
+    render_blocks(&mut out, &lines, 0);
+    out
+}
+
+fn render_blocks(out: &mut String, lines: &[&str], depth: usize) {
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            index += 1;
+        } else if let Some(fence) = code_fence(trimmed) {
+            let language = trimmed[fence.len()..].trim();
+            let start = index + 1;
+            let mut end = start;
+            while end < lines.len() && !lines[end].trim_start().starts_with(fence) {
+                end += 1;
+            }
+            out.push_str("<pre><code");
+            if let Some(language) = language.split_whitespace().next() {
+                out.push_str(" class=\"language-");
+                push_escaped(out, language);
+                out.push('"');
+            }
+            out.push('>');
+            for line in &lines[start..end] {
+                push_escaped(out, line);
+                out.push('\n');
+            }
+            out.push_str("</code></pre>\n");
+            index = end + 1;
+        } else if let Some((level, text)) = heading(trimmed) {
+            let _ = write!(out, "<h{level}>");
+            render_inline(out, text, 0);
+            let _ = writeln!(out, "</h{level}>");
+            index += 1;
+        } else if is_rule(trimmed) {
+            out.push_str("<hr>\n");
+            index += 1;
+        } else if trimmed.starts_with('>') {
+            let mut quoted = Vec::new();
+            while index < lines.len() && lines[index].trim_start().starts_with('>') {
+                let line = lines[index].trim_start()[1..].strip_prefix(' ');
+                quoted.push(line.unwrap_or(&lines[index].trim_start()[1..]));
+                index += 1;
+            }
+            out.push_str("<blockquote>\n");
+            if depth < MAX_NESTING {
+                render_blocks(out, &quoted, depth + 1);
+            } else {
+                out.push_str("<p>");
+                push_escaped(out, &quoted.join("\n"));
+                out.push_str("</p>\n");
+            }
+            out.push_str("</blockquote>\n");
+        } else if let Some((ordered, _)) = list_item(trimmed) {
+            let tag = if ordered { "ol" } else { "ul" };
+            let _ = writeln!(out, "<{tag}>");
+            while index < lines.len() {
+                let Some((item_ordered, text)) = list_item(lines[index].trim_start()) else {
+                    break;
+                };
+                if item_ordered != ordered {
+                    break;
+                }
+                let mut item = text.to_string();
+                index += 1;
+                // Indented lines continue the item.
+                while index < lines.len()
+                    && lines[index].starts_with([' ', '\t'])
+                    && !lines[index].trim().is_empty()
+                    && list_item(lines[index].trim_start()).is_none()
+                {
+                    item.push(' ');
+                    item.push_str(lines[index].trim());
+                    index += 1;
+                }
+                out.push_str("<li>");
+                render_inline(out, &item, 0);
+                out.push_str("</li>\n");
+            }
+            let _ = writeln!(out, "</{tag}>");
+        } else {
+            let mut paragraph = Vec::new();
+            while index < lines.len() {
+                let trimmed = lines[index].trim_start();
+                if trimmed.is_empty()
+                    || heading(trimmed).is_some()
+                    || code_fence(trimmed).is_some()
+                    || is_rule(trimmed)
+                    || trimmed.starts_with('>')
+                    || (!paragraph.is_empty() && list_item(trimmed).is_some())
+                {
+                    break;
+                }
+                paragraph.push(lines[index]);
+                index += 1;
+            }
+            out.push_str("<p>");
+            for (number, line) in paragraph.iter().enumerate() {
+                if number != 0 {
+                    out.push('\n');
+                }
+                let hard_break = line.ends_with("  ");
+                render_inline(out, line.trim(), 0);
+                if hard_break && number + 1 < paragraph.len() {
+                    out.push_str("<br>");
+                }
+            }
+            out.push_str("</p>\n");
+        }
+    }
+}
+
+fn code_fence(line: &str) -> Option<&'static str> {
+    if line.starts_with("```") {
+        Some("```")
+    } else if line.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = &line[level..];
+    if !text.is_empty() && !text.starts_with(' ') {
+        return None;
+    }
+    Some((level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    3 <= line.len()
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| line.chars().all(|c| c == *marker))
+}
+
+/// Parse a list item marker, returning whether the list is ordered and the item's text.
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(marker) {
+            return Some((false, text));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if (1..=9).contains(&digits) {
+        let rest = &line[digits..];
+        if let Some(text) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((true, text));
+        }
+    }
+    None
+}
+
+fn render_inline(out: &mut String, text: &str, depth: usize) {
+    if MAX_NESTING < depth {
+        push_escaped(out, text);
+        return;
+    }
+    Inline::new(text, depth).render(out);
+}
+
+/// One run of inline Markdown being rendered.
+///
+/// Ends of links and code spans are found once rather than by rescanning the rest of the text
+/// from every `[` or backtick, so rendering stays linear in the length of the text.
+struct Inline<'a> {
+    text: &'a str,
+    depth: usize,
+    /// The `]` or `)` that closes each `[` or `(`, by index.
+    closing: HashMap<usize, usize>,
+    /// For each terminator looked for, where it was last looked for from and where it was
+    /// found.
+    searches: HashMap<&'a str, (usize, Option<usize>)>,
+}
+
+impl<'a> Inline<'a> {
+    fn new(text: &'a str, depth: usize) -> Self {
+        let mut closing = HashMap::new();
+        let (mut brackets, mut parentheses) = (Vec::new(), Vec::new());
+        for (index, c) in text.char_indices() {
+            let (open, stack) = match c {
+                '[' | ']' => (c == '[', &mut brackets),
+                '(' | ')' => (c == '(', &mut parentheses),
+                _ => continue,
+            };
+            if open {
+                stack.push(index);
+            } else if let Some(start) = stack.pop() {
+                closing.insert(start, index);
+            }
+        }
+        Self {
+            text,
+            depth,
+            closing,
+            searches: HashMap::new(),
+        }
+    }
+
+    /// The index of the first `needle` at or after `from`.
+    fn find(&mut self, from: usize, needle: &'a str) -> Option<usize> {
+        // The first match from an earlier position is also the first from any later position
+        // up to it, and a needle missing after an earlier position is missing after later ones.
+        if let Some(&(searched_from, found)) = self.searches.get(needle)
+            && searched_from <= from
+            && found.is_none_or(|found| from <= found)
+        {
+            return found;
+        }
+        let found = self.text[from..].find(needle).map(|index| from + index);
+        self.searches.insert(needle, (from, found));
+        found
+    }
+
+    fn render(&mut self, out: &mut String) {
+        let text = self.text;
+        let mut position = 0;
+        while let Some(c) = text[position..].chars().next() {
+            let rest = &text[position..];
+            let consumed = match c {
+                '\\' => rest[1..]
+                    .chars()
+                    .next()
+                    .filter(char::is_ascii_punctuation)
+                    .map(|escaped| {
+                        push_escaped(out, &escaped.to_string());
+                        1 + escaped.len_utf8()
+                    }),
+                '`' => self.inline_code(out, position),
+                '!' if rest.starts_with("![") => {
+                    self.link(out, position + 1, true).map(|length| length + 1)
+                }
+                '[' => self.link(out, position, false),
+                '<' => self.autolink(out, position),
+                '*' | '_' | '~' => self.emphasis(out, position),
+                _ => None,
+            };
+            match consumed {
+                Some(length) => position += length,
+                None => {
+                    push_escaped(out, &rest[..c.len_utf8()]);
+                    position += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn inline_code(&mut self, out: &mut String, start: usize) -> Option<usize> {
+        let text = self.text;
+        let ticks = text[start..].chars().take_while(|c| *c == '`').count();
+        let fence = &text[start..start + ticks];
+        let end = self.find(start + ticks, fence)?;
+        out.push_str("<code>");
+        push_escaped(out, text[start + ticks..end].trim());
+        out.push_str("</code>");
+        Some(end + ticks - start)
+    }
+
+    fn link(&mut self, out: &mut String, start: usize, image: bool) -> Option<usize> {
+        let text = self.text;
+        let label_end = *self.closing.get(&start)?;
+        let label = &text[start + 1..label_end];
+        let open = label_end + 1;
+        if !text[open..].starts_with('(') {
+            return None;
+        }
+        // Balanced parentheses are allowed inside the destination.
+        let destination_end = *self.closing.get(&open)?;
+        let inside = text[open + 1..destination_end].trim();
+        let (url, title) = match inside.split_once(char::is_whitespace) {
+            Some((url, title)) => {
+                let title = title.trim();
+                let title = title
+                    .strip_prefix('"')
+                    .and_then(|title| title.strip_suffix('"'))
+                    .unwrap_or(title);
+                (url, Some(title))
+            }
+            None => (inside, None),
+        };
+        let url = if is_safe_url(url) { url } else { "" };
+        if image {
+            out.push_str("<img src=\"");
+            push_escaped(out, url);
+            out.push_str("\" alt=\"");
+            push_escaped(out, label);
+            out.push('"');
+        } else {
+            out.push_str("<a href=\"");
+            push_escaped(out, url);
+            out.push('"');
+        }
+        if let Some(title) = title {
+            out.push_str(" title=\"");
+            push_escaped(out, title);
+            out.push('"');
+        }
+        out.push('>');
+        if !image {
+            render_inline(out, label, self.depth + 1);
+            out.push_str("</a>");
+        }
+        Some(destination_end + 1 - start)
+    }
+
+    fn autolink(&mut self, out: &mut String, start: usize) -> Option<usize> {
+        let end = self.find(start, ">")?;
+        let url = &self.text[start + 1..end];
+        if url.contains(char::is_whitespace)
+            || !(url.starts_with("http://") || url.starts_with("https://"))
+        {
+            return None;
+        }
+        out.push_str("<a href=\"");
+        push_escaped(out, url);
+        out.push_str("\">");
+        push_escaped(out, url);
+        out.push_str("</a>");
+        Some(end + 1 - start)
+    }
+
+    fn emphasis(&mut self, out: &mut String, start: usize) -> Option<usize> {
+        let text = self.text;
+        let marker = text[start..].chars().next()?;
+        let run = text[start..]
+            .chars()
+            .take_while(|c| *c == marker)
+            .count()
+            .min(2);
+        let (delimiter, tag) = match (marker, run) {
+            ('~', 2) => ("~~", "del"),
+            ('~', _) => return None,
+            (_, 2) => (&text[start..start + 2], "strong"),
+            _ => (&text[start..start + 1], "em"),
+        };
+        let inner_start = start + delimiter.len();
+        if text[inner_start..].starts_with(char::is_whitespace) {
+            return None;
+        }
+        let end = self.find(inner_start, delimiter)?;
+        if end == inner_start || text[inner_start..end].ends_with(char::is_whitespace) {
+            return None;
+        }
+        let _ = write!(out, "<{tag}>");
+        render_inline(out, &text[inner_start..end], self.depth + 1);
+        let _ = write!(out, "</{tag}>");
+        Some(end + delimiter.len() - start)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_removes_scripts_handlers_and_unsafe_links() {
+        assert_eq!(
+            "<p>hi</p>",
+            sanitize(r#"<p onclick="steal()">hi<script>steal("</p>")</script></p>"#)
+        );
+        assert_eq!(
+            r#"<a rel="nofollow noopener noreferrer">x</a><a href="https://example.com" rel="nofollow noopener noreferrer">y</a>"#,
+            sanitize(
+                r#"<a href=" JaVaScRiPt:alert(1)">x</a><a href='https://example.com' target=_blank>y</a>"#
+            )
+        );
+        assert_eq!(
+            "<b>bold</b> a &lt; b",
+            sanitize("<b>bold</b> a < b<!-- hidden -->")
+        );
+    }
+
+    #[test]
+    fn sanitize_closes_and_drops_tags() {
+        assert_eq!("<ul><li>one</li></ul>", sanitize("<ul><li><div>one</div>"));
+        assert_eq!("<em>a</em>b", sanitize("<em>a</strong></em>b"));
+        assert_eq!(
+            "<p>&amp; &lt; &#39; \u{a0}</p>",
+            sanitize("<p>&amp; &lt; ' &nbsp;</p>")
+        );
+        assert_eq!(
+            r#"<img src="/a.png" alt="a &quot;b&quot;">"#,
+            sanitize(r#"<img src="/a.png" alt='a "b"' onerror="x()"/>"#)
+        );
+    }
+
+    #[test]
+    fn markdown_blocks() {
+        assert_eq!(
+            "<h1>Title</h1>\n<p>Some text\nwrapped.</p>\n<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<hr>\n<blockquote>\n<p>quoted</p>\n</blockquote>\n<pre><code class=\"language-rust\">let x = &lt;T&gt;;\n</code></pre>\n<ol>\n<li>first</li>\n</ol>\n",
+            render_markdown(
+                "# Title\n\nSome text\nwrapped.\n\n- one\n- two\n\n---\n> quoted\n\n```rust\nlet x = <T>;\n```\n1. first\n"
+            )
+        );
+    }
+
+    #[test]
+    fn markdown_inline() {
+        assert_eq!(
+            "<p><strong>bold</strong>, <em>em</em>, <code>a &lt; b</code>, <del>gone</del>, <a href=\"https://example.com\" title=\"Example\">a <em>link</em></a>, <img src=\"/i.png\" alt=\"pic\"> and <a href=\"https://momentohq.com\">https://momentohq.com</a></p>\n",
+            render_markdown(
+                "**bold**, *em*, `a < b`, ~~gone~~, [a _link_](https://example.com \"Example\"), ![pic](/i.png) and <https://momentohq.com>"
+            )
+        );
+    }
+
+    #[test]
+    fn deep_and_unterminated_input_is_handled_quickly() {
+        let quotes = render_markdown(&">".repeat(5000));
+        assert_eq!(MAX_NESTING + 1, quotes.matches("<blockquote>").count());
+        assert!(quotes.contains(&format!("<p>{}</p>", "&gt;".repeat(5000 - MAX_NESTING - 1))));
+
+        assert_eq!("", sanitize(&"<a".repeat(80000)));
+        assert_eq!("x", sanitize("x<a title='never closed>"));
+
+        let brackets = "[".repeat(80000);
+        assert_eq!(format!("<p>{brackets}</p>\n"), render_markdown(&brackets));
+        let links = format!("{}x{}", "[".repeat(5000), "](/a)".repeat(5000));
+        assert!(render_markdown(&links).starts_with("<p><a href=\"/a\">"));
+        for repeated in ["<", "[](", "`x``", "*a"] {
+            let markdown = repeated.repeat(20000);
+            assert!(render_markdown(&markdown).starts_with("<p>"));
+        }
+    }
+
+    #[test]
+    fn markdown_escapes_html_and_unsafe_links() {
+        assert_eq!(
+            "<p>&lt;script&gt;x()&lt;/script&gt; <a href=\"\">click</a> 2 * 3 \\*</p>\n",
+            render_markdown("<script>x()</script> [click](javascript:x()) 2 * 3 \\\\*")
+        );
+    }
+}
@@ -1,13 +1,16 @@
 //! Host interface utilities for HTTP
 
+use std::fmt::Write;
+use std::time::Duration;
+
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
 use momento_functions_wit::host::momento::host::http;
 use thiserror::Error;
 
-use crate::encoding::EncodeError;
-use crate::{
-    aws,
-    encoding::{Encode, Extract},
-};
+#[cfg(feature = "aws")]
+use crate::aws;
+use crate::encoding::{Encode, EncodeError, Extract};
+use crate::host_calls::timed;
 
 /// HTTP response
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -86,16 +89,17 @@ pub fn get(
     url: impl Into<String>,
     headers: impl IntoIterator<Item = (String, String)>,
 ) -> Result<Response, HttpGetError> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::get(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: Default::default(),
         authorization: http::Authorization::None,
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.get", || http::get(&request))?;
     Ok(Response {
         status,
         headers,
@@ -148,11 +152,7 @@ pub fn put<E: Encode>(
     headers: impl IntoIterator<Item = (String, String)>,
     body: E,
 ) -> Result<Response, HttpPutError<E::Error>> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::put(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: body
@@ -160,7 +160,12 @@ pub fn put<E: Encode>(
             .map_err(|e| HttpPutError::EncodeFailed { cause: e })?
             .into(),
         authorization: http::Authorization::None,
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.put", || http::put(&request))?;
     Ok(Response {
         status,
         headers,
@@ -221,11 +226,7 @@ pub fn post<E: Encode>(
     headers: impl IntoIterator<Item = (String, String)>,
     body: E,
 ) -> Result<Response, HttpPostError<E::Error>> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::post(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: body
@@ -233,7 +234,12 @@ pub fn post<E: Encode>(
             .map_err(|e| HttpPostError::EncodeFailed { cause: e })?
             .into(),
         authorization: http::Authorization::None,
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.post", || http::post(&request))?;
     Ok(Response {
         status,
         headers,
@@ -271,16 +277,17 @@ pub fn delete(
     url: impl Into<String>,
     headers: impl IntoIterator<Item = (String, String)>,
 ) -> Result<Response, HttpDeleteError> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::delete(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: Default::default(),
         authorization: http::Authorization::None,
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.delete", || http::delete(&request))?;
     Ok(Response {
         status,
         headers,
@@ -288,6 +295,202 @@ pub fn delete(
     })
 }
 
+/// The method of a [Request] sent with [start].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// GET
+    Get,
+    /// PUT
+    Put,
+    /// POST
+    Post,
+    /// DELETE
+    Delete,
+}
+
+impl From<Method> for http::Method {
+    fn from(method: Method) -> Self {
+        match method {
+            Method::Get => http::Method::Get,
+            Method::Put => http::Method::Put,
+            Method::Post => http::Method::Post,
+            Method::Delete => http::Method::Delete,
+        }
+    }
+}
+
+/// A request to send with [start] or [join_all].
+///
+/// Bodies are bytes; serialize them before building the request, like with
+/// `serde_json::to_vec`.
+#[derive(Debug, Clone)]
+pub struct Request {
+    method: Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// A GET request.
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(Method::Get, url, Vec::new())
+    }
+
+    /// A PUT request with `body`.
+    pub fn put(url: impl Into<String>, body: impl Into<Vec<u8>>) -> Self {
+        Self::new(Method::Put, url, body.into())
+    }
+
+    /// A POST request with `body`.
+    pub fn post(url: impl Into<String>, body: impl Into<Vec<u8>>) -> Self {
+        Self::new(Method::Post, url, body.into())
+    }
+
+    /// A DELETE request.
+    pub fn delete(url: impl Into<String>) -> Self {
+        Self::new(Method::Delete, url, Vec::new())
+    }
+
+    fn new(method: Method, url: impl Into<String>, body: Vec<u8>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// Add a header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    fn into_http(self) -> (http::Method, http::Request) {
+        (
+            self.method.into(),
+            http::Request {
+                url: self.url,
+                headers: self.headers,
+                body: self.body,
+                authorization: http::Authorization::None,
+            },
+        )
+    }
+}
+
+/// An error occurred while sending a request with [start] or waiting for its response.
+#[derive(Debug, Error)]
+pub enum HttpRequestError {
+    /// The host does not send requests concurrently.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+    /// An error occurred while calling the host http function.
+    #[error(transparent)]
+    HttpError(#[from] http::Error),
+}
+
+/// A request that has been sent, whose response may not have arrived yet.
+///
+/// Create one with [start]. Requests you start are in flight at the same time, so
+/// independent calls cost as much time as the slowest one rather than all of them added up.
+pub struct PendingResponse {
+    inner: http::PendingResponse,
+}
+
+impl PendingResponse {
+    /// True when the response has arrived, so [PendingResponse::wait] will not block.
+    pub fn is_ready(&self) -> bool {
+        self.inner.ready()
+    }
+
+    /// Wait for the response.
+    pub fn wait(self) -> Result<Response, HttpRequestError> {
+        let http::Response {
+            status,
+            headers,
+            body,
+        } = timed("http.wait", || self.inner.wait())?;
+        Ok(Response {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Send an HTTP request without waiting for its response.
+///
+/// ```rust,no_run
+/// use momento_functions_host::http::{self, Request};
+///
+/// # fn example() -> Result<(), http::HttpRequestError> {
+/// let profile = http::start(Request::get("https://example.com/profile/42"))?;
+/// let orders = http::start(Request::get("https://example.com/orders?user=42"))?;
+/// let (profile, orders) = (profile.wait()?, orders.wait()?);
+/// println!("statuses: {} {}", profile.status, orders.status);
+/// # Ok(())
+/// # }
+/// ```
+pub fn start(request: Request) -> Result<PendingResponse, HttpRequestError> {
+    abi::require(HostCapability::ConcurrentHttp)?;
+    let (method, request) = request.into_http();
+    Ok(PendingResponse {
+        inner: http::start(method, &request),
+    })
+}
+
+/// Send several HTTP requests concurrently and wait for all of their responses.
+///
+/// The results are in the same order as the requests.
+///
+/// ```rust,no_run
+/// use momento_functions_host::http::{self, Request};
+///
+/// let responses = http::join_all(
+///     ["a", "b", "c"].map(|id| Request::get(format!("https://example.com/items/{id}"))),
+/// );
+/// for response in responses {
+///     match response {
+///         Ok(response) => println!("status: {}", response.status),
+///         Err(e) => eprintln!("request failed: {e}"),
+///     }
+/// }
+/// ```
+pub fn join_all(
+    requests: impl IntoIterator<Item = Request>,
+) -> Vec<Result<Response, HttpRequestError>> {
+    let pending: Vec<Result<PendingResponse, HttpRequestError>> =
+        requests.into_iter().map(start).collect();
+    pending.into_iter().map(|pending| pending?.wait()).collect()
+}
+
+/// Wait until any of the pending responses has arrived, returning its index.
+///
+/// Returns `None` if `timeout` elapses first, or if `pending` is empty. Without a
+/// timeout, this waits as long as it takes.
+pub fn wait_any(pending: &[PendingResponse], timeout: Option<Duration>) -> Option<usize> {
+    let pending: Vec<&http::PendingResponse> = pending.iter().map(|p| &p.inner).collect();
+    let timeout_milliseconds =
+        timeout.map(|timeout| u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+    http::wait_any(&pending, timeout_milliseconds).map(|index| index as usize)
+}
+
+/// Percent-encode everything but unreserved characters, for a path segment or query string.
+pub fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+#[cfg(feature = "aws")]
 impl aws::auth::Credentials {
     fn into_http(
         self,
@@ -343,6 +546,7 @@ impl aws::auth::Credentials {
 ///     Err(e) => eprintln!("get failed: {e}"),
 /// }
 /// ```
+#[cfg(feature = "aws")]
 pub fn get_aws_sigv4(
     url: impl Into<String>,
     headers: impl IntoIterator<Item = (String, String)>,
@@ -350,16 +554,17 @@ pub fn get_aws_sigv4(
     region: impl Into<String>,
     service: impl Into<String>,
 ) -> Result<Response, HttpGetError> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::get(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: Default::default(),
         authorization: aws_credentials.into_http(region, service),
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.get", || http::get(&request))?;
     Ok(Response {
         status,
         headers,
@@ -393,6 +598,7 @@ pub fn get_aws_sigv4(
 ///     Err(e) => eprintln!("put failed: {e}"),
 /// }
 /// ```
+#[cfg(feature = "aws")]
 pub fn put_aws_sigv4<E: Encode>(
     url: impl Into<String>,
     headers: impl IntoIterator<Item = (String, String)>,
@@ -401,11 +607,7 @@ pub fn put_aws_sigv4<E: Encode>(
     service: impl Into<String>,
     body: E,
 ) -> Result<Response, HttpPutError<E::Error>> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::put(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: body
@@ -413,7 +615,12 @@ pub fn put_aws_sigv4<E: Encode>(
             .map_err(|e| HttpPutError::EncodeFailed { cause: e })?
             .into(),
         authorization: aws_credentials.into_http(region, service),
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.put", || http::put(&request))?;
     Ok(Response {
         status,
         headers,
@@ -447,6 +654,7 @@ pub fn put_aws_sigv4<E: Encode>(
 ///     Err(e) => eprintln!("post failed: {e}"),
 /// }
 /// ```
+#[cfg(feature = "aws")]
 pub fn post_aws_sigv4<E: Encode>(
     url: impl Into<String>,
     headers: impl IntoIterator<Item = (String, String)>,
@@ -455,11 +663,7 @@ pub fn post_aws_sigv4<E: Encode>(
     service: impl Into<String>,
     body: E,
 ) -> Result<Response, HttpPostError<E::Error>> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::post(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: body
@@ -467,7 +671,12 @@ pub fn post_aws_sigv4<E: Encode>(
             .map_err(|e| HttpPostError::EncodeFailed { cause: e })?
             .into(),
         authorization: aws_credentials.into_http(region, service),
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.post", || http::post(&request))?;
     Ok(Response {
         status,
         headers,
@@ -504,6 +713,7 @@ pub fn post_aws_sigv4<E: Encode>(
 ///     Err(e) => eprintln!("delete failed: {e}"),
 /// }
 /// ```
+#[cfg(feature = "aws")]
 pub fn delete_aws_sigv4(
     url: impl Into<String>,
     headers: impl IntoIterator<Item = (String, String)>,
@@ -511,19 +721,45 @@ pub fn delete_aws_sigv4(
     region: impl Into<String>,
     service: impl Into<String>,
 ) -> Result<Response, HttpDeleteError> {
-    let http::Response {
-        status,
-        headers,
-        body,
-    } = http::delete(&http::Request {
+    let request = http::Request {
         url: url.into(),
         headers: headers.into_iter().collect(),
         body: Default::default(),
         authorization: aws_credentials.into_http(region, service),
-    })?;
+    };
+    let http::Response {
+        status,
+        headers,
+        body,
+    } = timed("http.delete", || http::delete(&request))?;
     Ok(Response {
         status,
         headers,
         body,
     })
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_carry_their_method_and_headers() {
+        let (method, request) = Request::post("https://example.com/items", b"{}".to_vec())
+            .header("content-type", "application/json")
+            .into_http();
+        assert!(matches!(method, http::Method::Post));
+        assert_eq!("https://example.com/items", request.url);
+        assert_eq!(
+            vec![("content-type".to_string(), "application/json".to_string())],
+            request.headers
+        );
+        assert_eq!(b"{}".to_vec(), request.body);
+        assert!(matches!(request.authorization, http::Authorization::None));
+
+        let (method, request) = Request::get("https://example.com/items").into_http();
+        assert!(matches!(method, http::Method::Get));
+        assert!(request.body.is_empty());
+    }
+}
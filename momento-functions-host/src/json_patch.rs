@@ -0,0 +1,434 @@
+//! JSON Patch and JSON Merge Patch
+//!
+//! Use these to implement `PATCH` endpoints over cached documents or DynamoDB items.
+//!
+//! - [JSON Patch (RFC 6902)](https://www.rfc-editor.org/rfc/rfc6902) is a list of operations
+//!   addressed by JSON Pointer. Apply it with [apply] and compute one with [diff].
+//! - [JSON Merge Patch (RFC 7396)](https://www.rfc-editor.org/rfc/rfc7396) is a partial
+//!   document, where `null` removes a field. Apply it with [merge] and compute one with
+//!   [merge_diff].
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::json_patch::{self, PatchOperation};
+//! use serde_json::json;
+//!
+//! let mut document = json!({ "name": "widget", "tags": ["a"] });
+//! let patch: Vec<PatchOperation> = match serde_json::from_str(
+//!     r#"[{ "op": "replace", "path": "/name", "value": "gadget" },
+//!         { "op": "add", "path": "/tags/-", "value": "b" }]"#,
+//! ) {
+//!     Ok(patch) => patch,
+//!     Err(e) => {
+//!         eprintln!("invalid patch: {e}");
+//!         return;
+//!     }
+//! };
+//! match json_patch::apply(&mut document, &patch) {
+//!     Ok(()) => println!("patched: {document}"),
+//!     Err(e) => eprintln!("patch failed: {e}"),
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A JSON Patch operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Add a value, inserting into arrays and replacing object members.
+    Add {
+        /// Where to add the value.
+        path: String,
+        /// The value to add.
+        value: Value,
+    },
+    /// Remove the value at a path.
+    Remove {
+        /// The value to remove.
+        path: String,
+    },
+    /// Replace the value at a path, which must exist.
+    Replace {
+        /// The value to replace.
+        path: String,
+        /// The new value.
+        value: Value,
+    },
+    /// Move a value from one path to another.
+    Move {
+        /// The value to move.
+        from: String,
+        /// Where to move it.
+        path: String,
+    },
+    /// Copy a value from one path to another.
+    Copy {
+        /// The value to copy.
+        from: String,
+        /// Where to copy it.
+        path: String,
+    },
+    /// Check that the value at a path equals a value.
+    Test {
+        /// The value to check.
+        path: String,
+        /// The expected value.
+        value: Value,
+    },
+}
+
+/// A JSON Patch could not be applied.
+#[derive(Debug, thiserror::Error)]
+pub enum PatchError {
+    /// A path is not a valid JSON Pointer.
+    #[error("Invalid JSON pointer '{pointer}'")]
+    InvalidPointer {
+        /// The invalid pointer.
+        pointer: String,
+    },
+    /// A path does not exist in the document.
+    #[error("Nothing at '{pointer}'")]
+    NotFound {
+        /// The missing path.
+        pointer: String,
+    },
+    /// A `test` operation did not match.
+    #[error("Test failed at '{pointer}'")]
+    TestFailed {
+        /// The tested path.
+        pointer: String,
+    },
+    /// A `move` tried to move a value into itself.
+    #[error("Cannot move '{from}' into its own child '{pointer}'")]
+    MoveIntoChild {
+        /// The moved path.
+        from: String,
+        /// The destination path.
+        pointer: String,
+    },
+}
+
+/// Apply a JSON Patch to a document.
+///
+/// The patch is applied atomically: when an operation fails, the document is left unchanged.
+pub fn apply(document: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
+    let mut patched = document.clone();
+    for operation in patch {
+        apply_operation(&mut patched, operation)?;
+    }
+    *document = patched;
+    Ok(())
+}
+
+fn apply_operation(document: &mut Value, operation: &PatchOperation) -> Result<(), PatchError> {
+    match operation {
+        PatchOperation::Add { path, value } => add(document, path, value.clone()),
+        PatchOperation::Remove { path } => remove(document, path).map(drop),
+        PatchOperation::Replace { path, value } => {
+            let target = document
+                .pointer_mut(validate(path)?)
+                .ok_or_else(|| not_found(path))?;
+            *target = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if from == path {
+                return Ok(());
+            }
+            if path.starts_with(&format!("{from}/")) {
+                return Err(PatchError::MoveIntoChild {
+                    from: from.clone(),
+                    pointer: path.clone(),
+                });
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = document
+                .pointer(validate(from)?)
+                .ok_or_else(|| not_found(from))?
+                .clone();
+            add(document, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            if document.pointer(validate(path)?) == Some(value) {
+                Ok(())
+            } else {
+                Err(PatchError::TestFailed {
+                    pointer: path.clone(),
+                })
+            }
+        }
+    }
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+    let Some((parent, last)) = split(validate(path)?) else {
+        *document = value;
+        return Ok(());
+    };
+    match document.pointer_mut(parent) {
+        Some(Value::Object(object)) => {
+            object.insert(last, value);
+            Ok(())
+        }
+        Some(Value::Array(array)) => {
+            let index = if last == "-" {
+                array.len()
+            } else {
+                parse_index(&last, array.len() + 1).ok_or_else(|| not_found(path))?
+            };
+            array.insert(index, value);
+            Ok(())
+        }
+        _ => Err(not_found(path)),
+    }
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value, PatchError> {
+    let Some((parent, last)) = split(validate(path)?) else {
+        return Ok(std::mem::take(document));
+    };
+    match document.pointer_mut(parent) {
+        Some(Value::Object(object)) => object.remove(&last).ok_or_else(|| not_found(path)),
+        Some(Value::Array(array)) => {
+            let index = parse_index(&last, array.len()).ok_or_else(|| not_found(path))?;
+            Ok(array.remove(index))
+        }
+        _ => Err(not_found(path)),
+    }
+}
+
+fn validate(pointer: &str) -> Result<&str, PatchError> {
+    if pointer.is_empty() || pointer.starts_with('/') {
+        Ok(pointer)
+    } else {
+        Err(PatchError::InvalidPointer {
+            pointer: pointer.to_string(),
+        })
+    }
+}
+
+/// Split a pointer into its parent pointer and its unescaped last token.
+fn split(pointer: &str) -> Option<(&str, String)> {
+    let (parent, last) = pointer.rsplit_once('/')?;
+    Some((parent, unescape(last)))
+}
+
+fn parse_index(token: &str, limit: usize) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    token.parse().ok().filter(|index| *index < limit)
+}
+
+fn not_found(pointer: &str) -> PatchError {
+    PatchError::NotFound {
+        pointer: pointer.to_string(),
+    }
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Compute a JSON Patch that turns `from` into `to`.
+///
+/// Objects are compared member by member. Arrays are compared element by element, with
+/// elements added or removed at the end; an array whose elements shift is patched
+/// element by element rather than with moves.
+pub fn diff(from: &Value, to: &Value) -> Vec<PatchOperation> {
+    let mut patch = Vec::new();
+    diff_into(&mut patch, String::new(), from, to);
+    patch
+}
+
+fn diff_into(patch: &mut Vec<PatchOperation>, path: String, from: &Value, to: &Value) {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            for (key, from_value) in from {
+                let child = format!("{path}/{}", escape(key));
+                match to.get(key) {
+                    Some(to_value) => diff_into(patch, child, from_value, to_value),
+                    None => patch.push(PatchOperation::Remove { path: child }),
+                }
+            }
+            for (key, to_value) in to {
+                if !from.contains_key(key) {
+                    patch.push(PatchOperation::Add {
+                        path: format!("{path}/{}", escape(key)),
+                        value: to_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            for (index, (from_value, to_value)) in from.iter().zip(to).enumerate() {
+                diff_into(patch, format!("{path}/{index}"), from_value, to_value);
+            }
+            // Remove from the end so earlier indexes stay valid.
+            for index in (to.len()..from.len()).rev() {
+                patch.push(PatchOperation::Remove {
+                    path: format!("{path}/{index}"),
+                });
+            }
+            for value in to.iter().skip(from.len()) {
+                patch.push(PatchOperation::Add {
+                    path: format!("{path}/-"),
+                    value: value.clone(),
+                });
+            }
+        }
+        (from, to) if from != to => patch.push(PatchOperation::Replace {
+            path,
+            value: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Apply a JSON Merge Patch to a document.
+pub fn merge(document: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *document = patch.clone();
+        return;
+    };
+    if !document.is_object() {
+        *document = Value::Object(Map::new());
+    }
+    if let Value::Object(object) = document {
+        for (key, value) in patch {
+            if value.is_null() {
+                object.remove(key);
+            } else {
+                merge(object.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Compute a JSON Merge Patch that turns `from` into `to`.
+///
+/// Merge patches cannot set a member to `null`, or change part of an array. Members whose
+/// new value is `null` are removed instead, and changed arrays are replaced whole.
+pub fn merge_diff(from: &Value, to: &Value) -> Value {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            let mut patch = Map::new();
+            for key in from.keys() {
+                if !to.contains_key(key) {
+                    patch.insert(key.clone(), Value::Null);
+                }
+            }
+            for (key, to_value) in to {
+                match from.get(key) {
+                    Some(from_value) if from_value == to_value => {}
+                    Some(from_value) => {
+                        patch.insert(key.clone(), merge_diff(from_value, to_value));
+                    }
+                    None => {
+                        patch.insert(key.clone(), to_value.clone());
+                    }
+                }
+            }
+            Value::Object(patch)
+        }
+        _ => to.clone(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patch(json: Value) -> Vec<PatchOperation> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn applies_rfc_6902_operations() {
+        let mut document = json!({ "foo": ["bar", "baz"], "a/b": 1 });
+        apply(
+            &mut document,
+            &patch(json!([
+                { "op": "add", "path": "/foo/1", "value": "qux" },
+                { "op": "remove", "path": "/foo/0" },
+                { "op": "move", "from": "/a~1b", "path": "/moved" },
+                { "op": "copy", "from": "/moved", "path": "/copied" },
+                { "op": "replace", "path": "/copied", "value": 2 },
+                { "op": "add", "path": "/foo/-", "value": "end" },
+                { "op": "test", "path": "/foo", "value": ["qux", "baz", "end"] },
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            json!({ "foo": ["qux", "baz", "end"], "moved": 1, "copied": 2 }),
+            document
+        );
+    }
+
+    #[test]
+    fn failed_patches_leave_the_document_unchanged() {
+        let mut document = json!({ "a": 1 });
+        let error = apply(
+            &mut document,
+            &patch(json!([
+                { "op": "replace", "path": "/a", "value": 2 },
+                { "op": "test", "path": "/a", "value": 3 },
+            ])),
+        )
+        .unwrap_err();
+        assert!(matches!(error, PatchError::TestFailed { .. }));
+        assert_eq!(json!({ "a": 1 }), document);
+
+        let error = apply(
+            &mut document,
+            &patch(json!([{ "op": "remove", "path": "/missing" }])),
+        )
+        .unwrap_err();
+        assert!(matches!(error, PatchError::NotFound { .. }));
+    }
+
+    #[test]
+    fn diff_round_trips() {
+        let from = json!({ "a": 1, "b": { "c": [1, 2, 3] }, "gone": true, "x/y": 0 });
+        let to = json!({ "a": 2, "b": { "c": [1, 5] }, "new": null, "x/y": 1 });
+        let mut patched = from.clone();
+        apply(&mut patched, &diff(&from, &to)).unwrap();
+        assert_eq!(to, patched);
+        assert!(diff(&to, &to).is_empty());
+    }
+
+    #[test]
+    fn merges_rfc_7396_patches() {
+        let mut document = json!({ "title": "Goodbye!", "author": { "givenName": "John", "familyName": "Doe" }, "tags": ["example", "sample"] });
+        merge(
+            &mut document,
+            &json!({ "title": "Hello!", "phoneNumber": "+01-123-456-7890", "author": { "familyName": null }, "tags": ["example"] }),
+        );
+        assert_eq!(
+            json!({ "title": "Hello!", "author": { "givenName": "John" }, "tags": ["example"], "phoneNumber": "+01-123-456-7890" }),
+            document
+        );
+    }
+
+    #[test]
+    fn merge_diff_round_trips() {
+        let from = json!({ "a": 1, "b": { "c": 1, "d": 2 }, "gone": true });
+        let to = json!({ "a": 1, "b": { "c": 3, "d": 2 }, "new": [1] });
+        let patch = merge_diff(&from, &to);
+        assert_eq!(json!({ "b": { "c": 3 }, "gone": null, "new": [1] }), patch);
+        let mut merged = from.clone();
+        merge(&mut merged, &patch);
+        assert_eq!(to, merged);
+    }
+}
@@ -0,0 +1,497 @@
+//! Host interfaces for producing to Kafka or Amazon MSK
+//!
+//! [KafkaProducer] writes records to an existing Kafka pipeline. The host keeps producers and
+//! their broker connections open across invocations, so a Function does not reconnect and
+//! authenticate on each request.
+//!
+//! [send_batch](KafkaProducer::send_batch) sends several records together and reports each
+//! one's acknowledgment separately, so a Function can retry or report only the records that
+//! failed.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use momento_functions_host::kafka::{Acks, KafkaError, KafkaProducer, KafkaRecord, ProducerConfig, Sasl};
+//!
+//! fn record_signup(user_id: &str, email: &str) -> Result<(), KafkaError> {
+//!     let producer = KafkaProducer::new(
+//!         ProducerConfig::new(["b-1.kafka.internal:9096", "b-2.kafka.internal:9096"])
+//!             .sasl(Sasl::ScramSha512 {
+//!                 username: "functions".to_string(),
+//!                 password: std::env::var("KAFKA_PASSWORD").unwrap_or_default(),
+//!             })
+//!             .acks(Acks::All)
+//!             .timeout(Duration::from_secs(5)),
+//!     )?;
+//!     let delivery = producer.send(
+//!         KafkaRecord::new("signups", email.as_bytes().to_vec())
+//!             .key(user_id)
+//!             .header("source", "signup-function"),
+//!     )?;
+//!     log::info!("signup written to partition {} at {}", delivery.partition, delivery.offset);
+//!     Ok(())
+//! }
+//! ```
+
+use std::time::Duration;
+
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
+use momento_functions_wit::host::momento::host::kafka;
+use serde::Serialize;
+
+/// An error occurred while producing to Kafka.
+#[derive(Debug, thiserror::Error)]
+pub enum KafkaError {
+    /// The host could not reach the brokers.
+    #[error("Failed to connect to Kafka: {message}")]
+    Connection {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The brokers rejected the credentials, or the topic's ACLs denied the write.
+    #[error("Unauthorized: {message}")]
+    Unauthorized {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The topic does not exist.
+    #[error("Unknown topic: {message}")]
+    UnknownTopic {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The record is larger than the topic accepts.
+    #[error("Record too large: {message}")]
+    RecordTooLarge {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The brokers did not acknowledge the record in time. It may still be written.
+    #[error("Timed out waiting for acknowledgment: {message}")]
+    Timeout {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The request failed for some other reason.
+    #[error("Kafka request failed: {message}")]
+    Other {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The host is too old to produce to Kafka.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+impl From<kafka::KafkaError> for KafkaError {
+    fn from(e: kafka::KafkaError) -> Self {
+        match e {
+            kafka::KafkaError::Connection(message) => Self::Connection { message },
+            kafka::KafkaError::Unauthorized(message) => Self::Unauthorized { message },
+            kafka::KafkaError::UnknownTopic(message) => Self::UnknownTopic { message },
+            kafka::KafkaError::MessageTooLarge(message) => Self::RecordTooLarge { message },
+            kafka::KafkaError::Timeout(message) => Self::Timeout { message },
+            kafka::KafkaError::Other(message) => Self::Other { message },
+        }
+    }
+}
+
+/// How to authenticate to the brokers with SASL. The password is not printed by [Debug].
+#[derive(Clone)]
+pub enum Sasl {
+    /// `PLAIN`, for Confluent Cloud API keys and similar.
+    Plain {
+        /// The SASL username.
+        username: String,
+        /// The SASL password.
+        password: String,
+    },
+    /// `SCRAM-SHA-256`
+    ScramSha256 {
+        /// The SASL username.
+        username: String,
+        /// The SASL password.
+        password: String,
+    },
+    /// `SCRAM-SHA-512`, as Amazon MSK uses for username and password authentication.
+    ScramSha512 {
+        /// The SASL username.
+        username: String,
+        /// The SASL password.
+        password: String,
+    },
+}
+
+impl std::fmt::Debug for Sasl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (mechanism, username) = match self {
+            Sasl::Plain { username, .. } => ("Plain", username),
+            Sasl::ScramSha256 { username, .. } => ("ScramSha256", username),
+            Sasl::ScramSha512 { username, .. } => ("ScramSha512", username),
+        };
+        f.debug_struct(mechanism)
+            .field("username", username)
+            .field("password", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl From<Sasl> for kafka::Sasl {
+    fn from(sasl: Sasl) -> Self {
+        match sasl {
+            Sasl::Plain { username, password } => {
+                kafka::Sasl::Plain(kafka::SaslCredentials { username, password })
+            }
+            Sasl::ScramSha256 { username, password } => {
+                kafka::Sasl::ScramSha256(kafka::SaslCredentials { username, password })
+            }
+            Sasl::ScramSha512 { username, password } => {
+                kafka::Sasl::ScramSha512(kafka::SaslCredentials { username, password })
+            }
+        }
+    }
+}
+
+/// Which brokers must have a record before it is acknowledged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acks {
+    /// Don't wait for an acknowledgment. Deliveries report an offset of -1.
+    None,
+    /// The partition leader.
+    Leader,
+    /// Every in-sync replica. This is the default.
+    All,
+}
+
+/// How record batches are compressed on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip
+    Gzip,
+    /// Snappy
+    Snappy,
+    /// LZ4
+    Lz4,
+    /// Zstandard
+    Zstd,
+}
+
+/// How a [KafkaProducer] connects and what it waits for. The SASL password is not printed by
+/// [Debug].
+#[derive(Clone)]
+pub struct ProducerConfig {
+    bootstrap_servers: Vec<String>,
+    sasl: Option<Sasl>,
+    tls: bool,
+    acks: Acks,
+    compression: Option<Compression>,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for ProducerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProducerConfig")
+            .field("bootstrap_servers", &self.bootstrap_servers)
+            .field("sasl", &self.sasl)
+            .field("tls", &self.tls)
+            .field("acks", &self.acks)
+            .field("compression", &self.compression)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl ProducerConfig {
+    /// Connect to `bootstrap_servers`, given as `host:port`, over TLS, waiting up to 30
+    /// seconds for every in-sync replica to acknowledge each record.
+    pub fn new(bootstrap_servers: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            bootstrap_servers: bootstrap_servers.into_iter().map(Into::into).collect(),
+            sasl: None,
+            tls: true,
+            acks: Acks::All,
+            compression: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Authenticate with SASL.
+    pub fn sasl(mut self, sasl: Sasl) -> Self {
+        self.sasl = Some(sasl);
+        self
+    }
+
+    /// Connect without TLS, for brokers on a private network that don't offer it.
+    pub fn plaintext(mut self) -> Self {
+        self.tls = false;
+        self
+    }
+
+    /// Wait for these brokers to acknowledge each record.
+    pub fn acks(mut self, acks: Acks) -> Self {
+        self.acks = acks;
+        self
+    }
+
+    /// Compress record batches.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Wait this long for acknowledgments before reporting [KafkaError::Timeout].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl From<ProducerConfig> for kafka::ProducerConfig {
+    fn from(config: ProducerConfig) -> Self {
+        kafka::ProducerConfig {
+            bootstrap_servers: config.bootstrap_servers,
+            sasl: config.sasl.map(Into::into),
+            tls: config.tls,
+            acks: match config.acks {
+                Acks::None => kafka::Acks::None,
+                Acks::Leader => kafka::Acks::Leader,
+                Acks::All => kafka::Acks::All,
+            },
+            compression: config.compression.map(|compression| match compression {
+                Compression::Gzip => kafka::Compression::Gzip,
+                Compression::Snappy => kafka::Compression::Snappy,
+                Compression::Lz4 => kafka::Compression::Lz4,
+                Compression::Zstd => kafka::Compression::Zstd,
+            }),
+            timeout_milliseconds: config.timeout.as_millis() as u64,
+        }
+    }
+}
+
+/// A record to produce.
+#[derive(Debug, Clone)]
+pub struct KafkaRecord {
+    message: kafka::Message,
+}
+
+impl KafkaRecord {
+    /// A record for `topic` with `value` and no key.
+    pub fn new(topic: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            message: kafka::Message {
+                topic: topic.into(),
+                key: None,
+                value: Some(value.into()),
+                headers: Vec::new(),
+                partition: None,
+            },
+        }
+    }
+
+    /// A record for `topic` whose value is `value` as JSON.
+    pub fn json(
+        topic: impl Into<String>,
+        value: &impl Serialize,
+    ) -> Result<Self, serde_json::Error> {
+        Ok(Self::new(topic, serde_json::to_vec(value)?))
+    }
+
+    /// A record with no value, which deletes `key` from a compacted topic.
+    pub fn tombstone(topic: impl Into<String>, key: impl Into<Vec<u8>>) -> Self {
+        let mut record = Self::new(topic, Vec::new()).key(key);
+        record.message.value = None;
+        record
+    }
+
+    /// Set the record's key. Records with the same key go to the same partition, in order.
+    pub fn key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.message.key = Some(key.into());
+        self
+    }
+
+    /// Add a header.
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        self.message.headers.push(kafka::Header {
+            key: key.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Write to `partition` instead of the one the key hashes to.
+    pub fn partition(mut self, partition: i32) -> Self {
+        self.message.partition = Some(partition);
+        self
+    }
+}
+
+/// Where an acknowledged record was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delivery {
+    /// The partition the record was written to.
+    pub partition: i32,
+    /// The record's offset in the partition, or -1 when the producer doesn't wait for
+    /// acknowledgments.
+    pub offset: i64,
+}
+
+/// The acknowledgments for a batch, from [KafkaProducer::send_batch].
+#[derive(Debug)]
+pub struct BatchDelivery {
+    results: Vec<Result<Delivery, KafkaError>>,
+}
+
+impl BatchDelivery {
+    /// Whether every record was acknowledged.
+    pub fn all_delivered(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// The records that failed, by their position in the batch.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &KafkaError)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().err().map(|e| (index, e)))
+    }
+
+    /// Each record's result, in the order the records were sent.
+    pub fn results(&self) -> &[Result<Delivery, KafkaError>] {
+        &self.results
+    }
+
+    /// Each record's result, in the order the records were sent.
+    pub fn into_results(self) -> Vec<Result<Delivery, KafkaError>> {
+        self.results
+    }
+}
+
+/// Kafka producer for Function host interfaces.
+///
+/// This producer uses Momento's host-provided connection cache, which keeps producers and
+/// their connections alive across invocations of your Function for reuse. The brokers must
+/// be reachable from the Functions host environment.
+pub struct KafkaProducer {
+    producer: kafka::Producer,
+}
+
+impl KafkaProducer {
+    /// Create a producer.
+    pub fn new(config: ProducerConfig) -> Result<Self, KafkaError> {
+        abi::require(HostCapability::KafkaProducer)?;
+        Ok(Self {
+            producer: kafka::Producer::new(&config.into()),
+        })
+    }
+
+    /// Create a producer for Amazon MSK that signs in with IAM, using `credentials`.
+    ///
+    /// Leave [sasl](ProducerConfig::sasl) unset; MSK's IAM brokers listen on port 9098.
+    #[cfg(feature = "aws")]
+    pub fn msk_iam(
+        config: ProducerConfig,
+        credentials: &crate::aws::auth::AwsCredentialsProvider,
+    ) -> Result<Self, KafkaError> {
+        abi::require(HostCapability::KafkaProducer)?;
+        Ok(Self {
+            producer: kafka::Producer::msk_iam(&config.into(), credentials.resource()),
+        })
+    }
+
+    /// Send one record, waiting for its acknowledgment.
+    pub fn send(&self, record: KafkaRecord) -> Result<Delivery, KafkaError> {
+        self.send_batch([record])
+            .into_results()
+            .pop()
+            .unwrap_or_else(|| {
+                Err(KafkaError::Other {
+                    message: "the host returned no delivery for the record".to_string(),
+                })
+            })
+    }
+
+    /// Send records together, reporting each one's acknowledgment.
+    ///
+    /// Sending a batch is one call to the host, so it is much faster than sending records one
+    /// at a time. Records with the same key keep their order within the batch.
+    pub fn send_batch(&self, records: impl IntoIterator<Item = KafkaRecord>) -> BatchDelivery {
+        let messages: Vec<kafka::Message> =
+            records.into_iter().map(|record| record.message).collect();
+        let count = messages.len();
+        let mut results: Vec<Result<Delivery, KafkaError>> = self
+            .producer
+            .send(&messages)
+            .into_iter()
+            .map(|result| {
+                result
+                    .map(|delivery| Delivery {
+                        partition: delivery.partition,
+                        offset: delivery.offset,
+                    })
+                    .map_err(Into::into)
+            })
+            .collect();
+        // Report records the host did not account for rather than dropping them silently.
+        while results.len() < count {
+            results.push(Err(KafkaError::Other {
+                message: "the host returned no delivery for the record".to_string(),
+            }));
+        }
+        BatchDelivery { results }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_failures_keep_their_position() {
+        let batch = BatchDelivery {
+            results: vec![
+                Ok(Delivery {
+                    partition: 0,
+                    offset: 41,
+                }),
+                Err(kafka::KafkaError::MessageTooLarge("1 MiB".to_string()).into()),
+                Ok(Delivery {
+                    partition: 2,
+                    offset: 7,
+                }),
+            ],
+        };
+        assert!(!batch.all_delivered());
+        let failures: Vec<usize> = batch.failures().map(|(index, _)| index).collect();
+        assert_eq!(vec![1], failures);
+    }
+
+    #[test]
+    fn tombstones_have_a_key_and_no_value() {
+        let record = KafkaRecord::tombstone("users", "user-1").header("reason", "deleted");
+        assert_eq!(Some(b"user-1".to_vec()), record.message.key);
+        assert_eq!(None, record.message.value);
+        assert_eq!(1, record.message.headers.len());
+
+        let config: kafka::ProducerConfig = ProducerConfig::new(["b-1:9096"])
+            .plaintext()
+            .timeout(Duration::from_millis(1500))
+            .into();
+        assert!(!config.tls);
+        assert_eq!(1500, config.timeout_milliseconds);
+        assert!(matches!(config.acks, kafka::Acks::All));
+    }
+
+    #[test]
+    fn passwords_are_not_printed() {
+        let config = ProducerConfig::new(["b-1:9096"]).sasl(Sasl::ScramSha512 {
+            username: "producer".to_string(),
+            password: "hunter2".to_string(),
+        });
+        let printed = format!("{config:?}");
+        assert!(printed.contains("producer"));
+        assert!(printed.contains("[REDACTED]"));
+        assert!(!printed.contains("hunter2"));
+    }
+}
@@ -0,0 +1,286 @@
+//! Cache keys grouped into a collection that can be listed
+//!
+//! The cache cannot list its keys, so a Function that needs to find or clear everything it
+//! wrote has to remember the keys itself. [KeyedSet] does that: items written through it are
+//! also recorded in a manifest, one cache item holding the collection's keys. Read the keys
+//! back with [KeyedSet::keys], or delete every item with [KeyedSet::delete_all].
+//!
+//! The manifest is updated with compare-and-set, so Functions writing to the same
+//! collection at once do not drop each other's keys. Keys are recorded before their items
+//! are written and again after, and forgotten after they are deleted, so the manifest may
+//! name an item that has expired or was never written, but once a write has finished it
+//! never misses the item, even when a [KeyedSet::delete_all] forgot the key while it was
+//! being written. [KeyedSet::prune] forgets the keys whose items are gone.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::encoding::Json;
+//! use momento_functions_host::keyed_set::KeyedSet;
+//! use std::time::Duration;
+//!
+//! # fn f() -> Result<(), momento_functions_host::keyed_set::KeyedSetError> {
+//! let renders = KeyedSet::new("renders:tenant-7");
+//! renders.set(
+//!     "render:tenant-7:home",
+//!     Json(serde_json::json!({ "html": "<h1>Home</h1>" })),
+//!     Duration::from_secs(3600),
+//! )?;
+//!
+//! // Later, when the tenant's templates change:
+//! let deleted = renders.delete_all()?;
+//! log::info!("cleared {deleted} renders");
+//! # Ok(()) }
+//! ```
+
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::time::Duration;
+
+use crate::cache::{
+    self, CacheDeleteError, CacheGetWithHashError, CacheItemMetadataError, CacheSetError,
+    CacheSetIfError, CacheSetIfHashError, SetIfCondition, SetIfHashCondition, SetIfHashResult,
+    SetIfResult,
+};
+use crate::encoding::Encode;
+
+const MAX_UPDATE_ATTEMPTS: usize = 10;
+
+/// A manifest's keys, and the hash of the cache value they were read from.
+type Manifest = (BTreeSet<String>, Vec<u8>);
+
+/// An error occurred while reading or writing a [KeyedSet].
+#[derive(Debug, thiserror::Error)]
+pub enum KeyedSetError {
+    /// The manifest could not be read from the cache.
+    #[error(transparent)]
+    ManifestRead(#[from] CacheGetWithHashError<Infallible>),
+    /// The manifest could not be created in the cache.
+    #[error(transparent)]
+    ManifestCreate(#[from] CacheSetIfError<Infallible>),
+    /// The manifest could not be updated in the cache.
+    #[error(transparent)]
+    ManifestUpdate(#[from] CacheSetIfHashError<Infallible>),
+    /// The manifest kept changing while it was being updated.
+    #[error("Manifest {manifest_key} changed on each of {attempts} attempts to update it")]
+    Contended {
+        /// The manifest's cache key.
+        manifest_key: String,
+        /// How many times the update was tried.
+        attempts: usize,
+    },
+    /// The manifest is not a list of keys.
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(#[from] serde_json::Error),
+    /// The value could not be encoded.
+    #[error("Failed to encode value.")]
+    Encode {
+        /// The underlying encoding error.
+        cause: Box<dyn std::error::Error>,
+    },
+    /// An item could not be written to the cache.
+    #[error(transparent)]
+    Write(#[from] CacheSetError<Infallible>),
+    /// An item could not be deleted from the cache.
+    #[error(transparent)]
+    Delete(#[from] CacheDeleteError),
+    /// An item's metadata could not be read from the cache.
+    #[error(transparent)]
+    Metadata(#[from] CacheItemMetadataError),
+}
+
+/// Cache items whose keys are recorded in a manifest, so they can be listed and deleted
+/// together.
+#[derive(Debug, Clone)]
+pub struct KeyedSet {
+    manifest_key: String,
+    manifest_ttl: Duration,
+}
+
+impl KeyedSet {
+    /// A collection whose manifest is stored at `{name}:manifest`.
+    ///
+    /// The manifest lives for a day after it last changed unless you change it with
+    /// [KeyedSet::manifest_ttl].
+    pub fn new(name: impl AsRef<str>) -> Self {
+        Self {
+            manifest_key: format!("{}:manifest", name.as_ref()),
+            manifest_ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Store the manifest at `manifest_key` instead.
+    pub fn manifest_key(mut self, manifest_key: impl Into<String>) -> Self {
+        self.manifest_key = manifest_key.into();
+        self
+    }
+
+    /// How long the manifest lives after it last changed. Make it at least as long as the
+    /// longest ttl of the collection's items, or their keys are forgotten while the items
+    /// still exist.
+    pub fn manifest_ttl(mut self, manifest_ttl: Duration) -> Self {
+        self.manifest_ttl = manifest_ttl;
+        self
+    }
+
+    /// Write `value` to `key` and record `key` in the collection.
+    ///
+    /// The key is recorded again after the write, in case a [KeyedSet::delete_all] or
+    /// [KeyedSet::remove] forgot it while the item was being written. Recording a key that
+    /// is already there only reads the manifest.
+    pub fn set<E: Encode>(
+        &self,
+        key: impl Into<String>,
+        value: E,
+        ttl: Duration,
+    ) -> Result<(), KeyedSetError> {
+        let value: Vec<u8> = value
+            .try_serialize()
+            .map_err(|e| KeyedSetError::Encode { cause: Box::new(e) })?
+            .into();
+        let key = key.into();
+        self.track([key.clone()])?;
+        cache::set(&key, value, ttl)?;
+        self.track([key])
+    }
+
+    /// Delete the item at `key` and forget it.
+    pub fn remove(&self, key: impl Into<String>) -> Result<(), KeyedSetError> {
+        let key = key.into();
+        cache::delete(&key)?;
+        self.untrack([key])
+    }
+
+    /// Record keys whose items are written some other way.
+    ///
+    /// Record them both before and after writing the items, as [KeyedSet::set] does, so a
+    /// concurrent [KeyedSet::delete_all] cannot leave a written item unrecorded.
+    pub fn track(
+        &self,
+        keys: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<(), KeyedSetError> {
+        let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+        self.update(|manifest| {
+            let before = manifest.len();
+            manifest.extend(keys.iter().cloned());
+            manifest.len() != before
+        })
+    }
+
+    /// Forget keys without deleting their items.
+    pub fn untrack(
+        &self,
+        keys: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<(), KeyedSetError> {
+        let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+        self.update(|manifest| {
+            let before = manifest.len();
+            manifest.retain(|key| !keys.contains(key));
+            manifest.len() != before
+        })
+    }
+
+    /// The keys recorded in the collection, in order.
+    pub fn keys(&self) -> Result<BTreeSet<String>, KeyedSetError> {
+        Ok(self.read()?.map(|(keys, _)| keys).unwrap_or_default())
+    }
+
+    /// Delete every item in the collection and forget their keys. Returns how many keys were
+    /// deleted.
+    ///
+    /// Keys recorded while this runs are left in the manifest. A key that is written again
+    /// while this runs may be forgotten here, and is recorded again when that write finishes.
+    pub fn delete_all(&self) -> Result<usize, KeyedSetError> {
+        let keys = self.keys()?;
+        for key in &keys {
+            cache::delete(key)?;
+        }
+        self.untrack(keys.iter().cloned())?;
+        Ok(keys.len())
+    }
+
+    /// Forget the keys whose items have expired or were deleted without [KeyedSet::remove].
+    /// Returns how many were forgotten.
+    pub fn prune(&self) -> Result<usize, KeyedSetError> {
+        let mut gone = Vec::new();
+        for key in self.keys()? {
+            if cache::item_metadata(&key)?.is_none() {
+                gone.push(key);
+            }
+        }
+        let pruned = gone.len();
+        self.untrack(gone)?;
+        Ok(pruned)
+    }
+
+    fn read(&self) -> Result<Option<Manifest>, KeyedSetError> {
+        let Some(manifest) = cache::get_with_hash::<Vec<u8>>(&self.manifest_key)? else {
+            return Ok(None);
+        };
+        Ok(Some((
+            serde_json::from_slice(&manifest.value)?,
+            manifest.hash,
+        )))
+    }
+
+    /// Apply `change` to the manifest until it is stored without a concurrent write getting
+    /// in first. `change` returns whether it changed anything.
+    fn update(&self, change: impl Fn(&mut BTreeSet<String>) -> bool) -> Result<(), KeyedSetError> {
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let (mut manifest, hash) = self.read()?.unzip();
+            let keys = manifest.get_or_insert_default();
+            if !change(keys) {
+                return Ok(());
+            }
+            let value = serde_json::to_vec(keys)?;
+            let stored = match hash {
+                Some(hash) => matches!(
+                    cache::set_if_hash(
+                        &self.manifest_key,
+                        value,
+                        self.manifest_ttl,
+                        SetIfHashCondition::PresentAndHashEqual(hash),
+                    )?,
+                    SetIfHashResult::Stored(_)
+                ),
+                None => matches!(
+                    cache::set_if(
+                        &self.manifest_key,
+                        value,
+                        self.manifest_ttl,
+                        SetIfCondition::Absent,
+                    )?,
+                    SetIfResult::Stored
+                ),
+            };
+            if stored {
+                return Ok(());
+            }
+            log::debug!("manifest {} changed while updating it", self.manifest_key);
+        }
+        Err(KeyedSetError::Contended {
+            manifest_key: self.manifest_key.clone(),
+            attempts: MAX_UPDATE_ATTEMPTS,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_are_sorted_json_arrays() {
+        let set = KeyedSet::new("renders");
+        assert_eq!("renders:manifest", set.manifest_key);
+        assert_eq!("other", set.manifest_key("other").manifest_key);
+
+        let keys: BTreeSet<String> = ["b", "a", "b"].map(String::from).into();
+        let json = serde_json::to_string(&keys).unwrap();
+        assert_eq!(r#"["a","b"]"#, json);
+        assert_eq!(
+            keys,
+            serde_json::from_str::<BTreeSet<String>>(&json).unwrap()
+        );
+    }
+}
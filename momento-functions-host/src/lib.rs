@@ -11,16 +11,86 @@
 //! You are likely to be interested in the sibling crates:
 //! * [`momento-functions`](https://crates.io/crates/momento-functions): Code generators for Functions.
 //! * [`momento-functions-log`](https://crates.io/crates/momento-functions-log): Standard `log` adapter.
+//!
+//! ## Features
+//! The host interfaces beyond cache, logging, and spawn are behind cargo features, all enabled
+//! by default: `aws`, `http`, `kafka`, `redis`, `sql`, `token`, and `topics`. A Function that only needs some of
+//! them can turn off default features and list the ones it uses, so it does not compile the rest:
+//! ```toml
+//! momento-functions-host = { version = "0", default-features = false, features = ["http"] }
+//! ```
+//!
+//! The `html` feature, for Markdown rendering and HTML sanitization, is off by default. So is
+//! `control`, for creating caches and topics from a Function.
 
+#[cfg(feature = "http")]
+pub mod agent;
+#[cfg(feature = "http")]
+pub mod api;
+#[cfg(feature = "aws")]
 pub mod aws;
 pub mod cache;
+#[cfg(feature = "http")]
+pub mod clickhouse;
+pub mod concurrency;
+pub mod config;
+pub mod config_store;
+#[cfg(feature = "control")]
+pub mod control;
+pub mod diagnostics;
+#[cfg(feature = "http")]
+pub mod elasticsearch;
+#[cfg(feature = "http")]
+pub mod embeddings;
 pub mod encoding;
+pub mod fetch;
+pub mod flags;
+pub mod host_calls;
+mod host_error;
+#[cfg(feature = "html")]
+pub mod html;
+#[cfg(feature = "http")]
 pub mod http;
+pub mod json_patch;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod keyed_set;
+#[cfg(feature = "http")]
+pub mod llm;
 pub mod logging;
+pub mod mime;
+#[cfg(feature = "http")]
+pub mod notify;
+#[cfg(feature = "http")]
+pub mod oidc;
+pub mod parallel;
+pub mod prompts;
+pub mod quota;
+#[cfg(feature = "http")]
+pub mod rag;
+#[cfg(feature = "redis")]
 pub mod redis;
+mod refresh;
+pub mod retry;
+pub mod rollout;
+pub mod sessions;
+pub mod signing;
+#[cfg(feature = "aws")]
+pub mod snapshot;
 mod spawn;
+#[cfg(feature = "sql")]
+pub mod sql;
+#[cfg(any(feature = "aws", feature = "sql"))]
+pub mod sql_types;
+#[cfg(feature = "token")]
 pub mod token;
+#[cfg(feature = "topics")]
 pub mod topics;
+pub mod vector_store;
 pub mod web_extensions;
 
-pub use spawn::spawn;
+pub use host_error::{ErrorKind, HostError, StatusClass};
+pub use spawn::{FunctionSpawnError, spawn};
+
+/// The version of this crate.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
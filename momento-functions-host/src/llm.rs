@@ -0,0 +1,1382 @@
+//! Chat with tool use across model providers
+//!
+//! A [ChatRequest] describes a conversation, the [Tool]s the model may call, and how to
+//! generate the reply, without naming a provider. [AnthropicChat], [GeminiChat], and
+//! [OpenAiChat] each translate it into their own API, and [from_env] picks one from the
+//! Function's environment, so switching vendors is a configuration change.
+//!
+//! Any [Chat] model can also answer for a [Rag](crate::rag::Rag).
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::llm::{self, Chat, ChatRequest, Message, Tool, ToolResult};
+//! use serde_json::json;
+//!
+//! fn weather(city: &str) -> String {
+//!     format!("It is sunny in {city}.")
+//! }
+//!
+//! fn ask(question: &str) -> Result<String, Box<dyn std::error::Error>> {
+//!     let model = llm::from_env()?;
+//!     let mut request = ChatRequest::new()
+//!         .system("You are a concise travel assistant.")
+//!         .user(question)
+//!         .tool(Tool::new(
+//!             "weather",
+//!             "The current weather in a city",
+//!             json!({
+//!                 "type": "object",
+//!                 "properties": { "city": { "type": "string" } },
+//!                 "required": ["city"],
+//!             }),
+//!         ));
+//!     let response = model.send(&request)?;
+//!     if response.tool_calls().next().is_none() {
+//!         return Ok(response.text());
+//!     }
+//!     let results: Vec<ToolResult> = response
+//!         .tool_calls()
+//!         .map(|call| {
+//!             let city = call.arguments["city"].as_str().unwrap_or_default();
+//!             ToolResult::new(call, weather(city))
+//!         })
+//!         .collect();
+//!     request = request
+//!         .message(response.message())
+//!         .message(Message::tool_results(results));
+//!     Ok(model.send(&request)?.text())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::config::{Config, ConfigError};
+use crate::encoding::Json;
+use crate::http;
+use crate::rag::ChatMessage;
+pub use crate::rag::{ChatError, OpenAiChat, Role};
+
+/// A conversation to send to a [Chat] model.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatRequest {
+    /// Instructions for the model.
+    pub system: Option<String>,
+    /// The conversation so far, oldest first.
+    pub messages: Vec<Message>,
+    /// The tools the model may call.
+    pub tools: Vec<Tool>,
+    /// How random the reply is. Overrides the model's setting.
+    pub temperature: Option<f32>,
+    /// The longest reply to generate, in tokens. Overrides the model's setting.
+    pub max_tokens: Option<u32>,
+}
+
+impl ChatRequest {
+    /// An empty conversation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the instructions for the model.
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    /// Add a message from the person asking.
+    pub fn user(self, text: impl Into<String>) -> Self {
+        self.message(Message::user(text))
+    }
+
+    /// Add a message from the model.
+    pub fn assistant(self, text: impl Into<String>) -> Self {
+        self.message(Message::assistant(text))
+    }
+
+    /// Add a message.
+    pub fn message(mut self, message: Message) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    /// Offer the model a tool.
+    pub fn tool(mut self, tool: Tool) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// How random the reply is. Providers accept different ranges; 0 is the most focused.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// The longest reply to generate, in tokens.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+}
+
+impl From<&[ChatMessage]> for ChatRequest {
+    fn from(messages: &[ChatMessage]) -> Self {
+        Self {
+            messages: messages
+                .iter()
+                .map(|message| Message {
+                    role: message.role,
+                    content: vec![Content::Text(message.content.clone())],
+                })
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
+/// One message in a [ChatRequest].
+///
+/// [Role::System] messages are added to the request's instructions, since not every provider
+/// accepts them in the conversation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// Who the message is from.
+    pub role: Role,
+    /// What the message says.
+    pub content: Vec<Content>,
+}
+
+impl Message {
+    /// A message from the person asking.
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: vec![Content::Text(text.into())],
+        }
+    }
+
+    /// A message from the model.
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: vec![Content::Text(text.into())],
+        }
+    }
+
+    /// The results of the tool calls in the model's last reply.
+    pub fn tool_results(results: impl IntoIterator<Item = ToolResult>) -> Self {
+        Self {
+            role: Role::User,
+            content: results.into_iter().map(Content::ToolResult).collect(),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                Content::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A part of a [Message].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    /// Text.
+    Text(String),
+    /// The model asked to call a tool.
+    ToolCall(ToolCall),
+    /// The result of calling a tool.
+    ToolResult(ToolResult),
+}
+
+/// A tool the model may call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tool {
+    /// The tool's name.
+    pub name: String,
+    /// What the tool does, for the model to decide when to call it.
+    pub description: String,
+    /// A JSON Schema for the tool's arguments.
+    pub parameters: Value,
+}
+
+impl Tool {
+    /// A tool that takes arguments described by the JSON Schema `parameters`.
+    pub fn new(name: impl Into<String>, description: impl Into<String>, parameters: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+        }
+    }
+}
+
+/// A call the model asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// The call's id, to match its [ToolResult] to it. Gemini does not always assign one, so
+    /// this may be the tool's name.
+    pub id: String,
+    /// The tool to call.
+    pub name: String,
+    /// The arguments to call it with.
+    pub arguments: Value,
+}
+
+/// The result of a [ToolCall].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolResult {
+    /// The id of the call this answers.
+    pub call_id: String,
+    /// The name of the tool that was called.
+    pub name: String,
+    /// What the tool returned.
+    pub content: String,
+    /// Whether the tool failed, and `content` describes why.
+    pub is_error: bool,
+}
+
+impl ToolResult {
+    /// The successful result of `call`.
+    pub fn new(call: &ToolCall, content: impl Into<String>) -> Self {
+        Self {
+            call_id: call.id.clone(),
+            name: call.name.clone(),
+            content: content.into(),
+            is_error: false,
+        }
+    }
+
+    /// `call` failed, because of `message`.
+    pub fn error(call: &ToolCall, message: impl Into<String>) -> Self {
+        Self {
+            is_error: true,
+            ..Self::new(call, message)
+        }
+    }
+}
+
+/// Why the model stopped generating.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// The reply is complete.
+    EndTurn,
+    /// The reply reached the token limit.
+    MaxTokens,
+    /// The model is waiting for the results of its tool calls.
+    ToolUse,
+    /// Any other reason, as the provider named it.
+    Other(String),
+}
+
+/// How many tokens a request used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Tokens in the request.
+    pub input_tokens: u32,
+    /// Tokens in the reply.
+    pub output_tokens: u32,
+}
+
+/// A model's reply to a [ChatRequest].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatResponse {
+    /// The reply's text and tool calls, in order.
+    pub content: Vec<Content>,
+    /// Why the model stopped.
+    pub stop_reason: StopReason,
+    /// How many tokens the request used.
+    pub usage: Usage,
+}
+
+impl ChatResponse {
+    /// The reply's text.
+    pub fn text(&self) -> String {
+        self.message().text()
+    }
+
+    /// The tools the model asked to call.
+    pub fn tool_calls(&self) -> impl Iterator<Item = &ToolCall> {
+        self.content.iter().filter_map(|content| match content {
+            Content::ToolCall(call) => Some(call),
+            _ => None,
+        })
+    }
+
+    /// The reply as a message, to add to the conversation before sending tool results.
+    pub fn message(&self) -> Message {
+        Message {
+            role: Role::Assistant,
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// One event of a streamed reply, from [Chat::collect_stream].
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// More of the reply's text.
+    Text(String),
+    /// The model asked to call a tool. Calls are reported once their arguments are complete.
+    ToolCall(ToolCall),
+    /// The reply is complete. This is always the last event.
+    Done {
+        /// Why the model stopped.
+        stop_reason: StopReason,
+        /// How many tokens the request used.
+        usage: Usage,
+    },
+}
+
+/// A model that replies to a [ChatRequest].
+pub trait Chat {
+    /// Send `request` and wait for the whole reply.
+    fn send(&self, request: &ChatRequest) -> Result<ChatResponse, ChatError>;
+
+    /// Send `request` with the provider's streaming API, and collect the reply's events once
+    /// the whole stream has arrived.
+    ///
+    /// Host HTTP responses arrive whole, so this does not hand you events as they are
+    /// generated. Use it to relay a reply in the provider-independent shape of its stream,
+    /// like to an SSE response.
+    fn collect_stream(&self, request: &ChatRequest) -> Result<Vec<StreamEvent>, ChatError>;
+}
+
+impl<C: Chat + ?Sized> Chat for Box<C> {
+    fn send(&self, request: &ChatRequest) -> Result<ChatResponse, ChatError> {
+        (**self).send(request)
+    }
+
+    fn collect_stream(&self, request: &ChatRequest) -> Result<Vec<StreamEvent>, ChatError> {
+        (**self).collect_stream(request)
+    }
+}
+
+/// Which provider [from_env] uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Provider {
+    Anthropic,
+    Gemini,
+    OpenAi,
+}
+
+#[derive(Deserialize)]
+struct ChatSettings {
+    provider: Provider,
+    model: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ProviderSettings {
+    api_key: String,
+}
+
+/// Create the chat model named by the `LLM_PROVIDER` environment variable: `anthropic`,
+/// `gemini`, or `openai`.
+///
+/// The provider's API key is read from `ANTHROPIC_API_KEY`, `GEMINI_API_KEY`, or
+/// `OPENAI_API_KEY`. `LLM_MODEL`, `LLM_TEMPERATURE`, and `LLM_MAX_TOKENS` optionally override
+/// the provider's defaults.
+pub fn from_env() -> Result<Box<dyn Chat>, ConfigError> {
+    let settings: ChatSettings = Config::with_prefix("LLM_").load()?;
+    let api_key = |prefix: &str| {
+        Config::with_prefix(prefix)
+            .load::<ProviderSettings>()
+            .map(|settings| settings.api_key)
+    };
+    Ok(match settings.provider {
+        Provider::Anthropic => {
+            let mut chat = AnthropicChat::new(api_key("ANTHROPIC_")?);
+            if let Some(model) = settings.model {
+                chat = chat.model(model);
+            }
+            if let Some(temperature) = settings.temperature {
+                chat = chat.temperature(temperature);
+            }
+            if let Some(max_tokens) = settings.max_tokens {
+                chat = chat.max_tokens(max_tokens);
+            }
+            Box::new(chat)
+        }
+        Provider::Gemini => {
+            let mut chat = GeminiChat::new(api_key("GEMINI_")?);
+            if let Some(model) = settings.model {
+                chat = chat.model(model);
+            }
+            if let Some(temperature) = settings.temperature {
+                chat = chat.temperature(temperature);
+            }
+            if let Some(max_tokens) = settings.max_tokens {
+                chat = chat.max_tokens(max_tokens);
+            }
+            Box::new(chat)
+        }
+        Provider::OpenAi => {
+            let mut chat = OpenAiChat::new(api_key("OPENAI_")?);
+            if let Some(model) = settings.model {
+                chat = chat.model(model);
+            }
+            if let Some(temperature) = settings.temperature {
+                chat = chat.temperature(temperature);
+            }
+            if let Some(max_tokens) = settings.max_tokens {
+                chat = chat.max_tokens(max_tokens);
+            }
+            Box::new(chat)
+        }
+    })
+}
+
+/// The Anthropic Messages API.
+///
+/// Defaults to `claude-sonnet-4-5` and replies of up to 1024 tokens, since the API requires
+/// a limit.
+#[derive(Debug, Clone)]
+pub struct AnthropicChat {
+    api_key: String,
+    model: String,
+    temperature: Option<f32>,
+    max_tokens: u32,
+}
+
+impl AnthropicChat {
+    /// Create a chat model that authenticates with `api_key`.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "claude-sonnet-4-5".to_string(),
+            temperature: None,
+            max_tokens: 1024,
+        }
+    }
+
+    /// Create a chat model with the API key from the `ANTHROPIC_API_KEY` environment variable.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("ANTHROPIC_API_KEY").unwrap_or_default())
+    }
+
+    /// Use a different model, like `claude-haiku-4-5`.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// How random the reply is, from 0 to 1. Lower is more focused.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// The longest reply to generate, in tokens.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    fn post(&self, request: &ChatRequest, stream: bool) -> Result<Vec<u8>, ChatError> {
+        post(
+            "https://api.anthropic.com/v1/messages".to_string(),
+            [
+                ("x-api-key".to_string(), self.api_key.clone()),
+                ("anthropic-version".to_string(), "2023-06-01".to_string()),
+            ],
+            anthropic_body(self, request, stream),
+        )
+    }
+}
+
+impl Chat for AnthropicChat {
+    fn send(&self, request: &ChatRequest) -> Result<ChatResponse, ChatError> {
+        anthropic_response(serde_json::from_slice(&self.post(request, false)?)?)
+    }
+
+    fn collect_stream(&self, request: &ChatRequest) -> Result<Vec<StreamEvent>, ChatError> {
+        anthropic_events(&self.post(request, true)?)
+    }
+}
+
+fn anthropic_body(chat: &AnthropicChat, request: &ChatRequest, stream: bool) -> Value {
+    let messages: Vec<Value> = conversation(request)
+        .map(|message| {
+            let content: Vec<Value> = message
+                .content
+                .iter()
+                .map(|content| match content {
+                    Content::Text(text) => json!({ "type": "text", "text": text }),
+                    Content::ToolCall(call) => json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.name,
+                        "input": call.arguments,
+                    }),
+                    Content::ToolResult(result) => json!({
+                        "type": "tool_result",
+                        "tool_use_id": result.call_id,
+                        "content": result.content,
+                        "is_error": result.is_error,
+                    }),
+                })
+                .collect();
+            json!({ "role": role(message.role), "content": content })
+        })
+        .collect();
+    let mut body = json!({
+        "model": chat.model,
+        "max_tokens": request.max_tokens.unwrap_or(chat.max_tokens),
+        "messages": messages,
+    });
+    if let Some(system) = system_text(request) {
+        body["system"] = system.into();
+    }
+    if let Some(temperature) = request.temperature.or(chat.temperature) {
+        body["temperature"] = temperature.into();
+    }
+    if !request.tools.is_empty() {
+        let tools: Vec<Value> = request
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "input_schema": tool.parameters,
+                })
+            })
+            .collect();
+        body["tools"] = tools.into();
+    }
+    if stream {
+        body["stream"] = true.into();
+    }
+    body
+}
+
+#[derive(Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicBlock>,
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: AnthropicUsage,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Default)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+fn anthropic_response(response: AnthropicResponse) -> Result<ChatResponse, ChatError> {
+    let content = response
+        .content
+        .into_iter()
+        .filter_map(|block| match block {
+            AnthropicBlock::Text { text } => Some(Content::Text(text)),
+            AnthropicBlock::ToolUse { id, name, input } => Some(Content::ToolCall(ToolCall {
+                id,
+                name,
+                arguments: input,
+            })),
+            AnthropicBlock::Other => None,
+        })
+        .collect();
+    Ok(ChatResponse {
+        content,
+        stop_reason: anthropic_stop_reason(response.stop_reason.as_deref()),
+        usage: Usage {
+            input_tokens: response.usage.input_tokens,
+            output_tokens: response.usage.output_tokens,
+        },
+    })
+}
+
+fn anthropic_stop_reason(reason: Option<&str>) -> StopReason {
+    match reason {
+        None | Some("end_turn") | Some("stop_sequence") => StopReason::EndTurn,
+        Some("max_tokens") => StopReason::MaxTokens,
+        Some("tool_use") => StopReason::ToolUse,
+        Some(other) => StopReason::Other(other.to_string()),
+    }
+}
+
+fn anthropic_events(body: &[u8]) -> Result<Vec<StreamEvent>, ChatError> {
+    let mut events = Vec::new();
+    // Tool calls in progress, by content block index: id, name, and argument JSON so far.
+    let mut calls: BTreeMap<u64, (String, String, String)> = BTreeMap::new();
+    let mut usage = Usage::default();
+    let mut stop_reason = None;
+    for data in sse_data(body) {
+        let event: Value = serde_json::from_str(&data)?;
+        let index = event["index"].as_u64().unwrap_or_default();
+        match event["type"].as_str() {
+            Some("message_start") => {
+                usage.input_tokens = token_count(&event["message"]["usage"]["input_tokens"]);
+            }
+            Some("content_block_start") if event["content_block"]["type"] == "tool_use" => {
+                let block = &event["content_block"];
+                calls.insert(
+                    index,
+                    (
+                        block["id"].as_str().unwrap_or_default().to_string(),
+                        block["name"].as_str().unwrap_or_default().to_string(),
+                        String::new(),
+                    ),
+                );
+            }
+            Some("content_block_delta") => match event["delta"]["type"].as_str() {
+                Some("text_delta") => {
+                    let text = event["delta"]["text"].as_str().unwrap_or_default();
+                    events.push(StreamEvent::Text(text.to_string()));
+                }
+                Some("input_json_delta") => {
+                    if let Some((_, _, arguments)) = calls.get_mut(&index) {
+                        arguments
+                            .push_str(event["delta"]["partial_json"].as_str().unwrap_or_default());
+                    }
+                }
+                _ => {}
+            },
+            Some("content_block_stop") => {
+                if let Some((id, name, arguments)) = calls.remove(&index) {
+                    events.push(StreamEvent::ToolCall(ToolCall {
+                        id,
+                        name,
+                        arguments: parse_arguments(&arguments)?,
+                    }));
+                }
+            }
+            Some("message_delta") => {
+                stop_reason = event["delta"]["stop_reason"].as_str().map(str::to_string);
+                usage.output_tokens = token_count(&event["usage"]["output_tokens"]);
+            }
+            Some("error") => {
+                return Err(ChatError::Stream {
+                    message: event["error"]["message"]
+                        .as_str()
+                        .unwrap_or("unknown error")
+                        .to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+    events.push(StreamEvent::Done {
+        stop_reason: anthropic_stop_reason(stop_reason.as_deref()),
+        usage,
+    });
+    Ok(events)
+}
+
+/// The Gemini API's `generateContent`.
+///
+/// Defaults to `gemini-2.5-flash`.
+#[derive(Debug, Clone)]
+pub struct GeminiChat {
+    api_key: String,
+    model: String,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+}
+
+impl GeminiChat {
+    /// Create a chat model that authenticates with `api_key`.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "gemini-2.5-flash".to_string(),
+            temperature: None,
+            max_tokens: None,
+        }
+    }
+
+    /// Create a chat model with the API key from the `GEMINI_API_KEY` environment variable.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("GEMINI_API_KEY").unwrap_or_default())
+    }
+
+    /// Use a different model, like `gemini-2.5-pro`.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// How random the reply is, from 0 to 2. Lower is more focused.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// The longest reply to generate, in tokens.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    fn post(&self, request: &ChatRequest, method: &str) -> Result<Vec<u8>, ChatError> {
+        post(
+            format!(
+                "https://generativelanguage.googleapis.com/v1beta/models/{}:{method}",
+                http::encode_component(&self.model)
+            ),
+            [("x-goog-api-key".to_string(), self.api_key.clone())],
+            gemini_body(self, request),
+        )
+    }
+}
+
+impl Chat for GeminiChat {
+    fn send(&self, request: &ChatRequest) -> Result<ChatResponse, ChatError> {
+        let response = serde_json::from_slice(&self.post(request, "generateContent")?)?;
+        let (content, finish_reason, usage) = gemini_parts(response)?;
+        Ok(ChatResponse {
+            stop_reason: gemini_stop_reason(finish_reason.as_deref(), &content),
+            content,
+            usage,
+        })
+    }
+
+    fn collect_stream(&self, request: &ChatRequest) -> Result<Vec<StreamEvent>, ChatError> {
+        gemini_events(&self.post(request, "streamGenerateContent?alt=sse")?)
+    }
+}
+
+fn gemini_body(chat: &GeminiChat, request: &ChatRequest) -> Value {
+    let contents: Vec<Value> = conversation(request)
+        .map(|message| {
+            let parts: Vec<Value> = message
+                .content
+                .iter()
+                .map(|content| match content {
+                    Content::Text(text) => json!({ "text": text }),
+                    Content::ToolCall(call) => json!({
+                        "functionCall": { "name": call.name, "args": call.arguments },
+                    }),
+                    Content::ToolResult(result) => json!({
+                        "functionResponse": {
+                            "name": result.name,
+                            "response": {
+                                (if result.is_error { "error" } else { "output" }): result.content,
+                            },
+                        },
+                    }),
+                })
+                .collect();
+            let role = match message.role {
+                Role::Assistant => "model",
+                _ => "user",
+            };
+            json!({ "role": role, "parts": parts })
+        })
+        .collect();
+    let mut body = json!({ "contents": contents });
+    if let Some(system) = system_text(request) {
+        body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+    }
+    if !request.tools.is_empty() {
+        let declarations: Vec<Value> = request
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                })
+            })
+            .collect();
+        body["tools"] = json!([{ "functionDeclarations": declarations }]);
+    }
+    let mut generation = serde_json::Map::new();
+    if let Some(temperature) = request.temperature.or(chat.temperature) {
+        generation.insert("temperature".to_string(), temperature.into());
+    }
+    if let Some(max_tokens) = request.max_tokens.or(chat.max_tokens) {
+        generation.insert("maxOutputTokens".to_string(), max_tokens.into());
+    }
+    if !generation.is_empty() {
+        body["generationConfig"] = Value::Object(generation);
+    }
+    body
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPart {
+    text: Option<String>,
+    function_call: Option<GeminiFunctionCall>,
+}
+
+#[derive(Deserialize)]
+struct GeminiFunctionCall {
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    args: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+}
+
+/// The first candidate's content, finish reason, and the response's usage.
+fn gemini_parts(
+    response: GeminiResponse,
+) -> Result<(Vec<Content>, Option<String>, Usage), ChatError> {
+    let usage = response
+        .usage_metadata
+        .map(|usage| Usage {
+            input_tokens: usage.prompt_token_count,
+            output_tokens: usage.candidates_token_count,
+        })
+        .unwrap_or_default();
+    let Some(candidate) = response.candidates.into_iter().next() else {
+        return Ok((Vec::new(), None, usage));
+    };
+    let content = candidate
+        .content
+        .map(|content| content.parts)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|part| match (part.text, part.function_call) {
+            (_, Some(call)) => Some(Content::ToolCall(ToolCall {
+                id: call.id.unwrap_or_else(|| call.name.clone()),
+                name: call.name,
+                arguments: call.args,
+            })),
+            (Some(text), None) => Some(Content::Text(text)),
+            (None, None) => None,
+        })
+        .collect();
+    Ok((content, candidate.finish_reason, usage))
+}
+
+/// Gemini finishes with `STOP` whether or not it called a tool.
+fn gemini_stop_reason(reason: Option<&str>, content: &[Content]) -> StopReason {
+    if content
+        .iter()
+        .any(|content| matches!(content, Content::ToolCall(_)))
+    {
+        return StopReason::ToolUse;
+    }
+    match reason {
+        None | Some("STOP") => StopReason::EndTurn,
+        Some("MAX_TOKENS") => StopReason::MaxTokens,
+        Some(other) => StopReason::Other(other.to_string()),
+    }
+}
+
+fn gemini_events(body: &[u8]) -> Result<Vec<StreamEvent>, ChatError> {
+    let mut events = Vec::new();
+    let mut called = Vec::new();
+    let mut finish_reason = None;
+    let mut usage = Usage::default();
+    for data in sse_data(body) {
+        let (content, reason, chunk_usage) = gemini_parts(serde_json::from_str(&data)?)?;
+        for content in content {
+            match content {
+                Content::Text(text) => events.push(StreamEvent::Text(text)),
+                Content::ToolCall(call) => {
+                    called.push(Content::ToolCall(call.clone()));
+                    events.push(StreamEvent::ToolCall(call));
+                }
+                Content::ToolResult(_) => {}
+            }
+        }
+        if reason.is_some() {
+            finish_reason = reason;
+        }
+        if chunk_usage != Usage::default() {
+            usage = chunk_usage;
+        }
+    }
+    events.push(StreamEvent::Done {
+        stop_reason: gemini_stop_reason(finish_reason.as_deref(), &called),
+        usage,
+    });
+    Ok(events)
+}
+
+impl Chat for OpenAiChat {
+    fn send(&self, request: &ChatRequest) -> Result<ChatResponse, ChatError> {
+        let response: Value = serde_json::from_slice(&self.post_request(request, false)?)?;
+        let choice = &response["choices"][0];
+        if choice.is_null() {
+            return Err(ChatError::Empty);
+        }
+        let mut content = Vec::new();
+        if let Some(text) = choice["message"]["content"].as_str() {
+            content.push(Content::Text(text.to_string()));
+        }
+        for call in choice["message"]["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            content.push(Content::ToolCall(ToolCall {
+                id: call["id"].as_str().unwrap_or_default().to_string(),
+                name: call["function"]["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                arguments: parse_arguments(
+                    call["function"]["arguments"].as_str().unwrap_or_default(),
+                )?,
+            }));
+        }
+        Ok(ChatResponse {
+            content,
+            stop_reason: openai_stop_reason(choice["finish_reason"].as_str()),
+            usage: openai_usage(&response["usage"]),
+        })
+    }
+
+    fn collect_stream(&self, request: &ChatRequest) -> Result<Vec<StreamEvent>, ChatError> {
+        openai_events(&self.post_request(request, true)?)
+    }
+}
+
+impl OpenAiChat {
+    fn post_request(&self, request: &ChatRequest, stream: bool) -> Result<Vec<u8>, ChatError> {
+        post(
+            "https://api.openai.com/v1/chat/completions".to_string(),
+            [(
+                "authorization".to_string(),
+                format!("Bearer {}", self.api_key),
+            )],
+            openai_body(self, request, stream),
+        )
+    }
+}
+
+fn openai_body(chat: &OpenAiChat, request: &ChatRequest, stream: bool) -> Value {
+    let mut messages = Vec::new();
+    if let Some(system) = system_text(request) {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    for message in conversation(request) {
+        let mut calls = Vec::new();
+        for content in &message.content {
+            match content {
+                Content::ToolCall(call) => calls.push(json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": call.arguments.to_string() },
+                })),
+                // Each tool result is a message of its own.
+                Content::ToolResult(result) => messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": result.call_id,
+                    "content": result.content,
+                })),
+                Content::Text(_) => {}
+            }
+        }
+        let text = message.text();
+        if text.is_empty() && calls.is_empty() {
+            continue;
+        }
+        let mut converted = json!({ "role": role(message.role), "content": text });
+        if !calls.is_empty() {
+            converted["tool_calls"] = calls.into();
+        }
+        messages.push(converted);
+    }
+    let mut body = json!({ "model": chat.model, "messages": messages });
+    if let Some(temperature) = request.temperature.or(chat.temperature) {
+        body["temperature"] = temperature.into();
+    }
+    if let Some(max_tokens) = request.max_tokens.or(chat.max_tokens) {
+        body["max_tokens"] = max_tokens.into();
+    }
+    if !request.tools.is_empty() {
+        let tools: Vec<Value> = request
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": tool.parameters,
+                    },
+                })
+            })
+            .collect();
+        body["tools"] = tools.into();
+    }
+    if stream {
+        body["stream"] = true.into();
+        body["stream_options"] = json!({ "include_usage": true });
+    }
+    body
+}
+
+fn openai_stop_reason(reason: Option<&str>) -> StopReason {
+    match reason {
+        None | Some("stop") => StopReason::EndTurn,
+        Some("length") => StopReason::MaxTokens,
+        Some("tool_calls") => StopReason::ToolUse,
+        Some(other) => StopReason::Other(other.to_string()),
+    }
+}
+
+fn openai_usage(usage: &Value) -> Usage {
+    Usage {
+        input_tokens: token_count(&usage["prompt_tokens"]),
+        output_tokens: token_count(&usage["completion_tokens"]),
+    }
+}
+
+fn openai_events(body: &[u8]) -> Result<Vec<StreamEvent>, ChatError> {
+    let mut events = Vec::new();
+    // Tool calls in progress, by index: id, name, and argument JSON so far.
+    let mut calls: BTreeMap<u64, (String, String, String)> = BTreeMap::new();
+    let mut finish_reason = None;
+    let mut usage = Usage::default();
+    for data in sse_data(body) {
+        if data == "[DONE]" {
+            break;
+        }
+        let chunk: Value = serde_json::from_str(&data)?;
+        if !chunk["usage"].is_null() {
+            usage = openai_usage(&chunk["usage"]);
+        }
+        let choice = &chunk["choices"][0];
+        if let Some(text) = choice["delta"]["content"].as_str() {
+            events.push(StreamEvent::Text(text.to_string()));
+        }
+        for call in choice["delta"]["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            let (id, name, arguments) = calls
+                .entry(call["index"].as_u64().unwrap_or_default())
+                .or_default();
+            if let Some(call_id) = call["id"].as_str() {
+                *id = call_id.to_string();
+            }
+            if let Some(call_name) = call["function"]["name"].as_str() {
+                name.push_str(call_name);
+            }
+            arguments.push_str(call["function"]["arguments"].as_str().unwrap_or_default());
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            finish_reason = Some(reason.to_string());
+        }
+    }
+    for (id, name, arguments) in calls.into_values() {
+        events.push(StreamEvent::ToolCall(ToolCall {
+            id,
+            name,
+            arguments: parse_arguments(&arguments)?,
+        }));
+    }
+    events.push(StreamEvent::Done {
+        stop_reason: openai_stop_reason(finish_reason.as_deref()),
+        usage,
+    });
+    Ok(events)
+}
+
+/// The request's instructions, with any system messages in the conversation appended.
+fn system_text(request: &ChatRequest) -> Option<String> {
+    let parts: Vec<String> = request
+        .system
+        .iter()
+        .cloned()
+        .chain(
+            request
+                .messages
+                .iter()
+                .filter(|message| message.role == Role::System)
+                .map(Message::text),
+        )
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("\n\n"))
+}
+
+/// The request's messages, without system messages.
+fn conversation(request: &ChatRequest) -> impl Iterator<Item = &Message> {
+    request
+        .messages
+        .iter()
+        .filter(|message| message.role != Role::System)
+}
+
+fn role(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+fn token_count(count: &Value) -> u32 {
+    count
+        .as_u64()
+        .unwrap_or_default()
+        .try_into()
+        .unwrap_or(u32::MAX)
+}
+
+/// Tool arguments arrive as JSON text; a call with no arguments may send none at all.
+fn parse_arguments(arguments: &str) -> Result<Value, ChatError> {
+    if arguments.trim().is_empty() {
+        return Ok(json!({}));
+    }
+    Ok(serde_json::from_str(arguments)?)
+}
+
+/// The `data` of each event in a server-sent events body.
+fn sse_data(body: &[u8]) -> Vec<String> {
+    let mut events = Vec::new();
+    let mut data: Option<String> = None;
+    for line in String::from_utf8_lossy(body).lines() {
+        if line.is_empty() {
+            events.extend(data.take());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match &mut data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => data = Some(value.to_string()),
+            }
+        }
+    }
+    events.extend(data);
+    events
+}
+
+/// POST a JSON body, returning the response body if the status is 2xx.
+fn post(
+    url: String,
+    headers: impl IntoIterator<Item = (String, String)>,
+    body: Value,
+) -> Result<Vec<u8>, ChatError> {
+    let response = http::post(
+        url,
+        headers
+            .into_iter()
+            .chain([("content-type".to_string(), "application/json".to_string())]),
+        Json(body),
+    )?;
+    if !(200..300).contains(&response.status) {
+        return Err(ChatError::Rejected {
+            status: response.status,
+            message: String::from_utf8_lossy(&response.body).into_owned(),
+        });
+    }
+    Ok(response.body)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn tool_conversation() -> ChatRequest {
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            name: "weather".to_string(),
+            arguments: json!({ "city": "Paris" }),
+        };
+        ChatRequest::new()
+            .system("be brief")
+            .user("weather in Paris?")
+            .message(Message {
+                role: Role::Assistant,
+                content: vec![Content::ToolCall(call.clone())],
+            })
+            .message(Message::tool_results([ToolResult::new(&call, "sunny")]))
+            .tool(Tool::new(
+                "weather",
+                "the weather",
+                json!({ "type": "object" }),
+            ))
+    }
+
+    #[test]
+    fn requests_translate_per_provider() {
+        let request = tool_conversation();
+
+        let anthropic = anthropic_body(&AnthropicChat::new("key"), &request, true);
+        assert_eq!(json!("be brief"), anthropic["system"]);
+        assert_eq!(json!(1024), anthropic["max_tokens"]);
+        assert_eq!(
+            json!("tool_use"),
+            anthropic["messages"][1]["content"][0]["type"]
+        );
+        assert_eq!(
+            json!({ "type": "tool_result", "tool_use_id": "call_1", "content": "sunny", "is_error": false }),
+            anthropic["messages"][2]["content"][0]
+        );
+        assert_eq!(json!("weather"), anthropic["tools"][0]["name"]);
+
+        let gemini = gemini_body(&GeminiChat::new("key"), &request.clone().max_tokens(50));
+        assert_eq!(json!("model"), gemini["contents"][1]["role"]);
+        assert_eq!(
+            json!({ "name": "weather", "response": { "output": "sunny" } }),
+            gemini["contents"][2]["parts"][0]["functionResponse"]
+        );
+        assert_eq!(json!(50), gemini["generationConfig"]["maxOutputTokens"]);
+        assert_eq!(
+            json!("weather"),
+            gemini["tools"][0]["functionDeclarations"][0]["name"]
+        );
+
+        let openai = openai_body(&OpenAiChat::new("key"), &request, false);
+        let roles: Vec<&str> = openai["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(vec!["system", "user", "assistant", "tool"], roles);
+        assert_eq!(
+            json!(r#"{"city":"Paris"}"#),
+            openai["messages"][2]["tool_calls"][0]["function"]["arguments"]
+        );
+    }
+
+    #[test]
+    fn anthropic_streams_assemble_tool_calls() {
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Let me check.\"}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"weather\",\"input\":{}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"city\\\":\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"Paris\\\"}\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":30}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        assert_eq!(
+            vec![
+                StreamEvent::Text("Let me check.".to_string()),
+                StreamEvent::ToolCall(ToolCall {
+                    id: "toolu_1".to_string(),
+                    name: "weather".to_string(),
+                    arguments: json!({ "city": "Paris" }),
+                }),
+                StreamEvent::Done {
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage {
+                        input_tokens: 12,
+                        output_tokens: 30,
+                    },
+                },
+            ],
+            anthropic_events(body.as_bytes()).unwrap()
+        );
+
+        let overloaded = "data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+        assert!(matches!(
+            anthropic_events(overloaded.as_bytes()),
+            Err(ChatError::Stream { .. })
+        ));
+    }
+
+    #[test]
+    fn gemini_and_openai_streams_end_with_done() {
+        let gemini = concat!(
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Hel\"}]}}]}\r\n\r\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"functionCall\":{\"name\":\"weather\",\"args\":{\"city\":\"Paris\"}}}]},\"finishReason\":\"STOP\"}],",
+            "\"usageMetadata\":{\"promptTokenCount\":4,\"candidatesTokenCount\":6}}\r\n\r\n",
+        );
+        let events = gemini_events(gemini.as_bytes()).unwrap();
+        assert_eq!(StreamEvent::Text("Hel".to_string()), events[0]);
+        assert!(matches!(&events[1], StreamEvent::ToolCall(call) if call.id == "weather"));
+        assert_eq!(
+            StreamEvent::Done {
+                stop_reason: StopReason::ToolUse,
+                usage: Usage {
+                    input_tokens: 4,
+                    output_tokens: 6,
+                },
+            },
+            events[2]
+        );
+
+        let openai = concat!(
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"weather\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":5}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let events = openai_events(openai.as_bytes()).unwrap();
+        assert_eq!(
+            vec![
+                StreamEvent::ToolCall(ToolCall {
+                    id: "call_1".to_string(),
+                    name: "weather".to_string(),
+                    arguments: json!({}),
+                }),
+                StreamEvent::Done {
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage {
+                        input_tokens: 3,
+                        output_tokens: 5,
+                    },
+                },
+            ],
+            events
+        );
+    }
+}
@@ -4,6 +4,7 @@ use momento_functions_wit::host::momento::host::logging;
 use thiserror::Error;
 
 /// Where do you want your logs to go?
+#[derive(Clone, Debug)]
 pub enum LogDestination {
     /// Momento topic within the same cache as your function
     Topic {
@@ -37,6 +38,7 @@ impl LogDestination {
 }
 
 /// A single configuration for a destination
+#[derive(Clone, Debug)]
 pub struct LogConfiguration {
     /// At what level would you like your function's logs to be filtered into this destination?
     log_level: log::LevelFilter,
@@ -0,0 +1,385 @@
+//! Content types: guessing them, and reading and writing their parameters
+//!
+//! - [sniff] recognizes common file formats from their first bytes.
+//! - [from_extension] and [from_path] map file extensions to MIME types.
+//! - [guess] combines them, for naming the type of an upload or an S3 object.
+//! - [ContentType] parses and formats `content-type` values with parameters, like
+//!   `text/html; charset=utf-8` or `multipart/form-data; boundary="x"`.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::mime::{self, ContentType};
+//!
+//! let upload = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+//! assert_eq!("image/png", mime::guess("avatar", &upload));
+//! assert_eq!("text/css", mime::guess("site.css", b"body {}"));
+//!
+//! let content_type: ContentType = "multipart/form-data; boundary=\"abc 123\"".parse()?;
+//! assert_eq!(Some("abc 123"), content_type.boundary());
+//! # Ok::<(), mime::MimeError>(())
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The type of bytes with no better guess.
+pub const APPLICATION_OCTET_STREAM: &str = "application/octet-stream";
+
+/// File signatures recognized by [sniff], as (offset, bytes, MIME type).
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (0, b"BM", "image/bmp"),
+    (0, b"\x00\x00\x01\x00", "image/x-icon"),
+    (4, b"ftypavif", "image/avif"),
+    (4, b"ftypisom", "video/mp4"),
+    (4, b"ftypmp42", "video/mp4"),
+    (0, b"\x1a\x45\xdf\xa3", "video/webm"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (0, b"\x00asm", "application/wasm"),
+    (0, b"wOFF", "font/woff"),
+    (0, b"wOF2", "font/woff2"),
+];
+
+/// Extensions recognized by [from_extension].
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("md", "text/markdown"),
+    ("mjs", "text/javascript"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("ogg", "audio/ogg"),
+    ("otf", "font/otf"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain"),
+    ("wasm", "application/wasm"),
+    ("webm", "video/webm"),
+    ("webmanifest", "application/manifest+json"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xml", "application/xml"),
+    ("zip", "application/zip"),
+    ("zst", "application/zstd"),
+];
+
+/// The MIME type of `bytes`, from the signature at their start, if it is a known format.
+///
+/// Text formats are recognized only when they announce themselves: HTML that starts with a
+/// doctype or `<html>`, and SVG and XML documents.
+pub fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    let signature = SIGNATURES.iter().find(|(offset, signature, _)| {
+        bytes
+            .get(*offset..offset + signature.len())
+            .is_some_and(|start| start == *signature)
+    });
+    if let Some((_, _, mime)) = signature {
+        return Some(mime);
+    }
+    let start = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    let start = &start[start.iter().take_while(|b| b.is_ascii_whitespace()).count()..];
+    let starts_with = |prefix: &[u8]| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(prefix))
+    };
+    if starts_with(b"<!doctype html") || starts_with(b"<html") {
+        Some("text/html")
+    } else if starts_with(b"<svg") {
+        Some("image/svg+xml")
+    } else if starts_with(b"<?xml") {
+        // An XML declaration followed by an svg root is still an image.
+        let head = &start[..start.len().min(512)];
+        if head.windows(4).any(|w| w.eq_ignore_ascii_case(b"<svg")) {
+            Some("image/svg+xml")
+        } else {
+            Some("application/xml")
+        }
+    } else {
+        None
+    }
+}
+
+/// The MIME type for a file extension, without the dot, ignoring case.
+pub fn from_extension(extension: &str) -> Option<&'static str> {
+    let extension = extension.to_ascii_lowercase();
+    EXTENSIONS
+        .binary_search_by(|(known, _)| known.cmp(&extension.as_str()))
+        .ok()
+        .map(|i| EXTENSIONS[i].1)
+}
+
+/// The MIME type for the extension of a path, URL path, or S3 key.
+pub fn from_path(path: &str) -> Option<&'static str> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let (_, extension) = name.rsplit_once('.')?;
+    from_extension(extension)
+}
+
+/// The MIME type for content named `path`: from its extension, then from its bytes, then
+/// [APPLICATION_OCTET_STREAM].
+pub fn guess(path: &str, bytes: &[u8]) -> &'static str {
+    from_path(path)
+        .or_else(|| sniff(bytes))
+        .unwrap_or(APPLICATION_OCTET_STREAM)
+}
+
+/// A `content-type` value could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid content type: {value}")]
+pub struct MimeError {
+    /// The value that could not be parsed.
+    pub value: String,
+}
+
+/// A parsed `content-type` value: a MIME type and its parameters.
+///
+/// The type and parameter names are lowercased. Parameter values keep their case, with any
+/// quoting removed; [Display] quotes them again where needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    essence: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl ContentType {
+    /// A content type with no parameters, like `application/json`.
+    pub fn new(essence: &str) -> Result<Self, MimeError> {
+        let invalid = || MimeError {
+            value: essence.to_string(),
+        };
+        let (kind, subtype) = essence.trim().split_once('/').ok_or_else(invalid)?;
+        if !is_token(kind) || !is_token(subtype) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            essence: format!("{kind}/{subtype}").to_ascii_lowercase(),
+            parameters: Vec::new(),
+        })
+    }
+
+    /// Set a parameter, replacing any with the same name.
+    pub fn with_parameter(mut self, name: &str, value: impl Into<String>) -> Self {
+        let name = name.to_ascii_lowercase();
+        self.parameters.retain(|(existing, _)| *existing != name);
+        self.parameters.push((name, value.into()));
+        self
+    }
+
+    /// The MIME type without parameters, like `text/html`.
+    pub fn essence(&self) -> &str {
+        &self.essence
+    }
+
+    /// The part before the `/`, like `text`.
+    pub fn kind(&self) -> &str {
+        self.essence.split('/').next().unwrap_or_default()
+    }
+
+    /// The part after the `/`, like `html`.
+    pub fn subtype(&self) -> &str {
+        self.essence.split('/').nth(1).unwrap_or_default()
+    }
+
+    /// The value of a parameter, ignoring the case of its name.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The parameters, in order.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.parameters
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// The `charset` parameter.
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter("charset")
+    }
+
+    /// The `boundary` parameter of a multipart type.
+    pub fn boundary(&self) -> Option<&str> {
+        self.parameter("boundary")
+    }
+
+    /// Whether this is a `text/*` type, or another type that is text, like JSON or XML.
+    pub fn is_text(&self) -> bool {
+        let subtype = self.subtype();
+        self.kind() == "text"
+            || matches!(subtype, "json" | "xml" | "javascript")
+            || subtype.ends_with("+json")
+            || subtype.ends_with("+xml")
+    }
+}
+
+impl FromStr for ContentType {
+    type Err = MimeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || MimeError {
+            value: value.to_string(),
+        };
+        let (essence, mut rest) = match value.find(';') {
+            Some(i) => (&value[..i], &value[i + 1..]),
+            None => (value, ""),
+        };
+        let mut content_type = Self::new(essence).map_err(|_| invalid())?;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ';']);
+            if rest.is_empty() {
+                return Ok(content_type);
+            }
+            let (name, after_name) = rest.split_once('=').ok_or_else(invalid)?;
+            let name = name.trim();
+            if !is_token(name) {
+                return Err(invalid());
+            }
+            let after_name = after_name.trim_start();
+            let (parameter_value, after_value) = if let Some(quoted) = after_name.strip_prefix('"')
+            {
+                unquote(quoted).ok_or_else(invalid)?
+            } else {
+                let end = after_name.find(';').unwrap_or(after_name.len());
+                (after_name[..end].trim_end().to_string(), &after_name[end..])
+            };
+            // The first occurrence of a parameter wins.
+            if content_type.parameter(name).is_none() {
+                content_type = content_type.with_parameter(name, parameter_value);
+            }
+            rest = after_value;
+        }
+    }
+}
+
+impl Display for ContentType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.essence)?;
+        for (name, value) in &self.parameters {
+            if is_token(value) {
+                write!(f, "; {name}={value}")?;
+            } else {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "; {name}=\"{escaped}\"")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read a quoted string whose opening quote was already consumed, returning its value and
+/// what follows the closing quote.
+fn unquote(quoted: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &quoted[i + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Whether `value` is an RFC 9110 token, which needs no quoting.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_signatures_and_announced_text() {
+        assert_eq!(Some("image/png"), sniff(b"\x89PNG\r\n\x1a\n...."));
+        assert_eq!(Some("image/webp"), sniff(b"RIFF\x10\0\0\0WEBPVP8 "));
+        assert_eq!(Some("application/pdf"), sniff(b"%PDF-1.7"));
+        assert_eq!(
+            Some("text/html"),
+            sniff(b"\xef\xbb\xbf  <!DOCTYPE HTML><p>")
+        );
+        assert_eq!(
+            Some("image/svg+xml"),
+            sniff(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>")
+        );
+        assert_eq!(
+            Some("application/xml"),
+            sniff(b"<?xml version=\"1.0\"?><feed/>")
+        );
+        assert_eq!(None, sniff(b"{\"json\": true}"));
+        assert_eq!(None, sniff(b""));
+    }
+
+    #[test]
+    fn maps_extensions() {
+        assert!(EXTENSIONS.windows(2).all(|w| w[0].0 < w[1].0), "sorted");
+        assert_eq!(Some("image/jpeg"), from_extension("JPG"));
+        assert_eq!(
+            Some("text/javascript"),
+            from_path("/assets/app.min.js?v=3#top")
+        );
+        assert_eq!(None, from_path("releases.d/README"));
+        assert_eq!("image/gif", guess("uploads/1234", b"GIF89a...."));
+        assert_eq!(APPLICATION_OCTET_STREAM, guess("blob", b"\x01\x02"));
+    }
+
+    #[test]
+    fn parses_and_formats_parameters() {
+        let content_type: ContentType =
+            "Multipart/Form-Data; Boundary=\"a \\\"b\\\" c\"; charset=UTF-8; charset=ascii"
+                .parse()
+                .unwrap();
+        assert_eq!("multipart/form-data", content_type.essence());
+        assert_eq!(Some("a \"b\" c"), content_type.boundary());
+        assert_eq!(Some("UTF-8"), content_type.charset());
+        assert_eq!(
+            "multipart/form-data; boundary=\"a \\\"b\\\" c\"; charset=UTF-8",
+            content_type.to_string()
+        );
+
+        let json = ContentType::new("application/problem+json")
+            .unwrap()
+            .with_parameter("charset", "utf-8");
+        assert!(json.is_text());
+        assert_eq!("application/problem+json; charset=utf-8", json.to_string());
+
+        assert!("text".parse::<ContentType>().is_err());
+        assert!("text/html; charset".parse::<ContentType>().is_err());
+        assert!("text/html; charset=\"open".parse::<ContentType>().is_err());
+        assert!("text/ht ml".parse::<ContentType>().is_err());
+    }
+}
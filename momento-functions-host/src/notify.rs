@@ -0,0 +1,472 @@
+//! Notifications to Slack, Discord, and GitHub
+//!
+//! Thin clients for the messages ops-automation Functions send most: a Slack message through
+//! an incoming webhook or the Web API, a Discord webhook, and a comment on a GitHub issue or
+//! pull request. Each takes typed payloads, sets the service's auth headers, and reports
+//! failures as a [NotifyError], whose [HostError](crate::HostError) kind says whether to
+//! retry, like when a service is rate limiting you.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::notify::{DiscordMessage, DiscordWebhook, SlackMessage, SlackWebhook};
+//!
+//! let alert = "Cache hit rate dropped below 80%";
+//! if let Err(e) = SlackWebhook::new(std::env::var("SLACK_WEBHOOK_URL").unwrap_or_default())
+//!     .send(&SlackMessage::new(alert))
+//! {
+//!     eprintln!("failed to notify slack: {e}");
+//! }
+//! if let Err(e) = DiscordWebhook::new(std::env::var("DISCORD_WEBHOOK_URL").unwrap_or_default())
+//!     .send(&DiscordMessage::new(alert).username("cache-monitor"))
+//! {
+//!     eprintln!("failed to notify discord: {e}");
+//! }
+//! ```
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::encoding::Json;
+use crate::http::{self, HttpPostError};
+
+const USER_AGENT: &str = concat!("momento-functions-host/", env!("CARGO_PKG_VERSION"));
+
+/// An error occurred while sending a notification.
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    /// The request to the service failed.
+    #[error(transparent)]
+    Http(#[from] HttpPostError<serde_json::Error>),
+    /// The service responded with an error status.
+    #[error("Service returned status {status}: {message}")]
+    Rejected {
+        /// The HTTP status the service responded with.
+        status: u16,
+        /// The body of the service's response.
+        message: String,
+        /// How long the service asked you to wait before trying again, when rate limited.
+        retry_after: Option<Duration>,
+    },
+    /// The Slack Web API responded with `ok: false`.
+    #[error("Slack returned error: {error}")]
+    Slack {
+        /// Slack's error code, like `channel_not_found`.
+        error: String,
+    },
+    /// The service's response could not be decoded.
+    #[error("Failed to decode response.")]
+    Decode {
+        /// The underlying decoding error.
+        #[from]
+        cause: serde_json::Error,
+    },
+}
+
+/// A Slack message. Only `text` is required; it is also the notification text when you
+/// send `blocks`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SlackMessage {
+    text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    blocks: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_ts: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unfurl_links: Option<bool>,
+}
+
+impl SlackMessage {
+    /// A message with this text, in Slack's `mrkdwn` format.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a Block Kit block, like a section or an actions block.
+    pub fn block(mut self, block: serde_json::Value) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    /// Reply in the thread of the message with this `ts`.
+    pub fn in_thread(mut self, thread_ts: impl Into<String>) -> Self {
+        self.thread_ts = Some(thread_ts.into());
+        self
+    }
+
+    /// Whether Slack should show previews of links in the message.
+    pub fn unfurl_links(mut self, unfurl_links: bool) -> Self {
+        self.unfurl_links = Some(unfurl_links);
+        self
+    }
+}
+
+/// A Slack incoming webhook, which posts to the channel it was created for.
+#[derive(Debug, Clone)]
+pub struct SlackWebhook {
+    url: String,
+}
+
+impl SlackWebhook {
+    /// A webhook with its `https://hooks.slack.com/services/...` url. The url is the secret,
+    /// so keep it out of your code.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    /// Post a message.
+    pub fn send(&self, message: &SlackMessage) -> Result<(), NotifyError> {
+        let response = http::post(&self.url, json_headers([]), Json(message))?;
+        check_status(response).map(drop)
+    }
+}
+
+/// A Slack Web API client, for posting to any channel the bot is in and replying in threads.
+#[derive(Debug, Clone)]
+pub struct SlackClient {
+    token: String,
+    api_url: String,
+}
+
+/// A message Slack posted.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SlackPosted {
+    /// The channel's id.
+    pub channel: String,
+    /// The message's timestamp, which identifies it. Pass it to [SlackMessage::in_thread] to
+    /// reply to it.
+    pub ts: String,
+}
+
+#[derive(Serialize)]
+struct SlackPostMessage<'a> {
+    channel: &'a str,
+    #[serde(flatten)]
+    message: &'a SlackMessage,
+}
+
+#[derive(Deserialize)]
+struct SlackResponse {
+    ok: bool,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(flatten)]
+    posted: Option<SlackPosted>,
+}
+
+impl SlackClient {
+    /// A client that authenticates with a bot token, starting with `xoxb-`.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            api_url: "https://slack.com/api".to_string(),
+        }
+    }
+
+    /// A client with the token from the `SLACK_BOT_TOKEN` environment variable.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("SLACK_BOT_TOKEN").unwrap_or_default())
+    }
+
+    /// Post `message` to `channel`, a channel id or name.
+    pub fn post_message(
+        &self,
+        channel: &str,
+        message: &SlackMessage,
+    ) -> Result<SlackPosted, NotifyError> {
+        let response = http::post(
+            format!("{}/chat.postMessage", self.api_url),
+            json_headers([("authorization", format!("Bearer {}", self.token))]),
+            Json(SlackPostMessage { channel, message }),
+        )?;
+        let body: SlackResponse = serde_json::from_slice(&check_status(response)?)?;
+        match (body.ok, body.posted) {
+            (true, Some(posted)) => Ok(posted),
+            (_, _) => Err(NotifyError::Slack {
+                error: body.error.unwrap_or_else(|| "unknown_error".to_string()),
+            }),
+        }
+    }
+}
+
+/// A Discord webhook message. Mentions like `@everyone` are not pinged unless you call
+/// [allow_mentions](Self::allow_mentions), so text from elsewhere can't page a whole server.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiscordMessage {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avatar_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    embeds: Vec<DiscordEmbed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_mentions: Option<serde_json::Value>,
+}
+
+impl DiscordMessage {
+    /// A message with this content, in Discord's Markdown.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            allowed_mentions: Some(serde_json::json!({ "parse": [] })),
+            ..Default::default()
+        }
+    }
+
+    /// Post as this name instead of the webhook's.
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Post with this avatar instead of the webhook's.
+    pub fn avatar_url(mut self, avatar_url: impl Into<String>) -> Self {
+        self.avatar_url = Some(avatar_url.into());
+        self
+    }
+
+    /// Add an embed. Discord shows up to 10.
+    pub fn embed(mut self, embed: DiscordEmbed) -> Self {
+        self.embeds.push(embed);
+        self
+    }
+
+    /// Ping the users, roles, and `@everyone` mentioned in the content.
+    pub fn allow_mentions(mut self) -> Self {
+        self.allowed_mentions = None;
+        self
+    }
+}
+
+/// A rich block in a Discord message.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiscordEmbed {
+    /// The embed's title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The embed's text, in Discord's Markdown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Where the title links to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The color of the embed's edge, as `0xRRGGBB`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    /// Name and value pairs shown under the description.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<DiscordEmbedField>,
+}
+
+/// A name and value shown in a [DiscordEmbed].
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscordEmbedField {
+    /// The field's name.
+    pub name: String,
+    /// The field's value.
+    pub value: String,
+    /// Whether the field may sit beside other inline fields.
+    pub inline: bool,
+}
+
+/// A Discord webhook, which posts to the channel it was created for.
+#[derive(Debug, Clone)]
+pub struct DiscordWebhook {
+    url: String,
+}
+
+impl DiscordWebhook {
+    /// A webhook with its `https://discord.com/api/webhooks/...` url. The url is the secret,
+    /// so keep it out of your code.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    /// Post a message.
+    pub fn send(&self, message: &DiscordMessage) -> Result<(), NotifyError> {
+        let response = http::post(&self.url, json_headers([]), Json(message))?;
+        check_status(response).map(drop)
+    }
+}
+
+/// A GitHub REST API client, for commenting on issues and pull requests.
+#[derive(Debug, Clone)]
+pub struct GitHubClient {
+    token: String,
+    api_url: String,
+}
+
+/// A comment GitHub created.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GitHubComment {
+    /// The comment's id.
+    pub id: u64,
+    /// The comment's page on GitHub.
+    pub html_url: String,
+}
+
+#[derive(Serialize)]
+struct GitHubCommentRequest<'a> {
+    body: &'a str,
+}
+
+impl GitHubClient {
+    /// A client that authenticates with a personal access token or an installation token.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            api_url: "https://api.github.com".to_string(),
+        }
+    }
+
+    /// A client with the token from the `GITHUB_TOKEN` environment variable.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("GITHUB_TOKEN").unwrap_or_default())
+    }
+
+    /// Use a GitHub Enterprise Server, like `https://github.example.com/api/v3`.
+    pub fn api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Comment on an issue or pull request in `owner/repo`. `body` is GitHub Markdown.
+    pub fn comment_on_issue(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue_number: u64,
+        body: &str,
+    ) -> Result<GitHubComment, NotifyError> {
+        let response = http::post(
+            format!(
+                "{}/repos/{owner}/{repo}/issues/{issue_number}/comments",
+                self.api_url
+            ),
+            json_headers([
+                ("authorization", format!("Bearer {}", self.token)),
+                ("accept", "application/vnd.github+json".to_string()),
+                ("x-github-api-version", "2022-11-28".to_string()),
+            ]),
+            Json(GitHubCommentRequest { body }),
+        )?;
+        Ok(serde_json::from_slice(&check_status(response)?)?)
+    }
+}
+
+fn json_headers<const N: usize>(headers: [(&str, String); N]) -> Vec<(String, String)> {
+    [
+        ("content-type", "application/json".to_string()),
+        ("user-agent", USER_AGENT.to_string()),
+    ]
+    .into_iter()
+    .chain(headers)
+    .map(|(name, value)| (name.to_string(), value))
+    .collect()
+}
+
+/// The body of a successful response.
+fn check_status(response: http::Response) -> Result<Vec<u8>, NotifyError> {
+    if (200..300).contains(&response.status) {
+        return Ok(response.body);
+    }
+    let retry_after = response
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+        .and_then(|(_, value)| value.trim().parse::<f64>().ok())
+        .filter(|seconds| seconds.is_finite() && 0.0 <= *seconds)
+        .map(Duration::from_secs_f64);
+    Err(NotifyError::Rejected {
+        status: response.status,
+        message: String::from_utf8_lossy(&response.body).into_owned(),
+        retry_after,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payloads_match_the_service_apis() {
+        let message = SlackMessage::new("deployed")
+            .block(serde_json::json!({"type": "divider"}))
+            .in_thread("1700000000.000100");
+        assert_eq!(
+            serde_json::json!({
+                "channel": "C123",
+                "text": "deployed",
+                "blocks": [{"type": "divider"}],
+                "thread_ts": "1700000000.000100",
+            }),
+            serde_json::to_value(SlackPostMessage {
+                channel: "C123",
+                message: &message,
+            })
+            .unwrap()
+        );
+
+        let message = DiscordMessage::new("@everyone deployed").embed(DiscordEmbed {
+            title: Some("v1.2.3".to_string()),
+            color: Some(0x2ecc71),
+            ..Default::default()
+        });
+        assert_eq!(
+            serde_json::json!({
+                "content": "@everyone deployed",
+                "embeds": [{"title": "v1.2.3", "color": 0x2ecc71}],
+                "allowed_mentions": {"parse": []},
+            }),
+            serde_json::to_value(&message).unwrap()
+        );
+        assert!(
+            serde_json::to_value(message.allow_mentions())
+                .unwrap()
+                .get("allowed_mentions")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn error_statuses_keep_the_retry_delay() {
+        let response = http::Response {
+            status: 429,
+            headers: vec![("Retry-After".to_string(), "1.5".to_string())],
+            body: b"rate limited".to_vec(),
+        };
+        match check_status(response) {
+            Err(NotifyError::Rejected {
+                status,
+                message,
+                retry_after,
+            }) => {
+                assert_eq!(429, status);
+                assert_eq!("rate limited", message);
+                assert_eq!(Some(Duration::from_millis(1500)), retry_after);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        let body: SlackResponse =
+            serde_json::from_str(r#"{"ok":true,"channel":"C123","ts":"1.2","message":{}}"#)
+                .unwrap();
+        assert_eq!(
+            Some(SlackPosted {
+                channel: "C123".to_string(),
+                ts: "1.2".to_string()
+            }),
+            body.posted
+        );
+        let body: SlackResponse =
+            serde_json::from_str(r#"{"ok":false,"error":"channel_not_found"}"#).unwrap();
+        assert!(!body.ok);
+        assert_eq!(None, body.posted);
+    }
+}
@@ -0,0 +1,697 @@
+//! OpenID Connect discovery and JWT validation, with keys kept in the cache
+//!
+//! Auth0, Cognito, Okta, and other OpenID Connect providers sign their tokens with keys they
+//! publish as a JSON Web Key Set (JWKS). [OidcProvider::discover] finds the key set from the
+//! provider's `.well-known/openid-configuration`, and a [JwtValidator] checks a token's
+//! signature against it, along with its issuer, audience, and lifetime.
+//!
+//! The discovery document and the key set are kept in your Momento cache, so each Function
+//! instance does not fetch them on its first request, and in the instance's memory after
+//! that. Providers rotate keys by publishing a new key id (`kid`) before signing with it, so
+//! a token with a key id that is not in the cached set fetches the set again, at most once per
+//! [min_refresh_interval](OidcProvider::min_refresh_interval).
+//!
+//! Signatures are checked on the host. RS256, RS384, RS512, PS256, PS384, PS512, ES256,
+//! ES384, and EdDSA tokens are accepted. Tokens signed with a shared secret (`HS256`) or not
+//! at all (`none`) are always rejected, since a public key set can't vouch for them.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use std::sync::LazyLock;
+//!
+//! use momento_functions_host::oidc::{JwtError, JwtValidator, OidcProvider};
+//!
+//! static VALIDATOR: LazyLock<Result<JwtValidator, JwtError>> = LazyLock::new(|| {
+//!     let provider = OidcProvider::discover("https://my-tenant.us.auth0.com/")?;
+//!     Ok(JwtValidator::new(provider, "https://api.example.com"))
+//! });
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//!     scope: String,
+//! }
+//!
+//! fn caller() -> Result<Claims, String> {
+//!     let validator = VALIDATOR.as_ref().map_err(|e| e.to_string())?;
+//!     validator
+//!         .validate_request::<Claims>()
+//!         .map_err(|e| format!("unauthorized: {e}"))
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use base64::Engine;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::cache::{self, CacheGetError, CacheSetError};
+use crate::http::{self, HttpGetError};
+use crate::signing::{self, SigningError};
+
+/// JWS algorithms a [JwtValidator] accepts unless you choose others.
+pub const DEFAULT_ALGORITHMS: &[&str] = &[
+    "RS256", "RS384", "RS512", "PS256", "PS384", "PS512", "ES256", "ES384", "EdDSA",
+];
+
+/// An error occurred while fetching a provider's configuration or keys.
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    /// The request to the provider failed.
+    #[error(transparent)]
+    Http(#[from] HttpGetError),
+    /// The provider responded with an error status.
+    #[error("Provider returned status {status} for {url}: {message}")]
+    Provider {
+        /// The url that was fetched.
+        url: String,
+        /// The HTTP status the provider responded with.
+        status: u16,
+        /// The body of the provider's response.
+        message: String,
+    },
+    /// The provider's response could not be decoded.
+    #[error("Failed to decode provider response.")]
+    Decode {
+        /// The underlying decoding error.
+        #[from]
+        cause: serde_json::Error,
+    },
+    /// The discovery document names a different issuer than the one it was fetched for.
+    #[error("Discovery document is for issuer {actual}, not {expected}")]
+    IssuerMismatch {
+        /// The issuer that was discovered.
+        expected: String,
+        /// The issuer the document names.
+        actual: String,
+    },
+    /// A cached document could not be read.
+    #[error(transparent)]
+    CacheRead(#[from] CacheGetError<Infallible>),
+    /// A document could not be cached.
+    #[error(transparent)]
+    CacheWrite(#[from] CacheSetError<Infallible>),
+}
+
+/// A token was not valid.
+#[derive(Debug, thiserror::Error)]
+pub enum JwtError {
+    /// The request has no `Authorization: Bearer` token.
+    #[error("the request has no bearer token")]
+    MissingToken,
+    /// The token is not a JWT in compact form.
+    #[error("the token is malformed: {message}")]
+    Malformed {
+        /// What is wrong with the token.
+        message: String,
+    },
+    /// The token is signed with an algorithm that is not accepted.
+    #[error("the token's algorithm {algorithm} is not accepted")]
+    UnsupportedAlgorithm {
+        /// The token's `alg`.
+        algorithm: String,
+    },
+    /// The provider's key set has no key for the token, even after fetching it again.
+    #[error("the provider has no key {kid:?} for the token")]
+    UnknownKey {
+        /// The token's `kid`, if it has one.
+        kid: Option<String>,
+    },
+    /// The token's signature does not match.
+    #[error("the token's signature is not valid")]
+    InvalidSignature,
+    /// The token has expired.
+    #[error("the token expired at {expires_at}")]
+    Expired {
+        /// The token's `exp`, in seconds since the Unix epoch.
+        expires_at: u64,
+    },
+    /// The token is not valid yet.
+    #[error("the token is not valid until {not_before}")]
+    NotYetValid {
+        /// The token's `nbf`, in seconds since the Unix epoch.
+        not_before: u64,
+    },
+    /// The token was issued by a different issuer.
+    #[error("the token was issued by {issuer:?}")]
+    WrongIssuer {
+        /// The token's `iss`, if it has one.
+        issuer: Option<String>,
+    },
+    /// The token is not for any of the accepted audiences.
+    #[error("the token is not for this audience")]
+    WrongAudience,
+    /// A claim the validator requires is missing, or is not the right type.
+    #[error("the token has no valid {claim} claim")]
+    MissingClaim {
+        /// The claim's name.
+        claim: &'static str,
+    },
+    /// The claims could not be decoded into the requested type.
+    #[error("Failed to decode claims.")]
+    Claims {
+        /// The underlying decoding error.
+        cause: serde_json::Error,
+    },
+    /// The provider's keys could not be fetched.
+    #[error(transparent)]
+    Provider(#[from] OidcError),
+    /// The signature could not be checked.
+    #[error(transparent)]
+    Signing(#[from] SigningError),
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct JwkSet {
+    keys: Vec<Value>,
+}
+
+/// A key from a key set, with the fields used to choose it.
+#[derive(Debug, Clone)]
+struct Jwk {
+    kid: Option<String>,
+    alg: Option<String>,
+    json: String,
+}
+
+impl Jwk {
+    fn from_value(key: Value) -> Option<Self> {
+        let field = |name: &str| key.get(name).and_then(Value::as_str).map(str::to_string);
+        // Keys published for encryption can't check signatures.
+        if field("use").is_some_and(|usage| usage != "sig") {
+            return None;
+        }
+        Some(Self {
+            kid: field("kid"),
+            alg: field("alg"),
+            json: key.to_string(),
+        })
+    }
+}
+
+struct LoadedKeys {
+    keys: Vec<Jwk>,
+    loaded_at: Instant,
+}
+
+/// An OpenID Connect provider's issuer and signing keys.
+pub struct OidcProvider {
+    issuer: String,
+    jwks_uri: String,
+    keys_ttl: Duration,
+    min_refresh_interval: Duration,
+    keys: Mutex<Option<LoadedKeys>>,
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+impl OidcProvider {
+    /// How long fetched documents are kept by default.
+    pub const DEFAULT_KEYS_TTL: Duration = Duration::from_secs(10 * 60);
+
+    /// How often an unknown key id may fetch the key set again, by default.
+    pub const DEFAULT_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Find the provider's key set from its discovery document at
+    /// `{issuer}/.well-known/openid-configuration`.
+    ///
+    /// `issuer` must match the `iss` of the provider's tokens exactly, including any trailing
+    /// slash. Auth0 issuers end with one; Cognito and Okta issuers do not.
+    pub fn discover(issuer: impl Into<String>) -> Result<Self, OidcError> {
+        let issuer = issuer.into();
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let document = fetch_cached(&url, Self::DEFAULT_KEYS_TTL, false)?;
+        let discovery: Discovery = serde_json::from_slice(&document)?;
+        if discovery.issuer != issuer {
+            return Err(OidcError::IssuerMismatch {
+                expected: issuer,
+                actual: discovery.issuer,
+            });
+        }
+        Ok(Self::new(issuer, discovery.jwks_uri))
+    }
+
+    /// A provider whose key set is at `jwks_uri`, for providers without a discovery document.
+    pub fn new(issuer: impl Into<String>, jwks_uri: impl Into<String>) -> Self {
+        Self {
+            issuer: issuer.into(),
+            jwks_uri: jwks_uri.into(),
+            keys_ttl: Self::DEFAULT_KEYS_TTL,
+            min_refresh_interval: Self::DEFAULT_MIN_REFRESH_INTERVAL,
+            keys: Mutex::new(None),
+            last_refresh: Mutex::new(None),
+        }
+    }
+
+    /// Keep the key set for this long, in the cache and in memory, before fetching it again.
+    pub fn keys_ttl(mut self, keys_ttl: Duration) -> Self {
+        self.keys_ttl = keys_ttl;
+        self
+    }
+
+    /// Fetch the key set again for an unknown key id at most this often, so tokens with made
+    /// up key ids can't make every request call the provider.
+    pub fn min_refresh_interval(mut self, min_refresh_interval: Duration) -> Self {
+        self.min_refresh_interval = min_refresh_interval;
+        self
+    }
+
+    /// The issuer tokens must name in their `iss` claim.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Where the provider publishes its keys.
+    pub fn jwks_uri(&self) -> &str {
+        &self.jwks_uri
+    }
+
+    /// The key for a token's header.
+    fn key(&self, kid: Option<&str>, algorithm: &str) -> Result<Jwk, JwtError> {
+        let keys = match self.loaded_keys() {
+            Some(keys) => keys,
+            None => self.load_keys(false)?,
+        };
+        if let Some(key) = select_key(&keys, kid, algorithm) {
+            return Ok(key);
+        }
+        if self.may_refresh(Instant::now()) {
+            let keys = self.load_keys(true)?;
+            if let Some(key) = select_key(&keys, kid, algorithm) {
+                return Ok(key);
+            }
+        }
+        Err(JwtError::UnknownKey {
+            kid: kid.map(str::to_string),
+        })
+    }
+
+    fn loaded_keys(&self) -> Option<Vec<Jwk>> {
+        let keys = self.keys.lock().unwrap_or_else(|p| p.into_inner());
+        keys.as_ref()
+            .filter(|keys| keys.loaded_at.elapsed() < self.keys_ttl)
+            .map(|keys| keys.keys.clone())
+    }
+
+    /// Load the key set from the cache, or from the provider when `refresh` is set or it is
+    /// not cached. The lock is not held while fetching.
+    fn load_keys(&self, refresh: bool) -> Result<Vec<Jwk>, OidcError> {
+        let document = fetch_cached(&self.jwks_uri, self.keys_ttl, refresh)?;
+        let JwkSet { keys } = serde_json::from_slice(&document)?;
+        let keys: Vec<Jwk> = keys.into_iter().filter_map(Jwk::from_value).collect();
+        *self.keys.lock().unwrap_or_else(|p| p.into_inner()) = Some(LoadedKeys {
+            keys: keys.clone(),
+            loaded_at: Instant::now(),
+        });
+        Ok(keys)
+    }
+
+    /// Whether an unknown key id may fetch the key set again now, recording it if so.
+    fn may_refresh(&self, now: Instant) -> bool {
+        let mut last_refresh = self.last_refresh.lock().unwrap_or_else(|p| p.into_inner());
+        match *last_refresh {
+            Some(last) if now.saturating_duration_since(last) < self.min_refresh_interval => false,
+            _ => {
+                *last_refresh = Some(now);
+                true
+            }
+        }
+    }
+}
+
+/// The key matching a token's `kid`, or the only signing key when the token has none. A key
+/// that names an algorithm must name the token's.
+fn select_key(keys: &[Jwk], kid: Option<&str>, algorithm: &str) -> Option<Jwk> {
+    let fits = |key: &&Jwk| key.alg.as_deref().is_none_or(|alg| alg == algorithm);
+    match kid {
+        Some(kid) => keys
+            .iter()
+            .filter(fits)
+            .find(|key| key.kid.as_deref() == Some(kid))
+            .cloned(),
+        None => {
+            let mut candidates = keys.iter().filter(fits);
+            match (candidates.next(), candidates.next()) {
+                (Some(key), None) => Some(key.clone()),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// A document from the cache or `url`, caching what is fetched for `ttl`.
+fn fetch_cached(url: &str, ttl: Duration, refresh: bool) -> Result<Vec<u8>, OidcError> {
+    let key = format!("oidc:{url}");
+    if !refresh && let Some(document) = cache::get::<Vec<u8>>(&key)? {
+        return Ok(document);
+    }
+    let response = http::get(
+        url,
+        [("accept".to_string(), "application/json".to_string())],
+    )?;
+    if response.status != 200 {
+        return Err(OidcError::Provider {
+            url: url.to_string(),
+            status: response.status,
+            message: String::from_utf8_lossy(&response.body).into_owned(),
+        });
+    }
+    cache::set(&key, response.body.clone(), ttl)?;
+    Ok(response.body)
+}
+
+/// Validates JWTs from an [OidcProvider].
+///
+/// Tokens must be signed by one of the provider's keys, name the provider as their issuer,
+/// be for one of the accepted audiences, and be within their `nbf` and `exp`, allowing for
+/// [leeway](Self::leeway) of clock skew.
+pub struct JwtValidator {
+    provider: OidcProvider,
+    audiences: Vec<String>,
+    audience_claim: &'static str,
+    algorithms: Vec<String>,
+    leeway: Duration,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+impl JwtValidator {
+    /// A validator for tokens from `provider` for `audience`, usually your API's identifier or
+    /// your application's client id.
+    pub fn new(provider: OidcProvider, audience: impl Into<String>) -> Self {
+        Self {
+            provider,
+            audiences: vec![audience.into()],
+            audience_claim: "aud",
+            algorithms: DEFAULT_ALGORITHMS.iter().map(|a| a.to_string()).collect(),
+            leeway: Duration::from_secs(60),
+        }
+    }
+
+    /// Also accept tokens for `audience`.
+    pub fn also_accept_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audiences.push(audience.into());
+        self
+    }
+
+    /// Read the audience from this claim instead of `aud`. Cognito access tokens name their
+    /// app client in `client_id`.
+    pub fn audience_claim(mut self, claim: &'static str) -> Self {
+        self.audience_claim = claim;
+        self
+    }
+
+    /// Accept only these JWS algorithms, like `["RS256"]`. Algorithms that need a shared
+    /// secret are never accepted.
+    pub fn algorithms(mut self, algorithms: &[&str]) -> Self {
+        self.algorithms = algorithms
+            .iter()
+            .filter(|algorithm| DEFAULT_ALGORITHMS.contains(algorithm))
+            .map(|algorithm| algorithm.to_string())
+            .collect();
+        self
+    }
+
+    /// Allow this much clock skew when checking `exp` and `nbf`. Defaults to 60 seconds.
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// The provider tokens are checked against.
+    pub fn provider(&self) -> &OidcProvider {
+        &self.provider
+    }
+
+    /// Validate `token`, returning its claims.
+    ///
+    /// Use `serde_json::Value` for `C` to get every claim.
+    pub fn validate<C: DeserializeOwned>(&self, token: &str) -> Result<C, JwtError> {
+        let TokenParts {
+            header,
+            claims,
+            signed,
+            signature,
+        } = split(token)?;
+        let header: Header = serde_json::from_slice(&header).map_err(malformed)?;
+        if !self.algorithms.contains(&header.alg) {
+            return Err(JwtError::UnsupportedAlgorithm {
+                algorithm: header.alg,
+            });
+        }
+        let key = self.provider.key(header.kid.as_deref(), &header.alg)?;
+        if !signing::verify_jws(&key.json, &header.alg, signed.as_bytes(), &signature)? {
+            return Err(JwtError::InvalidSignature);
+        }
+        let claims: Value = serde_json::from_slice(&claims).map_err(malformed)?;
+        self.check_claims(&claims, epoch_seconds(SystemTime::now()))?;
+        serde_json::from_value(claims).map_err(|cause| JwtError::Claims { cause })
+    }
+
+    /// Validate the bearer token in the `Authorization` header of the request this Function
+    /// was invoked with, returning its claims.
+    pub fn validate_request<C: DeserializeOwned>(&self) -> Result<C, JwtError> {
+        let token = crate::web_extensions::headers()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+            .and_then(|(_, value)| {
+                let (scheme, token) = value.trim().split_once(' ')?;
+                scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+            })
+            .ok_or(JwtError::MissingToken)?;
+        self.validate(token)
+    }
+
+    fn check_claims(&self, claims: &Value, now: u64) -> Result<(), JwtError> {
+        let leeway = self.leeway.as_secs();
+        let expires_at = claims
+            .get("exp")
+            .and_then(Value::as_u64)
+            .ok_or(JwtError::MissingClaim { claim: "exp" })?;
+        if expires_at.saturating_add(leeway) <= now {
+            return Err(JwtError::Expired { expires_at });
+        }
+        if let Some(not_before) = claims.get("nbf") {
+            let not_before = not_before
+                .as_u64()
+                .ok_or(JwtError::MissingClaim { claim: "nbf" })?;
+            if now.saturating_add(leeway) < not_before {
+                return Err(JwtError::NotYetValid { not_before });
+            }
+        }
+        let issuer = claims.get("iss").and_then(Value::as_str);
+        if issuer != Some(self.provider.issuer.as_str()) {
+            return Err(JwtError::WrongIssuer {
+                issuer: issuer.map(str::to_string),
+            });
+        }
+        let accepted = |audience: &Value| {
+            audience
+                .as_str()
+                .is_some_and(|audience| self.audiences.iter().any(|a| a == audience))
+        };
+        let for_us = match claims.get(self.audience_claim) {
+            Some(Value::Array(audiences)) => audiences.iter().any(accepted),
+            Some(audience) => accepted(audience),
+            None => false,
+        };
+        if !for_us {
+            return Err(JwtError::WrongAudience);
+        }
+        Ok(())
+    }
+}
+
+/// A compact JWT, with its parts decoded.
+struct TokenParts<'a> {
+    header: Vec<u8>,
+    claims: Vec<u8>,
+    /// The encoded header and claims, which the signature is over.
+    signed: &'a str,
+    signature: Vec<u8>,
+}
+
+fn split(token: &str) -> Result<TokenParts<'_>, JwtError> {
+    let mut parts = token.trim().split('.');
+    let (Some(header), Some(claims), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(JwtError::Malformed {
+            message: "expected three parts".to_string(),
+        });
+    };
+    let decode = |part: &str| {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(malformed)
+    };
+    let signed = &token.trim()[..header.len() + 1 + claims.len()];
+    Ok(TokenParts {
+        header: decode(header)?,
+        claims: decode(claims)?,
+        signed,
+        signature: decode(signature)?,
+    })
+}
+
+fn malformed(e: impl std::fmt::Display) -> JwtError {
+    JwtError::Malformed {
+        message: e.to_string(),
+    }
+}
+
+fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn validator() -> JwtValidator {
+        JwtValidator::new(
+            OidcProvider::new(
+                "https://issuer.example.com/",
+                "https://issuer.example.com/jwks",
+            ),
+            "api",
+        )
+        .also_accept_audience("admin-api")
+    }
+
+    fn jwk(kid: &str, alg: Option<&str>) -> Jwk {
+        Jwk {
+            kid: Some(kid.to_string()),
+            alg: alg.map(str::to_string),
+            json: String::new(),
+        }
+    }
+
+    #[test]
+    fn tokens_split_into_their_parts() {
+        let TokenParts {
+            header,
+            claims,
+            signed,
+            signature,
+        } = split("eyJhbGciOiJSUzI1NiJ9.eyJzdWIiOiJhZGEifQ.c2ln").unwrap();
+        assert_eq!(br#"{"alg":"RS256"}"#.to_vec(), header);
+        assert_eq!(br#"{"sub":"ada"}"#.to_vec(), claims);
+        assert_eq!("eyJhbGciOiJSUzI1NiJ9.eyJzdWIiOiJhZGEifQ", signed);
+        assert_eq!(b"sig".to_vec(), signature);
+
+        assert!(matches!(
+            split("a.b").err(),
+            Some(JwtError::Malformed { .. })
+        ));
+        assert!(matches!(
+            split("a.b.c.d").err(),
+            Some(JwtError::Malformed { .. })
+        ));
+        assert!(matches!(
+            split("a!.b.c").err(),
+            Some(JwtError::Malformed { .. })
+        ));
+    }
+
+    #[test]
+    fn claims_are_checked_with_leeway() {
+        let validator = validator();
+        let claims = |claims: Value| validator.check_claims(&claims, 1_000);
+        let iss = "https://issuer.example.com/";
+
+        assert!(claims(serde_json::json!({"iss": iss, "aud": "api", "exp": 1_001})).is_ok());
+        assert!(
+            claims(serde_json::json!({"iss": iss, "aud": ["other", "admin-api"], "exp": 941}))
+                .is_ok()
+        );
+        assert!(matches!(
+            claims(serde_json::json!({"iss": iss, "aud": "api", "exp": 940})),
+            Err(JwtError::Expired { expires_at: 940 })
+        ));
+        assert!(matches!(
+            claims(serde_json::json!({"iss": iss, "aud": "api", "exp": 2_000, "nbf": 1_061})),
+            Err(JwtError::NotYetValid { not_before: 1_061 })
+        ));
+        assert!(matches!(
+            claims(serde_json::json!({"iss": iss, "aud": "api"})),
+            Err(JwtError::MissingClaim { claim: "exp" })
+        ));
+        assert!(matches!(
+            claims(
+                serde_json::json!({"iss": "https://issuer.example.com", "aud": "api", "exp": 2_000})
+            ),
+            Err(JwtError::WrongIssuer { .. })
+        ));
+        assert!(matches!(
+            claims(serde_json::json!({"iss": iss, "aud": ["other"], "exp": 2_000})),
+            Err(JwtError::WrongAudience)
+        ));
+
+        let cognito = validator.audience_claim("client_id");
+        assert!(
+            cognito
+                .check_claims(
+                    &serde_json::json!({"iss": iss, "client_id": "api", "exp": 2_000}),
+                    1_000
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn keys_are_chosen_by_id_and_algorithm() {
+        let keys = vec![jwk("old", Some("RS256")), jwk("new", None)];
+        assert_eq!(
+            Some("old".to_string()),
+            select_key(&keys, Some("old"), "RS256").and_then(|key| key.kid)
+        );
+        assert!(select_key(&keys, Some("old"), "ES256").is_none());
+        assert!(select_key(&keys, Some("rotated"), "RS256").is_none());
+        // Without a key id, only an unambiguous key is used.
+        assert!(select_key(&keys, None, "RS256").is_none());
+        assert_eq!(
+            Some("new".to_string()),
+            select_key(&keys, None, "ES256").and_then(|key| key.kid)
+        );
+
+        assert!(Jwk::from_value(serde_json::json!({"kid": "enc", "use": "enc"})).is_none());
+        assert!(Jwk::from_value(serde_json::json!({"kid": "sig", "use": "sig"})).is_some());
+        assert_eq!(
+            vec!["RS256".to_string()],
+            validator()
+                .algorithms(&["RS256", "HS256", "none"])
+                .algorithms
+        );
+    }
+
+    #[test]
+    fn refreshes_are_rate_limited() {
+        let provider =
+            OidcProvider::new("issuer", "jwks").min_refresh_interval(Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(provider.may_refresh(start));
+        assert!(!provider.may_refresh(start + Duration::from_secs(59)));
+        assert!(provider.may_refresh(start + Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,230 @@
+//! Fan work out to spawned Functions and gather their results
+//!
+//! A single invocation only runs for so long. When a job is too big for one invocation,
+//! split it into partitions and [spawn](crate::spawn) a worker Function for each. The
+//! workers report their results to a cache list named for the job, and the coordinator
+//! waits on that list like a barrier: it returns once every partition has reported, or
+//! when its timeout fires.
+//!
+//! **Coordinator:**
+//! ```rust,no_run
+//! use momento_functions_host::parallel;
+//! use std::time::Duration;
+//!
+//! let articles: Vec<String> = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+//! let partitions: Vec<Vec<String>> = articles.chunks(2).map(|chunk| chunk.to_vec()).collect();
+//! match parallel::parallel::<_, usize>(
+//!     "index-articles-worker",
+//!     "index-job-1234",
+//!     partitions,
+//!     Duration::from_secs(60),
+//! ) {
+//!     Ok(indexed) => println!("indexed {} articles", indexed.iter().sum::<usize>()),
+//!     Err(e) => eprintln!("indexing failed: {e}"),
+//! }
+//! ```
+//!
+//! **Worker:**
+//! ```rust,no_run
+//! use momento_functions_host::parallel::Partition;
+//!
+//! type Work = Partition<Vec<String>>;
+//!
+//! // Registered with `momento_functions::spawn!(index_articles, Work);`
+//! fn index_articles(work: Work) {
+//!     let indexed = work.payload.len();
+//!     if let Err(e) = work.complete(indexed) {
+//!         log::error!("failed to report partition {}: {e}", work.index);
+//!     }
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::cache::{
+    self, CacheListFetchError, CacheListPushBackError, CollectionTtl, EndIndex, StartIndex,
+};
+use crate::encoding::Json;
+use crate::spawn::{FunctionSpawnError, spawn};
+
+/// How long a job's results are kept in the cache.
+const RESULTS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How often [FanOut::wait] checks for results.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An error occurred while running work in parallel.
+#[derive(Debug, thiserror::Error)]
+pub enum ParallelError {
+    /// A worker could not be spawned.
+    #[error("Failed to spawn worker for partition {index}: {cause}")]
+    Spawn {
+        /// The partition whose worker failed to spawn.
+        index: usize,
+        /// The underlying spawn error.
+        cause: FunctionSpawnError<serde_json::Error>,
+    },
+    /// An error occurred while reading results from the cache.
+    #[error(transparent)]
+    FetchResults(#[from] CacheListFetchError<serde_json::Error>),
+    /// An error occurred while reporting a result to the cache.
+    #[error(transparent)]
+    ReportResult(#[from] CacheListPushBackError<serde_json::Error>),
+    /// Not every partition reported a result before the timeout.
+    #[error("Timed out with {completed} of {expected} partitions complete")]
+    TimedOut {
+        /// The number of partitions that reported a result.
+        completed: usize,
+        /// The number of partitions that were spawned.
+        expected: usize,
+    },
+}
+
+/// The payload a worker receives: one partition of a job.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Partition<P> {
+    /// The job this partition belongs to.
+    pub job_id: String,
+    /// This partition's position among the job's partitions.
+    pub index: usize,
+    /// The number of partitions in the job.
+    pub count: usize,
+    /// The work to do.
+    pub payload: P,
+}
+
+impl<P> Partition<P> {
+    /// Report this partition's result to the coordinator.
+    pub fn complete<R: Serialize>(&self, result: R) -> Result<(), ParallelError> {
+        cache::list_push_back(
+            results_list(&self.job_id),
+            Json(PartitionResult {
+                index: self.index,
+                result,
+            }),
+            CollectionTtl::initialize_only(RESULTS_TTL),
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PartitionResult<R> {
+    index: usize,
+    result: R,
+}
+
+/// A job whose partitions have been spawned, from [fan_out].
+#[derive(Debug)]
+pub struct FanOut {
+    job_id: String,
+    count: usize,
+}
+
+impl FanOut {
+    /// The job's id.
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    /// The number of partitions that were spawned.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Check which partitions have reported so far, without waiting.
+    ///
+    /// The results are in partition order, with `None` for partitions that have not
+    /// reported yet.
+    pub fn poll<R: DeserializeOwned>(&self) -> Result<Vec<Option<R>>, ParallelError> {
+        let mut results: Vec<Option<R>> = (0..self.count).map(|_| None).collect();
+        if let Some(reported) = cache::list_fetch::<Json<PartitionResult<R>>>(
+            results_list(&self.job_id),
+            StartIndex::Unbounded,
+            EndIndex::Unbounded,
+        )? {
+            for reported in reported {
+                let Json(PartitionResult { index, result }) = reported?;
+                if let Some(slot) = results.get_mut(index) {
+                    *slot = Some(result);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Wait for every partition to report, returning their results in partition order.
+    pub fn wait<R: DeserializeOwned>(&self, timeout: Duration) -> Result<Vec<R>, ParallelError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let results = self.poll::<R>()?;
+            let completed = results.iter().filter(|result| result.is_some()).count();
+            if completed == self.count {
+                return Ok(results.into_iter().flatten().collect());
+            }
+            if deadline <= Instant::now() {
+                return Err(ParallelError::TimedOut {
+                    completed,
+                    expected: self.count,
+                });
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Remove the job's results from the cache.
+    pub fn clean_up(&self) -> Result<(), cache::CacheDeleteError> {
+        cache::delete(results_list(&self.job_id))
+    }
+}
+
+/// Spawn one `function_name` worker per partition, each receiving a [Partition].
+///
+/// `job_id` must be unique to this run, since workers report to a cache list named for it.
+pub fn fan_out<P: Serialize>(
+    function_name: impl AsRef<str>,
+    job_id: impl Into<String>,
+    partitions: impl IntoIterator<Item = P>,
+) -> Result<FanOut, ParallelError> {
+    let job_id = job_id.into();
+    let partitions: Vec<P> = partitions.into_iter().collect();
+    let count = partitions.len();
+    for (index, payload) in partitions.into_iter().enumerate() {
+        spawn(
+            function_name.as_ref(),
+            Json(Partition {
+                job_id: job_id.clone(),
+                index,
+                count,
+                payload,
+            }),
+        )
+        .map_err(|cause| ParallelError::Spawn { index, cause })?;
+    }
+    Ok(FanOut { job_id, count })
+}
+
+/// Spawn a worker per partition and wait for all of their results.
+///
+/// This is [fan_out] followed by [FanOut::wait]. The job's results are removed from the
+/// cache once they have all arrived.
+pub fn parallel<P: Serialize, R: DeserializeOwned>(
+    function_name: impl AsRef<str>,
+    job_id: impl Into<String>,
+    partitions: impl IntoIterator<Item = P>,
+    timeout: Duration,
+) -> Result<Vec<R>, ParallelError> {
+    let fan_out = fan_out(function_name, job_id, partitions)?;
+    let results = fan_out.wait(timeout)?;
+    if let Err(e) = fan_out.clean_up() {
+        log::warn!("failed to clean up results of job {}: {e}", fan_out.job_id);
+    }
+    Ok(results)
+}
+
+fn results_list(job_id: &str) -> String {
+    format!("parallel:{job_id}")
+}
@@ -0,0 +1,458 @@
+//! Versioned prompt templates, loaded at runtime
+//!
+//! Keep prompts in the cache or S3 instead of compiling them into the Function, so they can
+//! change without a redeploy. A [PromptStore] loads each prompt on first use, keeps it for
+//! the refresh interval, and falls back to the last copy it loaded, then to a built-in
+//! default, when the source is unavailable.
+//!
+//! A prompt is a JSON document:
+//! ```json
+//! {
+//!     "version": "7",
+//!     "system": "You classify support tickets as billing, bug, or other.",
+//!     "examples": [
+//!         { "user": "I was charged twice", "assistant": "billing" }
+//!     ],
+//!     "template": "Ticket from {{customer}}:\n{{ticket}}",
+//!     "max_tokens": 2000
+//! }
+//! ```
+//!
+//! `{{name}}` placeholders are filled from the variables passed to [PromptStore::render];
+//! write `{{{{` for a literal `{{`. Few-shot `examples` are dropped from the end, if needed,
+//! to keep the prompt's estimated tokens under `max_tokens`.
+//!
+//! The prompt named `classify` is read from the source's `classify` key or object. A pinned
+//! version, read with [PromptStore::render_version], is read from `classify@7`.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::prompts::{Prompt, PromptSource, PromptStore};
+//! use std::time::Duration;
+//!
+//! let prompts = PromptStore::new(
+//!     PromptSource::Cache {
+//!         key_prefix: "prompts/".to_string(),
+//!     },
+//!     Duration::from_secs(60),
+//! )
+//! .fallback(
+//!     "classify",
+//!     Prompt::new("Classify this ticket as billing, bug, or other:\n{{ticket}}"),
+//! );
+//!
+//! match prompts.render("classify", [("customer", "Ada"), ("ticket", "I was charged twice")]) {
+//!     Ok(rendered) => println!("v{} ~{} tokens", rendered.version, rendered.estimated_tokens()),
+//!     Err(e) => eprintln!("prompt failed: {e}"),
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// An error occurred while loading or rendering a prompt.
+#[derive(Debug, thiserror::Error)]
+pub enum PromptError {
+    /// The prompt is not in the source, and has no fallback.
+    #[error("Prompt {name} was not found")]
+    NotFound {
+        /// The prompt's name, with its version if one was asked for.
+        name: String,
+    },
+    /// The prompt could not be fetched, and has no fallback.
+    #[error("Failed to fetch prompt {name}: {message}")]
+    Fetch {
+        /// The prompt's name.
+        name: String,
+        /// Why the fetch failed.
+        message: String,
+    },
+    /// The prompt was fetched, but is not a valid prompt document.
+    #[error("Invalid prompt document: {0}")]
+    Invalid(#[from] serde_json::Error),
+    /// The template uses a variable that was not passed.
+    #[error("Prompt {name} needs the variable {variable}")]
+    MissingVariable {
+        /// The prompt's name.
+        name: String,
+        /// The missing variable.
+        variable: String,
+    },
+    /// The prompt is longer than its `max_tokens`, even without examples.
+    #[error("Prompt {name} is about {estimated} tokens, over its limit of {max_tokens}")]
+    TooLong {
+        /// The prompt's name.
+        name: String,
+        /// The rendered prompt's estimated tokens.
+        estimated: usize,
+        /// The prompt's limit.
+        max_tokens: usize,
+    },
+}
+
+/// Where a [PromptStore] loads prompts from.
+pub enum PromptSource {
+    /// Prompts are cache values, under `key_prefix` followed by the prompt's name.
+    Cache {
+        /// The prefix of every prompt's key, like `prompts/`.
+        key_prefix: String,
+    },
+    /// Prompts are S3 objects, under `prefix` followed by the prompt's name.
+    #[cfg(feature = "aws")]
+    S3 {
+        /// The client to read objects with.
+        client: crate::aws::s3::S3Client,
+        /// The bucket holding the prompts.
+        bucket: String,
+        /// The prefix of every prompt's key, like `prompts/`.
+        prefix: String,
+    },
+}
+
+/// A prompt template, with its instructions and few-shot examples.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prompt {
+    /// The prompt's version, reported in each [RenderedPrompt].
+    #[serde(default)]
+    pub version: String,
+    /// Instructions for the model.
+    #[serde(default)]
+    pub system: Option<String>,
+    /// Example exchanges, shown to the model before the request.
+    #[serde(default)]
+    pub examples: Vec<Example>,
+    /// The request, with `{{name}}` placeholders.
+    pub template: String,
+    /// The most estimated tokens the rendered prompt may use.
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+}
+
+/// A few-shot example: a request and the reply the model should give.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Example {
+    /// The example request.
+    pub user: String,
+    /// The reply to it.
+    pub assistant: String,
+}
+
+impl Prompt {
+    /// A prompt with only a template, for fallbacks.
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            version: "fallback".to_string(),
+            system: None,
+            examples: Vec::new(),
+            template: template.into(),
+            max_tokens: None,
+        }
+    }
+
+    /// Parse a prompt document from JSON.
+    pub fn from_json(json: &[u8]) -> Result<Self, PromptError> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    /// Set the instructions for the model.
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    /// Add a few-shot example.
+    pub fn example(mut self, user: impl Into<String>, assistant: impl Into<String>) -> Self {
+        self.examples.push(Example {
+            user: user.into(),
+            assistant: assistant.into(),
+        });
+        self
+    }
+
+    /// Fill the template with `variables`.
+    pub fn render<K: AsRef<str>, V: AsRef<str>>(
+        &self,
+        name: &str,
+        variables: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<RenderedPrompt, PromptError> {
+        let variables: HashMap<String, String> = variables
+            .into_iter()
+            .map(|(name, value)| (name.as_ref().to_string(), value.as_ref().to_string()))
+            .collect();
+        let mut rendered = RenderedPrompt {
+            name: name.to_string(),
+            version: self.version.clone(),
+            system: self.system.clone(),
+            examples: self.examples.clone(),
+            user: interpolate(name, &self.template, &variables)?,
+        };
+        if let Some(max_tokens) = self.max_tokens {
+            while max_tokens < rendered.estimated_tokens() && rendered.examples.pop().is_some() {}
+            let estimated = rendered.estimated_tokens();
+            if max_tokens < estimated {
+                return Err(PromptError::TooLong {
+                    name: name.to_string(),
+                    estimated,
+                    max_tokens,
+                });
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+/// A prompt with its variables filled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedPrompt {
+    /// The prompt's name.
+    pub name: String,
+    /// The version that was rendered, for logging alongside the model's reply.
+    pub version: String,
+    /// Instructions for the model.
+    pub system: Option<String>,
+    /// The few-shot examples that fit.
+    pub examples: Vec<Example>,
+    /// The filled-in request.
+    pub user: String,
+}
+
+impl RenderedPrompt {
+    /// About how many tokens the prompt uses.
+    ///
+    /// This is an estimate of one token per four characters, which is close for English
+    /// with most tokenizers. Use it for budgeting, not billing.
+    pub fn estimated_tokens(&self) -> usize {
+        let texts = self
+            .system
+            .iter()
+            .chain(
+                self.examples
+                    .iter()
+                    .flat_map(|example| [&example.user, &example.assistant]),
+            )
+            .chain([&self.user]);
+        texts.map(|text| estimate_tokens(text)).sum()
+    }
+}
+
+#[cfg(feature = "http")]
+impl From<RenderedPrompt> for crate::llm::ChatRequest {
+    fn from(prompt: RenderedPrompt) -> Self {
+        let mut request = crate::llm::ChatRequest::new();
+        if let Some(system) = prompt.system {
+            request = request.system(system);
+        }
+        for example in prompt.examples {
+            request = request.user(example.user).assistant(example.assistant);
+        }
+        request.user(prompt.user)
+    }
+}
+
+/// About how many tokens `text` is, at one token per four characters.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+struct Loaded {
+    prompt: Prompt,
+    fetched_at: Instant,
+}
+
+/// Loads prompts from a [PromptSource], keeping each for a refresh interval.
+pub struct PromptStore {
+    source: PromptSource,
+    refresh_interval: Duration,
+    fallbacks: HashMap<String, Prompt>,
+    loaded: Mutex<HashMap<String, Loaded>>,
+}
+
+impl PromptStore {
+    /// Load prompts from `source`, fetching each again once it is `refresh_interval` old.
+    pub fn new(source: PromptSource, refresh_interval: Duration) -> Self {
+        Self {
+            source,
+            refresh_interval,
+            fallbacks: HashMap::new(),
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use `prompt` for `name` when the source does not have it and it has never been
+    /// loaded.
+    pub fn fallback(mut self, name: impl Into<String>, prompt: Prompt) -> Self {
+        self.fallbacks.insert(name.into(), prompt);
+        self
+    }
+
+    /// The current version of the prompt `name`.
+    pub fn get(&self, name: &str) -> Result<Prompt, PromptError> {
+        self.load(name, name)
+    }
+
+    /// Render the current version of the prompt `name` with `variables`.
+    pub fn render<K: AsRef<str>, V: AsRef<str>>(
+        &self,
+        name: &str,
+        variables: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<RenderedPrompt, PromptError> {
+        self.get(name)?.render(name, variables)
+    }
+
+    /// Render a pinned version of the prompt `name` with `variables`. The fallback is not
+    /// used for pinned versions.
+    pub fn render_version<K: AsRef<str>, V: AsRef<str>>(
+        &self,
+        name: &str,
+        version: &str,
+        variables: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<RenderedPrompt, PromptError> {
+        self.load(name, &format!("{name}@{version}"))?
+            .render(name, variables)
+    }
+
+    fn load(&self, name: &str, key: &str) -> Result<Prompt, PromptError> {
+        let mut loaded = self
+            .loaded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(current) = loaded.get(key)
+            && current.fetched_at.elapsed() < self.refresh_interval
+        {
+            return Ok(current.prompt.clone());
+        }
+        let fallback = || match (loaded.get(key), key == name) {
+            (Some(current), _) => Some(current.prompt.clone()),
+            (None, true) => self.fallbacks.get(name).cloned(),
+            (None, false) => None,
+        };
+        match self.fetch(key) {
+            Ok(Some(prompt)) => {
+                loaded.insert(
+                    key.to_string(),
+                    Loaded {
+                        prompt: prompt.clone(),
+                        fetched_at: Instant::now(),
+                    },
+                );
+                Ok(prompt)
+            }
+            Ok(None) => fallback().ok_or_else(|| PromptError::NotFound {
+                name: key.to_string(),
+            }),
+            Err(e) => match fallback() {
+                Some(prompt) => {
+                    log::warn!("failed to refresh prompt {key}, using the last copy: {e}");
+                    Ok(prompt)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    fn fetch(&self, key: &str) -> Result<Option<Prompt>, PromptError> {
+        let fetch_error = |message: String| PromptError::Fetch {
+            name: key.to_string(),
+            message,
+        };
+        let json = match &self.source {
+            PromptSource::Cache { key_prefix } => {
+                crate::cache::get::<Vec<u8>>(format!("{key_prefix}{key}"))
+                    .map_err(|e| fetch_error(e.to_string()))?
+            }
+            #[cfg(feature = "aws")]
+            PromptSource::S3 {
+                client,
+                bucket,
+                prefix,
+            } => client
+                .get::<Vec<u8>>(bucket.clone(), format!("{prefix}{key}"))
+                .map_err(|e| fetch_error(e.to_string()))?,
+        };
+        json.map(|json| Prompt::from_json(&json)).transpose()
+    }
+}
+
+/// Fill `{{name}}` placeholders in one pass, so variables containing braces are left alone.
+fn interpolate(
+    prompt: &str,
+    template: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, PromptError> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        rendered.push_str(&rest[..open]);
+        rest = &rest[open..];
+        if let Some(after) = rest.strip_prefix("{{{{") {
+            rendered.push_str("{{");
+            rest = after;
+            continue;
+        }
+        let Some(close) = rest.find("}}") else {
+            break;
+        };
+        let variable = rest[2..close].trim();
+        let value = variables
+            .get(variable)
+            .ok_or_else(|| PromptError::MissingVariable {
+                name: prompt.to_string(),
+                variable: variable.to_string(),
+            })?;
+        rendered.push_str(value);
+        rest = &rest[close + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_fill_once_and_must_be_given() {
+        let prompt = Prompt::new("{{ ticket }} from {{customer}} {{{{raw}}");
+        let rendered = prompt
+            .render("p", [("ticket", "{{customer}}"), ("customer", "Ada")])
+            .unwrap();
+        assert_eq!("{{customer}} from Ada {{raw}}", rendered.user);
+
+        assert!(matches!(
+            prompt.render("p", [("ticket", "t")]),
+            Err(PromptError::MissingVariable { variable, .. }) if variable == "customer"
+        ));
+    }
+
+    #[test]
+    fn examples_are_dropped_to_fit() {
+        let prompt = Prompt::from_json(
+            br#"{
+                "version": "3",
+                "system": "classify",
+                "examples": [
+                    { "user": "charged twice", "assistant": "billing" },
+                    { "user": "the app crashes when I open settings", "assistant": "bug" }
+                ],
+                "template": "{{ticket}}",
+                "max_tokens": 12
+            }"#,
+        )
+        .unwrap();
+        let rendered = prompt.render("classify", [("ticket", "refund?")]).unwrap();
+        assert_eq!("3", rendered.version);
+        assert_eq!(1, rendered.examples.len());
+        assert!(rendered.estimated_tokens() <= 12);
+
+        let long = "x".repeat(100);
+        assert!(matches!(
+            prompt.render("classify", [("ticket", long.as_str())]),
+            Err(PromptError::TooLong { max_tokens: 12, .. })
+        ));
+        assert_eq!(2, estimate_tokens("hello"));
+    }
+}
@@ -0,0 +1,484 @@
+//! Monthly usage quotas per caller
+//!
+//! Usage-based plans count what each tenant or API key uses in a month, refuse work past
+//! the plan's allowance, and send the totals to billing. [consume] and [Quota::consume] keep
+//! that count in the cache, one counter per tenant per calendar month (UTC), updated with
+//! compare-and-set so concurrent invocations do not lose each other's usage. A [Quota] with a
+//! [limit](Quota::limit) refuses calls that would go past it, without counting them.
+//!
+//! Every so often, the invocation that updates a counter also hands its total to the quota's
+//! [UsageExporter], like [DynamoDBExporter](crate::quota::DynamoDBExporter), so billing reads
+//! usage from a durable table rather than the cache. Reports carry the month's total, not
+//! the units since the last report, so a report that fails or arrives twice does not skew
+//! the count.
+//!
+//! Usage counted in the last export interval of a month is exported when the tenant is first
+//! counted in the next month. For tenants that may not come back, export last month's totals
+//! with [Quota::flush] from a scheduled Function.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::quota::{Quota, QuotaError};
+//!
+//! # fn f() -> Result<(), QuotaError> {
+//! let api_key_id = "key-42";
+//! let tokens_used = 1_250;
+//! match Quota::new("llm-tokens").limit(1_000_000).consume(api_key_id, tokens_used) {
+//!     Ok(usage) => log::debug!("{api_key_id} has used {} tokens in {}", usage.used, usage.period),
+//!     Err(QuotaError::Exceeded { .. }) => { /* respond 429 */ }
+//!     Err(e) => return Err(e),
+//! }
+//! # Ok(()) }
+//! ```
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{
+    self, CacheGetWithHashError, CacheSetIfError, CacheSetIfHashError, SetIfCondition,
+    SetIfHashCondition, SetIfHashResult, SetIfResult,
+};
+
+const MAX_UPDATE_ATTEMPTS: usize = 10;
+
+/// An error occurred while counting usage.
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaError {
+    /// The call would take the tenant past its limit for the month. Nothing was counted.
+    #[error("{tenant} has used {used} of {limit} for {period}; {requested} more would exceed it")]
+    Exceeded {
+        /// The tenant.
+        tenant: String,
+        /// The month, like `2024-07`.
+        period: String,
+        /// What the tenant has used this month.
+        used: u64,
+        /// The tenant's limit for the month.
+        limit: u64,
+        /// What the call asked to use.
+        requested: u64,
+    },
+    /// The counter could not be read from the cache.
+    #[error(transparent)]
+    Read(#[from] CacheGetWithHashError<Infallible>),
+    /// The counter could not be created in the cache.
+    #[error(transparent)]
+    Create(#[from] CacheSetIfError<Infallible>),
+    /// The counter could not be updated in the cache.
+    #[error(transparent)]
+    Update(#[from] CacheSetIfHashError<Infallible>),
+    /// The counter kept changing while it was being updated.
+    #[error("Counter {key} changed on each of {attempts} attempts to update it")]
+    Contended {
+        /// The counter's cache key.
+        key: String,
+        /// How many times the update was tried.
+        attempts: usize,
+    },
+    /// The cache item is not a usage counter.
+    #[error("Invalid counter: {0}")]
+    InvalidCounter(#[from] serde_json::Error),
+}
+
+/// What a tenant has used in a month.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    /// The quota's name.
+    pub quota: String,
+    /// The tenant.
+    pub tenant: String,
+    /// The month, like `2024-07`.
+    pub period: String,
+    /// What the tenant has used this month, including this call.
+    pub used: u64,
+    /// The tenant's limit for the month, if the quota has one.
+    pub limit: Option<u64>,
+}
+
+impl Usage {
+    /// What the tenant has left this month, if the quota has a limit.
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_sub(self.used))
+    }
+}
+
+/// Receives each tenant's monthly total, for billing or reporting.
+pub trait UsageExporter {
+    /// Record `usage`, replacing any earlier total for the same quota, tenant, and month.
+    fn export(&self, usage: &Usage) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+impl<F> UsageExporter for F
+where
+    F: Fn(&Usage) -> Result<(), Box<dyn std::error::Error>>,
+{
+    fn export(&self, usage: &Usage) -> Result<(), Box<dyn std::error::Error>> {
+        self(usage)
+    }
+}
+
+/// The cache item behind one tenant's monthly counter.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Counter {
+    used: u64,
+    /// When the counter was last handed to the exporter, in seconds since the epoch.
+    #[serde(default)]
+    exported_at: u64,
+}
+
+/// A named monthly usage counter for each tenant.
+#[derive(Clone)]
+pub struct Quota {
+    name: String,
+    limit: Option<u64>,
+    counter_ttl: Duration,
+    export_interval: Duration,
+    exporter: Option<Arc<dyn UsageExporter>>,
+}
+
+impl std::fmt::Debug for Quota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Quota")
+            .field("name", &self.name)
+            .field("limit", &self.limit)
+            .field("counter_ttl", &self.counter_ttl)
+            .field("export_interval", &self.export_interval)
+            .field("exporter", &self.exporter.is_some())
+            .finish()
+    }
+}
+
+/// Count `units` against `tenant` in the default, unlimited quota.
+///
+/// This is `Quota::new("default").consume(tenant, units)`.
+pub fn consume(tenant: &str, units: u64) -> Result<Usage, QuotaError> {
+    Quota::new("default").consume(tenant, units)
+}
+
+impl Quota {
+    /// A quota named `name`, without a limit or an exporter.
+    ///
+    /// Counters are stored at `quota:{name length}:{name}:{tenant}:{month}`, and live for 62
+    /// days after they last changed, so last month's totals can still be read and exported.
+    /// Quotas with different names count separately, even when names or tenants contain `:`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            limit: None,
+            counter_ttl: Duration::from_secs(62 * 24 * 60 * 60),
+            export_interval: Duration::from_secs(60),
+            exporter: None,
+        }
+    }
+
+    /// Refuse calls that would take a tenant past `limit` in a month.
+    ///
+    /// Create the quota with each tenant's own limit when it depends on their plan.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// How long counters live after they last changed.
+    pub fn counter_ttl(mut self, counter_ttl: Duration) -> Self {
+        self.counter_ttl = counter_ttl;
+        self
+    }
+
+    /// Hand each tenant's total to `exporter` at most once per the export interval.
+    pub fn exporter(mut self, exporter: impl UsageExporter + 'static) -> Self {
+        self.exporter = Some(Arc::new(exporter));
+        self
+    }
+
+    /// How often each tenant's total is exported while it is changing. Defaults to a minute.
+    pub fn export_interval(mut self, export_interval: Duration) -> Self {
+        self.export_interval = export_interval;
+        self
+    }
+
+    /// Count `units` against `tenant` for the current month.
+    ///
+    /// Fails with [QuotaError::Exceeded] if the quota has a limit the units would go past.
+    /// Export failures are logged rather than returned, and retried with a later call.
+    pub fn consume(&self, tenant: &str, units: u64) -> Result<Usage, QuotaError> {
+        let period = month(SystemTime::now());
+        let key = self.key(tenant, &period);
+        let now = epoch_seconds(SystemTime::now());
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let (counter, hash) = read(&key)?.unzip();
+            let new_period = hash.is_none();
+            let mut counter = counter.unwrap_or_default();
+            let used = counter.used.saturating_add(units);
+            if let Some(limit) = self.limit.filter(|limit| *limit < used) {
+                return Err(QuotaError::Exceeded {
+                    tenant: tenant.to_string(),
+                    period,
+                    used: counter.used,
+                    limit,
+                    requested: units,
+                });
+            }
+            counter.used = used;
+            // The invocation whose update lands claims the export, so each interval's total is
+            // exported once however many invocations are counting.
+            let export = self.exporter.is_some()
+                && self.export_interval.as_secs() <= now.saturating_sub(counter.exported_at);
+            if export {
+                counter.exported_at = now;
+            }
+            if self.store(&key, &counter, hash)? {
+                let usage = self.usage_of(tenant, period, counter.used);
+                if export {
+                    self.export(&usage);
+                }
+                if new_period && self.exporter.is_some() {
+                    self.export_previous_month(tenant, &usage.period);
+                }
+                return Ok(usage);
+            }
+            log::debug!("quota counter {key} changed while updating it");
+        }
+        Err(QuotaError::Contended {
+            key,
+            attempts: MAX_UPDATE_ATTEMPTS,
+        })
+    }
+
+    /// What `tenant` has used this month.
+    pub fn usage(&self, tenant: &str) -> Result<Usage, QuotaError> {
+        self.usage_in(tenant, &month(SystemTime::now()))
+    }
+
+    /// What `tenant` used in `period`, a month like `2024-07`, while its counter is still in
+    /// the cache.
+    pub fn usage_in(&self, tenant: &str, period: &str) -> Result<Usage, QuotaError> {
+        let used = read(&self.key(tenant, period))?
+            .map(|(counter, _)| counter.used)
+            .unwrap_or_default();
+        Ok(self.usage_of(tenant, period.to_string(), used))
+    }
+
+    /// Export `tenant`'s total for `period` now, like after the end of a month from a
+    /// scheduled Function, so usage counted since the last export is not missed when the
+    /// tenant is not counted again the next month.
+    pub fn flush(&self, tenant: &str, period: &str) -> Result<Usage, QuotaError> {
+        let usage = self.usage_in(tenant, period)?;
+        self.export(&usage);
+        Ok(usage)
+    }
+
+    fn key(&self, tenant: &str, period: &str) -> String {
+        format!("quota:{}:{period}", scope(&self.name, tenant))
+    }
+
+    /// Export the month before `period`, whose last interval was not exported when it ended.
+    fn export_previous_month(&self, tenant: &str, period: &str) {
+        let Some(previous) = previous_month(period) else {
+            return;
+        };
+        match read(&self.key(tenant, &previous)) {
+            Ok(Some((counter, _))) => self.export(&self.usage_of(tenant, previous, counter.used)),
+            Ok(None) => {}
+            Err(e) => log::warn!(
+                "failed to read usage of quota {} for {tenant} in {previous}: {e}",
+                self.name
+            ),
+        }
+    }
+
+    fn usage_of(&self, tenant: &str, period: String, used: u64) -> Usage {
+        Usage {
+            quota: self.name.clone(),
+            tenant: tenant.to_string(),
+            period,
+            used,
+            limit: self.limit,
+        }
+    }
+
+    fn export(&self, usage: &Usage) {
+        if let Some(exporter) = &self.exporter
+            && let Err(e) = exporter.export(usage)
+        {
+            log::warn!(
+                "failed to export usage of quota {} for {}: {e}",
+                usage.quota,
+                usage.tenant
+            );
+        }
+    }
+
+    /// Store `counter` unless another write got in since it was read at `hash`.
+    fn store(
+        &self,
+        key: &str,
+        counter: &Counter,
+        hash: Option<Vec<u8>>,
+    ) -> Result<bool, QuotaError> {
+        let value = serde_json::to_vec(counter)?;
+        Ok(match hash {
+            Some(hash) => matches!(
+                cache::set_if_hash(
+                    key,
+                    value,
+                    self.counter_ttl,
+                    SetIfHashCondition::PresentAndHashEqual(hash),
+                )?,
+                SetIfHashResult::Stored(_)
+            ),
+            None => matches!(
+                cache::set_if(key, value, self.counter_ttl, SetIfCondition::Absent)?,
+                SetIfResult::Stored
+            ),
+        })
+    }
+}
+
+fn read(key: &str) -> Result<Option<(Counter, Vec<u8>)>, QuotaError> {
+    let Some(counter) = cache::get_with_hash::<Vec<u8>>(key)? else {
+        return Ok(None);
+    };
+    Ok(Some((
+        serde_json::from_slice(&counter.value)?,
+        counter.hash,
+    )))
+}
+
+/// Exports monthly totals to a DynamoDB table.
+///
+/// Each total is one item keyed by `pk`, `{quota length}:{quota}:{tenant}`, and `period`, with `quota`,
+/// `tenant`, `used`, and, when the quota has a limit, `limit` attributes. The table needs a
+/// `pk` partition key and a `period` sort key, both strings.
+#[cfg(feature = "aws")]
+pub struct DynamoDBExporter {
+    client: crate::aws::ddb::DynamoDBClient,
+    table_name: String,
+}
+
+#[cfg(feature = "aws")]
+impl DynamoDBExporter {
+    /// Export totals to `table_name` with `client`.
+    pub fn new(client: crate::aws::ddb::DynamoDBClient, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+}
+
+#[cfg(feature = "aws")]
+impl UsageExporter for DynamoDBExporter {
+    fn export(&self, usage: &Usage) -> Result<(), Box<dyn std::error::Error>> {
+        use crate::aws::ddb::{AttributeValue, Item};
+
+        let mut item: Item = [
+            (
+                "pk",
+                AttributeValue::from(scope(&usage.quota, &usage.tenant)),
+            ),
+            ("period", AttributeValue::from(usage.period.as_str())),
+            ("quota", AttributeValue::from(usage.quota.as_str())),
+            ("tenant", AttributeValue::from(usage.tenant.as_str())),
+            ("used", AttributeValue::Number(usage.used.to_string())),
+        ]
+        .into();
+        if let Some(limit) = usage.limit {
+            item.attributes.insert(
+                "limit".to_string(),
+                AttributeValue::Number(limit.to_string()),
+            );
+        }
+        self.client.put_item(&self.table_name, item)?;
+        Ok(())
+    }
+}
+
+/// `name` and `tenant` in one string, with the name's length first so that no other pair
+/// gives the same string.
+fn scope(name: &str, tenant: &str) -> String {
+    format!("{}:{name}:{tenant}", name.len())
+}
+
+fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The UTC calendar month `time` falls in, like `2024-07`.
+fn month(time: SystemTime) -> String {
+    // Howard Hinnant's civil_from_days, with eras of 400 years starting on March 1st.
+    let days = epoch_seconds(time) / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!("{year:04}-{month:02}")
+}
+
+/// The month before `period`, a month like `2024-07`.
+fn previous_month(period: &str) -> Option<String> {
+    let (year, month) = period.split_once('-')?;
+    let (year, month): (u32, u32) = (year.parse().ok()?, month.parse().ok()?);
+    match month {
+        1 => Some(format!("{:04}-12", year.checked_sub(1)?)),
+        2..=12 => Some(format!("{year:04}-{:02}", month - 1)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn periods_are_utc_months() {
+        let at = |seconds| month(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!("1970-01", at(0));
+        // 2024-02-29T23:59:59Z and a second later.
+        assert_eq!("2024-02", at(1_709_251_199));
+        assert_eq!("2024-03", at(1_709_251_200));
+        // 2023-12-31T23:59:59Z and a second later.
+        assert_eq!("2023-12", at(1_704_067_199));
+        assert_eq!("2024-01", at(1_704_067_200));
+
+        assert_eq!(Some("2024-06"), previous_month("2024-07").as_deref());
+        assert_eq!(Some("2023-12"), previous_month("2024-01").as_deref());
+        assert_eq!(None, previous_month("2024-13"));
+    }
+
+    #[test]
+    fn counters_and_usage() {
+        let counter: Counter = serde_json::from_str(r#"{"used":7}"#).unwrap();
+        assert_eq!((7, 0), (counter.used, counter.exported_at));
+
+        let quota = Quota::new("llm-tokens").limit(100);
+        assert_eq!(
+            "quota:10:llm-tokens:key-42:2024-07",
+            quota.key("key-42", "2024-07")
+        );
+        assert_ne!(
+            Quota::new("a:b").key("c", "2024-07"),
+            Quota::new("a").key("b:c", "2024-07")
+        );
+        let usage = quota.usage_of("key-42", "2024-07".to_string(), 130);
+        assert_eq!(Some(0), usage.remaining());
+        assert_eq!(
+            None,
+            Quota::new("calls").usage_of("a", "p".into(), 5).remaining()
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Retrieval-augmented generation: retrieve, prompt, generate
+//!
+//! [Rag] embeds a question with an [Embedder], retrieves the nearest documents from a
+//! [VectorStore], fills a [PromptTemplate] with them, and asks a [Chat] model for the answer.
+//! Each piece is a trait, so you can swap providers and backends independently.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::embeddings::{CachedEmbedder, OpenAiEmbedder};
+//! use momento_functions_host::rag::{OpenAiChat, Rag};
+//! use momento_functions_host::vector_store::TurbopufferStore;
+//! use std::time::Duration;
+//!
+//! let rag = Rag::new(
+//!     CachedEmbedder::new(OpenAiEmbedder::from_env(), Duration::from_secs(300)),
+//!     TurbopufferStore::from_env().include_attributes(["metadata$title", "text"]),
+//!     OpenAiChat::from_env(),
+//! )
+//! .top_k(8);
+//! match rag.answer("Who won the draft lottery?") {
+//!     Ok(answer) => {
+//!         println!("{}", answer.answer);
+//!         for source in answer.sources {
+//!             println!("  from {} {:?}", source.id, source.text("metadata$title"));
+//!         }
+//!     }
+//!     Err(e) => eprintln!("answer failed: {e}"),
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::embeddings::{EmbedError, Embedder};
+use crate::http::HttpPostError;
+use crate::llm::Chat;
+use crate::vector_store::{VectorMatch, VectorStore, VectorStoreError};
+
+/// An error occurred while asking a chat model.
+#[derive(Debug, thiserror::Error)]
+pub enum ChatError {
+    /// The request to the chat model failed.
+    #[error(transparent)]
+    Http(#[from] HttpPostError<serde_json::Error>),
+    /// The chat model rejected the request.
+    #[error("Chat model returned status {status}: {message}")]
+    Rejected {
+        /// The HTTP status the model provider responded with.
+        status: u16,
+        /// The body of the provider's response.
+        message: String,
+    },
+    /// The chat model's response could not be decoded.
+    #[error("Failed to decode chat response.")]
+    Decode {
+        /// The underlying decoding error.
+        #[from]
+        cause: serde_json::Error,
+    },
+    /// The chat model returned no answer.
+    #[error("Chat model returned no choices")]
+    Empty,
+    /// The chat model reported an error partway through a streamed reply.
+    #[error("Chat stream failed: {message}")]
+    Stream {
+        /// The provider's description of the error.
+        message: String,
+    },
+}
+
+/// An error occurred while answering a question.
+#[derive(Debug, thiserror::Error)]
+pub enum RagError {
+    /// The question could not be embedded.
+    #[error(transparent)]
+    Embed(#[from] EmbedError),
+    /// Documents could not be retrieved.
+    #[error(transparent)]
+    Retrieve(#[from] VectorStoreError),
+    /// The answer could not be generated.
+    #[error(transparent)]
+    Generate(#[from] ChatError),
+    /// The embedder returned no vector for the question.
+    #[error("Embedder returned no vector for the question")]
+    NoEmbedding,
+}
+
+/// Who a [ChatMessage] is from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Instructions for the model.
+    System,
+    /// The person asking.
+    User,
+    /// The model.
+    Assistant,
+}
+
+/// One message in a chat.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Who the message is from.
+    pub role: Role,
+    /// What the message says.
+    pub content: String,
+}
+
+impl ChatMessage {
+    /// A message of instructions for the model.
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::System,
+            content: content.into(),
+        }
+    }
+
+    /// A message from the person asking.
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+}
+
+/// Chat completions from the OpenAI API.
+///
+/// Defaults to `gpt-4o-mini`.
+#[derive(Debug, Clone)]
+pub struct OpenAiChat {
+    pub(crate) api_key: String,
+    pub(crate) model: String,
+    pub(crate) temperature: Option<f32>,
+    pub(crate) max_tokens: Option<u32>,
+}
+
+impl OpenAiChat {
+    /// Create a chat model that authenticates with `api_key`.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "gpt-4o-mini".to_string(),
+            temperature: None,
+            max_tokens: None,
+        }
+    }
+
+    /// Create a chat model with the API key from the `OPENAI_API_KEY` environment variable.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("OPENAI_API_KEY").unwrap_or_default())
+    }
+
+    /// Use a different model, like `gpt-4o`.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// How random the reply is, from 0 to 2. Lower is more focused.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// The longest reply to generate, in tokens.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+}
+
+/// The prompt a [Rag] sends: a system message, and a user message with `{context}` and
+/// `{question}` placeholders.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    system: String,
+    user: String,
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self::new(
+            "Answer the question using only the numbered context passages. Cite the passages \
+             you use like [1]. If the context does not contain the answer, say that you do not \
+             know.",
+            "Context:\n{context}\n\nQuestion: {question}",
+        )
+    }
+}
+
+impl PromptTemplate {
+    /// Create a template. `user` should contain `{context}` and `{question}`.
+    pub fn new(system: impl Into<String>, user: impl Into<String>) -> Self {
+        Self {
+            system: system.into(),
+            user: user.into(),
+        }
+    }
+
+    /// The messages for `question` with `context`.
+    ///
+    /// Placeholders are filled in one pass, so braces in the context or question are left
+    /// alone.
+    pub fn render(&self, context: &str, question: &str) -> Vec<ChatMessage> {
+        let mut user = String::with_capacity(self.user.len() + context.len() + question.len());
+        let mut rest = self.user.as_str();
+        while let Some(open) = rest.find('{') {
+            user.push_str(&rest[..open]);
+            rest = &rest[open..];
+            if let Some(after) = rest.strip_prefix("{context}") {
+                user.push_str(context);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("{question}") {
+                user.push_str(question);
+                rest = after;
+            } else {
+                user.push('{');
+                rest = &rest[1..];
+            }
+        }
+        user.push_str(rest);
+        vec![
+            ChatMessage::system(self.system.clone()),
+            ChatMessage::user(user),
+        ]
+    }
+}
+
+/// An answer from [Rag::answer].
+#[derive(Debug, Clone)]
+pub struct RagAnswer {
+    /// The chat model's answer.
+    pub answer: String,
+    /// The documents the answer was grounded in, in the order they were numbered.
+    pub sources: Vec<VectorMatch>,
+}
+
+/// Answers questions from the documents in a vector store.
+#[derive(Debug, Clone)]
+pub struct Rag<E, S, M> {
+    embedder: E,
+    store: S,
+    model: M,
+    template: PromptTemplate,
+    top_k: usize,
+    text_attribute: String,
+    max_context_chars: usize,
+}
+
+impl<E: Embedder, S: VectorStore, M: Chat> Rag<E, S, M> {
+    /// Answer with `model`, from documents found in `store` by vectors from `embedder`.
+    ///
+    /// By default this retrieves 5 documents, reads their text from the `text` attribute,
+    /// and uses up to 12,000 characters of it.
+    pub fn new(embedder: E, store: S, model: M) -> Self {
+        Self {
+            embedder,
+            store,
+            model,
+            template: PromptTemplate::default(),
+            top_k: 5,
+            text_attribute: "text".to_string(),
+            max_context_chars: 12_000,
+        }
+    }
+
+    /// Use a different prompt.
+    pub fn template(mut self, template: PromptTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// How many documents to retrieve.
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// The document attribute holding the text to answer from. Make sure your store
+    /// returns it.
+    pub fn text_attribute(mut self, text_attribute: impl Into<String>) -> Self {
+        self.text_attribute = text_attribute.into();
+        self
+    }
+
+    /// The most document text to put in the prompt. Documents past the limit are left out,
+    /// and a document that crosses it is cut short.
+    pub fn max_context_chars(mut self, max_context_chars: usize) -> Self {
+        self.max_context_chars = max_context_chars;
+        self
+    }
+
+    /// Find the documents nearest to `question`.
+    pub fn retrieve(&self, question: &str) -> Result<Vec<VectorMatch>, RagError> {
+        let vector = self
+            .embedder
+            .embed(&[question.to_string()])?
+            .pop()
+            .ok_or(RagError::NoEmbedding)?;
+        Ok(self.store.query(&vector, self.top_k)?)
+    }
+
+    /// Answer `question` from the documents nearest to it.
+    pub fn answer(&self, question: &str) -> Result<RagAnswer, RagError> {
+        let (context, sources) = build_context(
+            self.retrieve(question)?,
+            &self.text_attribute,
+            self.max_context_chars,
+        );
+        let messages = self.template.render(&context, question);
+        let answer = self.model.send(&messages.as_slice().into())?.text();
+        Ok(RagAnswer { answer, sources })
+    }
+}
+
+/// Number each document's text into a context block, returning it and the documents it
+/// includes.
+fn build_context(
+    sources: Vec<VectorMatch>,
+    text_attribute: &str,
+    max_chars: usize,
+) -> (String, Vec<VectorMatch>) {
+    let mut context = String::new();
+    let mut remaining = max_chars;
+    let mut used = Vec::new();
+    for source in sources {
+        if remaining == 0 {
+            break;
+        }
+        let Some(text) = source.text(text_attribute).map(str::to_owned) else {
+            continue;
+        };
+        let text = match text.char_indices().nth(remaining) {
+            Some((cut, _)) => &text[..cut],
+            None => text.as_str(),
+        };
+        remaining -= text.chars().count();
+        if !context.is_empty() {
+            context.push_str("\n\n");
+        }
+        context.push_str(&format!("[{}] {text}", used.len() + 1));
+        used.push(source);
+    }
+    (context, used)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn document(id: &str, text: Option<&str>) -> VectorMatch {
+        VectorMatch {
+            id: id.to_string(),
+            distance: None,
+            attributes: text
+                .map(|text| HashMap::from([("text".to_string(), text.into())]))
+                .unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn templates_fill_placeholders_once() {
+        let template = PromptTemplate::new("be brief", "{question} {x} {context} {");
+        let messages = template.render("ctx {question}", "why {context}?");
+        assert_eq!(ChatMessage::system("be brief"), messages[0]);
+        assert_eq!(
+            ChatMessage::user("why {context}? {x} ctx {question} {"),
+            messages[1]
+        );
+        assert_eq!(
+            r#"{"role":"user","content":"hi"}"#,
+            serde_json::to_string(&ChatMessage::user("hi")).unwrap()
+        );
+    }
+
+    #[test]
+    fn context_is_numbered_and_bounded() {
+        let sources = vec![
+            document("a", Some("alpha")),
+            document("b", None),
+            document("c", Some("gamma ray")),
+            document("d", Some("delta")),
+        ];
+        let ids = |used: Vec<VectorMatch>| used.into_iter().map(|m| m.id).collect::<Vec<_>>();
+        let (context, used) = build_context(sources.clone(), "text", 100);
+        assert_eq!("[1] alpha\n\n[2] gamma ray\n\n[3] delta", context);
+        assert_eq!(vec!["a", "c", "d"], ids(used));
+
+        let (context, used) = build_context(sources, "text", 8);
+        assert_eq!("[1] alpha\n\n[2] gam", context);
+        assert_eq!(vec!["a", "c"], ids(used));
+    }
+}
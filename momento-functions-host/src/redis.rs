@@ -1,10 +1,17 @@
 //! Host interfaces for working with redis or valkey
 
+use momento_functions_wit::abi::{self, HostCapability};
 use momento_functions_wit::host::momento::host;
 
 use crate::encoding::{Encode, EncodeError, Extract, ExtractError};
 use crate::redis::RedisSetError::UnexpectedValueResponse;
 
+pub mod script;
+pub mod search;
+mod slot;
+
+pub use slot::{HASH_SLOTS, hash_slot};
+
 /// Redis client for Function host interfaces.
 ///
 /// This client is used to connect to a Redis or Valkey instance that you own.
@@ -56,6 +63,12 @@ pub enum RedisGetError<E: ExtractError> {
         /// The message from Redis
         message: String,
     },
+    /// Redis returned a value of a kind the command does not produce.
+    #[error("Unexpected value response: {value:?}")]
+    UnexpectedValueResponse {
+        /// The value Redis returned.
+        value: host::redis::Value,
+    },
 }
 
 /// An error occurred while setting a redis value.
@@ -104,6 +117,34 @@ pub enum RedisDeleteError {
     },
 }
 
+/// An error occurred while sending a pipe to a redis cluster.
+#[derive(Debug, thiserror::Error)]
+pub enum RedisPipeError {
+    /// An error occurred while calling the host redis function.
+    #[error(transparent)]
+    RedisError(#[from] host::redis::RedisError),
+    /// A single command used keys from more than one hash slot. Redis Cluster cannot run it.
+    ///
+    /// Use hash tags, like `{user1000}.following` and `{user1000}.followers`, to keep keys
+    /// that are used together in one slot. For reading and writing many keys, use
+    /// [RedisClusterClient::mget] and [RedisClusterClient::mset], which split by slot for you.
+    #[error("Command {command} uses keys from multiple hash slots: {slots:?}")]
+    CrossSlot {
+        /// The name of the offending command.
+        command: String,
+        /// The slots its keys hash to.
+        slots: Vec<u16>,
+    },
+    /// Redis returned fewer responses than commands were sent.
+    #[error("Expected {expected} responses from redis, but received {received}")]
+    MissingResponses {
+        /// The number of commands sent.
+        expected: usize,
+        /// The number of responses received.
+        received: usize,
+    },
+}
+
 impl RedisClusterClient {
     /// Makes a new client
     pub fn new_momento_managed(cluster_name: impl Into<String>) -> Self {
@@ -211,6 +252,197 @@ impl RedisClusterClient {
             .command(&host::redis::Command { command, arguments })?;
         Ok(value)
     }
+
+    /// Execute several redis commands, batching them by hash slot.
+    ///
+    /// Commands are grouped by the slot of their keys and each group is sent as one pipe,
+    /// so a pipe of commands that share a slot costs a single round trip. Commands keep
+    /// their order within a slot, and responses are returned in the order the commands
+    /// were given.
+    ///
+    /// A command's keys are taken from its first argument, except for well-known multi-key
+    /// commands like `DEL` or `MSET` and keyless commands like `PING`. A single command whose
+    /// keys span slots is rejected with [RedisPipeError::CrossSlot].
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::redis::{RedisClusterClient, RedisPipeError, Command};
+    /// # fn f(client: &RedisClusterClient) -> Result<(), RedisPipeError> {
+    /// let responses = client.pipe(vec![
+    ///     Command::builder().set("{user1000}.name", "Ada").unwrap().build(),
+    ///     Command::builder().get("{user1000}.name").build(),
+    ///     Command::builder().get("some-other-key").build(),
+    /// ])?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn pipe(&self, commands: Vec<Command>) -> Result<Vec<RedisValue>, RedisPipeError> {
+        abi::require(HostCapability::RedisClusterPipe)
+            .map_err(|e| host::redis::RedisError::Other(e.to_string()))?;
+        // (slot, indices of the commands sent to it). Keyless commands ride along with
+        // whichever group comes first.
+        let mut groups: Vec<(Option<u16>, Vec<usize>)> = Vec::new();
+        for (index, command) in commands.iter().enumerate() {
+            let slots = slot::command_slots(&command.command, &command.arguments);
+            match slots.as_slice() {
+                [] => match groups.first_mut() {
+                    Some((_, indices)) => indices.push(index),
+                    None => groups.push((None, vec![index])),
+                },
+                [slot] => match groups
+                    .iter_mut()
+                    .find(|(s, _)| s.is_none_or(|s| s == *slot))
+                {
+                    Some((group_slot, indices)) => {
+                        *group_slot = Some(*slot);
+                        indices.push(index);
+                    }
+                    None => groups.push((Some(*slot), vec![index])),
+                },
+                _ => {
+                    return Err(RedisPipeError::CrossSlot {
+                        command: command.command.clone(),
+                        slots,
+                    });
+                }
+            }
+        }
+
+        let mut commands: Vec<Option<Command>> = commands.into_iter().map(Some).collect();
+        let mut responses: Vec<Option<RedisValue>> = commands.iter().map(|_| None).collect();
+        for (_, indices) in groups {
+            let batch: Vec<host::redis::Command> = indices
+                .iter()
+                .filter_map(|index| commands[*index].take())
+                .map(|Command { command, arguments }| host::redis::Command { command, arguments })
+                .collect();
+            let stream = self.client.pipe(&batch)?;
+            for (received, index) in indices.iter().enumerate() {
+                match stream.next() {
+                    Some(value) => responses[*index] = Some(value.into()),
+                    None => {
+                        return Err(RedisPipeError::MissingResponses {
+                            expected: indices.len(),
+                            received,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(responses.into_iter().flatten().collect())
+    }
+
+    /// Get many values from Redis by key, splitting the keys into one `MGET` per hash slot.
+    ///
+    /// Values are returned in the same order as the keys. Missing keys are `None`.
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::redis::RedisClusterClient;
+    /// # fn f(client: &RedisClusterClient) {
+    /// match client.mget::<Vec<u8>>(["a", "b", "c"]) {
+    ///     Ok(values) => { /* values[0] is "a" */ }
+    ///     Err(e) => eprintln!("mget failed: {e}"),
+    /// }
+    /// # }
+    /// ```
+    pub fn mget<T: Extract>(
+        &self,
+        keys: impl IntoIterator<Item = impl Into<Vec<u8>>>,
+    ) -> Result<Vec<Option<T>>, RedisGetError<T::Error>> {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(Into::into).collect();
+        let mut values: Vec<Option<T>> = keys.iter().map(|_| None).collect();
+        for indices in group_by_slot(&keys) {
+            let value = self.client.command(&host::redis::Command {
+                command: "mget".to_string(),
+                arguments: indices.iter().map(|index| keys[*index].clone()).collect(),
+            })?;
+            let stream = match value {
+                host::redis::Value::Bulk(stream) => stream,
+                host::redis::Value::SimpleError(message) => {
+                    return Err(RedisGetError::SimpleError { message });
+                }
+                host::redis::Value::Okay => return Err(RedisGetError::UnexpectedOkayResponse),
+                value => return Err(RedisGetError::UnexpectedValueResponse { value }),
+            };
+            for index in indices {
+                values[index] = match stream.next() {
+                    None | Some(host::redis::Value::Nil) => None,
+                    Some(host::redis::Value::Data(data)) => Some(
+                        T::extract(data).map_err(|e| RedisGetError::ExtractFailed { cause: e })?,
+                    ),
+                    Some(host::redis::Value::SimpleError(message)) => {
+                        return Err(RedisGetError::SimpleError { message });
+                    }
+                    Some(host::redis::Value::Bulk(response)) => {
+                        return Err(RedisGetError::UnexpectedBulkResponse { response });
+                    }
+                    Some(value) => return Err(RedisGetError::UnexpectedValueResponse { value }),
+                };
+            }
+        }
+        Ok(values)
+    }
+
+    /// Set many values in Redis, splitting the pairs into one `MSET` per hash slot.
+    ///
+    /// Each `MSET` is atomic, but pairs in different slots are set independently. If you
+    /// need the whole set to be atomic, use hash tags to put the keys in one slot.
+    ///
+    /// ```rust,no_run
+    /// # use momento_functions_host::redis::RedisClusterClient;
+    /// # fn f(client: &RedisClusterClient) {
+    /// match client.mset([("a", b"1".to_vec()), ("b", b"2".to_vec())]) {
+    ///     Ok(()) => {}
+    ///     Err(e) => eprintln!("mset failed: {e}"),
+    /// }
+    /// # }
+    /// ```
+    pub fn mset<T: Encode>(
+        &self,
+        pairs: impl IntoIterator<Item = (impl Into<Vec<u8>>, T)>,
+    ) -> Result<(), RedisSetError<T::Error>> {
+        let mut keys = Vec::new();
+        let mut encoded = Vec::new();
+        for (key, value) in pairs {
+            keys.push(key.into());
+            encoded.push(
+                value
+                    .try_serialize()
+                    .map_err(|e| RedisSetError::EncodeError { cause: e })?
+                    .into(),
+            );
+        }
+        for indices in group_by_slot(&keys) {
+            let arguments = indices
+                .iter()
+                .flat_map(|index| [keys[*index].clone(), std::mem::take(&mut encoded[*index])])
+                .collect();
+            let value = self.client.command(&host::redis::Command {
+                command: "mset".to_string(),
+                arguments,
+            })?;
+            match value {
+                host::redis::Value::Okay => {}
+                host::redis::Value::SimpleError(message) => {
+                    return Err(RedisSetError::SimpleError { message });
+                }
+                e => return Err(UnexpectedValueResponse { value: Some(e) }),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Group key indices by hash slot, in the order each slot first appears.
+fn group_by_slot(keys: &[Vec<u8>]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<(u16, Vec<usize>)> = Vec::new();
+    for (index, key) in keys.iter().enumerate() {
+        let slot = hash_slot(key);
+        match groups.iter_mut().find(|(s, _)| *s == slot) {
+            Some((_, indices)) => indices.push(index),
+            None => groups.push((slot, vec![index])),
+        }
+    }
+    groups.into_iter().map(|(_, indices)| indices).collect()
 }
 
 impl RedisClient {
@@ -349,6 +581,29 @@ impl RedisClient {
     }
 }
 
+/// A client that can execute a single redis command.
+///
+/// This is implemented by both [RedisClient] and [RedisClusterClient], so helpers like
+/// those in [search] work with either.
+pub trait ExecuteCommand {
+    /// Execute one command, returning its response.
+    fn execute(&self, command: Command) -> Result<RedisValue, host::redis::RedisError>;
+}
+
+impl ExecuteCommand for RedisClient {
+    fn execute(&self, command: Command) -> Result<RedisValue, host::redis::RedisError> {
+        self.pipe(vec![command])?.next().ok_or_else(|| {
+            host::redis::RedisError::Other("redis did not return a response".to_string())
+        })
+    }
+}
+
+impl ExecuteCommand for RedisClusterClient {
+    fn execute(&self, command: Command) -> Result<RedisValue, host::redis::RedisError> {
+        self.command(command).map(Into::into)
+    }
+}
+
 /// A raw redis command
 #[derive(Debug, Clone)]
 pub struct Command {
@@ -0,0 +1,199 @@
+//! Lua scripts that run atomically on the Redis or Valkey server
+//!
+//! A script reads and writes several keys without another client running in between, which
+//! is what rate limiters and locks need. [Script] sends the script's body only when the
+//! server does not already have it: it loads the script once per Function instance and then
+//! calls it by its sha with `EVALSHA`, falling back to `EVAL` when a server answers
+//! `NOSCRIPT`, like after a restart or on a cluster node that has not seen it yet.
+//!
+//! It works with any [ExecuteCommand] client: [RedisClient](super::RedisClient) or
+//! [RedisClusterClient](super::RedisClusterClient). In a cluster, every key a script touches
+//! must be in the same hash slot.
+
+use std::sync::OnceLock;
+
+use momento_functions_wit::host::momento::host;
+
+use super::{Command, ExecuteCommand, RedisValue};
+
+/// An error occurred while running a script.
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptError {
+    /// An error occurred while calling the host redis function.
+    #[error(transparent)]
+    RedisError(#[from] host::redis::RedisError),
+    /// Redis returned a simple error, like an error raised by the script.
+    #[error("Simple error returned from redis: {message}")]
+    SimpleError {
+        /// The message from Redis.
+        message: String,
+    },
+    /// Redis returned a response of an unexpected shape.
+    #[error("Unexpected response from redis: {value:?}")]
+    UnexpectedResponse {
+        /// The value Redis returned.
+        value: RedisValue,
+    },
+}
+
+/// A Lua script, and the sha the server knows it by once it has been loaded.
+///
+/// Keep scripts in a static, so the sha is loaded once per Function instance rather than on
+/// every request.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use std::sync::LazyLock;
+///
+/// use momento_functions_host::redis::script::{Script, ScriptError};
+/// use momento_functions_host::redis::{RedisClient, RedisValue};
+///
+/// /// Count a request against a fixed window, returning the count so far.
+/// static RATE_LIMIT: LazyLock<Script> = LazyLock::new(|| {
+///     Script::new(
+///         r#"
+///         local count = redis.call("INCR", KEYS[1])
+///         if count == 1 then
+///             redis.call("PEXPIRE", KEYS[1], ARGV[1])
+///         end
+///         return count
+///         "#,
+///     )
+/// });
+///
+/// fn allow(client: &RedisClient, caller: &str) -> Result<bool, ScriptError> {
+///     let key = format!("rate:{caller}");
+///     match RATE_LIMIT.invoke(client, [key], ["60000"])? {
+///         RedisValue::Int(count) => Ok(count <= 100),
+///         value => Err(ScriptError::UnexpectedResponse { value }),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Script {
+    source: String,
+    sha: OnceLock<String>,
+}
+
+impl Script {
+    /// A script with this Lua source.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            sha: OnceLock::new(),
+        }
+    }
+
+    /// The script's Lua source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The sha the server returned when the script was loaded, if it has been.
+    pub fn sha(&self) -> Option<&str> {
+        self.sha.get().map(String::as_str)
+    }
+
+    /// Run the script with `keys` as `KEYS` and `args` as `ARGV`, returning what it returns.
+    ///
+    /// An error raised by the script is returned as [ScriptError::SimpleError].
+    pub fn invoke(
+        &self,
+        client: &impl ExecuteCommand,
+        keys: impl IntoIterator<Item = impl Into<Vec<u8>>>,
+        args: impl IntoIterator<Item = impl Into<Vec<u8>>>,
+    ) -> Result<RedisValue, ScriptError> {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(Into::into).collect();
+        let args: Vec<Vec<u8>> = args.into_iter().map(Into::into).collect();
+        let sha = match self.sha.get() {
+            Some(sha) => sha,
+            None => {
+                let sha = self.load(client)?;
+                self.sha.get_or_init(|| sha)
+            }
+        };
+        match client.execute(eval_command("EVALSHA", sha, &keys, &args)) {
+            Ok(RedisValue::SimpleError(message)) if is_noscript(&message) => {}
+            Err(host::redis::RedisError::Other(message)) if is_noscript(&message) => {}
+            result => return into_result(result?),
+        }
+        // EVAL also caches the script on the server that runs it.
+        into_result(client.execute(eval_command("EVAL", &self.source, &keys, &args))?)
+    }
+
+    /// Load the script into the server's script cache, returning its sha.
+    fn load(&self, client: &impl ExecuteCommand) -> Result<String, ScriptError> {
+        let command = Command::builder()
+            .any("SCRIPT")
+            .arg("LOAD")
+            .arg(self.source.as_bytes())
+            .build();
+        match into_result(client.execute(command)?)? {
+            RedisValue::Data(sha) => match String::from_utf8(sha) {
+                Ok(sha) => Ok(sha),
+                Err(e) => Err(ScriptError::UnexpectedResponse {
+                    value: RedisValue::Data(e.into_bytes()),
+                }),
+            },
+            RedisValue::SimpleString(sha) => Ok(sha),
+            value => Err(ScriptError::UnexpectedResponse { value }),
+        }
+    }
+}
+
+fn eval_command(command: &str, script: &str, keys: &[Vec<u8>], args: &[Vec<u8>]) -> Command {
+    let mut builder = Command::builder()
+        .any(command)
+        .arg(script.as_bytes())
+        .arg(keys.len().to_string());
+    for argument in keys.iter().chain(args) {
+        builder = builder.arg(argument.clone());
+    }
+    builder.build()
+}
+
+/// Whether the server does not have the script, whether it said so in a reply or the host
+/// reported it as a failure.
+fn is_noscript(message: &str) -> bool {
+    message.contains("NOSCRIPT")
+}
+
+fn into_result(value: RedisValue) -> Result<RedisValue, ScriptError> {
+    match value {
+        RedisValue::SimpleError(message) => Err(ScriptError::SimpleError { message }),
+        value => Ok(value),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::redis::slot::command_keys;
+
+    #[test]
+    fn eval_commands_count_their_keys() {
+        let keys = vec![b"{user}.a".to_vec(), b"{user}.b".to_vec()];
+        let args = vec![b"1".to_vec()];
+        let command = eval_command("EVALSHA", "abc123", &keys, &args);
+        assert_eq!("EVALSHA", command.command);
+        assert_eq!(
+            vec![
+                b"abc123".to_vec(),
+                b"2".to_vec(),
+                keys[0].clone(),
+                keys[1].clone(),
+                args[0].clone()
+            ],
+            command.arguments
+        );
+        assert_eq!(
+            vec![b"{user}.a".as_slice(), b"{user}.b"],
+            command_keys(&command.command, &command.arguments)
+        );
+        assert!(is_noscript("NOSCRIPT No matching script. Please use EVAL."));
+        assert!(!is_noscript(
+            "ERR user_script:1: Script attempted to access a non local key"
+        ));
+    }
+}
@@ -0,0 +1,730 @@
+//! Helpers for Redis and Valkey search indexes
+//!
+//! These wrap the `FT.*` commands so you don't need to hand-assemble their arguments.
+//! They work with any [ExecuteCommand] client: [RedisClient](super::RedisClient) or
+//! [RedisClusterClient](super::RedisClusterClient).
+
+use std::collections::HashMap;
+
+use momento_functions_wit::host::momento::host;
+
+use super::{Command, ExecuteCommand, RedisValue};
+
+/// An error occurred while managing a search index.
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    /// An error occurred while calling the host redis function.
+    #[error(transparent)]
+    RedisError(#[from] host::redis::RedisError),
+    /// Redis returned a simple error.
+    #[error("Simple error returned from redis: {message}")]
+    SimpleError {
+        /// The message from Redis.
+        message: String,
+    },
+    /// Redis returned a response of an unexpected shape.
+    #[error("Unexpected response from redis: {value:?}")]
+    UnexpectedResponse {
+        /// The value Redis returned.
+        value: RedisValue,
+    },
+}
+
+/// What [ensure_index] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnsureIndexOutcome {
+    /// The index already existed, so it was left alone.
+    AlreadyExists,
+    /// The index did not exist, so it was created.
+    Created,
+}
+
+/// The kind of key an index covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexDataType {
+    /// Index hashes written with `HSET`.
+    #[default]
+    Hash,
+    /// Index JSON documents written with `JSON.SET`.
+    Json,
+}
+
+/// The vector index algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorAlgorithm {
+    /// Brute-force search. Exact, and fine for small data sets.
+    Flat,
+    /// Hierarchical Navigable Small World graphs. Approximate, and fast for large data sets.
+    Hnsw,
+}
+
+/// The element type of stored vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VectorType {
+    /// 32-bit floats, written as little-endian bytes.
+    #[default]
+    Float32,
+    /// 64-bit floats, written as little-endian bytes.
+    Float64,
+}
+
+/// How vector similarity is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMetric {
+    /// Cosine distance.
+    #[default]
+    Cosine,
+    /// Euclidean distance.
+    L2,
+    /// Inner product.
+    InnerProduct,
+}
+
+/// A vector field in an index schema.
+///
+/// Start with [VectorField::hnsw] or [VectorField::flat].
+#[derive(Debug, Clone)]
+pub struct VectorField {
+    name: String,
+    algorithm: VectorAlgorithm,
+    dimensions: usize,
+    vector_type: VectorType,
+    distance_metric: DistanceMetric,
+    m: Option<u32>,
+    ef_construction: Option<u32>,
+}
+
+impl VectorField {
+    /// An HNSW vector field of the given dimensions.
+    pub fn hnsw(name: impl Into<String>, dimensions: usize) -> Self {
+        Self::new(name, VectorAlgorithm::Hnsw, dimensions)
+    }
+
+    /// A FLAT vector field of the given dimensions.
+    pub fn flat(name: impl Into<String>, dimensions: usize) -> Self {
+        Self::new(name, VectorAlgorithm::Flat, dimensions)
+    }
+
+    fn new(name: impl Into<String>, algorithm: VectorAlgorithm, dimensions: usize) -> Self {
+        Self {
+            name: name.into(),
+            algorithm,
+            dimensions,
+            vector_type: Default::default(),
+            distance_metric: Default::default(),
+            m: None,
+            ef_construction: None,
+        }
+    }
+
+    /// Set the distance metric. Defaults to cosine.
+    pub fn distance_metric(mut self, distance_metric: DistanceMetric) -> Self {
+        self.distance_metric = distance_metric;
+        self
+    }
+
+    /// Set the element type. Defaults to 32-bit floats.
+    pub fn vector_type(mut self, vector_type: VectorType) -> Self {
+        self.vector_type = vector_type;
+        self
+    }
+
+    /// Set the HNSW `M` parameter: the number of edges per node. Ignored for FLAT fields.
+    pub fn m(mut self, m: u32) -> Self {
+        self.m = Some(m);
+        self
+    }
+
+    /// Set the HNSW `EF_CONSTRUCTION` parameter. Ignored for FLAT fields.
+    pub fn ef_construction(mut self, ef_construction: u32) -> Self {
+        self.ef_construction = Some(ef_construction);
+        self
+    }
+
+    fn arguments(&self) -> Vec<String> {
+        let mut attributes = vec![
+            "TYPE".to_string(),
+            match self.vector_type {
+                VectorType::Float32 => "FLOAT32",
+                VectorType::Float64 => "FLOAT64",
+            }
+            .to_string(),
+            "DIM".to_string(),
+            self.dimensions.to_string(),
+            "DISTANCE_METRIC".to_string(),
+            match self.distance_metric {
+                DistanceMetric::Cosine => "COSINE",
+                DistanceMetric::L2 => "L2",
+                DistanceMetric::InnerProduct => "IP",
+            }
+            .to_string(),
+        ];
+        if self.algorithm == VectorAlgorithm::Hnsw {
+            if let Some(m) = self.m {
+                attributes.extend(["M".to_string(), m.to_string()]);
+            }
+            if let Some(ef_construction) = self.ef_construction {
+                attributes.extend(["EF_CONSTRUCTION".to_string(), ef_construction.to_string()]);
+            }
+        }
+        let mut arguments = vec![
+            self.name.clone(),
+            "VECTOR".to_string(),
+            match self.algorithm {
+                VectorAlgorithm::Flat => "FLAT",
+                VectorAlgorithm::Hnsw => "HNSW",
+            }
+            .to_string(),
+            attributes.len().to_string(),
+        ];
+        arguments.extend(attributes);
+        arguments
+    }
+}
+
+#[derive(Debug, Clone)]
+enum SchemaField {
+    Vector(VectorField),
+    Tag { name: String },
+    Text { name: String },
+    Numeric { name: String },
+}
+
+impl SchemaField {
+    fn arguments(&self) -> Vec<String> {
+        match self {
+            SchemaField::Vector(field) => field.arguments(),
+            SchemaField::Tag { name } => vec![name.clone(), "TAG".to_string()],
+            SchemaField::Text { name } => vec![name.clone(), "TEXT".to_string()],
+            SchemaField::Numeric { name } => vec![name.clone(), "NUMERIC".to_string()],
+        }
+    }
+}
+
+/// A description of a search index, used to create it.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_host::redis::RedisClient;
+/// use momento_functions_host::redis::search::{ensure_index, IndexSpec, VectorField, DistanceMetric};
+///
+/// let redis = RedisClient::new("valkey://my.valkey.instance:6379");
+/// let spec = IndexSpec::new("document_index")
+///     .prefix("doc:")
+///     .vector_field(VectorField::hnsw("vector", 1536).distance_metric(DistanceMetric::Cosine))
+///     .tag_field("category");
+/// match ensure_index(&redis, &spec) {
+///     Ok(outcome) => println!("index: {outcome:?}"),
+///     Err(e) => eprintln!("failed to ensure index: {e}"),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexSpec {
+    name: String,
+    data_type: IndexDataType,
+    prefixes: Vec<String>,
+    fields: Vec<SchemaField>,
+}
+
+impl IndexSpec {
+    /// Start describing an index with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            data_type: Default::default(),
+            prefixes: Vec::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// The name of the index.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the kind of key to index. Defaults to hashes.
+    pub fn data_type(mut self, data_type: IndexDataType) -> Self {
+        self.data_type = data_type;
+        self
+    }
+
+    /// Only index keys starting with this prefix. May be called more than once.
+    /// Without a prefix, every key of the indexed data type is indexed.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Add a vector field to the schema.
+    pub fn vector_field(mut self, field: VectorField) -> Self {
+        self.fields.push(SchemaField::Vector(field));
+        self
+    }
+
+    /// Add a tag field to the schema, for exact-match filters like `@category:{news}`.
+    pub fn tag_field(mut self, name: impl Into<String>) -> Self {
+        self.fields.push(SchemaField::Tag { name: name.into() });
+        self
+    }
+
+    /// Add a full-text field to the schema.
+    pub fn text_field(mut self, name: impl Into<String>) -> Self {
+        self.fields.push(SchemaField::Text { name: name.into() });
+        self
+    }
+
+    /// Add a numeric field to the schema, for range filters like `@price:[0 100]`.
+    pub fn numeric_field(mut self, name: impl Into<String>) -> Self {
+        self.fields.push(SchemaField::Numeric { name: name.into() });
+        self
+    }
+
+    /// The `FT.CREATE` command for this index.
+    pub fn create_command(&self) -> Command {
+        let mut command = Command::builder()
+            .any("FT.CREATE")
+            .arg(self.name.clone())
+            .arg("ON")
+            .arg(match self.data_type {
+                IndexDataType::Hash => "HASH",
+                IndexDataType::Json => "JSON",
+            });
+        if !self.prefixes.is_empty() {
+            command = command.arg("PREFIX").arg(self.prefixes.len().to_string());
+            for prefix in &self.prefixes {
+                command = command.arg(prefix.clone());
+            }
+        }
+        command = command.arg("SCHEMA");
+        for argument in self.fields.iter().flat_map(SchemaField::arguments) {
+            command = command.arg(argument);
+        }
+        command.build()
+    }
+}
+
+/// Create an index if it does not exist yet.
+///
+/// This checks for the index with `FT.INFO`, and creates it with `FT.CREATE` only
+/// when Redis says the index does not exist. Any other failure of `FT.INFO` is returned.
+/// An existing index is not compared against `spec`.
+pub fn ensure_index(
+    client: &impl ExecuteCommand,
+    spec: &IndexSpec,
+) -> Result<EnsureIndexOutcome, SearchError> {
+    let info = client.execute(Command::builder().any("FT.INFO").arg(spec.name()).build());
+    match info {
+        Ok(RedisValue::Bulk(_)) => return Ok(EnsureIndexOutcome::AlreadyExists),
+        Ok(RedisValue::SimpleError(message)) if is_missing_index(&message) => {
+            log::debug!("index {} not found, creating it: {message}", spec.name());
+        }
+        Ok(RedisValue::SimpleError(message)) => return Err(SearchError::SimpleError { message }),
+        Ok(value) => return Err(SearchError::UnexpectedResponse { value }),
+        Err(e) => return Err(SearchError::RedisError(e)),
+    }
+    match client.execute(spec.create_command())? {
+        RedisValue::Okay => Ok(EnsureIndexOutcome::Created),
+        RedisValue::SimpleString(s) if s.eq_ignore_ascii_case("ok") => {
+            Ok(EnsureIndexOutcome::Created)
+        }
+        RedisValue::SimpleError(message) => Err(SearchError::SimpleError { message }),
+        value => Err(SearchError::UnexpectedResponse { value }),
+    }
+}
+
+/// Redis and Valkey word the error for a missing index differently.
+fn is_missing_index(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("unknown index") || message.contains("no such index")
+}
+
+/// Drop an index with `FT.DROPINDEX`.
+///
+/// When `delete_documents` is true, the indexed keys are deleted too.
+pub fn drop_index(
+    client: &impl ExecuteCommand,
+    name: impl Into<String>,
+    delete_documents: bool,
+) -> Result<(), SearchError> {
+    let mut command = Command::builder().any("FT.DROPINDEX").arg(name.into());
+    if delete_documents {
+        command = command.arg("DD");
+    }
+    match client.execute(command.build())? {
+        RedisValue::Okay => Ok(()),
+        RedisValue::SimpleString(s) if s.eq_ignore_ascii_case("ok") => Ok(()),
+        RedisValue::SimpleError(message) => Err(SearchError::SimpleError { message }),
+        value => Err(SearchError::UnexpectedResponse { value }),
+    }
+}
+
+/// A K-nearest-neighbors vector search, optionally narrowed by a pre-filter.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_host::redis::RedisClient;
+/// use momento_functions_host::redis::search::{search, KnnQuery};
+///
+/// let redis = RedisClient::new("valkey://my.valkey.instance:6379");
+/// let embedding: Vec<f32> = vec![0.1, 0.2, 0.3];
+/// let query = KnnQuery::new("document_index", "vector", &embedding, 5)
+///     .filter("@category:{news}")
+///     .return_fields(["title", "url"]);
+/// match search(&redis, &query) {
+///     Ok(results) => {
+///         for document in results.documents {
+///             println!("{} {:?} {:?}", document.id, document.score, document.field("title"));
+///         }
+///     }
+///     Err(e) => eprintln!("search failed: {e}"),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct KnnQuery {
+    index: String,
+    vector_field: String,
+    vector: Vec<u8>,
+    k: usize,
+    filter: Option<String>,
+    return_fields: Vec<String>,
+    score_field: String,
+}
+
+impl KnnQuery {
+    /// Search `index` for the `k` nearest neighbors of `vector` in `vector_field`.
+    ///
+    /// The vector is sent as little-endian 32-bit floats, matching [VectorType::Float32].
+    pub fn new(
+        index: impl Into<String>,
+        vector_field: impl Into<String>,
+        vector: &[f32],
+        k: usize,
+    ) -> Self {
+        Self::new_raw(
+            index,
+            vector_field,
+            vector.iter().copied().flat_map(f32::to_le_bytes).collect(),
+            k,
+        )
+    }
+
+    /// Like [KnnQuery::new], but with an already-encoded vector (for example, one read
+    /// back from the cache, or 64-bit floats).
+    pub fn new_raw(
+        index: impl Into<String>,
+        vector_field: impl Into<String>,
+        vector: Vec<u8>,
+        k: usize,
+    ) -> Self {
+        Self {
+            index: index.into(),
+            vector_field: vector_field.into(),
+            vector,
+            k,
+            filter: None,
+            return_fields: Vec::new(),
+            score_field: "__vector_score".to_string(),
+        }
+    }
+
+    /// Only consider documents matching this query expression, like `@category:{news}`
+    /// or `@price:[0 100]`. Without a filter, every document is considered.
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Only return these fields of each document. Without this, every field is returned,
+    /// including the (large) vector itself.
+    pub fn return_fields(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.return_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The name the distance is returned as. Defaults to `__vector_score`.
+    pub fn score_field(mut self, score_field: impl Into<String>) -> Self {
+        self.score_field = score_field.into();
+        self
+    }
+
+    /// The `FT.SEARCH` command for this query.
+    pub fn command(&self) -> Command {
+        let filter = match &self.filter {
+            Some(filter) => format!("({filter})"),
+            None => "*".to_string(),
+        };
+        let mut command = Command::builder()
+            .any("FT.SEARCH")
+            .arg(self.index.clone())
+            .arg(format!(
+                "{filter}=>[KNN {} @{} $query_vector AS {}]",
+                self.k, self.vector_field, self.score_field
+            ))
+            .arg("PARAMS")
+            .arg("2")
+            .arg("query_vector")
+            .arg(self.vector.clone())
+            .arg("SORTBY")
+            .arg(self.score_field.clone());
+        if !self.return_fields.is_empty() {
+            command = command
+                .arg("RETURN")
+                .arg((self.return_fields.len() + 1).to_string())
+                .arg(self.score_field.clone());
+            for field in &self.return_fields {
+                command = command.arg(field.clone());
+            }
+        }
+        command
+            .arg("LIMIT")
+            .arg("0")
+            .arg(self.k.to_string())
+            .arg("DIALECT")
+            .arg("2")
+            .build()
+    }
+}
+
+/// The results of a [search].
+#[derive(Debug)]
+pub struct SearchResults {
+    /// The total number of matching documents reported by the server.
+    pub total: i64,
+    /// The returned documents, nearest first.
+    pub documents: Vec<SearchDocument>,
+}
+
+/// A document returned by a [search].
+#[derive(Debug)]
+pub struct SearchDocument {
+    /// The key of the document.
+    pub id: String,
+    /// The distance from the query vector, when the server returned it.
+    pub score: Option<f32>,
+    /// The returned fields of the document, other than the score.
+    pub fields: HashMap<String, Vec<u8>>,
+}
+
+impl SearchDocument {
+    /// Get a field as a string, if it is present and valid UTF-8.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .get(name)
+            .and_then(|value| std::str::from_utf8(value).ok())
+    }
+}
+
+/// Run a [KnnQuery] and parse its results.
+pub fn search(
+    client: &impl ExecuteCommand,
+    query: &KnnQuery,
+) -> Result<SearchResults, SearchError> {
+    let response = match client.execute(query.command())? {
+        RedisValue::Bulk(response) => response,
+        RedisValue::SimpleError(message) => return Err(SearchError::SimpleError { message }),
+        value => return Err(SearchError::UnexpectedResponse { value }),
+    };
+    parse_search_results(response, &query.score_field)
+}
+
+fn parse_search_results(
+    response: impl Iterator<Item = RedisValue>,
+    score_field: &str,
+) -> Result<SearchResults, SearchError> {
+    let mut response = response.peekable();
+    let total = match response.next() {
+        Some(RedisValue::Int(total)) => total,
+        Some(value) => return Err(SearchError::UnexpectedResponse { value }),
+        None => {
+            return Err(SearchError::UnexpectedResponse {
+                value: RedisValue::Nil,
+            });
+        }
+    };
+    let mut documents = Vec::new();
+    while let Some(id) = response.next() {
+        let id = match id {
+            RedisValue::Data(id) => String::from_utf8_lossy(&id).into_owned(),
+            RedisValue::SimpleString(id) => id,
+            value => return Err(SearchError::UnexpectedResponse { value }),
+        };
+        let mut document = SearchDocument {
+            id,
+            score: None,
+            fields: HashMap::new(),
+        };
+        // A search with NOCONTENT returns only ids, so the field list is optional.
+        if let Some(RedisValue::Bulk(fields)) =
+            response.next_if(|value| matches!(value, RedisValue::Bulk(_)))
+        {
+            let mut fields = fields.map(|value| match value {
+                RedisValue::Data(data) => Ok(data),
+                RedisValue::SimpleString(s) => Ok(s.into_bytes()),
+                value => Err(SearchError::UnexpectedResponse { value }),
+            });
+            while let (Some(name), Some(value)) = (fields.next(), fields.next()) {
+                let name = String::from_utf8_lossy(&name?).into_owned();
+                let value = value?;
+                if name == score_field {
+                    document.score = std::str::from_utf8(&value)
+                        .ok()
+                        .and_then(|score| score.parse().ok());
+                } else {
+                    document.fields.insert(name, value);
+                }
+            }
+        }
+        documents.push(document);
+    }
+    Ok(SearchResults { total, documents })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Answers each command with the next canned response, recording what was sent.
+    struct Scripted {
+        responses: RefCell<Vec<Result<RedisValue, host::redis::RedisError>>>,
+        sent: RefCell<Vec<String>>,
+    }
+
+    impl Scripted {
+        fn new(mut responses: Vec<Result<RedisValue, host::redis::RedisError>>) -> Self {
+            responses.reverse();
+            Self {
+                responses: RefCell::new(responses),
+                sent: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl ExecuteCommand for Scripted {
+        fn execute(&self, command: Command) -> Result<RedisValue, host::redis::RedisError> {
+            self.sent.borrow_mut().push(command.command.clone());
+            self.responses.borrow_mut().pop().expect("a response")
+        }
+    }
+
+    fn arguments(command: Command) -> Vec<String> {
+        command
+            .arguments
+            .into_iter()
+            .map(|a| String::from_utf8(a).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn only_a_missing_index_is_created() {
+        let spec = IndexSpec::new("document_index").tag_field("category");
+
+        let missing = Scripted::new(vec![
+            Ok(RedisValue::SimpleError("Unknown index name".to_string())),
+            Ok(RedisValue::Okay),
+        ]);
+        assert_eq!(
+            EnsureIndexOutcome::Created,
+            ensure_index(&missing, &spec).unwrap()
+        );
+        assert_eq!(vec!["FT.INFO", "FT.CREATE"], *missing.sent.borrow());
+
+        let denied = Scripted::new(vec![Ok(RedisValue::SimpleError(
+            "NOPERM this user has no permissions".to_string(),
+        ))]);
+        assert!(matches!(
+            ensure_index(&denied, &spec),
+            Err(SearchError::SimpleError { .. })
+        ));
+        assert_eq!(vec!["FT.INFO"], *denied.sent.borrow());
+
+        let unreachable = Scripted::new(vec![Err(host::redis::RedisError::Other(
+            "connection refused".to_string(),
+        ))]);
+        assert!(matches!(
+            ensure_index(&unreachable, &spec),
+            Err(SearchError::RedisError(_))
+        ));
+        assert_eq!(vec!["FT.INFO"], *unreachable.sent.borrow());
+    }
+
+    #[test]
+    fn create_command_splits_every_argument() {
+        let spec = IndexSpec::new("document_index")
+            .prefix("doc:")
+            .vector_field(VectorField::hnsw("vector", 3).m(16))
+            .tag_field("category");
+        let command = spec.create_command();
+        assert_eq!("FT.CREATE", command.command);
+        assert_eq!(
+            vec![
+                "document_index",
+                "ON",
+                "HASH",
+                "PREFIX",
+                "1",
+                "doc:",
+                "SCHEMA",
+                "vector",
+                "VECTOR",
+                "HNSW",
+                "8",
+                "TYPE",
+                "FLOAT32",
+                "DIM",
+                "3",
+                "DISTANCE_METRIC",
+                "COSINE",
+                "M",
+                "16",
+                "category",
+                "TAG",
+            ],
+            arguments(command)
+        );
+    }
+
+    #[test]
+    fn knn_command_includes_filter_and_return_fields() {
+        let command = KnnQuery::new("idx", "vector", &[1.0], 3)
+            .filter("@tag:{a}")
+            .return_fields(["title"])
+            .command();
+        let arguments: Vec<Vec<u8>> = command.arguments;
+        assert_eq!(
+            b"(@tag:{a})=>[KNN 3 @vector $query_vector AS __vector_score]".to_vec(),
+            arguments[1]
+        );
+        assert_eq!(1.0f32.to_le_bytes().to_vec(), arguments[5]);
+        assert_eq!(
+            vec![
+                b"RETURN".to_vec(),
+                b"2".to_vec(),
+                b"__vector_score".to_vec(),
+                b"title".to_vec()
+            ],
+            arguments[8..12].to_vec()
+        );
+    }
+
+    #[test]
+    fn parses_nocontent_search_results() {
+        // Field lists arrive as host response streams, so only the id-only shape can be built here.
+        let results = parse_search_results(
+            vec![
+                RedisValue::Int(2),
+                RedisValue::Data(b"doc:1".to_vec()),
+                RedisValue::Data(b"doc:2".to_vec()),
+            ]
+            .into_iter(),
+            "__vector_score",
+        )
+        .unwrap();
+        assert_eq!(2, results.total);
+        let ids: Vec<&str> = results.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(vec!["doc:1", "doc:2"], ids);
+        assert!(results.documents[0].score.is_none());
+    }
+}
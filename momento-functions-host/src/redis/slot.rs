@@ -0,0 +1,144 @@
+//! Redis Cluster hash slot routing
+
+/// The number of hash slots in a Redis Cluster.
+pub const HASH_SLOTS: u16 = 16384;
+
+/// Commands whose every argument is a key.
+const ALL_KEYS: &[&str] = &[
+    "DEL", "EXISTS", "MGET", "PFCOUNT", "SDIFF", "SINTER", "SUNION", "TOUCH", "UNLINK", "WATCH",
+];
+
+/// Commands whose arguments alternate between a key and a value.
+const ALTERNATING_KEYS: &[&str] = &["MSET", "MSETNX"];
+
+/// Commands whose second argument counts the keys that follow it, like
+/// `EVAL script numkeys key [key ...] arg [arg ...]`.
+const COUNTED_KEYS: &[&str] = &[
+    "EVAL",
+    "EVALSHA",
+    "EVAL_RO",
+    "EVALSHA_RO",
+    "FCALL",
+    "FCALL_RO",
+];
+
+/// Commands that do not operate on a key, so they can be sent to any node.
+const NO_KEYS: &[&str] = &[
+    "CLIENT", "CLUSTER", "COMMAND", "CONFIG", "DBSIZE", "ECHO", "FUNCTION", "INFO", "PING",
+    "SCRIPT", "TIME",
+];
+
+/// Compute the Redis Cluster hash slot for a key.
+///
+/// If the key contains a hash tag, like `{user1000}.following`, only the text between the
+/// first `{` and the next `}` is hashed. Use hash tags to keep related keys in the same slot,
+/// so they can be used together in multi-key commands and pipes.
+///
+/// ```rust
+/// use momento_functions_host::redis::hash_slot;
+///
+/// assert_eq!(hash_slot("{user1000}.following"), hash_slot("{user1000}.followers"));
+/// ```
+pub fn hash_slot(key: impl AsRef<[u8]>) -> u16 {
+    let key = key.as_ref();
+    let hashed = match key.iter().position(|&b| b == b'{') {
+        Some(open) => match key[open + 1..].iter().position(|&b| b == b'}') {
+            // An empty tag like `{}` hashes the whole key.
+            Some(0) | None => key,
+            Some(length) => &key[open + 1..open + 1 + length],
+        },
+        None => key,
+    };
+    crc16(hashed) % HASH_SLOTS
+}
+
+/// The keys a command operates on, as best as can be determined from its name.
+///
+/// Commands not known to be multi-key or keyless are assumed to take their key as the
+/// first argument, which is true for the vast majority of Redis commands.
+pub(crate) fn command_keys<'a>(command: &str, arguments: &'a [Vec<u8>]) -> Vec<&'a [u8]> {
+    let name = command.to_ascii_uppercase();
+    if ALL_KEYS.contains(&name.as_str()) {
+        arguments.iter().map(Vec::as_slice).collect()
+    } else if ALTERNATING_KEYS.contains(&name.as_str()) {
+        arguments.iter().step_by(2).map(Vec::as_slice).collect()
+    } else if COUNTED_KEYS.contains(&name.as_str()) {
+        let count = arguments
+            .get(1)
+            .and_then(|count| std::str::from_utf8(count).ok())
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0);
+        arguments
+            .iter()
+            .skip(2)
+            .take(count)
+            .map(Vec::as_slice)
+            .collect()
+    } else if NO_KEYS.contains(&name.as_str()) || name.starts_with("FT.") {
+        Vec::new()
+    } else {
+        arguments.first().map(Vec::as_slice).into_iter().collect()
+    }
+}
+
+/// The distinct slots of a command's keys, in the order they first appear.
+pub(crate) fn command_slots(command: &str, arguments: &[Vec<u8>]) -> Vec<u16> {
+    let mut slots: Vec<u16> = Vec::new();
+    for slot in command_keys(command, arguments).into_iter().map(hash_slot) {
+        if !slots.contains(&slot) {
+            slots.push(slot);
+        }
+    }
+    slots
+}
+
+/// CRC16-CCITT (XMODEM), as specified by Redis Cluster.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_match_redis() {
+        assert_eq!(0x31c3, crc16(b"123456789"));
+        assert_eq!(12182, hash_slot("foo"));
+        assert_eq!(hash_slot("user1000"), hash_slot("{user1000}.following"));
+        assert_eq!(hash_slot("{}.a"), crc16(b"{}.a") % HASH_SLOTS);
+    }
+
+    #[test]
+    fn keys_are_found_by_command_shape() {
+        let arguments = vec![b"a".to_vec(), b"1".to_vec(), b"b".to_vec(), b"2".to_vec()];
+        assert_eq!(
+            vec![b"a".as_slice(), b"b"],
+            command_keys("mset", &arguments)
+        );
+        assert_eq!(4, command_keys("DEL", &arguments).len());
+        assert_eq!(vec![b"a".as_slice()], command_keys("hset", &arguments));
+        assert!(command_keys("FT.SEARCH", &arguments).is_empty());
+
+        let eval: Vec<Vec<u8>> = ["return 1", "2", "k1", "k2", "arg"]
+            .iter()
+            .map(|argument| argument.as_bytes().to_vec())
+            .collect();
+        assert_eq!(
+            vec![b"k1".as_slice(), b"k2"],
+            command_keys("evalsha", &eval)
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+/// Tracks when a periodically refreshed value was last read from its source.
+///
+/// The interval restarts on every attempt, whether or not the read succeeded, so a broken
+/// source is read once per interval rather than on every call. Callers keep their last good
+/// value when a read fails.
+#[derive(Debug)]
+pub(crate) struct RefreshTimer {
+    attempted_at: Option<Instant>,
+}
+
+impl RefreshTimer {
+    /// A timer that is due right away.
+    pub(crate) const fn new() -> Self {
+        Self { attempted_at: None }
+    }
+
+    /// True when the value should be read again. Restarts the interval when it is.
+    pub(crate) fn due(&mut self, interval: Duration) -> bool {
+        let due = self
+            .attempted_at
+            .is_none_or(|attempted_at| interval <= attempted_at.elapsed());
+        if due {
+            self.restart();
+        }
+        due
+    }
+
+    /// Restart the interval, for a value that was just read some other way.
+    pub(crate) fn restart(&mut self) {
+        self.attempted_at = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_once_per_interval() {
+        let mut timer = RefreshTimer::new();
+        assert!(timer.due(Duration::from_secs(60)));
+        assert!(!timer.due(Duration::from_secs(60)));
+        assert!(timer.due(Duration::ZERO));
+
+        let mut timer = RefreshTimer::new();
+        timer.restart();
+        assert!(!timer.due(Duration::from_secs(60)));
+    }
+}
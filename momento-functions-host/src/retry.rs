@@ -0,0 +1,220 @@
+//! Retrying host calls with jittered exponential backoff
+//!
+//! [with_backoff] retries any call whose error is a [HostError], and only while the error is
+//! [retryable](HostError::is_retryable): throttling and unavailability are retried, while a
+//! malformed request or a permissions problem is returned right away. It never sleeps past
+//! the invocation's deadline, so a struggling dependency turns into an error your Function
+//! can still handle rather than a timed out invocation.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::retry::{self, RetryPolicy};
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy::new()
+//!     .max_attempts(5)
+//!     .initial_backoff(Duration::from_millis(20));
+//! match retry::with_backoff(&policy, || momento_functions_host::cache::get::<Vec<u8>>("my_key")) {
+//!     Ok(value) => { /* use value */ }
+//!     Err(e) => eprintln!("cache get failed after retries: {e}"),
+//! }
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, SystemTime};
+
+use crate::HostError;
+
+/// How and how long to retry.
+///
+/// The default policy makes up to 3 attempts, waiting a random time of up to 50 milliseconds
+/// before the second and up to 100 before the third. Retries stop at the invocation deadline
+/// when the host provides one.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    deadline: Option<SystemTime>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            deadline: invocation_deadline(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most times to make the call, including the first. `1` never retries.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// The longest wait before the first retry. Each retry after it may wait twice as long.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// The longest wait before any retry.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Do not retry if the wait would end after `deadline`. This replaces the invocation
+    /// deadline, so use it to leave time to do something else when retries run out.
+    pub fn deadline(mut self, deadline: SystemTime) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// The wait before retry number `retry`, starting at 0, given a random number.
+    ///
+    /// This is "full jitter": a uniformly random wait up to the exponential backoff, which
+    /// spreads retries from many instances out instead of having them arrive together.
+    fn backoff(&self, retry: u32, random: u64) -> Duration {
+        let ceiling = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        let ceiling_nanos = u64::try_from(ceiling.as_nanos()).unwrap_or(u64::MAX);
+        Duration::from_nanos(random % ceiling_nanos.saturating_add(1))
+    }
+}
+
+/// When the current invocation times out, if the host provides it.
+pub fn invocation_deadline() -> Option<SystemTime> {
+    let epoch_millis: u64 = std::env::var("__INVOCATION_DEADLINE_EPOCH_MILLIS")
+        .ok()?
+        .parse()
+        .ok()?;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_millis(epoch_millis))
+}
+
+/// Make a call, retrying it with backoff while it fails with a retryable error.
+///
+/// Returns the first success, the first error that is not retryable, or the last error once
+/// the policy's attempts run out or the next wait would pass its deadline.
+pub fn with_backoff<T, E: HostError>(
+    policy: &RetryPolicy,
+    mut call: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let random = RandomState::new();
+    let mut retry = 0;
+    loop {
+        let error = match call() {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        if !error.is_retryable() || policy.max_attempts <= retry + 1 {
+            return Err(error);
+        }
+        let wait = policy.backoff(retry, random.hash_one(retry));
+        if let Some(deadline) = policy.deadline
+            && deadline <= SystemTime::now() + wait
+        {
+            return Err(error);
+        }
+        std::thread::sleep(wait);
+        retry += 1;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("{0:?}")]
+    struct TestError(ErrorKind);
+
+    impl HostError for TestError {
+        fn kind(&self) -> ErrorKind {
+            self.0
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(3),
+            deadline: None,
+        }
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let policy = policy();
+        for random in [0, 1, 12345, u64::MAX] {
+            assert!(policy.backoff(0, random) <= Duration::from_millis(1));
+            assert!(policy.backoff(1, random) <= Duration::from_millis(2));
+            assert!(policy.backoff(5, random) <= Duration::from_millis(3));
+            assert!(policy.backoff(100, random) <= Duration::from_millis(3));
+        }
+        assert_eq!(
+            Duration::from_millis(2),
+            policy.backoff(1, 2_000_000 + 2 * 2_000_001)
+        );
+    }
+
+    #[test]
+    fn only_retryable_errors_are_retried() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_backoff(&policy(), || {
+            calls += 1;
+            Err(TestError(ErrorKind::Throttled))
+        });
+        assert!(result.is_err());
+        assert_eq!(4, calls);
+
+        let mut calls = 0;
+        let result: Result<(), _> = with_backoff(&policy(), || {
+            calls += 1;
+            Err(TestError(ErrorKind::InvalidRequest))
+        });
+        assert!(result.is_err());
+        assert_eq!(1, calls);
+
+        let mut calls = 0;
+        let result = with_backoff(&policy(), || {
+            calls += 1;
+            if calls < 3 {
+                Err(TestError(ErrorKind::Unavailable))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(3, result.unwrap());
+    }
+
+    #[test]
+    fn retries_stop_at_the_deadline() {
+        let policy = policy()
+            .initial_backoff(Duration::from_secs(60))
+            .max_backoff(Duration::from_secs(60))
+            .deadline(SystemTime::now());
+        let mut calls = 0;
+        let result: Result<(), _> = with_backoff(&policy, || {
+            calls += 1;
+            Err(TestError(ErrorKind::Unavailable))
+        });
+        assert!(result.is_err());
+        assert_eq!(1, calls);
+    }
+}
@@ -0,0 +1,283 @@
+//! Gradual rollout of a new code path inside one Function
+//!
+//! Ship the old and new logic in the same build, and let a [Rollout] decide which one each
+//! caller gets from a weight: the percentage of callers that take the new path. The weight
+//! is read from the cache, the attached configuration document, or an environment variable,
+//! and refreshed after the refresh interval, so it can be raised or rolled back without a
+//! redeploy.
+//!
+//! Callers are bucketed the same way as [crate::flags]: a caller always lands in the same
+//! bucket for a given rollout, on every instance, so raising the weight only ever moves
+//! callers from the old path to the new one.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::rollout::{Rollout, WeightSource};
+//!
+//! static PRICING_V2: Rollout =
+//!     Rollout::new("pricing-v2", WeightSource::Cache("rollout:pricing-v2"));
+//!
+//! fn old_price(cart: &[u64]) -> u64 { cart.iter().sum() }
+//! fn new_price(cart: &[u64]) -> u64 { cart.iter().sum::<u64>() * 95 / 100 }
+//!
+//! let customer_id = "customer-42";
+//! let cart = [1200, 800];
+//! let price = PRICING_V2.select(customer_id, || old_price(&cart), || new_price(&cart));
+//! ```
+
+use std::convert::Infallible;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::cache::CacheGetError;
+use crate::config_store::ConfigStoreError;
+use crate::flags::{BUCKETS, bucket};
+use crate::refresh::RefreshTimer;
+
+/// Where a [Rollout] reads its weight from.
+///
+/// The weight is a percentage from 0 to 100, like `25` or `2.5`. Values outside that range
+/// are clamped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightSource {
+    /// A fixed weight, useful for tests and defaults.
+    Fixed(f64),
+    /// The text of the cache item at this key.
+    Cache(&'static str),
+    /// This top-level field of the attached configuration document. See
+    /// [crate::config_store].
+    ConfigDocument(&'static str),
+    /// This environment variable.
+    Env(&'static str),
+}
+
+/// An error occurred while reading a [Rollout]'s weight.
+#[derive(Debug, thiserror::Error)]
+pub enum RolloutError {
+    /// The cache item holding the weight does not exist.
+    #[error("{key} is not in the cache")]
+    NotCached {
+        /// The cache key.
+        key: &'static str,
+    },
+    /// The cache could not be read.
+    #[error(transparent)]
+    Cache(#[from] CacheGetError<Infallible>),
+    /// The configuration document could not be read.
+    #[error(transparent)]
+    ConfigStore(#[from] ConfigStoreError),
+    /// The configuration document has no such field.
+    #[error("{field} is not in the configuration document")]
+    MissingField {
+        /// The field.
+        field: &'static str,
+    },
+    /// The configuration document's field holds something other than a number.
+    #[error("{field} is not a number: {value}")]
+    NotANumber {
+        /// The field.
+        field: &'static str,
+        /// What the field holds.
+        value: serde_json::Value,
+    },
+    /// The environment variable could not be read.
+    #[error("{variable}: {cause}")]
+    Env {
+        /// The environment variable.
+        variable: &'static str,
+        /// Why it could not be read.
+        cause: std::env::VarError,
+    },
+    /// The weight is not a percentage.
+    #[error("expected a percentage: {text:?}")]
+    NotAPercentage {
+        /// The text that was read.
+        text: String,
+    },
+}
+
+/// Which code path a caller takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Path {
+    /// The existing logic.
+    Old,
+    /// The logic being rolled out.
+    New,
+}
+
+struct Weight {
+    percent: f64,
+    refresh: RefreshTimer,
+}
+
+/// Routes a percentage of callers to a new code path.
+///
+/// Until the weight has been read successfully, every caller takes the old path. When a
+/// refresh fails, the last weight keeps being used and the failure is logged.
+pub struct Rollout {
+    name: &'static str,
+    source: WeightSource,
+    refresh_interval: Duration,
+    weight: Mutex<Weight>,
+}
+
+impl Rollout {
+    /// A rollout named `name`, reading its weight from `source` every 30 seconds.
+    ///
+    /// The name picks the buckets, so two rollouts with different names split callers
+    /// independently. Renaming a rollout reshuffles its callers.
+    pub const fn new(name: &'static str, source: WeightSource) -> Self {
+        Self {
+            name,
+            source,
+            refresh_interval: Duration::from_secs(30),
+            weight: Mutex::new(Weight {
+                percent: 0.0,
+                refresh: RefreshTimer::new(),
+            }),
+        }
+    }
+
+    /// Read the weight again after `refresh_interval`.
+    pub const fn refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// The percentage of callers taking the new path.
+    pub fn percent(&self) -> f64 {
+        let mut weight = self
+            .weight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if weight.refresh.due(self.refresh_interval) {
+            match read(self.source) {
+                Ok(percent) => weight.percent = percent,
+                Err(e) => log::warn!("failed to read the weight of rollout {}: {e}", self.name),
+            }
+        }
+        weight.percent
+    }
+
+    /// Read the weight now, rather than waiting for the refresh interval.
+    pub fn refresh(&self) -> Result<f64, RolloutError> {
+        let percent = read(self.source)?;
+        let mut weight = self
+            .weight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        weight.percent = percent;
+        weight.refresh.restart();
+        Ok(percent)
+    }
+
+    /// Which path `caller_id`, like a user id or API key id, takes.
+    pub fn path(&self, caller_id: &str) -> Path {
+        path(self.name, caller_id, self.percent())
+    }
+
+    /// Whether `caller_id` takes the new path.
+    pub fn is_new(&self, caller_id: &str) -> bool {
+        self.path(caller_id) == Path::New
+    }
+
+    /// Run `old` or `new`, depending on which path `caller_id` takes.
+    pub fn select<T>(
+        &self,
+        caller_id: &str,
+        old: impl FnOnce() -> T,
+        new: impl FnOnce() -> T,
+    ) -> T {
+        match self.path(caller_id) {
+            Path::Old => old(),
+            Path::New => new(),
+        }
+    }
+}
+
+fn path(name: &str, caller_id: &str, percent: f64) -> Path {
+    if (bucket(name, caller_id) as f64) < percent * (BUCKETS / 100) as f64 {
+        Path::New
+    } else {
+        Path::Old
+    }
+}
+
+fn read(source: WeightSource) -> Result<f64, RolloutError> {
+    match source {
+        WeightSource::Fixed(percent) => Ok(percent.clamp(0.0, 100.0)),
+        WeightSource::Cache(key) => match crate::cache::get::<Vec<u8>>(key)? {
+            Some(value) => parse_percent(&String::from_utf8_lossy(&value)),
+            None => Err(RolloutError::NotCached { key }),
+        },
+        WeightSource::ConfigDocument(field) => {
+            let document: serde_json::Value = crate::config_store::load()?;
+            match document.get(field) {
+                Some(serde_json::Value::Number(number)) => parse_percent(&number.to_string()),
+                Some(serde_json::Value::String(text)) => parse_percent(text),
+                Some(other) => Err(RolloutError::NotANumber {
+                    field,
+                    value: other.clone(),
+                }),
+                None => Err(RolloutError::MissingField { field }),
+            }
+        }
+        WeightSource::Env(variable) => match std::env::var(variable) {
+            Ok(text) => parse_percent(&text),
+            Err(cause) => Err(RolloutError::Env { variable, cause }),
+        },
+    }
+}
+
+fn parse_percent(text: &str) -> Result<f64, RolloutError> {
+    let text = text.trim();
+    match text.trim_end_matches('%').trim().parse::<f64>() {
+        Ok(percent) if percent.is_finite() => Ok(percent.clamp(0.0, 100.0)),
+        _ => Err(RolloutError::NotAPercentage {
+            text: text.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raising_the_weight_only_adds_callers() {
+        let callers: Vec<String> = (0..2000).map(|i| format!("caller-{i}")).collect();
+        let new_at = |percent| {
+            callers
+                .iter()
+                .filter(|caller| path("pricing-v2", caller, percent) == Path::New)
+                .collect::<Vec<_>>()
+        };
+        let (none, ten, fifty, all) = (new_at(0.0), new_at(10.0), new_at(50.0), new_at(100.0));
+        assert!(none.is_empty());
+        assert!((120..280).contains(&ten.len()), "{} of 2000", ten.len());
+        assert!(
+            (800..1200).contains(&fifty.len()),
+            "{} of 2000",
+            fifty.len()
+        );
+        assert_eq!(2000, all.len());
+        assert!(ten.iter().all(|caller| fifty.contains(caller)));
+
+        let fixed = Rollout::new("pricing-v2", WeightSource::Fixed(250.0));
+        assert_eq!(100.0, fixed.percent());
+        assert!(fixed.is_new("caller-1"));
+    }
+
+    #[test]
+    fn parses_percentages() {
+        assert_eq!(25.0, parse_percent("25").unwrap());
+        assert_eq!(2.5, parse_percent(" 2.5% ").unwrap());
+        assert_eq!(0.0, parse_percent("-3").unwrap());
+        assert!(matches!(
+            parse_percent("half"),
+            Err(RolloutError::NotAPercentage { text }) if text == "half"
+        ));
+        assert!(parse_percent("NaN").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+//! Sessions stored in the cache
+//!
+//! A session is typed data kept in the cache under a random id. The client holds a token:
+//! the id plus an HMAC-SHA256 signature made with your secret, so tokens cannot be guessed
+//! or forged. Send it back as a cookie with [SessionStore::cookie], or hand it out as a bearer
+//! token. Each time a session is saved its ttl starts over. Loading a session does not write
+//! to the cache, so a session that is only read expires `ttl` after it was last saved.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::sessions::SessionStore;
+//! use std::time::Duration;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Cart {
+//!     items: Vec<String>,
+//! }
+//!
+//! let store = SessionStore::new(std::env::var("SESSION_SECRET").unwrap_or_default())
+//!     .ttl(Duration::from_secs(30 * 60));
+//!
+//! let mut session = match store.load_from_request::<Cart>() {
+//!     Ok(Some(session)) => session,
+//!     Ok(None) => match store.create(Cart { items: vec![] }) {
+//!         Ok(session) => session,
+//!         Err(e) => {
+//!             eprintln!("failed to create session: {e}");
+//!             return;
+//!         }
+//!     },
+//!     Err(e) => {
+//!         eprintln!("failed to load session: {e}");
+//!         return;
+//!     }
+//! };
+//! session.data.items.push("sku-123".to_string());
+//! if let Err(e) = store.save(&session) {
+//!     eprintln!("failed to save session: {e}");
+//! }
+//! // Return this as a `set-cookie` header.
+//! let set_cookie = store.cookie(&session);
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use momento_functions_hmac::{HmacKey, constant_time_eq, hex};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+
+use crate::cache::{self, CacheDeleteError, CacheGetError, CacheSetError};
+use crate::encoding::Json;
+use crate::web_extensions::headers;
+
+/// An error occurred while reading or writing a session.
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    /// The session could not be read from the cache.
+    #[error(transparent)]
+    Read(#[from] CacheGetError<serde_json::Error>),
+    /// The session could not be written to the cache.
+    #[error(transparent)]
+    Write(#[from] CacheSetError<serde_json::Error>),
+    /// The session could not be removed from the cache.
+    #[error(transparent)]
+    Destroy(#[from] CacheDeleteError),
+}
+
+/// A session and its data.
+#[derive(Debug, Clone)]
+pub struct Session<T> {
+    id: String,
+    token: String,
+    /// The session's data. Call [SessionStore::save] after changing it.
+    pub data: T,
+}
+
+impl<T> Session<T> {
+    /// The session's id, which is also its cache key without the store's prefix.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The signed token to give to the client.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+/// Creates, loads, and destroys sessions.
+pub struct SessionStore {
+    key: HmacKey,
+    ttl: Duration,
+    key_prefix: String,
+    cookie_name: String,
+}
+
+impl SessionStore {
+    /// Create a store that signs tokens with `secret`.
+    ///
+    /// Sessions last 30 minutes without use, are stored under `session:` keys, and use a
+    /// cookie named `session` unless you change them.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: HmacKey::new(secret),
+            ttl: Duration::from_secs(30 * 60),
+            key_prefix: "session:".to_string(),
+            cookie_name: "session".to_string(),
+        }
+    }
+
+    /// How long a session lasts without being saved.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The prefix of the cache keys sessions are stored under.
+    pub fn key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// The name of the session cookie.
+    pub fn cookie_name(mut self, cookie_name: impl Into<String>) -> Self {
+        self.cookie_name = cookie_name.into();
+        self
+    }
+
+    /// Create a new session holding `data`.
+    pub fn create<T: Serialize>(&self, data: T) -> Result<Session<T>, SessionError> {
+        let id = new_id();
+        let session = Session {
+            token: self.sign(&id),
+            id,
+            data,
+        };
+        self.save(&session)?;
+        Ok(session)
+    }
+
+    /// Load the session a token refers to.
+    ///
+    /// Returns `None` when the token's signature is not valid or the session has expired.
+    /// Nothing is written to the cache, so call [SessionStore::save] when you change the
+    /// session's data or want to restart its ttl.
+    pub fn load<T: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<Option<Session<T>>, SessionError> {
+        let Some(id) = self.verify(token) else {
+            return Ok(None);
+        };
+        let Some(Json(data)) = cache::get::<Json<T>>(self.cache_key(id))? else {
+            return Ok(None);
+        };
+        Ok(Some(Session {
+            id: id.to_string(),
+            token: token.to_string(),
+            data,
+        }))
+    }
+
+    /// Load the session of the request this Function is handling.
+    ///
+    /// The token is read from the session cookie, or else from an `authorization: Bearer`
+    /// header.
+    pub fn load_from_request<T: DeserializeOwned>(
+        &self,
+    ) -> Result<Option<Session<T>>, SessionError> {
+        match request_token(headers().iter(), &self.cookie_name) {
+            Some(token) => self.load(&token),
+            None => Ok(None),
+        }
+    }
+
+    /// Write the session's data and restart its ttl.
+    pub fn save<T: Serialize>(&self, session: &Session<T>) -> Result<(), SessionError> {
+        cache::set(self.cache_key(&session.id), Json(&session.data), self.ttl)?;
+        Ok(())
+    }
+
+    /// Remove the session, so its token stops working.
+    pub fn destroy<T>(&self, session: &Session<T>) -> Result<(), SessionError> {
+        cache::delete(self.cache_key(&session.id))?;
+        Ok(())
+    }
+
+    /// A `set-cookie` header value that gives the session's token to a browser.
+    pub fn cookie<T>(&self, session: &Session<T>) -> String {
+        format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+            self.cookie_name,
+            session.token,
+            self.ttl.as_secs()
+        )
+    }
+
+    /// A `set-cookie` header value that removes the session cookie from a browser.
+    pub fn clear_cookie(&self) -> String {
+        format!(
+            "{}=; Path=/; Max-Age=0; HttpOnly; Secure; SameSite=Lax",
+            self.cookie_name
+        )
+    }
+
+    fn cache_key(&self, id: &str) -> String {
+        format!("{}{id}", self.key_prefix)
+    }
+
+    fn sign(&self, id: &str) -> String {
+        format!("{id}.{}", hex(&self.key.sign(id.as_bytes())))
+    }
+
+    fn verify<'a>(&self, token: &'a str) -> Option<&'a str> {
+        let (id, _) = token.split_once('.')?;
+        constant_time_eq(self.sign(id).as_bytes(), token.as_bytes()).then_some(id)
+    }
+}
+
+fn request_token<'a>(
+    mut headers: impl Iterator<Item = (&'a String, &'a String)>,
+    cookie_name: &str,
+) -> Option<String> {
+    let mut bearer = None;
+    for (name, value) in &mut headers {
+        if name.eq_ignore_ascii_case("cookie") {
+            let cookie = value
+                .split(';')
+                .filter_map(|cookie| cookie.trim().split_once('='))
+                .find(|(name, _)| *name == cookie_name)
+                .map(|(_, value)| value.to_string());
+            if cookie.is_some() {
+                return cookie;
+            }
+        } else if name.eq_ignore_ascii_case("authorization") {
+            bearer = value
+                .strip_prefix("Bearer ")
+                .or_else(|| value.strip_prefix("bearer "))
+                .map(|token| token.trim().to_string());
+        }
+    }
+    bearer
+}
+
+/// A new, unique session id.
+///
+/// The ids do not need to be secret, since tokens are signed, but they must not repeat.
+/// The standard library's randomly keyed hasher mixes a counter, the time, and the
+/// invocation so that ids differ across calls, instances, and restarts.
+fn new_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let invocation = std::env::var("__INVOCATION_ID").unwrap_or_default();
+    let random = RandomState::new();
+    let digest = Sha256::new()
+        .chain_update(random.hash_one((count, now, &invocation)).to_le_bytes())
+        .chain_update(RandomState::new().hash_one(count).to_le_bytes())
+        .chain_update(count.to_le_bytes())
+        .chain_update(now.to_le_bytes())
+        .chain_update(invocation.as_bytes())
+        .finalize();
+    hex(&digest[..16])
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_verify_only_with_the_same_secret() {
+        let store = SessionStore::new("secret");
+        let id = new_id();
+        let token = store.sign(&id);
+        assert_eq!(Some(id.as_str()), store.verify(&token));
+        assert_eq!(None, SessionStore::new("other").verify(&token));
+        let last = if token.ends_with('0') { '1' } else { '0' };
+        let tampered = format!("{}{last}", &token[..token.len() - 1]);
+        assert_eq!(None, store.verify(&tampered));
+        assert_eq!(None, store.verify(&id));
+    }
+
+    #[test]
+    fn ids_do_not_repeat() {
+        let ids: std::collections::HashSet<String> = (0..1000).map(|_| new_id()).collect();
+        assert_eq!(1000, ids.len());
+    }
+
+    #[test]
+    fn tokens_are_read_from_cookies_before_bearer_headers() {
+        let cookie = (
+            "Cookie".to_string(),
+            "theme=dark; session=abc.123".to_string(),
+        );
+        let bearer = ("Authorization".to_string(), "Bearer xyz.789".to_string());
+        assert_eq!(
+            Some("abc.123".to_string()),
+            request_token(
+                [(&bearer.0, &bearer.1), (&cookie.0, &cookie.1)].into_iter(),
+                "session"
+            )
+        );
+        assert_eq!(
+            Some("xyz.789".to_string()),
+            request_token([(&bearer.0, &bearer.1)].into_iter(), "session")
+        );
+        assert_eq!(
+            None,
+            request_token([(&cookie.0, &cookie.1)].into_iter(), "other")
+        );
+    }
+}
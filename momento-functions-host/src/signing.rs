@@ -0,0 +1,402 @@
+//! Signed response bodies, for webhooks that downstream consumers can verify
+//!
+//! When a Function sends webhooks or serves data that another system acts on, the receiver
+//! needs to know the body came from your Function and was not changed on the way. [sign]
+//! produces a `Momento-Signature` header value over a body and the time it was signed:
+//!
+//! `t=1700000000,hmac-sha256=<hex>` or `t=1700000000,ed25519=<hex>`
+//!
+//! The signature covers `{t}.{body}`, so a body can't be replayed with a newer timestamp.
+//! Use HMAC-SHA256 when the consumer can hold the same secret, and Ed25519 when consumers
+//! should only be able to verify, not sign: they get the public key. Ed25519 is computed on
+//! the host.
+//!
+//! Consumers verify with a [SignatureVerifier], which also rejects signatures older than a
+//! tolerance. A consumer written as a Function can verify the request it was invoked with.
+//!
+//! **Examples:**
+//! Sign with a key kept in Secrets Manager:
+//! ```rust,no_run
+//! use std::time::{Duration, SystemTime};
+//!
+//! use momento_functions_host::aws::auth::AwsCredentialsProvider;
+//! use momento_functions_host::aws::secrets_manager::{GetSecretValueRequest, SecretsManagerClient};
+//! use momento_functions_host::build_environment_aws_credentials;
+//! use momento_functions_host::signing::{self, SigningKey};
+//!
+//! # fn example(body: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+//! let credentials = AwsCredentialsProvider::new("us-east-1", build_environment_aws_credentials!())?;
+//! let secret: Vec<u8> = SecretsManagerClient::new(&credentials).get_secret_value(
+//!     GetSecretValueRequest::new("webhook-signing-key"),
+//!     Duration::from_secs(300),
+//! )?;
+//! let signature = signing::sign(&SigningKey::ed25519(secret), body, SystemTime::now())?;
+//! // Send `signature` as the `momento-signature` header with `body`.
+//! # Ok(())
+//! # }
+//! ```
+//! ________
+//! Verify a webhook in the Function that receives it:
+//! ```rust,no_run
+//! use momento_functions_host::signing::{SignatureVerifier, VerifyingKey};
+//!
+//! # fn example(body: &[u8]) {
+//! let verifier = SignatureVerifier::new(VerifyingKey::hmac_sha256(
+//!     std::env::var("WEBHOOK_SECRET").unwrap_or_default(),
+//! ));
+//! match verifier.verify_request(body) {
+//!     Ok(signed_at) => println!("signed at {signed_at:?}"),
+//!     Err(e) => eprintln!("rejecting webhook: {e}"),
+//! }
+//! # }
+//! ```
+
+use std::time::{Duration, SystemTime};
+
+use momento_functions_hmac::{HmacKey, from_hex, hex};
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
+use momento_functions_wit::host::momento::host::signing;
+
+pub use momento_functions_hmac::constant_time_eq;
+
+/// The header signatures are sent in.
+pub const SIGNATURE_HEADER: &str = "momento-signature";
+
+/// How far a signature's timestamp may be from now before [SignatureVerifier] rejects it, by
+/// default.
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(5 * 60);
+
+const HMAC_SHA256: &str = "hmac-sha256";
+const ED25519: &str = "ed25519";
+
+/// An error occurred while signing or checking a signature.
+#[derive(Debug, thiserror::Error)]
+pub enum SigningError {
+    /// The key is not a valid Ed25519 key.
+    #[error("Invalid signing key: {message}")]
+    InvalidKey {
+        /// Why the key is invalid.
+        message: String,
+    },
+    /// The host does not support the signature algorithm.
+    #[error("Unsupported signature algorithm: {algorithm}")]
+    UnsupportedAlgorithm {
+        /// The algorithm, like `RS256`.
+        algorithm: String,
+    },
+    /// The host could not sign or verify.
+    #[error("Signing failed: {message}")]
+    Other {
+        /// The host's description of the failure.
+        message: String,
+    },
+    /// The host is too old to use Ed25519 keys.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+}
+
+impl From<signing::SigningError> for SigningError {
+    fn from(e: signing::SigningError) -> Self {
+        match e {
+            signing::SigningError::InvalidKey(message) => Self::InvalidKey { message },
+            signing::SigningError::UnsupportedAlgorithm(algorithm) => {
+                Self::UnsupportedAlgorithm { algorithm }
+            }
+            signing::SigningError::Other(message) => Self::Other { message },
+        }
+    }
+}
+
+/// A signature could not be verified.
+#[derive(Debug, thiserror::Error)]
+pub enum SignatureError {
+    /// There is no signature header, or it has no signature for the verifying key's algorithm.
+    #[error("the body is not signed")]
+    MissingSignature,
+    /// The header has no `t` timestamp, or it is not a number of seconds.
+    #[error("the signature has no valid timestamp")]
+    InvalidTimestamp,
+    /// The signature's timestamp is further from now than the verifier's tolerance.
+    #[error("the signature was made at {signed_at_epoch_seconds}, outside of the tolerance")]
+    Expired {
+        /// When the body was signed, in seconds since the Unix epoch.
+        signed_at_epoch_seconds: u64,
+    },
+    /// The signature does not match the body and timestamp.
+    #[error("the signature is not valid")]
+    InvalidSignature,
+    /// The signature could not be checked.
+    #[error(transparent)]
+    Signing(#[from] SigningError),
+}
+
+#[derive(Clone)]
+enum Key {
+    HmacSha256(HmacKey),
+    Ed25519(Vec<u8>),
+}
+
+impl Key {
+    fn hmac(secret: &[u8]) -> Self {
+        Self::HmacSha256(HmacKey::new(secret))
+    }
+
+    fn algorithm(&self) -> &'static str {
+        match self {
+            Key::HmacSha256(_) => HMAC_SHA256,
+            Key::Ed25519(_) => ED25519,
+        }
+    }
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.algorithm())
+    }
+}
+
+/// A key that signs bodies. Its bytes are not printed by [Debug].
+#[derive(Debug, Clone)]
+pub struct SigningKey(Key);
+
+impl SigningKey {
+    /// A key for HMAC-SHA256 with a shared secret.
+    pub fn hmac_sha256(secret: impl AsRef<[u8]>) -> Self {
+        Self(Key::hmac(secret.as_ref()))
+    }
+
+    /// An Ed25519 private key, given as its 32-byte seed.
+    pub fn ed25519(private_key: impl Into<Vec<u8>>) -> Self {
+        Self(Key::Ed25519(private_key.into()))
+    }
+
+    /// The key consumers verify with. For HMAC-SHA256 this is the same secret.
+    pub fn verifying_key(&self) -> Result<VerifyingKey, SigningError> {
+        match &self.0 {
+            Key::HmacSha256(key) => Ok(VerifyingKey(Key::HmacSha256(key.clone()))),
+            Key::Ed25519(private_key) => {
+                abi::require(HostCapability::Ed25519Signing)?;
+                let public_key = signing::ed25519_public_key(private_key)?;
+                Ok(VerifyingKey(Key::Ed25519(public_key)))
+            }
+        }
+    }
+}
+
+/// A key that checks signatures.
+#[derive(Debug, Clone)]
+pub struct VerifyingKey(Key);
+
+impl VerifyingKey {
+    /// A key for HMAC-SHA256 with a shared secret.
+    pub fn hmac_sha256(secret: impl AsRef<[u8]>) -> Self {
+        Self(Key::hmac(secret.as_ref()))
+    }
+
+    /// An Ed25519 public key, given as its 32 bytes.
+    pub fn ed25519(public_key: impl Into<Vec<u8>>) -> Self {
+        Self(Key::Ed25519(public_key.into()))
+    }
+}
+
+/// The `Momento-Signature` header value for `body`, signed with `key` at `signed_at`.
+pub fn sign(key: &SigningKey, body: &[u8], signed_at: SystemTime) -> Result<String, SigningError> {
+    let timestamp = epoch_seconds(signed_at);
+    let message = signed_message(timestamp, body);
+    let signature = match &key.0 {
+        Key::HmacSha256(key) => key.sign(&message).to_vec(),
+        Key::Ed25519(private_key) => {
+            abi::require(HostCapability::Ed25519Signing)?;
+            signing::ed25519_sign(private_key, &message)?
+        }
+    };
+    Ok(format!(
+        "t={timestamp},{}={}",
+        key.0.algorithm(),
+        hex(&signature)
+    ))
+}
+
+/// Whether `signature` is a valid JWS signature of `message` by `jwk`, a JSON Web Key in its
+/// JSON form, with the JWS `algorithm`, like `RS256` or `ES256`. The check runs on the host.
+///
+/// To validate whole tokens from an OpenID Connect provider, use the `oidc` module.
+pub fn verify_jws(
+    jwk: &str,
+    algorithm: &str,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, SigningError> {
+    abi::require(HostCapability::JwkVerification)?;
+    Ok(signing::jwk_verify(jwk, algorithm, message, signature)?)
+}
+
+/// Checks `Momento-Signature` headers made by [sign].
+#[derive(Debug, Clone)]
+pub struct SignatureVerifier {
+    key: VerifyingKey,
+    tolerance: Duration,
+}
+
+impl SignatureVerifier {
+    /// A verifier that accepts signatures made within [DEFAULT_TOLERANCE] of now.
+    pub fn new(key: VerifyingKey) -> Self {
+        Self {
+            key,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Accept signatures made within `tolerance` of now, in either direction, to allow for
+    /// delivery delays and clock drift.
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Check a signature header value against `body`, returning when the body was signed.
+    pub fn verify(&self, header: &str, body: &[u8]) -> Result<SystemTime, SignatureError> {
+        self.verify_at(header, body, SystemTime::now())
+    }
+
+    /// Check the signature on the request this Function was invoked with, for a Function that
+    /// receives webhooks. `body` is the request payload, exactly as it arrived.
+    pub fn verify_request(&self, body: &[u8]) -> Result<SystemTime, SignatureError> {
+        let header = crate::web_extensions::headers()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(SIGNATURE_HEADER))
+            .map(|(_, value)| value.as_str())
+            .ok_or(SignatureError::MissingSignature)?;
+        self.verify(header, body)
+    }
+
+    /// Check the signature on a response from another Function.
+    #[cfg(feature = "http")]
+    pub fn verify_response(
+        &self,
+        response: &crate::http::Response,
+    ) -> Result<SystemTime, SignatureError> {
+        let header = response
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(SIGNATURE_HEADER))
+            .map(|(_, value)| value.as_str())
+            .ok_or(SignatureError::MissingSignature)?;
+        self.verify(header, &response.body)
+    }
+
+    fn verify_at(
+        &self,
+        header: &str,
+        body: &[u8],
+        now: SystemTime,
+    ) -> Result<SystemTime, SignatureError> {
+        let algorithm = self.key.0.algorithm();
+        let mut timestamp = None;
+        let mut signature = None;
+        for (name, value) in header.split(',').filter_map(|part| part.split_once('=')) {
+            match name.trim() {
+                "t" => timestamp = value.trim().parse::<u64>().ok(),
+                name if name == algorithm => signature = from_hex(value.trim()),
+                _ => {}
+            }
+        }
+        let signature = signature.ok_or(SignatureError::MissingSignature)?;
+        let timestamp = timestamp.ok_or(SignatureError::InvalidTimestamp)?;
+        if self.tolerance.as_secs() < epoch_seconds(now).abs_diff(timestamp) {
+            return Err(SignatureError::Expired {
+                signed_at_epoch_seconds: timestamp,
+            });
+        }
+
+        let message = signed_message(timestamp, body);
+        let valid = match &self.key.0 {
+            Key::HmacSha256(key) => constant_time_eq(&key.sign(&message), &signature),
+            Key::Ed25519(public_key) => {
+                abi::require(HostCapability::Ed25519Signing).map_err(SigningError::from)?;
+                signing::ed25519_verify(public_key, &message, &signature)
+                    .map_err(SigningError::from)?
+            }
+        };
+        if !valid {
+            return Err(SignatureError::InvalidSignature);
+        }
+        Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp))
+    }
+}
+
+fn signed_message(timestamp: u64, body: &[u8]) -> Vec<u8> {
+    let mut message = format!("{timestamp}.").into_bytes();
+    message.extend_from_slice(body);
+    message
+}
+
+fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn signatures_verify_within_the_tolerance() {
+        let key = SigningKey::hmac_sha256("shared secret");
+        let header = sign(&key, b"{\"event\":\"paid\"}", at(1_700_000_000)).unwrap();
+        assert!(header.starts_with("t=1700000000,hmac-sha256="));
+
+        let verifier = SignatureVerifier::new(key.verifying_key().unwrap());
+        assert_eq!(
+            at(1_700_000_000),
+            verifier
+                .verify_at(&header, b"{\"event\":\"paid\"}", at(1_700_000_299))
+                .unwrap()
+        );
+        assert!(matches!(
+            verifier.verify_at(&header, b"{\"event\":\"paid\"}", at(1_700_000_301)),
+            Err(SignatureError::Expired {
+                signed_at_epoch_seconds: 1_700_000_000
+            })
+        ));
+        assert!(matches!(
+            verifier.verify_at(&header, b"{\"event\":\"refunded\"}", at(1_700_000_000)),
+            Err(SignatureError::InvalidSignature)
+        ));
+
+        // The timestamp is signed too.
+        let replayed = header.replace("t=1700000000", "t=1700000100");
+        assert!(matches!(
+            verifier.verify_at(&replayed, b"{\"event\":\"paid\"}", at(1_700_000_100)),
+            Err(SignatureError::InvalidSignature)
+        ));
+        let other_secret = SignatureVerifier::new(VerifyingKey::hmac_sha256("another secret"));
+        assert!(matches!(
+            other_secret.verify_at(&header, b"{\"event\":\"paid\"}", at(1_700_000_000)),
+            Err(SignatureError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        let verifier = SignatureVerifier::new(VerifyingKey::hmac_sha256("secret"));
+        assert!(matches!(
+            verifier.verify_at("t=1700000000,ed25519=00", b"", at(1_700_000_000)),
+            Err(SignatureError::MissingSignature)
+        ));
+        assert!(matches!(
+            verifier.verify_at("t=soon,hmac-sha256=00", b"", at(1_700_000_000)),
+            Err(SignatureError::InvalidTimestamp)
+        ));
+        assert!(matches!(
+            verifier.verify_at("", b"", at(1_700_000_000)),
+            Err(SignatureError::MissingSignature)
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+//! Copying cache items to S3 and back
+//!
+//! [export] reads a set of cache items and writes them, with their remaining time-to-live,
+//! to one S3 object. [import] reads that object and writes the items back to the cache.
+//! Use it to seed a demo cache, or to back up warm state before a migration.
+//!
+//! The snapshot is a JSON document, with each value base64-encoded:
+//! ```json
+//! {
+//!     "exported_at_millis": 1760400000000,
+//!     "items": [{ "key": "user:7", "value": "eyJuYW1lIjoiQWRhIn0=", "ttl_millis": 86400000 }]
+//! }
+//! ```
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::aws::auth::AwsCredentialsProvider;
+//! use momento_functions_host::aws::s3::S3Client;
+//! use momento_functions_host::build_environment_aws_credentials;
+//! use momento_functions_host::snapshot::{self, RestoreTtl};
+//!
+//! # fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let credentials =
+//!     AwsCredentialsProvider::new("us-east-1", build_environment_aws_credentials!())?;
+//! let s3 = S3Client::new(&credentials);
+//!
+//! let keys = ["catalog:featured", "catalog:categories", "config:homepage"];
+//! let exported = snapshot::export(&s3, "my-backups", "warm-state.json", keys)?;
+//! println!("exported {}, {} missing", exported.exported, exported.missing.len());
+//!
+//! let imported = snapshot::import(&s3, "my-backups", "warm-state.json", RestoreTtl::AsExported)?;
+//! println!("restored {} items", imported.restored);
+//! # Ok(()) }
+//! ```
+
+use std::convert::Infallible;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::aws::s3::{ObjectOptions, S3Client, S3GetError, S3PutError};
+use crate::cache::{self, CacheGetError, CacheItemMetadataError, CacheSetError};
+
+/// An error occurred while exporting or importing a snapshot.
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    /// An item could not be read from the cache.
+    #[error(transparent)]
+    CacheRead(#[from] CacheGetError<Infallible>),
+    /// An item's time-to-live could not be read from the cache.
+    #[error(transparent)]
+    Metadata(#[from] CacheItemMetadataError),
+    /// An item could not be written to the cache.
+    #[error(transparent)]
+    CacheWrite(#[from] CacheSetError<Infallible>),
+    /// The snapshot could not be written to S3.
+    #[error(transparent)]
+    Upload(#[from] S3PutError<Infallible>),
+    /// The snapshot could not be read from S3.
+    #[error(transparent)]
+    Download(#[from] S3GetError<Infallible>),
+    /// There is no snapshot at the given location.
+    #[error("No snapshot at s3://{bucket}/{key}")]
+    NotFound {
+        /// The bucket that was read.
+        bucket: String,
+        /// The key that was read.
+        key: String,
+    },
+    /// The snapshot is not a valid snapshot document.
+    #[error("Invalid snapshot: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+/// A set of cache items, as written to S3.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// When the items were read, in milliseconds since the Unix epoch.
+    pub exported_at_millis: u64,
+    /// The items.
+    pub items: Vec<SnapshotItem>,
+}
+
+/// One cache item in a [Snapshot].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotItem {
+    /// The item's key.
+    pub key: String,
+    /// The item's value.
+    #[serde(with = "base64_bytes")]
+    pub value: Vec<u8>,
+    /// How long the item had left to live when it was exported, or `None` if it does not
+    /// expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_millis: Option<u64>,
+}
+
+/// What [export] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    /// How many items were written to the snapshot.
+    pub exported: usize,
+    /// The keys that were not in the cache.
+    pub missing: Vec<String>,
+}
+
+/// What [import] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// How many items were written to the cache.
+    pub restored: usize,
+    /// How many items were skipped because they would already have expired.
+    pub expired: usize,
+}
+
+/// How long restored items live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreTtl {
+    /// Each item gets the time it had left when it was exported, less the time since. Items
+    /// that would have expired by now are skipped, and items that did not expire are
+    /// restored with the longest time-to-live the cache allows.
+    AsExported,
+    /// Every item gets this time-to-live.
+    Fixed(Duration),
+}
+
+/// Read the items at `keys` from the cache and write them to `s3://{bucket}/{key}`.
+///
+/// Keys that are not in the cache are reported in [ExportReport::missing] and left out of
+/// the snapshot.
+pub fn export(
+    client: &S3Client,
+    bucket: impl Into<String>,
+    key: impl Into<String>,
+    keys: impl IntoIterator<Item = impl Into<String>>,
+) -> Result<ExportReport, SnapshotError> {
+    let (snapshot, missing) = read_items(keys)?;
+    let exported = snapshot.items.len();
+    client.put_with_options(
+        bucket,
+        key,
+        serde_json::to_vec(&snapshot)?,
+        ObjectOptions {
+            content_type: Some("application/json".to_string()),
+            ..Default::default()
+        },
+    )?;
+    Ok(ExportReport { exported, missing })
+}
+
+/// Read the snapshot at `s3://{bucket}/{key}` and write its items to the cache.
+///
+/// Items are written one at a time. If one fails, the items before it have already been
+/// restored, and importing again is safe.
+pub fn import(
+    client: &S3Client,
+    bucket: impl Into<String>,
+    key: impl Into<String>,
+    ttl: RestoreTtl,
+) -> Result<ImportReport, SnapshotError> {
+    let (bucket, key) = (bucket.into(), key.into());
+    let Some(json) = client.get::<Vec<u8>>(bucket.clone(), key.clone())? else {
+        return Err(SnapshotError::NotFound { bucket, key });
+    };
+    let snapshot: Snapshot = serde_json::from_slice(&json)?;
+    restore(&snapshot, ttl, now_millis())
+}
+
+/// Read `keys` from the cache into a snapshot, along with the keys that were missing.
+pub fn read_items(
+    keys: impl IntoIterator<Item = impl Into<String>>,
+) -> Result<(Snapshot, Vec<String>), SnapshotError> {
+    let mut snapshot = Snapshot {
+        exported_at_millis: now_millis(),
+        items: Vec::new(),
+    };
+    let mut missing = Vec::new();
+    for key in keys {
+        let key = key.into();
+        // Read the time-to-live first, so it is never longer than the value's.
+        let metadata = cache::item_metadata(&key)?;
+        match (metadata, cache::get::<Vec<u8>>(&key)?) {
+            (Some(metadata), Some(value)) => snapshot.items.push(SnapshotItem {
+                key,
+                value,
+                ttl_millis: metadata
+                    .remaining_ttl
+                    .map(|ttl| u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX)),
+            }),
+            _ => missing.push(key),
+        }
+    }
+    Ok((snapshot, missing))
+}
+
+/// Write a snapshot's items to the cache, as of `now_millis`.
+pub fn restore(
+    snapshot: &Snapshot,
+    ttl: RestoreTtl,
+    now_millis: u64,
+) -> Result<ImportReport, SnapshotError> {
+    let elapsed = Duration::from_millis(now_millis.saturating_sub(snapshot.exported_at_millis));
+    let mut report = ImportReport::default();
+    for item in &snapshot.items {
+        let Some(ttl) = restored_ttl(item, ttl, elapsed) else {
+            report.expired += 1;
+            continue;
+        };
+        cache::set(&item.key, item.value.clone(), ttl)?;
+        report.restored += 1;
+    }
+    Ok(report)
+}
+
+fn restored_ttl(item: &SnapshotItem, ttl: RestoreTtl, elapsed: Duration) -> Option<Duration> {
+    match (ttl, item.ttl_millis) {
+        (RestoreTtl::Fixed(ttl), _) => Some(ttl),
+        (RestoreTtl::AsExported, None) => Some(Duration::MAX),
+        (RestoreTtl::AsExported, Some(millis)) => Duration::from_millis(millis)
+            .checked_sub(elapsed)
+            .filter(|remaining| !remaining.is_zero()),
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| u64::try_from(now.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or_default()
+}
+
+mod base64_bytes {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn item(ttl_millis: Option<u64>) -> SnapshotItem {
+        SnapshotItem {
+            key: "k".to_string(),
+            value: b"{\"name\":\"Ada\"}".to_vec(),
+            ttl_millis,
+        }
+    }
+
+    #[test]
+    fn snapshots_round_trip_as_json() {
+        let snapshot = Snapshot {
+            exported_at_millis: 5,
+            items: vec![item(Some(1000)), item(None)],
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains(r#""value":"eyJuYW1lIjoiQWRhIn0=""#), "{json}");
+        assert_eq!(1, json.matches("ttl_millis").count());
+        assert_eq!(snapshot, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn restored_ttls_count_down_from_export() {
+        let elapsed = Duration::from_millis(400);
+        let fixed = RestoreTtl::Fixed(Duration::from_secs(60));
+        assert_eq!(
+            Some(Duration::from_millis(600)),
+            restored_ttl(&item(Some(1000)), RestoreTtl::AsExported, elapsed)
+        );
+        assert_eq!(
+            None,
+            restored_ttl(&item(Some(400)), RestoreTtl::AsExported, elapsed)
+        );
+        assert_eq!(
+            Some(Duration::MAX),
+            restored_ttl(&item(None), RestoreTtl::AsExported, elapsed)
+        );
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            restored_ttl(&item(Some(1)), fixed, elapsed)
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Values and rows shared by the SQL clients
+//!
+//! [sql](crate::sql) and [aws::rds_data](crate::aws::rds_data) reach different databases
+//! through different host interfaces, but return the same [Row]s of [SqlValue]s, so code that
+//! reads rows works with either.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// A SQL value, as a parameter or in a result row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    /// SQL `NULL`.
+    Null,
+    /// A boolean.
+    Boolean(bool),
+    /// An integer.
+    Long(i64),
+    /// A floating point number.
+    Double(f64),
+    /// Text. Dates, decimals, JSON, and UUIDs are returned as text too.
+    Text(String),
+    /// Binary data.
+    Blob(Vec<u8>),
+}
+
+impl SqlValue {
+    /// This value as JSON. Blobs become base64 strings, and text in a `json` or `jsonb`
+    /// column is parsed.
+    fn to_json(&self, type_name: &str) -> Value {
+        match self {
+            SqlValue::Null => Value::Null,
+            SqlValue::Boolean(b) => Value::Bool(*b),
+            SqlValue::Long(l) => Value::from(*l),
+            SqlValue::Double(d) => serde_json::Number::from_f64(*d)
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            SqlValue::Text(text)
+                if type_name.eq_ignore_ascii_case("json")
+                    || type_name.eq_ignore_ascii_case("jsonb") =>
+            {
+                serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.clone()))
+            }
+            SqlValue::Text(text) => Value::String(text.clone()),
+            SqlValue::Blob(blob) => {
+                Value::String(base64::engine::general_purpose::STANDARD.encode(blob))
+            }
+        }
+    }
+}
+
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Boolean(value)
+    }
+}
+
+impl From<i32> for SqlValue {
+    fn from(value: i32) -> Self {
+        SqlValue::Long(value.into())
+    }
+}
+
+impl From<i64> for SqlValue {
+    fn from(value: i64) -> Self {
+        SqlValue::Long(value)
+    }
+}
+
+impl From<u32> for SqlValue {
+    fn from(value: u32) -> Self {
+        SqlValue::Long(value.into())
+    }
+}
+
+impl From<f64> for SqlValue {
+    fn from(value: f64) -> Self {
+        SqlValue::Double(value)
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        SqlValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::Text(value)
+    }
+}
+
+impl From<Vec<u8>> for SqlValue {
+    fn from(value: Vec<u8>) -> Self {
+        SqlValue::Blob(value)
+    }
+}
+
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(SqlValue::Null, Into::into)
+    }
+}
+
+/// A column of a result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// The column's name, or its alias in the SQL.
+    pub name: String,
+    /// The database's name for the column's type, like `int4` or `varchar`.
+    pub type_name: String,
+    /// Whether the column can be `NULL`, when the database reports it.
+    pub nullable: Option<bool>,
+}
+
+/// A row of a result.
+#[derive(Debug, Clone)]
+pub struct Row {
+    columns: Arc<[Column]>,
+    values: Vec<SqlValue>,
+}
+
+impl Row {
+    pub(crate) fn new(columns: Arc<[Column]>, values: Vec<SqlValue>) -> Self {
+        Self { columns, values }
+    }
+
+    /// The value of the column `name`.
+    pub fn get(&self, name: &str) -> Option<&SqlValue> {
+        self.columns
+            .iter()
+            .position(|column| column.name == name)
+            .and_then(|index| self.values.get(index))
+    }
+
+    /// The row's columns.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// The row's values, in column order.
+    pub fn values(&self) -> &[SqlValue] {
+        &self.values
+    }
+
+    /// The row as a JSON object keyed by column name.
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.columns
+                .iter()
+                .zip(&self.values)
+                .map(|(column, value)| (column.name.clone(), value.to_json(&column.type_name)))
+                .collect(),
+        )
+    }
+
+    /// Deserialize the row into `T`, matching fields to column names.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_value(self.to_json())
+    }
+
+    /// The row's values keyed by column name.
+    pub fn into_map(self) -> HashMap<String, SqlValue> {
+        self.columns
+            .iter()
+            .map(|column| column.name.clone())
+            .zip(self.values)
+            .collect()
+    }
+}
+
+/// Deserialize each row into `T`, reporting the first row that fails by its position.
+pub(crate) fn deserialize_rows<T: DeserializeOwned, E>(
+    rows: &[Row],
+    error: impl Fn(usize, serde_json::Error) -> E,
+) -> Result<Vec<T>, E> {
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| row.deserialize().map_err(|cause| error(index, cause)))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_become_json_by_column_type() {
+        let columns: Arc<[Column]> = [("settings", "JSON"), ("note", "text"), ("blob", "bytea")]
+            .map(|(name, type_name)| Column {
+                name: name.to_string(),
+                type_name: type_name.to_string(),
+                nullable: None,
+            })
+            .into();
+        let row = Row::new(
+            columns,
+            vec![
+                SqlValue::Text(r#"{"beta":true}"#.to_string()),
+                SqlValue::Text(r#"{"beta":true}"#.to_string()),
+                SqlValue::from(b"hi".to_vec()),
+            ],
+        );
+        assert_eq!(
+            serde_json::json!({
+                "settings": {"beta": true},
+                "note": r#"{"beta":true}"#,
+                "blob": "aGk=",
+            }),
+            row.to_json()
+        );
+        let error = deserialize_rows::<u8, _>(&[row], |index, _| index).unwrap_err();
+        assert_eq!(0, error);
+    }
+}
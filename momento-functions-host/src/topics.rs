@@ -1,5 +1,12 @@
 //! Host interfaces for working with Momento Topics apis
 
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::Duration;
+
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
+use momento_functions_wit::binding::{FromBinding, IntoBinding};
 use momento_functions_wit::host::momento::functions::topic;
 use serde::Serialize;
 
@@ -19,6 +26,34 @@ pub enum PublishError<E: EncodeError> {
     PublishError(#[from] topic::Error),
 }
 
+/// An error occurred while publishing with [publish_sequenced].
+#[derive(Debug, thiserror::Error)]
+pub enum PublishSequencedError<E: EncodeError> {
+    /// An error occurred while encoding the provided message.
+    #[error("Failed to encode message")]
+    EncodeFailed {
+        /// The underlying encoding error.
+        cause: E,
+    },
+    /// The host does not return sequence numbers from publish.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+    /// An error occurred while calling the host publish function.
+    #[error(transparent)]
+    PublishError(#[from] topic::Error),
+}
+
+/// An error occurred while subscribing to a topic or reading from a [Subscription].
+#[derive(Debug, thiserror::Error)]
+pub enum SubscribeError {
+    /// The host does not provide topic subscriptions.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedByHost),
+    /// An error occurred while calling the host subscribe function.
+    #[error(transparent)]
+    SubscribeError(#[from] topic::Error),
+}
+
 /// Publish a message to a topic in the cache this Function is running within.
 ///
 /// Examples:
@@ -27,7 +62,7 @@ pub enum PublishError<E: EncodeError> {
 /// ```rust,no_run
 /// # use momento_functions_host::topics;
 /// match topics::publish("my_topic", "hello there") {
-///     Ok(()) => {}
+///     Ok(_) => {}
 ///     Err(e) => eprintln!("failed to publish: {e}"),
 /// }
 /// ```
@@ -36,7 +71,7 @@ pub enum PublishError<E: EncodeError> {
 /// ```rust,no_run
 /// # use momento_functions_host::topics;
 /// match topics::publish("my_topic", b"hello there".to_vec()) {
-///     Ok(()) => {}
+///     Ok(_) => {}
 ///     Err(e) => eprintln!("failed to publish: {e}"),
 /// }
 /// ```
@@ -52,7 +87,7 @@ pub enum PublishError<E: EncodeError> {
 /// }
 ///
 /// match topics::publish("my_topic", Json(MyStruct{ hello: "hello".to_string() })) {
-///     Ok(()) => {}
+///     Ok(_) => {}
 ///     Err(e) => eprintln!("failed to publish: {e}"),
 /// }
 /// ```
@@ -76,6 +111,283 @@ pub fn publish<T: PublishKind>(
     .map_err(Into::into)
 }
 
+/// Publish a message like [publish], returning where it landed in the topic.
+///
+/// Keep its [resume token](Published::resume_token) to hand to a [subscribe]r, or compare
+/// sequence numbers to spot lost messages with a [SequenceTracker].
+///
+/// **Examples:**
+/// ```rust,no_run
+/// # use momento_functions_host::topics;
+/// match topics::publish_sequenced("my_topic", "hello there") {
+///     Ok(published) => eprintln!("published at {}", published.resume_token()),
+///     Err(e) => eprintln!("failed to publish: {e}"),
+/// }
+/// ```
+pub fn publish_sequenced<T: PublishKind>(
+    topic: impl AsRef<str>,
+    value: T,
+) -> Result<Published, PublishSequencedError<<<T as PublishKind>::Encoding as Encode>::Error>> {
+    abi::require(HostCapability::TopicSequenceNumbers)?;
+    match value
+        .as_publish()
+        .map_err(|e| PublishSequencedError::EncodeFailed { cause: e })?
+    {
+        Publish::Str(s) => topic::publish_sequenced(topic.as_ref(), s),
+        Publish::String(s) => topic::publish_sequenced(topic.as_ref(), s.as_str()),
+        Publish::Bytes(b) => topic::publish_bytes_sequenced(
+            topic.as_ref(),
+            &b.try_serialize()
+                .map_err(|e| PublishSequencedError::EncodeFailed { cause: e })?
+                .into(),
+        ),
+    }
+    .map(Published::from_binding)
+    .map_err(Into::into)
+}
+
+/// Subscribe to a topic in the cache this Function is running within.
+///
+/// With a `resume` token, the subscription starts right after the message it was taken from,
+/// so a consumer that stored its [SequenceTracker::resume_token] picks up where it left off.
+/// Without one, it starts with the next message published.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use momento_functions_host::topics::{self, SequenceObservation, SequenceTracker};
+///
+/// # fn f() -> Result<(), topics::SubscribeError> {
+/// let mut tracker = SequenceTracker::new("3.1041".parse().ok());
+/// let subscription = topics::subscribe("orders", tracker.resume_token())?;
+/// while let Some(message) = subscription.next_message(Duration::from_secs(1))? {
+///     let published = message.published;
+///     if tracker.observe(published.sequence_page, published.sequence_number)
+///         == SequenceObservation::Duplicate
+///     {
+///         continue;
+///     }
+///     // Handle message.value, then store tracker.resume_token().
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn subscribe(
+    topic: impl AsRef<str>,
+    resume: Option<ResumeToken>,
+) -> Result<Subscription, SubscribeError> {
+    abi::require(HostCapability::TopicSubscriptions)?;
+    Ok(Subscription {
+        subscription: topic::subscribe(topic.as_ref(), resume.into_binding())?,
+    })
+}
+
+/// A subscription to a topic, from [subscribe].
+pub struct Subscription {
+    subscription: topic::Subscription,
+}
+
+impl Subscription {
+    /// The next message, waiting up to `wait` for one to be published. `None` when none was.
+    pub fn next_message(&self, wait: Duration) -> Result<Option<TopicMessage>, SubscribeError> {
+        let wait = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX);
+        Ok(Option::from_binding(self.subscription.next(wait)?))
+    }
+}
+
+/// A message read from a [Subscription].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicMessage {
+    /// What was published.
+    pub value: TopicValue,
+    /// Where the message is in the topic.
+    pub published: Published,
+}
+
+/// What was published to a topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopicValue {
+    /// A string message.
+    Text(String),
+    /// A bytes message.
+    Binary(Vec<u8>),
+}
+
+/// Where a published message landed in its topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Published {
+    /// The message's position in the topic. Each message is numbered one after the last.
+    pub sequence_number: u64,
+    /// Changes when the topic's numbering restarts, like after the topic is recreated.
+    pub sequence_page: u64,
+}
+
+impl FromBinding<topic::Published> for Published {
+    fn from_binding(published: topic::Published) -> Self {
+        Self {
+            sequence_number: published.sequence_number,
+            sequence_page: published.sequence_page,
+        }
+    }
+}
+
+impl IntoBinding<topic::Published> for ResumeToken {
+    fn into_binding(self) -> topic::Published {
+        topic::Published {
+            sequence_number: self.sequence_number,
+            sequence_page: self.sequence_page,
+        }
+    }
+}
+
+impl FromBinding<topic::Message> for TopicMessage {
+    fn from_binding(message: topic::Message) -> Self {
+        Self {
+            value: match message.value {
+                topic::MessageValue::Text(text) => TopicValue::Text(text),
+                topic::MessageValue::Binary(bytes) => TopicValue::Binary(bytes),
+            },
+            published: Published::from_binding(message.published),
+        }
+    }
+}
+
+impl Published {
+    /// A token for resuming a subscription just after this message.
+    pub fn resume_token(&self) -> ResumeToken {
+        ResumeToken {
+            sequence_number: self.sequence_number,
+            sequence_page: self.sequence_page,
+        }
+    }
+}
+
+/// The last message a consumer handled, for resuming a subscription right after it.
+///
+/// Tokens are written as `page.number`, like `3.1042`, so they can be stored in the cache or
+/// passed along in a message, and parsed back with [str::parse].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResumeToken {
+    /// The sequence number of the last message handled.
+    pub sequence_number: u64,
+    /// The sequence page of the last message handled.
+    pub sequence_page: u64,
+}
+
+impl ResumeToken {
+    /// The sequence number to resume reading from.
+    pub fn next_sequence_number(&self) -> u64 {
+        self.sequence_number.saturating_add(1)
+    }
+}
+
+impl Display for ResumeToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.sequence_page, self.sequence_number)
+    }
+}
+
+/// A resume token could not be parsed.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid resume token {token:?}: expected page.number, like 3.1042")]
+pub struct ResumeTokenError {
+    /// The text that was not a resume token.
+    pub token: String,
+}
+
+impl FromStr for ResumeToken {
+    type Err = ResumeTokenError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let invalid = || ResumeTokenError {
+            token: token.to_string(),
+        };
+        let (page, number) = token.split_once('.').ok_or_else(invalid)?;
+        Ok(Self {
+            sequence_page: page.parse().map_err(|_| invalid())?,
+            sequence_number: number.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// How a message's sequence number compares to the messages seen before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceObservation {
+    /// The first message seen.
+    First,
+    /// The message right after the last one seen.
+    Next,
+    /// A message at or before the last one seen, which was already handled.
+    Duplicate,
+    /// Messages were skipped between the last one seen and this one.
+    Gap {
+        /// The sequence numbers that were not seen.
+        missed: Range<u64>,
+    },
+    /// The topic's numbering restarted, so whether messages were missed is unknown.
+    Restarted,
+}
+
+/// Tracks the sequence numbers a consumer has handled, to skip redelivered messages and
+/// detect lost ones.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions_host::topics::{ResumeToken, SequenceObservation, SequenceTracker};
+///
+/// let checkpoint: Option<ResumeToken> = "3.1041".parse().ok();
+/// let mut tracker = SequenceTracker::new(checkpoint);
+/// # let (page, number) = (3, 1042);
+/// match tracker.observe(page, number) {
+///     SequenceObservation::Duplicate => { /* already handled; skip it */ }
+///     SequenceObservation::Gap { missed } => {
+///         eprintln!("lost {} messages", missed.end - missed.start);
+///     }
+///     _ => { /* handle the message */ }
+/// }
+/// // Store tracker.resume_token() to pick up from here next time.
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SequenceTracker {
+    last: Option<ResumeToken>,
+}
+
+impl SequenceTracker {
+    /// A tracker that continues after `last`, or starts fresh.
+    pub fn new(last: Option<ResumeToken>) -> Self {
+        Self { last }
+    }
+
+    /// Record a message, returning how it relates to the messages before it. Duplicates do
+    /// not move the tracker; everything else becomes the last message seen.
+    pub fn observe(&mut self, sequence_page: u64, sequence_number: u64) -> SequenceObservation {
+        let observation = match self.last {
+            None => SequenceObservation::First,
+            Some(last) if last.sequence_page != sequence_page => SequenceObservation::Restarted,
+            Some(last) if sequence_number <= last.sequence_number => {
+                return SequenceObservation::Duplicate;
+            }
+            Some(last) if sequence_number == last.next_sequence_number() => {
+                SequenceObservation::Next
+            }
+            Some(last) => SequenceObservation::Gap {
+                missed: last.next_sequence_number()..sequence_number,
+            },
+        };
+        self.last = Some(ResumeToken {
+            sequence_number,
+            sequence_page,
+        });
+        observation
+    }
+
+    /// The token to resume after the last message seen, if any.
+    pub fn resume_token(&self) -> Option<ResumeToken> {
+        self.last
+    }
+}
+
 /// Bind a type to a kind of topic message
 pub trait PublishKind {
     /// Type of payload to publish
@@ -130,3 +442,37 @@ pub enum Publish<'a, P: Encode = &'a [u8]> {
     /// Publish encoded bytes to the topic
     Bytes(P),
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_tokens_round_trip() {
+        let token: ResumeToken = "3.1042".parse().unwrap();
+        assert_eq!(3, token.sequence_page);
+        assert_eq!(1043, token.next_sequence_number());
+        assert_eq!("3.1042", token.to_string());
+        assert!("1042".parse::<ResumeToken>().is_err());
+        assert!("a.b".parse::<ResumeToken>().is_err());
+    }
+
+    #[test]
+    fn tracker_spots_duplicates_gaps_and_restarts() {
+        let mut tracker = SequenceTracker::default();
+        assert_eq!(SequenceObservation::First, tracker.observe(1, 5));
+        assert_eq!(SequenceObservation::Next, tracker.observe(1, 6));
+        assert_eq!(SequenceObservation::Duplicate, tracker.observe(1, 6));
+        assert_eq!(SequenceObservation::Duplicate, tracker.observe(1, 2));
+        assert_eq!(
+            SequenceObservation::Gap { missed: 7..10 },
+            tracker.observe(1, 10)
+        );
+        assert_eq!(SequenceObservation::Restarted, tracker.observe(2, 1));
+        assert_eq!("2.1", tracker.resume_token().unwrap().to_string());
+
+        let mut resumed = SequenceTracker::new(tracker.resume_token());
+        assert_eq!(SequenceObservation::Next, resumed.observe(2, 2));
+    }
+}
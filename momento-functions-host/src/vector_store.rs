@@ -0,0 +1,1365 @@
+//! Nearest-neighbor search over a vector index
+//!
+//! [VectorStore] writes, searches, and deletes documents in a vector index. Code written
+//! against it runs on [TurbopufferStore], [PineconeStore], [QdrantStore], or
+//! [RedisVectorStore] alike, and [from_env] picks one from the Function's environment, so you
+//! can switch backends without a code change.
+//!
+//! Query filters are Turbopuffer [Filter]s, re-exported here from
+//! `momento-functions-turbopuffer`. The other stores translate them into their own filter
+//! syntax, and report a [VectorStoreError::UnsupportedFilter] for what they cannot express.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions_host::vector_store::{self, Filter, VectorDocument, VectorStore};
+//!
+//! let store = match vector_store::from_env() {
+//!     Ok(store) => store,
+//!     Err(e) => {
+//!         eprintln!("bad configuration: {e}");
+//!         return;
+//!     }
+//! };
+//! let embedding: Vec<f32> = vec![0.1, 0.2, 0.3];
+//! let document = VectorDocument::new("article-1", embedding.clone())
+//!     .attribute("title", "Draft lottery results")
+//!     .attribute("category", "news");
+//! if let Err(e) = store.upsert(&[document]) {
+//!     eprintln!("upsert failed: {e}");
+//! }
+//!
+//! let news = Filter::field("category").eq("news");
+//! match store.query_filtered(&embedding, 5, Some(&news)) {
+//!     Ok(matches) => {
+//!         for found in matches {
+//!             println!("{} {:?} {:?}", found.id, found.distance, found.text("title"));
+//!         }
+//!     }
+//!     Err(e) => eprintln!("query failed: {e}"),
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+pub use momento_functions_turbopuffer::{ComparisonOp, Filter};
+#[cfg(feature = "redis")]
+use momento_functions_wit::host::momento::host::redis::RedisError;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::{Config, ConfigError};
+#[cfg(feature = "redis")]
+use crate::redis::{
+    Command, ExecuteCommand, RedisClient, RedisValue,
+    search::{self, KnnQuery, SearchError},
+};
+#[cfg(feature = "http")]
+use crate::{
+    encoding::Json,
+    http::{self, HttpPostError},
+};
+
+/// An error occurred while using a vector store.
+#[derive(Debug, thiserror::Error)]
+pub enum VectorStoreError {
+    /// The request to the vector store failed.
+    #[cfg(feature = "http")]
+    #[error(transparent)]
+    Http(#[from] HttpPostError<serde_json::Error>),
+    /// The vector store rejected the request.
+    #[error("Vector store returned status {status}: {message}")]
+    Rejected {
+        /// The HTTP status the vector store responded with.
+        status: u16,
+        /// The body of the vector store's response.
+        message: String,
+    },
+    /// The vector store's response could not be decoded.
+    #[error("Failed to decode vector store response.")]
+    Decode {
+        /// The underlying decoding error.
+        #[from]
+        cause: serde_json::Error,
+    },
+    /// The search index could not be queried or written.
+    #[cfg(feature = "redis")]
+    #[error(transparent)]
+    Search(#[from] SearchError),
+    /// The filter uses something this vector store cannot express.
+    #[error("Unsupported filter: {reason}")]
+    UnsupportedFilter {
+        /// What could not be expressed.
+        reason: String,
+    },
+}
+
+#[cfg(feature = "redis")]
+impl From<RedisError> for VectorStoreError {
+    fn from(e: RedisError) -> Self {
+        Self::Search(e.into())
+    }
+}
+
+/// A document to write to a [VectorStore].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorDocument {
+    /// The document's id. Writing a document with an existing id replaces it.
+    pub id: String,
+    /// The document's embedding.
+    pub vector: Vec<f32>,
+    /// The document's attributes, which can be returned by and filtered on in queries.
+    pub attributes: HashMap<String, Value>,
+}
+
+impl VectorDocument {
+    /// A document with no attributes.
+    pub fn new(id: impl Into<String>, vector: Vec<f32>) -> Self {
+        Self {
+            id: id.into(),
+            vector,
+            attributes: HashMap::new(),
+        }
+    }
+
+    /// Set an attribute.
+    pub fn attribute(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// A document found by a [VectorStore] query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorMatch {
+    /// The document's id.
+    pub id: String,
+    /// The distance from the query vector, when the store reports it. Smaller is closer.
+    pub distance: Option<f32>,
+    /// The document's returned attributes.
+    pub attributes: HashMap<String, Value>,
+}
+
+impl VectorMatch {
+    /// Get an attribute as a string, if it is present and a string.
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).and_then(Value::as_str)
+    }
+}
+
+/// A vector index that can store documents and find the nearest neighbors of a vector.
+pub trait VectorStore {
+    /// Write documents, replacing any with the same ids.
+    fn upsert(&self, documents: &[VectorDocument]) -> Result<(), VectorStoreError>;
+
+    /// Find the `top_k` documents nearest to `vector` that match `filter`, nearest first.
+    fn query_filtered(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError>;
+
+    /// Find the `top_k` documents nearest to `vector`, nearest first.
+    fn query(&self, vector: &[f32], top_k: usize) -> Result<Vec<VectorMatch>, VectorStoreError> {
+        self.query_filtered(vector, top_k, None)
+    }
+
+    /// Delete documents by id. Ids that do not exist are ignored.
+    fn delete(&self, ids: &[String]) -> Result<(), VectorStoreError>;
+}
+
+impl<S: VectorStore + ?Sized> VectorStore for Box<S> {
+    fn upsert(&self, documents: &[VectorDocument]) -> Result<(), VectorStoreError> {
+        (**self).upsert(documents)
+    }
+
+    fn query_filtered(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError> {
+        (**self).query_filtered(vector, top_k, filter)
+    }
+
+    fn delete(&self, ids: &[String]) -> Result<(), VectorStoreError> {
+        (**self).delete(ids)
+    }
+}
+
+/// Which backend [from_env] uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Backend {
+    Turbopuffer,
+    Pinecone,
+    Qdrant,
+    Redis,
+}
+
+#[derive(Deserialize)]
+struct StoreSettings {
+    backend: Backend,
+}
+
+#[cfg(feature = "http")]
+#[derive(Deserialize)]
+struct TurbopufferSettings {
+    region: String,
+    namespace: String,
+    api_key: String,
+    #[serde(default)]
+    include_attributes: Vec<String>,
+    distance_metric: Option<String>,
+}
+
+#[cfg(feature = "http")]
+#[derive(Deserialize)]
+struct PineconeSettings {
+    host: String,
+    api_key: String,
+    namespace: Option<String>,
+    metric: Option<String>,
+}
+
+#[cfg(feature = "http")]
+#[derive(Deserialize)]
+struct QdrantSettings {
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+    vector_name: Option<String>,
+    distance: Option<String>,
+}
+
+#[cfg(feature = "redis")]
+#[derive(Deserialize)]
+struct RedisSettings {
+    connection_string: String,
+    index: String,
+    vector_field: Option<String>,
+    key_prefix: Option<String>,
+    #[serde(default)]
+    return_fields: Vec<String>,
+}
+
+/// Create the vector store named by the `VECTOR_STORE_BACKEND` environment variable.
+///
+/// - `turbopuffer` reads `TURBOPUFFER_REGION`, `TURBOPUFFER_NAMESPACE`, and
+///   `TURBOPUFFER_API_KEY`, and optionally `TURBOPUFFER_INCLUDE_ATTRIBUTES` (comma-separated)
+///   and `TURBOPUFFER_DISTANCE_METRIC`.
+/// - `pinecone` reads `PINECONE_HOST` and `PINECONE_API_KEY`, and optionally
+///   `PINECONE_NAMESPACE` and `PINECONE_METRIC`.
+/// - `qdrant` reads `QDRANT_URL` and `QDRANT_COLLECTION`, and optionally `QDRANT_API_KEY`,
+///   `QDRANT_VECTOR_NAME`, and `QDRANT_DISTANCE`.
+/// - `redis` reads `REDIS_CONNECTION_STRING` and `REDIS_INDEX`, and optionally
+///   `REDIS_VECTOR_FIELD` (defaulting to `vector`), `REDIS_KEY_PREFIX`, and
+///   `REDIS_RETURN_FIELDS` (comma-separated).
+///
+/// A backend whose crate feature is disabled is reported as an invalid value.
+pub fn from_env() -> Result<Box<dyn VectorStore>, ConfigError> {
+    let settings: StoreSettings = Config::with_prefix("VECTOR_STORE_").load()?;
+    match settings.backend {
+        #[cfg(feature = "http")]
+        Backend::Turbopuffer => {
+            let settings: TurbopufferSettings = Config::with_prefix("TURBOPUFFER_").load()?;
+            let mut store =
+                TurbopufferStore::new(settings.region, settings.namespace, settings.api_key)
+                    .include_attributes(settings.include_attributes);
+            if let Some(distance_metric) = settings.distance_metric {
+                store = store.distance_metric(distance_metric);
+            }
+            Ok(Box::new(store))
+        }
+        #[cfg(feature = "http")]
+        Backend::Pinecone => {
+            let settings: PineconeSettings = Config::with_prefix("PINECONE_").load()?;
+            let mut store = PineconeStore::new(settings.host, settings.api_key);
+            if let Some(namespace) = settings.namespace {
+                store = store.namespace(namespace);
+            }
+            if let Some(metric) = settings.metric {
+                store = store.metric(metric);
+            }
+            Ok(Box::new(store))
+        }
+        #[cfg(feature = "http")]
+        Backend::Qdrant => {
+            let settings: QdrantSettings = Config::with_prefix("QDRANT_").load()?;
+            let mut store = QdrantStore::new(settings.url, settings.collection);
+            if let Some(api_key) = settings.api_key {
+                store = store.api_key(api_key);
+            }
+            if let Some(vector_name) = settings.vector_name {
+                store = store.vector_name(vector_name);
+            }
+            if let Some(distance) = settings.distance {
+                store = store.distance(distance);
+            }
+            Ok(Box::new(store))
+        }
+        #[cfg(feature = "redis")]
+        Backend::Redis => {
+            let settings: RedisSettings = Config::with_prefix("REDIS_").load()?;
+            let mut store = RedisVectorStore::new(
+                RedisClient::new(settings.connection_string),
+                settings.index,
+                settings.vector_field.as_deref().unwrap_or("vector"),
+            )
+            .return_fields(settings.return_fields);
+            if let Some(key_prefix) = settings.key_prefix {
+                store = store.key_prefix(key_prefix);
+            }
+            Ok(Box::new(store))
+        }
+        #[allow(unreachable_patterns)]
+        backend => Err(ConfigError::Invalid {
+            variable: "VECTOR_STORE_BACKEND".to_string(),
+            message: format!("the {backend:?} backend is not enabled in this build"),
+        }),
+    }
+}
+
+/// A Turbopuffer namespace.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct TurbopufferStore {
+    endpoint: String,
+    api_key: String,
+    include_attributes: Vec<String>,
+    distance_metric: String,
+}
+
+#[cfg(feature = "http")]
+impl TurbopufferStore {
+    /// Use `namespace` in `region`, like `gcp-us-central1`, authenticating with `api_key`.
+    pub fn new(
+        region: impl AsRef<str>,
+        namespace: impl AsRef<str>,
+        api_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            endpoint: format!(
+                "https://{}.turbopuffer.com/v2/namespaces/{}",
+                region.as_ref(),
+                namespace.as_ref()
+            ),
+            api_key: api_key.into(),
+            include_attributes: Vec::new(),
+            distance_metric: "cosine_distance".to_string(),
+        }
+    }
+
+    /// Use the namespace named by the `TURBOPUFFER_REGION`, `TURBOPUFFER_NAMESPACE`, and
+    /// `TURBOPUFFER_API_KEY` environment variables.
+    pub fn from_env() -> Self {
+        let env = |name| std::env::var(name).unwrap_or_default();
+        Self::new(
+            env("TURBOPUFFER_REGION"),
+            env("TURBOPUFFER_NAMESPACE"),
+            env("TURBOPUFFER_API_KEY"),
+        )
+    }
+
+    /// Return these attributes of each document. Without this, only ids and distances are
+    /// returned.
+    pub fn include_attributes(
+        mut self,
+        attributes: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.include_attributes = attributes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// How upserted vectors are compared, like `euclidean_squared`. Defaults to
+    /// `cosine_distance`, and must match the namespace's existing metric.
+    pub fn distance_metric(mut self, distance_metric: impl Into<String>) -> Self {
+        self.distance_metric = distance_metric.into();
+        self
+    }
+
+    fn post(&self, path: &str, body: Value) -> Result<Value, VectorStoreError> {
+        post_json(
+            match path {
+                "" => self.endpoint.clone(),
+                path => format!("{}/{path}", self.endpoint),
+            },
+            [(
+                "authorization".to_string(),
+                format!("Bearer {}", self.api_key),
+            )],
+            body,
+        )
+    }
+}
+
+#[cfg(feature = "http")]
+impl VectorStore for TurbopufferStore {
+    fn upsert(&self, documents: &[VectorDocument]) -> Result<(), VectorStoreError> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let rows: Vec<Value> = documents.iter().map(turbopuffer_row).collect();
+        self.post(
+            "",
+            serde_json::json!({
+                "upsert_rows": rows,
+                "distance_metric": self.distance_metric,
+            }),
+        )?;
+        Ok(())
+    }
+
+    fn query_filtered(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError> {
+        let mut body = serde_json::json!({
+            "rank_by": ["vector", "ANN", vector],
+            "top_k": top_k,
+        });
+        if !self.include_attributes.is_empty() {
+            body["include_attributes"] = self.include_attributes.clone().into();
+        }
+        if let Some(filter) = filter {
+            body["filters"] = serde_json::to_value(filter)?;
+        }
+        let response = self.post("query", body)?;
+        parse_turbopuffer_rows(response)
+    }
+
+    fn delete(&self, ids: &[String]) -> Result<(), VectorStoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.post("", serde_json::json!({ "deletes": ids }))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "http")]
+fn turbopuffer_row(document: &VectorDocument) -> Value {
+    let mut row: serde_json::Map<String, Value> = document
+        .attributes
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    row.insert("id".to_string(), document.id.clone().into());
+    row.insert("vector".to_string(), document.vector.clone().into());
+    Value::Object(row)
+}
+
+#[cfg(feature = "http")]
+fn parse_turbopuffer_rows(mut response: Value) -> Result<Vec<VectorMatch>, VectorStoreError> {
+    let rows: Vec<serde_json::Map<String, Value>> =
+        serde_json::from_value(response["rows"].take())?;
+    Ok(rows
+        .into_iter()
+        .map(|mut row| {
+            let id = match row.remove("id") {
+                Some(Value::String(id)) => id,
+                Some(id) => id.to_string(),
+                None => String::new(),
+            };
+            let distance = row
+                .remove("$dist")
+                .and_then(|distance| distance.as_f64())
+                .map(|distance| distance as f32);
+            VectorMatch {
+                id,
+                distance,
+                attributes: row.into_iter().collect(),
+            }
+        })
+        .collect())
+}
+
+/// A Pinecone index.
+///
+/// Attributes are written as Pinecone metadata, which holds strings, numbers, booleans, and
+/// lists of strings. Filters translate to Pinecone's metadata filter operators; globs, and
+/// negated ranges, have no equivalent and are rejected.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct PineconeStore {
+    host: String,
+    api_key: String,
+    namespace: String,
+    metric: String,
+}
+
+#[cfg(feature = "http")]
+impl PineconeStore {
+    /// Use the index at `host`, like `my-index-abc123.svc.us-east1-gcp.pinecone.io`,
+    /// authenticating with `api_key`.
+    pub fn new(host: impl AsRef<str>, api_key: impl Into<String>) -> Self {
+        let host = host.as_ref().trim_end_matches('/');
+        Self {
+            host: match host.starts_with("https://") || host.starts_with("http://") {
+                true => host.to_string(),
+                false => format!("https://{host}"),
+            },
+            api_key: api_key.into(),
+            namespace: String::new(),
+            metric: "cosine".to_string(),
+        }
+    }
+
+    /// Read and write this namespace of the index. Defaults to the default namespace.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// The index's metric: `cosine`, `dotproduct`, or `euclidean`. Defaults to `cosine`.
+    /// Pinecone reports scores, and this is how they become [VectorMatch::distance]s.
+    pub fn metric(mut self, metric: impl Into<String>) -> Self {
+        self.metric = metric.into();
+        self
+    }
+
+    fn post(&self, path: &str, body: Value) -> Result<Value, VectorStoreError> {
+        post_json(
+            format!("{}/{path}", self.host),
+            [
+                ("api-key".to_string(), self.api_key.clone()),
+                ("x-pinecone-api-version".to_string(), "2025-04".to_string()),
+            ],
+            body,
+        )
+    }
+}
+
+#[cfg(feature = "http")]
+impl VectorStore for PineconeStore {
+    fn upsert(&self, documents: &[VectorDocument]) -> Result<(), VectorStoreError> {
+        // Pinecone takes at most 1000 vectors per request.
+        for chunk in documents.chunks(1000) {
+            let vectors: Vec<Value> = chunk
+                .iter()
+                .map(|document| {
+                    let mut vector = serde_json::json!({
+                        "id": document.id,
+                        "values": document.vector,
+                    });
+                    let metadata: serde_json::Map<String, Value> = document
+                        .attributes
+                        .iter()
+                        .filter(|(_, value)| !value.is_null())
+                        .map(|(name, value)| (name.clone(), value.clone()))
+                        .collect();
+                    if !metadata.is_empty() {
+                        vector["metadata"] = Value::Object(metadata);
+                    }
+                    vector
+                })
+                .collect();
+            self.post(
+                "vectors/upsert",
+                serde_json::json!({ "vectors": vectors, "namespace": self.namespace }),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn query_filtered(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError> {
+        let mut body = serde_json::json!({
+            "vector": vector,
+            "topK": top_k,
+            "namespace": self.namespace,
+            "includeMetadata": true,
+        });
+        if let Some(filter) = filter {
+            body["filter"] = pinecone_filter(filter)?;
+        }
+        let response = self.post("query", body)?;
+        parse_scored(response, "matches", "metadata", &self.metric)
+    }
+
+    fn delete(&self, ids: &[String]) -> Result<(), VectorStoreError> {
+        for chunk in ids.chunks(1000) {
+            self.post(
+                "vectors/delete",
+                serde_json::json!({ "ids": chunk, "namespace": self.namespace }),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A Qdrant collection.
+///
+/// Qdrant point ids are unsigned integers or UUIDs. Document ids of either form are used as
+/// they are; any other id is hashed into a UUID, and the original is kept in the point's
+/// `_id` payload field so queries can return it. Filters translate to Qdrant's `must`,
+/// `should`, and `must_not` clauses; globs have no equivalent and are rejected.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct QdrantStore {
+    collection_url: String,
+    api_key: Option<String>,
+    vector_name: Option<String>,
+    distance: String,
+}
+
+#[cfg(feature = "http")]
+impl QdrantStore {
+    /// Use `collection` on the Qdrant server at `url`, like `https://xyz.cloud.qdrant.io:6333`.
+    pub fn new(url: impl AsRef<str>, collection: impl AsRef<str>) -> Self {
+        Self {
+            collection_url: format!(
+                "{}/collections/{}",
+                url.as_ref().trim_end_matches('/'),
+                http::encode_component(collection.as_ref())
+            ),
+            api_key: None,
+            vector_name: None,
+            distance: "Cosine".to_string(),
+        }
+    }
+
+    /// Authenticate with this API key.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Write and search this named vector, for collections with more than one.
+    pub fn vector_name(mut self, vector_name: impl Into<String>) -> Self {
+        self.vector_name = Some(vector_name.into());
+        self
+    }
+
+    /// The collection's distance: `Cosine`, `Dot`, `Euclid`, or `Manhattan`. Defaults to
+    /// `Cosine`. Qdrant reports scores, and this is how they become [VectorMatch::distance]s.
+    pub fn distance(mut self, distance: impl Into<String>) -> Self {
+        self.distance = distance.into();
+        self
+    }
+
+    fn post(&self, path: &str, body: Value) -> Result<Value, VectorStoreError> {
+        post_json(
+            format!("{}/{path}", self.collection_url),
+            self.api_key
+                .iter()
+                .map(|api_key| ("api-key".to_string(), api_key.clone())),
+            body,
+        )
+    }
+}
+
+#[cfg(feature = "http")]
+impl VectorStore for QdrantStore {
+    fn upsert(&self, documents: &[VectorDocument]) -> Result<(), VectorStoreError> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let points: Vec<Value> = documents
+            .iter()
+            .map(|document| qdrant_point(document, self.vector_name.as_deref()))
+            .collect();
+        self.post(
+            "points/batch?wait=true",
+            serde_json::json!({ "operations": [{ "upsert": { "points": points } }] }),
+        )?;
+        Ok(())
+    }
+
+    fn query_filtered(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError> {
+        let mut body = serde_json::json!({
+            "vector": match &self.vector_name {
+                Some(name) => serde_json::json!({ "name": name, "vector": vector }),
+                None => serde_json::json!(vector),
+            },
+            "limit": top_k,
+            "with_payload": true,
+        });
+        if let Some(filter) = filter {
+            body["filter"] = qdrant_filter(filter)?;
+        }
+        let response = self.post("points/search", body)?;
+        let mut matches = parse_scored(response, "result", "payload", &self.distance)?;
+        for found in &mut matches {
+            if let Some(Value::String(id)) = found.attributes.remove(QDRANT_ID_FIELD) {
+                found.id = id;
+            }
+        }
+        Ok(matches)
+    }
+
+    fn delete(&self, ids: &[String]) -> Result<(), VectorStoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let points: Vec<Value> = ids.iter().map(|id| qdrant_id(id)).collect();
+        self.post(
+            "points/delete?wait=true",
+            serde_json::json!({ "points": points }),
+        )?;
+        Ok(())
+    }
+}
+
+/// The payload field holding a document id that is not a valid Qdrant point id.
+#[cfg(feature = "http")]
+const QDRANT_ID_FIELD: &str = "_id";
+
+#[cfg(feature = "http")]
+fn qdrant_point(document: &VectorDocument, vector_name: Option<&str>) -> Value {
+    let mut payload: serde_json::Map<String, Value> = document
+        .attributes
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    let id = qdrant_id(&document.id);
+    if id.as_str() != Some(document.id.as_str()) && !id.is_u64() {
+        payload.insert(QDRANT_ID_FIELD.to_string(), document.id.clone().into());
+    }
+    serde_json::json!({
+        "id": id,
+        "vector": match vector_name {
+            Some(name) => serde_json::json!({ name: document.vector }),
+            None => serde_json::json!(document.vector),
+        },
+        "payload": payload,
+    })
+}
+
+/// The point id for a document id: the id itself when it is an unsigned integer or a UUID,
+/// otherwise a UUID made from its SHA-256 hash.
+#[cfg(feature = "http")]
+fn qdrant_id(id: &str) -> Value {
+    use sha2::Digest;
+
+    if let Ok(number) = id.parse::<u64>() {
+        return number.into();
+    }
+    let is_uuid = id.len() == 36
+        && id.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        });
+    if is_uuid {
+        return id.into();
+    }
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&sha2::Sha256::digest(id.as_bytes())[..16]);
+    // Mark it as a version 8 (custom) RFC 9562 UUID.
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+    .into()
+}
+
+/// Read `{list: [{id, score, attributes}]}`, turning scores into distances for `metric`.
+#[cfg(feature = "http")]
+fn parse_scored(
+    mut response: Value,
+    list: &str,
+    attributes: &str,
+    metric: &str,
+) -> Result<Vec<VectorMatch>, VectorStoreError> {
+    let found: Vec<serde_json::Map<String, Value>> = serde_json::from_value(response[list].take())?;
+    Ok(found
+        .into_iter()
+        .map(|mut found| {
+            let id = match found.remove("id") {
+                Some(Value::String(id)) => id,
+                Some(id) => id.to_string(),
+                None => String::new(),
+            };
+            let distance = found
+                .get("score")
+                .and_then(Value::as_f64)
+                .map(|score| match metric.to_ascii_lowercase().as_str() {
+                    "cosine" => 1.0 - score,
+                    "dot" | "dotproduct" => -score,
+                    _ => score,
+                } as f32);
+            let attributes = match found.remove(attributes) {
+                Some(Value::Object(attributes)) => attributes.into_iter().collect(),
+                _ => HashMap::new(),
+            };
+            VectorMatch {
+                id,
+                distance,
+                attributes,
+            }
+        })
+        .collect())
+}
+
+/// Translate a filter into a Pinecone metadata filter.
+#[cfg(feature = "http")]
+fn pinecone_filter(filter: &Filter) -> Result<Value, VectorStoreError> {
+    let group = |filters: &[Filter], operator: &str| {
+        if filters.is_empty() {
+            return Err(pinecone_unsupported("an empty And or Or"));
+        }
+        let clauses = filters
+            .iter()
+            .map(pinecone_filter)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(serde_json::json!({ operator: clauses }))
+    };
+    match filter {
+        Filter::And(filters) => group(filters, "$and"),
+        Filter::Or(filters) => group(filters, "$or"),
+        Filter::Not(filter) => pinecone_filter(&negate(filter)?),
+        Filter::Comparison { field, op, value } => {
+            let operator = match op {
+                ComparisonOp::Eq => "$eq",
+                ComparisonOp::Neq => "$ne",
+                ComparisonOp::In => "$in",
+                ComparisonOp::NotIn => "$nin",
+                ComparisonOp::Lt => "$lt",
+                ComparisonOp::Lte => "$lte",
+                ComparisonOp::Gt => "$gt",
+                ComparisonOp::Gte => "$gte",
+                ComparisonOp::Glob | ComparisonOp::NotGlob => {
+                    return Err(pinecone_unsupported("a glob"));
+                }
+            };
+            Ok(serde_json::json!({ field: { operator: value } }))
+        }
+    }
+}
+
+/// Push a Not down to the comparisons, which Pinecone can negate one by one.
+#[cfg(feature = "http")]
+fn negate(filter: &Filter) -> Result<Filter, VectorStoreError> {
+    let negate_all = |filters: &[Filter]| filters.iter().map(negate).collect::<Result<Vec<_>, _>>();
+    Ok(match filter {
+        Filter::And(filters) => Filter::Or(negate_all(filters)?),
+        Filter::Or(filters) => Filter::And(negate_all(filters)?),
+        Filter::Not(filter) => (**filter).clone(),
+        Filter::Comparison { field, op, value } => Filter::Comparison {
+            field: field.clone(),
+            op: match op {
+                ComparisonOp::Eq => ComparisonOp::Neq,
+                ComparisonOp::Neq => ComparisonOp::Eq,
+                ComparisonOp::In => ComparisonOp::NotIn,
+                ComparisonOp::NotIn => ComparisonOp::In,
+                ComparisonOp::Glob | ComparisonOp::NotGlob => {
+                    return Err(pinecone_unsupported("a glob"));
+                }
+                _ => return Err(pinecone_unsupported("a negated range")),
+            },
+            value: value.clone(),
+        },
+    })
+}
+
+#[cfg(feature = "http")]
+fn pinecone_unsupported(reason: &str) -> VectorStoreError {
+    VectorStoreError::UnsupportedFilter {
+        reason: format!("Pinecone cannot express {reason}"),
+    }
+}
+
+/// Translate a filter into a Qdrant filter.
+#[cfg(feature = "http")]
+fn qdrant_filter(filter: &Filter) -> Result<Value, VectorStoreError> {
+    let clauses = |filters: &[Filter]| {
+        if filters.is_empty() {
+            return Err(qdrant_unsupported("an empty And or Or"));
+        }
+        filters
+            .iter()
+            .map(qdrant_filter)
+            .collect::<Result<Vec<_>, _>>()
+    };
+    match filter {
+        Filter::And(filters) => Ok(serde_json::json!({ "must": clauses(filters)? })),
+        Filter::Or(filters) => Ok(serde_json::json!({ "should": clauses(filters)? })),
+        Filter::Not(filter) => Ok(serde_json::json!({ "must_not": [qdrant_filter(filter)?] })),
+        Filter::Comparison { field, op, value } => {
+            let condition = |condition: Value| {
+                let mut condition = condition;
+                condition["key"] = field.clone().into();
+                condition
+            };
+            let not = |condition: Value| serde_json::json!({ "must_not": [condition] });
+            let range = |bound: &str| condition(serde_json::json!({ "range": { bound: value } }));
+            Ok(match op {
+                ComparisonOp::Eq => condition(serde_json::json!({ "match": { "value": value } })),
+                ComparisonOp::Neq => not(condition(
+                    serde_json::json!({ "match": { "value": value } }),
+                )),
+                ComparisonOp::In => condition(serde_json::json!({ "match": { "any": value } })),
+                ComparisonOp::NotIn => {
+                    condition(serde_json::json!({ "match": { "except": value } }))
+                }
+                ComparisonOp::Lt => range("lt"),
+                ComparisonOp::Lte => range("lte"),
+                ComparisonOp::Gt => range("gt"),
+                ComparisonOp::Gte => range("gte"),
+                ComparisonOp::Glob | ComparisonOp::NotGlob => {
+                    return Err(qdrant_unsupported("a glob"));
+                }
+            })
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+fn qdrant_unsupported(reason: &str) -> VectorStoreError {
+    VectorStoreError::UnsupportedFilter {
+        reason: format!("Qdrant cannot express {reason}"),
+    }
+}
+
+/// POST a JSON body and read a JSON response, failing on any status but 2xx.
+#[cfg(feature = "http")]
+fn post_json(
+    url: String,
+    headers: impl IntoIterator<Item = (String, String)>,
+    body: Value,
+) -> Result<Value, VectorStoreError> {
+    let mut response = http::post(
+        url,
+        headers
+            .into_iter()
+            .chain([("content-type".to_string(), "application/json".to_string())]),
+        Json(body),
+    )?;
+    if !(200..300).contains(&response.status) {
+        return Err(VectorStoreError::Rejected {
+            status: response.status,
+            message: String::from_utf8_lossy(&response.body).into_owned(),
+        });
+    }
+    let Json(body) = response.extract::<Json<Value>>()?;
+    Ok(body)
+}
+
+/// A Redis or Valkey search index over hashes with a vector field.
+///
+/// Documents are written as hashes with `HSET`, with the vector as little-endian 32-bit
+/// floats. Create the index with [search::ensure_index]. Filters are translated for tag
+/// and numeric fields: equality and lists on strings compare tags, comparisons on numbers
+/// use ranges, and globs are supported as tag prefixes like `news*`.
+#[cfg(feature = "redis")]
+#[derive(Debug, Clone)]
+pub struct RedisVectorStore<C> {
+    client: C,
+    index: String,
+    vector_field: String,
+    key_prefix: String,
+    return_fields: Vec<String>,
+}
+
+#[cfg(feature = "redis")]
+impl<C: ExecuteCommand> RedisVectorStore<C> {
+    /// Query `index` by its `vector_field` using `client`.
+    ///
+    /// ```rust,no_run
+    /// use momento_functions_host::redis::RedisClient;
+    /// use momento_functions_host::vector_store::RedisVectorStore;
+    ///
+    /// let store = RedisVectorStore::new(
+    ///     RedisClient::new("valkey://my.valkey.instance:6379"),
+    ///     "document_index",
+    ///     "vector",
+    /// )
+    /// .return_fields(["title", "text"]);
+    /// ```
+    pub fn new(client: C, index: impl Into<String>, vector_field: impl Into<String>) -> Self {
+        Self {
+            client,
+            index: index.into(),
+            vector_field: vector_field.into(),
+            key_prefix: String::new(),
+            return_fields: Vec::new(),
+        }
+    }
+
+    /// Store each document under its id with this prefix, like `document:`. The prefix
+    /// should match the index's, and is removed from the ids of query results.
+    pub fn key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// Only return these fields of each document. Without this, every field is returned,
+    /// including the vector itself.
+    pub fn return_fields(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.return_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+#[cfg(feature = "redis")]
+impl<C: ExecuteCommand> RedisVectorStore<C> {
+    fn execute(&self, command: Command) -> Result<(), VectorStoreError> {
+        match self.client.execute(command)? {
+            RedisValue::SimpleError(message) => Err(SearchError::SimpleError { message }.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+impl<C: ExecuteCommand> VectorStore for RedisVectorStore<C> {
+    fn upsert(&self, documents: &[VectorDocument]) -> Result<(), VectorStoreError> {
+        // One command per key, so a cluster client can route each to its own slot.
+        for document in documents {
+            let mut command = Command::builder()
+                .any("HSET")
+                .arg(format!("{}{}", self.key_prefix, document.id));
+            for (name, value) in &document.attributes {
+                if let Some(value) = redis_field_value(value) {
+                    command = command.arg(name.clone()).arg(value);
+                }
+            }
+            let vector: Vec<u8> = document
+                .vector
+                .iter()
+                .copied()
+                .flat_map(f32::to_le_bytes)
+                .collect();
+            command = command.arg(self.vector_field.clone()).arg(vector);
+            self.execute(command.build())?;
+        }
+        Ok(())
+    }
+
+    fn query_filtered(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError> {
+        let mut query = KnnQuery::new(
+            self.index.as_str(),
+            self.vector_field.as_str(),
+            vector,
+            top_k,
+        );
+        if let Some(filter) = filter {
+            query = query.filter(redis_filter(filter)?);
+        }
+        if !self.return_fields.is_empty() {
+            query = query.return_fields(self.return_fields.iter().cloned());
+        }
+        let results = search::search(&self.client, &query)?;
+        Ok(results
+            .documents
+            .into_iter()
+            .map(|document| VectorMatch {
+                id: match document.id.strip_prefix(&self.key_prefix) {
+                    Some(id) => id.to_string(),
+                    None => document.id,
+                },
+                distance: document.score,
+                attributes: document
+                    .fields
+                    .into_iter()
+                    .map(|(name, value)| {
+                        (
+                            name,
+                            Value::String(String::from_utf8_lossy(&value).into_owned()),
+                        )
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    fn delete(&self, ids: &[String]) -> Result<(), VectorStoreError> {
+        for id in ids {
+            self.execute(
+                Command::builder()
+                    .any("DEL")
+                    .arg(format!("{}{id}", self.key_prefix))
+                    .build(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The text a hash field is set to for an attribute. Lists become comma-separated tags, and
+/// nulls are left out.
+#[cfg(feature = "redis")]
+fn redis_field_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(redis_field_value)
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Translate a filter into `FT.SEARCH` query syntax.
+#[cfg(feature = "redis")]
+fn redis_filter(filter: &Filter) -> Result<String, VectorStoreError> {
+    let group = |filters: &[Filter], separator: &str| {
+        if filters.is_empty() {
+            return Err(unsupported("an empty And or Or"));
+        }
+        let clauses = filters
+            .iter()
+            .map(redis_filter)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(format!("({})", clauses.join(separator)))
+    };
+    match filter {
+        Filter::And(filters) => group(filters, " "),
+        Filter::Or(filters) => group(filters, " | "),
+        Filter::Not(filter) => Ok(format!("-{}", redis_filter(filter)?)),
+        Filter::Comparison { field, op, value } => {
+            let field = escape_tag(field);
+            match op {
+                ComparisonOp::Eq => redis_equals(&field, value),
+                ComparisonOp::Neq => Ok(format!("-{}", redis_equals(&field, value)?)),
+                ComparisonOp::In => redis_in(&field, value),
+                ComparisonOp::NotIn => Ok(format!("-{}", redis_in(&field, value)?)),
+                ComparisonOp::Lt => Ok(format!("@{field}:[-inf ({}]", number(value)?)),
+                ComparisonOp::Lte => Ok(format!("@{field}:[-inf {}]", number(value)?)),
+                ComparisonOp::Gt => Ok(format!("@{field}:[({} +inf]", number(value)?)),
+                ComparisonOp::Gte => Ok(format!("@{field}:[{} +inf]", number(value)?)),
+                ComparisonOp::Glob => redis_glob(&field, value),
+                ComparisonOp::NotGlob => Ok(format!("-{}", redis_glob(&field, value)?)),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_equals(field: &str, value: &Value) -> Result<String, VectorStoreError> {
+    match value {
+        Value::Number(n) => Ok(format!("@{field}:[{n} {n}]")),
+        value => Ok(format!("@{field}:{{{}}}", tag(value)?)),
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_in(field: &str, value: &Value) -> Result<String, VectorStoreError> {
+    let Value::Array(values) = value else {
+        return Err(unsupported("In or NotIn without a list"));
+    };
+    if values.is_empty() {
+        return Err(unsupported("In or NotIn with an empty list"));
+    }
+    if values.iter().all(Value::is_number) {
+        let clauses: Vec<String> = values
+            .iter()
+            .map(|n| format!("@{field}:[{n} {n}]"))
+            .collect();
+        return Ok(format!("({})", clauses.join(" | ")));
+    }
+    let tags = values.iter().map(tag).collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("@{field}:{{{}}}", tags.join(" | ")))
+}
+
+#[cfg(feature = "redis")]
+fn redis_glob(field: &str, value: &Value) -> Result<String, VectorStoreError> {
+    let prefix = value
+        .as_str()
+        .and_then(|pattern| pattern.strip_suffix('*'))
+        .filter(|prefix| !prefix.contains(['*', '?', '[']))
+        .ok_or_else(|| unsupported("a glob other than a prefix like `news*`"))?;
+    Ok(format!("@{field}:{{{}*}}", escape_tag(prefix)))
+}
+
+#[cfg(feature = "redis")]
+fn tag(value: &Value) -> Result<String, VectorStoreError> {
+    match value {
+        Value::String(s) => Ok(escape_tag(s)),
+        Value::Bool(b) => Ok(b.to_string()),
+        value => Err(unsupported(&format!("comparing a tag to {value}"))),
+    }
+}
+
+#[cfg(feature = "redis")]
+fn number(value: &Value) -> Result<String, VectorStoreError> {
+    match value {
+        Value::Number(n) => Ok(n.to_string()),
+        value => Err(unsupported(&format!("a range over {value}"))),
+    }
+}
+
+/// Escape the characters that would otherwise end or split a tag or field name.
+#[cfg(feature = "redis")]
+fn escape_tag(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_punctuation() && c != '_' || c.is_whitespace() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(feature = "redis")]
+fn unsupported(reason: &str) -> VectorStoreError {
+    VectorStoreError::UnsupportedFilter {
+        reason: format!("Redis search cannot express {reason}"),
+    }
+}
+
+#[cfg(all(test, any(feature = "http", feature = "redis")))]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn turbopuffer_rows_become_matches() {
+        use serde_json::json;
+
+        let matches = parse_turbopuffer_rows(json!({
+            "rows": [
+                {"id": "a", "$dist": 0.25, "title": "First"},
+                {"id": 7, "title": "Second", "views": 3},
+            ],
+            "performance": {},
+        }))
+        .unwrap();
+        assert_eq!(2, matches.len());
+        assert_eq!("a", matches[0].id);
+        assert_eq!(Some(0.25), matches[0].distance);
+        assert_eq!(Some("First"), matches[0].text("title"));
+        assert_eq!("7", matches[1].id);
+        assert_eq!(None, matches[1].distance);
+        assert_eq!(None, matches[1].text("views"));
+        assert_eq!(Some(&json!(3)), matches[1].attributes.get("views"));
+
+        assert!(parse_turbopuffer_rows(json!({"rows": "nope"})).is_err());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn turbopuffer_rows_carry_attributes() {
+        use serde_json::json;
+
+        let document = VectorDocument::new("a", vec![0.5, 1.0]).attribute("views", 3);
+        assert_eq!(
+            json!({"id": "a", "vector": [0.5, 1.0], "views": 3}),
+            turbopuffer_row(&document)
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn filters_translate_to_pinecone_and_qdrant() {
+        use serde_json::json;
+
+        let filter = Filter::field("category").is_in(["news", "sports"]).and(
+            !Filter::field("views")
+                .eq(0)
+                .or(Filter::field("draft").eq(true)),
+        );
+        assert_eq!(
+            json!({"$and": [
+                {"category": {"$in": ["news", "sports"]}},
+                {"$and": [{"views": {"$ne": 0}}, {"draft": {"$ne": true}}]},
+            ]}),
+            pinecone_filter(&filter).unwrap()
+        );
+        assert_eq!(
+            json!({"must": [
+                {"key": "category", "match": {"any": ["news", "sports"]}},
+                {"must_not": [{"should": [
+                    {"key": "views", "match": {"value": 0}},
+                    {"key": "draft", "match": {"value": true}},
+                ]}]},
+            ]}),
+            qdrant_filter(&filter).unwrap()
+        );
+        assert_eq!(
+            json!({"key": "price", "range": {"lt": 9.5}}),
+            qdrant_filter(&Filter::field("price").lt(9.5)).unwrap()
+        );
+
+        assert!(pinecone_filter(&!Filter::field("price").lt(9.5)).is_err());
+        assert!(pinecone_filter(&Filter::field("slug").glob("draft-*")).is_err());
+        assert!(qdrant_filter(&Filter::field("slug").glob("draft-*")).is_err());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn qdrant_ids_are_integers_or_uuids() {
+        use serde_json::json;
+
+        assert_eq!(json!(42), qdrant_id("42"));
+        let uuid = "5c56c793-69f3-4fbf-87e6-c4bf54c28c26";
+        assert_eq!(json!(uuid), qdrant_id(uuid));
+        let hashed = qdrant_id("article-1");
+        assert_eq!(hashed, qdrant_id("article-1"));
+        assert_eq!(Some('8'), hashed.as_str().and_then(|id| id.chars().nth(14)));
+
+        let point = qdrant_point(&VectorDocument::new("article-1", vec![1.0]), None);
+        assert_eq!(json!("article-1"), point["payload"][QDRANT_ID_FIELD]);
+        let point = qdrant_point(&VectorDocument::new("42", vec![1.0]), Some("text"));
+        assert_eq!(json!({"text": [1.0]}), point["vector"]);
+        assert!(point["payload"].get(QDRANT_ID_FIELD).is_none());
+
+        let matches = parse_scored(
+            json!({"result": [{"id": hashed, "score": 0.75, "payload": {"_id": "article-1"}}]}),
+            "result",
+            "payload",
+            "Cosine",
+        )
+        .unwrap();
+        assert_eq!(Some(0.25), matches[0].distance);
+    }
+
+    #[cfg(feature = "redis")]
+    #[test]
+    fn filters_translate_to_redis_queries() {
+        let filter = Filter::field("category")
+            .is_in(["news", "sports day"])
+            .and(Filter::field("views").gte(10))
+            .and(!Filter::field("draft").eq(true))
+            .and(
+                Filter::field("id")
+                    .eq(7)
+                    .or(Filter::field("slug").glob("draft-*")),
+            );
+        assert_eq!(
+            r"(@category:{news | sports\ day} @views:[10 +inf] -@draft:{true} (@id:[7 7] | @slug:{draft\-*}))",
+            redis_filter(&filter).unwrap()
+        );
+        assert_eq!(
+            "-(@n:[1 1] | @n:[2 2])",
+            redis_filter(&Filter::field("n").not_in([1, 2])).unwrap()
+        );
+        assert_eq!(
+            "@price:[-inf (9.5]",
+            redis_filter(&Filter::field("price").lt(9.5)).unwrap()
+        );
+
+        assert!(redis_filter(&Filter::field("title").glob("*Draft*")).is_err());
+        assert!(redis_filter(&Filter::field("price").lt("cheap")).is_err());
+        assert!(redis_filter(&Filter::all([])).is_err());
+    }
+}
@@ -2,8 +2,9 @@
 //!
 //! These interfaces don't do anything on other kinds of Functions.
 
-use std::{collections::HashMap, env, sync::LazyLock};
+use std::{collections::HashMap, env, net::IpAddr, sync::LazyLock};
 
+use momento_functions_wit::abi::{self, HostCapability, UnsupportedByHost};
 use momento_functions_wit::function_web::momento::functions::web_function_support;
 
 static NOT_FOUND: &str = "<not found>";
@@ -14,10 +15,14 @@ static GET_ENVIRONMENT_ONCE: LazyLock<FunctionEnvironment> = LazyLock::new(|| {
     let cache_name = env::var("__CACHE_NAME").unwrap_or(NOT_FOUND.to_string());
     let function_name = env::var("__FUNCTION_NAME").unwrap_or(NOT_FOUND.to_string());
     let invocation_id = env::var("__INVOCATION_ID").unwrap_or(NOT_FOUND.to_string());
+    let function_version = env::var("__FUNCTION_VERSION").ok();
+    let region = env::var("__REGION").ok();
     FunctionEnvironment {
         cache_name,
         function_name,
         invocation_id,
+        function_version,
+        region,
     }
 });
 static GET_HEADERS_ONCE: LazyLock<HashMap<String, String>> = LazyLock::new(|| {
@@ -38,6 +43,28 @@ static GET_TOKEN_METADATA_ONCE: LazyLock<Option<String>> =
 static GET_HTTP_METHOD_ONCE: LazyLock<String> = LazyLock::new(web_function_support::http_method);
 static GET_HTTP_PATH_ONCE: LazyLock<String> =
     LazyLock::new(|| web_function_support::invocation_path().unwrap_or_default());
+static GET_CONNECTION_INFO_ONCE: LazyLock<ConnectionInfo> = LazyLock::new(|| {
+    let web_function_support::ConnectionInfo {
+        source_ip,
+        source_port,
+        tls,
+    } = web_function_support::connection();
+    ConnectionInfo {
+        source_ip: source_ip.and_then(|ip| ip.parse().ok()),
+        source_port,
+        tls: tls.map(
+            |web_function_support::TlsInfo {
+                 protocol_version,
+                 cipher_suite,
+                 server_name,
+             }| TlsInfo {
+                protocol_version,
+                cipher_suite,
+                server_name,
+            },
+        ),
+    }
+});
 
 /// Data structure containing easy-to-access information regarding the current invocation's
 /// environment. Momento will populate this information as necessary, either through provided
@@ -50,6 +77,7 @@ static GET_HTTP_PATH_ONCE: LazyLock<String> =
 /// // Examples
 /// log::info!("Cache: {}", function_environment.cache_name());
 /// log::info!("Invocation ID: {}", function_environment.invocation_id());
+/// log::info!("Function: {} version {:?}", function_environment.function_name(), function_environment.function_version());
 ///
 /// let joined_query_parameters = function_environment.query_parameters()
 ///     .iter()
@@ -62,6 +90,8 @@ pub struct FunctionEnvironment {
     cache_name: String,
     function_name: String,
     invocation_id: String,
+    function_version: Option<String>,
+    region: Option<String>,
 }
 
 impl FunctionEnvironment {
@@ -88,6 +118,26 @@ impl FunctionEnvironment {
         &self.function_name
     }
 
+    /// The deployed revision of the function, if the host provides it. You can also access this via:
+    /// ```rust,no_run
+    /// let function_version = std::env::var("__FUNCTION_VERSION").ok();
+    /// ```
+    ///
+    /// Each deployment of a function gets a new version, so you can use this to tell which
+    /// deployment produced a log line or to key configuration by release.
+    pub fn function_version(&self) -> Option<&str> {
+        self.function_version.as_deref()
+    }
+
+    /// The Momento region the function is running in, like `us-west-2`, if the host provides it.
+    /// You can also access this via:
+    /// ```rust,no_run
+    /// let region = std::env::var("__REGION").ok();
+    /// ```
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
     /// The ID of the currently executing invocation. You can also access this via:
     /// ```rust,no_run
     /// let invocation_id = std::env::var("__INVOCATION_ID").unwrap_or_default();
@@ -137,6 +187,105 @@ impl FunctionEnvironment {
     pub fn http_path(&self) -> &str {
         &GET_HTTP_PATH_ONCE
     }
+
+    /// Details about the connection the request arrived on, like the caller's IP address,
+    /// when the host provides them. You can also access this via:
+    /// ```rust,no_run
+    /// use momento_functions_host::web_extensions::connection_info;
+    /// let source_ip = connection_info().ok().and_then(|info| info.source_ip());
+    /// ```
+    pub fn connection_info(&self) -> Result<&ConnectionInfo, UnsupportedByHost> {
+        connection_info()
+    }
+
+    /// The caller's `User-Agent` header, if present. You can also access this via:
+    /// ```rust,no_run
+    /// use momento_functions_host::web_extensions::user_agent;
+    /// let user_agent = user_agent();
+    /// ```
+    pub fn user_agent(&self) -> Option<&str> {
+        user_agent()
+    }
+}
+
+/// Details about the connection a web request arrived on.
+///
+/// Use these for IP allow-lists, geolocation, or abuse detection. The source address
+/// is the one Momento saw, so it cannot be spoofed with headers like `X-Forwarded-For`.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    source_ip: Option<IpAddr>,
+    source_port: Option<u16>,
+    tls: Option<TlsInfo>,
+}
+
+impl ConnectionInfo {
+    /// The caller's IP address.
+    pub fn source_ip(&self) -> Option<IpAddr> {
+        self.source_ip
+    }
+
+    /// The caller's port.
+    pub fn source_port(&self) -> Option<u16> {
+        self.source_port
+    }
+
+    /// TLS details, when the caller connected with TLS.
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.tls.as_ref()
+    }
+}
+
+/// TLS details of a web request's connection.
+#[derive(Debug, Clone)]
+pub struct TlsInfo {
+    protocol_version: String,
+    cipher_suite: String,
+    server_name: Option<String>,
+}
+
+impl TlsInfo {
+    /// The negotiated protocol version, like `TLSv1.3`.
+    pub fn protocol_version(&self) -> &str {
+        &self.protocol_version
+    }
+
+    /// The negotiated cipher suite, like `TLS_AES_128_GCM_SHA256`.
+    pub fn cipher_suite(&self) -> &str {
+        &self.cipher_suite
+    }
+
+    /// The server name the caller asked for with SNI, if any.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+}
+
+/// Returns details about the connection the web request arrived on.
+///
+/// Fails on hosts that are too old to report the connection, without calling the host.
+///
+/// ```rust,no_run
+/// use std::net::{IpAddr, Ipv4Addr};
+/// use momento_functions_host::web_extensions::connection_info;
+///
+/// let allowed = [IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7))];
+/// match connection_info().map(|info| info.source_ip()) {
+///     Ok(Some(ip)) if allowed.contains(&ip) => { /* handle the request */ }
+///     _ => { /* respond with 403 */ }
+/// }
+/// ```
+pub fn connection_info() -> Result<&'static ConnectionInfo, UnsupportedByHost> {
+    abi::require(HostCapability::WebConnectionInfo)?;
+    Ok(&GET_CONNECTION_INFO_ONCE)
+}
+
+/// Returns the caller's `User-Agent` header, if present.
+pub fn user_agent() -> Option<&'static str> {
+    headers()
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+        .map(|(_, value)| value.as_str())
 }
 
 /// Returns the headers for the web function, if any are present.
@@ -1,35 +1,109 @@
 use std::fmt::Write;
+use std::sync::Mutex;
 
 use log::{Log, set_logger_racy, set_max_level};
 use momento_functions_host::logging::{LogConfiguration, LogConfigurationError};
+use momento_functions_host::web_extensions::FunctionEnvironment;
 use time::format_description::well_known::Rfc3339;
 
-pub struct HostLog {}
+use crate::sampling::Sampling;
+
+/// How each log record is written.
+///
+/// Every format says where each record was logged from, unless that is turned off with
+/// [set_source_location](crate::set_source_location). [LogFormat::TextWithContext] and
+/// [LogFormat::Json] also include the invocation id, function name, and cache name, so you can
+/// correlate records from the same invocation without putting them in every message, and the
+/// record's `target` when it was set to something other than the module path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// A line of text, like `INFO 2025-01-01T00:00:00Z my_function src/lib.rs:10 hello`
+    #[default]
+    Text,
+    /// A line of text with the invocation's context, like
+    /// `INFO 2025-01-01T00:00:00Z my_function src/lib.rs:10 cache_name=c function_name=f invocation_id=i hello`
+    TextWithContext,
+    /// A JSON object, with the message and context as fields.
+    Json,
+}
+
+pub struct HostLog;
+
+struct LoggerState {
+    installed: bool,
+    format: LogFormat,
+    source_location: bool,
+    sampling: Sampling,
+    sampled: u64,
+    configurations: Vec<LogConfiguration>,
+}
+
+static LOGGER: HostLog = HostLog;
+static STATE: Mutex<LoggerState> = Mutex::new(LoggerState {
+    installed: false,
+    format: LogFormat::Text,
+    source_location: true,
+    sampling: Sampling::all(),
+    sampled: 0,
+    configurations: Vec::new(),
+});
 
 impl HostLog {
+    /// Configure the host's destinations, installing the logger the first time.
+    ///
+    /// Calling this again replaces the destinations and format.
     pub fn init(
         configurations: impl IntoIterator<Item = LogConfiguration>,
+        format: LogFormat,
     ) -> Result<(), LogConfigurationError> {
-        static mut LOGGER: Option<HostLog> = None;
+        let configurations: Vec<LogConfiguration> = configurations.into_iter().collect();
+        let mut state = lock_state();
+        momento_functions_host::logging::configure_host_logging(configurations.iter().cloned())?;
+        state.configurations = configurations;
+        state.format = format;
+        if state.installed {
+            return Ok(());
+        }
         // We're setting this to DEBUG so all logs are captured and sent to the host serving
         // the function. The host will determine whether to log the mesage.
         set_max_level(log::LevelFilter::Debug);
-        momento_functions_host::logging::configure_host_logging(configurations)?;
-        #[allow(static_mut_refs)]
-        #[allow(clippy::expect_used)]
         // SAFETY: concurrency requirement is satisfied by the single threaded nature
         // of the Function environment.
-        unsafe {
-            LOGGER.replace(HostLog {});
-            set_logger_racy(LOGGER.as_mut().expect("logger just set")).map_err(|e| {
-                LogConfigurationError::Unknown {
-                    message: format!("Failed to configure logger! {e:?}"),
-                }
-            })
-        }
+        unsafe { set_logger_racy(&LOGGER) }.map_err(|e| LogConfigurationError::Unknown {
+            message: format!("Failed to configure logger! {e:?}"),
+        })?;
+        state.installed = true;
+        Ok(())
+    }
+
+    /// Add a destination alongside the ones already configured.
+    pub fn add_destination(configuration: LogConfiguration) -> Result<(), LogConfigurationError> {
+        let (mut configurations, format) = {
+            let state = lock_state();
+            (state.configurations.clone(), state.format)
+        };
+        configurations.push(configuration);
+        Self::init(configurations, format)
     }
 }
 
+pub fn set_source_location(include: bool) {
+    lock_state().source_location = include;
+}
+
+pub fn set_sampling(sampling: Sampling) {
+    let mut state = lock_state();
+    state.sampling = sampling;
+    state.sampled = 0;
+}
+
+fn lock_state() -> std::sync::MutexGuard<'static, LoggerState> {
+    // A panic while holding the lock leaves the state intact, so keep using it.
+    STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 impl Log for HostLog {
     fn enabled(&self, _metadata: &log::Metadata) -> bool {
         // Host logging will filter out logs based on level
@@ -37,23 +111,164 @@ impl Log for HostLog {
     }
 
     fn log(&self, record: &log::Record) {
-        let mut buffer = String::with_capacity(128);
+        let (format, source_location) = {
+            let mut state = lock_state();
+            let LoggerState {
+                sampling, sampled, ..
+            } = &mut *state;
+            if !sampling.keep(record.level(), sampled) {
+                return;
+            }
+            (state.format, state.source_location)
+        };
         let utc_now = time::OffsetDateTime::now_utc();
         let timestamp = utc_now.format(&Rfc3339).unwrap_or("<unknown>".to_string());
-        let record_level = record.level();
-        let level = record_level.as_str();
-        let module = record.module_path().unwrap_or("<unknown>");
-        let file = record.file().unwrap_or("<unknown>");
-        let line = record.line().unwrap_or(0);
-        let log_message = record.args();
-
-        let _ = write!(
-            &mut buffer,
-            "{level} {timestamp} {module} {file}:{line} {log_message}"
-        );
 
-        momento_functions_host::logging::log(buffer.as_str(), record_level);
+        let environment = FunctionEnvironment::get_function_environment();
+        let context = Context {
+            timestamp: &timestamp,
+            cache_name: environment.cache_name(),
+            function_name: environment.function_name(),
+            function_version: environment.function_version(),
+            // The environment is captured once per instance, but the invocation id changes
+            // with every invocation, so read it fresh.
+            invocation_id: &std::env::var("__INVOCATION_ID").unwrap_or_default(),
+        };
+        let buffer = format_record(record, format, source_location, &context);
+
+        momento_functions_host::logging::log(buffer.as_str(), record.level());
     }
 
     fn flush(&self) {}
 }
+
+/// What a record is written with besides the record itself.
+struct Context<'a> {
+    timestamp: &'a str,
+    cache_name: &'a str,
+    function_name: &'a str,
+    function_version: Option<&'a str>,
+    invocation_id: &'a str,
+}
+
+fn format_record(
+    record: &log::Record,
+    format: LogFormat,
+    source_location: bool,
+    context: &Context,
+) -> String {
+    let Context {
+        timestamp,
+        cache_name,
+        function_name,
+        function_version,
+        invocation_id,
+    } = context;
+    let level = record.level().as_str();
+    let module = record.module_path().unwrap_or("<unknown>");
+    let file = record.file().unwrap_or("<unknown>");
+    let line = record.line().unwrap_or(0);
+    // `log` sets the target to the module path unless the call names one, like
+    // `log::info!(target: "billing", ...)`.
+    let target = Some(record.target()).filter(|target| Some(*target) != record.module_path());
+    let log_message = record.args();
+
+    match format {
+        LogFormat::Text | LogFormat::TextWithContext => {
+            let mut buffer = String::with_capacity(128);
+            let _ = write!(&mut buffer, "{level} {timestamp}");
+            if source_location {
+                let _ = write!(&mut buffer, " {module} {file}:{line}");
+            }
+            if format == LogFormat::TextWithContext {
+                let _ = write!(
+                    &mut buffer,
+                    " cache_name={cache_name} function_name={function_name} invocation_id={invocation_id}"
+                );
+                if let Some(target) = target {
+                    let _ = write!(&mut buffer, " target={target}");
+                }
+            }
+            let _ = write!(&mut buffer, " {log_message}");
+            buffer
+        }
+        LogFormat::Json => {
+            let mut fields = serde_json::json!({
+                "level": level,
+                "timestamp": timestamp,
+                "cache_name": cache_name,
+                "function_name": function_name,
+                "function_version": function_version,
+                "invocation_id": invocation_id,
+                "message": log_message.to_string(),
+            });
+            if source_location {
+                fields["module"] = module.into();
+                fields["file"] = file.into();
+                fields["line"] = line.into();
+            }
+            if let Some(target) = target {
+                fields["target"] = target.into();
+            }
+            fields.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context<'static> {
+        Context {
+            timestamp: "2025-01-01T00:00:00Z",
+            cache_name: "c",
+            function_name: "f",
+            function_version: None,
+            invocation_id: "i",
+        }
+    }
+
+    fn format(target: &str, format: LogFormat, source_location: bool) -> String {
+        format_record(
+            &log::Record::builder()
+                .args(format_args!("hello"))
+                .level(log::Level::Info)
+                .target(target)
+                .module_path(Some("my_function::orders"))
+                .file(Some("src/orders.rs"))
+                .line(Some(10))
+                .build(),
+            format,
+            source_location,
+            &context(),
+        )
+    }
+
+    #[test]
+    fn records_say_where_they_were_logged() {
+        assert_eq!(
+            "INFO 2025-01-01T00:00:00Z my_function::orders src/orders.rs:10 hello",
+            format("billing", LogFormat::Text, true)
+        );
+        assert_eq!(
+            "INFO 2025-01-01T00:00:00Z my_function::orders src/orders.rs:10 cache_name=c function_name=f invocation_id=i hello",
+            format("my_function::orders", LogFormat::TextWithContext, true)
+        );
+        assert_eq!(
+            "INFO 2025-01-01T00:00:00Z cache_name=c function_name=f invocation_id=i target=billing hello",
+            format("billing", LogFormat::TextWithContext, false)
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format("billing", LogFormat::Json, true)).unwrap();
+        assert_eq!(
+            serde_json::json!(["my_function::orders", "src/orders.rs", 10, "billing"]),
+            serde_json::json!([json["module"], json["file"], json["line"], json["target"]])
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&format("my_function::orders", LogFormat::Json, false)).unwrap();
+        assert!(json.get("module").is_none() && json.get("target").is_none());
+    }
+}
@@ -15,10 +15,87 @@ use momento_functions_host::logging::{LogConfiguration, LogConfigurationError};
 
 use crate::host_logging::HostLog;
 mod host_logging;
+mod sampling;
+
+pub use host_logging::LogFormat;
+pub use sampling::{Sampling, log_once_per};
 
 /// Entrypoint for configuring logs to be delivered to a destination(s)
+///
+/// Records are written as [LogFormat::Text]. To include the invocation id, function name, and
+/// cache name in each record, use [configure_logs_with_format].
+///
+/// You can call this again, even mid-invocation, to replace the destinations. To keep the
+/// current destinations and add another, use [add_destination].
 pub fn configure_logs(
     configurations: impl IntoIterator<Item = LogConfiguration>,
 ) -> Result<(), LogConfigurationError> {
-    HostLog::init(configurations)
+    HostLog::init(configurations, LogFormat::default())
+}
+
+/// Configure logs to be delivered to a destination(s), written in the given format.
+///
+/// ```rust,no_run
+/// use momento_functions_host::logging::LogDestination;
+/// use momento_functions_log::LogFormat;
+///
+/// if let Err(e) = momento_functions_log::configure_logs_with_format(
+///     [LogDestination::topic("logs").into()],
+///     LogFormat::Json,
+/// ) {
+///     eprintln!("failed to configure logs: {e}");
+/// }
+/// ```
+pub fn configure_logs_with_format(
+    configurations: impl IntoIterator<Item = LogConfiguration>,
+    format: LogFormat,
+) -> Result<(), LogConfigurationError> {
+    HostLog::init(configurations, format)
+}
+
+/// Send logs to another destination, in addition to the ones already configured.
+///
+/// Each destination keeps its own levels, so you can, for example, send debug logs to a
+/// topic only for requests that ask for it. If logs have not been configured yet, this
+/// configures them with just this destination.
+///
+/// ```rust,no_run
+/// use momento_functions_host::logging::{LogConfiguration, LogDestination};
+/// use momento_functions_host::web_extensions::headers;
+///
+/// if headers().contains_key("x-debug-logs") {
+///     let debug_topic = LogConfiguration::new(LogDestination::topic("debug-logs"))
+///         .with_log_level(log::LevelFilter::Debug);
+///     if let Err(e) = momento_functions_log::add_destination(debug_topic) {
+///         log::warn!("failed to add the debug log destination: {e}");
+///     }
+/// }
+/// ```
+pub fn add_destination(configuration: LogConfiguration) -> Result<(), LogConfigurationError> {
+    HostLog::add_destination(configuration)
+}
+
+/// Choose whether records say where they were logged from: the module path, file, and line.
+///
+/// This is on by default. Turn it off to keep records short when the message is enough.
+///
+/// ```rust,no_run
+/// momento_functions_log::set_source_location(false);
+/// ```
+pub fn set_source_location(include: bool) {
+    host_logging::set_source_location(include)
+}
+
+/// Only send a sample of your Function's log records.
+///
+/// This applies to every destination, and lasts until it is set again.
+///
+/// ```rust,no_run
+/// use momento_functions_log::Sampling;
+///
+/// // Send 1 in 100 info and debug records, and every warning and error.
+/// momento_functions_log::set_sampling(Sampling::one_in(100));
+/// ```
+pub fn set_sampling(sampling: Sampling) {
+    host_logging::set_sampling(sampling)
 }
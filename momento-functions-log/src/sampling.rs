@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many of your Function's log records to send.
+///
+/// High-traffic Functions can produce more logs than a topic or CloudWatch log group is
+/// worth paying for. Sampling sends only one in every `n` records, while records at or
+/// above a severity (warnings, by default) are always sent.
+///
+/// Set it with [set_sampling](crate::set_sampling).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampling {
+    one_in: u64,
+    always_keep: log::Level,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl Sampling {
+    /// Send every record. This is the default.
+    pub const fn all() -> Self {
+        Self {
+            one_in: 1,
+            always_keep: log::Level::Warn,
+        }
+    }
+
+    /// Send one in every `n` records below the always-kept level.
+    pub const fn one_in(n: u64) -> Self {
+        Self {
+            one_in: if n == 0 { 1 } else { n },
+            always_keep: log::Level::Warn,
+        }
+    }
+
+    /// Always send records at this level or more severe. Defaults to [log::Level::Warn].
+    pub const fn always_keep(mut self, level: log::Level) -> Self {
+        self.always_keep = level;
+        self
+    }
+
+    /// Decide whether to send a record, advancing `counter` for sampled records.
+    pub(crate) fn keep(&self, level: log::Level, counter: &mut u64) -> bool {
+        if level <= self.always_keep {
+            return true;
+        }
+        let keep = counter.is_multiple_of(self.one_in);
+        *counter = counter.wrapping_add(1);
+        keep
+    }
+}
+
+/// Keys tracked by [log_once_per] before expired ones are cleared out.
+const MAX_TRACKED_KEYS: usize = 1024;
+
+static LAST_LOGGED: Mutex<Option<HashMap<String, (Instant, Duration)>>> = Mutex::new(None);
+
+/// Rate-limit a log statement: returns true at most once per `period` for each `key`.
+///
+/// Use this to keep a repeated warning, like a throttled dependency, from flooding your
+/// destinations. Keys are remembered for as long as the Function instance lives, so
+/// the limit holds across invocations.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// if momento_functions_log::log_once_per(Duration::from_secs(60), "ddb-throttled") {
+///     log::warn!("DynamoDB is throttling requests");
+/// }
+/// ```
+pub fn log_once_per(period: Duration, key: impl AsRef<str>) -> bool {
+    let now = Instant::now();
+    let mut last_logged = LAST_LOGGED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let last_logged = last_logged.get_or_insert_with(HashMap::new);
+    if let Some((at, _)) = last_logged.get(key.as_ref())
+        && now.duration_since(*at) < period
+    {
+        return false;
+    }
+    if MAX_TRACKED_KEYS <= last_logged.len() {
+        last_logged.retain(|_, (at, period)| now.duration_since(*at) < *period);
+    }
+    last_logged.insert(key.as_ref().to_string(), (now, period));
+    true
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling_keeps_one_in_n_and_all_warnings() {
+        let sampling = Sampling::one_in(3);
+        let mut counter = 0;
+        let kept: Vec<bool> = (0..6)
+            .map(|_| sampling.keep(log::Level::Info, &mut counter))
+            .collect();
+        assert_eq!(vec![true, false, false, true, false, false], kept);
+        assert!(sampling.keep(log::Level::Warn, &mut counter));
+        assert!(sampling.keep(log::Level::Error, &mut counter));
+    }
+
+    #[test]
+    fn log_once_per_limits_each_key() {
+        let period = Duration::from_secs(60);
+        assert!(log_once_per(period, "a"));
+        assert!(!log_once_per(period, "a"));
+        assert!(log_once_per(period, "b"));
+        assert!(log_once_per(Duration::ZERO, "c"));
+        assert!(log_once_per(Duration::ZERO, "c"));
+    }
+}
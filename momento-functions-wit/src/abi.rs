@@ -0,0 +1,267 @@
+//! Negotiating which host interfaces are available.
+//!
+//! The `momento:host` package stays at version 1.0.0 while interfaces and functions are added
+//! to it, because a component only imports the host functions it calls: a Function that calls
+//! nothing new instantiates on every 1.x host, and bumping the package version would stop it
+//! from instantiating on hosts that only provide 1.0.0.
+//!
+//! Instead, the host advertises the level of the package it provides, like `1.1.0`, and each
+//! [HostCapability] records the level that added it. The wrappers in `momento-functions-host`
+//! check [host_supports] before calling anything newer than the 1.0.0 level, and return an
+//! error instead of trapping.
+
+use std::fmt::{self, Display};
+
+/// The environment variable the host uses to advertise the `momento:host` level it provides.
+pub const HOST_ABI_VERSION_VARIABLE: &str = "__HOST_ABI_VERSION";
+
+/// A `major.minor.patch` WIT package version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AbiVersion {
+    /// Changes when something is removed or changed incompatibly.
+    pub major: u32,
+    /// Changes when something is added.
+    pub minor: u32,
+    /// Changes for fixes that do not change the interface.
+    pub patch: u32,
+}
+
+impl AbiVersion {
+    /// Parse a version like `1.2.0`. A missing minor or patch number is read as 0.
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+        let patch = parts.next().map_or(Some(0), |patch| patch.parse().ok())?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    /// Whether a host providing this version has everything added up to `required`.
+    pub fn provides(&self, required: AbiVersion) -> bool {
+        self.major == required.major && required.minor <= self.minor
+    }
+}
+
+impl Display for AbiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The `momento:host` level this crate's bindings were generated from: the level of its newest
+/// capability.
+pub fn built_abi() -> AbiVersion {
+    HostCapability::ALL
+        .iter()
+        .map(HostCapability::since)
+        .max()
+        .unwrap_or(AbiVersion {
+            major: 1,
+            minor: 0,
+            patch: 0,
+        })
+}
+
+/// The `momento:host` level the host advertises, if it advertises one.
+pub fn host_abi() -> Option<AbiVersion> {
+    std::env::var(HOST_ABI_VERSION_VARIABLE)
+        .ok()
+        .and_then(|version| AbiVersion::parse(&version))
+}
+
+/// Declares [HostCapability], with the level that added each capability and how it is
+/// described in errors. Adding a capability is one line here.
+macro_rules! host_capabilities {
+    (
+        $(
+            $(#[doc = $doc:literal])*
+            $capability:ident => $description:literal since ($major:literal, $minor:literal),
+        )*
+    ) => {
+        /// Host functions added after the `momento:host@1.0.0` level.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum HostCapability {
+            $(
+                $(#[doc = $doc])*
+                $capability,
+            )*
+        }
+
+        impl HostCapability {
+            /// Every capability, in the order they were added.
+            pub const ALL: &[HostCapability] = &[$(HostCapability::$capability),*];
+
+            /// The first `momento:host` level with this capability.
+            pub fn since(&self) -> AbiVersion {
+                match self {
+                    $(
+                        HostCapability::$capability => AbiVersion {
+                            major: $major,
+                            minor: $minor,
+                            patch: 0,
+                        },
+                    )*
+                }
+            }
+        }
+
+        impl Display for HostCapability {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(match self {
+                    $(HostCapability::$capability => $description,)*
+                })
+            }
+        }
+    };
+}
+
+host_capabilities! {
+    /// `redis.cluster-client.pipe`
+    RedisClusterPipe => "Redis cluster pipelines" since (1, 1),
+    /// The `aws-ddb-streams` interface.
+    DynamoDbStreams => "DynamoDB Streams" since (1, 1),
+    /// `aws-lambda.client.invoke-with-response-stream`
+    LambdaResponseStreaming => "Lambda response streaming" since (1, 1),
+    /// `aws-s3.client.put-encrypted`
+    S3ServerSideEncryption => "S3 server-side encryption" since (1, 1),
+    /// `aws-s3.client.copy`
+    S3Copy => "S3 copy" since (1, 1),
+    /// The `diagnostics` interface.
+    EndpointDiagnostics => "endpoint diagnostics" since (1, 1),
+    /// The `signing` interface.
+    Ed25519Signing => "Ed25519 signing" since (1, 1),
+    /// `signing.jwk-verify`
+    JwkVerification => "JSON Web Key verification" since (1, 1),
+    /// The `control` interface.
+    ControlPlane => "control-plane operations" since (1, 1),
+    /// The `aws-rds-data` interface.
+    RdsData => "RDS Data API" since (1, 1),
+    /// The `sql` interface.
+    SqlClient => "Postgres and MySQL clients" since (1, 1),
+    /// The `kafka` interface.
+    KafkaProducer => "Kafka producers" since (1, 1),
+    /// The `config-store` interface.
+    ConfigStore => "configuration documents" since (1, 1),
+    /// `aws-ddb.client.execute-statement` and `aws-ddb.client.batch-execute-statement`
+    DynamoDbPartiQl => "DynamoDB PartiQL statements" since (1, 1),
+    /// `aws-s3.client.select-object-content`
+    S3Select => "S3 Select" since (1, 1),
+    /// `topic.publish-sequenced` and `topic.publish-bytes-sequenced`
+    TopicSequenceNumbers => "topic sequence numbers" since (1, 1),
+    /// `topic.subscribe`
+    TopicSubscriptions => "topic subscriptions" since (1, 1),
+    /// `cache-scalar.get-item-metadata`
+    CacheItemMetadata => "cache item metadata" since (1, 1),
+    /// `http.start` and `http.wait-any`
+    ConcurrentHttp => "concurrent HTTP requests" since (1, 1),
+    /// `web-function-support.connection`
+    WebConnectionInfo => "web request connection details" since (1, 1),
+}
+
+/// The host does not provide a capability this Function called.
+#[derive(Debug, Clone)]
+pub struct UnsupportedByHost {
+    /// The capability that was called.
+    pub capability: HostCapability,
+    /// The version the host provides.
+    pub host: AbiVersion,
+}
+
+impl Display for UnsupportedByHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} needs momento:host@{}, but the host provides momento:host@{}",
+            self.capability,
+            self.capability.since(),
+            self.host
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedByHost {}
+
+/// Whether the host provides `capability`.
+///
+/// A host that does not advertise its version is assumed to provide everything this
+/// Function was built against.
+pub fn host_supports(capability: HostCapability) -> bool {
+    require(capability).is_ok()
+}
+
+/// Check that the host provides `capability` before calling it.
+pub fn require(capability: HostCapability) -> Result<(), UnsupportedByHost> {
+    check(host_abi(), capability)
+}
+
+/// Every capability that a host providing `host` lacks.
+pub fn unsupported_capabilities(host: AbiVersion) -> Vec<HostCapability> {
+    HostCapability::ALL
+        .iter()
+        .copied()
+        .filter(|capability| check(Some(host), *capability).is_err())
+        .collect()
+}
+
+fn check(host: Option<AbiVersion>, capability: HostCapability) -> Result<(), UnsupportedByHost> {
+    match host {
+        Some(host) if !host.provides(capability.since()) => {
+            Err(UnsupportedByHost { capability, host })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> AbiVersion {
+        AbiVersion::parse(version).expect("valid version")
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(
+            AbiVersion {
+                major: 1,
+                minor: 2,
+                patch: 3
+            },
+            version("1.2.3")
+        );
+        assert_eq!(version("2.0.0"), version("2"));
+        assert_eq!(None, AbiVersion::parse("1.x"));
+        assert_eq!(None, AbiVersion::parse("1.2.3.4"));
+        assert_eq!("1.1.0", built_abi().to_string());
+    }
+
+    #[test]
+    fn older_minor_hosts_lack_newer_capabilities() {
+        let capability = HostCapability::S3Copy;
+        assert!(check(None, capability).is_ok());
+        assert!(check(Some(version("1.1.0")), capability).is_ok());
+        assert!(check(Some(version("1.4.2")), capability).is_ok());
+        assert!(check(Some(version("2.1.0")), capability).is_err());
+
+        let error = check(Some(version("1.0.5")), capability).expect_err("too old");
+        assert_eq!(
+            "S3 copy needs momento:host@1.1.0, but the host provides momento:host@1.0.5",
+            error.to_string()
+        );
+        assert_eq!(
+            HostCapability::ALL.len(),
+            unsupported_capabilities(version("1.0.0")).len()
+        );
+        assert!(unsupported_capabilities(version("1.1.0")).is_empty());
+    }
+}
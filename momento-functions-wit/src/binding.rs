@@ -0,0 +1,32 @@
+//! Guest-facing types over the generated bindings.
+//!
+//! The generated binding types follow the WIT exactly, so they change whenever it does, and a
+//! Function that names them breaks when a record gains a field. Crates that wrap the host
+//! interfaces expose their own types instead, and convert to and from the generated ones
+//! through these traits. When a minor level adds a function taking a wider record, the
+//! guest-facing type stays as it is and gains a conversion into the new record, filling the
+//! new fields with their defaults.
+
+/// A guest-facing type read from a generated binding type.
+pub trait FromBinding<B>: Sized {
+    /// Convert what the host returned.
+    fn from_binding(binding: B) -> Self;
+}
+
+/// A guest-facing type passed to the host as a generated binding type.
+pub trait IntoBinding<B> {
+    /// Convert what is passed to the host.
+    fn into_binding(self) -> B;
+}
+
+impl<B, T: FromBinding<B>> FromBinding<Option<B>> for Option<T> {
+    fn from_binding(binding: Option<B>) -> Self {
+        binding.map(T::from_binding)
+    }
+}
+
+impl<B, T: IntoBinding<B>> IntoBinding<Option<B>> for Option<T> {
+    fn into_binding(self) -> Option<B> {
+        self.map(T::into_binding)
+    }
+}
@@ -0,0 +1,8 @@
+wit_bindgen::generate!({
+    world: "momento:functions/cache-event-function",
+    path: ["wit/host/", "wit/"],
+    generate_all,
+    default_bindings_module: "momento_functions_wit::function_cache_event",
+    export_macro_name: "export_cache_event_function",
+    pub_export_macro: true,
+});
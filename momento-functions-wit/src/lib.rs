@@ -12,6 +12,32 @@
 //! * [`momento-functions-host`](https://crates.io/crates/momento-functions-host): Interfaces and tools for calling host interfaces.
 //! * [`momento-functions-log`](https://crates.io/crates/momento-functions-log): Standard `log` adapter.
 
+/// The version of the `momento:functions` WIT package these bindings were generated from.
+pub const FUNCTIONS_WIT_VERSION: &str = "1.0.0";
+/// The version of the `momento:host` WIT package these bindings were generated from.
+pub const HOST_WIT_VERSION: &str = "1.0.0";
+/// The version of this crate.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub mod abi;
+pub mod binding;
+pub mod function_cache_event;
 pub mod function_spawn;
 pub mod function_web;
 pub mod host;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wit_versions_match_packages() {
+        assert!(include_str!("../wit/world.wit").starts_with(&format!(
+            "package momento:functions@{FUNCTIONS_WIT_VERSION};"
+        )));
+        assert!(
+            include_str!("../wit/host/world.wit")
+                .starts_with(&format!("package momento:host@{HOST_WIT_VERSION};"))
+        );
+    }
+}
@@ -45,11 +45,12 @@
 //! ```
 
 use itertools::Itertools;
-use momento_functions::{WebError, WebResponse, WebResult};
+use momento_functions::{MultiStatus, WebError, WebResponse, WebResult};
 use momento_functions_host::{
     encoding::Json, logging::LogDestination, web_extensions::FunctionEnvironment,
 };
 
+use momento_functions_text::truncate_tokens;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tiktoken_rs::{CoreBPE, cl100k_base_singleton};
@@ -151,32 +152,55 @@ fn index_documents(Json(documents): Json<Vec<DocumentInput>>) -> WebResult<WebRe
 
     // When embedding lots of text (like we are doing here), we should split this up into a small chunk size
     // so we remain within OpenAI's limits. 100 is a sweet spot between throughput and speed.
+    // A chunk that fails is reported as failed, document by document, without failing the others.
+    let mut results = MultiStatus::new();
     let chunks = documents.into_iter().chunks(100);
     for chunk in chunks.into_iter() {
         let chunk: Vec<DocumentInput> = chunk.collect();
-        let page_contents = chunk
-            .iter()
-            .map(|document| document.page_content.clone())
-            .collect();
-        // Queries OpenAI to generate an embedding for these documents so we can ship them off to Turbopuffer
-        let embedding_data = get_embeddings(page_contents, openai_api_key.clone(), bpe)?;
-
-        let mut turbopuffer_inputs = Vec::new();
-        // The response from OpenAI is sorted by index, so we can safely zip together the responses
-        // to reconstruct the embeddings for our documents
-        for (document, embedding) in chunk.into_iter().zip(embedding_data) {
-            turbopuffer_inputs.push(document.into_turbopuffer_document(embedding.embedding));
-        }
-
-        index_documents_in_turbopuffer(
-            turbopuffer_inputs,
+        let ids: Vec<String> = chunk.iter().map(|document| document.id.clone()).collect();
+        match index_chunk(
+            chunk,
+            &openai_api_key,
+            bpe,
             &turbopuffer_endpoint,
             &turbopuffer_api_key,
-        )?;
+        ) {
+            Ok(()) => results.succeeded_all(ids),
+            Err(e) => {
+                log::error!("Failed to index a chunk of {} documents: {e:?}", ids.len());
+                results.failed_all(ids, 502, "Failed to embed or index documents");
+            }
+        }
     }
-    Ok(WebResponse::new().with_status(200).with_body(json!({
-        "message": "Documents indexed successfully",
-    }))?)
+    Ok(results.into_web_response())
+}
+
+fn index_chunk(
+    chunk: Vec<DocumentInput>,
+    openai_api_key: &str,
+    bpe: &'static CoreBPE,
+    turbopuffer_endpoint: &str,
+    turbopuffer_api_key: &str,
+) -> WebResult<()> {
+    let page_contents = chunk
+        .iter()
+        .map(|document| document.page_content.clone())
+        .collect();
+    // Queries OpenAI to generate an embedding for these documents so we can ship them off to Turbopuffer
+    let embedding_data = get_embeddings(page_contents, openai_api_key.to_string(), bpe)?;
+
+    let mut turbopuffer_inputs = Vec::new();
+    // The response from OpenAI is sorted by index, so we can safely zip together the responses
+    // to reconstruct the embeddings for our documents
+    for (document, embedding) in chunk.into_iter().zip(embedding_data) {
+        turbopuffer_inputs.push(document.into_turbopuffer_document(embedding.embedding));
+    }
+
+    index_documents_in_turbopuffer(
+        turbopuffer_inputs,
+        turbopuffer_endpoint,
+        turbopuffer_api_key,
+    )
 }
 
 // ------------------------------------------------------
@@ -246,26 +270,16 @@ fn get_embeddings(
             continue;
         }
 
-        let mut tokens = tokenizer.encode_with_special_tokens(document);
-
-        let maybe_updated_document = if tokens.len() > MAX_TOKENS {
-            // log that we're truncating from input length to MAX_TOKENS
-            // OpenAI has a limit of MAX_TOKENS tokens per input
+        // OpenAI has a limit of MAX_TOKENS tokens per input
+        let truncated = truncate_tokens(tokenizer, document, MAX_TOKENS);
+        if truncated.len() < document.len() {
             log::debug!(
-                "token length was {}, truncating to {}",
-                tokens.len(),
-                MAX_TOKENS
+                "truncated document from {} to {} bytes to fit {MAX_TOKENS} tokens",
+                document.len(),
+                truncated.len()
             );
-            tokens.truncate(MAX_TOKENS);
-            tokenizer.decode(tokens).map_err(|e| {
-                WebError::message(format!(
-                    "Failed to convert truncated tokens back to string for OpenAI input: {e:?}"
-                ))
-            })?
-        } else {
-            document.to_string()
-        };
-        documents_for_embedding.push(maybe_updated_document);
+        }
+        documents_for_embedding.push(truncated.to_string());
     }
 
     let result = momento_functions_host::http::post(
@@ -52,8 +52,9 @@ use momento_functions_host::{
     cache, encoding::Json, logging::LogDestination, web_extensions::FunctionEnvironment,
 };
 
+use momento_functions_turbopuffer::Filter;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
+use serde_json::json;
 
 #[derive(Deserialize, Debug)]
 struct Request {
@@ -287,11 +288,7 @@ fn get_article_embeddings_from_turbopuffer(
         json!({
             "top_k": article_ids.len(),
             "include_attributes": vec!["id", "vector"],
-            "filters": Filter::Comparison(
-                ComparisonFilter("id".to_string(),
-                ComparisonOp::In,
-                Value::Array(article_ids.iter().map(|id| Value::String(id.clone())).collect()))
-            ),
+            "filters": Filter::field("id").is_in(article_ids),
         }),
     );
     match result {
@@ -363,11 +360,7 @@ fn get_similar_articles_from_turbopuffer(
             "rank_by": ["vector", "ANN", mean_vector],
             "top_k": topk,
             "include_attributes": vec!["metadata$title", "metadata$link"],
-            "filters": Filter::Comparison(
-                ComparisonFilter("id".to_string(),
-                ComparisonOp::NotIn,
-                Value::Array(already_seen_article_ids.iter().map(|id| Value::String(id.clone())).collect()))
-            ),
+            "filters": Filter::field("id").not_in(already_seen_article_ids),
         }),
     );
     match result {
@@ -449,62 +442,3 @@ fn setup_logging() -> WebResult<()> {
     ])?;
     Ok(())
 }
-
-// ---------------------------------------------------------------------------
-// | Utility structs when de/serializing filtering args for Turbopuffer
-// ---------------------------------------------------------------------------
-
-// Recursive struct without any validation, Turbopuffer will let you know
-// if the filter is invalid. Although these are largely uncessary, we've provded this
-// here for more filter building/allowing for filter overrides via request parameters
-// if you so choose.
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum Filter {
-    // Logical group: ["And", [filters...]] or ["Or", [filters...]]
-    Logical(LogicalFilter),
-
-    // Negation: ["Not", filter]
-    Not(NotFilter),
-
-    // Atomic comparison: ["field", "op", value]
-    Comparison(ComparisonFilter),
-}
-
-/// Represents a logical group like ["And", [...]] or ["Or", [...]]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LogicalFilter(
-    pub String,      // "And" or "Or"
-    pub Vec<Filter>, // nested filters
-);
-
-/// Represents a unary negation: ["Not", inner_filter]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NotFilter(
-    pub String,      // must be "Not"
-    pub Box<Filter>, // inner filter
-);
-
-/// Represents a basic comparison: ["field", "op", value]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ComparisonFilter(
-    pub String,       // field
-    pub ComparisonOp, // operator
-    pub Value,        // right-hand side (string, number, or array)
-);
-
-/// Supported comparison operators
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-pub enum ComparisonOp {
-    Eq,
-    Neq,
-    In,
-    NotIn,
-    Lt,
-    Lte,
-    Gt,
-    Gte,
-    Glob,
-    NotGlob,
-}
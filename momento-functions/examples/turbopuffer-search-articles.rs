@@ -102,11 +102,15 @@ use std::time::Duration;
 
 use momento_functions::{WebError, WebResponse, WebResult};
 use momento_functions_host::{
-    cache, encoding::Json, logging::LogDestination, web_extensions::FunctionEnvironment,
+    embeddings::{CachedEmbedder, Embedder, OpenAiEmbedder},
+    encoding::Json,
+    logging::LogDestination,
+    web_extensions::FunctionEnvironment,
 };
 
+use momento_functions_turbopuffer::Filter;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
+use serde_json::json;
 
 #[derive(Deserialize, Debug)]
 struct Request {
@@ -116,16 +120,6 @@ struct Request {
     filters: Option<Filter>,
 }
 
-#[derive(Deserialize, Debug)]
-struct EmbeddingResponse {
-    data: Vec<EmbeddingData>,
-}
-#[derive(Deserialize, Serialize, Debug)]
-struct EmbeddingData {
-    embedding: Vec<f32>,
-    index: usize,
-}
-
 #[derive(Deserialize, Debug)]
 struct QueryResponse {
     rows: Vec<QueryRow>,
@@ -261,85 +255,17 @@ fn search(Json(request): Json<Request>) -> WebResult<WebResponse> {
 // ------------------------------------------------------
 
 fn get_cached_query_embedding(query: String) -> WebResult<Vec<f32>> {
-    log::debug!("Checking if embeddings are already cached for \"{query}\"");
-    Ok(match cache::get::<Vec<u8>>(query.clone())? {
-        Some(hit) => {
-            log::debug!("cache hit");
-            // Convert raw bytes back into our Vec<f32> type
-            hit.chunks_exact(4)
-                .map(|chunk| {
-                    let arr = <[u8; 4]>::try_from(chunk)
-                        .map_err(|_| WebError::message("Chunk length should be 4"))?;
-                    Ok(f32::from_le_bytes(arr))
-                })
-                .collect::<Result<Vec<f32>, WebError>>()?
-        }
-        None => {
-            log::debug!("cache miss, querying embeddings from open ai");
-            match get_embeddings(query.clone())?.into_iter().next() {
-                Some(embedding) => {
-                    // Convert to raw bytes before storing in cache
-                    let new_query_embedding = embedding
-                        .embedding
-                        .clone()
-                        .into_iter()
-                        .flat_map(f32::to_le_bytes)
-                        .collect::<Vec<u8>>();
-                    let ttl: u64 = std::env::var("TTL_SECONDS")
-                        .unwrap_or(DEFAULT_TTL_SECONDS.to_string())
-                        .parse::<u64>()
-                        .unwrap_or(DEFAULT_TTL_SECONDS);
-                    cache::set(query, new_query_embedding.clone(), Duration::from_secs(ttl))?;
-                    embedding.embedding
-                }
-                None => {
-                    log::error!("Failed to get embedding for query: {query}");
-                    return Err(WebError::message("Failed to get embedding for query"));
-                }
-            }
-        }
-    })
-}
-
-fn get_embeddings(query: String) -> WebResult<Vec<EmbeddingData>> {
-    log::debug!("getting embeddings for document with content: {query:?}");
-    let query = if query.contains("\n") {
-        // openai guide currently says to replace newlines with spaces. This, then, must be how you get the cargo to come.
-        // https://platform.openai.com/docs/guides/embeddings
-        query.replace("\n", " ")
-    } else {
-        query
-    };
-
-    // Required to be set as an environment variable when creating the function
-    let openai_api_key = std::env::var("OPENAI_API_KEY").unwrap_or_default();
-    let result = momento_functions_host::http::post(
-        "https://api.openai.com/v1/embeddings",
-        [
-            (
-                "authorization".to_string(),
-                format!("Bearer {openai_api_key}"),
-            ),
-            ("content-type".to_string(), "application/json".to_string()),
-        ],
-        // 1536 float32 for text-embedding-3-small
-        serde_json::json!({
-            "model": "text-embedding-3-small",
-            "encoding_format": "float",
-            "input": [query],
-        })
-        .to_string(),
-    );
-    let mut response = result?;
-    log::debug!(
-        "OpenAI response: {} - {:?}",
-        response.status,
-        response.headers
-    );
-    let Json(EmbeddingResponse { mut data }) = response.extract()?;
-    data.sort_by_key(|d| d.index);
-    log::trace!("OpenAI extracted data: {data:?}");
-    Ok(data)
+    log::debug!("getting embeddings for \"{query}\"");
+    let ttl: u64 = std::env::var("TTL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TTL_SECONDS);
+    // Swap in BedrockTitanEmbedder or StubEmbedder here to use a different provider.
+    let embedder = CachedEmbedder::new(OpenAiEmbedder::from_env(), Duration::from_secs(ttl));
+    embedder
+        .embed(&[query])?
+        .pop()
+        .ok_or_else(|| WebError::message("Failed to get embedding for query"))
 }
 
 fn setup_logging() -> WebResult<()> {
@@ -349,60 +275,3 @@ fn setup_logging() -> WebResult<()> {
     ])?;
     Ok(())
 }
-
-// ---------------------------------------------------------------------------
-// | Utility structs when de/serializing filtering args for Turbopuffer
-// ---------------------------------------------------------------------------
-
-// Recursive struct without any validation, Turbopuffer will let you know
-// if the filter is invalid.
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum Filter {
-    // Logical group: ["And", [filters...]] or ["Or", [filters...]]
-    Logical(LogicalFilter),
-
-    // Negation: ["Not", filter]
-    Not(NotFilter),
-
-    // Atomic comparison: ["field", "op", value]
-    Comparison(ComparisonFilter),
-}
-
-/// Represents a logical group like ["And", [...]] or ["Or", [...]]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LogicalFilter(
-    pub String,      // "And" or "Or"
-    pub Vec<Filter>, // nested filters
-);
-
-/// Represents a unary negation: ["Not", inner_filter]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NotFilter(
-    pub String,      // must be "Not"
-    pub Box<Filter>, // inner filter
-);
-
-/// Represents a basic comparison: ["field", "op", value]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ComparisonFilter(
-    pub String,       // field
-    pub ComparisonOp, // operator
-    pub Value,        // right-hand side (string, number, or array)
-);
-
-/// Supported comparison operators
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-pub enum ComparisonOp {
-    Eq,
-    Neq,
-    In,
-    NotIn,
-    Lt,
-    Lte,
-    Gt,
-    Gte,
-    Glob,
-    NotGlob,
-}
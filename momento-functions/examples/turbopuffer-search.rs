@@ -2,7 +2,8 @@
 //! example performs a nearest-neighbor query through the documents in the Turbopuffer namespace.
 //!
 //! You need to provide `OPENAI_API_KEY`, `TURBOPUFFER_REGION`, `TURBOPUFFER_NAMESPACE` and `TURBOPUFFER_API_KEY`
-//! environment variables upon creating the function. If you'd like to store queries for longer,
+//! environment variables upon creating the function; requests fail with a 500 naming any that
+//! are missing. If you'd like to store queries for longer,
 //! pass along the `TTL` environment variable upon creating the function.
 //!
 //! Once uploaded, you can call with:
@@ -19,7 +20,11 @@ use std::time::Duration;
 
 use momento_functions::{WebError, WebResponse, WebResult};
 use momento_functions_host::{
-    cache, encoding::Json, logging::LogDestination, web_extensions::FunctionEnvironment,
+    api::{ApiAuth, ApiError},
+    cache, declare_api,
+    encoding::Json,
+    logging::LogDestination,
+    web_extensions::FunctionEnvironment,
 };
 
 use serde::{Deserialize, Serialize};
@@ -32,6 +37,13 @@ struct Request {
     include_attributes: Option<Vec<String>>,
 }
 
+#[derive(Serialize, Debug)]
+struct EmbeddingRequest {
+    model: &'static str,
+    encoding_format: &'static str,
+    input: Vec<String>,
+}
+
 #[derive(Deserialize, Debug)]
 struct EmbeddingResponse {
     data: Vec<EmbeddingData>,
@@ -42,6 +54,13 @@ struct EmbeddingData {
     index: usize,
 }
 
+#[derive(Serialize, Debug)]
+struct QueryRequest {
+    rank_by: serde_json::Value,
+    top_k: usize,
+    include_attributes: Vec<String>,
+}
+
 #[derive(Deserialize, Debug)]
 struct QueryResponse {
     rows: Vec<QueryRow>,
@@ -54,9 +73,28 @@ struct QueryRow {
     id: String,
 }
 
+declare_api! {
+    struct OpenAi;
+
+    fn embeddings() -> EmbeddingResponse = POST "/v1/embeddings" with EmbeddingRequest;
+}
+
+declare_api! {
+    struct Turbopuffer;
+
+    fn query(namespace: &str) -> QueryResponse = POST "/v2/namespaces/{namespace}/query" with QueryRequest;
+}
+
 // Default to 30 second caching time for queries in Momento
 const DEFAULT_TTL_SECONDS: u64 = 30;
 
+momento_functions::require_env!(
+    "OPENAI_API_KEY",
+    "TURBOPUFFER_REGION",
+    "TURBOPUFFER_NAMESPACE",
+    "TURBOPUFFER_API_KEY",
+);
+
 momento_functions::post!(search);
 fn search(Json(request): Json<Request>) -> WebResult<WebResponse> {
     setup_logging()?;
@@ -74,57 +112,35 @@ fn search(Json(request): Json<Request>) -> WebResult<WebResponse> {
     let include_attributes = include_attributes.unwrap_or_default();
 
     // These are passed in as environment variables when creating the function
-    let turbopuffer_api_key = format!(
-        "Bearer {}",
-        std::env::var("TURBOPUFFER_API_KEY").unwrap_or_default()
-    );
     let turbopuffer_region = std::env::var("TURBOPUFFER_REGION").unwrap_or_default();
     let turbopuffer_namespace = std::env::var("TURBOPUFFER_NAMESPACE").unwrap_or_default();
-    let turbopuffer_endpoint = format!(
-        "https://{turbopuffer_region}.turbopuffer.com/v2/namespaces/{turbopuffer_namespace}/query"
+    let turbopuffer = Turbopuffer::new(
+        format!("https://{turbopuffer_region}.turbopuffer.com"),
+        ApiAuth::Bearer(std::env::var("TURBOPUFFER_API_KEY").unwrap_or_default()),
+    )
+    .header("user-agent", "momento-turbobuffer-example");
+
+    let result = turbopuffer.query(
+        &turbopuffer_namespace,
+        &QueryRequest {
+            rank_by: json!(["vector", "ANN", embeddings]),
+            top_k: topk,
+            include_attributes,
+        },
     );
-
-    let result = momento_functions_host::http::post(
-        turbopuffer_endpoint,
-        [
-            ("Authorization".to_string(), turbopuffer_api_key.to_string()),
-            ("Content-Type".to_string(), "application/json".to_string()),
-            ("Accept".to_string(), "*/*".to_string()),
-            (
-                "User-Agent".to_string(),
-                "momento-turbobuffer-example".to_string(),
-            ),
-        ],
-        json!({
-            "rank_by": ["vector", "ANN", embeddings],
-            "top_k": topk,
-            "include_attributes": include_attributes,
-        }),
-    );
-    log::debug!("Turbopuffer response: {result:?}");
     match result {
-        Ok(mut response) => {
-            if response.status != 200 {
-                let message = format!(
-                    "Failed to search documents: {}",
-                    String::from_utf8(response.body).unwrap_or_default(),
-                );
-                return Ok(WebResponse::new()
-                    .with_status(response.status)
-                    .with_body(json!({
-                        "message": message,
-                    }))?);
-            }
-            // Just get the data we care about, no need to report back Turbopuffer timings/billing info
-            let Json(QueryResponse { rows }) = response.extract()?;
-            let response_body = serde_json::to_vec(&rows)?;
-            Ok(WebResponse::new()
-                .with_status(200)
-                .with_headers(vec![(
-                    "Content-Type".to_string(),
-                    "application/json".to_string(),
-                )])
-                .with_body(response_body)?)
+        // Just get the data we care about, no need to report back Turbopuffer timings/billing info
+        Ok(QueryResponse { rows }) => Ok(WebResponse::new()
+            .with_status(200)
+            .with_headers(vec![(
+                "Content-Type".to_string(),
+                "application/json".to_string(),
+            )])
+            .with_body(serde_json::to_vec(&rows)?)?),
+        Err(ApiError::Status { status, message }) => {
+            Ok(WebResponse::new().with_status(status).with_body(json!({
+                "message": format!("Failed to search documents: {message}"),
+            }))?)
         }
         Err(e) => {
             log::error!("Failed to search documents: {e:?}");
@@ -186,27 +202,16 @@ fn get_embeddings(mut query: String) -> WebResult<Vec<EmbeddingData>> {
     query.truncate(10_000);
 
     // Required to be set as an environment variable when creating the function
-    let openai_api_key = std::env::var("OPENAI_API_KEY").unwrap_or_default();
-    let result = momento_functions_host::http::post(
-        "https://api.openai.com/v1/embeddings",
-        [
-            (
-                "authorization".to_string(),
-                format!("Bearer {openai_api_key}"),
-            ),
-            ("content-type".to_string(), "application/json".to_string()),
-        ],
-        // 1536 float32 for text-embedding-3-small
-        serde_json::json!({
-            "model": "text-embedding-3-small",
-            "encoding_format": "float",
-            "input": [query],
-        })
-        .to_string(),
+    let openai = OpenAi::new(
+        "https://api.openai.com",
+        ApiAuth::Bearer(std::env::var("OPENAI_API_KEY").unwrap_or_default()),
     );
-    log::debug!("OpenAI response: {result:?}");
-    let mut response = result?;
-    let Json(EmbeddingResponse { mut data }) = response.extract()?;
+    // 1536 float32 for text-embedding-3-small
+    let EmbeddingResponse { mut data } = openai.embeddings(&EmbeddingRequest {
+        model: "text-embedding-3-small",
+        encoding_format: "float",
+        input: vec![query],
+    })?;
     data.sort_by_key(|d| d.index);
     log::debug!("OpenAI extracted data: {data:?}");
     Ok(data)
@@ -2,7 +2,10 @@ use momento_functions::{WebError, WebResponse, WebResult};
 use momento_functions_host::{
     encoding::Json,
     logging::LogDestination,
-    redis::{Command, RedisClient, RedisValue},
+    redis::{
+        Command, RedisClient,
+        search::{DistanceMetric, IndexSpec, VectorField, ensure_index},
+    },
     web_extensions::FunctionEnvironment,
 };
 
@@ -82,58 +85,17 @@ fn ensure_index_exists(
     dimensions: usize,
     redis: &RedisClient,
 ) -> Result<(), Result<WebResponse, WebError>> {
-    match redis.pipe(vec![
-        Command::builder()
-            .any("FT.INFO")
-            .arg("document_index")
-            .build(),
-    ]) {
-        Ok(mut definition) => match definition.next() {
-            Some(definition) => {
-                if let RedisValue::Bulk(definition_stream) = definition {
-                    let values: Vec<RedisValue> = definition_stream.collect();
-                    log::debug!("Redis index info: {values:?}");
-                } else {
-                    log::warn!("Unexpected response type from Redis FT.INFO: {definition:?}");
-                    return Err(WebResponse::new()
-                        .with_status(500)
-                        .with_body("Unexpected response from Redis FT.INFO")
-                        .map_err(WebError::from));
-                }
-            }
-            None => {
-                log::info!("redis did not return an answer for index info");
-                return Err(WebResponse::new()
-                    .with_status(400)
-                    .with_body("redis is not answering index info")
-                    .map_err(WebError::from));
-            }
-        },
+    let spec = IndexSpec::new("document_index").vector_field(
+        VectorField::hnsw("vector", dimensions).distance_metric(DistanceMetric::Cosine),
+    );
+    match ensure_index(redis, &spec) {
+        Ok(outcome) => log::info!("index ready: {outcome:?}"),
         Err(e) => {
-            log::info!("index does not exist, creating a new one: {e:?}");
-            match redis.pipe(vec![
-                Command::builder()
-                    .any("FT.CREATE")
-                    .arg("document_index")
-                    .arg("SCHEMA")
-                    .arg("vector")
-                    .arg("VECTOR HNSW")
-                    .arg("6")
-                    .arg("TYPE FLOAT32")
-                    .arg("DIM")
-                    .arg(dimensions.to_string())
-                    .arg("DISTANCE_METRIC COSINE")
-                    .build(),
-            ]) {
-                Ok(_) => log::info!("Index created successfully"),
-                Err(e) => {
-                    log::error!("Failed to create index: {e:?}");
-                    return Err(WebResponse::new()
-                        .with_status(500)
-                        .with_body("Failed to create index")
-                        .map_err(WebError::from));
-                }
-            }
+            log::error!("Failed to ensure index exists: {e:?}");
+            return Err(WebResponse::new()
+                .with_status(500)
+                .with_body("Failed to create index")
+                .map_err(WebError::from));
         }
     }
     Ok(())
@@ -0,0 +1,100 @@
+use std::sync::Once;
+
+use momento_functions_wit::abi::{self, AbiVersion};
+
+/// The versions a Function was compiled against.
+///
+/// Include these when reporting a problem: they identify exactly which guest crates and
+/// WIT interfaces are in the Function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The version of `momento-functions`.
+    pub momento_functions: &'static str,
+    /// The version of `momento-functions-host`.
+    pub momento_functions_host: &'static str,
+    /// The version of `momento-functions-wit`.
+    pub momento_functions_wit: &'static str,
+    /// The version of the `momento:functions` WIT package, which describes the exports the
+    /// host calls.
+    pub functions_wit: &'static str,
+    /// The version of the `momento:host` WIT package, which describes the imports the host
+    /// must provide.
+    pub host_wit: &'static str,
+}
+
+/// The versions this Function was compiled against.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// momento_functions::post!(version);
+/// fn version(_payload: Vec<u8>) -> String {
+///     let info = momento_functions::build_info();
+///     format!("momento-functions {} (momento:host@{})", info.momento_functions, info.host_wit)
+/// }
+/// ```
+pub const fn build_info() -> BuildInfo {
+    BuildInfo {
+        momento_functions: env!("CARGO_PKG_VERSION"),
+        momento_functions_host: momento_functions_host::VERSION,
+        momento_functions_wit: momento_functions_wit::VERSION,
+        functions_wit: momento_functions_wit::FUNCTIONS_WIT_VERSION,
+        host_wit: momento_functions_wit::HOST_WIT_VERSION,
+    }
+}
+
+static CHECK_HOST_ABI: Once = Once::new();
+
+/// An internal helper for the handler macros.
+///
+/// Reports, once per instance, when the host advertises an older `momento:host` level than
+/// the one this Function was built against, and which calls will return errors on it. A host
+/// with a different major version is not reported here: the Function would not have
+/// instantiated on it.
+#[doc(hidden)]
+pub fn check_host_abi() {
+    CHECK_HOST_ABI.call_once(|| {
+        if let Ok(host_version) = std::env::var(abi::HOST_ABI_VERSION_VARIABLE)
+            && let Some(message) = older_level(&host_version)
+        {
+            log::error!("{message}");
+        }
+    });
+}
+
+/// A host with an older level runs the Function, but calls to what it lacks return errors.
+fn older_level(host_version: &str) -> Option<String> {
+    let host = AbiVersion::parse(host_version)?;
+    let unsupported = abi::unsupported_capabilities(host);
+    if unsupported.is_empty() {
+        return None;
+    }
+    let unsupported: Vec<String> = unsupported.iter().map(ToString::to_string).collect();
+    Some(format!(
+        "This Function was built against momento:host@{}, but the host provides \
+         momento:host@{host_version}. It will run, but these return errors: {}.",
+        abi::built_abi(),
+        unsupported.join(", "),
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_and_newer_hosts_are_not_reported() {
+        let built = abi::built_abi();
+        assert_eq!(None, older_level(&built.to_string()));
+        assert_eq!(None, older_level(&format!("{}.99.0", built.major)));
+        assert_eq!(None, older_level("not a version"));
+    }
+
+    #[test]
+    fn older_hosts_are_reported_with_what_they_lack() {
+        let message = older_level("1.0.0").expect("host is older");
+        assert!(message.contains("momento:host@1.0.0"));
+        assert!(message.contains("It will run"));
+        assert!(message.contains("S3 copy"));
+    }
+}
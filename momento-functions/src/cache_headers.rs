@@ -0,0 +1,338 @@
+//! Caching headers for CDNs and browsers in front of a Web Function
+//!
+//! [CacheControl] builds `Cache-Control` and `Surrogate-Control` values, and [WebResponse] has
+//! methods to set them along with `Age` and surrogate keys. Surrogate keys tag a response so
+//! a CDN can purge every response with a tag at once. [purge_surrogate_keys] publishes those
+//! tags to a topic, where the Function or service that talks to your CDN's purge API can pick
+//! them up.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use momento_functions::WebResponse;
+//! use momento_functions::cache_headers::CacheControl;
+//!
+//! momento_functions::post!(product);
+//! fn product(_payload: Vec<u8>) -> WebResponse {
+//!     WebResponse::new()
+//!         .cache_control(
+//!             CacheControl::public()
+//!                 .max_age(Duration::from_secs(60))
+//!                 .s_maxage(Duration::from_secs(3600))
+//!                 .stale_while_revalidate(Duration::from_secs(30)),
+//!         )
+//!         .surrogate_keys(["product-42", "catalog"])
+//! }
+//! ```
+//!
+//! When product 42 changes, purge it everywhere it was cached:
+//! ```rust,no_run
+//! use momento_functions::cache_headers::purge_surrogate_keys;
+//!
+//! if let Err(e) = purge_surrogate_keys("cdn-purges", ["product-42"]) {
+//!     eprintln!("failed to request a purge: {e}");
+//! }
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use momento_functions_host::encoding::Json;
+use momento_functions_host::topics::{self, PublishError};
+use serde::{Deserialize, Serialize};
+
+use crate::WebResponse;
+
+/// Who may store a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visibility {
+    Public,
+    Private,
+    NoStore,
+}
+
+/// A `Cache-Control` value.
+///
+/// Start from who may store the response, then add lifetimes and revalidation rules. Values
+/// are written in the order of [RFC 9111](https://www.rfc-editor.org/rfc/rfc9111), with
+/// lifetimes in whole seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheControl {
+    visibility: Visibility,
+    max_age: Option<Duration>,
+    s_maxage: Option<Duration>,
+    stale_while_revalidate: Option<Duration>,
+    stale_if_error: Option<Duration>,
+    no_cache: bool,
+    must_revalidate: bool,
+    immutable: bool,
+}
+
+impl CacheControl {
+    fn with_visibility(visibility: Visibility) -> Self {
+        Self {
+            visibility,
+            max_age: None,
+            s_maxage: None,
+            stale_while_revalidate: None,
+            stale_if_error: None,
+            no_cache: false,
+            must_revalidate: false,
+            immutable: false,
+        }
+    }
+
+    /// Any cache, including shared ones like CDNs, may store the response.
+    pub fn public() -> Self {
+        Self::with_visibility(Visibility::Public)
+    }
+
+    /// Only the caller's own cache, like their browser, may store the response. Use this for
+    /// responses that depend on who is asking.
+    pub fn private() -> Self {
+        Self::with_visibility(Visibility::Private)
+    }
+
+    /// No cache may store the response. Lifetimes and other directives are not written.
+    pub fn no_store() -> Self {
+        Self::with_visibility(Visibility::NoStore)
+    }
+
+    /// How long the response is fresh.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// How long the response is fresh in shared caches, overriding [max_age](Self::max_age)
+    /// there.
+    pub fn s_maxage(mut self, s_maxage: Duration) -> Self {
+        self.s_maxage = Some(s_maxage);
+        self
+    }
+
+    /// How long after it goes stale the response may still be served while the cache fetches
+    /// a fresh one.
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_while_revalidate = Some(window);
+        self
+    }
+
+    /// How long after it goes stale the response may still be served if fetching a fresh one
+    /// fails.
+    pub fn stale_if_error(mut self, window: Duration) -> Self {
+        self.stale_if_error = Some(window);
+        self
+    }
+
+    /// Caches may store the response, but must check with the Function before each use.
+    pub fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+        self
+    }
+
+    /// Caches must not serve the response once it is stale without checking with the Function.
+    pub fn must_revalidate(mut self) -> Self {
+        self.must_revalidate = true;
+        self
+    }
+
+    /// The response never changes while it is fresh, so caches need not revalidate it when a
+    /// user reloads. Use this for versioned assets.
+    pub fn immutable(mut self) -> Self {
+        self.immutable = true;
+        self
+    }
+}
+
+impl Display for CacheControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let visibility = match self.visibility {
+            Visibility::NoStore => return f.write_str("no-store"),
+            Visibility::Public => "public",
+            Visibility::Private => "private",
+        };
+        let mut directives = vec![visibility.to_string()];
+        let lifetimes = [
+            ("max-age", self.max_age),
+            ("s-maxage", self.s_maxage),
+            ("stale-while-revalidate", self.stale_while_revalidate),
+            ("stale-if-error", self.stale_if_error),
+        ];
+        for (name, lifetime) in lifetimes {
+            if let Some(lifetime) = lifetime {
+                directives.push(format!("{name}={}", lifetime.as_secs()));
+            }
+        }
+        let flags = [
+            ("no-cache", self.no_cache),
+            ("must-revalidate", self.must_revalidate),
+            ("immutable", self.immutable),
+        ];
+        for (name, set) in flags {
+            if set {
+                directives.push(name.to_string());
+            }
+        }
+        f.write_str(&directives.join(", "))
+    }
+}
+
+impl WebResponse {
+    /// Sets the `Cache-Control` header, replacing any already set.
+    pub fn cache_control(self, cache_control: CacheControl) -> Self {
+        self.replace_header("cache-control", cache_control.to_string())
+    }
+
+    /// Sets the `Surrogate-Control` header, replacing any already set. CDNs that support it
+    /// use it instead of `Cache-Control` and remove it before responding, so browsers and CDNs
+    /// can be told different lifetimes.
+    pub fn surrogate_control(self, surrogate_control: CacheControl) -> Self {
+        self.replace_header("surrogate-control", surrogate_control.to_string())
+    }
+
+    /// Sets the `Age` header, replacing any already set, for a response that was cached for
+    /// `age` before it was served.
+    pub fn age(self, age: Duration) -> Self {
+        self.replace_header("age", age.as_secs().to_string())
+    }
+
+    /// Tags the response with surrogate keys, for purging with [purge_surrogate_keys].
+    ///
+    /// Keys are written to `Surrogate-Key`, separated by spaces, and to `Cache-Tag`, separated
+    /// by commas, so both kinds of CDN see them. Whitespace and commas in a key are replaced
+    /// with `-`, and empty keys are dropped. Keys from earlier calls are kept.
+    pub fn surrogate_keys(mut self, keys: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let mut all_keys: Vec<String> = self
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("surrogate-key"))
+            .flat_map(|(_, value)| value.split_whitespace().map(str::to_string))
+            .collect();
+        for key in keys {
+            let key = surrogate_key(key.as_ref());
+            if !key.is_empty() && !all_keys.contains(&key) {
+                all_keys.push(key);
+            }
+        }
+        if all_keys.is_empty() {
+            return self;
+        }
+        self = self.replace_header("surrogate-key", all_keys.join(" "));
+        self.replace_header("cache-tag", all_keys.join(","))
+    }
+}
+
+fn surrogate_key(key: &str) -> String {
+    key.trim()
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || c == ',' {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// A request to purge the responses tagged with surrogate keys, published by
+/// [purge_surrogate_keys].
+///
+/// The Function or service that subscribes to the topic should decode this and call its CDN's
+/// purge API for each key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SurrogateKeyPurge {
+    /// The keys to purge.
+    pub surrogate_keys: Vec<String>,
+}
+
+/// Publish a [SurrogateKeyPurge] for `keys` to `topic`, as JSON.
+///
+/// Keys are cleaned up the same way as [WebResponse::surrogate_keys], so they match the tags
+/// the responses were given. Nothing is published when there are no keys.
+pub fn purge_surrogate_keys(
+    topic: impl AsRef<str>,
+    keys: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<(), PublishError<serde_json::Error>> {
+    let surrogate_keys: Vec<String> = keys
+        .into_iter()
+        .map(|key| surrogate_key(key.as_ref()))
+        .filter(|key| !key.is_empty())
+        .collect();
+    if surrogate_keys.is_empty() {
+        return Ok(());
+    }
+    topics::publish(topic, Json(SurrogateKeyPurge { surrogate_keys }))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(response: &'a WebResponse, name: &str) -> Vec<&'a str> {
+        response
+            .headers
+            .iter()
+            .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn cache_control_values() {
+        assert_eq!(
+            "public, max-age=60, s-maxage=3600, stale-while-revalidate=30",
+            CacheControl::public()
+                .stale_while_revalidate(Duration::from_secs(30))
+                .s_maxage(Duration::from_secs(3600))
+                .max_age(Duration::from_millis(60_900))
+                .to_string()
+        );
+        assert_eq!(
+            "private, max-age=0, no-cache, must-revalidate",
+            CacheControl::private()
+                .max_age(Duration::ZERO)
+                .must_revalidate()
+                .no_cache()
+                .to_string()
+        );
+        assert_eq!(
+            "no-store",
+            CacheControl::no_store()
+                .max_age(Duration::from_secs(5))
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn headers_replace_earlier_values() {
+        let response = WebResponse::new()
+            .header("Cache-Control", "no-store")
+            .cache_control(CacheControl::public().immutable())
+            .age(Duration::from_secs(5))
+            .age(Duration::from_secs(7));
+        assert_eq!(
+            vec!["public, immutable"],
+            header(&response, "cache-control")
+        );
+        assert_eq!(vec!["7"], header(&response, "age"));
+    }
+
+    #[test]
+    fn surrogate_keys_accumulate_and_are_cleaned() {
+        let response = WebResponse::new()
+            .surrogate_keys(["product 42", "catalog"])
+            .surrogate_keys(["catalog", "", "a,b"]);
+        assert_eq!(
+            vec!["product-42 catalog a-b"],
+            header(&response, "surrogate-key")
+        );
+        assert_eq!(
+            vec!["product-42,catalog,a-b"],
+            header(&response, "cache-tag")
+        );
+    }
+}
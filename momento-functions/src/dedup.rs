@@ -0,0 +1,234 @@
+//! Short-circuiting redelivered requests
+//!
+//! Webhook providers redeliver when they don't see a response quickly, and some redeliver
+//! whether or not they did. [Deduplicator] recognizes a request it has already handled by
+//! the SHA-256 of its path, query, caller, and body, and answers it without running the
+//! handler again.
+
+use std::fmt::Write;
+use std::time::{Duration, SystemTime};
+
+use log::warn;
+use momento_functions_host::cache::{self, SetIfCondition, SetIfResult};
+use momento_functions_host::web_extensions::{FunctionEnvironment, headers, query_parameters};
+use sha2::{Digest, Sha256};
+
+use crate::page_cache::{CachedPage, epoch_millis};
+use crate::{WebResponse, WebResult};
+
+/// What a deduplication key holds while the first delivery is being handled.
+const IN_FLIGHT: &[u8] = &[0];
+
+/// The longest a delivery is considered in flight, so one whose Function failed before
+/// responding does not block redeliveries for the whole window.
+const MAX_IN_FLIGHT_TTL: Duration = Duration::from_secs(60);
+
+/// How a [Deduplicator] answers a request it has already seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnDuplicate {
+    /// Respond with the response the first delivery got, or with `409 Conflict` while the
+    /// first delivery is still being handled.
+    #[default]
+    Replay,
+    /// Always respond with `409 Conflict`.
+    Conflict,
+}
+
+/// Recognizes requests whose path and body were already handled within a window.
+///
+/// Requests are told apart by their query parameters and by the caller's `authorization`
+/// header too, so two callers sending the same body never get each other's responses.
+///
+/// The first delivery of a request runs the handler. Its response is remembered for the
+/// window and replayed for redeliveries, with an `x-duplicate: true` header. Server errors
+/// are not remembered, so a redelivery after one is handled again. Cache errors never fail a
+/// request: it is handled as if it were the first delivery.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions::{Deduplicator, WebResponse, WebResult};
+/// use std::time::Duration;
+///
+/// momento_functions::post!(payment_webhook);
+/// fn payment_webhook(payload: Vec<u8>) -> WebResponse {
+///     Deduplicator::new(Duration::from_secs(10 * 60)).serve(&payload, || {
+///         // Record the payment exactly once.
+///         Ok(WebResponse::new().with_status(204))
+///     })
+/// }
+/// ```
+pub struct Deduplicator {
+    window: Duration,
+    on_duplicate: OnDuplicate,
+    key_prefix: String,
+}
+
+impl Deduplicator {
+    /// Recognize duplicates within `window` of the first delivery, replaying its response.
+    /// Requests are remembered under `dedup:` keys unless you change it.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            on_duplicate: OnDuplicate::Replay,
+            key_prefix: "dedup:".to_string(),
+        }
+    }
+
+    /// How to answer duplicates.
+    pub fn on_duplicate(mut self, on_duplicate: OnDuplicate) -> Self {
+        self.on_duplicate = on_duplicate;
+        self
+    }
+
+    /// The prefix of the cache keys requests are remembered under.
+    pub fn key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// Respond to the current request, whose body is `body`, running `handle` only if it is
+    /// not a duplicate.
+    pub fn serve(
+        &self,
+        body: &[u8],
+        handle: impl FnOnce() -> WebResult<WebResponse>,
+    ) -> WebResponse {
+        let environment = FunctionEnvironment::get_function_environment();
+        let mut query: Vec<_> = query_parameters().iter().collect();
+        query.sort();
+        let caller = headers()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+            .map(|(_, value)| value.as_str())
+            .unwrap_or_default();
+        let mut parts = vec![environment.http_path().as_bytes(), caller.as_bytes()];
+        parts.extend(
+            query
+                .iter()
+                .flat_map(|(name, value)| [name.as_bytes(), value.as_bytes()]),
+        );
+        parts.push(body);
+        let key = self.cache_key(&parts);
+        match cache::set_if(
+            &key,
+            IN_FLIGHT,
+            self.window.min(MAX_IN_FLIGHT_TTL),
+            SetIfCondition::Absent,
+        ) {
+            Ok(SetIfResult::Stored) => {}
+            Ok(SetIfResult::NotStored) => return self.duplicate(&key),
+            Err(e) => {
+                warn!("failed to check {key} for a duplicate: {e}");
+                return handled(handle);
+            }
+        }
+
+        let response = handled(handle);
+        let result = if is_remembered(&response) {
+            let page = CachedPage::from_response(&response, epoch_millis(SystemTime::now()));
+            cache::set(&key, page.encode(), self.window).map_err(|e| e.to_string())
+        } else {
+            // Let the provider's redelivery try again.
+            cache::delete(&key).map_err(|e| e.to_string())
+        };
+        if let Err(e) = result {
+            warn!("failed to record the response for {key}: {e}");
+        }
+        response
+    }
+
+    fn duplicate(&self, key: &str) -> WebResponse {
+        if self.on_duplicate == OnDuplicate::Replay {
+            match cache::get::<Vec<u8>>(key) {
+                Ok(Some(entry)) => {
+                    if let Some(page) = CachedPage::decode(&entry) {
+                        return WebResponse {
+                            status: page.status,
+                            headers: page.headers,
+                            body: page.body,
+                        }
+                        .header("x-duplicate", "true");
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("failed to read the response for {key}: {e}"),
+            }
+        }
+        WebResponse {
+            status: 409,
+            headers: vec![("x-duplicate".to_string(), "true".to_string())],
+            body: b"duplicate request".to_vec(),
+        }
+    }
+
+    /// The key for a request made of `parts`. Each part is hashed after its length, so no two
+    /// different lists of parts hash the same way.
+    fn cache_key(&self, parts: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        let mut key = self.key_prefix.clone();
+        for byte in hasher.finalize() {
+            let _ = write!(key, "{byte:02x}");
+        }
+        key
+    }
+}
+
+fn handled(handle: impl FnOnce() -> WebResult<WebResponse>) -> WebResponse {
+    match handle() {
+        Ok(response) => response,
+        Err(e) => e.response,
+    }
+}
+
+fn is_remembered(response: &WebResponse) -> bool {
+    response.status < 500
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_hash_every_part() {
+        let dedup = Deduplicator::new(Duration::from_secs(60));
+        let key = dedup.cache_key(&[b"/hooks", b"Bearer a", br#"{"id":1}"#]);
+        assert!(key.starts_with("dedup:"));
+        assert_eq!(6 + 64, key.len());
+        assert_eq!(
+            key,
+            dedup.cache_key(&[b"/hooks", b"Bearer a", br#"{"id":1}"#])
+        );
+        assert_ne!(
+            key,
+            dedup.cache_key(&[b"/hooks", b"Bearer a", br#"{"id":2}"#])
+        );
+        assert_ne!(
+            key,
+            dedup.cache_key(&[b"/hooks", b"Bearer b", br#"{"id":1}"#])
+        );
+        assert_ne!(
+            key,
+            dedup.cache_key(&[b"/other", b"Bearer a", br#"{"id":1}"#])
+        );
+        assert_ne!(
+            dedup.cache_key(&[b"/a", b"", b"b"]),
+            dedup.cache_key(&[b"/a", b"b", b""])
+        );
+        assert_ne!(
+            dedup.cache_key(&[b"/a", b"", b"q", b"1", b"b"]),
+            dedup.cache_key(&[b"/a", b"", b"b"])
+        );
+    }
+
+    #[test]
+    fn in_flight_marker_is_not_a_response() {
+        assert_eq!(None, CachedPage::decode(IN_FLIGHT));
+        assert!(is_remembered(&WebResponse::new().with_status(409)));
+        assert!(!is_remembered(&WebResponse::new().with_status(503)));
+    }
+}
@@ -0,0 +1,134 @@
+use std::any::{Any, TypeId, type_name};
+use std::collections::HashMap;
+
+/// A container for values that live as long as the Function instance.
+///
+/// Momento keeps your Function instance warm across many invocations. Clients like
+/// `RedisClient` or `DynamoDBClient` only need to be constructed once per instance, so
+/// building them on every request is wasted work. Register them once in an `Extensions`
+/// setup function and they will be handed to each invocation of your handler.
+///
+/// Values are looked up by type, so you can register at most one value of each type. Use a
+/// newtype if you need more than one of the same client.
+///
+/// Because handlers receive `&Extensions` as a plain argument, tests can construct an
+/// `Extensions` with fakes and call the handler directly.
+///
+/// Examples:
+/// ________
+/// ```rust,no_run
+/// use momento_functions::Extensions;
+/// use momento_functions_host::redis::RedisClient;
+///
+/// momento_functions::post!(handle, extensions = setup);
+///
+/// fn setup(extensions: &mut Extensions) {
+///     extensions.insert(RedisClient::new("my-redis-endpoint"));
+/// }
+///
+/// fn handle(payload: Vec<u8>, extensions: &Extensions) -> Vec<u8> {
+///     let redis = extensions.expect::<RedisClient>();
+///     match redis.get::<Vec<u8>>("cached") {
+///         Ok(Some(value)) => value,
+///         Ok(None) => payload,
+///         Err(e) => format!("redis error: {e:?}").into_bytes(),
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Create an empty set of extensions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a value, returning the previously registered value of the same type, if any.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.values
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    /// Register a value, returning `self` so registrations can be chained.
+    pub fn with<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.insert(value);
+        self
+    }
+
+    /// Get the registered value of type `T`, if there is one.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Get the registered value of type `T`.
+    ///
+    /// Panics if no value of type `T` was registered. Use this when a missing value is a
+    /// programming error in your setup function.
+    pub fn expect<T: Send + Sync + 'static>(&self) -> &T {
+        match self.get::<T>() {
+            Some(value) => value,
+            None => panic!("no extension of type {} was registered", type_name::<T>()),
+        }
+    }
+
+    /// Get the registered value of type `T`, registering one built by `init` if there is none yet.
+    pub fn get_or_insert_with<T: Send + Sync + 'static>(&mut self, init: impl FnOnce() -> T) -> &T {
+        if !self.contains::<T>() {
+            self.insert(init());
+        }
+        self.expect::<T>()
+    }
+
+    /// Remove and return the registered value of type `T`, if there is one.
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.values
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+
+    /// True when a value of type `T` has been registered.
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    /// The number of registered values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// True when no values have been registered.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_or_insert_with_only_builds_missing_values() {
+        let mut extensions = Extensions::new().with(7_u32);
+        assert_eq!(7, *extensions.get_or_insert_with(|| 8_u32));
+        assert_eq!("built", *extensions.get_or_insert_with(|| "built"));
+        assert_eq!("built", *extensions.get_or_insert_with(|| "again"));
+        assert_eq!(2, extensions.len());
+    }
+}
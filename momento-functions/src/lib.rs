@@ -9,11 +9,30 @@
 //! You are likely to be interested in the sibling crates:
 //! * [`momento-functions-host`](https://crates.io/crates/momento-functions-host): Interfaces and tools for calling host interfaces.
 //! * [`momento-functions-log`](https://crates.io/crates/momento-functions-log): Standard `log` adapter.
+mod build_info;
+pub mod cache_headers;
+mod debug_endpoint;
+mod dedup;
 mod encode_response_bridge;
+mod extensions;
 mod macros;
+mod multi_status;
+mod page_cache;
+pub mod replay;
 mod response;
+pub mod template;
 
-pub use macros::post_template;
+pub use build_info::{BuildInfo, build_info, check_host_abi};
+pub use debug_endpoint::DebugEndpoint;
+pub use dedup::{Deduplicator, OnDuplicate};
+pub use extensions::Extensions;
+pub use macros::{
+    CacheEvent, CacheEventKind, cache_event_template, check_required_env, init_template,
+    log_request, post_proto_template, post_template, post_template_with_extensions,
+    require_env_template, spawn_ready,
+};
+pub use multi_status::{ItemStatus, MultiStatus};
+pub use page_cache::{PageCache, PageCacheError, PageRequest};
 pub use response::IntoWebResponse;
 pub use response::WebError;
 pub use response::WebResponse;
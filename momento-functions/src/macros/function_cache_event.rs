@@ -0,0 +1,105 @@
+use std::time::{Duration, SystemTime};
+
+use momento_functions_wit::function_cache_event::exports::momento::functions::guest_function_cache_event;
+
+/// The kind of change a [CacheEvent] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheEventKind {
+    /// The item was written.
+    Set,
+    /// The item was deleted.
+    Delete,
+    /// The item's ttl elapsed.
+    Expire,
+}
+
+/// A change to an item in a cache, delivered to a [crate::cache_event!] handler.
+#[derive(Debug, Clone)]
+pub struct CacheEvent {
+    /// The kind of change.
+    pub kind: CacheEventKind,
+    /// The cache that holds the item.
+    pub cache_name: String,
+    /// The item's key.
+    pub key: Vec<u8>,
+    /// The item's new value. Only present for [CacheEventKind::Set].
+    pub value: Option<Vec<u8>>,
+    /// The item's remaining ttl. Only present for [CacheEventKind::Set].
+    pub ttl: Option<Duration>,
+    /// When the change happened.
+    pub timestamp: SystemTime,
+}
+
+impl CacheEvent {
+    /// The key as a string, if it is valid UTF-8.
+    pub fn key_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.key).ok()
+    }
+}
+
+impl From<guest_function_cache_event::CacheEvent> for CacheEvent {
+    fn from(event: guest_function_cache_event::CacheEvent) -> Self {
+        Self {
+            kind: match event.kind {
+                guest_function_cache_event::CacheEventKind::Set => CacheEventKind::Set,
+                guest_function_cache_event::CacheEventKind::Delete => CacheEventKind::Delete,
+                guest_function_cache_event::CacheEventKind::Expire => CacheEventKind::Expire,
+            },
+            cache_name: event.cache_name,
+            key: event.key,
+            value: event.value,
+            ttl: event.ttl_milliseconds.map(Duration::from_millis),
+            timestamp: SystemTime::UNIX_EPOCH
+                + Duration::from_millis(event.timestamp_epoch_milliseconds),
+        }
+    }
+}
+
+/// Create a handler for changes to cache items.
+///
+/// The handler is invoked once per event with the item's key, and its value and ttl when
+/// it was set. Use it for write-through and notification patterns without publishing to a
+/// topic first.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions::{CacheEvent, CacheEventKind};
+///
+/// momento_functions::cache_event!(on_change);
+/// fn on_change(event: CacheEvent) {
+///     match event.kind {
+///         CacheEventKind::Set => println!("{:?} was set in {}", event.key_str(), event.cache_name),
+///         CacheEventKind::Delete | CacheEventKind::Expire => {
+///             println!("{:?} is gone from {}", event.key_str(), event.cache_name)
+///         }
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! cache_event {
+    ($event_handler: ident) => {
+        struct CacheEventFunction;
+        momento_functions_wit::function_cache_event::export_cache_event_function!(CacheEventFunction);
+
+        #[automatically_derived]
+        impl momento_functions_wit::function_cache_event::exports::momento::functions::guest_function_cache_event::Guest for CacheEventFunction {
+            fn on_cache_event(event: momento_functions_wit::function_cache_event::exports::momento::functions::guest_function_cache_event::CacheEvent) {
+                momento_functions::cache_event_template(event, $event_handler)
+            }
+        }
+    };
+}
+
+/// An internal helper for the cache_event! macro.
+#[doc(hidden)]
+pub fn cache_event_template(
+    event: guest_function_cache_event::CacheEvent,
+    handler: fn(event: CacheEvent),
+) {
+    crate::check_host_abi();
+    if let Err(message) = crate::check_required_env() {
+        log::error!("{message}");
+        return;
+    }
+    handler(event.into())
+}
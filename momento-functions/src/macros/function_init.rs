@@ -0,0 +1,60 @@
+use std::sync::Once;
+
+/// Register a function that runs once when the Function instance starts, before the
+/// first request is handled.
+///
+/// Use this to pre-build clients, fetch secrets, and prime caches so that the cost is not
+/// paid by whichever request happens to arrive first. The init function runs at most once
+/// per instance, regardless of which handler macro you use alongside it.
+///
+/// The init function takes no arguments and returns nothing. Store what it builds in a
+/// `static` (for example a `std::sync::OnceLock`) so your handlers can reach it.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use std::sync::OnceLock;
+///
+/// use momento_functions_host::redis::RedisClient;
+///
+/// static REDIS: OnceLock<RedisClient> = OnceLock::new();
+///
+/// momento_functions::init!(setup);
+/// fn setup() {
+///     REDIS.get_or_init(|| RedisClient::new("my-redis-endpoint"));
+/// }
+///
+/// momento_functions::post!(handle);
+/// fn handle(payload: Vec<u8>) -> Vec<u8> {
+///     let Some(redis) = REDIS.get() else {
+///         return b"not initialized".to_vec();
+///     };
+///     match redis.get::<Vec<u8>>("cached") {
+///         Ok(Some(value)) => value,
+///         Ok(None) => payload,
+///         Err(e) => format!("redis error: {e:?}").into_bytes(),
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! init {
+    ($init_handler: ident) => {
+        // Generated exports run the module's static constructors once, before the first
+        // call into the component. Registering here is what makes init run before any request.
+        #[used]
+        #[unsafe(link_section = ".init_array")]
+        static __MOMENTO_FUNCTIONS_INIT: extern "C" fn() = {
+            extern "C" fn __momento_functions_init() {
+                momento_functions::init_template($init_handler)
+            }
+            __momento_functions_init
+        };
+    };
+}
+
+static INIT: Once = Once::new();
+
+/// An internal helper for the init! macro.
+#[doc(hidden)]
+pub fn init_template(handler: fn()) {
+    INIT.call_once(handler);
+}
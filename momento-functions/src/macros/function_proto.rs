@@ -0,0 +1,94 @@
+use momento_functions_wit::function_web::exports::momento::functions::guest_function_web;
+
+use crate::response::{IntoWebResponse, WebResult};
+
+const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Create a handler that accepts a protobuf request and returns a protobuf response.
+///
+/// The request body is decoded as `RequestMessage`, and the message your handler returns is
+/// encoded as the response body with a `content-type` of `application/x-protobuf`. A body
+/// that does not decode results in a 400 with the decoding error; an `Err` from your
+/// handler is returned like any other [WebResult](crate::WebResult) error.
+///
+/// The messages are encoded and decoded with [`prost`](https://docs.rs/prost), so your
+/// Function needs `prost` as a dependency and messages that implement `prost::Message`,
+/// typically generated by `prost-build`.
+///
+/// **Examples:**
+/// ```rust,ignore
+/// use momento_functions::WebResult;
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct GetPriceRequest {
+///     #[prost(string, tag = "1")]
+///     pub sku: String,
+/// }
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct GetPriceResponse {
+///     #[prost(uint64, tag = "1")]
+///     pub cents: u64,
+/// }
+///
+/// momento_functions::post_proto!(get_price, GetPriceRequest, GetPriceResponse);
+/// fn get_price(request: GetPriceRequest) -> WebResult<GetPriceResponse> {
+///     Ok(GetPriceResponse {
+///         cents: if request.sku.is_empty() { 0 } else { 499 },
+///     })
+/// }
+/// ```
+#[macro_export]
+macro_rules! post_proto {
+    ($post_handler: ident, $request: ty, $response: ty) => {
+        struct WebFunction;
+        momento_functions_wit::__export_web_function_impl!(WebFunction);
+
+        #[automatically_derived]
+        impl momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Guest for WebFunction {
+            fn post(payload: Vec<u8>) -> momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Response {
+                momento_functions::post_proto_template::<$request, $response>(
+                    payload,
+                    |payload| <$request as ::prost::Message>::decode(payload).map_err(|e| e.to_string()),
+                    |response| ::prost::Message::encode_to_vec(response),
+                    $post_handler,
+                )
+            }
+        }
+    };
+}
+
+/// An internal helper for the post_proto! macro.
+#[doc(hidden)]
+pub fn post_proto_template<TRequest, TResponse>(
+    payload: Vec<u8>,
+    decode: fn(&[u8]) -> Result<TRequest, String>,
+    encode: fn(&TResponse) -> Vec<u8>,
+    handler: fn(request: TRequest) -> WebResult<TResponse>,
+) -> guest_function_web::Response {
+    crate::check_host_abi();
+    if let Err(message) = crate::check_required_env() {
+        return super::function_web::misconfigured(message);
+    }
+    let request = match decode(&payload) {
+        Ok(request) => request,
+        Err(error) => {
+            return guest_function_web::Response {
+                status: 400,
+                headers: vec![],
+                body: format!("Failed to decode protobuf request body: {error}").into_bytes(),
+            };
+        }
+    };
+    match handler(request) {
+        Ok(response) => guest_function_web::Response {
+            status: 200,
+            headers: vec![guest_function_web::Header {
+                name: "content-type".to_string(),
+                value: PROTOBUF_CONTENT_TYPE.to_string(),
+            }],
+            body: encode(&response),
+        },
+        Err(error) => WebResult::<()>::Err(error).response(),
+    }
+}
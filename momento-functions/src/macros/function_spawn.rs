@@ -30,6 +30,9 @@ macro_rules! spawn {
         #[automatically_derived]
         impl momento_functions_wit::function_spawn::exports::momento::functions::guest_function_spawn::Guest for SpawnFunction {
             fn spawned(payload: Vec<u8>) {
+                if !momento_functions::spawn_ready() {
+                    return;
+                }
                 $spawn_handler(payload)
             }
         }
@@ -42,9 +45,28 @@ macro_rules! spawn {
         #[automatically_derived]
         impl momento_functions_wit::function_spawn::exports::momento::functions::guest_function_spawn::Guest for SpawnFunction {
             fn spawned(payload: Vec<u8>) {
+                if !momento_functions::spawn_ready() {
+                    return;
+                }
                 let payload: $request = serde_json::from_slice(&payload).expect("payload is not valid json");
                 $post_handler(payload)
             }
         }
     }
 }
+
+/// An internal helper for the spawn! macro.
+///
+/// Returns whether the handler can run, logging why not when a variable named by
+/// require_env! is missing.
+#[doc(hidden)]
+pub fn spawn_ready() -> bool {
+    crate::check_host_abi();
+    match crate::check_required_env() {
+        Ok(()) => true,
+        Err(message) => {
+            log::error!("{message}");
+            false
+        }
+    }
+}
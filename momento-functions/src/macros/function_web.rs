@@ -1,6 +1,9 @@
+use std::sync::OnceLock;
+
 use momento_functions_host::encoding::Extract;
 use momento_functions_wit::function_web::exports::momento::functions::guest_function_web;
 
+use crate::Extensions;
 use crate::response::IntoWebResponse;
 /// Create a handler that accepts a post payload and returns a response.
 ///
@@ -54,6 +57,84 @@ use crate::response::IntoWebResponse;
 ///     Json(Response { message: format!("Hello, {}!", request.name) })
 /// }
 /// ```
+///
+/// **Raw Body Alongside Typed Input:**
+///
+/// Accept `(Body, T)` to receive the exact request bytes together with the extracted value.
+/// This is useful for webhooks, where a signature is computed over the bytes on the wire and
+/// re-serializing the parsed value would not reproduce them. To check the signature before
+/// parsing at all, accept just [Body] and call [Body::extract] yourself.
+/// ```rust,no_run
+/// use momento_functions::{WebResponse, WebResult};
+/// use momento_functions_host::encoding::{Body, Json};
+/// use momento_functions_host::web_extensions::headers;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Event {
+///     kind: String,
+/// }
+///
+/// momento_functions::post!(webhook);
+/// fn webhook((body, Json(event)): (Body, Json<Event>)) -> WebResult<WebResponse> {
+///     let signature = headers().get("x-signature").map(String::as_str).unwrap_or_default();
+///     if !signature_matches(body.as_bytes(), signature) {
+///         return Ok(WebResponse::new().with_status(401));
+///     }
+///     Ok(WebResponse::new().with_body(format!("received {}", event.kind))?)
+/// }
+///
+/// fn signature_matches(body: &[u8], signature: &str) -> bool {
+///     // Compute an HMAC of `body` with your shared secret and compare it to `signature`.
+///     !body.is_empty() && !signature.is_empty()
+/// }
+/// ```
+///
+/// **Instance-scoped Extensions:**
+///
+/// Pass a setup function with `extensions = setup` to build clients once per Function instance
+/// rather than once per request. The setup function runs once per instance, when the first
+/// request arrives and before its handler is called, so that request waits for setup. Your
+/// handler receives the [Extensions] as a second argument.
+/// ```rust,no_run
+/// use momento_functions::Extensions;
+/// use momento_functions_host::aws::auth::AwsCredentialsProvider;
+/// use momento_functions_host::aws::ddb::DynamoDBClient;
+/// use momento_functions_host::build_environment_aws_credentials;
+///
+/// momento_functions::post!(handle, extensions = setup);
+///
+/// fn setup(extensions: &mut Extensions) {
+///     match AwsCredentialsProvider::new("us-east-1", build_environment_aws_credentials!()) {
+///         Ok(credentials) => {
+///             extensions.insert(DynamoDBClient::new(&credentials));
+///         }
+///         Err(e) => eprintln!("failed to build credentials: {e:?}"),
+///     }
+/// }
+///
+/// fn handle(payload: Vec<u8>, extensions: &Extensions) -> &'static str {
+///     match extensions.get::<DynamoDBClient>() {
+///         Some(_client) => "ready",
+///         None => "not configured",
+///     }
+/// }
+/// ```
+///
+/// **Request Logging:**
+///
+/// Add `log_requests` to log one structured line per request, with its method, path, status,
+/// duration, headers, query parameters, and body. The `authorization`, `proxy-authorization`,
+/// `cookie`, and `set-cookie` headers are always redacted. Name more headers, query parameters,
+/// and JSON body fields to keep out of the logs with `redact`; names match regardless of case,
+/// at any depth in the body. Bodies that are not JSON are logged as their size. Lines are logged
+/// with the `momento_functions::request_log` target, so your logger can send them to their own
+/// destination or filter them out. This can follow `extensions = setup`.
+/// ```rust,no_run
+/// momento_functions::post!(login, log_requests(redact = ["password"]));
+/// fn login(payload: Vec<u8>) -> &'static str {
+///     "welcome"
+/// }
+/// ```
 #[macro_export]
 macro_rules! post {
     ($post_handler: ident) => {
@@ -67,6 +148,49 @@ macro_rules! post {
             }
         }
     };
+    ($post_handler: ident, extensions = $setup: ident) => {
+        struct WebFunction;
+        momento_functions_wit::__export_web_function_impl!(WebFunction);
+
+        #[automatically_derived]
+        impl momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Guest for WebFunction {
+            fn post(payload: Vec<u8>) -> momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Response {
+                momento_functions::post_template_with_extensions(payload, $setup, $post_handler)
+            }
+        }
+    };
+    ($post_handler: ident, log_requests) => {
+        momento_functions::post!($post_handler, log_requests(redact = []));
+    };
+    ($post_handler: ident, log_requests(redact = [$($redact: literal),* $(,)?])) => {
+        struct WebFunction;
+        momento_functions_wit::__export_web_function_impl!(WebFunction);
+
+        #[automatically_derived]
+        impl momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Guest for WebFunction {
+            fn post(payload: Vec<u8>) -> momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Response {
+                momento_functions::log_request(payload, &[$($redact),*], |payload| {
+                    momento_functions::post_template(payload, $post_handler)
+                })
+            }
+        }
+    };
+    ($post_handler: ident, extensions = $setup: ident, log_requests) => {
+        momento_functions::post!($post_handler, extensions = $setup, log_requests(redact = []));
+    };
+    ($post_handler: ident, extensions = $setup: ident, log_requests(redact = [$($redact: literal),* $(,)?])) => {
+        struct WebFunction;
+        momento_functions_wit::__export_web_function_impl!(WebFunction);
+
+        #[automatically_derived]
+        impl momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Guest for WebFunction {
+            fn post(payload: Vec<u8>) -> momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Response {
+                momento_functions::log_request(payload, &[$($redact),*], |payload| {
+                    momento_functions::post_template_with_extensions(payload, $setup, $post_handler)
+                })
+            }
+        }
+    };
 }
 
 /// An internal helper for the post! macro.
@@ -79,18 +203,104 @@ where
     TExtract: Extract,
     TResponse: IntoWebResponse,
 {
-    let request = match TExtract::extract(payload) {
-        Ok(request) => request,
-        Err(error) => {
-            return guest_function_web::Response {
-                status: 400,
-                headers: vec![],
-                body: format!("Failed to parse request body: {error}")
-                    .to_string()
-                    .as_bytes()
-                    .to_vec(),
-            };
-        }
+    post_template_inner(payload, handler)
+}
+
+fn post_template_inner<TExtract, TResponse>(
+    payload: Vec<u8>,
+    handler: impl FnOnce(TExtract) -> TResponse,
+) -> guest_function_web::Response
+where
+    TExtract: Extract,
+    TResponse: IntoWebResponse,
+{
+    crate::check_host_abi();
+    if let Err(message) = crate::check_required_env() {
+        return misconfigured(message);
+    }
+    // Copy the request only when it may need to be captured.
+    let replay = crate::replay::capturing().then(|| payload.clone());
+    let response = match TExtract::extract(payload) {
+        Ok(request) => handler(request).response(),
+        Err(error) => guest_function_web::Response {
+            status: 400,
+            headers: vec![],
+            body: format!("Failed to parse request body: {error}")
+                .to_string()
+                .as_bytes()
+                .to_vec(),
+        },
     };
-    handler(request).response()
+    if let Some(payload) = replay {
+        crate::replay::capture(payload, &response);
+    }
+    response
+}
+
+/// The response to every request while a variable named by require_env! is missing.
+pub(crate) fn misconfigured(message: String) -> guest_function_web::Response {
+    guest_function_web::Response {
+        status: 500,
+        headers: vec![],
+        body: message.into_bytes(),
+    }
+}
+
+static EXTENSIONS: OnceLock<Extensions> = OnceLock::new();
+
+/// An internal helper for the post! macro.
+#[doc(hidden)]
+pub fn post_template_with_extensions<TExtract, TResponse>(
+    payload: Vec<u8>,
+    setup: fn(extensions: &mut Extensions),
+    handler: fn(request: TExtract, extensions: &Extensions) -> TResponse,
+) -> guest_function_web::Response
+where
+    TExtract: Extract,
+    TResponse: IntoWebResponse,
+{
+    // Setup usually reads the environment, so don't run it until the environment is complete.
+    if let Err(message) = crate::check_required_env() {
+        return misconfigured(message);
+    }
+    let extensions = instance_extensions(&EXTENSIONS, setup);
+    post_template_inner(payload, |request| handler(request, extensions))
+}
+
+/// The instance's extensions, running `setup` the first time they are needed.
+fn instance_extensions(
+    extensions: &OnceLock<Extensions>,
+    setup: fn(extensions: &mut Extensions),
+) -> &Extensions {
+    extensions.get_or_init(|| {
+        let mut extensions = Extensions::new();
+        setup(&mut extensions);
+        extensions
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn setup_runs_once_per_instance() {
+        static SETUPS: AtomicUsize = AtomicUsize::new(0);
+        fn setup(extensions: &mut Extensions) {
+            extensions.insert(SETUPS.fetch_add(1, Ordering::SeqCst) + 1);
+        }
+
+        let extensions = OnceLock::new();
+        assert_eq!(0, SETUPS.load(Ordering::SeqCst));
+        for _ in 0..3 {
+            assert_eq!(
+                Some(&1),
+                instance_extensions(&extensions, setup).get::<usize>()
+            );
+        }
+        assert_eq!(1, SETUPS.load(Ordering::SeqCst));
+    }
 }
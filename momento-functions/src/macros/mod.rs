@@ -1,4 +1,15 @@
+mod function_cache_event;
+mod function_init;
+mod function_proto;
 mod function_spawn;
 mod function_web;
+mod request_log;
+mod require_env;
 
-pub use function_web::post_template;
+pub use function_cache_event::{CacheEvent, CacheEventKind, cache_event_template};
+pub use function_init::init_template;
+pub use function_proto::post_proto_template;
+pub use function_spawn::spawn_ready;
+pub use function_web::{post_template, post_template_with_extensions};
+pub use request_log::log_request;
+pub use require_env::{check_required_env, require_env_template};
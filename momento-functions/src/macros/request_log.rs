@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+use momento_functions_host::web_extensions::{FunctionEnvironment, headers, query_parameters};
+use momento_functions_wit::function_web::exports::momento::functions::guest_function_web;
+use serde_json::Value;
+
+/// What a redacted header, query parameter, or body field is logged as.
+const REDACTED: &str = "[REDACTED]";
+
+/// Credential headers, which are redacted whether or not they are named in `redact`.
+const ALWAYS_REDACTED: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+];
+
+/// Request bodies are logged up to this many characters.
+const MAX_BODY_CHARS: usize = 1024;
+
+/// The log target request lines are written with, so they can be filtered on their own.
+const TARGET: &str = "momento_functions::request_log";
+
+/// An internal helper for the post! macro.
+///
+/// Runs `handle` and logs one structured line describing the request and its response, at
+/// `error` for 5xx responses and `info` otherwise, with the `momento_functions::request_log`
+/// target. Header names, query parameter names, and JSON body fields named in `redact` or
+/// [ALWAYS_REDACTED] are logged with their values replaced, ignoring case.
+#[doc(hidden)]
+pub fn log_request(
+    payload: Vec<u8>,
+    redact: &[&str],
+    handle: impl FnOnce(Vec<u8>) -> guest_function_web::Response,
+) -> guest_function_web::Response {
+    let environment = FunctionEnvironment::get_function_environment();
+    let request_bytes = payload.len();
+    let request_body = summarize_body(&payload, redact);
+
+    let start = Instant::now();
+    let response = handle(payload);
+    let duration = start.elapsed();
+
+    let method = environment.http_method();
+    let path = environment.http_path();
+    let status = response.status;
+    let duration_millis = duration.as_secs_f64() * 1000.0;
+    let level = if 500 <= status {
+        log::Level::Error
+    } else {
+        log::Level::Info
+    };
+    log::log!(
+        target: TARGET,
+        level,
+        "{}",
+        serde_json::json!({
+            "message": format!("{method} {path} {status} in {duration_millis:.1}ms"),
+            "method": method,
+            "path": path,
+            "status": status,
+            "duration_ms": duration_millis,
+            "invocation_id": environment.invocation_id(),
+            "request_headers": redact_map(headers(), redact),
+            "query_parameters": redact_map(query_parameters(), redact),
+            "request_bytes": request_bytes,
+            "request_body": request_body,
+            "response_bytes": response.body.len(),
+        })
+    );
+    response
+}
+
+fn is_redacted(name: &str, redact: &[&str]) -> bool {
+    ALWAYS_REDACTED
+        .iter()
+        .chain(redact)
+        .any(|redacted| redacted.eq_ignore_ascii_case(name))
+}
+
+fn redact_map<'a>(
+    values: &'a HashMap<String, String>,
+    redact: &[&str],
+) -> BTreeMap<&'a str, &'a str> {
+    values
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_redacted(name, redact) {
+                REDACTED
+            } else {
+                value.as_str()
+            };
+            (name.as_str(), value)
+        })
+        .collect()
+}
+
+/// A JSON body with its redacted fields replaced at any depth, or the size of any other body.
+/// Either is cut off at [MAX_BODY_CHARS].
+fn summarize_body(payload: &[u8], redact: &[&str]) -> String {
+    let summary = match serde_json::from_slice::<Value>(payload) {
+        Ok(mut body) => {
+            redact_fields(&mut body, redact);
+            body.to_string()
+        }
+        Err(_) if payload.is_empty() => String::new(),
+        Err(_) => format!("<{} bytes>", payload.len()),
+    };
+    match summary.char_indices().nth(MAX_BODY_CHARS) {
+        Some((cut, _)) => format!("{}...", &summary[..cut]),
+        None => summary,
+    }
+}
+
+fn redact_fields(value: &mut Value, redact: &[&str]) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                if is_redacted(name, redact) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_fields(field, redact);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                redact_fields(value, redact);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_headers_and_body_fields_ignoring_case() {
+        let redact = ["password"];
+        let headers = HashMap::from([
+            ("Authorization".to_string(), "Bearer secret".to_string()),
+            ("Cookie".to_string(), "session=1".to_string()),
+            ("content-type".to_string(), "application/json".to_string()),
+        ]);
+        assert_eq!(
+            BTreeMap::from([
+                ("Authorization", REDACTED),
+                ("Cookie", REDACTED),
+                ("content-type", "application/json")
+            ]),
+            redact_map(&headers, &redact)
+        );
+        assert_eq!(REDACTED, redact_map(&headers, &[])["Cookie"]);
+
+        let body = br#"{"user":"ada","Password":"hunter2","nested":[{"password":1}]}"#;
+        assert_eq!(
+            r#"{"Password":"[REDACTED]","nested":[{"password":"[REDACTED]"}],"user":"ada"}"#,
+            summarize_body(body, &redact)
+        );
+    }
+
+    #[test]
+    fn summarizes_other_bodies_by_size() {
+        assert_eq!("<3 bytes>", summarize_body(&[0, 159, 146], &[]));
+        assert_eq!("", summarize_body(&[], &[]));
+
+        let long = format!("\"{}\"", "é".repeat(2 * MAX_BODY_CHARS));
+        let summary = summarize_body(long.as_bytes(), &[]);
+        assert_eq!(MAX_BODY_CHARS + 3, summary.chars().count());
+        assert!(summary.ends_with("..."));
+    }
+}
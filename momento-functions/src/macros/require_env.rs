@@ -0,0 +1,120 @@
+use std::sync::Mutex;
+
+/// Check, when the Function instance starts, that environment variables are set.
+///
+/// Without this, a missing API key usually shows up as an empty string passed deep into an
+/// HTTP call, and the error that comes back says nothing about configuration. With it, each
+/// variable that is unset or empty is reported in one structured log line when the instance
+/// starts, and every request is answered with a 500 that names them, before your handler
+/// runs. Spawn and cache event Functions log the message and skip the handler.
+///
+/// You can use this more than once; the variables of every use are checked.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// momento_functions::require_env!("TURBOPUFFER_API_KEY", "OPENAI_API_KEY");
+///
+/// momento_functions::post!(search);
+/// fn search(_payload: Vec<u8>) -> String {
+///     // Both variables are set and non-empty if this runs.
+///     let api_key = std::env::var("TURBOPUFFER_API_KEY").unwrap_or_default();
+///     format!("using a key of {} characters", api_key.len())
+/// }
+/// ```
+#[macro_export]
+macro_rules! require_env {
+    ($($variable: literal),+ $(,)?) => {
+        const _: () = {
+            // Runs with the module's static constructors, like init!.
+            #[used]
+            #[unsafe(link_section = ".init_array")]
+            static __MOMENTO_FUNCTIONS_REQUIRE_ENV: extern "C" fn() = {
+                extern "C" fn __momento_functions_require_env() {
+                    momento_functions::require_env_template(&[$($variable),+])
+                }
+                __momento_functions_require_env
+            };
+        };
+    };
+}
+
+static MISSING: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// An internal helper for the require_env! macro.
+#[doc(hidden)]
+pub fn require_env_template(variables: &[&'static str]) {
+    let missing = missing_variables(variables, |variable| std::env::var(variable).ok());
+    if missing.is_empty() {
+        return;
+    }
+    let mut all_missing = MISSING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    all_missing.extend(missing);
+    // This runs from `.init_array`, before any logger can be set up, so it writes to stderr
+    // directly. Handlers report the same problem again when they are called.
+    eprintln!(
+        "{}",
+        serde_json::json!({
+            "level": "ERROR",
+            "message": missing_message(&all_missing),
+            "missing_environment_variables": *all_missing,
+        })
+    );
+}
+
+/// An internal helper for the handler macros.
+///
+/// Returns the message to report instead of running the handler when a variable named by
+/// require_env! is missing.
+#[doc(hidden)]
+pub fn check_required_env() -> Result<(), String> {
+    let missing = MISSING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(missing_message(&missing))
+    }
+}
+
+fn missing_variables(
+    variables: &[&'static str],
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Vec<&'static str> {
+    variables
+        .iter()
+        .copied()
+        .filter(|variable| lookup(variable).is_none_or(|value| value.is_empty()))
+        .collect()
+}
+
+fn missing_message(missing: &[&str]) -> String {
+    format!(
+        "This Function is missing required environment variables: {}. Set them in the \
+         Function's configuration and redeploy.",
+        missing.join(", ")
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_and_empty_variables_are_missing() {
+        let lookup = |variable: &str| match variable {
+            "SET" => Some("value".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(
+            vec!["EMPTY", "UNSET"],
+            missing_variables(&["SET", "EMPTY", "UNSET"], lookup)
+        );
+        assert!(missing_variables(&["SET"], lookup).is_empty());
+        assert!(missing_message(&["A", "B"]).contains("variables: A, B."));
+    }
+}
@@ -0,0 +1,218 @@
+//! Per-item results for bulk endpoints
+//!
+//! A bulk endpoint that fails the whole request when one item fails makes the caller resend
+//! everything, including the items that went through. [MultiStatus] collects a result for
+//! each item instead and responds with all of them, so the caller can retry only the
+//! failures.
+
+use std::fmt::Display;
+
+use momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Response;
+use serde::Serialize;
+
+use crate::{IntoWebResponse, WebResponse};
+
+/// The result of one item in a [MultiStatus].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ItemStatus {
+    /// The item's id, as the caller knows it.
+    pub id: String,
+    /// An HTTP status for the item alone.
+    pub status: u16,
+    /// Why the item failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ItemStatus {
+    /// Whether the item succeeded.
+    pub fn is_success(&self) -> bool {
+        self.status < 400
+    }
+}
+
+/// Collects a result for each item of a bulk request and responds with all of them.
+///
+/// The response is `200 OK` when every item succeeded and `207 Multi-Status` otherwise, with
+/// a JSON body:
+/// ```json
+/// {
+///     "succeeded": 1,
+///     "failed": 1,
+///     "results": [
+///         { "id": "a", "status": 200 },
+///         { "id": "b", "status": 502, "error": "upstream timed out" }
+///     ]
+/// }
+/// ```
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions::MultiStatus;
+/// use momento_functions_host::encoding::Json;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Document {
+///     id: String,
+///     text: String,
+/// }
+///
+/// fn index(documents: &[Document]) -> Result<(), String> {
+///     Ok(())
+/// }
+///
+/// momento_functions::post!(index_documents);
+/// fn index_documents(Json(documents): Json<Vec<Document>>) -> MultiStatus {
+///     let mut results = MultiStatus::new();
+///     for chunk in documents.chunks(100) {
+///         let ids = chunk.iter().map(|document| document.id.clone());
+///         match index(chunk) {
+///             Ok(()) => results.succeeded_all(ids),
+///             Err(reason) => results.failed_all(ids, 502, reason),
+///         }
+///     }
+///     results
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiStatus {
+    results: Vec<ItemStatus>,
+}
+
+impl MultiStatus {
+    /// No results yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `id` succeeded.
+    pub fn succeeded(&mut self, id: impl Into<String>) {
+        self.push(id, 200, None::<String>);
+    }
+
+    /// Record that `id` failed with `status`, because of `reason`.
+    pub fn failed(&mut self, id: impl Into<String>, status: u16, reason: impl Display) {
+        self.push(id, status, Some(reason));
+    }
+
+    /// Record that `id` succeeded or, with status 500, failed.
+    pub fn record<T, E: Display>(&mut self, id: impl Into<String>, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.succeeded(id),
+            Err(e) => self.failed(id, 500, e),
+        }
+    }
+
+    /// Record that every item in `ids` succeeded.
+    pub fn succeeded_all(&mut self, ids: impl IntoIterator<Item = impl Into<String>>) {
+        for id in ids {
+            self.succeeded(id);
+        }
+    }
+
+    /// Record that every item in `ids` failed the same way, as when a whole chunk is
+    /// rejected.
+    pub fn failed_all(
+        &mut self,
+        ids: impl IntoIterator<Item = impl Into<String>>,
+        status: u16,
+        reason: impl Display,
+    ) {
+        let reason = reason.to_string();
+        for id in ids {
+            self.failed(id, status, &reason);
+        }
+    }
+
+    /// The results, in the order they were recorded.
+    pub fn results(&self) -> &[ItemStatus] {
+        &self.results
+    }
+
+    /// How many items succeeded.
+    pub fn succeeded_count(&self) -> usize {
+        self.results.iter().filter(|item| item.is_success()).count()
+    }
+
+    /// How many items failed.
+    pub fn failed_count(&self) -> usize {
+        self.results.len() - self.succeeded_count()
+    }
+
+    /// The response: `200` when nothing failed and `207` otherwise, with the results as a
+    /// JSON body.
+    pub fn into_web_response(self) -> WebResponse {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            succeeded: usize,
+            failed: usize,
+            results: &'a [ItemStatus],
+        }
+        let failed = self.failed_count();
+        let body = Body {
+            succeeded: self.results.len() - failed,
+            failed,
+            results: &self.results,
+        };
+        WebResponse {
+            status: if failed == 0 { 200 } else { 207 },
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            // Serializing strings and numbers cannot fail.
+            body: serde_json::to_vec(&body).unwrap_or_default(),
+        }
+    }
+
+    fn push(&mut self, id: impl Into<String>, status: u16, error: Option<impl Display>) {
+        self.results.push(ItemStatus {
+            id: id.into(),
+            status,
+            error: error.map(|e| e.to_string()),
+        });
+    }
+}
+
+impl IntoWebResponse for MultiStatus {
+    fn response(self) -> Response {
+        self.into_web_response().response()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_failures_are_207_with_each_result() {
+        let mut results = MultiStatus::new();
+        results.succeeded("a");
+        results.record("b", &Err::<(), _>("disk full"));
+        results.failed_all(["c", "d"], 502, "upstream timed out");
+        assert_eq!((1, 3), (results.succeeded_count(), results.failed_count()));
+
+        let response = results.into_web_response();
+        assert_eq!(207, response.status);
+        let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "succeeded": 1,
+                "failed": 3,
+                "results": [
+                    { "id": "a", "status": 200 },
+                    { "id": "b", "status": 500, "error": "disk full" },
+                    { "id": "c", "status": 502, "error": "upstream timed out" },
+                    { "id": "d", "status": 502, "error": "upstream timed out" },
+                ],
+            }),
+            body
+        );
+    }
+
+    #[test]
+    fn complete_success_is_200() {
+        let mut results = MultiStatus::new();
+        results.succeeded_all(["a", "b"]);
+        assert_eq!(200, results.into_web_response().status);
+        assert_eq!(200, MultiStatus::new().into_web_response().status);
+    }
+}
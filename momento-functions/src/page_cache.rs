@@ -0,0 +1,477 @@
+//! Full-page response caching
+//!
+//! [PageCache] stores whole responses, with their status, headers, and body, so a Web Function
+//! in front of a slow origin only renders a page once per ttl. With stale-while-revalidate,
+//! an expired page is served immediately while a spawned Function renders a fresh copy.
+
+use std::time::{Duration, SystemTime};
+
+use momento_functions_host::cache::{self, SetIfCondition};
+use momento_functions_host::encoding::Json;
+use momento_functions_host::http::encode_component;
+use momento_functions_host::web_extensions::{FunctionEnvironment, headers, query_parameters};
+use serde::{Deserialize, Serialize};
+
+use crate::{WebResponse, WebResult};
+
+/// How long one instance holds the right to refresh a stale page, so a burst of requests for
+/// it spawns one refresh rather than one each.
+const REFRESH_LOCK_TTL: Duration = Duration::from_secs(30);
+
+/// Statuses that are cached. These are the responses HTTP caches may store by default.
+const CACHEABLE_STATUSES: &[u16] = &[200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+const ENTRY_VERSION: u8 = 1;
+
+/// The request a cached page is keyed by, and that a refresh renders again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    /// The HTTP method, `GET` or `HEAD`.
+    pub method: String,
+    /// The invocation path, relative to the Function.
+    pub path: String,
+    /// The query parameters, sorted by name.
+    pub query: Vec<(String, String)>,
+    /// The values of the cache's vary headers, in the order they were configured. Headers the
+    /// request did not send have empty values.
+    pub headers: Vec<(String, String)>,
+}
+
+/// Caches entire responses keyed by method, path, query, and a chosen set of request headers.
+///
+/// Only `GET` and `HEAD` requests are cached, and only responses with a cacheable status and
+/// without `set-cookie` or a `cache-control` of `no-store` or `private`. Cache errors never fail
+/// a request: the page is rendered as if it were not cached. Responses carry an `x-cache`
+/// header of `HIT`, `STALE`, or `MISS`, and cached ones an `age` header.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions::{PageCache, PageRequest, WebResponse, WebResult};
+/// use std::time::Duration;
+///
+/// fn cache() -> PageCache {
+///     PageCache::new(Duration::from_secs(60))
+///         .stale_while_revalidate(Duration::from_secs(600), "refresh-pages")
+///         .vary("accept-language")
+/// }
+///
+/// fn render(request: &PageRequest) -> WebResult<WebResponse> {
+///     let html = format!("<h1>{}</h1>", request.path);
+///     Ok(WebResponse::new()
+///         .header("content-type", "text/html")
+///         .with_body(html)?)
+/// }
+///
+/// momento_functions::post!(page);
+/// fn page(_payload: Vec<u8>) -> WebResponse {
+///     cache().serve(render)
+/// }
+/// ```
+///
+/// The Spawn Function named `refresh-pages` renders stale pages again:
+/// ```rust,no_run
+/// # use momento_functions::{PageCache, PageRequest, WebResponse, WebResult};
+/// # use std::time::Duration;
+/// # fn cache() -> PageCache { PageCache::new(Duration::from_secs(60)) }
+/// # fn render(request: &PageRequest) -> WebResult<WebResponse> { Ok(WebResponse::new()) }
+/// momento_functions::spawn!(refresh, PageRequest);
+/// fn refresh(request: PageRequest) {
+///     if let Err(e) = cache().refresh(&request, render) {
+///         eprintln!("failed to refresh {}: {e}", request.path);
+///     }
+/// }
+/// ```
+pub struct PageCache {
+    ttl: Duration,
+    stale_while_revalidate: Option<(Duration, String)>,
+    vary: Vec<String>,
+    key_prefix: String,
+}
+
+/// A page could not be rendered or stored by [PageCache::refresh].
+#[derive(Debug, thiserror::Error)]
+pub enum PageCacheError {
+    /// Rendering the page returned an error.
+    #[error("failed to render the page: {0}")]
+    Render(String),
+    /// The rendered page could not be stored.
+    #[error(transparent)]
+    Store(#[from] cache::CacheSetError<std::convert::Infallible>),
+}
+
+impl PageCache {
+    /// Cache pages for `ttl`. Pages are stored under `page:` keys unless you change it.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            stale_while_revalidate: None,
+            vary: Vec::new(),
+            key_prefix: "page:".to_string(),
+        }
+    }
+
+    /// Keep serving pages for `window` after they expire, while spawning `refresh_function`
+    /// with the [PageRequest] to render them again. That Function should call
+    /// [PageCache::refresh] with the same configuration.
+    pub fn stale_while_revalidate(
+        mut self,
+        window: Duration,
+        refresh_function: impl Into<String>,
+    ) -> Self {
+        self.stale_while_revalidate = Some((window, refresh_function.into()));
+        self
+    }
+
+    /// Cache a separate copy of each page for each value of this request header, such as
+    /// `accept-language` or `accept-encoding`.
+    pub fn vary(mut self, header: impl Into<String>) -> Self {
+        self.vary.push(header.into().to_ascii_lowercase());
+        self
+    }
+
+    /// The prefix of the cache keys pages are stored under.
+    pub fn key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// Respond to the current request from the cache, rendering and caching the page when it
+    /// is missing.
+    pub fn serve(
+        &self,
+        render: impl FnOnce(&PageRequest) -> WebResult<WebResponse>,
+    ) -> WebResponse {
+        let request = self.current_request();
+        if request.method != "GET" && request.method != "HEAD" {
+            return rendered(&request, render);
+        }
+        let key = self.cache_key(&request);
+        let now = epoch_millis(SystemTime::now());
+        match cache::get::<Vec<u8>>(&key) {
+            Ok(Some(entry)) => match CachedPage::decode(&entry) {
+                Some(page) => {
+                    let age = Duration::from_millis(now.saturating_sub(page.stored_at));
+                    if age < self.ttl {
+                        return page.into_response("HIT", age);
+                    }
+                    if let Some((window, refresh_function)) = &self.stale_while_revalidate
+                        && age < self.ttl + *window
+                    {
+                        self.spawn_refresh(&key, refresh_function, &request);
+                        return page.into_response("STALE", age);
+                    }
+                }
+                None => log::warn!("ignoring unreadable cached page {key}"),
+            },
+            Ok(None) => {}
+            Err(e) => log::warn!("failed to read cached page {key}: {e}"),
+        }
+
+        let response = rendered(&request, render);
+        if is_cacheable(&response) {
+            let page = CachedPage::from_response(&response, now);
+            if let Err(e) = cache::set(&key, page.encode(), self.stored_ttl()) {
+                log::warn!("failed to cache page {key}: {e}");
+            }
+        }
+        response.header("x-cache", "MISS")
+    }
+
+    /// Render a page again and store it, for the refresh Function spawned by a stale hit.
+    ///
+    /// Responses that are not cacheable are not stored, and the stale page keeps being served
+    /// until it falls out of the stale-while-revalidate window.
+    pub fn refresh(
+        &self,
+        request: &PageRequest,
+        render: impl FnOnce(&PageRequest) -> WebResult<WebResponse>,
+    ) -> Result<(), PageCacheError> {
+        let key = self.cache_key(request);
+        let response = render(request).map_err(|e| PageCacheError::Render(e.to_string()))?;
+        if is_cacheable(&response) {
+            let page = CachedPage::from_response(&response, epoch_millis(SystemTime::now()));
+            cache::set(&key, page.encode(), self.stored_ttl())?;
+        }
+        let _ = cache::delete(refresh_lock_key(&key));
+        Ok(())
+    }
+
+    fn current_request(&self) -> PageRequest {
+        let environment = FunctionEnvironment::get_function_environment();
+        let mut query: Vec<(String, String)> = query_parameters()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        query.sort();
+        let headers = self
+            .vary
+            .iter()
+            .map(|vary| {
+                let value = headers()
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(vary))
+                    .map(|(_, value)| value.clone())
+                    .unwrap_or_default();
+                (vary.clone(), value)
+            })
+            .collect();
+        PageRequest {
+            method: environment.http_method().to_ascii_uppercase(),
+            path: environment.http_path().to_string(),
+            query,
+            headers,
+        }
+    }
+
+    /// The key for a request's page. Everything taken from the request is percent-encoded,
+    /// so a `&`, `=`, or newline inside a value cannot make two requests share a key.
+    fn cache_key(&self, request: &PageRequest) -> String {
+        let mut key = format!(
+            "{}{} {}",
+            self.key_prefix,
+            // HEAD is answered from the GET page, without its body.
+            if request.method == "HEAD" {
+                "GET"
+            } else {
+                &request.method
+            },
+            encode_component(&request.path).replace("%2F", "/")
+        );
+        for (index, (name, value)) in request.query.iter().enumerate() {
+            key.push(if index == 0 { '?' } else { '&' });
+            key.push_str(&format!(
+                "{}={}",
+                encode_component(name),
+                encode_component(value)
+            ));
+        }
+        for (name, value) in &request.headers {
+            key.push_str(&format!("\n{name}: {}", encode_component(value)));
+        }
+        key
+    }
+
+    fn stored_ttl(&self) -> Duration {
+        match &self.stale_while_revalidate {
+            Some((window, _)) => self.ttl + *window,
+            None => self.ttl,
+        }
+    }
+
+    fn spawn_refresh(&self, key: &str, refresh_function: &str, request: &PageRequest) {
+        match cache::set_if(
+            refresh_lock_key(key),
+            b"1".as_slice(),
+            REFRESH_LOCK_TTL,
+            SetIfCondition::Absent,
+        ) {
+            Ok(cache::SetIfResult::Stored) => {
+                if let Err(e) = momento_functions_host::spawn(refresh_function, Json(request)) {
+                    log::warn!("failed to spawn {refresh_function} to refresh {key}: {e}");
+                }
+            }
+            Ok(cache::SetIfResult::NotStored) => {}
+            Err(e) => log::warn!("failed to lock {key} for refresh: {e}"),
+        }
+    }
+}
+
+fn refresh_lock_key(key: &str) -> String {
+    format!("{key}\nrefreshing")
+}
+
+fn rendered(
+    request: &PageRequest,
+    render: impl FnOnce(&PageRequest) -> WebResult<WebResponse>,
+) -> WebResponse {
+    match render(request) {
+        Ok(response) => response,
+        Err(e) => e.response,
+    }
+}
+
+fn is_cacheable(response: &WebResponse) -> bool {
+    CACHEABLE_STATUSES.contains(&response.status)
+        && !response.headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("set-cookie")
+                || (name.eq_ignore_ascii_case("cache-control")
+                    && value.split(',').any(|directive| {
+                        let directive = directive.trim();
+                        directive.eq_ignore_ascii_case("no-store")
+                            || directive.eq_ignore_ascii_case("private")
+                    }))
+        })
+}
+
+pub(crate) fn epoch_millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// A response as it is stored in the cache.
+///
+/// The layout is a version byte, the time it was stored in epoch milliseconds, the status, the
+/// header count, each header's name and value prefixed by their lengths, and then the body.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct CachedPage {
+    pub(crate) stored_at: u64,
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl CachedPage {
+    pub(crate) fn from_response(response: &WebResponse, stored_at: u64) -> Self {
+        Self {
+            stored_at,
+            status: response.status,
+            headers: response.headers.clone(),
+            body: response.body.clone(),
+        }
+    }
+
+    fn into_response(self, state: &str, age: Duration) -> WebResponse {
+        let method = FunctionEnvironment::get_function_environment().http_method();
+        let body = if method.eq_ignore_ascii_case("HEAD") {
+            Vec::new()
+        } else {
+            self.body
+        };
+        WebResponse {
+            status: self.status,
+            headers: self.headers,
+            body,
+        }
+        .age(age)
+        .header("x-cache", state)
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut entry = Vec::with_capacity(self.body.len() + 64);
+        entry.push(ENTRY_VERSION);
+        entry.extend_from_slice(&self.stored_at.to_be_bytes());
+        entry.extend_from_slice(&self.status.to_be_bytes());
+        entry.extend_from_slice(&(self.headers.len() as u32).to_be_bytes());
+        for (name, value) in &self.headers {
+            for part in [name, value] {
+                entry.extend_from_slice(&(part.len() as u32).to_be_bytes());
+                entry.extend_from_slice(part.as_bytes());
+            }
+        }
+        entry.extend_from_slice(&self.body);
+        entry
+    }
+
+    pub(crate) fn decode(entry: &[u8]) -> Option<Self> {
+        let (&version, mut rest) = entry.split_first()?;
+        if version != ENTRY_VERSION {
+            return None;
+        }
+        let stored_at = u64::from_be_bytes(take(&mut rest, 8)?.try_into().ok()?);
+        let status = u16::from_be_bytes(take(&mut rest, 2)?.try_into().ok()?);
+        let count = u32::from_be_bytes(take(&mut rest, 4)?.try_into().ok()?);
+        let mut headers = Vec::new();
+        for _ in 0..count {
+            let mut parts = [String::new(), String::new()];
+            for part in &mut parts {
+                let length = u32::from_be_bytes(take(&mut rest, 4)?.try_into().ok()?);
+                *part = String::from_utf8(take(&mut rest, length as usize)?.to_vec()).ok()?;
+            }
+            let [name, value] = parts;
+            headers.push((name, value));
+        }
+        Some(Self {
+            stored_at,
+            status,
+            headers,
+            body: rest.to_vec(),
+        })
+    }
+}
+
+fn take<'a>(rest: &mut &'a [u8], length: usize) -> Option<&'a [u8]> {
+    if rest.len() < length {
+        return None;
+    }
+    let (taken, remaining) = rest.split_at(length);
+    *rest = remaining;
+    Some(taken)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_pages_round_trip() {
+        let page = CachedPage {
+            stored_at: 1_700_000_000_000,
+            status: 404,
+            headers: vec![
+                ("content-type".to_string(), "text/html".to_string()),
+                ("vary".to_string(), "accept-language".to_string()),
+            ],
+            body: b"<h1>not found</h1>".to_vec(),
+        };
+        let entry = page.encode();
+        assert_eq!(Some(page), CachedPage::decode(&entry));
+        assert_eq!(None, CachedPage::decode(&entry[..20]));
+        assert_eq!(None, CachedPage::decode(&[2]));
+    }
+
+    #[test]
+    fn keys_include_query_and_vary_headers() {
+        let cache = PageCache::new(Duration::from_secs(60)).vary("Accept-Language");
+        let request = PageRequest {
+            method: "HEAD".to_string(),
+            path: "/docs".to_string(),
+            query: vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string()),
+            ],
+            headers: vec![("accept-language".to_string(), "en".to_string())],
+        };
+        assert_eq!(
+            "page:GET /docs?a=1&b=2\naccept-language: en",
+            cache.cache_key(&request)
+        );
+
+        let smuggled = PageRequest {
+            method: "GET".to_string(),
+            path: "/docs".to_string(),
+            query: vec![("a".to_string(), "1&b=2".to_string())],
+            headers: vec![("accept-language".to_string(), "en".to_string())],
+        };
+        assert_eq!(
+            "page:GET /docs?a=1%26b%3D2\naccept-language: en",
+            cache.cache_key(&smuggled)
+        );
+        let split = PageRequest {
+            query: Vec::new(),
+            headers: vec![(
+                "accept-language".to_string(),
+                "en\naccept-language: fr".to_string(),
+            )],
+            ..smuggled
+        };
+        assert_eq!(
+            "page:GET /docs\naccept-language: en%0Aaccept-language%3A%20fr",
+            cache.cache_key(&split)
+        );
+    }
+
+    #[test]
+    fn private_and_failed_responses_are_not_cached() {
+        assert!(is_cacheable(&WebResponse::new()));
+        assert!(is_cacheable(&WebResponse::new().with_status(404)));
+        assert!(!is_cacheable(&WebResponse::new().with_status(500)));
+        assert!(!is_cacheable(
+            &WebResponse::new().header("Cache-Control", "max-age=60, private")
+        ));
+        assert!(!is_cacheable(
+            &WebResponse::new().header("set-cookie", "session=1")
+        ));
+    }
+}
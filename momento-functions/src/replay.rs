@@ -0,0 +1,330 @@
+//! Capture the requests of failed invocations, so they can be sent again
+//!
+//! A failure that only happens for one caller's payload is hard to reproduce from logs. With
+//! [capture_replays] turned on, web Functions made with [post!](crate::post) store the full
+//! request of each failed invocation: its method, path, headers, query parameters, and body.
+//! It is stored as a JSON [Replay] in the cache or in S3, keyed by the invocation id, so you
+//! can find it from the invocation's logs and re-send the exact payload to a dev deployment.
+//!
+//! Credentials in the `authorization`, `cookie`, and `proxy-authorization` headers are
+//! redacted unless you choose otherwise with [ReplayCapture::redact].
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use momento_functions::replay::{self, ReplayCapture};
+//!
+//! momento_functions::init!(setup);
+//! fn setup() {
+//!     // Stored at `replay:{invocation id}` for a day.
+//!     replay::capture_replays(ReplayCapture::cache(Duration::from_secs(24 * 60 * 60)));
+//! }
+//!
+//! momento_functions::post!(handle);
+//! fn handle(payload: Vec<u8>) -> momento_functions::WebResponse {
+//!     momento_functions::WebResponse::new().with_status(if payload.is_empty() { 500 } else { 204 })
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::Engine;
+#[cfg(feature = "aws")]
+use momento_functions_host::aws::s3::S3Client;
+use momento_functions_host::cache;
+use momento_functions_host::web_extensions::{FunctionEnvironment, headers, query_parameters};
+use momento_functions_wit::function_web::exports::momento::functions::guest_function_web;
+use serde::{Deserialize, Serialize};
+
+/// What redacted headers and query parameters are stored as.
+const REDACTED: &str = "[REDACTED]";
+
+static CAPTURE: Mutex<Option<ReplayCapture>> = Mutex::new(None);
+
+/// Where replays are stored.
+enum ReplayStore {
+    Cache {
+        ttl: Duration,
+    },
+    #[cfg(feature = "aws")]
+    S3 {
+        client: S3Client,
+        bucket: String,
+    },
+}
+
+/// Which failed invocations to capture, and where to store them.
+pub struct ReplayCapture {
+    store: ReplayStore,
+    prefix: String,
+    statuses: RangeInclusive<u16>,
+    redact: Vec<String>,
+}
+
+impl ReplayCapture {
+    /// Store replays in the Function's cache at `replay:{invocation id}`, for `ttl`.
+    pub fn cache(ttl: Duration) -> Self {
+        Self::new(ReplayStore::Cache { ttl }, "replay:")
+    }
+
+    /// Store replays in `bucket` at `replays/{invocation id}.json`.
+    ///
+    /// Give the bucket a lifecycle rule to expire old replays.
+    #[cfg(feature = "aws")]
+    pub fn s3(client: S3Client, bucket: impl Into<String>) -> Self {
+        Self::new(
+            ReplayStore::S3 {
+                client,
+                bucket: bucket.into(),
+            },
+            "replays/",
+        )
+    }
+
+    fn new(store: ReplayStore, prefix: &str) -> Self {
+        Self {
+            store,
+            prefix: prefix.to_string(),
+            statuses: 500..=599,
+            redact: ["authorization", "cookie", "proxy-authorization"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+
+    /// Put replays under `prefix` instead, like `replays:checkout:`.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Capture invocations that respond with these statuses. Defaults to `500..=599`.
+    pub fn statuses(mut self, statuses: RangeInclusive<u16>) -> Self {
+        self.statuses = statuses;
+        self
+    }
+
+    /// Store the values of these headers and query parameters as `[REDACTED]`, ignoring
+    /// case, in place of the default credential headers.
+    pub fn redact(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.redact = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn key(&self, invocation_id: &str) -> String {
+        match self.store {
+            ReplayStore::Cache { .. } => format!("{}{invocation_id}", self.prefix),
+            #[cfg(feature = "aws")]
+            ReplayStore::S3 { .. } => format!("{}{invocation_id}.json", self.prefix),
+        }
+    }
+
+    fn store(&self, key: &str, replay: Vec<u8>) -> Result<(), String> {
+        match &self.store {
+            ReplayStore::Cache { ttl } => cache::set(key, replay, *ttl).map_err(|e| e.to_string()),
+            #[cfg(feature = "aws")]
+            ReplayStore::S3 { client, bucket } => client
+                .put_with_options(
+                    bucket.as_str(),
+                    key,
+                    replay,
+                    momento_functions_host::aws::s3::ObjectOptions {
+                        content_type: Some("application/json".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    fn redact_map(&self, values: &HashMap<String, String>) -> BTreeMap<String, String> {
+        values
+            .iter()
+            .map(|(name, value)| {
+                let redacted = self
+                    .redact
+                    .iter()
+                    .any(|redacted| redacted.eq_ignore_ascii_case(name));
+                let value = if redacted { REDACTED } else { value };
+                (name.clone(), value.to_string())
+            })
+            .collect()
+    }
+}
+
+/// A captured request.
+///
+/// It is stored as JSON. The body is in `body` when it is UTF-8, and base64-encoded in
+/// `body_base64` otherwise, so `jq -r .body` gives back a JSON or text payload as it was sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    /// The failed invocation's id.
+    pub invocation_id: String,
+    /// The status the Function responded with.
+    pub status: u16,
+    /// The request's HTTP method.
+    pub method: String,
+    /// The request's path, relative to the Function.
+    pub path: String,
+    /// The request's headers.
+    pub headers: BTreeMap<String, String>,
+    /// The request's query parameters.
+    pub query_parameters: BTreeMap<String, String>,
+    /// The request's body, when it is UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// The request's body, base64-encoded, when it is not UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_base64: Option<String>,
+}
+
+impl Replay {
+    /// The request's body as it was sent.
+    pub fn body(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        match (&self.body, &self.body_base64) {
+            (Some(body), _) => Ok(body.clone().into_bytes()),
+            (None, Some(encoded)) => base64::engine::general_purpose::STANDARD.decode(encoded),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Capture failed invocations from now on, for as long as the Function instance lives.
+///
+/// Turn it on in an [init!](crate::init) function, so it is on before the first request.
+pub fn capture_replays(capture: ReplayCapture) {
+    *lock() = Some(capture);
+}
+
+/// Stop capturing failed invocations.
+pub fn stop_capturing_replays() {
+    *lock() = None;
+}
+
+/// Whether requests need to be kept for capturing.
+pub(crate) fn capturing() -> bool {
+    lock().is_some()
+}
+
+/// Store the request if the response is a failure the capture is looking for. Failing to
+/// store it is logged, and leaves the response alone.
+pub(crate) fn capture(payload: Vec<u8>, response: &guest_function_web::Response) {
+    let capture = lock();
+    let Some(capture) = capture.as_ref() else {
+        return;
+    };
+    if !capture.statuses.contains(&response.status) {
+        return;
+    }
+    // The environment is captured once per instance, but the invocation id changes with every
+    // invocation, so read it fresh.
+    let invocation_id = std::env::var("__INVOCATION_ID").unwrap_or_default();
+    let environment = FunctionEnvironment::get_function_environment();
+    let (body, body_base64) = encode_body(payload);
+    let replay = Replay {
+        invocation_id: invocation_id.clone(),
+        status: response.status,
+        method: environment.http_method().to_string(),
+        path: environment.http_path().to_string(),
+        headers: capture.redact_map(headers()),
+        query_parameters: capture.redact_map(query_parameters()),
+        body,
+        body_base64,
+    };
+    let key = capture.key(&invocation_id);
+    // Serializing strings cannot fail.
+    match capture.store(&key, serde_json::to_vec(&replay).unwrap_or_default()) {
+        Ok(()) => log::info!("captured the request of failed invocation {invocation_id} at {key}"),
+        Err(e) => {
+            log::warn!("failed to capture the request of failed invocation {invocation_id}: {e}")
+        }
+    }
+}
+
+/// A body as UTF-8 text, or base64-encoded when it is not UTF-8.
+fn encode_body(payload: Vec<u8>) -> (Option<String>, Option<String>) {
+    match String::from_utf8(payload) {
+        Ok(body) => (Some(body), None),
+        Err(e) => (
+            None,
+            Some(base64::engine::general_purpose::STANDARD.encode(e.into_bytes())),
+        ),
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<ReplayCapture>> {
+    CAPTURE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_are_redacted() {
+        let capture = ReplayCapture::cache(Duration::from_secs(60)).prefix("replays:checkout:");
+        assert_eq!("replays:checkout:inv-1", capture.key("inv-1"));
+
+        let headers = HashMap::from([
+            ("Authorization".to_string(), "Bearer secret".to_string()),
+            ("content-type".to_string(), "application/json".to_string()),
+        ]);
+        assert_eq!(
+            BTreeMap::from([
+                ("Authorization".to_string(), REDACTED.to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+            ]),
+            capture.redact_map(&headers)
+        );
+        let query = HashMap::from([("Cart".to_string(), "42".to_string())]);
+        assert_eq!(
+            REDACTED,
+            capture.redact(["cart"]).redact_map(&query)["Cart"]
+        );
+    }
+
+    #[test]
+    fn bodies_round_trip() {
+        let (body, body_base64) = encode_body(br#"{"sku":"abc"}"#.to_vec());
+        let replay = Replay {
+            invocation_id: "inv-1".to_string(),
+            status: 500,
+            method: "POST".to_string(),
+            path: "/checkout".to_string(),
+            headers: BTreeMap::new(),
+            query_parameters: BTreeMap::new(),
+            body,
+            body_base64,
+        };
+        assert_eq!(
+            serde_json::json!({
+                "invocation_id": "inv-1",
+                "status": 500,
+                "method": "POST",
+                "path": "/checkout",
+                "headers": {},
+                "query_parameters": {},
+                "body": r#"{"sku":"abc"}"#,
+            }),
+            serde_json::to_value(&replay).unwrap()
+        );
+
+        let (body, body_base64) = encode_body(vec![0, 159, 146]);
+        let binary = Replay {
+            body,
+            body_base64,
+            ..replay
+        };
+        assert_eq!(Some("AJ+S"), binary.body_base64.as_deref());
+        assert_eq!(vec![0, 159, 146], binary.body().unwrap());
+        let stored: Replay = serde_json::from_slice(&serde_json::to_vec(&binary).unwrap()).unwrap();
+        assert_eq!(binary, stored);
+    }
+}
@@ -1,5 +1,6 @@
 use momento_functions_host::encoding::Encode;
 use momento_functions_host::http;
+use momento_functions_host::signing::{self, SIGNATURE_HEADER, SigningError, SigningKey};
 use momento_functions_wit::function_web::exports::momento::functions::guest_function_web::Response;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
@@ -16,7 +17,7 @@ pub trait IntoWebResponse {
 #[derive(Debug)]
 pub struct WebError {
     source: Option<Box<dyn Error>>,
-    response: WebResponse,
+    pub(crate) response: WebResponse,
 }
 
 impl WebError {
@@ -86,9 +87,9 @@ impl IntoWebResponse for http::Response {
 /// and [WebResponse::with_body] respectfully.
 #[derive(Debug)]
 pub struct WebResponse {
-    status: u16,
-    headers: Vec<(String, String)>,
-    body: Vec<u8>,
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
 }
 
 impl Default for WebResponse {
@@ -119,6 +120,13 @@ impl WebResponse {
         self
     }
 
+    /// Sets a header, removing any others with the same name.
+    pub(crate) fn replace_header(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(key));
+        self.header(key, value)
+    }
+
     /// Overrides the collection of headers for the response.
     pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
         self.headers = headers;
@@ -131,6 +139,29 @@ impl WebResponse {
         self.body = body;
         Ok(self)
     }
+
+    /// Signs the body with `key`, setting the `Momento-Signature` header, so consumers can
+    /// check it with a [SignatureVerifier](momento_functions_host::signing::SignatureVerifier).
+    ///
+    /// Call this after setting the body; changing the body afterward invalidates the signature.
+    ///
+    /// **Examples:**
+    /// ```rust,no_run
+    /// use momento_functions::{WebResponse, WebResult};
+    /// use momento_functions_host::signing::SigningKey;
+    ///
+    /// momento_functions::post!(order_status);
+    /// fn order_status(_payload: Vec<u8>) -> WebResult<WebResponse> {
+    ///     let key = SigningKey::hmac_sha256(std::env::var("WEBHOOK_SECRET").unwrap_or_default());
+    ///     Ok(WebResponse::new()
+    ///         .with_body(r#"{"order":42,"status":"shipped"}"#)?
+    ///         .signed(&key)?)
+    /// }
+    /// ```
+    pub fn signed(self, key: &SigningKey) -> Result<Self, SigningError> {
+        let signature = signing::sign(key, &self.body, std::time::SystemTime::now())?;
+        Ok(self.replace_header(SIGNATURE_HEADER, signature))
+    }
 }
 
 impl IntoWebResponse for WebResponse {
@@ -0,0 +1,468 @@
+//! HTML templates with a typed context, checked when your Function compiles
+//!
+//! A [Template] is written against a context type, declared with [template_context!]. Every
+//! placeholder in the template, and in the partials it includes, must name a field of that
+//! context, or the [template!] that builds it fails to compile. Renaming a field can't leave a
+//! page quietly rendering a blank where the value used to be.
+//!
+//! Templates use a small part of Mustache's syntax:
+//! - `{{ name }}` writes the field, escaped for HTML.
+//! - `{{& name }}` writes the field as it is, for HTML you have already rendered or sanitized.
+//! - `{{> header }}` includes the partial named `header`, rendered with the same context.
+//! - `{{! note }}` is a comment, and writes nothing.
+//!
+//! Partials let pages share a layout without repeating it. Keep each one in its own file and
+//! include it with `include_str!`, so it is checked along with the template.
+//!
+//! **Examples:**
+//! ```rust,no_run
+//! use momento_functions::WebResponse;
+//! use momento_functions::template::Template;
+//! use momento_functions_host::encoding::Json;
+//!
+//! struct ProductPage {
+//!     title: String,
+//!     name: String,
+//!     price: String,
+//! }
+//! momento_functions::template_context!(ProductPage { title, name, price });
+//!
+//! static PRODUCT: Template<ProductPage> = momento_functions::template!(
+//!     ProductPage,
+//!     "{{> header}}<h1>{{ name }}</h1><p>{{ price }}</p>{{> footer}}",
+//!     partials = {
+//!         "header" => "<html><head><title>{{ title }}</title></head><body>",
+//!         "footer" => "</body></html>",
+//!     },
+//! );
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Request {
+//!     name: String,
+//!     cents: u64,
+//! }
+//!
+//! momento_functions::post!(product);
+//! fn product(Json(request): Json<Request>) -> WebResponse {
+//!     PRODUCT.response(&ProductPage {
+//!         title: format!("{} | Shop", request.name),
+//!         price: format!("${}.{:02}", request.cents / 100, request.cents % 100),
+//!         name: request.name,
+//!     })
+//! }
+//! ```
+
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+use crate::WebResponse;
+
+/// How deeply partials may include other partials. Deeper nesting is reported as a compile
+/// error, which also catches partials that include each other.
+pub const MAX_PARTIAL_DEPTH: usize = 8;
+
+/// A type whose fields can be written into a [Template].
+///
+/// Implement this with [template_context!].
+pub trait TemplateContext {
+    /// The fields a template may name.
+    const FIELDS: &'static [&'static str];
+
+    /// Write the field called `name`, unescaped. Names not in [FIELDS](Self::FIELDS) write
+    /// nothing.
+    fn write_field(&self, name: &str, out: &mut String);
+}
+
+/// Implement [TemplateContext] for a struct, naming the fields templates may use.
+///
+/// Each field must implement [Display](std::fmt::Display). Fields you leave out can't be
+/// named in a template.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// struct Greeting {
+///     name: String,
+///     visits: u32,
+/// }
+/// momento_functions::template_context!(Greeting { name, visits });
+/// ```
+#[macro_export]
+macro_rules! template_context {
+    ($context: ty { $($field: ident),* $(,)? }) => {
+        #[automatically_derived]
+        impl momento_functions::template::TemplateContext for $context {
+            const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn write_field(&self, name: &str, out: &mut String) {
+                use std::fmt::Write as _;
+                match name {
+                    $(stringify!($field) => {
+                        let _ = write!(out, "{}", self.$field);
+                    })*
+                    _ => {}
+                }
+            }
+        }
+    };
+}
+
+/// Build a [Template] for a context type, checking its placeholders when the Function compiles.
+///
+/// Pass the context type, the template source, and optionally the partials it may include.
+/// Sources can be literals or `include_str!` of files next to your code. The result is a
+/// constant, so keep it in a `static`. Compilation fails if a placeholder names a field the
+/// context does not have, a partial is missing, or a tag is not closed.
+///
+/// **Examples:**
+/// ```rust,no_run
+/// use momento_functions::template::Template;
+///
+/// struct Greeting {
+///     name: String,
+/// }
+/// momento_functions::template_context!(Greeting { name });
+///
+/// static GREETING: Template<Greeting> = momento_functions::template!(
+///     Greeting,
+///     "{{> layout}}<p>Hello, {{ name }}!</p>",
+///     partials = {
+///         "layout" => "<!doctype html><title>Welcome, {{ name }}</title>",
+///     },
+/// );
+/// ```
+///
+/// A placeholder for a field the context does not have is an error:
+/// ```rust,compile_fail
+/// use momento_functions::template::Template;
+///
+/// struct Greeting {
+///     name: String,
+/// }
+/// momento_functions::template_context!(Greeting { name });
+///
+/// static GREETING: Template<Greeting> =
+///     momento_functions::template!(Greeting, "<p>Hello, {{ nmae }}!</p>");
+/// ```
+#[macro_export]
+macro_rules! template {
+    ($context: ty, $source: expr $(,)?) => {
+        momento_functions::template!($context, $source, partials = {})
+    };
+    ($context: ty, $source: expr, partials = { $($name: literal => $partial: expr),* $(,)? } $(,)?) => {{
+        const TEMPLATE: momento_functions::template::Template<$context> =
+            momento_functions::template::Template::checked($source, &[$(($name, $partial)),*]);
+        TEMPLATE
+    }};
+}
+
+/// An HTML template that renders values of type `C`.
+///
+/// Build one with [template!].
+pub struct Template<C> {
+    source: &'static str,
+    partials: &'static [(&'static str, &'static str)],
+    context: PhantomData<fn(&C)>,
+}
+
+impl<C: TemplateContext> Template<C> {
+    /// An internal helper for the template! macro.
+    ///
+    /// Panics if the template is invalid, which is a compile error when it runs in a constant.
+    #[doc(hidden)]
+    pub const fn checked(
+        source: &'static str,
+        partials: &'static [(&'static str, &'static str)],
+    ) -> Self {
+        check(source.as_bytes(), C::FIELDS, partials, 0);
+        Self {
+            source,
+            partials,
+            context: PhantomData,
+        }
+    }
+
+    /// Render the template with `context`.
+    pub fn render(&self, context: &C) -> String {
+        let mut out = String::with_capacity(self.source.len());
+        self.render_into(self.source, context, &mut out);
+        out
+    }
+
+    /// A 200 response with the rendered template as an HTML body.
+    pub fn response(&self, context: &C) -> WebResponse {
+        let response = WebResponse::new().header("content-type", "text/html; charset=utf-8");
+        WebResponse {
+            body: self.render(context).into_bytes(),
+            ..response
+        }
+    }
+
+    fn render_into(&self, source: &str, context: &C, out: &mut String) {
+        let mut from = 0;
+        let mut field = String::new();
+        while let Scan::Tag(tag) = next_tag(source.as_bytes(), from) {
+            out.push_str(&source[from..tag.start]);
+            let name = &source[tag.name_start..tag.name_end];
+            match tag.kind {
+                TagKind::Escaped => {
+                    field.clear();
+                    context.write_field(name, &mut field);
+                    push_escaped(out, &field);
+                }
+                TagKind::Raw => context.write_field(name, out),
+                TagKind::Partial => {
+                    if let Some((_, partial)) = self.partials.iter().find(|(n, _)| *n == name) {
+                        self.render_into(partial, context, out);
+                    }
+                }
+                TagKind::Comment => {}
+            }
+            from = tag.end;
+        }
+        out.push_str(&source[from..]);
+    }
+}
+
+impl<C> std::fmt::Debug for Template<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Template")
+            .field("source", &self.source)
+            .field("partials", &self.partials)
+            .finish()
+    }
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => {
+                let _ = out.write_char(c);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    Escaped,
+    Raw,
+    Partial,
+    Comment,
+}
+
+/// A `{{ ... }}` tag, as byte offsets into its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tag {
+    kind: TagKind,
+    /// The first `{`.
+    start: usize,
+    /// Just past the last `}`.
+    end: usize,
+    name_start: usize,
+    name_end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    Tag(Tag),
+    Done,
+    Unterminated,
+}
+
+/// Find the first tag at or after `from`.
+///
+/// This is a `const fn` so [template!] can run it while compiling, which is why it walks
+/// indices rather than using iterators.
+const fn next_tag(source: &[u8], from: usize) -> Scan {
+    let mut start = from;
+    while start + 1 < source.len() {
+        if source[start] == b'{' && source[start + 1] == b'{' {
+            break;
+        }
+        start += 1;
+    }
+    if source.len() <= start + 1 {
+        return Scan::Done;
+    }
+    let mut name_start = start + 2;
+    let kind = if source.len() <= name_start {
+        TagKind::Escaped
+    } else {
+        match source[name_start] {
+            b'&' => TagKind::Raw,
+            b'>' => TagKind::Partial,
+            b'!' => TagKind::Comment,
+            _ => TagKind::Escaped,
+        }
+    };
+    if !matches!(kind, TagKind::Escaped) {
+        name_start += 1;
+    }
+    let mut close = name_start;
+    while close + 1 < source.len() {
+        if source[close] == b'}' && source[close + 1] == b'}' {
+            break;
+        }
+        close += 1;
+    }
+    if source.len() <= close + 1 {
+        return Scan::Unterminated;
+    }
+    let mut name_end = close;
+    while name_start < name_end && source[name_start].is_ascii_whitespace() {
+        name_start += 1;
+    }
+    while name_start < name_end && source[name_end - 1].is_ascii_whitespace() {
+        name_end -= 1;
+    }
+    Scan::Tag(Tag {
+        kind,
+        start,
+        end: close + 2,
+        name_start,
+        name_end,
+    })
+}
+
+/// Panic unless every tag in `source` is closed and names a field or a partial.
+const fn check(source: &[u8], fields: &[&str], partials: &[(&str, &str)], depth: usize) {
+    if MAX_PARTIAL_DEPTH < depth {
+        panic!("template partials are nested too deeply, or include each other");
+    }
+    let mut from = 0;
+    loop {
+        let tag = match next_tag(source, from) {
+            Scan::Tag(tag) => tag,
+            Scan::Done => return,
+            Scan::Unterminated => panic!("template has a `{{{{` without a matching `}}}}`"),
+        };
+        from = tag.end;
+        if matches!(tag.kind, TagKind::Comment) {
+            continue;
+        }
+        if tag.name_start == tag.name_end {
+            panic!("template has an empty `{{{{ }}}}` tag");
+        }
+        if matches!(tag.kind, TagKind::Partial) {
+            let mut i = 0;
+            loop {
+                if partials.len() <= i {
+                    panic!("template includes a partial that was not passed to template!");
+                }
+                if name_is(
+                    source,
+                    tag.name_start,
+                    tag.name_end,
+                    partials[i].0.as_bytes(),
+                ) {
+                    check(partials[i].1.as_bytes(), fields, partials, depth + 1);
+                    break;
+                }
+                i += 1;
+            }
+        } else {
+            let mut i = 0;
+            loop {
+                if fields.len() <= i {
+                    panic!("template names a field that its context does not have");
+                }
+                if name_is(source, tag.name_start, tag.name_end, fields[i].as_bytes()) {
+                    break;
+                }
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Whether `source[start..end]` is `name`.
+const fn name_is(source: &[u8], start: usize, end: usize, name: &[u8]) -> bool {
+    if end - start != name.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < name.len() {
+        if source[start + i] != name[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    struct Page {
+        title: &'static str,
+        body: &'static str,
+        count: u32,
+    }
+
+    impl TemplateContext for Page {
+        const FIELDS: &'static [&'static str] = &["title", "body", "count"];
+
+        fn write_field(&self, name: &str, out: &mut String) {
+            let _ = match name {
+                "title" => write!(out, "{}", self.title),
+                "body" => write!(out, "{}", self.body),
+                "count" => write!(out, "{}", self.count),
+                _ => Ok(()),
+            };
+        }
+    }
+
+    const PAGE: Template<Page> = Template::checked(
+        "{{> layout}}{{! the body is already HTML }}{{& body }}<p>{{count}}</p>",
+        &[
+            ("layout", "<title>{{ title }}</title>{{>nav}}"),
+            ("nav", "<nav>{{title}}</nav>"),
+        ],
+    );
+
+    #[test]
+    fn renders_fields_and_partials() {
+        let page = Page {
+            title: "Tom & Jerry's <show>",
+            body: "<b>hi</b>",
+            count: 3,
+        };
+        assert_eq!(
+            "<title>Tom &amp; Jerry&#39;s &lt;show&gt;</title><nav>Tom &amp; Jerry&#39;s &lt;show&gt;</nav><b>hi</b><p>3</p>",
+            PAGE.render(&page)
+        );
+
+        let response = PAGE.response(&page);
+        assert_eq!(
+            vec![(
+                "content-type".to_string(),
+                "text/html; charset=utf-8".to_string()
+            )],
+            response.headers
+        );
+        assert_eq!(PAGE.render(&page).into_bytes(), response.body);
+    }
+
+    #[test]
+    fn scans_tags() {
+        assert_eq!(Scan::Done, next_tag(b"plain { text }", 0));
+        assert_eq!(Scan::Unterminated, next_tag(b"a {{ name }", 0));
+        assert_eq!(
+            Scan::Tag(Tag {
+                kind: TagKind::Raw,
+                start: 2,
+                end: 14,
+                name_start: 7,
+                name_end: 11,
+            }),
+            next_tag(b"a {{&  name }} b", 0)
+        );
+    }
+}
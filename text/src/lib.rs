@@ -0,0 +1,14 @@
+//! Text utilities for embedding and retrieval pipelines.
+//!
+//! Embedding models limit how much text they take at once, and retrieval works better over
+//! passages than whole documents. This crate splits text into sentences, packs sentences into
+//! overlapping chunks, and, with the `tiktoken` feature, counts, truncates, and chunks by
+//! tokens without splitting a character in half.
+
+mod sentences;
+#[cfg(feature = "tiktoken")]
+mod tokens;
+
+pub use sentences::{chunk_sentences, chunk_sentences_by, split_sentences};
+#[cfg(feature = "tiktoken")]
+pub use tokens::{chunk_sentences_by_tokens, chunk_tokens, count_tokens, truncate_tokens};
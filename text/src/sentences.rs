@@ -0,0 +1,212 @@
+//! Sentence splitting and sentence-aligned chunking.
+
+/// Characters that may close a sentence after its terminal punctuation, like `."` or `?)`.
+const CLOSERS: [char; 6] = ['"', '\'', ')', ']', '\u{201d}', '\u{2019}'];
+
+/// Split text into sentences.
+///
+/// A sentence ends at `.`, `!`, or `?`, along with any closing quotes or brackets, when it is
+/// followed by whitespace or the end of the text. A blank line also ends a sentence, so
+/// headings and list items without punctuation stay separate. This is a heuristic: it does not
+/// know about abbreviations, so `Dr. Smith` is two sentences.
+///
+/// **Examples:**
+/// ```rust
+/// use momento_functions_text::split_sentences;
+///
+/// assert_eq!(
+///     vec!["It scored 3.5 points.", "Really?", "\"Yes.\"", "Next paragraph"],
+///     split_sentences("It scored 3.5 points. Really? \"Yes.\"\n\nNext paragraph"),
+/// );
+/// ```
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let end = match c {
+            '.' | '!' | '?' => {
+                let mut end = index + c.len_utf8();
+                while let Some(&(closer_index, closer)) = chars.peek()
+                    && CLOSERS.contains(&closer)
+                {
+                    end = closer_index + closer.len_utf8();
+                    chars.next();
+                }
+                match chars.peek() {
+                    None => Some(end),
+                    Some(&(_, next)) if next.is_whitespace() => Some(end),
+                    Some(_) => None,
+                }
+            }
+            '\n' => match chars.peek() {
+                Some(&(_, '\n')) => Some(index),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(end) = end {
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+            start = end;
+        }
+    }
+    let sentence = text[start..].trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+    sentences
+}
+
+/// Split text into chunks of whole sentences, each at most `max_chars` characters.
+///
+/// Each chunk after the first starts with the last `overlap` sentences of the chunk before
+/// it, so a passage that straddles two chunks is still found whole in one of them. A
+/// sentence longer than `max_chars` is split between words.
+///
+/// **Examples:**
+/// ```rust
+/// use momento_functions_text::chunk_sentences;
+///
+/// let text = "One fish. Two fish. Red fish. Blue fish.";
+/// assert_eq!(
+///     vec!["One fish. Two fish.", "Two fish. Red fish.", "Red fish. Blue fish."],
+///     chunk_sentences(text, 20, 1),
+/// );
+/// ```
+pub fn chunk_sentences(text: &str, max_chars: usize, overlap: usize) -> Vec<String> {
+    chunk_sentences_by(text, max_chars, overlap, |chunk| chunk.chars().count())
+}
+
+/// Split text into chunks of whole sentences, each at most `max_len` long as measured by
+/// `len`.
+///
+/// This is [chunk_sentences] with your own measure of length, like a token count. A single
+/// word longer than `max_len` cannot be split between words, so it becomes a chunk of its
+/// own that is over budget; truncate chunks afterward if the limit is strict.
+pub fn chunk_sentences_by(
+    text: &str,
+    max_len: usize,
+    overlap: usize,
+    len: impl Fn(&str) -> usize,
+) -> Vec<String> {
+    let pieces: Vec<&str> = split_sentences(text)
+        .into_iter()
+        .flat_map(|sentence| {
+            if len(sentence) <= max_len {
+                vec![sentence]
+            } else {
+                split_words(sentence, max_len, &len)
+            }
+        })
+        .collect();
+
+    let mut chunks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for piece in pieces {
+        if !current.is_empty() && max_len < len(&join(&current, piece)) {
+            chunks.push(current.join(" "));
+            // Always drop at least one sentence, so every chunk makes progress.
+            let keep = overlap.min(current.len() - 1);
+            current.drain(..current.len() - keep);
+            while !current.is_empty() && max_len < len(&join(&current, piece)) {
+                current.remove(0);
+            }
+        }
+        current.push(piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current.join(" "));
+    }
+    chunks
+}
+
+fn join(sentences: &[&str], next: &str) -> String {
+    let mut joined = sentences.join(" ");
+    joined.push(' ');
+    joined.push_str(next);
+    joined
+}
+
+/// Split an oversized sentence into runs of whole words that each fit in `max_len`.
+fn split_words<'a>(
+    sentence: &'a str,
+    max_len: usize,
+    len: &impl Fn(&str) -> usize,
+) -> Vec<&'a str> {
+    let mut runs = Vec::new();
+    let mut run: Option<(usize, usize)> = None;
+    for word in sentence.split_whitespace() {
+        let word_start = word.as_ptr() as usize - sentence.as_ptr() as usize;
+        let word_end = word_start + word.len();
+        run = match run {
+            Some((start, end)) if max_len < len(&sentence[start..word_end]) => {
+                runs.push(&sentence[start..end]);
+                Some((word_start, word_end))
+            }
+            Some((start, _)) => Some((start, word_end)),
+            None => Some((word_start, word_end)),
+        };
+    }
+    if let Some((start, end)) = run {
+        runs.push(&sentence[start..end]);
+    }
+    runs
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_terminals_and_blank_lines() {
+        assert_eq!(
+            vec![
+                "# Heading",
+                "First (really.)",
+                "Second!",
+                "Version 1.2 is out",
+                "last line",
+            ],
+            split_sentences(
+                "# Heading\n\nFirst (really.) Second!\n \nVersion 1.2 is out\n\nlast line"
+            )
+        );
+        assert!(split_sentences("  \n\n ").is_empty());
+        assert_eq!(vec!["no terminal"], split_sentences("no terminal"));
+        assert_eq!(vec!["Ünïcødé?", "Ja."], split_sentences("Ünïcødé? Ja."));
+    }
+
+    #[test]
+    fn chunks_respect_the_budget_and_overlap() {
+        let text = "aaaa. bbbb. cccc. dddd. eeee.";
+        for overlap in 0..4 {
+            let chunks = chunk_sentences(text, 12, overlap);
+            assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 12));
+            assert_eq!("aaaa. bbbb.", chunks[0]);
+            assert!(chunks.last().unwrap().ends_with("eeee."));
+        }
+        assert_eq!(
+            vec!["aaaa. bbbb.", "cccc. dddd.", "eeee."],
+            chunk_sentences(text, 12, 0)
+        );
+        assert_eq!(
+            vec!["aaaa. bbbb. cccc.", "cccc. dddd. eeee."],
+            chunk_sentences(text, 17, 1)
+        );
+        assert_eq!(vec![text], chunk_sentences(text, 1000, 2));
+        assert!(chunk_sentences("", 10, 1).is_empty());
+    }
+
+    #[test]
+    fn long_sentences_split_between_words() {
+        let chunks = chunk_sentences("one two three four five six. End.", 9, 0);
+        assert_eq!(vec!["one two", "three", "four five", "six. End."], chunks);
+
+        let chunks = chunk_sentences("supercalifragilistic word", 5, 0);
+        assert_eq!(vec!["supercalifragilistic", "word"], chunks);
+    }
+}
@@ -0,0 +1,179 @@
+//! Token budgets with OpenAI's tokenizers.
+//!
+//! Tokens are counted as ordinary text: special token markers like `<|endoftext|>` in the
+//! input count as the characters they are made of, which is how embedding APIs see them.
+
+use tiktoken_rs::{CoreBPE, Rank};
+
+use crate::chunk_sentences_by;
+
+/// Count the tokens in `text`.
+pub fn count_tokens(bpe: &CoreBPE, text: &str) -> usize {
+    bpe.encode_ordinary(text).len()
+}
+
+/// Shorten `text` to at most `max_tokens` tokens.
+///
+/// Decoding a cut-off list of tokens fails when the cut lands inside a multi-byte character,
+/// like an emoji or an accented letter. This cuts at the last whole character instead, and
+/// never allocates.
+///
+/// **Examples:**
+/// ```rust
+/// use momento_functions_text::{count_tokens, truncate_tokens};
+///
+/// let bpe = tiktoken_rs::cl100k_base_singleton();
+/// let document = "A very long article. ".repeat(10_000);
+/// // OpenAI's limit for text-embedding-3-small
+/// let input = truncate_tokens(bpe, &document, 8192);
+/// assert!(count_tokens(bpe, input) <= 8192);
+/// assert!(document.starts_with(input));
+/// ```
+pub fn truncate_tokens<'a>(bpe: &CoreBPE, text: &'a str, max_tokens: usize) -> &'a str {
+    let tokens = bpe.encode_ordinary(text);
+    if tokens.len() <= max_tokens {
+        return text;
+    }
+    let offsets = token_offsets(bpe, tokens);
+    fit(bpe, text, 0, &offsets, max_tokens).0
+}
+
+/// Split `text` into pieces of at most `max_tokens` tokens, each starting `overlap_tokens`
+/// tokens before the previous one ended.
+///
+/// This cuts anywhere, even mid-word. To keep sentences whole, use
+/// [chunk_sentences_by_tokens].
+pub fn chunk_tokens<'a>(
+    bpe: &CoreBPE,
+    text: &'a str,
+    max_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<&'a str> {
+    let max_tokens = max_tokens.max(1);
+    let overlap_tokens = overlap_tokens.min(max_tokens - 1);
+    let offsets = token_offsets(bpe, bpe.encode_ordinary(text));
+    let token_count = offsets.len() - 1;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < token_count {
+        let (chunk, end) = fit(bpe, text, start, &offsets, max_tokens);
+        // A token that is only part of a character cannot be a chunk on its own; skip it.
+        let end = end.max(start + 1);
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        if token_count <= end {
+            break;
+        }
+        start = (end - overlap_tokens).max(start + 1);
+    }
+    chunks
+}
+
+/// Split `text` into chunks of whole sentences of at most `max_tokens` tokens, each starting
+/// with the last `overlap` sentences of the chunk before it.
+///
+/// Unlike [chunk_sentences_by](crate::chunk_sentences_by), every chunk is within budget: a
+/// chunk that cannot be split between words is truncated.
+pub fn chunk_sentences_by_tokens(
+    bpe: &CoreBPE,
+    text: &str,
+    max_tokens: usize,
+    overlap: usize,
+) -> Vec<String> {
+    let mut chunks =
+        chunk_sentences_by(text, max_tokens, overlap, |chunk| count_tokens(bpe, chunk));
+    for chunk in &mut chunks {
+        let fitted = truncate_tokens(bpe, chunk, max_tokens).len();
+        chunk.truncate(fitted);
+    }
+    chunks
+}
+
+/// The byte offset where each token starts, plus the end of the text.
+fn token_offsets(bpe: &CoreBPE, tokens: Vec<Rank>) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(tokens.len() + 1);
+    let mut offset = 0;
+    offsets.push(offset);
+    for bytes in bpe._decode_native_and_split(tokens) {
+        offset += bytes.len();
+        offsets.push(offset);
+    }
+    offsets
+}
+
+/// The longest text starting at token `start` that is at most `max_tokens` tokens, cut at a
+/// character boundary, and the token it ends before.
+///
+/// Tokenizing a slice on its own can merge differently than it did in context, so this
+/// re-counts and backs off until the slice really fits.
+fn fit<'a>(
+    bpe: &CoreBPE,
+    text: &'a str,
+    start: usize,
+    offsets: &[usize],
+    max_tokens: usize,
+) -> (&'a str, usize) {
+    let from = floor(text, offsets[start]);
+    let mut end = (start + max_tokens).min(offsets.len() - 1);
+    loop {
+        let slice = &text[from..floor(text, offsets[end]).max(from)];
+        if end <= start || count_tokens(bpe, slice) <= max_tokens {
+            return (slice, end);
+        }
+        end -= 1;
+    }
+}
+
+fn floor(text: &str, offset: usize) -> usize {
+    text.floor_char_boundary(offset)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tiktoken_rs::cl100k_base_singleton;
+
+    #[test]
+    fn truncation_never_splits_a_character() {
+        let bpe = cl100k_base_singleton();
+        let text = "héllo wörld 🦀🦀🦀 ünïcødé ".repeat(20);
+        assert_eq!(text.as_str(), truncate_tokens(bpe, &text, 100_000));
+        for max_tokens in 0..40 {
+            let truncated = truncate_tokens(bpe, &text, max_tokens);
+            assert!(count_tokens(bpe, truncated) <= max_tokens);
+            assert!(text.starts_with(truncated));
+        }
+        assert!(!truncate_tokens(bpe, &text, 39).is_empty());
+    }
+
+    #[test]
+    fn token_chunks_cover_the_text_within_budget() {
+        let bpe = cl100k_base_singleton();
+        let text = "The quick brown 🦊 jumps over the lazy 🐶. ".repeat(30);
+        for (max_tokens, overlap) in [(1, 0), (7, 0), (16, 4), (50, 49)] {
+            let chunks = chunk_tokens(bpe, &text, max_tokens, overlap);
+            assert!(
+                chunks
+                    .iter()
+                    .all(|chunk| count_tokens(bpe, chunk) <= max_tokens)
+            );
+            if overlap == 0 && 1 < max_tokens {
+                assert_eq!(text, chunks.concat(), "{max_tokens}");
+            }
+            assert!(text.ends_with(chunks.last().unwrap()));
+        }
+        assert!(chunk_tokens(bpe, "", 10, 2).is_empty());
+    }
+
+    #[test]
+    fn sentence_chunks_are_within_budget() {
+        let bpe = cl100k_base_singleton();
+        let text = format!("Short one. {} Another short one.", "🦀".repeat(200));
+        let chunks = chunk_sentences_by_tokens(bpe, &text, 20, 1);
+        assert_eq!(3, chunks.len());
+        assert!(chunks.iter().all(|chunk| count_tokens(bpe, chunk) <= 20));
+        assert!(chunks[1].starts_with('🦀'));
+    }
+}
@@ -0,0 +1,387 @@
+//! Turbopuffer query filters.
+//!
+//! Turbopuffer takes filters as nested JSON arrays: `["id", "In", ["a", "b"]]` for a
+//! comparison, `["And", [...]]` and `["Or", [...]]` to combine them, and `["Not", filter]`
+//! to negate one. [Filter] builds that shape for you, and the builder only offers the
+//! values each operator accepts, so a list operator always gets a list and a glob always
+//! gets a pattern.
+
+use std::fmt;
+use std::ops::Not;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// A Turbopuffer query filter.
+///
+/// See Turbopuffer's Query docs for how filters are evaluated:
+/// <https://turbopuffer.com/docs/query#param-filters>
+///
+/// **Examples:**
+/// ```rust
+/// use momento_functions_turbopuffer::Filter;
+/// use serde_json::json;
+///
+/// let seen = ["11782925380870169755"];
+/// let filter = Filter::field("id")
+///     .not_in(seen)
+///     .and(Filter::field("metadata$title").not_glob("*Mock Draft*"));
+///
+/// assert_eq!(
+///     json!(["And", [
+///         ["id", "NotIn", ["11782925380870169755"]],
+///         ["metadata$title", "NotGlob", "*Mock Draft*"],
+///     ]]),
+///     serde_json::to_value(&filter).unwrap(),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Every filter must match: `["And", [filters...]]`.
+    And(Vec<Filter>),
+    /// At least one filter must match: `["Or", [filters...]]`.
+    Or(Vec<Filter>),
+    /// The filter must not match: `["Not", filter]`.
+    Not(Box<Filter>),
+    /// Compare an attribute to a value: `["field", "op", value]`.
+    Comparison {
+        /// The attribute to compare.
+        field: String,
+        /// How to compare it.
+        op: ComparisonOp,
+        /// The right-hand side: a string, a number, or a list.
+        value: Value,
+    },
+}
+
+/// A comparison operator for [Filter::Comparison].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum ComparisonOp {
+    /// The attribute equals the value.
+    Eq,
+    /// The attribute does not equal the value.
+    Neq,
+    /// The attribute is one of a list of values.
+    In,
+    /// The attribute is none of a list of values.
+    NotIn,
+    /// The attribute is less than the value.
+    Lt,
+    /// The attribute is less than or equal to the value.
+    Lte,
+    /// The attribute is greater than the value.
+    Gt,
+    /// The attribute is greater than or equal to the value.
+    Gte,
+    /// The attribute matches a glob pattern, like `*Draft*`.
+    Glob,
+    /// The attribute does not match a glob pattern.
+    NotGlob,
+}
+
+impl Filter {
+    /// Start a comparison on an attribute.
+    pub fn field(field: impl Into<String>) -> Field {
+        Field {
+            field: field.into(),
+        }
+    }
+
+    /// A filter that matches when every one of `filters` matches.
+    pub fn all(filters: impl IntoIterator<Item = Filter>) -> Self {
+        Self::And(filters.into_iter().collect())
+    }
+
+    /// A filter that matches when any one of `filters` matches.
+    pub fn any(filters: impl IntoIterator<Item = Filter>) -> Self {
+        Self::Or(filters.into_iter().collect())
+    }
+
+    /// Require both this filter and `other` to match.
+    ///
+    /// Chained calls build one flat `And` rather than nesting them.
+    pub fn and(self, other: Filter) -> Self {
+        match self {
+            Self::And(mut filters) => {
+                filters.push(other);
+                Self::And(filters)
+            }
+            filter => Self::And(vec![filter, other]),
+        }
+    }
+
+    /// Require this filter or `other` to match.
+    ///
+    /// Chained calls build one flat `Or` rather than nesting them.
+    pub fn or(self, other: Filter) -> Self {
+        match self {
+            Self::Or(mut filters) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            filter => Self::Or(vec![filter, other]),
+        }
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    /// Negate the filter. Negating a negation unwraps it.
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Not(filter) => *filter,
+            filter => Self::Not(Box::new(filter)),
+        }
+    }
+}
+
+/// An attribute to compare, from [Filter::field].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    field: String,
+}
+
+impl Field {
+    fn compare(self, op: ComparisonOp, value: Value) -> Filter {
+        Filter::Comparison {
+            field: self.field,
+            op,
+            value,
+        }
+    }
+
+    fn compare_list<V: Into<Value>>(
+        self,
+        op: ComparisonOp,
+        values: impl IntoIterator<Item = V>,
+    ) -> Filter {
+        self.compare(
+            op,
+            Value::Array(values.into_iter().map(Into::into).collect()),
+        )
+    }
+
+    /// The attribute equals `value`.
+    pub fn eq(self, value: impl Into<Value>) -> Filter {
+        self.compare(ComparisonOp::Eq, value.into())
+    }
+
+    /// The attribute does not equal `value`.
+    pub fn neq(self, value: impl Into<Value>) -> Filter {
+        self.compare(ComparisonOp::Neq, value.into())
+    }
+
+    /// The attribute is one of `values`.
+    pub fn is_in<V: Into<Value>>(self, values: impl IntoIterator<Item = V>) -> Filter {
+        self.compare_list(ComparisonOp::In, values)
+    }
+
+    /// The attribute is none of `values`.
+    pub fn not_in<V: Into<Value>>(self, values: impl IntoIterator<Item = V>) -> Filter {
+        self.compare_list(ComparisonOp::NotIn, values)
+    }
+
+    /// The attribute is less than `value`.
+    pub fn lt(self, value: impl Into<Value>) -> Filter {
+        self.compare(ComparisonOp::Lt, value.into())
+    }
+
+    /// The attribute is less than or equal to `value`.
+    pub fn lte(self, value: impl Into<Value>) -> Filter {
+        self.compare(ComparisonOp::Lte, value.into())
+    }
+
+    /// The attribute is greater than `value`.
+    pub fn gt(self, value: impl Into<Value>) -> Filter {
+        self.compare(ComparisonOp::Gt, value.into())
+    }
+
+    /// The attribute is greater than or equal to `value`.
+    pub fn gte(self, value: impl Into<Value>) -> Filter {
+        self.compare(ComparisonOp::Gte, value.into())
+    }
+
+    /// The attribute matches the glob `pattern`, like `*Draft*`.
+    pub fn glob(self, pattern: impl Into<String>) -> Filter {
+        self.compare(ComparisonOp::Glob, Value::String(pattern.into()))
+    }
+
+    /// The attribute does not match the glob `pattern`.
+    pub fn not_glob(self, pattern: impl Into<String>) -> Filter {
+        self.compare(ComparisonOp::NotGlob, Value::String(pattern.into()))
+    }
+}
+
+impl Serialize for Filter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::And(filters) => (LOGICAL_AND, filters).serialize(serializer),
+            Self::Or(filters) => (LOGICAL_OR, filters).serialize(serializer),
+            Self::Not(filter) => (LOGICAL_NOT, filter).serialize(serializer),
+            Self::Comparison { field, op, value } => {
+                let mut tuple = serializer.serialize_tuple(3)?;
+                tuple.serialize_element(field)?;
+                tuple.serialize_element(op)?;
+                tuple.serialize_element(value)?;
+                tuple.end()
+            }
+        }
+    }
+}
+
+const LOGICAL_AND: &str = "And";
+const LOGICAL_OR: &str = "Or";
+const LOGICAL_NOT: &str = "Not";
+
+impl<'de> Deserialize<'de> for Filter {
+    /// The first element decides the shape: `And`, `Or`, and `Not` are logical filters, and
+    /// anything else is the attribute of a comparison. Like Turbopuffer, this means an
+    /// attribute literally named `And`, `Or`, or `Not` cannot be filtered on.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FilterVisitor;
+
+        impl<'de> Visitor<'de> for FilterVisitor {
+            type Value = Filter;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a Turbopuffer filter array")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Filter, A::Error> {
+                let head: String = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let filter = match head.as_str() {
+                    LOGICAL_AND => Filter::And(required(&mut seq, 1, &self)?),
+                    LOGICAL_OR => Filter::Or(required(&mut seq, 1, &self)?),
+                    LOGICAL_NOT => Filter::Not(required(&mut seq, 1, &self)?),
+                    _ => Filter::Comparison {
+                        field: head,
+                        op: required(&mut seq, 1, &self)?,
+                        value: required(&mut seq, 2, &self)?,
+                    },
+                };
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::custom("too many elements in Turbopuffer filter"));
+                }
+                Ok(filter)
+            }
+        }
+
+        fn required<'de, A: SeqAccess<'de>, T: Deserialize<'de>>(
+            seq: &mut A,
+            index: usize,
+            visitor: &FilterVisitor,
+        ) -> Result<T, A::Error> {
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(index, visitor))
+        }
+
+        deserializer.deserialize_seq(FilterVisitor)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip(filter: &Filter) -> Filter {
+        serde_json::from_value(serde_json::to_value(filter).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn comparisons_serialize_as_triples() {
+        let cases = [
+            (Filter::field("id").eq("a"), json!(["id", "Eq", "a"])),
+            (Filter::field("n").neq(3), json!(["n", "Neq", 3])),
+            (
+                Filter::field("id").is_in(["a", "b"]),
+                json!(["id", "In", ["a", "b"]]),
+            ),
+            (
+                Filter::field("id").not_in(vec!["a".to_string()]),
+                json!(["id", "NotIn", ["a"]]),
+            ),
+            (Filter::field("n").lt(1.5), json!(["n", "Lt", 1.5])),
+            (Filter::field("n").lte(2), json!(["n", "Lte", 2])),
+            (Filter::field("n").gt(3), json!(["n", "Gt", 3])),
+            (Filter::field("n").gte(4), json!(["n", "Gte", 4])),
+            (Filter::field("t").glob("a*"), json!(["t", "Glob", "a*"])),
+            (
+                Filter::field("t").not_glob("*b"),
+                json!(["t", "NotGlob", "*b"]),
+            ),
+        ];
+        for (filter, expected) in cases {
+            assert_eq!(expected, serde_json::to_value(&filter).unwrap());
+            assert_eq!(filter, round_trip(&filter));
+        }
+    }
+
+    #[test]
+    fn logical_filters_flatten_and_round_trip() {
+        let filter = Filter::field("a").eq(1).and(Filter::field("b").eq(2)).and(
+            !Filter::field("c")
+                .is_in([3, 4])
+                .or(Filter::field("d").gt(5)),
+        );
+        assert_eq!(
+            json!([
+                "And",
+                [
+                    ["a", "Eq", 1],
+                    ["b", "Eq", 2],
+                    ["Not", ["Or", [["c", "In", [3, 4]], ["d", "Gt", 5]]]],
+                ]
+            ]),
+            serde_json::to_value(&filter).unwrap()
+        );
+        assert_eq!(filter, round_trip(&filter));
+
+        let inner = Filter::field("a").eq(1);
+        assert_eq!(inner, !!inner.clone());
+        assert_eq!(
+            Filter::Or(vec![]),
+            round_trip(&Filter::any(std::iter::empty()))
+        );
+    }
+
+    #[test]
+    fn deserializes_request_filters() {
+        let filter: Filter = serde_json::from_value(json!([
+            "And",
+            [
+                ["metadata$title", "NotGlob", "*Mock Draft*"],
+                ["Not", ["id", "In", ["11782925380870169755"]]],
+            ]
+        ]))
+        .unwrap();
+        assert_eq!(
+            Filter::all([
+                Filter::field("metadata$title").not_glob("*Mock Draft*"),
+                !Filter::field("id").is_in(["11782925380870169755"]),
+            ]),
+            filter
+        );
+
+        for invalid in [
+            json!([]),
+            json!(["id", "Eq"]),
+            json!(["id", "Like", "a"]),
+            json!(["id", "Eq", "a", "b"]),
+            json!(["Not"]),
+            json!({"id": "a"}),
+        ] {
+            assert!(
+                serde_json::from_value::<Filter>(invalid.clone()).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+}
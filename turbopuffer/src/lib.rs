@@ -0,0 +1,8 @@
+//! Types for building Turbopuffer requests.
+//!
+//! These are plain serde types with no host dependencies, so they work with either the
+//! `momento-functions-http` crate or `momento_functions_host::http`.
+
+mod filter;
+
+pub use filter::{ComparisonOp, Field, Filter};